@@ -0,0 +1,15 @@
+//! Feeds arbitrary commands to the i3 IPC command parser, and the resulting actions on to the
+//! conversion for running them.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use niri::ipc::i3::parse_command;
+use niri::ipc::server::prepare_action;
+
+fuzz_target!(|data: &[u8]| {
+    let command = String::from_utf8_lossy(data);
+    for action in parse_command(&command, &[]).into_iter().flatten() {
+        let _ = prepare_action(action);
+    }
+});
@@ -0,0 +1,16 @@
+//! Feeds arbitrary bytes to the IPC request parser, and actions on to the conversion for running
+//! them.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use niri::ipc::server::{parse_request, prepare_action};
+use niri_ipc::Request;
+
+fuzz_target!(|data: &[u8]| {
+    for read_only in [false, true] {
+        if let Ok(Request::Action(action)) = parse_request(data, read_only) {
+            let _ = prepare_action(action);
+        }
+    }
+});
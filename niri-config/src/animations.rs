@@ -149,6 +149,7 @@ impl Default for WorkspaceSwitchAnim {
 #[derive(Debug, Clone, PartialEq)]
 pub struct WindowOpenAnim {
     pub anim: Animation,
+    pub style: WindowAnimStyle,
     pub custom_shader: Option<String>,
 }
 
@@ -162,14 +163,30 @@ impl Default for WindowOpenAnim {
                     curve: Curve::EaseOutExpo,
                 }),
             },
+            style: WindowAnimStyle::default(),
             custom_shader: None,
         }
     }
 }
 
+/// How windows look while opening and closing.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAnimStyle {
+    /// Scale from or to a slightly smaller size while fading.
+    #[default]
+    Popin,
+    /// Scale from or to nothing without fading.
+    Scale,
+    /// Fade without scaling.
+    Fade,
+    /// Slide in from or out to the edge the window was inserted at while fading.
+    Slide,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct WindowCloseAnim {
     pub anim: Animation,
+    pub style: WindowAnimStyle,
     pub custom_shader: Option<String>,
 }
 
@@ -183,6 +200,7 @@ impl Default for WindowCloseAnim {
                     curve: Curve::EaseOutQuad,
                 }),
             },
+            style: WindowAnimStyle::default(),
             custom_shader: None,
         }
     }
@@ -344,9 +362,13 @@ where
         ctx: &mut knuffel::decode::Context<S>,
     ) -> Result<Self, DecodeError<S>> {
         let default = Self::default().anim;
+        let mut style = WindowAnimStyle::default();
         let mut custom_shader = None;
         let anim = Animation::decode_node(node, ctx, default, |child, ctx| {
-            if &**child.node_name == "custom-shader" {
+            if &**child.node_name == "style" {
+                style = parse_arg_node("style", child, ctx)?;
+                Ok(true)
+            } else if &**child.node_name == "custom-shader" {
                 custom_shader = parse_arg_node("custom-shader", child, ctx)?;
                 Ok(true)
             } else {
@@ -356,6 +378,7 @@ where
 
         Ok(Self {
             anim,
+            style,
             custom_shader,
         })
     }
@@ -370,9 +393,13 @@ where
         ctx: &mut knuffel::decode::Context<S>,
     ) -> Result<Self, DecodeError<S>> {
         let default = Self::default().anim;
+        let mut style = WindowAnimStyle::default();
         let mut custom_shader = None;
         let anim = Animation::decode_node(node, ctx, default, |child, ctx| {
-            if &**child.node_name == "custom-shader" {
+            if &**child.node_name == "style" {
+                style = parse_arg_node("style", child, ctx)?;
+                Ok(true)
+            } else if &**child.node_name == "custom-shader" {
                 custom_shader = parse_arg_node("custom-shader", child, ctx)?;
                 Ok(true)
             } else {
@@ -382,6 +409,7 @@ where
 
         Ok(Self {
             anim,
+            style,
             custom_shader,
         })
     }
@@ -95,6 +95,11 @@ pub struct Gradient {
     pub relative_to: GradientRelativeTo,
     #[knuffel(property(name = "in"), str, default)]
     pub in_: GradientInterpolation,
+    #[knuffel(property, default)]
+    pub animate: GradientAnimation,
+    /// How long one full turn takes with `animate="rotate"`.
+    #[knuffel(property, default = 4000)]
+    pub period_ms: u32,
 }
 
 impl From<Color> for Gradient {
@@ -105,10 +110,23 @@ impl From<Color> for Gradient {
             angle: 0,
             relative_to: GradientRelativeTo::Window,
             in_: GradientInterpolation::default(),
+            animate: GradientAnimation::default(),
+            period_ms: 4000,
         }
     }
 }
 
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GradientAnimation {
+    /// The gradient stays at its angle.
+    #[default]
+    None,
+    /// The gradient turns around continuously.
+    Rotate,
+    /// The gradient points towards the window that had focus before.
+    FollowFocus,
+}
+
 #[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum GradientRelativeTo {
     #[default]
@@ -595,7 +613,9 @@ impl MergeWith<TabIndicatorPart> for TabIndicator {
 pub struct TabBar {
     pub off: bool,
     pub show_in_split: bool,
+    pub position: TabIndicatorPosition,
     pub height: f64,
+    pub side_width: f64,
     pub padding_x: f64,
     pub padding_y: f64,
     pub separator_width: f64,
@@ -611,6 +631,9 @@ pub struct TabBar {
     pub active_border: Color,
     pub inactive_border: Color,
     pub urgent_border: Color,
+    pub hover_preview: bool,
+    pub hover_preview_delay_ms: u16,
+    pub double_click_action: TabBarDoubleClickAction,
 }
 
 impl Default for TabBar {
@@ -618,7 +641,9 @@ impl Default for TabBar {
         Self {
             off: false,
             show_in_split: false,
+            position: TabIndicatorPosition::Top,
             height: 20.0,
+            side_width: 200.0,
             padding_x: 6.0,
             padding_y: 2.0,
             separator_width: 1.0,
@@ -634,6 +659,9 @@ impl Default for TabBar {
             active_border: Color::from_rgba8_unpremul(0x4c, 0x78, 0x99, 0xff),
             inactive_border: Color::from_rgba8_unpremul(0x33, 0x33, 0x33, 0xff),
             urgent_border: Color::from_rgba8_unpremul(0x90, 0x00, 0x00, 0xff),
+            hover_preview: true,
+            hover_preview_delay_ms: 500,
+            double_click_action: TabBarDoubleClickAction::Fullscreen,
         }
     }
 }
@@ -649,12 +677,20 @@ impl MergeWith<TabBarPart> for TabBar {
             (self, part),
             show_in_split,
             height,
+            side_width,
             padding_x,
             padding_y,
             separator_width,
             border_width,
+            hover_preview,
+        );
+        merge_clone!(
+            (self, part),
+            position,
+            font,
+            hover_preview_delay_ms,
+            double_click_action
         );
-        merge_clone!((self, part), font);
         merge_clone!(
             (self, part),
             active_bg,
@@ -680,8 +716,12 @@ pub struct TabBarPart {
     #[knuffel(child)]
     pub show_in_split: Option<Flag>,
     #[knuffel(child, unwrap(argument))]
+    pub position: Option<TabIndicatorPosition>,
+    #[knuffel(child, unwrap(argument))]
     pub height: Option<FloatOrInt<0, 65535>>,
     #[knuffel(child, unwrap(argument))]
+    pub side_width: Option<FloatOrInt<0, 65535>>,
+    #[knuffel(child, unwrap(argument))]
     pub padding_x: Option<FloatOrInt<0, 65535>>,
     #[knuffel(child, unwrap(argument))]
     pub padding_y: Option<FloatOrInt<0, 65535>>,
@@ -711,6 +751,25 @@ pub struct TabBarPart {
     pub inactive_border: Option<Color>,
     #[knuffel(child)]
     pub urgent_border: Option<Color>,
+    #[knuffel(child)]
+    pub hover_preview: Option<Flag>,
+    #[knuffel(child, unwrap(argument))]
+    pub hover_preview_delay_ms: Option<u16>,
+    #[knuffel(child, unwrap(argument))]
+    pub double_click_action: Option<TabBarDoubleClickAction>,
+}
+
+/// What happens when double-clicking a tab in a tab or stack bar.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabBarDoubleClickAction {
+    /// Do nothing beyond activating the tab.
+    None,
+    /// Toggle fullscreen for the clicked window.
+    Fullscreen,
+    /// Toggle maximize to edges for the clicked window.
+    Maximize,
+    /// Turn the tabbed or stacked container back into a split.
+    Collapse,
 }
 
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
@@ -763,6 +822,17 @@ pub enum TabIndicatorPosition {
     Bottom,
 }
 
+impl From<niri_ipc::TabBarPosition> for TabIndicatorPosition {
+    fn from(value: niri_ipc::TabBarPosition) -> Self {
+        match value {
+            niri_ipc::TabBarPosition::Top => Self::Top,
+            niri_ipc::TabBarPosition::Bottom => Self::Bottom,
+            niri_ipc::TabBarPosition::Left => Self::Left,
+            niri_ipc::TabBarPosition::Right => Self::Right,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct InsertHint {
     pub off: bool,
@@ -1300,6 +1370,53 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn parse_gradient_animation() {
+        let config = Config::parse_mem(
+            r##"
+            layout {
+                focus-ring {
+                    active-gradient from="#101010" to="#202020" animate="rotate" period-ms=2000
+                    inactive-gradient from="#111111" to="#212121" animate="follow-focus"
+                    urgent-gradient from="#121212" to="#222222"
+                }
+            }
+            "##,
+        )
+        .unwrap();
+
+        let ring = config.layout.focus_ring;
+        assert_debug_snapshot!(
+            (
+                ring.active_gradient.map(|g| (g.animate, g.period_ms)),
+                ring.inactive_gradient.map(|g| (g.animate, g.period_ms)),
+                ring.urgent_gradient.map(|g| (g.animate, g.period_ms)),
+            ),
+            @r"
+        (
+            Some(
+                (
+                    Rotate,
+                    2000,
+                ),
+            ),
+            Some(
+                (
+                    FollowFocus,
+                    4000,
+                ),
+            ),
+            Some(
+                (
+                    None,
+                    4000,
+                ),
+            ),
+        )
+        "
+        );
+    }
+
     #[test]
     fn test_border_rule_on_off_merging() {
         fn is_on(config: &str, rules: &[&str]) -> String {
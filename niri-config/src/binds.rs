@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -6,12 +7,16 @@ use bitflags::bitflags;
 use knuffel::errors::DecodeError;
 use miette::miette;
 use niri_ipc::{
-    ColumnDisplay, LayoutSwitchTarget, PositionChange, SizeChange, WorkspaceReferenceArg,
+    ColumnDisplay, FloatingArrangement, LayoutSwitchTarget, OutputDirection, PositionChange,
+    ResizeAmount, ResizeDirection, SizeChange, TabBarPosition, WorkspaceReferenceArg,
 };
 use smithay::input::keyboard::keysyms::KEY_NoSymbol;
-use smithay::input::keyboard::xkb::{keysym_from_name, KEYSYM_CASE_INSENSITIVE, KEYSYM_NO_FLAGS};
+use smithay::input::keyboard::xkb::{
+    keysym_from_name, keysym_get_name, KEYSYM_CASE_INSENSITIVE, KEYSYM_NO_FLAGS,
+};
 use smithay::input::keyboard::Keysym;
 
+use crate::layout::LayoutPart;
 use crate::recent_windows::{MruDirection, MruFilter, MruScope};
 use crate::utils::{expect_only_children, MergeWith};
 
@@ -83,6 +88,9 @@ pub struct SwitchBinds {
     pub tablet_mode_on: Option<SwitchAction>,
     #[knuffel(child)]
     pub tablet_mode_off: Option<SwitchAction>,
+    /// Layout overrides in effect while in tablet mode.
+    #[knuffel(child)]
+    pub tablet_mode_layout: Option<LayoutPart>,
 }
 
 impl MergeWith<SwitchBinds> for SwitchBinds {
@@ -93,30 +101,47 @@ impl MergeWith<SwitchBinds> for SwitchBinds {
             lid_close,
             tablet_mode_on,
             tablet_mode_off,
+            tablet_mode_layout,
         );
     }
 }
 
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct SwitchAction {
-    #[knuffel(child, unwrap(arguments))]
-    pub spawn: Vec<String>,
+    /// Actions to run in order.
+    #[knuffel(children)]
+    pub actions: Vec<Action>,
 }
 
 // Remember to add new actions to the CLI enum too.
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub enum Action {
     Quit(#[knuffel(property(name = "skip-confirmation"), default)] bool),
+    PowerMenu,
     #[knuffel(skip)]
     ChangeVt(i32),
     Suspend,
     PowerOffMonitors,
     PowerOnMonitors,
+    PowerOffMonitor(#[knuffel(argument)] Option<String>),
+    PowerOnMonitor(#[knuffel(argument)] Option<String>),
+    SetOutputProfile(#[knuffel(argument)] String),
+    BrightnessUp(#[knuffel(argument)] Option<u8>),
+    BrightnessDown(#[knuffel(argument)] Option<u8>),
+    BrightnessSet(#[knuffel(argument)] u8),
+    TogglePowerSaving,
+    ToggleNightLight,
     ToggleDebugTint,
     DebugToggleOpaqueRegions,
     DebugToggleDamage,
+    DebugToggleFrameStats,
     Spawn(#[knuffel(arguments)] Vec<String>),
+    SpawnSd(#[knuffel(arguments)] Vec<String>),
     SpawnSh(#[knuffel(argument)] String),
+    SpawnOnWorkspace(
+        #[knuffel(argument)] String,
+        #[knuffel(arguments)] Vec<String>,
+    ),
     DoScreenTransition(#[knuffel(property(name = "delay-ms"))] Option<u16>),
     #[knuffel(skip)]
     ConfirmScreenshot {
@@ -160,6 +185,9 @@ pub enum Action {
     ToggleWindowedFullscreen,
     #[knuffel(skip)]
     ToggleWindowedFullscreenById(u64),
+    ToggleWindowFullscreenSpan,
+    #[knuffel(skip)]
+    ToggleWindowFullscreenSpanById(u64),
     MoveWindowToScratchpad,
     #[knuffel(skip)]
     MoveWindowToScratchpadById(u64),
@@ -268,6 +296,12 @@ pub enum Action {
         reference: WorkspaceReference,
     },
     MoveWorkspaceToMonitor(#[knuffel(argument)] String),
+    MoveWorkspaceToMonitorInDirection(#[knuffel(argument, str)] OutputDirection),
+    #[knuffel(skip)]
+    MoveWorkspaceToMonitorInDirectionByRef {
+        direction: OutputDirection,
+        reference: WorkspaceReference,
+    },
     SetWorkspaceName(#[knuffel(argument)] String),
     #[knuffel(skip)]
     SetWorkspaceNameByRef {
@@ -316,6 +350,8 @@ pub enum Action {
     ToggleSplitLayout,
     SetLayoutStacked,
     SetLayoutTabbed,
+    SetTabBarPosition(#[knuffel(argument, str)] TabBarPosition),
+    UndoLayoutChange,
     SetWindowWidth(#[knuffel(argument, str)] SizeChange),
     #[knuffel(skip)]
     SetWindowWidthById {
@@ -331,6 +367,27 @@ pub enum Action {
     ResetWindowHeight,
     #[knuffel(skip)]
     ResetWindowHeightById(u64),
+    ResizeGrow(
+        #[knuffel(argument, str)] ResizeDirection,
+        #[knuffel(argument, str)] ResizeAmount,
+    ),
+    #[knuffel(skip)]
+    ResizeGrowById {
+        id: u64,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+    },
+    ResizeShrink(
+        #[knuffel(argument, str)] ResizeDirection,
+        #[knuffel(argument, str)] ResizeAmount,
+    ),
+    #[knuffel(skip)]
+    ResizeShrinkById {
+        id: u64,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+    },
+    ToggleResizeMode,
     SwitchPresetColumnWidth,
     SwitchPresetColumnWidthBack,
     SwitchPresetWindowWidth,
@@ -366,6 +423,21 @@ pub enum Action {
     ToggleWindowSticky,
     #[knuffel(skip)]
     ToggleWindowStickyById(u64),
+    ToggleWindowAlwaysOnTop,
+    #[knuffel(skip)]
+    ToggleWindowAlwaysOnTopById(u64),
+    RaiseWindow,
+    #[knuffel(skip)]
+    RaiseWindowById(u64),
+    LowerWindow,
+    #[knuffel(skip)]
+    LowerWindowById(u64),
+    ToggleWindowFollowFocus,
+    #[knuffel(skip)]
+    ToggleWindowFollowFocusById(u64),
+    ToggleWindowPictureInPicture,
+    #[knuffel(skip)]
+    ToggleWindowPictureInPictureById(u64),
     MoveWindowToFloating,
     #[knuffel(skip)]
     MoveWindowToFloatingById(u64),
@@ -375,6 +447,7 @@ pub enum Action {
     FocusFloating,
     FocusTiling,
     SwitchFocusBetweenFloatingAndTiling,
+    ArrangeFloating(#[knuffel(argument, str)] FloatingArrangement),
     #[knuffel(skip)]
     MoveFloatingWindowById {
         id: Option<u64>,
@@ -384,6 +457,9 @@ pub enum Action {
     ToggleWindowRuleOpacity,
     #[knuffel(skip)]
     ToggleWindowRuleOpacityById(u64),
+    ToggleWindowScreencastBlockOut,
+    #[knuffel(skip)]
+    ToggleWindowScreencastBlockOutById(u64),
     SetDynamicCastWindow,
     #[knuffel(skip)]
     SetDynamicCastWindowById(u64),
@@ -402,6 +478,7 @@ pub enum Action {
     UnsetWindowUrgent(u64),
     #[knuffel(skip)]
     LoadConfigFile,
+    SetAccentColor(#[knuffel(argument)] String),
     #[knuffel(skip)]
     MruAdvance {
         direction: MruDirection,
@@ -424,14 +501,39 @@ pub enum Action {
     MruCycleScope,
 }
 
+impl Action {
+    /// Returns the `move-workspace-to-monitor-left/right/up/down` action for the direction.
+    pub fn move_workspace_to_monitor(direction: OutputDirection) -> Self {
+        match direction {
+            OutputDirection::Left => Self::MoveWorkspaceToMonitorLeft,
+            OutputDirection::Right => Self::MoveWorkspaceToMonitorRight,
+            OutputDirection::Up => Self::MoveWorkspaceToMonitorUp,
+            OutputDirection::Down => Self::MoveWorkspaceToMonitorDown,
+        }
+    }
+}
+
 impl From<niri_ipc::Action> for Action {
     fn from(value: niri_ipc::Action) -> Self {
         match value {
             niri_ipc::Action::Quit { skip_confirmation } => Self::Quit(skip_confirmation),
+            niri_ipc::Action::PowerMenu {} => Self::PowerMenu,
             niri_ipc::Action::PowerOffMonitors {} => Self::PowerOffMonitors,
             niri_ipc::Action::PowerOnMonitors {} => Self::PowerOnMonitors,
+            niri_ipc::Action::PowerOffMonitor { output } => Self::PowerOffMonitor(output),
+            niri_ipc::Action::PowerOnMonitor { output } => Self::PowerOnMonitor(output),
+            niri_ipc::Action::SetOutputProfile { name } => Self::SetOutputProfile(name),
+            niri_ipc::Action::BrightnessUp { step } => Self::BrightnessUp(step),
+            niri_ipc::Action::BrightnessDown { step } => Self::BrightnessDown(step),
+            niri_ipc::Action::BrightnessSet { percent } => Self::BrightnessSet(percent),
+            niri_ipc::Action::TogglePowerSaving {} => Self::TogglePowerSaving,
+            niri_ipc::Action::ToggleNightLight {} => Self::ToggleNightLight,
             niri_ipc::Action::Spawn { command } => Self::Spawn(command),
+            niri_ipc::Action::SpawnSd { command } => Self::SpawnSd(command),
             niri_ipc::Action::SpawnSh { command } => Self::SpawnSh(command),
+            niri_ipc::Action::SpawnOnWorkspace { workspace, command } => {
+                Self::SpawnOnWorkspace(workspace, command)
+            }
             niri_ipc::Action::DoScreenTransition { delay_ms } => Self::DoScreenTransition(delay_ms),
             niri_ipc::Action::Screenshot { show_pointer, path } => {
                 Self::Screenshot(show_pointer, path)
@@ -471,6 +573,12 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleWindowedFullscreen { id: Some(id) } => {
                 Self::ToggleWindowedFullscreenById(id)
             }
+            niri_ipc::Action::ToggleWindowFullscreenSpan { id: None } => {
+                Self::ToggleWindowFullscreenSpan
+            }
+            niri_ipc::Action::ToggleWindowFullscreenSpan { id: Some(id) } => {
+                Self::ToggleWindowFullscreenSpanById(id)
+            }
             niri_ipc::Action::MoveWindowToScratchpad { id: None } => Self::MoveWindowToScratchpad,
             niri_ipc::Action::MoveWindowToScratchpad { id: Some(id) } => {
                 Self::MoveWindowToScratchpadById(id)
@@ -543,6 +651,17 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::SwapWindowLeft {} => Self::SwapWindowLeft,
             niri_ipc::Action::ToggleColumnTabbedDisplay {} => Self::ToggleColumnTabbedDisplay,
             niri_ipc::Action::SetColumnDisplay { display } => Self::SetColumnDisplay(display),
+            niri_ipc::Action::SetTabBarPosition { position } => Self::SetTabBarPosition(position),
+            niri_ipc::Action::FocusParent {} => Self::FocusParent,
+            niri_ipc::Action::FocusChild {} => Self::FocusChild,
+            niri_ipc::Action::SplitHorizontal {} => Self::SplitHorizontal,
+            niri_ipc::Action::SplitVertical {} => Self::SplitVertical,
+            niri_ipc::Action::SetLayoutSplitH {} => Self::SetLayoutSplitH,
+            niri_ipc::Action::SetLayoutSplitV {} => Self::SetLayoutSplitV,
+            niri_ipc::Action::ToggleSplitLayout {} => Self::ToggleSplitLayout,
+            niri_ipc::Action::SetLayoutStacked {} => Self::SetLayoutStacked,
+            niri_ipc::Action::SetLayoutTabbed {} => Self::SetLayoutTabbed,
+            niri_ipc::Action::UndoLayoutChange {} => Self::UndoLayoutChange,
             niri_ipc::Action::CenterColumn {} => Self::CenterColumn,
             niri_ipc::Action::CenterWindow { id: None } => Self::CenterWindow,
             niri_ipc::Action::CenterWindow { id: Some(id) } => Self::CenterWindowById(id),
@@ -638,6 +757,35 @@ impl From<niri_ipc::Action> for Action {
             } => Self::SetWindowHeightById { id, change },
             niri_ipc::Action::ResetWindowHeight { id: None } => Self::ResetWindowHeight,
             niri_ipc::Action::ResetWindowHeight { id: Some(id) } => Self::ResetWindowHeightById(id),
+            niri_ipc::Action::ResizeGrow {
+                id: None,
+                direction,
+                amount,
+            } => Self::ResizeGrow(direction, amount),
+            niri_ipc::Action::ResizeGrow {
+                id: Some(id),
+                direction,
+                amount,
+            } => Self::ResizeGrowById {
+                id,
+                direction,
+                amount,
+            },
+            niri_ipc::Action::ResizeShrink {
+                id: None,
+                direction,
+                amount,
+            } => Self::ResizeShrink(direction, amount),
+            niri_ipc::Action::ResizeShrink {
+                id: Some(id),
+                direction,
+                amount,
+            } => Self::ResizeShrinkById {
+                id,
+                direction,
+                amount,
+            },
+            niri_ipc::Action::ToggleResizeMode {} => Self::ToggleResizeMode,
             niri_ipc::Action::SwitchPresetColumnWidth {} => Self::SwitchPresetColumnWidth,
             niri_ipc::Action::SwitchPresetColumnWidthBack {} => Self::SwitchPresetColumnWidthBack,
             niri_ipc::Action::SwitchPresetWindowWidth { id: None } => Self::SwitchPresetWindowWidth,
@@ -701,9 +849,21 @@ impl From<niri_ipc::Action> for Action {
                 reference: None,
             } => Self::MoveWorkspaceToMonitor(output),
             niri_ipc::Action::MoveWorkspaceToMonitorNext {} => Self::MoveWorkspaceToMonitorNext,
+            niri_ipc::Action::MoveWorkspaceToMonitorInDirection {
+                direction,
+                reference: Some(reference),
+            } => Self::MoveWorkspaceToMonitorInDirectionByRef {
+                direction,
+                reference: WorkspaceReference::from(reference),
+            },
+            niri_ipc::Action::MoveWorkspaceToMonitorInDirection {
+                direction,
+                reference: None,
+            } => Self::move_workspace_to_monitor(direction),
             niri_ipc::Action::ToggleDebugTint {} => Self::ToggleDebugTint,
             niri_ipc::Action::DebugToggleOpaqueRegions {} => Self::DebugToggleOpaqueRegions,
             niri_ipc::Action::DebugToggleDamage {} => Self::DebugToggleDamage,
+            niri_ipc::Action::DebugToggleFrameStats {} => Self::DebugToggleFrameStats,
             niri_ipc::Action::ToggleWindowFloating { id: None } => Self::ToggleWindowFloating,
             niri_ipc::Action::ToggleWindowFloating { id: Some(id) } => {
                 Self::ToggleWindowFloatingById(id)
@@ -712,6 +872,24 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleWindowSticky { id: Some(id) } => {
                 Self::ToggleWindowStickyById(id)
             }
+            niri_ipc::Action::ToggleWindowAlwaysOnTop { id: None } => Self::ToggleWindowAlwaysOnTop,
+            niri_ipc::Action::ToggleWindowAlwaysOnTop { id: Some(id) } => {
+                Self::ToggleWindowAlwaysOnTopById(id)
+            }
+            niri_ipc::Action::RaiseWindow { id: None } => Self::RaiseWindow,
+            niri_ipc::Action::RaiseWindow { id: Some(id) } => Self::RaiseWindowById(id),
+            niri_ipc::Action::LowerWindow { id: None } => Self::LowerWindow,
+            niri_ipc::Action::LowerWindow { id: Some(id) } => Self::LowerWindowById(id),
+            niri_ipc::Action::ToggleWindowFollowFocus { id: None } => Self::ToggleWindowFollowFocus,
+            niri_ipc::Action::ToggleWindowFollowFocus { id: Some(id) } => {
+                Self::ToggleWindowFollowFocusById(id)
+            }
+            niri_ipc::Action::ToggleWindowPictureInPicture { id: None } => {
+                Self::ToggleWindowPictureInPicture
+            }
+            niri_ipc::Action::ToggleWindowPictureInPicture { id: Some(id) } => {
+                Self::ToggleWindowPictureInPictureById(id)
+            }
             niri_ipc::Action::MoveWindowToFloating { id: None } => Self::MoveWindowToFloating,
             niri_ipc::Action::MoveWindowToFloating { id: Some(id) } => {
                 Self::MoveWindowToFloatingById(id)
@@ -725,6 +903,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::SwitchFocusBetweenFloatingAndTiling {} => {
                 Self::SwitchFocusBetweenFloatingAndTiling
             }
+            niri_ipc::Action::ArrangeFloating { arrangement } => Self::ArrangeFloating(arrangement),
             niri_ipc::Action::MoveFloatingWindow { id, x, y } => {
                 Self::MoveFloatingWindowById { id, x, y }
             }
@@ -732,6 +911,12 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleWindowRuleOpacity { id: Some(id) } => {
                 Self::ToggleWindowRuleOpacityById(id)
             }
+            niri_ipc::Action::ToggleWindowScreencastBlockOut { id: None } => {
+                Self::ToggleWindowScreencastBlockOut
+            }
+            niri_ipc::Action::ToggleWindowScreencastBlockOut { id: Some(id) } => {
+                Self::ToggleWindowScreencastBlockOutById(id)
+            }
             niri_ipc::Action::SetDynamicCastWindow { id: None } => Self::SetDynamicCastWindow,
             niri_ipc::Action::SetDynamicCastWindow { id: Some(id) } => {
                 Self::SetDynamicCastWindowById(id)
@@ -748,6 +933,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::SetWindowUrgent { id } => Self::SetWindowUrgent(id),
             niri_ipc::Action::UnsetWindowUrgent { id } => Self::UnsetWindowUrgent(id),
             niri_ipc::Action::LoadConfigFile {} => Self::LoadConfigFile,
+            niri_ipc::Action::SetAccentColor { color } => Self::SetAccentColor(color),
         }
     }
 }
@@ -757,6 +943,9 @@ pub enum WorkspaceReference {
     Id(u64),
     Index(u8),
     Name(String),
+    Relative(i32),
+    NextNonEmpty,
+    PrevNonEmpty,
 }
 
 impl From<WorkspaceReferenceArg> for WorkspaceReference {
@@ -765,6 +954,9 @@ impl From<WorkspaceReferenceArg> for WorkspaceReference {
             WorkspaceReferenceArg::Id(id) => Self::Id(id),
             WorkspaceReferenceArg::Index(i) => Self::Index(i),
             WorkspaceReferenceArg::Name(n) => Self::Name(n),
+            WorkspaceReferenceArg::Relative(offset) => Self::Relative(offset),
+            WorkspaceReferenceArg::NextNonEmpty => Self::NextNonEmpty,
+            WorkspaceReferenceArg::PrevNonEmpty => Self::PrevNonEmpty,
         }
     }
 }
@@ -788,7 +980,9 @@ impl<S: knuffel::traits::ErrorSpan> knuffel::DecodeScalar<S> for WorkspaceRefere
         ctx: &mut knuffel::decode::Context<S>,
     ) -> Result<WorkspaceReference, DecodeError<S>> {
         match &**val {
-            knuffel::ast::Literal::String(ref s) => Ok(WorkspaceReference::Name(s.clone().into())),
+            knuffel::ast::Literal::String(ref s) => Ok(WorkspaceReferenceArg::parse_relative(s)
+                .map(WorkspaceReference::from)
+                .unwrap_or_else(|| WorkspaceReference::Name(s.clone().into()))),
             knuffel::ast::Literal::Int(ref value) => match value.try_into() {
                 Ok(v) => Ok(WorkspaceReference::Index(v)),
                 Err(e) => {
@@ -1044,7 +1238,10 @@ where
             }
             match Action::decode_node(child, ctx) {
                 Ok(action) => {
-                    if !matches!(action, Action::Spawn(_) | Action::SpawnSh(_)) {
+                    if !matches!(
+                        action,
+                        Action::Spawn(_) | Action::SpawnSd(_) | Action::SpawnSh(_)
+                    ) {
                         if let Some(node) = allow_when_locked_node {
                             ctx.emit_error(DecodeError::unexpected(
                                 node,
@@ -1185,6 +1382,35 @@ impl FromStr for Key {
     }
 }
 
+/// Formats the key the way it's written in the config.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (Modifiers::COMPOSITOR, "Mod"),
+            (Modifiers::SUPER, "Super"),
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::ISO_LEVEL3_SHIFT, "ISO_Level3_Shift"),
+            (Modifiers::ISO_LEVEL5_SHIFT, "ISO_Level5_Shift"),
+        ];
+        for (modifier, name) in modifiers {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+
+        match self.trigger {
+            Trigger::Keysym(keysym) => f.write_str(&keysym_get_name(keysym)),
+            Trigger::MouseLeft => f.write_str("MouseLeft"),
+            Trigger::MouseRight => f.write_str("MouseRight"),
+            Trigger::MouseMiddle => f.write_str("MouseMiddle"),
+            Trigger::MouseBack => f.write_str("MouseBack"),
+            Trigger::MouseForward => f.write_str("MouseForward"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1246,4 +1472,43 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn move_workspace_to_monitor_in_direction_is_an_alias() {
+        assert_eq!(
+            Action::from(niri_ipc::Action::MoveWorkspaceToMonitorInDirection {
+                direction: OutputDirection::Up,
+                reference: None,
+            }),
+            Action::MoveWorkspaceToMonitorUp,
+        );
+        assert_eq!(
+            Action::move_workspace_to_monitor(OutputDirection::Left),
+            Action::MoveWorkspaceToMonitorLeft,
+        );
+        assert_eq!(
+            Action::from(niri_ipc::Action::MoveWorkspaceToMonitorInDirection {
+                direction: OutputDirection::Right,
+                reference: Some(niri_ipc::WorkspaceReferenceArg::Index(2)),
+            }),
+            Action::MoveWorkspaceToMonitorInDirectionByRef {
+                direction: OutputDirection::Right,
+                reference: WorkspaceReference::Index(2),
+            },
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "Mod+Shift+Return",
+            "Super+Alt+l",
+            "Ctrl+MouseMiddle",
+            "XF86AudioMute",
+        ] {
+            let key = text.parse::<Key>().unwrap();
+            assert_eq!(key.to_string(), text);
+            assert_eq!(key.to_string().parse::<Key>().unwrap(), key);
+        }
+    }
 }
@@ -25,6 +25,8 @@ pub struct Debug {
     pub honor_xdg_activation_with_invalid_serial: bool,
     pub deactivate_unfocused_windows: bool,
     pub skip_cursor_only_updates_during_vrr: bool,
+    pub allow_ipc_macros: bool,
+    pub nested_outputs: Option<u8>,
 }
 
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
@@ -71,6 +73,10 @@ pub struct DebugPart {
     pub deactivate_unfocused_windows: Option<Flag>,
     #[knuffel(child)]
     pub skip_cursor_only_updates_during_vrr: Option<Flag>,
+    #[knuffel(child)]
+    pub allow_ipc_macros: Option<Flag>,
+    #[knuffel(child, unwrap(argument))]
+    pub nested_outputs: Option<u8>,
 }
 
 impl MergeWith<DebugPart> for Debug {
@@ -95,9 +101,15 @@ impl MergeWith<DebugPart> for Debug {
             honor_xdg_activation_with_invalid_serial,
             deactivate_unfocused_windows,
             skip_cursor_only_updates_during_vrr,
+            allow_ipc_macros,
         );
 
-        merge_clone_opt!((self, part), preview_render, render_drm_device);
+        merge_clone_opt!(
+            (self, part),
+            preview_render,
+            render_drm_device,
+            nested_outputs
+        );
 
         self.ignored_drm_devices
             .extend(part.ignored_drm_devices.iter().cloned());
@@ -2,7 +2,7 @@ use std::error::Error;
 use std::fmt;
 use std::path::PathBuf;
 
-use miette::Diagnostic;
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme};
 
 #[derive(Debug)]
 pub struct ConfigParseResult<T, E> {
@@ -25,6 +25,16 @@ pub struct ConfigIncludeError {
     pub includes: Vec<knuffel::Error>,
 }
 
+/// Renders the error with the offending config lines but without colors, for printing as JSON.
+pub fn render_plain(err: &miette::Report) -> String {
+    let handler = GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor());
+    let mut rv = String::new();
+    if handler.render_report(&mut rv, &**err).is_err() {
+        return err.to_string();
+    }
+    rv
+}
+
 impl<T, E> ConfigParseResult<T, E> {
     pub fn from_err(err: E) -> Self {
         Self {
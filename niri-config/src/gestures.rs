@@ -4,6 +4,7 @@ use crate::FloatOrInt;
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Gestures {
     pub dnd_edge_workspace_switch: DndEdgeWorkspaceSwitch,
+    pub dnd_edge_hover: DndEdgeHover,
     pub hot_corners: HotCorners,
 }
 
@@ -12,12 +13,14 @@ pub struct GesturesPart {
     #[knuffel(child)]
     pub dnd_edge_workspace_switch: Option<DndEdgeWorkspaceSwitchPart>,
     #[knuffel(child)]
+    pub dnd_edge_hover: Option<DndEdgeHoverPart>,
+    #[knuffel(child)]
     pub hot_corners: Option<HotCorners>,
 }
 
 impl MergeWith<GesturesPart> for Gestures {
     fn merge_with(&mut self, part: &GesturesPart) {
-        merge!((self, part), dnd_edge_workspace_switch);
+        merge!((self, part), dnd_edge_workspace_switch, dnd_edge_hover);
         merge_clone!((self, part), hot_corners);
     }
 }
@@ -56,6 +59,47 @@ impl MergeWith<DndEdgeWorkspaceSwitchPart> for DndEdgeWorkspaceSwitch {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DndEdgeHover {
+    pub off: bool,
+    pub trigger_size: f64,
+    pub delay_ms: u16,
+}
+
+impl Default for DndEdgeHover {
+    fn default() -> Self {
+        Self {
+            off: false,
+            trigger_size: 8.,
+            delay_ms: 500,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct DndEdgeHoverPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub trigger_size: Option<FloatOrInt<0, 65535>>,
+    #[knuffel(child, unwrap(argument))]
+    pub delay_ms: Option<u16>,
+}
+
+impl MergeWith<DndEdgeHoverPart> for DndEdgeHover {
+    fn merge_with(&mut self, part: &DndEdgeHoverPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge!((self, part), trigger_size);
+        merge_clone!((self, part), delay_ms);
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
 pub struct HotCorners {
     #[knuffel(child)]
@@ -20,6 +20,7 @@ pub struct Input {
     pub disable_power_key_handling: bool,
     pub warp_mouse_to_focus: Option<WarpMouseToFocus>,
     pub focus_follows_mouse: Option<FocusFollowsMouse>,
+    pub disable_click_to_raise: bool,
     pub workspace_auto_back_and_forth: bool,
     pub mod_key: Option<ModKey>,
     pub mod_key_nested: Option<ModKey>,
@@ -48,6 +49,8 @@ pub struct InputPart {
     #[knuffel(child)]
     pub focus_follows_mouse: Option<FocusFollowsMouse>,
     #[knuffel(child)]
+    pub disable_click_to_raise: Option<Flag>,
+    #[knuffel(child)]
     pub workspace_auto_back_and_forth: Option<Flag>,
     #[knuffel(child, unwrap(argument, str))]
     pub mod_key: Option<ModKey>,
@@ -61,6 +64,7 @@ impl MergeWith<InputPart> for Input {
             (self, part),
             keyboard,
             disable_power_key_handling,
+            disable_click_to_raise,
             workspace_auto_back_and_forth,
         );
 
@@ -381,6 +385,35 @@ pub struct Touch {
 pub struct FocusFollowsMouse {
     #[knuffel(property, str)]
     pub max_scroll_amount: Option<Percent>,
+    /// Limits focus-follows-mouse to tiled or floating windows; the others need a click.
+    #[knuffel(property, str)]
+    pub windows: Option<FocusFollowsMouseWindows>,
+    /// How long the pointer needs to stay over a window before it gets focused.
+    #[knuffel(property)]
+    pub delay_ms: Option<u16>,
+    /// Don't move the focus between tabs of the focused tabbed or stacked container.
+    #[knuffel(property, default)]
+    pub containers_only: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FocusFollowsMouseWindows {
+    Tiled,
+    Floating,
+}
+
+impl FromStr for FocusFollowsMouseWindows {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tiled" => Ok(Self::Tiled),
+            "floating" => Ok(Self::Floating),
+            _ => Err(miette!(
+                r#"invalid windows for focus-follows-mouse, can be "tiled" or "floating" (or leave unset for all windows)"#
+            )),
+        }
+    }
 }
 
 #[derive(knuffel::Decode, Debug, PartialEq, Eq, Clone, Copy)]
@@ -393,6 +426,8 @@ pub struct WarpMouseToFocus {
 pub enum WarpMouseToFocusMode {
     CenterXy,
     CenterXyAlways,
+    /// Where the pointer last was in the window, or the center.
+    LastPosition,
 }
 
 impl FromStr for WarpMouseToFocusMode {
@@ -402,8 +437,9 @@ impl FromStr for WarpMouseToFocusMode {
         match s {
             "center-xy" => Ok(Self::CenterXy),
             "center-xy-always" => Ok(Self::CenterXyAlways),
+            "last-position" => Ok(Self::LastPosition),
             _ => Err(miette!(
-                r#"invalid mode for warp-mouse-to-focus, can be "center-xy" or "center-xy-always" (or leave unset for separate centering)"#
+                r#"invalid mode for warp-mouse-to-focus, can be "center-xy", "center-xy-always" or "last-position" (or leave unset for separate centering)"#
             )),
         }
     }
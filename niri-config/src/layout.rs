@@ -4,7 +4,7 @@ use niri_ipc::{ColumnDisplay, SizeChange};
 use std::str::FromStr;
 
 use crate::appearance::{
-    Border, FocusRing, InsertHint, Shadow, TabBar, TabIndicator, DEFAULT_BACKGROUND_COLOR,
+    Border, FocusRing, Gradient, InsertHint, Shadow, TabBar, TabIndicator, DEFAULT_BACKGROUND_COLOR,
 };
 use crate::utils::{expect_only_children, Flag, MergeWith};
 use crate::{
@@ -28,7 +28,13 @@ pub struct Layout {
     pub default_column_display: ColumnDisplay,
     pub gaps: f64,
     pub struts: Struts,
+    pub safe_area: SafeArea,
     pub background_color: Color,
+    pub background_gradient: Option<Gradient>,
+    pub background_image: Option<BackgroundImage>,
+    pub floating_placement: FloatingPlacement,
+    pub auto_split: bool,
+    pub workspace_indices: WorkspaceIndices,
 }
 
 impl Default for Layout {
@@ -52,12 +58,44 @@ impl Default for Layout {
             default_column_display: ColumnDisplay::Normal,
             gaps: 16.,
             struts: Struts::default(),
+            safe_area: SafeArea::default(),
             preset_window_heights: vec![
                 PresetSize::Proportion(1. / 3.),
                 PresetSize::Proportion(0.5),
                 PresetSize::Proportion(2. / 3.),
             ],
             background_color: DEFAULT_BACKGROUND_COLOR,
+            background_gradient: None,
+            background_image: None,
+            floating_placement: FloatingPlacement::default(),
+            auto_split: false,
+            workspace_indices: WorkspaceIndices::default(),
+        }
+    }
+}
+
+impl Layout {
+    /// Uses `accent` for the focused colors that were left at their defaults.
+    pub fn apply_accent_color(&mut self, accent: Color) {
+        let focus_ring = FocusRing::default();
+        if self.focus_ring.active_color == focus_ring.active_color
+            && self.focus_ring.active_gradient.is_none()
+        {
+            self.focus_ring.active_color = accent;
+        }
+
+        let border = Border::default();
+        if self.border.active_color == border.active_color && self.border.active_gradient.is_none()
+        {
+            self.border.active_color = accent;
+        }
+
+        let tab_bar = TabBar::default();
+        if self.tab_bar.active_bg == tab_bar.active_bg {
+            self.tab_bar.active_bg = accent;
+        }
+        if self.tab_bar.active_border == tab_bar.active_border {
+            self.tab_bar.active_border = accent;
         }
     }
 }
@@ -74,6 +112,7 @@ impl MergeWith<LayoutPart> for Layout {
             insert_hint,
             empty_workspace_above_first,
             gaps,
+            auto_split,
         );
 
         merge_clone!(
@@ -82,9 +121,14 @@ impl MergeWith<LayoutPart> for Layout {
             preset_window_heights,
             default_column_display,
             struts,
+            safe_area,
             background_color,
+            floating_placement,
+            workspace_indices,
         );
 
+        merge_clone_opt!((self, part), background_gradient, background_image);
+
         if let Some(x) = part.default_column_width {
             self.default_column_width = x.0;
         }
@@ -138,7 +182,39 @@ pub struct LayoutPart {
     #[knuffel(child)]
     pub struts: Option<Struts>,
     #[knuffel(child)]
+    pub safe_area: Option<SafeArea>,
+    #[knuffel(child)]
     pub background_color: Option<Color>,
+    #[knuffel(child)]
+    pub background_gradient: Option<Gradient>,
+    #[knuffel(child)]
+    pub background_image: Option<BackgroundImage>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub floating_placement: Option<FloatingPlacement>,
+    #[knuffel(child)]
+    pub auto_split: Option<Flag>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub workspace_indices: Option<WorkspaceIndices>,
+}
+
+/// Image drawn over the background color and gradient.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct BackgroundImage {
+    #[knuffel(argument)]
+    pub path: String,
+    #[knuffel(property, default)]
+    pub mode: BackgroundImageMode,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackgroundImageMode {
+    /// Scale to cover the whole workspace, cropping the sides that don't fit.
+    #[default]
+    Fill,
+    /// Scale to fit inside the workspace, leaving the background around it.
+    Fit,
+    /// Repeat at the original size, starting from the top left corner.
+    Tile,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
@@ -171,6 +247,19 @@ pub struct Struts {
     pub bottom: FloatOrInt<-65535, 65535>,
 }
 
+/// Insets of the part of an output hidden by a notch or rounded corners.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct SafeArea {
+    #[knuffel(child, unwrap(argument), default)]
+    pub left: FloatOrInt<0, 65535>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub right: FloatOrInt<0, 65535>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub top: FloatOrInt<0, 65535>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub bottom: FloatOrInt<0, 65535>,
+}
+
 #[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum HideEdgeBorders {
     #[default]
@@ -194,6 +283,59 @@ impl FromStr for HideEdgeBorders {
     }
 }
 
+/// Where new floating windows without a position go.
+#[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum FloatingPlacement {
+    /// Avoid covering the focused floating window and the pointer.
+    Smart,
+    /// Center in the working area.
+    #[default]
+    Center,
+    /// Center under the pointer.
+    UnderCursor,
+    /// Step down and to the right from the top-left corner.
+    Cascade,
+}
+
+impl FromStr for FloatingPlacement {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smart" => Ok(Self::Smart),
+            "center" => Ok(Self::Center),
+            "under-cursor" => Ok(Self::UnderCursor),
+            "cascade" => Ok(Self::Cascade),
+            _ => Err(miette!("invalid floating-placement value: {s}")),
+        }
+    }
+}
+
+/// What happens to the indices of the other workspaces when a workspace is emptied.
+#[derive(knuffel::DecodeScalar, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum WorkspaceIndices {
+    /// Remove the emptied workspace, moving the workspaces below it up by one.
+    #[default]
+    FillGaps,
+    /// Keep the emptied workspace while there are workspaces with windows below it, so that
+    /// their indices don't change.
+    KeepGaps,
+}
+
+impl FromStr for WorkspaceIndices {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fill-gaps" => Ok(Self::FillGaps),
+            "keep-gaps" => Ok(Self::KeepGaps),
+            _ => Err(miette!(
+                r#"invalid workspace-indices value, can be "fill-gaps" or "keep-gaps""#
+            )),
+        }
+    }
+}
+
 impl<S> knuffel::Decode<S> for DefaultPresetSize
 where
     S: knuffel::traits::ErrorSpan,
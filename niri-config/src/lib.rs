@@ -40,6 +40,7 @@ pub mod layout;
 pub mod misc;
 pub mod output;
 pub mod recent_windows;
+pub mod tunables;
 pub mod utils;
 pub mod window_rule;
 pub mod workspace;
@@ -50,17 +51,24 @@ pub use crate::binds::*;
 pub use crate::debug::Debug;
 pub use crate::error::{ConfigIncludeError, ConfigParseResult};
 pub use crate::gestures::Gestures;
-pub use crate::input::{Input, ModKey, ScrollMethod, TrackLayout, WarpMouseToFocusMode, Xkb};
+pub use crate::input::{
+    FocusFollowsMouseWindows, Input, ModKey, ScrollMethod, TrackLayout, WarpMouseToFocusMode, Xkb,
+};
 pub use crate::layer_rule::LayerRule;
 pub use crate::layout::*;
 pub use crate::misc::*;
-pub use crate::output::{Output, OutputName, Outputs, Position, Vrr};
+pub use crate::output::{
+    Output, OutputName, OutputProfile, OutputProfileWorkspace, Outputs, Position, Vrr,
+};
 use crate::recent_windows::RecentWindowsPart;
 pub use crate::recent_windows::{MruDirection, MruFilter, MruPreviews, MruScope, RecentWindows};
+pub use crate::tunables::TUNABLE_PATHS;
 pub use crate::utils::FloatOrInt;
 use crate::utils::{Flag, MergeWith as _};
 use std::collections::HashMap;
-pub use crate::window_rule::{FloatingPosition, RelativeTo, WindowRule};
+pub use crate::window_rule::{
+    FloatingPosition, FloatingSize, FocusOnActivation, FullscreenSpan, RelativeTo, WindowRule,
+};
 pub use crate::workspace::{Workspace, WorkspaceLayoutPart};
 
 const RECURSION_LIMIT: u8 = 10;
@@ -69,10 +77,13 @@ const RECURSION_LIMIT: u8 = 10;
 pub struct Config {
     pub input: Input,
     pub outputs: Outputs,
+    pub output_profiles: Vec<OutputProfile>,
     pub spawn_at_startup: Vec<SpawnAtStartup>,
     pub spawn_sh_at_startup: Vec<SpawnShAtStartup>,
     pub layout: Layout,
     pub prefer_no_csd: bool,
+    pub accent_color: Option<Color>,
+    pub ui_font: Option<String>,
     pub cursor: Cursor,
     pub screenshot_path: ScreenshotPath,
     pub clipboard: Clipboard,
@@ -83,6 +94,15 @@ pub struct Config {
     pub overview: Overview,
     pub environment: Environment,
     pub xwayland_satellite: XwaylandSatellite,
+    pub occluded_windows: OccludedWindows,
+    pub unresponsive_windows: UnresponsiveWindows,
+    pub picture_in_picture: PictureInPicture,
+    pub fullscreen_video: FullscreenVideo,
+    pub session_snapshot: SessionSnapshot,
+    pub night_light: NightLight,
+    pub power_saving: PowerSaving,
+    pub power_menu: PowerMenu,
+    pub ipc: Ipc,
     pub window_rules: Vec<WindowRule>,
     pub layer_rules: Vec<LayerRule>,
     pub binds: Binds,
@@ -159,6 +179,7 @@ where
             if !matches!(
                 name,
                 "output"
+                    | "output-profile"
                     | "spawn-at-startup"
                     | "spawn-sh-at-startup"
                     | "window-rule"
@@ -199,6 +220,26 @@ where
                 "gestures" => m_merge!(gestures),
                 "overview" => m_merge!(overview),
                 "xwayland-satellite" => m_merge!(xwayland_satellite),
+                "occluded-windows" => m_merge!(occluded_windows),
+                "unresponsive-windows" => m_merge!(unresponsive_windows),
+                "picture-in-picture" => m_merge!(picture_in_picture),
+                "fullscreen-video" => m_merge!(fullscreen_video),
+                "session-snapshot" => m_merge!(session_snapshot),
+                "night-light" => m_merge!(night_light),
+                "power-saving" => m_merge!(power_saving),
+                "power-menu" => m_merge!(power_menu),
+                "ipc" => {
+                    let part = IpcPart::decode_node(node, ctx)?;
+                    let names = part.allowed_actions.as_deref().unwrap_or_default();
+                    for name in Ipc::unknown_names(names) {
+                        ctx.emit_error(DecodeError::unexpected(
+                            node,
+                            "allowed action",
+                            format!("unknown action `{name}` in allowed-actions"),
+                        ));
+                    }
+                    config.borrow_mut().ipc.merge_with(&part);
+                }
                 "switch-events" => m_merge!(switch_events),
                 "debug" => m_merge!(debug),
 
@@ -207,8 +248,21 @@ where
                     let part = Output::decode_node(node, ctx)?;
                     config.borrow_mut().outputs.0.push(part);
                 }
-                "spawn-at-startup" => m_push!(spawn_at_startup),
-                "spawn-sh-at-startup" => m_push!(spawn_sh_at_startup),
+                "output-profile" => m_push!(output_profiles),
+                "spawn-at-startup" => {
+                    let part = SpawnAtStartup::decode_node(node, ctx)?;
+                    if part.critical && part.has_spawn_options() {
+                        ctx.emit_error(critical_spawn_error(node));
+                    }
+                    config.borrow_mut().spawn_at_startup.push(part);
+                }
+                "spawn-sh-at-startup" => {
+                    let part = SpawnShAtStartup::decode_node(node, ctx)?;
+                    if part.critical && part.has_spawn_options() {
+                        ctx.emit_error(critical_spawn_error(node));
+                    }
+                    config.borrow_mut().spawn_sh_at_startup.push(part);
+                }
                 "window-rule" => m_push!(window_rules),
                 "layer-rule" => m_push!(layer_rules),
                 "workspace" => m_push!(workspaces),
@@ -250,6 +304,16 @@ where
                     config.borrow_mut().prefer_no_csd = Flag::decode_node(node, ctx)?.0
                 }
 
+                "accent-color" => {
+                    let color = Color::decode_node(node, ctx)?;
+                    config.borrow_mut().accent_color = Some(color);
+                }
+
+                "ui-font" => {
+                    let font = UiFont::decode_node(node, ctx)?;
+                    config.borrow_mut().ui_font = Some(font.0);
+                }
+
                 "screenshot-path" => {
                     let part = knuffel::Decode::decode_node(node, ctx)?;
                     config.borrow_mut().screenshot_path = part;
@@ -457,6 +521,17 @@ where
     }
 }
 
+// Critical commands are respawned by the watchdog, which doesn't route windows or wait.
+fn critical_spawn_error<S: knuffel::traits::ErrorSpan>(
+    node: &knuffel::ast::SpannedNode<S>,
+) -> DecodeError<S> {
+    DecodeError::unexpected(
+        node,
+        "property",
+        "critical=true can't be combined with workspace, output or delay-ms",
+    )
+}
+
 impl Config {
     pub fn load_default() -> Self {
         let res = Config::parse(
@@ -646,6 +721,113 @@ mod tests {
         assert_eq!(config.input.keyboard.repeat_rate, 25);
     }
 
+    #[test]
+    fn accent_color_keeps_explicit_colors() {
+        let config = Config::parse_mem(
+            r##"
+            accent-color "#ff0000"
+
+            layout {
+                border {
+                    active-color "#00ff00"
+                }
+            }
+            "##,
+        )
+        .unwrap();
+
+        let accent = config.accent_color.unwrap();
+        let mut layout = config.layout.clone();
+        layout.apply_accent_color(accent);
+        assert_eq!(layout.focus_ring.active_color, accent);
+        assert_eq!(layout.tab_bar.active_bg, accent);
+        assert_eq!(
+            layout.border.active_color,
+            config.layout.border.active_color
+        );
+    }
+
+    #[test]
+    fn ipc_allowed_actions_are_checked() {
+        let config = Config::parse_mem(
+            r#"
+            ipc {
+                allowed-actions "focus-workspace" "set-config-value" "output"
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(config.ipc.allows_action("set-config-value"));
+
+        let config = Config::parse_mem(
+            r#"
+            ipc {
+                allowed-actions "focus-workspace" "focus-wrokspace"
+            }
+            "#,
+        );
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn critical_startup_commands_reject_spawn_options() {
+        let config = Config::parse_mem(
+            r#"
+            spawn-at-startup "waybar" critical=true
+            spawn-sh-at-startup "firefox" workspace="web" delay-ms=500
+            "#,
+        );
+        assert!(config.is_ok());
+
+        let config = Config::parse_mem(
+            r#"
+            spawn-at-startup "waybar" critical=true output="DP-1"
+            "#,
+        );
+        assert!(config.is_err());
+
+        let config = Config::parse_mem(
+            r#"
+            spawn-sh-at-startup "qs" critical=true delay-ms=500
+            "#,
+        );
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn switch_events_take_any_actions() {
+        let config = Config::parse_mem(
+            r#"
+            switch-events {
+                lid-close {
+                    spawn "swaylock"
+                    power-off-monitor "eDP-1"
+                }
+
+                tablet-mode-layout {
+                    tab-bar {
+                        height 36
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let switch_events = &config.switch_events;
+        assert_eq!(
+            switch_events.lid_close.as_ref().unwrap().actions,
+            [
+                Action::Spawn(vec![String::from("swaylock")]),
+                Action::PowerOffMonitor(Some(String::from("eDP-1"))),
+            ]
+        );
+
+        let layout = switch_events.tablet_mode_layout.as_ref().unwrap();
+        let tab_bar = layout.tab_bar.as_ref().unwrap();
+        assert_eq!(tab_bar.height, Some(FloatOrInt(36.)));
+    }
+
     #[track_caller]
     fn do_parse(text: &str) -> Config {
         Config::parse_mem(text)
@@ -728,7 +910,8 @@ mod tests {
                 disable-power-key-handling
 
                 warp-mouse-to-focus
-                focus-follows-mouse
+                focus-follows-mouse windows="tiled" delay-ms=150 containers-only=true
+                disable-click-to-raise
                 workspace-auto-back-and-forth
 
                 mod-key "Mod5"
@@ -742,6 +925,7 @@ mod tests {
                 position x=10 y=20
                 mode "1920x1080@144"
                 variable-refresh-rate on-demand=true
+                max-render-rate 60
                 background-color "rgba(25, 25, 102, 1.0)"
                 hot-corners {
                     off
@@ -750,16 +934,36 @@ mod tests {
                     bottom-left
                     bottom-right
                 }
+                idle-dim {
+                    timeout-ms 120000
+                    brightness 0.3
+                }
+                power-off-after-ms 600000
             }
 
             output "eDP-2" {
                 mode custom=true "1920x1080@144"
+                safe-area {
+                    top 32
+                }
             }
 
             output "eDP-3" {
                 modeline 173.00  1920 2048 2248 2576  1080 1083 1088 1120 "-hsync" "+vsync"
             }
 
+            output-profile "docked" {
+                output "eDP-1" {
+                    off
+                }
+                output "DP-1" {
+                    scale 1.5
+                }
+                workspace "chat" {
+                    open-on-output "DP-1"
+                }
+            }
+
             layout {
                 focus-ring {
                     width 5
@@ -810,17 +1014,29 @@ mod tests {
 
                 default-column-display "tabbed"
 
+                floating-placement "smart"
+
+                auto-split
+
+                workspace-indices "keep-gaps"
+
+                background-image "~/wallpaper.png" mode="fit"
+
                 insert-hint {
                     color "rgb(255, 200, 127)"
                     gradient from="rgba(10, 20, 30, 1.0)" to="#0080ffff" relative-to="workspace-view"
                 }
             }
 
-            spawn-at-startup "alacritty" "-e" "fish"
-            spawn-sh-at-startup "qs -c ~/source/qs/MyAwesomeShell"
+            spawn-at-startup "alacritty" "-e" "fish" workspace="chat" delay-ms=500
+            spawn-sh-at-startup "qs -c ~/source/qs/MyAwesomeShell" critical=true
 
             prefer-no-csd
 
+            accent-color "#ff0000"
+
+            ui-font "Inter 13px"
+
             cursor {
                 xcursor-theme "breeze_cursors"
                 xcursor-size 16
@@ -834,6 +1050,47 @@ mod tests {
                 disable-primary
             }
 
+            occluded-windows {
+                frame-callback-interval-ms 5000
+            }
+
+            unresponsive-windows {
+                timeout-ms 10000
+            }
+
+            picture-in-picture {
+                corner "top-left"
+            }
+
+            fullscreen-video {
+                off
+            }
+
+            session-snapshot {
+                on
+                interval-ms 5000
+            }
+
+            night-light {
+                temperature 4000
+                from "21:30"
+                to "06:45"
+            }
+
+            power-saving {
+                on-battery
+                animation-slowdown 0.25
+            }
+
+            power-menu {
+                lock "swaylock" "-f"
+            }
+
+            ipc {
+                allowed-actions "focus-workspace" "spawn"
+                i3-socket
+            }
+
             hotkey-overlay {
                 skip-at-startup
             }
@@ -854,6 +1111,7 @@ mod tests {
 
                 window-close {
                     curve "cubic-bezier" 0.05 0.7 0.1 1  
+                    style "slide"
                 }
 
                 recent-windows-close {
@@ -866,6 +1124,10 @@ mod tests {
                     trigger-width 10
                     max-speed 50
                 }
+
+                dnd-edge-hover {
+                    delay-ms 300
+                }
             }
 
             environment {
@@ -875,17 +1137,26 @@ mod tests {
 
             window-rule {
                 match app-id=".*alacritty"
-                exclude title="~"
-                exclude is-active=true is-focused=false
+                exclude title="~" sandbox-engine="flatpak"
+                exclude is-active=true is-focused=false is-dialog=true has-parent=false
+                match-on-change
 
                 open-on-output "eDP-1"
                 open-maximized true
                 open-fullscreen false
                 open-floating false
                 open-focused true
+                open-always-on-top true
                 default-window-height { fixed 500; }
                 default-column-display "tabbed"
+                tab-bar-position "left"
+                default-floating-size "800x600"
                 default-floating-position x=100 y=-200 relative-to="bottom-left"
+                force-scale 2
+                focus-follows-mouse false
+                focus-on-activation "never"
+                track-activity false
+                title-format "%app: %title"
 
                 focus-ring {
                     off
@@ -920,9 +1191,11 @@ mod tests {
                 Mod+Comma { consume-window-into-column; }
                 Mod+1 { focus-workspace 1; }
                 Mod+Shift+1 { focus-workspace "workspace-1"; }
+                Mod+Ctrl+1 { focus-workspace "+1"; }
                 Mod+Shift+E allow-inhibiting=false { quit skip-confirmation=true; }
                 Mod+WheelScrollDown cooldown-ms=150 { focus-workspace-down; }
                 Super+Alt+S allow-when-locked=true { spawn-sh "pkill orca || exec orca"; }
+                Mod+M { spawn-on-workspace "mail" "thunderbird"; }
             }
 
             switch-events {
@@ -1127,8 +1400,16 @@ mod tests {
                 focus_follows_mouse: Some(
                     FocusFollowsMouse {
                         max_scroll_amount: None,
+                        windows: Some(
+                            Tiled,
+                        ),
+                        delay_ms: Some(
+                            150,
+                        ),
+                        containers_only: true,
                     },
                 ),
+                disable_click_to_raise: true,
                 workspace_auto_back_and_forth: true,
                 mod_key: Some(
                     IsoLevel3Shift,
@@ -1172,6 +1453,11 @@ mod tests {
                                 on_demand: true,
                             },
                         ),
+                        max_render_rate: Some(
+                            FloatOrInt(
+                                60.0,
+                            ),
+                        ),
                         focus_at_startup: true,
                         background_color: Some(
                             Color {
@@ -1191,7 +1477,20 @@ mod tests {
                                 bottom_right: true,
                             },
                         ),
+                        safe_area: None,
                         layout: None,
+                        idle_dim: Some(
+                            IdleDim {
+                                timeout_ms: 120000,
+                                brightness: FloatOrInt(
+                                    0.3,
+                                ),
+                                duration_ms: 1000,
+                            },
+                        ),
+                        power_off_after_ms: Some(
+                            600000,
+                        ),
                     },
                     Output {
                         off: false,
@@ -1213,11 +1512,30 @@ mod tests {
                         ),
                         modeline: None,
                         variable_refresh_rate: None,
+                        max_render_rate: None,
                         focus_at_startup: false,
                         background_color: None,
                         backdrop_color: None,
                         hot_corners: None,
+                        safe_area: Some(
+                            SafeArea {
+                                left: FloatOrInt(
+                                    0.0,
+                                ),
+                                right: FloatOrInt(
+                                    0.0,
+                                ),
+                                top: FloatOrInt(
+                                    32.0,
+                                ),
+                                bottom: FloatOrInt(
+                                    0.0,
+                                ),
+                            },
+                        ),
                         layout: None,
+                        idle_dim: None,
+                        power_off_after_ms: None,
                     },
                     Output {
                         off: false,
@@ -1242,14 +1560,71 @@ mod tests {
                             },
                         ),
                         variable_refresh_rate: None,
+                        max_render_rate: None,
                         focus_at_startup: false,
                         background_color: None,
                         backdrop_color: None,
                         hot_corners: None,
+                        safe_area: None,
                         layout: None,
+                        idle_dim: None,
+                        power_off_after_ms: None,
                     },
                 ],
             ),
+            output_profiles: [
+                OutputProfile {
+                    name: "docked",
+                    outputs: [
+                        Output {
+                            off: true,
+                            name: "eDP-1",
+                            scale: None,
+                            transform: Normal,
+                            position: None,
+                            mode: None,
+                            modeline: None,
+                            variable_refresh_rate: None,
+                            max_render_rate: None,
+                            focus_at_startup: false,
+                            background_color: None,
+                            backdrop_color: None,
+                            hot_corners: None,
+                            safe_area: None,
+                            layout: None,
+                            idle_dim: None,
+                        },
+                        Output {
+                            off: false,
+                            name: "DP-1",
+                            scale: Some(
+                                FloatOrInt(
+                                    1.5,
+                                ),
+                            ),
+                            transform: Normal,
+                            position: None,
+                            mode: None,
+                            modeline: None,
+                            variable_refresh_rate: None,
+                            max_render_rate: None,
+                            focus_at_startup: false,
+                            background_color: None,
+                            backdrop_color: None,
+                            hot_corners: None,
+                            safe_area: None,
+                            layout: None,
+                            idle_dim: None,
+                        },
+                    ],
+                    workspaces: [
+                        OutputProfileWorkspace {
+                            name: "chat",
+                            open_on_output: "DP-1",
+                        },
+                    ],
+                },
+            ],
             spawn_at_startup: [
                 SpawnAtStartup {
                     command: [
@@ -1257,11 +1632,23 @@ mod tests {
                         "-e",
                         "fish",
                     ],
+                    critical: false,
+                    workspace: Some(
+                        "chat",
+                    ),
+                    output: None,
+                    delay_ms: Some(
+                        500,
+                    ),
                 },
             ],
             spawn_sh_at_startup: [
                 SpawnShAtStartup {
                     command: "qs -c ~/source/qs/MyAwesomeShell",
+                    critical: true,
+                    workspace: None,
+                    output: None,
+                    delay_ms: None,
                 },
             ],
             layout: Layout {
@@ -1336,6 +1723,8 @@ mod tests {
                                 color_space: Srgb,
                                 hue_interpolation: Shorter,
                             },
+                            animate: None,
+                            period_ms: 4000,
                         },
                     ),
                     active_indicator_gradient: None,
@@ -1481,6 +1870,8 @@ mod tests {
                                 color_space: Srgb,
                                 hue_interpolation: Shorter,
                             },
+                            animate: None,
+                            period_ms: 4000,
                         },
                     ),
                 },
@@ -1534,14 +1925,49 @@ mod tests {
                         0.0,
                     ),
                 },
+                safe_area: SafeArea {
+                    left: FloatOrInt(
+                        0.0,
+                    ),
+                    right: FloatOrInt(
+                        0.0,
+                    ),
+                    top: FloatOrInt(
+                        0.0,
+                    ),
+                    bottom: FloatOrInt(
+                        0.0,
+                    ),
+                },
                 background_color: Color {
                     r: 0.25,
                     g: 0.25,
                     b: 0.25,
                     a: 1.0,
                 },
+                background_gradient: None,
+                background_image: Some(
+                    BackgroundImage {
+                        path: "~/wallpaper.png",
+                        mode: Fit,
+                    },
+                ),
+                floating_placement: Smart,
+                auto_split: true,
+                workspace_indices: KeepGaps,
             },
             prefer_no_csd: true,
+            accent_color: Some(
+                Color {
+                    r: 1.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 1.0,
+                },
+            ),
+            ui_font: Some(
+                "Inter 13px",
+            ),
             cursor: Cursor {
                 xcursor_theme: "breeze_cursors",
                 xcursor_size: 16,
@@ -1590,6 +2016,7 @@ mod tests {
                             },
                         ),
                     },
+                    style: Popin,
                     custom_shader: None,
                 },
                 window_close: WindowCloseAnim {
@@ -1607,6 +2034,7 @@ mod tests {
                             },
                         ),
                     },
+                    style: Slide,
                     custom_shader: None,
                 },
                 window_movement: WindowMovementAnim(
@@ -1700,6 +2128,11 @@ mod tests {
                     delay_ms: 100,
                     max_speed: 1500.0,
                 },
+                dnd_edge_hover: DndEdgeHover {
+                    off: false,
+                    trigger_size: 8.0,
+                    delay_ms: 300,
+                },
                 hot_corners: HotCorners {
                     off: false,
                     top_left: false,
@@ -1716,6 +2149,7 @@ mod tests {
                     b: 0.15,
                     a: 1.0,
                 },
+                backdrop_dim: 0.0,
                 workspace_shadow: WorkspaceShadow {
                     off: false,
                     offset: ShadowOffset {
@@ -1735,6 +2169,16 @@ mod tests {
                         a: 0.3137255,
                     },
                 },
+                workspace_labels: WorkspaceLabels {
+                    off: false,
+                    font: "sans 14px",
+                    color: Color {
+                        r: 1.0,
+                        g: 1.0,
+                        b: 1.0,
+                        a: 0.8,
+                    },
+                },
             },
             environment: Environment(
                 [
@@ -1753,6 +2197,68 @@ mod tests {
             xwayland_satellite: XwaylandSatellite {
                 off: false,
                 path: "xwayland-satellite",
+                scale_to_output: false,
+            },
+            occluded_windows: OccludedWindows {
+                off: false,
+                frame_callback_interval_ms: 5000,
+            },
+            unresponsive_windows: UnresponsiveWindows {
+                off: false,
+                ping_interval_ms: 5000,
+                timeout_ms: 10000,
+            },
+            picture_in_picture: PictureInPicture {
+                off: false,
+                corner: TopLeft,
+            },
+            fullscreen_video: FullscreenVideo {
+                off: true,
+            },
+            session_snapshot: SessionSnapshot {
+                on: true,
+                interval_ms: 5000,
+            },
+            night_light: NightLight {
+                temperature: 4000,
+                from: Some(
+                    TimeOfDay {
+                        hour: 21,
+                        minute: 30,
+                    },
+                ),
+                to: Some(
+                    TimeOfDay {
+                        hour: 6,
+                        minute: 45,
+                    },
+                ),
+                transition_minutes: 30,
+            },
+            power_saving: PowerSaving {
+                on_battery: true,
+                animation_slowdown: 0.25,
+                keep_shadows: false,
+            },
+            power_menu: PowerMenu {
+                exit: None,
+                lock: Some(
+                    [
+                        "swaylock",
+                        "-f",
+                    ],
+                ),
+                suspend: None,
+                reboot: None,
+            },
+            ipc: Ipc {
+                allowed_actions: Some(
+                    [
+                        "focus-workspace",
+                        "spawn",
+                    ],
+                ),
+                i3_socket: true,
             },
             window_rules: [
                 WindowRule {
@@ -1773,6 +2279,9 @@ mod tests {
                             is_window_cast_target: None,
                             is_urgent: None,
                             at_startup: None,
+                            is_dialog: None,
+                            has_parent: None,
+                            sandbox_engine: None,
                         },
                     ],
                     excludes: [
@@ -1792,6 +2301,15 @@ mod tests {
                             is_window_cast_target: None,
                             is_urgent: None,
                             at_startup: None,
+                            is_dialog: None,
+                            has_parent: None,
+                            sandbox_engine: Some(
+                                RegexEq(
+                                    Regex(
+                                        "flatpak",
+                                    ),
+                                ),
+                            ),
                         },
                         Match {
                             app_id: None,
@@ -1807,8 +2325,16 @@ mod tests {
                             is_window_cast_target: None,
                             is_urgent: None,
                             at_startup: None,
+                            is_dialog: Some(
+                                true,
+                            ),
+                            has_parent: Some(
+                                false,
+                            ),
+                            sandbox_engine: None,
                         },
                     ],
+                    match_on_change: true,
                     default_column_width: None,
                     default_window_height: Some(
                         DefaultPresetSize(
@@ -1836,6 +2362,9 @@ mod tests {
                     open_focused: Some(
                         true,
                     ),
+                    open_always_on_top: Some(
+                        true,
+                    ),
                     min_width: None,
                     min_height: None,
                     max_width: None,
@@ -1925,6 +2454,15 @@ mod tests {
                     default_column_display: Some(
                         Tabbed,
                     ),
+                    tab_bar_position: Some(
+                        Left,
+                    ),
+                    default_floating_size: Some(
+                        FloatingSize {
+                            width: 800,
+                            height: 600,
+                        },
+                    ),
                     default_floating_position: Some(
                         FloatingPosition {
                             x: FloatOrInt(
@@ -1938,6 +2476,28 @@ mod tests {
                     ),
                     scroll_factor: None,
                     tiled_state: None,
+                    fullscreen_span: None,
+                    open_animation: None,
+                    close_animation: None,
+                    keep_workspace: None,
+                    throttle_when_occluded: None,
+                    force_scale: Some(
+                        FloatOrInt(
+                            2.0,
+                        ),
+                    ),
+                    focus_follows_mouse: Some(
+                        false,
+                    ),
+                    focus_on_activation: Some(
+                        Never,
+                    ),
+                    track_activity: Some(
+                        false,
+                    ),
+                    title_format: Some(
+                        "%app: %title",
+                    ),
                 },
             ],
             layer_rules: [
@@ -2192,6 +2752,26 @@ mod tests {
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
                     },
+                    Bind {
+                        key: Key {
+                            trigger: Keysym(
+                                XK_1,
+                            ),
+                            modifiers: Modifiers(
+                                CTRL | COMPOSITOR,
+                            ),
+                        },
+                        action: FocusWorkspace(
+                            Relative(
+                                1,
+                            ),
+                        ),
+                        repeat: true,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                    },
                     Bind {
                         key: Key {
                             trigger: Keysym(
@@ -2244,6 +2824,27 @@ mod tests {
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
                     },
+                    Bind {
+                        key: Key {
+                            trigger: Keysym(
+                                XK_m,
+                            ),
+                            modifiers: Modifiers(
+                                COMPOSITOR,
+                            ),
+                        },
+                        action: SpawnOnWorkspace(
+                            "mail",
+                            [
+                                "thunderbird",
+                            ],
+                        ),
+                        repeat: true,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                    },
                 ],
             ),
             switch_events: SwitchBinds {
@@ -2251,22 +2852,31 @@ mod tests {
                 lid_close: None,
                 tablet_mode_on: Some(
                     SwitchAction {
-                        spawn: [
-                            "bash",
-                            "-c",
-                            "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled true",
+                        actions: [
+                            Spawn(
+                                [
+                                    "bash",
+                                    "-c",
+                                    "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled true",
+                                ],
+                            ),
                         ],
                     },
                 ),
                 tablet_mode_off: Some(
                     SwitchAction {
-                        spawn: [
-                            "bash",
-                            "-c",
-                            "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled false",
+                        actions: [
+                            Spawn(
+                                [
+                                    "bash",
+                                    "-c",
+                                    "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled false",
+                                ],
+                            ),
                         ],
                     },
                 ),
+                tablet_mode_layout: None,
             },
             debug: Debug {
                 preview_render: None,
@@ -2295,6 +2905,8 @@ mod tests {
                 honor_xdg_activation_with_invalid_serial: false,
                 deactivate_unfocused_windows: false,
                 skip_cursor_only_updates_during_vrr: false,
+                allow_ipc_macros: false,
+                nested_outputs: None,
             },
             workspaces: [
                 Workspace {
@@ -2487,6 +3099,10 @@ mod tests {
         +            command: [
         +                "waybar",
         +            ],
+        +            critical: false,
+        +            workspace: None,
+        +            output: None,
+        +            delay_ms: None,
         +        },
         +    ],
 
@@ -1,3 +1,7 @@
+use std::str::FromStr;
+
+use miette::miette;
+
 use crate::appearance::{Color, WorkspaceShadow, WorkspaceShadowPart, DEFAULT_BACKDROP_COLOR};
 use crate::utils::{Flag, MergeWith};
 use crate::FloatOrInt;
@@ -6,12 +10,50 @@ use crate::FloatOrInt;
 pub struct SpawnAtStartup {
     #[knuffel(arguments)]
     pub command: Vec<String>,
+    /// Whether to respawn the command when it crashes.
+    #[knuffel(property, default)]
+    pub critical: bool,
+    /// Name of the workspace to open the first window of the command on.
+    #[knuffel(property)]
+    pub workspace: Option<String>,
+    /// Name of the output to open the first window of the command on.
+    #[knuffel(property)]
+    pub output: Option<String>,
+    /// Delay before spawning the command.
+    #[knuffel(property)]
+    pub delay_ms: Option<u64>,
+}
+
+impl SpawnAtStartup {
+    /// Whether the entry sets options that only apply to a single spawn.
+    pub fn has_spawn_options(&self) -> bool {
+        self.workspace.is_some() || self.output.is_some() || self.delay_ms.is_some()
+    }
 }
 
 #[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
 pub struct SpawnShAtStartup {
     #[knuffel(argument)]
     pub command: String,
+    /// Whether to respawn the command when it crashes.
+    #[knuffel(property, default)]
+    pub critical: bool,
+    /// Name of the workspace to open the first window of the command on.
+    #[knuffel(property)]
+    pub workspace: Option<String>,
+    /// Name of the output to open the first window of the command on.
+    #[knuffel(property)]
+    pub output: Option<String>,
+    /// Delay before spawning the command.
+    #[knuffel(property)]
+    pub delay_ms: Option<u64>,
+}
+
+impl SpawnShAtStartup {
+    /// Whether the entry sets options that only apply to a single spawn.
+    pub fn has_spawn_options(&self) -> bool {
+        self.workspace.is_some() || self.output.is_some() || self.delay_ms.is_some()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -53,6 +95,9 @@ impl MergeWith<CursorPart> for Cursor {
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct UiFont(#[knuffel(argument)] pub String);
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct ScreenshotPath(#[knuffel(argument)] pub Option<String>);
 
@@ -101,6 +146,377 @@ impl MergeWith<ConfigNotificationPart> for ConfigNotification {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OccludedWindows {
+    pub off: bool,
+    pub frame_callback_interval_ms: u32,
+}
+
+impl Default for OccludedWindows {
+    fn default() -> Self {
+        Self {
+            off: false,
+            frame_callback_interval_ms: 1000,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OccludedWindowsPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub frame_callback_interval_ms: Option<u32>,
+}
+
+impl MergeWith<OccludedWindowsPart> for OccludedWindows {
+    fn merge_with(&mut self, part: &OccludedWindowsPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge_clone!((self, part), frame_callback_interval_ms);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnresponsiveWindows {
+    pub off: bool,
+    /// How often to ping clients with windows.
+    pub ping_interval_ms: u32,
+    /// How long a client can take to answer a ping before its windows show the prompt.
+    pub timeout_ms: u32,
+}
+
+impl Default for UnresponsiveWindows {
+    fn default() -> Self {
+        Self {
+            off: false,
+            ping_interval_ms: 5000,
+            timeout_ms: 5000,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnresponsiveWindowsPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub ping_interval_ms: Option<u32>,
+    #[knuffel(child, unwrap(argument))]
+    pub timeout_ms: Option<u32>,
+}
+
+impl MergeWith<UnresponsiveWindowsPart> for UnresponsiveWindows {
+    fn merge_with(&mut self, part: &UnresponsiveWindowsPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge_clone!((self, part), ping_interval_ms, timeout_ms);
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FullscreenVideo {
+    pub off: bool,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FullscreenVideoPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+}
+
+impl MergeWith<FullscreenVideoPart> for FullscreenVideo {
+    fn merge_with(&mut self, part: &FullscreenVideoPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionSnapshot {
+    pub on: bool,
+    pub interval_ms: u32,
+}
+
+impl Default for SessionSnapshot {
+    fn default() -> Self {
+        Self {
+            on: false,
+            interval_ms: 10_000,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionSnapshotPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub interval_ms: Option<u32>,
+}
+
+impl MergeWith<SessionSnapshotPart> for SessionSnapshot {
+    fn merge_with(&mut self, part: &SessionSnapshotPart) {
+        self.on |= part.on;
+        if part.off {
+            self.on = false;
+        }
+
+        merge_clone!((self, part), interval_ms);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightLight {
+    /// Color temperature at night, in Kelvin.
+    pub temperature: u16,
+    /// Local time when the night starts.
+    pub from: Option<TimeOfDay>,
+    /// Local time when the night ends.
+    pub to: Option<TimeOfDay>,
+    /// Duration of the ramp at either end of the night.
+    pub transition_minutes: u16,
+}
+
+impl Default for NightLight {
+    fn default() -> Self {
+        Self {
+            temperature: 4500,
+            from: None,
+            to: None,
+            transition_minutes: 30,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightLightPart {
+    #[knuffel(child, unwrap(argument))]
+    pub temperature: Option<u16>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub from: Option<TimeOfDay>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub to: Option<TimeOfDay>,
+    #[knuffel(child, unwrap(argument))]
+    pub transition_minutes: Option<u16>,
+}
+
+impl MergeWith<NightLightPart> for NightLight {
+    fn merge_with(&mut self, part: &NightLightPart) {
+        merge_clone!((self, part), temperature, transition_minutes);
+        merge_clone_opt!((self, part), from, to);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerSaving {
+    /// Whether to turn power saving on automatically while on battery.
+    pub on_battery: bool,
+    /// Animation slowdown while saving power, on top of the usual slowdown.
+    pub animation_slowdown: f64,
+    /// Whether to keep drawing window shadows while saving power.
+    pub keep_shadows: bool,
+}
+
+impl Default for PowerSaving {
+    fn default() -> Self {
+        Self {
+            on_battery: false,
+            animation_slowdown: 0.5,
+            keep_shadows: false,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct PowerSavingPart {
+    #[knuffel(child)]
+    pub on_battery: Option<Flag>,
+    #[knuffel(child, unwrap(argument))]
+    pub animation_slowdown: Option<FloatOrInt<0, { i32::MAX }>>,
+    #[knuffel(child)]
+    pub keep_shadows: Option<Flag>,
+}
+
+impl MergeWith<PowerSavingPart> for PowerSaving {
+    fn merge_with(&mut self, part: &PowerSavingPart) {
+        merge!((self, part), on_battery, animation_slowdown, keep_shadows);
+    }
+}
+
+/// Commands of the power menu entries.
+///
+/// `None` means the built-in behavior of the entry.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PowerMenu {
+    pub exit: Option<Vec<String>>,
+    pub lock: Option<Vec<String>>,
+    pub suspend: Option<Vec<String>>,
+    pub reboot: Option<Vec<String>>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct PowerMenuPart {
+    #[knuffel(child, unwrap(arguments))]
+    pub exit: Option<Vec<String>>,
+    #[knuffel(child, unwrap(arguments))]
+    pub lock: Option<Vec<String>>,
+    #[knuffel(child, unwrap(arguments))]
+    pub suspend: Option<Vec<String>>,
+    #[knuffel(child, unwrap(arguments))]
+    pub reboot: Option<Vec<String>>,
+}
+
+impl MergeWith<PowerMenuPart> for PowerMenu {
+    fn merge_with(&mut self, part: &PowerMenuPart) {
+        merge_clone_opt!((self, part), exit, lock, suspend, reboot);
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ipc {
+    /// Actions that IPC clients may run, or `None` to allow all actions.
+    pub allowed_actions: Option<Vec<String>>,
+    /// Whether to also listen on a socket speaking the i3 IPC protocol.
+    pub i3_socket: bool,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct IpcPart {
+    #[knuffel(child, unwrap(arguments))]
+    pub allowed_actions: Option<Vec<String>>,
+    #[knuffel(child)]
+    pub i3_socket: Option<Flag>,
+}
+
+impl MergeWith<IpcPart> for Ipc {
+    fn merge_with(&mut self, part: &IpcPart) {
+        merge_clone_opt!((self, part), allowed_actions);
+        merge!((self, part), i3_socket);
+    }
+}
+
+/// Names for `ipc { allowed-actions }` that allow IPC requests other than actions.
+pub const IPC_PERMISSIONS: &[&str] = &[
+    "output",
+    "headless-output",
+    "set-config-value",
+    "set-container-percents",
+    "pick-window",
+    "pick-color",
+    "workspace-thumbnail",
+];
+
+impl Ipc {
+    /// Returns the names in the list that are neither actions nor [`IPC_PERMISSIONS`].
+    pub fn unknown_names(names: &[String]) -> Vec<&str> {
+        let actions = niri_ipc::Action::names();
+        names
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !IPC_PERMISSIONS.contains(name) && !actions.iter().any(|a| a == name))
+            .collect()
+    }
+
+    /// Returns whether IPC clients may run the action with this name, like `focus-workspace`.
+    pub fn allows_action(&self, name: &str) -> bool {
+        self.allowed_actions
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|allowed| allowed == name))
+    }
+}
+
+/// Time of day with minute precision, written as `HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl TimeOfDay {
+    /// Returns the number of minutes since midnight.
+    pub fn minutes(self) -> u32 {
+        u32::from(self.hour) * 60 + u32::from(self.minute)
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s.split_once(':').and_then(|(hour, minute)| {
+            if minute.len() != 2 {
+                return None;
+            }
+            let hour = hour.parse::<u8>().ok().filter(|h| *h < 24)?;
+            let minute = minute.parse::<u8>().ok().filter(|m| *m < 60)?;
+            Some(Self { hour, minute })
+        });
+        parsed.ok_or_else(|| miette!(r#"invalid time of day, expected "HH:MM", e.g. "20:30""#))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PictureInPicture {
+    pub off: bool,
+    pub corner: Corner,
+}
+
+impl Default for PictureInPicture {
+    fn default() -> Self {
+        Self {
+            off: false,
+            corner: Corner::BottomRight,
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PictureInPicturePart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub corner: Option<Corner>,
+}
+
+impl MergeWith<PictureInPicturePart> for PictureInPicture {
+    fn merge_with(&mut self, part: &PictureInPicturePart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge_clone!((self, part), corner);
+    }
+}
+
+/// Corner of the working area of an output.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Clipboard {
     pub disable_primary: bool,
@@ -118,11 +534,14 @@ impl MergeWith<ClipboardPart> for Clipboard {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Overview {
     pub zoom: f64,
     pub backdrop_color: Color,
+    /// Opacity of the black drawn over the backdrop, from 0 to 1.
+    pub backdrop_dim: f64,
     pub workspace_shadow: WorkspaceShadow,
+    pub workspace_labels: WorkspaceLabels,
 }
 
 impl Default for Overview {
@@ -130,28 +549,81 @@ impl Default for Overview {
         Self {
             zoom: 0.5,
             backdrop_color: DEFAULT_BACKDROP_COLOR,
+            backdrop_dim: 0.,
             workspace_shadow: WorkspaceShadow::default(),
+            workspace_labels: WorkspaceLabels::default(),
         }
     }
 }
 
-#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
 pub struct OverviewPart {
     #[knuffel(child, unwrap(argument))]
     pub zoom: Option<FloatOrInt<0, 1>>,
     #[knuffel(child)]
     pub backdrop_color: Option<Color>,
+    #[knuffel(child, unwrap(argument))]
+    pub backdrop_dim: Option<FloatOrInt<0, 1>>,
     #[knuffel(child)]
     pub workspace_shadow: Option<WorkspaceShadowPart>,
+    #[knuffel(child)]
+    pub workspace_labels: Option<WorkspaceLabelsPart>,
 }
 
 impl MergeWith<OverviewPart> for Overview {
     fn merge_with(&mut self, part: &OverviewPart) {
-        merge!((self, part), zoom, workspace_shadow);
+        merge!(
+            (self, part),
+            zoom,
+            backdrop_dim,
+            workspace_shadow,
+            workspace_labels,
+        );
         merge_clone!((self, part), backdrop_color);
     }
 }
 
+/// Workspace names or indices shown under the workspaces in the overview.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceLabels {
+    pub off: bool,
+    pub font: String,
+    pub color: Color,
+}
+
+impl Default for WorkspaceLabels {
+    fn default() -> Self {
+        Self {
+            off: false,
+            font: String::from("sans 14px"),
+            color: Color::from_rgba8_unpremul(0xff, 0xff, 0xff, 0xcc),
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct WorkspaceLabelsPart {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub font: Option<String>,
+    #[knuffel(child)]
+    pub color: Option<Color>,
+}
+
+impl MergeWith<WorkspaceLabelsPart> for WorkspaceLabels {
+    fn merge_with(&mut self, part: &WorkspaceLabelsPart) {
+        self.off |= part.off;
+        if part.on {
+            self.off = false;
+        }
+
+        merge_clone!((self, part), font, color);
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
 pub struct Environment(#[knuffel(children)] pub Vec<EnvironmentVariable>);
 
@@ -167,6 +639,8 @@ pub struct EnvironmentVariable {
 pub struct XwaylandSatellite {
     pub off: bool,
     pub path: String,
+    /// Whether to draw X11 windows at the scale of their output.
+    pub scale_to_output: bool,
 }
 
 impl Default for XwaylandSatellite {
@@ -174,6 +648,7 @@ impl Default for XwaylandSatellite {
         Self {
             off: false,
             path: String::from("xwayland-satellite"),
+            scale_to_output: false,
         }
     }
 }
@@ -186,6 +661,8 @@ pub struct XwaylandSatellitePart {
     pub on: bool,
     #[knuffel(child, unwrap(argument))]
     pub path: Option<String>,
+    #[knuffel(child)]
+    pub scale_to_output: Option<Flag>,
 }
 
 impl MergeWith<XwaylandSatellitePart> for XwaylandSatellite {
@@ -195,6 +672,7 @@ impl MergeWith<XwaylandSatellitePart> for XwaylandSatellite {
             self.off = false;
         }
 
+        merge!((self, part), scale_to_output);
         merge_clone!((self, part), path);
     }
 }
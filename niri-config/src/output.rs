@@ -8,7 +8,7 @@ use knuffel::Decode;
 use niri_ipc::{ConfiguredMode, HSyncPolarity, Transform, VSyncPolarity};
 
 use crate::gestures::HotCorners;
-use crate::{Color, FloatOrInt, LayoutPart};
+use crate::{Color, FloatOrInt, LayoutPart, SafeArea};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct Outputs(pub Vec<Output>);
@@ -65,6 +65,8 @@ pub struct Output {
     pub modeline: Option<Modeline>,
     #[knuffel(child)]
     pub variable_refresh_rate: Option<Vrr>,
+    #[knuffel(child, unwrap(argument))]
+    pub max_render_rate: Option<FloatOrInt<1, 1000>>,
     #[knuffel(child)]
     pub focus_at_startup: bool,
     // Deprecated; use layout.background_color.
@@ -75,7 +77,14 @@ pub struct Output {
     #[knuffel(child)]
     pub hot_corners: Option<HotCorners>,
     #[knuffel(child)]
+    pub safe_area: Option<SafeArea>,
+    #[knuffel(child)]
     pub layout: Option<LayoutPart>,
+    #[knuffel(child)]
+    pub idle_dim: Option<IdleDim>,
+    /// Power off the output after this long without input.
+    #[knuffel(child, unwrap(argument))]
+    pub power_off_after_ms: Option<u32>,
 }
 
 impl Output {
@@ -90,6 +99,24 @@ impl Output {
     pub fn is_vrr_always_off(&self) -> bool {
         self.variable_refresh_rate.is_none()
     }
+
+    /// Returns the layout overrides for this output, including the output-level settings.
+    pub fn layout_config(&self) -> Option<LayoutPart> {
+        let mut layout = self.layout.clone();
+
+        // Support the deprecated non-layout background-color key.
+        if let Some(layout) = &mut layout {
+            if layout.background_color.is_none() {
+                layout.background_color = self.background_color;
+            }
+        }
+
+        if let Some(safe_area) = self.safe_area {
+            layout.get_or_insert_with(LayoutPart::default).safe_area = Some(safe_area);
+        }
+
+        layout
+    }
 }
 
 impl Default for Output {
@@ -104,14 +131,37 @@ impl Default for Output {
             mode: None,
             modeline: None,
             variable_refresh_rate: None,
+            max_render_rate: None,
             background_color: None,
             backdrop_color: None,
             hot_corners: None,
+            safe_area: None,
             layout: None,
+            idle_dim: None,
+            power_off_after_ms: None,
         }
     }
 }
 
+/// Set of output settings applied together when a specific combination of monitors is connected.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct OutputProfile {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(children(name = "output"))]
+    pub outputs: Vec<Output>,
+    #[knuffel(children(name = "workspace"))]
+    pub workspaces: Vec<OutputProfileWorkspace>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct OutputProfileWorkspace {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(child, unwrap(argument))]
+    pub open_on_output: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputName {
     pub connector: String,
@@ -134,6 +184,18 @@ pub struct Vrr {
     pub on_demand: bool,
 }
 
+/// Dimming of the output after a period without input.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct IdleDim {
+    #[knuffel(child, unwrap(argument))]
+    pub timeout_ms: u32,
+    /// Brightness to dim down to, from 0 (black) to 1 (not dimmed).
+    #[knuffel(child, unwrap(argument), default = FloatOrInt(0.5))]
+    pub brightness: FloatOrInt<0, 1>,
+    #[knuffel(child, unwrap(argument), default = 1000)]
+    pub duration_ms: u32,
+}
+
 impl FromIterator<Output> for Outputs {
     fn from_iter<T: IntoIterator<Item = Output>>(iter: T) -> Self {
         Self(Vec::from_iter(iter))
@@ -150,6 +212,33 @@ impl Outputs {
     }
 }
 
+impl OutputProfile {
+    /// Returns whether this profile is for exactly this set of connected outputs.
+    pub fn matches(&self, connected: &[OutputName]) -> bool {
+        connected
+            .iter()
+            .all(|name| self.outputs.iter().any(|o| name.matches(&o.name)))
+            && self
+                .outputs
+                .iter()
+                .all(|o| connected.iter().any(|name| name.matches(&o.name)))
+    }
+
+    /// Applies the output settings of this profile on top of `outputs`.
+    ///
+    /// Sections from the profile replace sections with the same name and take priority over all
+    /// other sections.
+    pub fn apply_to(&self, outputs: &mut Outputs) {
+        outputs.0.retain(|existing| {
+            !self
+                .outputs
+                .iter()
+                .any(|o| o.name.eq_ignore_ascii_case(&existing.name))
+        });
+        outputs.0.splice(0..0, self.outputs.iter().cloned());
+    }
+}
+
 impl OutputName {
     pub fn from_ipc_output(output: &niri_ipc::Output) -> Self {
         Self {
@@ -643,4 +732,36 @@ mod tests {
         "#
         );
     }
+
+    #[test]
+    fn test_output_profile_match() {
+        let profile = OutputProfile {
+            name: String::from("docked"),
+            outputs: vec![
+                Output {
+                    name: String::from("eDP-1"),
+                    ..Default::default()
+                },
+                Output {
+                    name: String::from("Some Company Some Monitor 1234"),
+                    ..Default::default()
+                },
+            ],
+            workspaces: vec![],
+        };
+
+        let laptop = make_output_name("eDP-1", None, None, None);
+        let monitor = make_output_name(
+            "DP-2",
+            Some("Some Company"),
+            Some("Some Monitor"),
+            Some("1234"),
+        );
+        let other = make_output_name("DP-3", None, None, None);
+
+        assert!(profile.matches(&[laptop.clone(), monitor.clone()]));
+        assert!(profile.matches(&[monitor.clone(), laptop.clone()]));
+        assert!(!profile.matches(&[laptop.clone()]));
+        assert!(!profile.matches(&[laptop, monitor, other]));
+    }
 }
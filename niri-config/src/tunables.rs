@@ -0,0 +1,115 @@
+//! Config options that can be read and changed at runtime through IPC.
+
+use niri_ipc::ConfigValue;
+
+use crate::input::FocusFollowsMouse;
+use crate::Config;
+
+/// Paths of all options that can be changed at runtime.
+pub const TUNABLE_PATHS: &[&str] = &[
+    "layout.gaps",
+    "input.focus-follows-mouse",
+    "animations.slowdown",
+];
+
+impl Config {
+    /// Returns the current value of a runtime-tunable option.
+    pub fn tunable_value(&self, path: &str) -> Result<ConfigValue, String> {
+        let value = match path {
+            "layout.gaps" => ConfigValue::Number(self.layout.gaps),
+            "input.focus-follows-mouse" => {
+                ConfigValue::Bool(self.input.focus_follows_mouse.is_some())
+            }
+            "animations.slowdown" => ConfigValue::Number(self.animations.slowdown),
+            _ => return Err(unknown_path(path)),
+        };
+        Ok(value)
+    }
+
+    /// Changes a runtime-tunable option after validating the new value.
+    pub fn set_tunable_value(&mut self, path: &str, value: ConfigValue) -> Result<(), String> {
+        match path {
+            "layout.gaps" => self.layout.gaps = number_in_range(path, value, 0., 65535.)?,
+            "input.focus-follows-mouse" => {
+                let enable = bool(path, value)?;
+                let ffm = &mut self.input.focus_follows_mouse;
+                if !enable {
+                    *ffm = None;
+                } else if ffm.is_none() {
+                    *ffm = Some(FocusFollowsMouse {
+                        max_scroll_amount: None,
+                        windows: None,
+                        delay_ms: None,
+                        containers_only: false,
+                    });
+                }
+            }
+            "animations.slowdown" => {
+                self.animations.slowdown = number_in_range(path, value, 0., f64::from(i32::MAX))?;
+            }
+            _ => return Err(unknown_path(path)),
+        }
+        Ok(())
+    }
+}
+
+fn unknown_path(path: &str) -> String {
+    format!(
+        "unknown or unsupported config option {path:?}, supported options: {}",
+        TUNABLE_PATHS.join(", ")
+    )
+}
+
+fn bool(path: &str, value: ConfigValue) -> Result<bool, String> {
+    match value {
+        ConfigValue::Bool(value) => Ok(value),
+        ConfigValue::Number(_) => Err(format!("{path} must be a boolean")),
+    }
+}
+
+fn number_in_range(path: &str, value: ConfigValue, min: f64, max: f64) -> Result<f64, String> {
+    match value {
+        ConfigValue::Number(value) if (min..=max).contains(&value) => Ok(value),
+        _ => Err(format!("{path} must be a number between {min} and {max}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_set_tunables() {
+        let mut config = Config::default();
+
+        for path in TUNABLE_PATHS {
+            let value = config.tunable_value(path).unwrap();
+            config.set_tunable_value(path, value).unwrap();
+        }
+
+        config
+            .set_tunable_value("layout.gaps", ConfigValue::Number(8.))
+            .unwrap();
+        assert_eq!(config.layout.gaps, 8.);
+
+        config
+            .set_tunable_value("input.focus-follows-mouse", ConfigValue::Bool(true))
+            .unwrap();
+        assert_eq!(
+            config.tunable_value("input.focus-follows-mouse"),
+            Ok(ConfigValue::Bool(true))
+        );
+
+        assert!(config
+            .set_tunable_value("layout.gaps", ConfigValue::Number(-1.))
+            .is_err());
+        assert!(config
+            .set_tunable_value("layout.gaps", ConfigValue::Bool(true))
+            .is_err());
+        assert!(config
+            .set_tunable_value("input.focus-follows-mouse", ConfigValue::Number(1.))
+            .is_err());
+        assert!(config.tunable_value("layout.struts").is_err());
+        assert_eq!(config.layout.gaps, 8.);
+    }
+}
@@ -1,6 +1,11 @@
+use std::str::FromStr;
+
 use niri_ipc::ColumnDisplay;
 
-use crate::appearance::{BlockOutFrom, BorderRule, CornerRadius, ShadowRule, TabIndicatorRule};
+use crate::animations::WindowAnimStyle;
+use crate::appearance::{
+    BlockOutFrom, BorderRule, CornerRadius, ShadowRule, TabIndicatorPosition, TabIndicatorRule,
+};
 use crate::layout::DefaultPresetSize;
 use crate::utils::RegexEq;
 use crate::FloatOrInt;
@@ -11,6 +16,10 @@ pub struct WindowRule {
     pub matches: Vec<Match>,
     #[knuffel(children(name = "exclude"))]
     pub excludes: Vec<Match>,
+    /// Whether to re-apply the opening properties when the rule starts or stops matching a live
+    /// window.
+    #[knuffel(child)]
+    pub match_on_change: bool,
 
     // Rules applied at initial configure.
     #[knuffel(child)]
@@ -31,6 +40,8 @@ pub struct WindowRule {
     pub open_floating: Option<bool>,
     #[knuffel(child, unwrap(argument))]
     pub open_focused: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_always_on_top: Option<bool>,
 
     // Rules applied dynamically.
     #[knuffel(child, unwrap(argument))]
@@ -66,12 +77,36 @@ pub struct WindowRule {
     pub variable_refresh_rate: Option<bool>,
     #[knuffel(child, unwrap(argument, str))]
     pub default_column_display: Option<ColumnDisplay>,
+    #[knuffel(child, unwrap(argument))]
+    pub tab_bar_position: Option<TabIndicatorPosition>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub default_floating_size: Option<FloatingSize>,
     #[knuffel(child)]
     pub default_floating_position: Option<FloatingPosition>,
     #[knuffel(child, unwrap(argument))]
     pub scroll_factor: Option<FloatOrInt<0, 100>>,
     #[knuffel(child, unwrap(argument))]
     pub tiled_state: Option<bool>,
+    #[knuffel(child)]
+    pub fullscreen_span: Option<FullscreenSpan>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_animation: Option<WindowAnimStyle>,
+    #[knuffel(child, unwrap(argument))]
+    pub close_animation: Option<WindowAnimStyle>,
+    #[knuffel(child, unwrap(argument))]
+    pub keep_workspace: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub throttle_when_occluded: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub force_scale: Option<FloatOrInt<0, 10>>,
+    #[knuffel(child, unwrap(argument))]
+    pub focus_follows_mouse: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub focus_on_activation: Option<FocusOnActivation>,
+    #[knuffel(child, unwrap(argument))]
+    pub track_activity: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub title_format: Option<String>,
 }
 
 #[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
@@ -94,6 +129,12 @@ pub struct Match {
     pub is_urgent: Option<bool>,
     #[knuffel(property)]
     pub at_startup: Option<bool>,
+    #[knuffel(property)]
+    pub is_dialog: Option<bool>,
+    #[knuffel(property)]
+    pub has_parent: Option<bool>,
+    #[knuffel(property, str)]
+    pub sandbox_engine: Option<RegexEq>,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
@@ -106,6 +147,22 @@ pub struct FloatingPosition {
     pub relative_to: RelativeTo,
 }
 
+/// Window size in logical pixels, written as `WxH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatingSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Set of outputs that a fullscreen window should span.
+///
+/// An empty list means all outputs adjacent to the window's own output.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct FullscreenSpan {
+    #[knuffel(arguments)]
+    pub outputs: Vec<String>,
+}
+
 #[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum RelativeTo {
     #[default]
@@ -118,3 +175,32 @@ pub enum RelativeTo {
     Left,
     Right,
 }
+
+/// Whether a window requesting activation through xdg-activation gets focused.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusOnActivation {
+    /// Focus the window even when the activation token is invalid or expired.
+    Always,
+    /// Never focus the window, only mark it urgent.
+    Never,
+    /// Focus the window when the activation token is valid, and mark it urgent otherwise.
+    Smart,
+}
+
+impl FromStr for FloatingSize {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((width, height)) = s.split_once('x') else {
+            return Err("no 'x' separator found");
+        };
+
+        let width: u16 = width.parse().map_err(|_| "error parsing width")?;
+        let height: u16 = height.parse().map_err(|_| "error parsing height")?;
+        if width == 0 || height == 0 {
+            return Err("width and height must be > 0");
+        }
+
+        Ok(Self { width, height })
+    }
+}
@@ -121,6 +121,194 @@ pub enum Request {
     LayoutTree,
     /// Request information about screencasts.
     Casts,
+    /// Create a virtual headless output.
+    ///
+    /// The output has no physical display behind it. It takes part in the layout like any other
+    /// output, so it can be used for screencasting, testing or remote desktop.
+    ///
+    /// The compositor should reply with [`Response::OutputCreated`].
+    CreateHeadlessOutput {
+        /// Width of the output in physical pixels.
+        width: u16,
+        /// Height of the output in physical pixels.
+        height: u16,
+        /// Scale of the output.
+        ///
+        /// The scale from the config takes precedence. If `None` and the config has no scale,
+        /// uses `1`.
+        scale: Option<f64>,
+        /// Name of the output.
+        ///
+        /// If `None`, picks an unused `HEADLESS-<n>` name.
+        name: Option<String>,
+    },
+    /// Destroy a virtual output created with [`Request::CreateHeadlessOutput`].
+    ///
+    /// Windows and workspaces on the output move to other outputs, same as when a monitor is
+    /// disconnected.
+    DestroyOutput {
+        /// Output name.
+        output: String,
+    },
+    /// Request the current value of a runtime-tunable config option.
+    ///
+    /// Only a small set of options can be read this way, see [`Request::SetConfigValue`].
+    GetConfigValue {
+        /// Path of the option, for example `"layout.gaps"`.
+        path: String,
+    },
+    /// Change a runtime-tunable config option.
+    ///
+    /// Supported options:
+    ///
+    /// * `layout.gaps`: number, at least 0.
+    /// * `input.focus-follows-mouse`: boolean.
+    /// * `animations.slowdown`: number, at least 0.
+    ///
+    /// Same as with [`Request::Output`], the change is temporary and not saved into the config
+    /// file. Reloading the config file will overwrite it.
+    ///
+    /// On success, the compositor sends an [`Event::ConfigValueChanged`].
+    SetConfigValue {
+        /// Path of the option, for example `"layout.gaps"`.
+        path: String,
+        /// New value of the option.
+        value: ConfigValue,
+    },
+    /// Request a rendered thumbnail of a workspace.
+    ///
+    /// Works for any workspace, including ones that are not currently visible, so pagers can show
+    /// a preview of every workspace. Windows blocked out from screen capture are blocked out.
+    ///
+    /// The compositor should reply with [`Response::WorkspaceThumbnail`].
+    WorkspaceThumbnail {
+        /// Id of the workspace.
+        id: u64,
+        /// Maximum width and height of the thumbnail in physical pixels.
+        ///
+        /// The thumbnail keeps the aspect ratio of the workspace, and is never larger than the
+        /// workspace itself. Values above [`WorkspaceThumbnail::MAX_SIZE`] are capped.
+        max_size: u16,
+    },
+    /// Request the direct scanout state of every output, for debugging.
+    ///
+    /// The compositor should reply with [`Response::DebugScanout`].
+    DebugScanout,
+    /// Check the layout of every workspace for internal inconsistencies, for debugging.
+    ///
+    /// The compositor should reply with [`Response::DebugVerifyLayout`].
+    DebugVerifyLayout,
+    /// Request recent log messages, oldest first.
+    ///
+    /// The compositor keeps the last few hundred messages at the info level or above in memory,
+    /// so they can be read even when its output doesn't go to a terminal.
+    ///
+    /// The compositor should reply with [`Response::RecentLogs`].
+    RecentLogs {
+        /// Least severe level to include.
+        ///
+        /// If `None`, includes every kept message.
+        level: Option<LogLevel>,
+        /// Maximum number of messages, counting from the most recent one.
+        ///
+        /// If `None`, returns every kept message.
+        limit: Option<usize>,
+    },
+    /// Play back a macro of actions and key presses.
+    ///
+    /// Steps run in order. Key presses go through the binds of the current binding mode like real
+    /// key presses, and keys without a bind reach the focused window.
+    ///
+    /// Since macros synthesize input, they are refused unless the config sets
+    /// `debug { allow-ipc-macros; }`.
+    ///
+    /// The compositor replies with `Response::Handled` once the whole macro has played.
+    PlayMacro {
+        /// Steps of the macro.
+        steps: Vec<MacroStep>,
+    },
+    /// Request the tiling tree of the focused workspace together with the commands that launched
+    /// its windows.
+    ///
+    /// The compositor should reply with [`Response::WorkspaceExport`].
+    ExportWorkspace,
+    /// Rebuild an exported tiling tree.
+    ///
+    /// The tree goes to the workspace with the exported name if it exists, or to the focused
+    /// workspace. The compositor runs the command of every window, and places windows opening with
+    /// a matching app ID into their slots. Once all slots are filled, or after a timeout, the
+    /// windows are arranged into the exported tree.
+    ImportWorkspace {
+        /// Exported workspace, as returned by [`Request::ExportWorkspace`].
+        export: WorkspaceExport,
+    },
+    /// Request the saved answers to background portal prompts.
+    ///
+    /// The compositor should reply with [`Response::BackgroundPermissions`].
+    BackgroundPermissions,
+    /// Request the binds that can't trigger because of other binds.
+    ///
+    /// The compositor should reply with [`Response::BindConflicts`].
+    BindConflicts,
+    /// Request the processes spawned by the compositor that are still running.
+    ///
+    /// The compositor should reply with [`Response::SpawnedProcesses`].
+    SpawnedProcesses,
+    /// Request the shares of the children of a tiling container.
+    ///
+    /// The compositor should reply with [`Response::ContainerPercents`].
+    ContainerPercents {
+        /// Container to query.
+        container: ContainerAddress,
+    },
+    /// Set the shares of the children of a tiling container.
+    ///
+    /// There must be one share per child, in the order of [`Request::LayoutTree`]. The shares are
+    /// scaled to add up to 1, and each of them must come out at least 0.05.
+    SetContainerPercents {
+        /// Container to resize the children of.
+        container: ContainerAddress,
+        /// Shares of the children.
+        percents: Vec<f64>,
+    },
+    /// Request the power saving state.
+    ///
+    /// The compositor should reply with [`Response::PowerSavingState`].
+    PowerSavingState,
+}
+
+/// Tiling container to address with [`Request::ContainerPercents`] and
+/// [`Request::SetContainerPercents`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ContainerAddress {
+    /// Container of the focused window, or of the focused container.
+    Focused,
+    /// Container at this path on the focused workspace.
+    ///
+    /// The path lists child indices starting from the root container, so an empty path is the
+    /// root container itself.
+    Path(Vec<usize>),
+    /// Container of the window with this id.
+    Window(u64),
+}
+
+/// Step of a macro played with [`Request::PlayMacro`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum MacroStep {
+    /// Perform an action.
+    Action(Action),
+    /// Press and release a key combination.
+    Key {
+        /// Key combination in the bind syntax of the config, for example `"Mod+Shift+T"`.
+        key: String,
+    },
+    /// Wait before the next step.
+    Wait {
+        /// Time to wait in milliseconds.
+        ms: u64,
+    },
 }
 
 /// Reply from niri to client.
@@ -169,6 +357,42 @@ pub enum Response {
     LayoutTree(LayoutTree),
     /// Information about screencasts.
     Casts(Vec<Cast>),
+    /// Name of the newly created virtual output.
+    OutputCreated(String),
+    /// Current value of a config option.
+    ConfigValue(ConfigValue),
+    /// Rendered thumbnail of a workspace.
+    WorkspaceThumbnail(WorkspaceThumbnail),
+    /// Direct scanout state of outputs.
+    ///
+    /// Map from output name to its scanout state.
+    DebugScanout(HashMap<String, OutputScanout>),
+    /// Result of checking the layout for inconsistencies.
+    DebugVerifyLayout(LayoutVerification),
+    /// Recent log messages, oldest first.
+    RecentLogs(Vec<LogMessage>),
+    /// Exported tiling tree of a workspace.
+    WorkspaceExport(WorkspaceExport),
+    /// Saved background portal decisions, sorted by app ID.
+    BackgroundPermissions(Vec<BackgroundPermission>),
+    /// Conflicting binds of the loaded config, by mode.
+    BindConflicts(Vec<BindConflict>),
+    /// Running processes spawned by the compositor, oldest first.
+    SpawnedProcesses(Vec<SpawnedProcess>),
+    /// Shares of the children of a tiling container.
+    ContainerPercents(ContainerPercents),
+    /// Information about power saving.
+    PowerSavingState(PowerSaving),
+}
+
+/// Value of a runtime-tunable config option.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ConfigValue {
+    /// Boolean value.
+    Bool(bool),
+    /// Numeric value.
+    Number(f64),
 }
 
 /// Overview information.
@@ -179,6 +403,213 @@ pub struct Overview {
     pub is_open: bool,
 }
 
+/// Power saving information.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct PowerSaving {
+    /// Whether power saving is currently on.
+    pub is_active: bool,
+    /// Whether the system runs on battery.
+    pub on_battery: bool,
+}
+
+/// Rendered thumbnail of a workspace.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WorkspaceThumbnail {
+    /// Id of the workspace.
+    pub workspace_id: u64,
+    /// Width of the thumbnail in physical pixels.
+    pub width: u32,
+    /// Height of the thumbnail in physical pixels.
+    pub height: u32,
+    /// Thumbnail image in PNG format, encoded with standard base64.
+    ///
+    /// Use [`WorkspaceThumbnail::png()`] to decode it.
+    pub png_base64: String,
+}
+
+/// Direct scanout state of an output after its last frame.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct OutputScanout {
+    /// Whether the last frame showed a client buffer directly, without composition.
+    pub direct: bool,
+    /// Id of the fullscreen window whose buffer can be scanned out, if any.
+    pub window_id: Option<u64>,
+    /// Why no window can be scanned out, if none can.
+    pub blocker: Option<ScanoutBlocker>,
+}
+
+/// Reason why an output can't scan out a window buffer directly.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ScanoutBlocker {
+    /// The output isn't driven through DRM, for example a winit window or a headless output.
+    NoDrm,
+    /// Direct scanout is disabled in the debug config.
+    Disabled,
+    /// The active workspace on the output has no fullscreen window.
+    NoFullscreenWindow,
+    /// Something is drawn on top of the fullscreen window.
+    ///
+    /// For example, a layer-shell surface, the overview or a niri dialog.
+    Overlay,
+    /// An animation is in progress on the output.
+    Animation,
+    /// The window buffer doesn't cover the whole output opaquely.
+    NotOpaque,
+}
+
+/// Log message kept by the compositor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct LogMessage {
+    /// Wall clock time of the message, in milliseconds since the Unix epoch.
+    pub time_ms: u64,
+    /// Severity of the message.
+    pub level: LogLevel,
+    /// Module that logged the message, for example `niri::backend::tty`.
+    pub target: String,
+    /// Text of the message, followed by its fields as `key=value`.
+    pub message: String,
+}
+
+/// Saved background portal decisions for an app.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct BackgroundPermission {
+    /// App ID of the sandboxed app.
+    pub app_id: String,
+    /// Whether the app may keep running without windows.
+    ///
+    /// `None` if the app never asked.
+    pub background: Option<bool>,
+    /// Whether the app may start automatically on login.
+    ///
+    /// `None` if the app never asked.
+    pub autostart: Option<bool>,
+}
+
+/// Shares of the children of a tiling container.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ContainerPercents {
+    /// Id of the workspace with the container.
+    pub workspace_id: u64,
+    /// Path of the container from the root container, as child indices.
+    pub path: Vec<usize>,
+    /// Layout of the container.
+    pub layout: LayoutTreeLayout,
+    /// Shares of the children, adding up to 1.
+    pub percents: Vec<f64>,
+}
+
+/// Process spawned by the compositor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct SpawnedProcess {
+    /// Process group of the process.
+    ///
+    /// Every spawned command gets a process group of its own. The process that niri started may
+    /// have exited already, leaving the processes that it started in turn.
+    pub pgid: u32,
+    /// Command and its arguments.
+    pub command: Vec<String>,
+    /// Wall clock time of the spawn, in milliseconds since the Unix epoch.
+    pub started_ms: u64,
+}
+
+/// Bind that is shadowed by another bind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct BindConflict {
+    /// Binding mode of the bind, `default` for the main binds.
+    pub mode: String,
+    /// Key combination of the bind, as written in the config.
+    pub key: String,
+    /// What takes precedence over the bind.
+    pub kind: BindConflictKind,
+}
+
+/// What takes precedence over a conflicting bind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum BindConflictKind {
+    /// An earlier bind in the same mode triggers on the same keys, so this bind never triggers.
+    ///
+    /// This happens when one of the binds uses `Mod` and the other one spells out the modifier
+    /// that `Mod` stands for.
+    Duplicate {
+        /// Key combination of the earlier bind.
+        shadowed_by: String,
+    },
+    /// The keyboard resize mode takes over these keys while it's active.
+    ResizeMode,
+}
+
+/// Severity of a log message.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum LogLevel {
+    /// Something went wrong.
+    Error,
+    /// Something might have gone wrong.
+    Warn,
+    /// Notable event during normal operation.
+    Info,
+}
+
+/// Result of checking the layout for internal inconsistencies.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct LayoutVerification {
+    /// Number of workspaces checked.
+    pub workspaces: usize,
+    /// Number of windows checked.
+    pub windows: usize,
+    /// Problems found, empty if the layout is consistent.
+    pub violations: Vec<LayoutViolation>,
+}
+
+/// Inconsistency found in the layout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct LayoutViolation {
+    /// Which invariant is broken.
+    pub kind: LayoutViolationKind,
+    /// Id of the workspace with the problem, if it's limited to one workspace.
+    pub workspace_id: Option<u64>,
+    /// Path to the offending node from the root of the tiling tree, as child indices.
+    ///
+    /// Empty for the root, or when the problem isn't tied to a node.
+    pub path: Vec<usize>,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+/// Invariant of the layout.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum LayoutViolationKind {
+    /// The sizes of the children of a split container don't add up to the whole container.
+    PercentSum,
+    /// A window appears in the layout more than once.
+    DuplicateWindow,
+    /// The focused or selected node isn't a valid node of the tree, or a container remembers
+    /// focus on a node that isn't its child.
+    InvalidFocus,
+    /// A container has a single child that is a container with the same layout, so one of them
+    /// is redundant.
+    RedundantNesting,
+    /// A node is missing, unreachable from the root, or has a wrong parent link.
+    BrokenLink,
+    /// A node is reachable from the root more than once, so the tree has a cycle or shares a
+    /// subtree.
+    Cycle,
+}
+
 /// Tiling layout tree for the focused workspace.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -219,6 +650,35 @@ pub struct LayoutTreeNode {
     pub children: Vec<LayoutTreeNode>,
 }
 
+/// Tiling tree of a workspace with the commands to relaunch its windows.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WorkspaceExport {
+    /// Name of the workspace, if any.
+    pub name: Option<String>,
+    /// Root of the tiling tree.
+    pub root: Option<WorkspaceExportNode>,
+}
+
+/// Node in an exported tiling tree.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WorkspaceExportNode {
+    /// Layout of a container node. `None` for leaf nodes.
+    pub layout: Option<LayoutTreeLayout>,
+    /// Share of the parent container, from 0 to 1.
+    pub percent: f64,
+    /// App ID of the window for leaf nodes.
+    pub app_id: Option<String>,
+    /// Command that launched the window for leaf nodes.
+    ///
+    /// `None` if the command is unknown, in which case a window with a matching app ID has to be
+    /// opened by hand to fill the slot on import.
+    pub command: Option<Vec<String>>,
+    /// Children nodes for container nodes.
+    pub children: Vec<WorkspaceExportNode>,
+}
+
 /// Color picked from the screen.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -242,22 +702,100 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(short, long))]
         skip_confirmation: bool,
     },
+    /// Show the power menu to exit niri, lock, suspend or reboot.
+    PowerMenu {},
     /// Power off all monitors via DPMS.
     PowerOffMonitors {},
     /// Power on all monitors via DPMS.
     PowerOnMonitors {},
+    /// Power off a single monitor via DPMS.
+    ///
+    /// The monitor powers back on with any input.
+    PowerOffMonitor {
+        /// Name of the output to power off.
+        ///
+        /// If `None`, uses the focused output.
+        #[cfg_attr(feature = "clap", arg())]
+        output: Option<String>,
+    },
+    /// Power on a single monitor that was powered off via DPMS.
+    PowerOnMonitor {
+        /// Name of the output to power on.
+        ///
+        /// If `None`, uses the focused output.
+        #[cfg_attr(feature = "clap", arg())]
+        output: Option<String>,
+    },
+    /// Switch to an output profile from the config.
+    ///
+    /// The profile stays active until the set of connected monitors changes.
+    SetOutputProfile {
+        /// Name of the output profile.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Increase the backlight brightness.
+    BrightnessUp {
+        /// Step in percent of the maximum brightness.
+        ///
+        /// If `None`, uses 5%.
+        #[cfg_attr(feature = "clap", arg())]
+        step: Option<u8>,
+    },
+    /// Decrease the backlight brightness.
+    ///
+    /// Stops at 1% to keep the screen readable.
+    BrightnessDown {
+        /// Step in percent of the maximum brightness.
+        ///
+        /// If `None`, uses 5%.
+        #[cfg_attr(feature = "clap", arg())]
+        step: Option<u8>,
+    },
+    /// Set the backlight brightness.
+    BrightnessSet {
+        /// Brightness in percent of the maximum brightness.
+        #[cfg_attr(feature = "clap", arg())]
+        percent: u8,
+    },
+    /// Toggle the power saving mode.
+    TogglePowerSaving {},
+    /// Toggle the night light.
+    ///
+    /// Stays in effect until the night light schedule from the config catches up.
+    ToggleNightLight {},
     /// Spawn a command.
     Spawn {
         /// Command to spawn.
         #[cfg_attr(feature = "clap", arg(last = true, required = true))]
         command: Vec<String>,
     },
+    /// Spawn a command in a transient systemd scope.
+    ///
+    /// Unlike `spawn`, this creates the scope even when niri isn't running as a systemd service.
+    SpawnSd {
+        /// Command to spawn.
+        #[cfg_attr(feature = "clap", arg(last = true, required = true))]
+        command: Vec<String>,
+    },
     /// Spawn a command through the shell.
     SpawnSh {
         /// Command to run.
         #[cfg_attr(feature = "clap", arg(last = true, required = true))]
         command: String,
     },
+    /// Spawn a command on a named workspace.
+    ///
+    /// Switches to the workspace, creating it if needed, and opens the first window of the
+    /// spawned command there.
+    SpawnOnWorkspace {
+        /// Name of the workspace.
+        #[cfg_attr(feature = "clap", arg())]
+        workspace: String,
+        /// Command to spawn.
+        #[cfg_attr(feature = "clap", arg(last = true, required = true))]
+        command: Vec<String>,
+    },
     /// Do a screen transition.
     DoScreenTransition {
         /// Delay in milliseconds for the screen to freeze before starting the transition.
@@ -360,6 +898,18 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Toggle fullscreen spanning multiple outputs on a window.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Toggle fullscreen spanning multiple outputs on the focused window")
+    )]
+    ToggleWindowFullscreenSpan {
+        /// Id of the window to toggle spanning fullscreen of.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
     /// Move a window to scratchpad.
     #[cfg_attr(
         feature = "clap",
@@ -528,6 +1078,32 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg())]
         display: ColumnDisplay,
     },
+    /// Set the tab bar position of the focused container.
+    SetTabBarPosition {
+        /// Edge of the container to put the tab bar on.
+        #[cfg_attr(feature = "clap", arg())]
+        position: TabBarPosition,
+    },
+    /// Focus the parent container of the focused window or container.
+    FocusParent {},
+    /// Focus the focused child of the focused container.
+    FocusChild {},
+    /// Split the focused window so that the next window opens to its right.
+    SplitHorizontal {},
+    /// Split the focused window so that the next window opens below it.
+    SplitVertical {},
+    /// Lay out the focused container horizontally.
+    SetLayoutSplitH {},
+    /// Lay out the focused container vertically.
+    SetLayoutSplitV {},
+    /// Toggle the focused container between horizontal and vertical layout.
+    ToggleSplitLayout {},
+    /// Show the focused container as a stack with one title bar per window.
+    SetLayoutStacked {},
+    /// Show the focused container as tabs.
+    SetLayoutTabbed {},
+    /// Undo the last move, split or layout change on the focused workspace.
+    UndoLayoutChange {},
     /// Center the focused column on the screen.
     CenterColumn {},
     /// Center a window on the screen.
@@ -551,7 +1127,7 @@ pub enum Action {
     /// Focus a workspace by reference (index or name).
     FocusWorkspace {
         /// Reference (index or name) of the workspace to focus.
-        #[cfg_attr(feature = "clap", arg())]
+        #[cfg_attr(feature = "clap", arg(allow_hyphen_values = true))]
         reference: WorkspaceReferenceArg,
     },
     /// Focus the previous workspace.
@@ -587,7 +1163,7 @@ pub enum Action {
         window_id: Option<u64>,
 
         /// Reference (index or name) of the workspace to move the window to.
-        #[cfg_attr(feature = "clap", arg())]
+        #[cfg_attr(feature = "clap", arg(allow_hyphen_values = true))]
         reference: WorkspaceReferenceArg,
 
         /// Whether the focus should follow the moved window.
@@ -619,7 +1195,7 @@ pub enum Action {
     /// Move the focused column to a workspace by reference (index or name).
     MoveColumnToWorkspace {
         /// Reference (index or name) of the workspace to move the column to.
-        #[cfg_attr(feature = "clap", arg())]
+        #[cfg_attr(feature = "clap", arg(allow_hyphen_values = true))]
         reference: WorkspaceReferenceArg,
 
         /// Whether the focus should follow the target workspace.
@@ -821,6 +1397,58 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Grow a window by moving one of its edges outwards.
+    ///
+    /// Takes space from the neighbor on that side in the closest split that has one.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Grow a window by moving one of its edges outwards")
+    )]
+    ResizeGrow {
+        /// Id of the window to resize.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+
+        /// Edge to move: left, right, up or down.
+        #[cfg_attr(feature = "clap", arg())]
+        direction: ResizeDirection,
+
+        /// How far to move the edge, in logical pixels (e.g. `10` or `10px`) or in percentage
+        /// points of the parent container (e.g. `10%` or `10ppt`).
+        #[cfg_attr(feature = "clap", arg())]
+        amount: ResizeAmount,
+    },
+    /// Shrink a window by moving one of its edges inwards.
+    ///
+    /// Gives space to the neighbor on that side in the closest split that has one.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Shrink a window by moving one of its edges inwards")
+    )]
+    ResizeShrink {
+        /// Id of the window to resize.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+
+        /// Edge to move: left, right, up or down.
+        #[cfg_attr(feature = "clap", arg())]
+        direction: ResizeDirection,
+
+        /// How far to move the edge, in logical pixels (e.g. `10` or `10px`) or in percentage
+        /// points of the parent container (e.g. `10%` or `10ppt`).
+        #[cfg_attr(feature = "clap", arg())]
+        amount: ResizeAmount,
+    },
+    /// Toggle the keyboard resize mode.
+    ///
+    /// While the mode is active, arrow keys or HJKL resize the focused window within its parent
+    /// container, and Escape or Enter leave the mode.
+    #[cfg_attr(feature = "clap", clap(about = "Toggle the keyboard resize mode"))]
+    ToggleResizeMode {},
     /// Toggle the maximized state of the focused column.
     MaximizeColumn {},
     /// Toggle the maximized-to-edges state of the focused window.
@@ -859,6 +1487,23 @@ pub enum Action {
     MoveWorkspaceToMonitorPrevious {},
     /// Move the focused workspace to the next monitor.
     MoveWorkspaceToMonitorNext {},
+    /// Move a workspace to the neighboring monitor in a direction.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Move the focused workspace to the neighboring monitor in a direction")
+    )]
+    MoveWorkspaceToMonitorInDirection {
+        /// Direction of the target monitor: left, right, up or down.
+        #[cfg_attr(feature = "clap", arg())]
+        direction: OutputDirection,
+
+        /// Reference (index or name) of the workspace to move.
+        ///
+        /// If `None`, uses the focused workspace. The direction is relative to the monitor that
+        /// the workspace is on.
+        #[cfg_attr(feature = "clap", arg(long))]
+        reference: Option<WorkspaceReferenceArg>,
+    },
     /// Move a workspace to a specific monitor.
     #[cfg_attr(
         feature = "clap",
@@ -881,6 +1526,8 @@ pub enum Action {
     DebugToggleOpaqueRegions {},
     /// Toggle visualization of output damage.
     DebugToggleDamage {},
+    /// Toggle an overlay with the commit rate and last commit age of every visible window.
+    DebugToggleFrameStats {},
     /// Move the focused window between the floating and the tiling layout.
     ToggleWindowFloating {
         /// Id of the window to move.
@@ -897,6 +1544,48 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Toggle keeping a floating window above other floating and fullscreen windows.
+    ToggleWindowAlwaysOnTop {
+        /// Id of the window to toggle.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Raise a floating window above the other floating windows without focusing it.
+    RaiseWindow {
+        /// Id of the window to raise.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Lower a floating window below the other floating windows without changing the focus.
+    LowerWindow {
+        /// Id of the window to lower.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Toggle keeping a window in the corner of whichever output is focused.
+    ToggleWindowFollowFocus {
+        /// Id of the window to toggle.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Toggle picture-in-picture mode for a window.
+    ///
+    /// Picture-in-picture windows are floating, always on top and sticky, placed in a corner.
+    ToggleWindowPictureInPicture {
+        /// Id of the window to toggle.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
     /// Move the focused window to the floating layout.
     MoveWindowToFloating {
         /// Id of the window to move.
@@ -919,6 +1608,12 @@ pub enum Action {
     FocusTiling {},
     /// Toggles the focus between the floating and the tiling layout.
     SwitchFocusBetweenFloatingAndTiling {},
+    /// Rearrange all floating windows on the active workspace.
+    ArrangeFloating {
+        /// How to arrange the windows.
+        #[cfg_attr(feature = "clap", arg())]
+        arrangement: FloatingArrangement,
+    },
     /// Move a floating window on screen.
     #[cfg_attr(feature = "clap", clap(about = "Move the floating window on screen"))]
     MoveFloatingWindow {
@@ -954,6 +1649,21 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Toggle blocking out a window from screencasts.
+    ///
+    /// This flips whatever the `block-out-from` window rules say for this window, until the window
+    /// closes.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Toggle blocking out the focused window from screencasts")
+    )]
+    ToggleWindowScreencastBlockOut {
+        /// Id of the window.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
     /// Set the dynamic cast target to a window.
     #[cfg_attr(
         feature = "clap",
@@ -1019,6 +1729,13 @@ pub enum Action {
     /// Can be useful for scripts changing the config file, to avoid waiting the small duration for
     /// niri's config file watcher to notice the changes.
     LoadConfigFile {},
+    /// Set the accent color until the next config reload.
+    #[cfg_attr(feature = "clap", clap(about = "Set the accent color"))]
+    SetAccentColor {
+        /// New accent color, in any CSS color format.
+        #[cfg_attr(feature = "clap", arg())]
+        color: String,
+    },
 }
 
 /// Change in window or column size.
@@ -1049,7 +1766,48 @@ pub enum PositionChange {
     AdjustProportion(f64),
 }
 
-/// Workspace reference (id, index or name) to operate on.
+/// Window edge to move with a directional resize.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ResizeDirection {
+    /// The left edge.
+    Left,
+    /// The right edge.
+    Right,
+    /// The top edge.
+    Up,
+    /// The bottom edge.
+    Down,
+}
+
+/// Direction of a neighboring output in the physical output arrangement.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum OutputDirection {
+    /// The output to the left.
+    Left,
+    /// The output to the right.
+    Right,
+    /// The output above.
+    Up,
+    /// The output below.
+    Down,
+}
+
+/// How far to move a window edge with a directional resize.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum ResizeAmount {
+    /// Logical pixels.
+    Fixed(i32),
+    /// Percentage points of the parent container.
+    Proportion(f64),
+}
+
+/// Workspace reference (id, index, name or relative position) to operate on.
+///
+/// On the command line, relative references are written as `+N`, `-N`, `next-on-output`,
+/// `prev-on-output`, `next-non-empty` and `prev-non-empty`.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum WorkspaceReferenceArg {
@@ -1059,6 +1817,14 @@ pub enum WorkspaceReferenceArg {
     Index(u8),
     /// Name of the workspace.
     Name(String),
+    /// Offset from the active workspace on the focused monitor, wrapping around.
+    ///
+    /// For example, `1` is the workspace below the active one, and `-2` is two workspaces above.
+    Relative(i32),
+    /// Next workspace with windows on the focused monitor, wrapping around.
+    NextNonEmpty,
+    /// Previous workspace with windows on the focused monitor, wrapping around.
+    PrevNonEmpty,
 }
 
 /// Layout to switch to.
@@ -1083,6 +1849,30 @@ pub enum ColumnDisplay {
     Tabbed,
 }
 
+/// How to rearrange floating windows.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum FloatingArrangement {
+    /// Stack windows down and to the right from the top-left corner, keeping their sizes.
+    Cascade,
+    /// Resize windows into a grid filling the working area.
+    Grid,
+}
+
+/// Edge of a container that its tab bar sits on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum TabBarPosition {
+    /// Above the windows, with the tabs in a row.
+    Top,
+    /// Below the windows, with the tabs in a row.
+    Bottom,
+    /// Left of the windows, with the tabs one under the other.
+    Left,
+    /// Right of the windows, with the tabs one under the other.
+    Right,
+}
+
 /// Marking mode for the focused window.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
@@ -1430,6 +2220,15 @@ pub struct Window {
     pub is_floating: bool,
     /// Whether this window requests your attention.
     pub is_urgent: bool,
+    /// Whether this window has drawn something new since it was last focused.
+    ///
+    /// Windows can opt out of this with the `track-activity false` window rule.
+    pub has_activity: bool,
+    /// Whether this window is blocked out from screencasts.
+    ///
+    /// This comes either from a `block-out-from` window rule, or from the
+    /// [`Action::ToggleWindowScreencastBlockOut`] action.
+    pub is_blocked_out_from_screencast: bool,
     /// Position- and size-related properties of the window.
     pub layout: WindowLayout,
     /// Timestamp when the window was most recently focused.
@@ -1498,6 +2297,18 @@ pub struct WindowLayout {
     pub window_offset_in_tile: (f64, f64),
     /// Whether this window is sticky (floating across workspaces on an output).
     pub is_sticky: bool,
+    /// Whether this window follows the focus across outputs.
+    ///
+    /// See [`Action::ToggleWindowFollowFocus`]. Such a window isn't sticky.
+    pub is_following_focus: bool,
+    /// Whether this window is kept above other floating and fullscreen windows.
+    pub is_always_on_top: bool,
+    /// Position of a floating window in the stacking order of its workspace.
+    ///
+    /// The index is 1-based, the topmost floating window is at position 1. Sticky windows are
+    /// stacked separately, among the other sticky windows of their output. `None` for tiled
+    /// windows.
+    pub pos_in_floating_stack: Option<usize>,
 }
 
 /// Output configuration change result.
@@ -1813,6 +2624,105 @@ pub enum Event {
         /// Stream ID of the stopped screencast.
         stream_id: u64,
     },
+    /// A runtime-tunable config option changed.
+    ///
+    /// This is sent both for changes made with [`Request::SetConfigValue`] and for changes coming
+    /// from a config file reload. The event stream does not replay current values on connection;
+    /// use [`Request::GetConfigValue`] for that.
+    ConfigValueChanged {
+        /// Path of the option, for example `"layout.gaps"`.
+        path: String,
+        /// New value of the option.
+        value: ConfigValue,
+    },
+    /// A critical client crashed and niri spawned it again.
+    ///
+    /// Critical clients are `spawn-at-startup` commands marked with `critical=true`.
+    CriticalClientRestarted {
+        /// Command of the client.
+        command: Vec<String>,
+        /// Number of restarts in a row without the client staying up for a while.
+        restarts: u32,
+    },
+    /// The laptop lid or the tablet mode switch changed.
+    ///
+    /// You will always receive this event when connecting to the event stream, indicating the
+    /// current state of the switches.
+    SwitchesChanged {
+        /// Whether the laptop lid is closed.
+        lid_closed: bool,
+        /// Whether a convertible laptop is in tablet mode.
+        tablet_mode: bool,
+    },
+}
+
+impl WorkspaceThumbnail {
+    /// Largest thumbnail width and height that the compositor renders.
+    pub const MAX_SIZE: u16 = 1024;
+
+    /// Creates a thumbnail from PNG image data.
+    pub fn from_png(workspace_id: u64, width: u32, height: u32, png: &[u8]) -> Self {
+        Self {
+            workspace_id,
+            width,
+            height,
+            png_base64: base64_encode(png),
+        }
+    }
+
+    /// Decodes the PNG image data.
+    ///
+    /// Returns `None` if [`Self::png_base64`] is not valid base64.
+    pub fn png(&self) -> Option<Vec<u8>> {
+        base64_decode(&self.png_base64)
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b1 = chunk.get(1).copied().unwrap_or(0);
+        let b2 = chunk.get(2).copied().unwrap_or(0);
+        let n = u32::from_be_bytes([0, chunk[0], b1, b2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let idx = (n >> (18 - 6 * i)) & 0x3f;
+                out.push(char::from(BASE64_ALPHABET[idx as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(data: &str) -> Option<Vec<u8>> {
+    let data = data.as_bytes();
+    if data.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    for chunk in data.chunks(4) {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)?;
+            n = (n << 6) | value as u32;
+        }
+        n <<= 6 * padding;
+
+        let bytes = n.to_be_bytes();
+        out.extend_from_slice(&bytes[1..4 - padding]);
+    }
+    Some(out)
 }
 
 impl From<Duration> for Timestamp {
@@ -1830,11 +2740,98 @@ impl From<Timestamp> for Duration {
     }
 }
 
+impl Action {
+    /// Returns the name of the action as written in the config, like `focus-workspace`.
+    pub fn name(&self) -> String {
+        // Actions serialize as an object with the variant name as the only key.
+        let value = serde_json::to_value(self).unwrap_or_default();
+        let variant = match &value {
+            serde_json::Value::Object(map) => map.keys().next().map_or("", String::as_str),
+            serde_json::Value::String(variant) => variant,
+            _ => "",
+        };
+        kebab_case(variant)
+    }
+
+    /// Returns the names of all actions as written in the config.
+    pub fn names() -> Vec<String> {
+        // The derived Deserialize impl hands the list of variants to the deserializer.
+        struct VariantNames<'a>(&'a mut &'static [&'static str]);
+
+        impl<'de> serde::Deserializer<'de> for VariantNames<'_> {
+            type Error = serde::de::value::Error;
+
+            fn deserialize_any<V: serde::de::Visitor<'de>>(
+                self,
+                _visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                Err(serde::de::Error::custom("expected an enum"))
+            }
+
+            fn deserialize_enum<V: serde::de::Visitor<'de>>(
+                self,
+                _name: &'static str,
+                variants: &'static [&'static str],
+                _visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                *self.0 = variants;
+                Err(serde::de::Error::custom("only collecting variant names"))
+            }
+
+            serde::forward_to_deserialize_any! {
+                bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+                byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map struct
+                identifier ignored_any
+            }
+        }
+
+        let mut variants: &'static [&'static str] = &[];
+        let _ = Action::deserialize(VariantNames(&mut variants));
+        variants.iter().map(|variant| kebab_case(variant)).collect()
+    }
+}
+
+/// Converts a `CamelCase` variant name to `kebab-case`.
+fn kebab_case(variant: &str) -> String {
+    let mut name = String::new();
+    for ch in variant.chars() {
+        if ch.is_ascii_uppercase() {
+            if !name.is_empty() {
+                name.push('-');
+            }
+            name.push(ch.to_ascii_lowercase());
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+impl WorkspaceReferenceArg {
+    /// Parses a relative reference like `+1` or `next-non-empty`.
+    ///
+    /// Returns `None` if the string is not a relative reference.
+    pub fn parse_relative(s: &str) -> Option<Self> {
+        let reference = match s {
+            "next-on-output" => Self::Relative(1),
+            "prev-on-output" => Self::Relative(-1),
+            "next-non-empty" => Self::NextNonEmpty,
+            "prev-non-empty" => Self::PrevNonEmpty,
+            _ if s.starts_with(['+', '-']) => Self::Relative(s.parse().ok()?),
+            _ => return None,
+        };
+
+        Some(reference)
+    }
+}
+
 impl FromStr for WorkspaceReferenceArg {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let reference = if let Ok(index) = s.parse::<i32>() {
+        let reference = if let Some(relative) = Self::parse_relative(s) {
+            relative
+        } else if let Ok(index) = s.parse::<i32>() {
             if let Ok(idx) = u8::try_from(index) {
                 Self::Index(idx)
             } else {
@@ -1848,6 +2845,21 @@ impl FromStr for WorkspaceReferenceArg {
     }
 }
 
+impl FromStr for ConfigValue {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "true" => Ok(Self::Bool(true)),
+            "false" => Ok(Self::Bool(false)),
+            _ => match s.parse::<f64>() {
+                Ok(value) if value.is_finite() => Ok(Self::Number(value)),
+                _ => Err(r#"value must be "true", "false" or a number"#),
+            },
+        }
+    }
+}
+
 impl FromStr for SizeChange {
     type Err = &'static str;
 
@@ -1888,6 +2900,55 @@ impl FromStr for SizeChange {
     }
 }
 
+impl FromStr for ResizeDirection {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            _ => Err(r#"invalid direction, can be "left", "right", "up" or "down""#),
+        }
+    }
+}
+
+impl FromStr for OutputDirection {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            _ => Err(r#"invalid direction, can be "left", "right", "up" or "down""#),
+        }
+    }
+}
+
+impl FromStr for ResizeAmount {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(value) = s.strip_suffix('%').or_else(|| s.strip_suffix("ppt")) {
+            let value: f64 = value.parse().map_err(|_| "error parsing value")?;
+            if !value.is_finite() || value < 0. {
+                return Err("value must not be negative");
+            }
+            Ok(Self::Proportion(value))
+        } else {
+            let value = s.strip_suffix("px").unwrap_or(s);
+            let value: i32 = value.parse().map_err(|_| "error parsing value")?;
+            if value < 0 {
+                return Err("value must not be negative");
+            }
+            Ok(Self::Fixed(value))
+        }
+    }
+}
+
 impl FromStr for PositionChange {
     type Err = &'static str;
 
@@ -1955,6 +3016,32 @@ impl FromStr for ColumnDisplay {
     }
 }
 
+impl FromStr for FloatingArrangement {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cascade" => Ok(Self::Cascade),
+            "grid" => Ok(Self::Grid),
+            _ => Err(r#"invalid floating arrangement, can be "cascade" or "grid""#),
+        }
+    }
+}
+
+impl FromStr for TabBarPosition {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top" => Ok(Self::Top),
+            "bottom" => Ok(Self::Bottom),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            _ => Err(r#"invalid tab bar position, can be "top", "bottom", "left" or "right""#),
+        }
+    }
+}
+
 impl FromStr for Transform {
     type Err = &'static str;
 
@@ -2139,6 +3226,87 @@ impl OutputAction {
 mod tests {
     use super::*;
 
+    #[test]
+    fn action_names() {
+        assert_eq!(Action::FocusWorkspaceDown {}.name(), "focus-workspace-down");
+        let quit = Action::Quit {
+            skip_confirmation: true,
+        };
+        assert_eq!(quit.name(), "quit");
+
+        let names = Action::names();
+        assert!(names.iter().any(|name| name == "focus-workspace-down"));
+        assert!(names.iter().any(|name| name == "spawn"));
+        assert!(!names.iter().any(|name| name == "FocusWorkspaceDown"));
+    }
+
+    #[test]
+    fn parse_config_value() {
+        assert_eq!(
+            "true".parse::<ConfigValue>().unwrap(),
+            ConfigValue::Bool(true)
+        );
+        assert_eq!(
+            "false".parse::<ConfigValue>().unwrap(),
+            ConfigValue::Bool(false)
+        );
+        assert_eq!(
+            "16".parse::<ConfigValue>().unwrap(),
+            ConfigValue::Number(16.)
+        );
+        assert_eq!(
+            "-0.5".parse::<ConfigValue>().unwrap(),
+            ConfigValue::Number(-0.5)
+        );
+
+        assert!("yes".parse::<ConfigValue>().is_err());
+        assert!("inf".parse::<ConfigValue>().is_err());
+        assert!("".parse::<ConfigValue>().is_err());
+    }
+
+    #[test]
+    fn parse_workspace_reference() {
+        let parse = |s: &str| s.parse::<WorkspaceReferenceArg>().unwrap();
+        assert_eq!(parse("3"), WorkspaceReferenceArg::Index(3));
+        assert_eq!(parse("+1"), WorkspaceReferenceArg::Relative(1));
+        assert_eq!(parse("-2"), WorkspaceReferenceArg::Relative(-2));
+        assert_eq!(parse("next-on-output"), WorkspaceReferenceArg::Relative(1));
+        assert_eq!(parse("prev-on-output"), WorkspaceReferenceArg::Relative(-1));
+        assert_eq!(parse("next-non-empty"), WorkspaceReferenceArg::NextNonEmpty);
+        assert_eq!(parse("prev-non-empty"), WorkspaceReferenceArg::PrevNonEmpty);
+        assert_eq!(
+            parse("-browser"),
+            WorkspaceReferenceArg::Name(String::from("-browser"))
+        );
+
+        assert!("256".parse::<WorkspaceReferenceArg>().is_err());
+    }
+
+    #[test]
+    fn workspace_thumbnail_png_round_trip() {
+        for data in [
+            &b""[..],
+            b"f",
+            b"fo",
+            b"foo",
+            b"foob",
+            b"\x89PNG\r\n\x1a\n\xff",
+        ] {
+            let thumbnail = WorkspaceThumbnail::from_png(1, 2, 3, data);
+            assert_eq!(thumbnail.png().unwrap(), data);
+        }
+
+        let thumbnail = WorkspaceThumbnail::from_png(1, 2, 3, b"foobar");
+        assert_eq!(thumbnail.png_base64, "Zm9vYmFy");
+        let thumbnail = WorkspaceThumbnail::from_png(1, 2, 3, b"fooba");
+        assert_eq!(thumbnail.png_base64, "Zm9vYmE=");
+
+        assert_eq!(base64_decode("Zm9vYg=="), Some(b"foob".to_vec()));
+        assert_eq!(base64_decode("Zm9"), None);
+        assert_eq!(base64_decode("Zm9v!A=="), None);
+        assert_eq!(base64_decode("Z==="), None);
+    }
+
     #[test]
     fn parse_size_change() {
         assert_eq!(
@@ -2200,4 +3368,28 @@ mod tests {
         assert!("-".parse::<PositionChange>().is_err());
         assert!("10% ".parse::<PositionChange>().is_err());
     }
+
+    #[test]
+    fn parse_resize_amount() {
+        assert_eq!(
+            "10".parse::<ResizeAmount>().unwrap(),
+            ResizeAmount::Fixed(10),
+        );
+        assert_eq!(
+            "10px".parse::<ResizeAmount>().unwrap(),
+            ResizeAmount::Fixed(10),
+        );
+        assert_eq!(
+            "5%".parse::<ResizeAmount>().unwrap(),
+            ResizeAmount::Proportion(5.),
+        );
+        assert_eq!(
+            "5ppt".parse::<ResizeAmount>().unwrap(),
+            ResizeAmount::Proportion(5.),
+        );
+
+        assert!("-10".parse::<ResizeAmount>().is_err());
+        assert!("10 px".parse::<ResizeAmount>().is_err());
+        assert!("ppt".parse::<ResizeAmount>().is_err());
+    }
 }
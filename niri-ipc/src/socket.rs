@@ -11,6 +11,12 @@ use crate::{Event, Reply, Request};
 /// Name of the environment variable containing the niri IPC socket path.
 pub const SOCKET_PATH_ENV: &str = "NIRI_SOCKET";
 
+/// Name of the environment variable containing the read-only niri IPC socket path.
+///
+/// The read-only socket accepts requests that don't change anything, like the event stream, so
+/// it can be given to status bars and sandboxed clients.
+pub const READ_ONLY_SOCKET_PATH_ENV: &str = "NIRI_SOCKET_READONLY";
+
 /// Helper for blocking communication over the niri socket.
 ///
 /// This struct is used to communicate with the niri IPC server. It handles the socket connection
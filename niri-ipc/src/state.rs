@@ -49,6 +49,9 @@ pub struct EventStreamState {
 
     /// State of screencasts.
     pub casts: CastsState,
+
+    /// State of the lid and tablet mode switches.
+    pub switches: SwitchesState,
 }
 
 /// The workspaces state communicated over the event stream.
@@ -86,6 +89,15 @@ pub struct ConfigState {
     pub failed: bool,
 }
 
+/// The switches state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct SwitchesState {
+    /// Whether the laptop lid is closed.
+    pub lid_closed: bool,
+    /// Whether a convertible laptop is in tablet mode.
+    pub tablet_mode: bool,
+}
+
 /// The casts state communicated over the event stream.
 #[derive(Debug, Default)]
 pub struct CastsState {
@@ -102,6 +114,7 @@ impl EventStreamStatePart for EventStreamState {
         events.extend(self.overview.replicate());
         events.extend(self.config.replicate());
         events.extend(self.casts.replicate());
+        events.extend(self.switches.replicate());
         events
     }
 
@@ -112,6 +125,7 @@ impl EventStreamStatePart for EventStreamState {
         let event = self.overview.apply(event)?;
         let event = self.config.apply(event)?;
         let event = self.casts.apply(event)?;
+        let event = self.switches.apply(event)?;
         Some(event)
     }
 }
@@ -321,3 +335,26 @@ impl EventStreamStatePart for CastsState {
         None
     }
 }
+
+impl EventStreamStatePart for SwitchesState {
+    fn replicate(&self) -> Vec<Event> {
+        vec![Event::SwitchesChanged {
+            lid_closed: self.lid_closed,
+            tablet_mode: self.tablet_mode,
+        }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::SwitchesChanged {
+                lid_closed,
+                tablet_mode,
+            } => {
+                self.lid_closed = lid_closed;
+                self.tablet_mode = tablet_mode;
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
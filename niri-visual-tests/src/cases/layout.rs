@@ -2,13 +2,15 @@ use std::collections::HashMap;
 use std::time::Duration;
 
 use niri::animation::Clock;
-use niri::layout::{ActivateWindow, AddWindowTarget, LayoutElement as _, Options, SizingMode};
+use niri::layout::{
+    ActivateWindow, AddWindowTarget, ContainerLayout, LayoutElement as _, Options, SizingMode,
+};
 use niri::render_helpers::RenderTarget;
-use niri_config::{Color, OutputName, PresetSize};
+use niri_config::{Color, OutputName, PresetSize, TabIndicatorPosition};
 use smithay::backend::renderer::element::RenderElement;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::layer_map_for_output;
-use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
+use smithay::output::{Mode, Output, PhysicalProperties, Scale, Subpixel};
 use smithay::utils::{Physical, Size};
 
 use super::{Args, TestCase};
@@ -16,6 +18,14 @@ use crate::test_window::TestWindow;
 
 type DynStepFn = Box<dyn FnOnce(&mut Layout)>;
 
+/// Window colors for the container tree cases, so that neighbors are easy to tell apart.
+const TREE_COLORS: [[f32; 4]; 4] = [
+    [0.15, 0.64, 0.41, 1.],
+    [0.21, 0.52, 0.89, 1.],
+    [0.88, 0.11, 0.14, 1.],
+    [0.96, 0.76, 0.07, 1.],
+];
+
 pub struct Layout {
     output: Output,
     windows: Vec<TestWindow>,
@@ -27,6 +37,10 @@ pub struct Layout {
 
 impl Layout {
     pub fn new(args: Args) -> Self {
+        Self::with_scale(args, 1.)
+    }
+
+    fn with_scale(args: Args, scale: f64) -> Self {
         let Args { size, clock } = args;
 
         let output = Output::new(
@@ -43,7 +57,7 @@ impl Layout {
             size: size.to_physical(1),
             refresh: 60000,
         });
-        output.change_current_state(mode, None, None, None);
+        output.change_current_state(mode, None, Some(Scale::Fractional(scale)), None);
         output.user_data().insert_if_missing(|| OutputName {
             connector: String::new(),
             make: None,
@@ -164,6 +178,109 @@ impl Layout {
         rv
     }
 
+    pub fn nested_splits(args: Args) -> Self {
+        let mut rv = Self::new(args);
+
+        rv.add_tree_window(0);
+        rv.add_tree_window(1);
+        rv.layout.split_vertical();
+        rv.add_tree_window(2);
+        rv.layout.split_horizontal();
+        rv.add_tree_window(3);
+        rv.communicate();
+
+        rv
+    }
+
+    pub fn tabbed(args: Args) -> Self {
+        Self::tabbed_with_scale(args, 1.)
+    }
+
+    pub fn tabbed_scale_1_5(args: Args) -> Self {
+        Self::tabbed_with_scale(args, 1.5)
+    }
+
+    pub fn tabbed_scale_2(args: Args) -> Self {
+        Self::tabbed_with_scale(args, 2.)
+    }
+
+    fn tabbed_with_scale(args: Args, scale: f64) -> Self {
+        let mut rv = Self::with_scale(args, scale);
+
+        rv.add_tree_window(0);
+        rv.layout.set_layout_mode(ContainerLayout::Tabbed);
+        rv.add_tree_window(1);
+        rv.add_tree_window(2);
+        rv.communicate();
+
+        rv
+    }
+
+    pub fn tabbed_side_bar(args: Args) -> Self {
+        let mut rv = Self::new(args);
+
+        rv.add_tree_window(0);
+        rv.layout.set_layout_mode(ContainerLayout::Tabbed);
+        rv.layout.set_tab_bar_position(TabIndicatorPosition::Left);
+        rv.add_tree_window(1);
+        rv.add_tree_window(2);
+        rv.communicate();
+
+        rv
+    }
+
+    pub fn stacked(args: Args) -> Self {
+        Self::stacked_with_scale(args, 1.)
+    }
+
+    pub fn stacked_scale_1_5(args: Args) -> Self {
+        Self::stacked_with_scale(args, 1.5)
+    }
+
+    pub fn stacked_scale_2(args: Args) -> Self {
+        Self::stacked_with_scale(args, 2.)
+    }
+
+    fn stacked_with_scale(args: Args, scale: f64) -> Self {
+        let mut rv = Self::with_scale(args, scale);
+
+        rv.add_tree_window(0);
+        rv.layout.set_layout_mode(ContainerLayout::Stacked);
+        rv.add_tree_window(1);
+        rv.add_tree_window(2);
+        rv.communicate();
+
+        rv
+    }
+
+    pub fn tabbed_in_split(args: Args) -> Self {
+        let mut rv = Self::new(args);
+
+        rv.add_tree_window(0);
+        rv.add_tree_window(1);
+        rv.layout.split_vertical();
+        rv.layout.set_layout_mode(ContainerLayout::Tabbed);
+        rv.add_tree_window(2);
+        rv.communicate();
+
+        rv
+    }
+
+    fn add_tree_window(&mut self, id: usize) {
+        let window = TestWindow::freeform(id);
+        window.set_color(TREE_COLORS[id % TREE_COLORS.len()]);
+        self.add_window(window, None);
+    }
+
+    /// Lets the windows take the sizes that the layout requested.
+    fn communicate(&mut self) {
+        for win in &self.windows {
+            if win.communicate() {
+                self.layout.update_window(win.id(), None);
+            }
+        }
+    }
+
     fn add_window(&mut self, mut window: TestWindow, width: Option<PresetSize>) {
         let ws = self.layout.active_workspace().unwrap();
         let min_size = window.min_size();
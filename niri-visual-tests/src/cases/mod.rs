@@ -22,6 +22,8 @@ pub mod layout;
 pub mod tile;
 pub mod window;
 
+pub type DynMakeTestCase = Box<dyn Fn(Args) -> Box<dyn TestCase>>;
+
 pub struct Args {
     pub size: Size<i32, Logical>,
     pub clock: Clock,
@@ -0,0 +1,334 @@
+//! Rendering test cases without a window, for capturing and comparing screenshots.
+//!
+//! Each case is rendered offscreen once its animations have finished, and saved as a PNG named
+//! after the case title. Comparing goes through Oklab, so that differences in rounding between
+//! GPU drivers don't count, while a wrong color or a shifted edge does.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::iter::zip;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use niri::animation::Clock;
+use niri::render_helpers::{resources, shaders};
+use niri::utils::write_png_rgba8;
+use smithay::backend::allocator::Fourcc;
+use smithay::backend::egl::native::EGLSurfacelessDisplay;
+use smithay::backend::egl::{EGLContext, EGLDisplay};
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::backend::renderer::{Bind, Color32F, ExportMem, Frame, Offscreen, Renderer};
+use smithay::utils::{Rectangle, Scale, Size, Transform};
+
+use crate::cases::{Args, DynMakeTestCase};
+
+/// Logical size of the area that cases render into, rendered at scale 1.
+const SIZE: (i32, i32) = (800, 600);
+
+/// Same as the background of the interactive view.
+const BACKGROUND: [f32; 4] = [0.3, 0.3, 0.3, 1.];
+
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+/// Cases still animating after this long are captured as they are.
+const MAX_SETTLE_TIME: Duration = Duration::from_secs(10);
+
+/// Renders every case and saves it into `dir`.
+pub fn capture(cases: &[(&str, DynMakeTestCase)], dir: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(dir).context("error creating the output directory")?;
+
+    let names = file_names(cases.iter().map(|(title, _)| *title))?;
+    let mut renderer = create_renderer()?;
+    for ((title, make), name) in zip(cases, names) {
+        let pixels =
+            render_case(&mut renderer, make).with_context(|| format!("error rendering {title}"))?;
+
+        let path = dir.join(name);
+        let file = File::create(&path).with_context(|| format!("error creating {path:?}"))?;
+        write_png_rgba8(file, SIZE.0 as u32, SIZE.1 as u32, &pixels)
+            .with_context(|| format!("error writing {path:?}"))?;
+        println!("captured {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Renders every case and compares it to the image in `dir`.
+///
+/// A pixel differs when its distance in Oklab is above `threshold`. Returns whether all cases
+/// matched.
+pub fn compare(
+    cases: &[(&str, DynMakeTestCase)],
+    dir: &Path,
+    threshold: f64,
+) -> anyhow::Result<bool> {
+    let names = file_names(cases.iter().map(|(title, _)| *title))?;
+    let mut renderer = create_renderer()?;
+    let mut failed = 0;
+    for ((title, make), name) in zip(cases, names) {
+        let path = dir.join(name);
+        let expected = match read_png(&path) {
+            Ok(expected) => expected,
+            Err(err) => {
+                println!("FAIL {title}: {err:#}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        let actual =
+            render_case(&mut renderer, make).with_context(|| format!("error rendering {title}"))?;
+
+        let diff = diff(&expected, &actual, threshold);
+        if diff.pixels == 0 {
+            println!("ok   {title}");
+        } else {
+            println!(
+                "FAIL {title}: {} pixels differ, max difference {:.3}",
+                diff.pixels, diff.max
+            );
+            failed += 1;
+        }
+    }
+
+    println!("{failed} of {} cases differ", cases.len());
+    Ok(failed == 0)
+}
+
+fn create_renderer() -> anyhow::Result<GlesRenderer> {
+    let mut renderer = unsafe {
+        let display =
+            EGLDisplay::new(EGLSurfacelessDisplay).context("error creating EGL display")?;
+        let context = EGLContext::new(&display).context("error creating EGL context")?;
+        GlesRenderer::new(context).context("error creating renderer")?
+    };
+
+    resources::init(&mut renderer);
+    shaders::init(&mut renderer);
+
+    Ok(renderer)
+}
+
+/// Renders the final frame of the case, returning RGBA pixels.
+fn render_case(renderer: &mut GlesRenderer, make: &DynMakeTestCase) -> anyhow::Result<Vec<u8>> {
+    let mut clock = Clock::with_time(Duration::ZERO);
+    clock.set_complete_instantly(true);
+
+    let mut case = make(Args {
+        size: Size::from(SIZE),
+        clock: clock.clone(),
+    });
+
+    // Run the steps of the case and let its animations finish.
+    let mut time = Duration::ZERO;
+    loop {
+        case.advance_animations(clock.now());
+        if !case.are_animations_ongoing() || time >= MAX_SETTLE_TIME {
+            break;
+        }
+        time += FRAME_TIME;
+        clock.set_unadjusted(time);
+    }
+
+    let size = Size::from(SIZE);
+    let rect = Rectangle::from_size(size);
+    let elements = case.render(renderer, size);
+
+    let buffer_size = Size::from(SIZE);
+    let mut texture: GlesTexture = renderer
+        .create_buffer(Fourcc::Abgr8888, buffer_size)
+        .context("error creating texture")?;
+
+    {
+        let mut target = renderer
+            .bind(&mut texture)
+            .context("error binding texture")?;
+        let mut frame = renderer
+            .render(&mut target, size, Transform::Normal)
+            .context("error creating frame")?;
+
+        frame
+            .clear(Color32F::from(BACKGROUND), &[rect])
+            .context("error clearing")?;
+
+        for element in elements.iter().rev() {
+            let src = element.src();
+            let dst = element.geometry(Scale::from(1.));
+
+            if let Some(mut damage) = rect.intersection(dst) {
+                damage.loc -= dst.loc;
+                element
+                    .draw(&mut frame, src, dst, &[damage], &[])
+                    .context("error drawing element")?;
+            }
+        }
+
+        frame.finish().context("error finishing frame")?;
+    }
+
+    let target = renderer
+        .bind(&mut texture)
+        .context("error binding texture")?;
+    let mapping = renderer
+        .copy_framebuffer(&target, Rectangle::from_size(buffer_size), Fourcc::Abgr8888)
+        .context("error copying framebuffer")?;
+    let pixels = renderer
+        .map_texture(&mapping)
+        .context("error mapping texture")?;
+    Ok(pixels.to_vec())
+}
+
+fn read_png(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let file = File::open(path).with_context(|| format!("error opening {path:?}"))?;
+    let mut reader = png::Decoder::new(BufReader::new(file))
+        .read_info()
+        .context("error reading PNG header")?;
+
+    let info = reader.info();
+    ensure!(
+        info.color_type == png::ColorType::Rgba && info.bit_depth == png::BitDepth::Eight,
+        "expected an 8-bit RGBA image"
+    );
+    if (info.width, info.height) != (SIZE.0 as u32, SIZE.1 as u32) {
+        bail!(
+            "expected a {}x{} image, got {}x{}",
+            SIZE.0,
+            SIZE.1,
+            info.width,
+            info.height
+        );
+    }
+
+    let mut pixels = vec![0; reader.output_buffer_size().context("image is too big")?];
+    reader
+        .next_frame(&mut pixels)
+        .context("error decoding PNG")?;
+    Ok(pixels)
+}
+
+struct Diff {
+    /// Number of pixels over the threshold.
+    pixels: usize,
+    /// Largest difference between two pixels.
+    max: f64,
+}
+
+fn diff(expected: &[u8], actual: &[u8], threshold: f64) -> Diff {
+    let mut rv = Diff { pixels: 0, max: 0. };
+
+    for (a, b) in expected.chunks_exact(4).zip(actual.chunks_exact(4)) {
+        let [al, aa, ab] = to_oklab(a);
+        let [bl, ba, bb] = to_oklab(b);
+        let color = ((al - bl).powi(2) + (aa - ba).powi(2) + (ab - bb).powi(2)).sqrt();
+        let alpha = (f64::from(a[3]) - f64::from(b[3])).abs() / 255.;
+        let difference = color.max(alpha);
+
+        rv.max = rv.max.max(difference);
+        if difference > threshold {
+            rv.pixels += 1;
+        }
+    }
+
+    rv
+}
+
+fn to_oklab(pixel: &[u8]) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = f64::from(c) / 255.;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let [r, g, b] = [linear(pixel[0]), linear(pixel[1]), linear(pixel[2])];
+
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// Returns the file names for the case titles, making sure that no two cases share one.
+fn file_names<'a>(titles: impl IntoIterator<Item = &'a str>) -> anyhow::Result<Vec<String>> {
+    let mut seen = HashMap::new();
+    titles
+        .into_iter()
+        .map(|title| {
+            let name = file_name(title);
+            if let Some(other) = seen.insert(name.clone(), title) {
+                bail!("cases {other:?} and {title:?} would both be saved as {name}");
+            }
+            Ok(name)
+        })
+        .collect()
+}
+
+/// Turns a case title like "Gradient - Oklch Alpha" into "gradient-oklch-alpha.png".
+fn file_name(title: &str) -> String {
+    let mut name = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !name.is_empty() {
+            name.push('-');
+        }
+        name.push_str(&word.to_ascii_lowercase());
+    }
+    name.push_str(".png");
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels(colors: &[[u8; 4]]) -> Vec<u8> {
+        colors.iter().flatten().copied().collect()
+    }
+
+    #[test]
+    fn oklab_of_black_and_white() {
+        let [l, a, b] = to_oklab(&[0, 0, 0, 255]);
+        assert!(l.abs() < 1e-6 && a.abs() < 1e-6 && b.abs() < 1e-6);
+
+        let [l, a, b] = to_oklab(&[255, 255, 255, 255]);
+        assert!((l - 1.).abs() < 1e-3);
+        assert!(a.abs() < 1e-3 && b.abs() < 1e-3);
+    }
+
+    #[test]
+    fn diff_ignores_rounding_and_counts_real_changes() {
+        let expected = pixels(&[[10, 20, 30, 255], [200, 100, 50, 255], [0, 0, 0, 255]]);
+
+        let rounded = pixels(&[[11, 20, 29, 255], [200, 101, 50, 255], [0, 0, 0, 255]]);
+        let diff = diff(&expected, &rounded, 0.02);
+        assert_eq!(diff.pixels, 0);
+        assert!(diff.max > 0.);
+
+        let changed = pixels(&[[10, 20, 30, 255], [50, 100, 200, 255], [0, 0, 0, 0]]);
+        let diff = diff(&expected, &changed, 0.02);
+        assert_eq!(diff.pixels, 2);
+        assert_eq!(diff.max, 1.);
+    }
+
+    #[test]
+    fn file_names_from_titles() {
+        assert_eq!(
+            file_name("Gradient - Oklch Alpha"),
+            "gradient-oklch-alpha.png"
+        );
+        assert_eq!(file_name("Tile: 50% Width"), "tile-50-width.png");
+
+        assert!(file_names(["Tile", "Tile Tabbed"]).is_ok());
+        assert!(file_names(["Tile - Tabbed", "Tile Tabbed"]).is_err());
+    }
+}
@@ -2,9 +2,11 @@
 extern crate tracing;
 
 use std::env;
+use std::path::PathBuf;
 
 use adw::prelude::{AdwApplicationWindowExt, NavigationPageExt};
-use cases::Args;
+use cases::{Args, DynMakeTestCase};
+use clap::Parser;
 use gtk::prelude::{ApplicationExt, ApplicationExtManual, BoxExt, GtkWindowExt, WidgetExt};
 use gtk::{gdk, gio, glib};
 use smithay_view::SmithayView;
@@ -29,10 +31,27 @@ use crate::cases::window::Window;
 use crate::cases::TestCase;
 
 mod cases;
+mod headless;
 mod smithay_view;
 mod test_window;
 
+#[derive(Parser)]
+#[command(about = "Test scenarios for visual inspection of niri rendering")]
+struct Cli {
+    /// Render every test case offscreen and save PNGs into this directory.
+    #[arg(long, value_name = "DIR", conflicts_with = "compare")]
+    capture: Option<PathBuf>,
+    /// Render every test case offscreen and compare to the PNGs in this directory.
+    #[arg(long, value_name = "DIR")]
+    compare: Option<PathBuf>,
+    /// Largest Oklab distance between two pixels that still counts as the same color.
+    #[arg(long, default_value_t = 0.02, requires = "compare")]
+    threshold: f64,
+}
+
 fn main() -> glib::ExitCode {
+    let cli = Cli::parse();
+
     let directives =
         env::var("RUST_LOG").unwrap_or_else(|_| "niri-visual-tests=debug,niri=debug".to_owned());
     let env_filter = EnvFilter::builder().parse_lossy(directives);
@@ -41,45 +60,49 @@ fn main() -> glib::ExitCode {
         .with_env_filter(env_filter)
         .init();
 
+    if let Some(dir) = &cli.capture {
+        return match headless::capture(&all_test_cases(), dir) {
+            Ok(()) => glib::ExitCode::SUCCESS,
+            Err(err) => {
+                error!("error capturing test cases: {err:?}");
+                glib::ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(dir) = &cli.compare {
+        return match headless::compare(&all_test_cases(), dir, cli.threshold) {
+            Ok(true) => glib::ExitCode::SUCCESS,
+            Ok(false) => glib::ExitCode::FAILURE,
+            Err(err) => {
+                error!("error comparing test cases: {err:?}");
+                glib::ExitCode::FAILURE
+            }
+        };
+    }
+
     let app = adw::Application::new(None::<&str>, gio::ApplicationFlags::NON_UNIQUE);
     app.connect_startup(on_startup);
     app.connect_activate(build_ui);
-    app.run()
-}
-
-fn on_startup(_app: &adw::Application) {
-    // Load our CSS.
-    let provider = gtk::CssProvider::new();
-    provider.load_from_string(include_str!("../resources/style.css"));
-    if let Some(display) = gdk::Display::default() {
-        gtk::style_context_add_provider_for_display(
-            &display,
-            &provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
-    }
+    // The arguments were already handled above.
+    app.run_with_args::<&str>(&[])
 }
 
-fn build_ui(app: &adw::Application) {
-    let stack = gtk::Stack::new();
-    let anim_adjustment = gtk::Adjustment::new(1., 0., 10., 0.1, 0.5, 0.);
-
-    struct S {
-        stack: gtk::Stack,
-        anim_adjustment: gtk::Adjustment,
-    }
+fn all_test_cases() -> Vec<(&'static str, DynMakeTestCase)> {
+    struct S(Vec<(&'static str, DynMakeTestCase)>);
 
     impl S {
-        fn add<T: TestCase + 'static>(&self, make: impl Fn(Args) -> T + 'static, title: &str) {
-            let view = SmithayView::new(make, &self.anim_adjustment);
-            self.stack.add_titled(&view, None, title);
+        fn add<T: TestCase + 'static>(
+            &mut self,
+            make: impl Fn(Args) -> T + 'static,
+            title: &'static str,
+        ) {
+            let make = move |args| Box::new(make(args)) as Box<dyn TestCase>;
+            self.0.push((title, Box::new(make)));
         }
     }
 
-    let s = S {
-        stack: stack.clone(),
-        anim_adjustment: anim_adjustment.clone(),
-    };
+    let mut s = S(Vec::new());
 
     s.add(Window::freeform, "Freeform Window");
     s.add(Window::fixed_size, "Fixed Size Window");
@@ -116,6 +139,16 @@ fn build_ui(app: &adw::Application) {
         "Layout - Open To The Left - Big",
     );
 
+    s.add(Layout::nested_splits, "Layout - Nested Splits");
+    s.add(Layout::tabbed, "Layout - Tabbed");
+    s.add(Layout::tabbed_scale_1_5, "Layout - Tabbed - Scale 1.5");
+    s.add(Layout::tabbed_scale_2, "Layout - Tabbed - Scale 2");
+    s.add(Layout::tabbed_side_bar, "Layout - Tabbed - Side Bar");
+    s.add(Layout::stacked, "Layout - Stacked");
+    s.add(Layout::stacked_scale_1_5, "Layout - Stacked - Scale 1.5");
+    s.add(Layout::stacked_scale_2, "Layout - Stacked - Scale 2");
+    s.add(Layout::tabbed_in_split, "Layout - Tabbed In Split");
+
     s.add(GradientAngle::new, "Gradient - Angle");
     s.add(GradientArea::new, "Gradient - Area");
     s.add(GradientSrgb::new, "Gradient - Srgb");
@@ -130,6 +163,31 @@ fn build_ui(app: &adw::Application) {
     s.add(GradientOklabAlpha::new, "Gradient - Oklab Alpha");
     s.add(GradientOklchAlpha::new, "Gradient - Oklch Alpha");
 
+    s.0
+}
+
+fn on_startup(_app: &adw::Application) {
+    // Load our CSS.
+    let provider = gtk::CssProvider::new();
+    provider.load_from_string(include_str!("../resources/style.css"));
+    if let Some(display) = gdk::Display::default() {
+        gtk::style_context_add_provider_for_display(
+            &display,
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+    }
+}
+
+fn build_ui(app: &adw::Application) {
+    let stack = gtk::Stack::new();
+    let anim_adjustment = gtk::Adjustment::new(1., 0., 10., 0.1, 0.5, 0.);
+
+    for (title, make) in all_test_cases() {
+        let view = SmithayView::new(make, &anim_adjustment);
+        stack.add_titled(&view, None, title);
+    }
+
     let content_headerbar = adw::HeaderBar::new();
 
     let anim_scale = gtk::Scale::new(gtk::Orientation::Horizontal, Some(&anim_adjustment));
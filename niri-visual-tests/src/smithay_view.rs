@@ -3,7 +3,7 @@ use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 use smithay::utils::Size;
 
-use crate::cases::{Args, TestCase};
+use crate::cases::{Args, DynMakeTestCase, TestCase};
 
 mod imp {
     use std::cell::{Cell, OnceCell, RefCell};
@@ -24,8 +24,6 @@ mod imp {
 
     use super::*;
 
-    type DynMakeTestCase = Box<dyn Fn(Args) -> Box<dyn TestCase>>;
-
     struct RendererData {
         renderer: GlesRenderer,
         dummy_texture: GlesTexture,
@@ -260,14 +258,9 @@ glib::wrapper! {
 }
 
 impl SmithayView {
-    pub fn new<T: TestCase + 'static>(
-        make_test_case: impl Fn(Args) -> T + 'static,
-        anim_adjustment: &gtk::Adjustment,
-    ) -> Self {
+    pub fn new(make_test_case: DynMakeTestCase, anim_adjustment: &gtk::Adjustment) -> Self {
         let obj: Self = glib::Object::builder().build();
 
-        let make = move |args| Box::new(make_test_case(args)) as Box<dyn TestCase>;
-        let make_test_case = Box::new(make) as _;
         let _ = obj.imp().make_test_case.set(make_test_case);
 
         anim_adjustment.connect_value_changed({
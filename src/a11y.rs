@@ -268,6 +268,16 @@ impl Niri {
         self.a11y_announce(self.hotkey_overlay.a11y_text());
     }
 
+    pub fn a11y_announce_power_menu(&mut self) {
+        if self.a11y.to_accesskit.is_none() {
+            return;
+        }
+
+        if let Some(entry) = self.exit_confirm_dialog.selected_entry() {
+            self.a11y_announce(String::from(entry.label()));
+        }
+    }
+
     fn a11y_focus(&self) -> NodeId {
         match self.keyboard_focus {
             KeyboardFocus::ScreenshotUi => ID_SCREENSHOT_UI,
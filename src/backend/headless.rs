@@ -1,37 +1,53 @@
-//! Headless backend for tests.
+//! Headless backend for tests and `niri --headless`.
 //!
-//! This can eventually grow into a more complete backend if needed, but for now it's missing some
-//! crucial parts like dmabufs.
+//! With `--headless`, all outputs are virtual outputs. This can eventually grow into a more
+//! complete backend if needed, but for now it's missing some crucial parts like dmabufs.
+//!
+//! With a renderer, frames go through a damage tracker like on a real backend, and the damage of
+//! every frame is recorded so that tests can check it.
 
+use std::collections::HashMap;
 use std::mem;
 use std::sync::{Arc, Mutex};
 
-use anyhow::Context as _;
+use anyhow::{anyhow, Context as _};
 use niri_config::OutputName;
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::egl::native::EGLSurfacelessDisplay;
 use smithay::backend::egl::{EGLContext, EGLDisplay};
+use smithay::backend::renderer::damage::OutputDamageTracker;
 use smithay::backend::renderer::element::RenderElementStates;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
 use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
-use smithay::utils::Size;
+use smithay::utils::{Physical, Rectangle, Size};
 use smithay::wayland::presentation::Refresh;
 
 use super::{IpcOutputMap, OutputId, RenderResult};
 use crate::niri::{Niri, RedrawState};
-use crate::render_helpers::{resources, shaders};
+use crate::render_helpers::{resources, shaders, RenderTarget};
 use crate::utils::{get_monotonic_time, logical_output};
 
 pub struct Headless {
     renderer: Option<GlesRenderer>,
+    damage: HashMap<Output, DamageRecorder>,
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
 }
 
+/// How many frames of damage to keep per output until a test takes them.
+pub const MAX_RECORDED_FRAMES: usize = 64;
+
+/// Damage of the frames rendered on one output.
+struct DamageRecorder {
+    tracker: OutputDamageTracker,
+    frames: Vec<Vec<Rectangle<i32, Physical>>>,
+}
+
 impl Headless {
     pub fn new() -> Self {
         Self {
             renderer: None,
+            damage: HashMap::new(),
             ipc_outputs: Default::default(),
         }
     }
@@ -115,6 +131,16 @@ impl Headless {
         niri.add_output(output, None, false);
     }
 
+    pub fn remove_output(&mut self, niri: &mut Niri, output: &Output) {
+        self.damage.remove(output);
+        self.ipc_outputs
+            .lock()
+            .unwrap()
+            .retain(|_, ipc_output| ipc_output.name != output.name());
+
+        niri.remove_output(output);
+    }
+
     pub fn seat_name(&self) -> String {
         "headless".to_owned()
     }
@@ -127,6 +153,11 @@ impl Headless {
     }
 
     pub fn render(&mut self, niri: &mut Niri, output: &Output) -> RenderResult {
+        if let Err(err) = self.record_damage(niri, output) {
+            warn!("error rendering: {err:?}");
+            return RenderResult::Skipped;
+        }
+
         let states = RenderElementStates::default();
         let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &states);
         presentation_feedbacks.presented::<_, smithay::utils::Monotonic>(
@@ -152,6 +183,48 @@ impl Headless {
         RenderResult::Submitted
     }
 
+    fn record_damage(&mut self, niri: &mut Niri, output: &Output) -> anyhow::Result<()> {
+        let Some(renderer) = &mut self.renderer else {
+            return Ok(());
+        };
+
+        // Tests don't move the pointer around, so leave it out to keep the damage stable.
+        let elements = niri.render::<GlesRenderer>(renderer, output, false, RenderTarget::Output);
+
+        let recorder = self
+            .damage
+            .entry(output.clone())
+            .or_insert_with(|| DamageRecorder {
+                tracker: OutputDamageTracker::from_output(output),
+                frames: Vec::new(),
+            });
+
+        // Age 1 gives the damage since the previous frame.
+        let (damage, _) = recorder
+            .tracker
+            .damage_output(1, &elements)
+            .map_err(|err| anyhow!("error computing damage: {err:?}"))?;
+
+        // Nobody may be taking the damage, so don't let it grow without bound.
+        if recorder.frames.len() == MAX_RECORDED_FRAMES {
+            recorder.frames.remove(0);
+        }
+        recorder.frames.push(damage.cloned().unwrap_or_default());
+
+        Ok(())
+    }
+
+    /// Returns the damage of every frame rendered on the output since the last call.
+    ///
+    /// Damage is only recorded with a renderer, and only for the last
+    /// [`MAX_RECORDED_FRAMES`] frames.
+    pub fn take_damage(&mut self, output: &Output) -> Vec<Vec<Rectangle<i32, Physical>>> {
+        self.damage
+            .get_mut(output)
+            .map(|recorder| mem::take(&mut recorder.frames))
+            .unwrap_or_default()
+    }
+
     pub fn import_dmabuf(&mut self, _dmabuf: &Dmabuf) -> bool {
         unimplemented!()
     }
@@ -20,6 +20,10 @@ pub use winit::Winit;
 pub mod headless;
 pub use headless::Headless;
 
+pub mod planes;
+
+pub mod virtual_output;
+
 #[allow(clippy::large_enum_variant)]
 pub enum Backend {
     Tty(Tty),
@@ -89,6 +93,10 @@ impl Backend {
         output: &Output,
         target_presentation_time: Duration,
     ) -> RenderResult {
+        if niri.virtual_outputs.contains(output) {
+            return virtual_output::render(niri, output, target_presentation_time);
+        }
+
         match self {
             Backend::Tty(tty) => tty.render(niri, output, target_presentation_time),
             Backend::Winit(winit) => winit.render(niri, output),
@@ -177,7 +185,19 @@ impl Backend {
         }
     }
 
+    pub fn power_off_output(&mut self, output: &Output) {
+        match self {
+            Backend::Tty(tty) => tty.power_off_output(output),
+            Backend::Winit(_) => (),
+            Backend::Headless(_) => (),
+        }
+    }
+
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
+        if niri.virtual_outputs.contains(output) {
+            return;
+        }
+
         match self {
             Backend::Tty(tty) => tty.set_output_on_demand_vrr(niri, output, enable_vrr),
             Backend::Winit(_) => (),
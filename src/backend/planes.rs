@@ -0,0 +1,199 @@
+//! Offloading surfaces to overlay planes.
+//!
+//! Overlay planes show a buffer without composition, which saves GPU work for things like a video
+//! playing in a subsurface. On some hardware, moving buffers on and off the planes causes frame
+//! drops though, so overlay planes are opt-in with `debug { enable-overlay-planes; }`, and even
+//! then niri only allows them on frames with something worth offloading.
+
+use std::collections::HashSet;
+
+use smithay::backend::allocator::format::FormatSet;
+use smithay::backend::allocator::{Buffer as _, Format};
+use smithay::backend::renderer::element::{Element, Id};
+use smithay::backend::renderer::utils::with_renderer_surface_state;
+use smithay::output::Output;
+use smithay::utils::{Scale, Transform};
+use smithay::wayland::compositor::{with_surface_tree_downward, TraversalAction};
+use smithay::wayland::dmabuf::get_dmabuf;
+
+use crate::layout::LayoutElement as _;
+use crate::niri::{Niri, OutputRenderElements};
+use crate::render_helpers::renderer::NiriRenderer;
+
+/// Returns whether the frame has a surface worth putting on an overlay plane.
+///
+/// Candidates are window subsurfaces with their own buffer, like a video, that an overlay plane
+/// can show as is: a dmabuf in a format supported by an overlay plane, with the output transform
+/// and without scaling. Nothing is offloaded while the output is animating.
+pub fn has_overlay_candidate<R: NiriRenderer>(
+    niri: &Niri,
+    output: &Output,
+    elements: &[OutputRenderElements<R>],
+    overlay_formats: &FormatSet,
+) -> bool {
+    let _span = tracy_client::span!("planes::has_overlay_candidate");
+
+    if overlay_formats.iter().next().is_none() || niri.layout.are_animations_ongoing(Some(output)) {
+        return false;
+    }
+
+    let ids = subsurface_candidates(niri, output, overlay_formats);
+    if ids.is_empty() {
+        return false;
+    }
+
+    let scale = Scale::from(output.current_scale().fractional_scale());
+    let transform = output.current_transform();
+    elements
+        .iter()
+        .any(|elem| ids.contains(elem.id()) && fits_plane(elem, scale, transform))
+}
+
+/// Collects the subsurfaces of visible windows whose buffer an overlay plane supports.
+fn subsurface_candidates(niri: &Niri, output: &Output, formats: &FormatSet) -> HashSet<Id> {
+    let mut ids = HashSet::new();
+
+    let Some(mon) = niri.layout.monitor_for_output(output) else {
+        return ids;
+    };
+
+    for (ws, _) in mon.workspaces_with_render_geo() {
+        for mapped in ws.windows() {
+            let root = mapped.toplevel().wl_surface();
+            with_surface_tree_downward(
+                root,
+                (),
+                |_, _, _| TraversalAction::DoChildren(()),
+                |surface, _, _| {
+                    if surface == root {
+                        return;
+                    }
+
+                    let format = with_renderer_surface_state(surface, |state| {
+                        let buffer = state.buffer()?;
+                        get_dmabuf(buffer).ok().map(|dmabuf| dmabuf.format())
+                    })
+                    .flatten();
+                    if plane_supports(formats, format) {
+                        ids.insert(Id::from_wayland_resource(surface));
+                    }
+                },
+                |_, _, _| true,
+            );
+        }
+    }
+
+    ids
+}
+
+/// Checks that an overlay plane can show the buffer, which must be a dmabuf.
+fn plane_supports(formats: &FormatSet, format: Option<Format>) -> bool {
+    format.is_some_and(|format| formats.contains(&format))
+}
+
+/// Checks that the element can go on a plane without rotation or scaling.
+fn fits_plane(elem: &impl Element, scale: Scale<f64>, output_transform: Transform) -> bool {
+    if elem.transform() != output_transform {
+        return false;
+    }
+
+    let src = elem.transform().transform_size(elem.src().size);
+    let dst = elem.geometry(scale).size;
+    src.w.round() as i32 == dst.w && src.h.round() as i32 == dst.h
+}
+
+#[cfg(test)]
+mod tests {
+    use smithay::backend::allocator::{Fourcc, Modifier};
+    use smithay::backend::renderer::utils::CommitCounter;
+    use smithay::utils::{Buffer, Physical, Rectangle, Size};
+
+    use super::*;
+
+    struct TestElement {
+        id: Id,
+        src: Size<f64, Buffer>,
+        size: Size<i32, Physical>,
+        transform: Transform,
+    }
+
+    impl TestElement {
+        fn new(src: (f64, f64), size: (i32, i32), transform: Transform) -> Self {
+            Self {
+                id: Id::new(),
+                src: Size::from(src),
+                size: Size::from(size),
+                transform,
+            }
+        }
+    }
+
+    impl Element for TestElement {
+        fn id(&self) -> &Id {
+            &self.id
+        }
+
+        fn current_commit(&self) -> CommitCounter {
+            CommitCounter::default()
+        }
+
+        fn src(&self) -> Rectangle<f64, Buffer> {
+            Rectangle::from_size(self.src)
+        }
+
+        fn geometry(&self, _scale: Scale<f64>) -> Rectangle<i32, Physical> {
+            Rectangle::from_size(self.size)
+        }
+
+        fn transform(&self) -> Transform {
+            self.transform
+        }
+    }
+
+    #[test]
+    fn plane_supports_only_listed_dmabuf_formats() {
+        let nv12 = Format {
+            code: Fourcc::Nv12,
+            modifier: Modifier::Linear,
+        };
+        let formats = FormatSet::from_iter([nv12]);
+
+        assert!(plane_supports(&formats, Some(nv12)));
+        // Not a dmabuf, like an shm buffer.
+        assert!(!plane_supports(&formats, None));
+
+        let other_modifier = Format {
+            code: Fourcc::Nv12,
+            modifier: Modifier::Invalid,
+        };
+        assert!(!plane_supports(&formats, Some(other_modifier)));
+
+        let other_code = Format {
+            code: Fourcc::Argb8888,
+            modifier: Modifier::Linear,
+        };
+        assert!(!plane_supports(&formats, Some(other_code)));
+    }
+
+    #[test]
+    fn fits_plane_without_scaling_or_rotation() {
+        let scale = Scale::from(1.);
+        let normal = Transform::Normal;
+
+        let elem = TestElement::new((1920., 1080.), (1920, 1080), normal);
+        assert!(fits_plane(&elem, scale, normal));
+
+        // Scaled up or down.
+        let elem = TestElement::new((1280., 720.), (1920, 1080), normal);
+        assert!(!fits_plane(&elem, scale, normal));
+        let elem = TestElement::new((1920., 1080.), (960, 540), normal);
+        assert!(!fits_plane(&elem, scale, normal));
+
+        // Rotated relative to the output.
+        let elem = TestElement::new((1080., 1920.), (1920, 1080), Transform::_90);
+        assert!(!fits_plane(&elem, scale, normal));
+
+        // Rotated together with the output.
+        assert!(fits_plane(&elem, scale, Transform::_90));
+    }
+}
@@ -1,7 +1,7 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
-use std::iter::zip;
+use std::iter::{self, zip};
 use std::num::NonZeroU64;
 use std::os::fd::{AsFd, OwnedFd};
 use std::path::Path;
@@ -61,10 +61,10 @@ use smithay_drm_extras::drm_scanner::{DrmScanEvent, DrmScanner};
 use wayland_protocols::wp::linux_dmabuf::zv1::server::zwp_linux_dmabuf_feedback_v1::TrancheFlags;
 use wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
 
-use super::{IpcOutputMap, RenderResult};
+use super::{planes, IpcOutputMap, RenderResult};
 use crate::backend::OutputId;
 use crate::frame_clock::FrameClock;
-use crate::niri::{Niri, RedrawState, State};
+use crate::niri::{Niri, RedrawState, ScanoutState, State};
 use crate::render_helpers::debug::draw_damage;
 use crate::render_helpers::renderer::AsGlesRenderer;
 use crate::render_helpers::{resources, shaders, RenderTarget};
@@ -375,9 +375,13 @@ struct TtyOutputState {
 
 struct Surface {
     name: OutputName,
+    /// GPU that renders this output.
+    render_node: DrmNode,
     compositor: GbmDrmCompositor,
     connector: connector::Handle,
     dmabuf_feedback: Option<SurfaceDmabufFeedback>,
+    /// Formats supported by the overlay planes.
+    overlay_formats: FormatSet,
     gamma_props: Option<GammaProps>,
     /// Gamma change to apply upon session resume.
     pending_gamma_change: Option<Option<Vec<u16>>>,
@@ -844,9 +848,13 @@ impl Tty {
                 );
             assert!(self.dmabuf_global.replace(dmabuf_global).is_none());
 
-            // Update the dmabuf feedbacks for all surfaces.
+            // Update the dmabuf feedbacks for all surfaces rendered on the primary GPU.
             for (node, device) in self.devices.iter_mut() {
                 for surface in device.surfaces.values_mut() {
+                    if surface.render_node != self.primary_render_node {
+                        continue;
+                    }
+
                     match surface_dmabuf_feedback(
                         &surface.compositor,
                         primary_formats.clone(),
@@ -1400,7 +1408,7 @@ impl Tty {
             GbmFramebufferExporter::new(device.gbm.clone(), device.render_node.into()),
             SUPPORTED_COLOR_FORMATS,
             // This is only used to pick a good internal format, so it can use the surface's render
-            // formats, even though we only ever render on the primary GPU.
+            // formats, even when we render on the primary GPU.
             render_formats.clone(),
             device.drm.cursor_size(),
             Some(device.gbm.clone()),
@@ -1441,14 +1449,30 @@ impl Tty {
             compositor.set_debug_flags(DebugFlags::TINT);
         }
 
+        let surface_render_node = output_render_node(
+            &mut self.gpu_manager,
+            self.primary_render_node,
+            device.render_node,
+            compositor.format(),
+        );
+        if surface_render_node != render_node {
+            debug!(
+                "{connector_name} is on {render_node}, frames will be copied over from \
+                 {surface_render_node}"
+            );
+        } else if render_node != self.primary_render_node {
+            debug!("{connector_name} is on {render_node}, rendering it there");
+        }
+
         let mut dmabuf_feedback = None;
-        if let Ok(primary_renderer) = self.gpu_manager.single_renderer(&self.primary_render_node) {
-            let primary_formats = primary_renderer.dmabuf_formats();
+        if let Ok(surface_renderer) = self.gpu_manager.single_renderer(&surface_render_node) {
+            let surface_render_formats = surface_renderer.dmabuf_formats();
 
+            // Clients rendering for this output should prefer the GPU that renders it.
             match surface_dmabuf_feedback(
                 &compositor,
-                primary_formats,
-                self.primary_render_node,
+                surface_render_formats,
+                surface_render_node,
                 device.render_node,
                 node,
             ) {
@@ -1482,11 +1506,22 @@ impl Tty {
         let sequence_delta_plot_name =
             tracy_client::PlotName::new_leak(format!("{connector_name} sequence delta"));
 
+        let overlay_formats = compositor
+            .surface()
+            .planes()
+            .overlay
+            .iter()
+            .flat_map(|plane| plane.formats.iter())
+            .copied()
+            .collect();
+
         let surface = Surface {
             name: output_name,
             connector: connector.handle(),
+            render_node: surface_render_node,
             compositor,
             dmabuf_feedback,
+            overlay_formats,
             gamma_props,
             pending_gamma_change: None,
             vblank_frame: None,
@@ -1550,8 +1585,9 @@ impl Tty {
             .global_space
             .outputs()
             .find(|output| {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                tty_state.node == node && tty_state.crtc == crtc
+                // Virtual outputs have no TTY state.
+                let tty_state: Option<&TtyOutputState> = output.user_data().get();
+                tty_state.is_some_and(|state| state.node == node && state.crtc == crtc)
             })
             .cloned();
         if let Some(output) = output {
@@ -1630,8 +1666,9 @@ impl Tty {
             .global_space
             .outputs()
             .find(|output| {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                tty_state.node == node && tty_state.crtc == crtc
+                // Virtual outputs have no TTY state.
+                let tty_state: Option<&TtyOutputState> = output.user_data().get();
+                tty_state.is_some_and(|state| state.node == node && state.crtc == crtc)
             })
             .cloned()
         else {
@@ -1763,6 +1800,8 @@ impl Tty {
         // We waited for the timer, now we can send frame callbacks again.
         output_state.frame_callback_sequence = output_state.frame_callback_sequence.wrapping_add(1);
 
+        let redraw_deferred = mem::take(&mut output_state.redraw_deferred);
+
         match mem::replace(&mut output_state.redraw_state, RedrawState::Idle) {
             RedrawState::Idle => unreachable!(),
             RedrawState::Queued => unreachable!(),
@@ -1775,7 +1814,7 @@ impl Tty {
             }
         }
 
-        if output_state.unfinished_animations_remain {
+        if output_state.unfinished_animations_remain || redraw_deferred {
             niri.queue_redraw(&output);
         } else {
             niri.send_frame_callbacks(&output);
@@ -1826,14 +1865,28 @@ impl Tty {
             return rv;
         }
 
+        // Stay under the max render rate. Rather than queueing a frame that would show up on the
+        // next VBlank, wait for the estimated VBlank before the target one. Clients don't get frame
+        // callbacks until then, which paces them to the same rate.
+        let output_state = niri.output_state.get_mut(output).unwrap();
+        if let Some(render_at) = output_state.frame_clock.render_delayed_until() {
+            trace!("deferring redraw to stay under the max render rate");
+            output_state.redraw_deferred = true;
+            queue_estimated_vblank_timer(niri, output.clone(), render_at);
+            return rv;
+        }
+
         let mut renderer = match self.gpu_manager.renderer(
-            &self.primary_render_node,
+            &surface.render_node,
             &device.render_node.unwrap_or(self.primary_render_node),
             surface.compositor.format(),
         ) {
             Ok(renderer) => renderer,
             Err(err) => {
-                warn!("error creating renderer for primary GPU: {err:?}");
+                warn!(
+                    "error creating renderer for {}: {err:?}",
+                    surface.render_node
+                );
                 return rv;
             }
         };
@@ -1842,6 +1895,9 @@ impl Tty {
         let mut elements =
             niri.render::<TtyRenderer>(&mut renderer, output, true, RenderTarget::Output);
 
+        // Let a fullscreen window go directly on the primary plane if possible.
+        let scanout_candidate = niri.prepare_direct_scanout(output, &mut elements);
+
         // Visualize the damage, if enabled.
         if niri.debug_draw_damage {
             let output_state = niri.output_state.get_mut(output).unwrap();
@@ -1849,7 +1905,8 @@ impl Tty {
         }
 
         // Overlay planes are disabled by default as they cause weird performance issues on my
-        // system.
+        // system. When enabled, they are only allowed for frames with a good candidate, like a
+        // video.
         let flags = {
             let debug = &self.config.borrow().debug;
 
@@ -1860,7 +1917,9 @@ impl Tty {
             };
             let mut flags = primary_scanout_flag | FrameFlags::ALLOW_CURSOR_PLANE_SCANOUT;
 
-            if debug.enable_overlay_planes {
+            if debug.enable_overlay_planes
+                && planes::has_overlay_candidate(niri, output, &elements, &surface.overlay_formats)
+            {
                 flags.insert(FrameFlags::ALLOW_OVERLAY_PLANE_SCANOUT);
             }
             if debug.disable_direct_scanout {
@@ -1884,6 +1943,12 @@ impl Tty {
         let drm_compositor = &mut surface.compositor;
         match drm_compositor.render_frame::<_, _>(&mut renderer, &elements, [0.; 4], flags) {
             Ok(res) => {
+                let output_state = niri.output_state.get_mut(output).unwrap();
+                output_state.scanout = ScanoutState {
+                    candidate: scanout_candidate,
+                    direct: matches!(res.primary_element, PrimaryPlaneElement::Element(_)),
+                };
+
                 let needs_sync = res.needs_sync()
                     || self
                         .config
@@ -1984,24 +2049,43 @@ impl Tty {
     }
 
     pub fn import_dmabuf(&mut self, dmabuf: &Dmabuf) -> bool {
-        let mut renderer = match self.gpu_manager.single_renderer(&self.primary_render_node) {
-            Ok(renderer) => renderer,
-            Err(err) => {
-                debug!("error creating renderer for primary GPU: {err:?}");
-                return false;
-            }
-        };
+        // Most outputs render on the primary GPU, so try it first. If it can't import the buffer
+        // (for example, a client rendering on a dGPU with a modifier the iGPU doesn't support),
+        // fall back to other GPUs. The multi-GPU renderer then copies the buffer over to the GPU
+        // rendering each output.
+        let secondary_nodes = self
+            .devices
+            .values()
+            .filter_map(|device| device.render_node)
+            .filter(|node| *node != self.primary_render_node);
+        let nodes = iter::once(self.primary_render_node)
+            .chain(secondary_nodes)
+            .collect::<Vec<_>>();
 
-        match renderer.import_dmabuf(dmabuf, None) {
-            Ok(_texture) => {
-                dmabuf.set_node(Some(self.primary_render_node));
-                true
-            }
-            Err(err) => {
-                debug!("error importing dmabuf: {err:?}");
-                false
+        for node in nodes {
+            let mut renderer = match self.gpu_manager.single_renderer(&node) {
+                Ok(renderer) => renderer,
+                Err(err) => {
+                    debug!("error creating renderer for {node}: {err:?}");
+                    continue;
+                }
+            };
+
+            match renderer.import_dmabuf(dmabuf, None) {
+                Ok(_texture) => {
+                    if node != self.primary_render_node {
+                        debug!("imported dmabuf on {node}, will copy to the primary GPU");
+                    }
+                    dmabuf.set_node(Some(node));
+                    return true;
+                }
+                Err(err) => {
+                    debug!("error importing dmabuf on {node}: {err:?}");
+                }
             }
         }
+
+        false
     }
 
     pub fn early_import(&mut self, surface: &WlSurface) {
@@ -2015,7 +2099,10 @@ impl Tty {
     }
 
     pub fn get_gamma_size(&self, output: &Output) -> anyhow::Result<u32> {
-        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let tty_state = output
+            .user_data()
+            .get::<TtyOutputState>()
+            .context("missing TTY output state")?;
         let crtc = tty_state.crtc;
 
         let device = self
@@ -2036,7 +2123,10 @@ impl Tty {
     }
 
     pub fn set_gamma(&mut self, output: &Output, ramp: Option<Vec<u16>>) -> anyhow::Result<()> {
-        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let tty_state = output
+            .user_data()
+            .get::<TtyOutputState>()
+            .context("missing TTY output state")?;
         let crtc = tty_state.crtc;
 
         let device = self
@@ -2136,8 +2226,9 @@ impl Tty {
                     .global_space
                     .outputs()
                     .find(|output| {
-                        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                        tty_state.node == *node && tty_state.crtc == crtc
+                        // Virtual outputs have no TTY state.
+                        let tty_state: Option<&TtyOutputState> = output.user_data().get();
+                        tty_state.is_some_and(|state| state.node == *node && state.crtc == crtc)
                     })
                     .map(logical_output);
 
@@ -2165,6 +2256,8 @@ impl Tty {
             }
         }
 
+        niri.virtual_outputs.fill_ipc_outputs(&mut ipc_outputs);
+
         let mut guard = self.ipc_outputs.lock().unwrap();
         *guard = ipc_outputs;
         niri.ipc_outputs_changed = true;
@@ -2206,6 +2299,23 @@ impl Tty {
         }
     }
 
+    /// Powers off a single output, like `set_monitors_active(false)`.
+    pub fn power_off_output(&mut self, output: &Output) {
+        let Some(tty_state) = output.user_data().get::<TtyOutputState>() else {
+            return;
+        };
+        let Some(device) = self.devices.get_mut(&tty_state.node) else {
+            return;
+        };
+        let Some(surface) = device.surfaces.get_mut(&tty_state.crtc) else {
+            return;
+        };
+
+        if let Err(err) = surface.compositor.clear() {
+            warn!("error clearing drm surface: {err:?}");
+        }
+    }
+
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
         let _span = tracy_client::span!("Tty::set_output_on_demand_vrr");
 
@@ -2386,8 +2496,9 @@ impl Tty {
                     .global_space
                     .outputs()
                     .find(|output| {
-                        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                        tty_state.node == node && tty_state.crtc == crtc
+                        // Virtual outputs have no TTY state.
+                        let tty_state: Option<&TtyOutputState> = output.user_data().get();
+                        tty_state.is_some_and(|state| state.node == node && state.crtc == crtc)
                     })
                     .cloned();
                 let Some(output) = output else {
@@ -2444,10 +2555,14 @@ impl Tty {
                     let wl_mode = Mode::from(mode);
                     output.change_current_state(Some(wl_mode), None, None, None);
                     output.set_preferred(wl_mode);
+                    let max_render_rate = output_state.frame_clock.max_render_rate();
                     output_state.frame_clock = FrameClock::new(
                         Some(refresh_interval(mode)),
                         surface.compositor.vrr_enabled(),
                     );
+                    output_state
+                        .frame_clock
+                        .set_max_render_rate(max_render_rate);
                     niri.output_resized(&output);
                 }
             }
@@ -2727,6 +2842,67 @@ fn ignored_nodes_from_config(config: &Config) -> HashSet<DrmNode> {
     disabled_nodes
 }
 
+/// Picks the GPU to render an output on.
+///
+/// Outputs are normally rendered on the primary GPU, and the GPU driving the output imports the
+/// frames. When it can't import any format that the primary GPU renders to, the output is rendered
+/// on its own GPU instead, which copies client buffers over from other GPUs as needed.
+fn output_render_node(
+    gpu_manager: &mut GpuManager<GbmGlesBackend<GlesRenderer, DrmDeviceFd>>,
+    primary_render_node: DrmNode,
+    output_render_node: Option<DrmNode>,
+    format: Fourcc,
+) -> DrmNode {
+    // Display-only devices can't render at all.
+    let Some(output_render_node) = output_render_node else {
+        return primary_render_node;
+    };
+    if output_render_node == primary_render_node {
+        return primary_render_node;
+    }
+
+    let render_formats = match gpu_manager.single_renderer(&primary_render_node) {
+        Ok(renderer) => renderer
+            .as_ref()
+            .egl_context()
+            .dmabuf_render_formats()
+            .clone(),
+        Err(err) => {
+            warn!("error creating renderer for primary GPU: {err:?}");
+            return output_render_node;
+        }
+    };
+    let import_formats = match gpu_manager.single_renderer(&output_render_node) {
+        Ok(renderer) => renderer
+            .as_ref()
+            .egl_context()
+            .dmabuf_texture_formats()
+            .clone(),
+        Err(err) => {
+            warn!("error creating renderer for {output_render_node}: {err:?}");
+            return primary_render_node;
+        }
+    };
+
+    if can_import_frames(&render_formats, &import_formats, format) {
+        primary_render_node
+    } else {
+        output_render_node
+    }
+}
+
+/// Returns whether a GPU importing `import_formats` can show frames of `format` rendered by a GPU
+/// with `render_formats`.
+fn can_import_frames(
+    render_formats: &FormatSet,
+    import_formats: &FormatSet,
+    format: Fourcc,
+) -> bool {
+    render_formats
+        .intersection(import_formats)
+        .any(|f| f.code == format)
+}
+
 fn surface_dmabuf_feedback(
     compositor: &GbmDrmCompositor,
     primary_formats: FormatSet,
@@ -3383,8 +3559,12 @@ mod tests {
     use insta::assert_debug_snapshot;
     use niri_config::output::Modeline;
     use niri_ipc::{HSyncPolarity, VSyncPolarity};
+    use smithay::backend::allocator::format::FormatSet;
+    use smithay::backend::allocator::{Format, Fourcc, Modifier};
 
-    use crate::backend::tty::{calculate_drm_mode_from_modeline, calculate_mode_cvt};
+    use crate::backend::tty::{
+        calculate_drm_mode_from_modeline, calculate_mode_cvt, can_import_frames,
+    };
 
     #[test]
     fn test_calculate_drmmode_from_modeline() {
@@ -3524,4 +3704,38 @@ mod tests {
         }
         "#);
     }
+
+    fn formats(formats: &[(Fourcc, Modifier)]) -> FormatSet {
+        formats
+            .iter()
+            .map(|&(code, modifier)| Format { code, modifier })
+            .collect()
+    }
+
+    #[test]
+    fn frames_import_only_with_a_shared_format() {
+        let render = formats(&[
+            (Fourcc::Xrgb8888, Modifier::Linear),
+            (Fourcc::Xrgb8888, Modifier::I915_x_tiled),
+            (Fourcc::Argb8888, Modifier::I915_x_tiled),
+        ]);
+
+        // The output GPU can read linear buffers.
+        let import = formats(&[
+            (Fourcc::Xrgb8888, Modifier::Linear),
+            (Fourcc::Argb8888, Modifier::Linear),
+        ]);
+        assert!(can_import_frames(&render, &import, Fourcc::Xrgb8888));
+        // Argb8888 is only rendered tiled, which the output GPU can't read.
+        assert!(!can_import_frames(&render, &import, Fourcc::Argb8888));
+
+        // The output GPU can't read anything the primary GPU renders.
+        let import = formats(&[(Fourcc::Xrgb8888, Modifier::Invalid)]);
+        assert!(!can_import_frames(&render, &import, Fourcc::Xrgb8888));
+        assert!(!can_import_frames(
+            &render,
+            &FormatSet::default(),
+            Fourcc::Xrgb8888
+        ));
+    }
 }
@@ -0,0 +1,263 @@
+//! Virtual outputs created at runtime through IPC.
+//!
+//! Virtual outputs work on top of any backend. They have no display behind them, so rendering
+//! only advances the frame callbacks at the nominal refresh rate. Their contents can still be
+//! screencast or screenshot like any other output.
+
+use std::collections::HashMap;
+use std::mem;
+use std::time::Duration;
+
+use niri_config::OutputName;
+use smithay::backend::renderer::element::RenderElementStates;
+use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
+use smithay::utils::Size;
+use smithay::wayland::presentation::Refresh;
+
+use super::{IpcOutputMap, OutputId, RenderResult};
+use crate::niri::{Niri, RedrawState};
+use crate::utils::{get_monotonic_time, logical_output};
+
+/// Default refresh rate of virtual outputs in mHz.
+pub const DEFAULT_REFRESH_RATE: i32 = 60_000;
+
+#[derive(Default)]
+pub struct VirtualOutputs {
+    outputs: HashMap<String, VirtualOutput>,
+}
+
+struct VirtualOutput {
+    output: Output,
+    id: OutputId,
+}
+
+/// Scale requested when creating a virtual output, stored in its user data.
+struct RequestedScale(f64);
+
+impl VirtualOutputs {
+    pub fn contains(&self, output: &Output) -> bool {
+        self.outputs.values().any(|v| &v.output == output)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Output> {
+        self.outputs
+            .iter()
+            .find(|(connector, _)| connector.eq_ignore_ascii_case(name))
+            .map(|(_, v)| &v.output)
+    }
+
+    /// Returns an unused connector name for a new virtual output.
+    pub fn next_name(&self, is_taken: impl Fn(&str) -> bool) -> String {
+        (1..)
+            .map(|n| format!("HEADLESS-{n}"))
+            .find(|name| self.find(name).is_none() && !is_taken(name))
+            .unwrap()
+    }
+
+    /// Fills in IPC information for all virtual outputs.
+    pub fn fill_ipc_outputs(&self, ipc_outputs: &mut IpcOutputMap) {
+        for v in self.outputs.values() {
+            ipc_outputs.insert(v.id, ipc_output(&v.output));
+        }
+    }
+}
+
+/// Returns the scale requested when creating the virtual output.
+///
+/// The output config takes precedence over it.
+pub fn requested_scale(output: &Output) -> Option<f64> {
+    output.user_data().get::<RequestedScale>().map(|s| s.0)
+}
+
+/// Creates a virtual output and adds it to the compositor.
+///
+/// The refresh rate is in mHz.
+pub fn add(
+    niri: &mut Niri,
+    ipc_outputs: &mut IpcOutputMap,
+    connector: String,
+    size: (u16, u16),
+    scale: Option<f64>,
+    refresh: i32,
+) {
+    let make = "niri".to_string();
+    let model = "virtual".to_string();
+
+    let output = Output::new(
+        connector.clone(),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: make.clone(),
+            model: model.clone(),
+            serial_number: String::new(),
+        },
+    );
+
+    let mode = Mode {
+        size: Size::from((i32::from(size.0), i32::from(size.1))),
+        refresh,
+    };
+    output.change_current_state(Some(mode), None, None, None);
+    output.set_preferred(mode);
+
+    output.user_data().insert_if_missing(|| OutputName {
+        connector: connector.clone(),
+        make: Some(make),
+        model: Some(model),
+        serial: None,
+    });
+    if let Some(scale) = scale {
+        output
+            .user_data()
+            .insert_if_missing(|| RequestedScale(scale));
+    }
+
+    let refresh_interval = Duration::from_nanos(1_000_000_000_000 / refresh as u64);
+    niri.add_output(output.clone(), Some(refresh_interval), false);
+
+    let id = OutputId::next();
+    ipc_outputs.insert(id, ipc_output(&output));
+    niri.ipc_outputs_changed = true;
+
+    niri.virtual_outputs
+        .outputs
+        .insert(connector, VirtualOutput { output, id });
+}
+
+/// Removes a virtual output from the compositor.
+///
+/// Returns `false` if there's no virtual output with this name.
+pub fn remove(niri: &mut Niri, ipc_outputs: &mut IpcOutputMap, name: &str) -> bool {
+    let Some(connector) = niri
+        .virtual_outputs
+        .outputs
+        .keys()
+        .find(|connector| connector.eq_ignore_ascii_case(name))
+        .cloned()
+    else {
+        return false;
+    };
+
+    let v = niri.virtual_outputs.outputs.remove(&connector).unwrap();
+    niri.remove_output(&v.output);
+
+    ipc_outputs.remove(&v.id);
+    niri.ipc_outputs_changed = true;
+
+    true
+}
+
+pub fn render(
+    niri: &mut Niri,
+    output: &Output,
+    target_presentation_time: Duration,
+) -> RenderResult {
+    let _span = tracy_client::span!("virtual_output::render");
+
+    let states = RenderElementStates::default();
+    let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &states);
+    presentation_feedbacks.presented::<_, smithay::utils::Monotonic>(
+        get_monotonic_time(),
+        Refresh::Unknown,
+        0,
+        wp_presentation_feedback::Kind::empty(),
+    );
+
+    // There's no VBlank to wait for, so pace the frames with a timer.
+    queue_estimated_vblank_timer(niri, output.clone(), target_presentation_time);
+
+    RenderResult::NoDamage
+}
+
+fn queue_estimated_vblank_timer(
+    niri: &mut Niri,
+    output: Output,
+    target_presentation_time: Duration,
+) {
+    let output_state = niri.output_state.get_mut(&output).unwrap();
+    match mem::take(&mut output_state.redraw_state) {
+        RedrawState::Idle => unreachable!(),
+        RedrawState::Queued => (),
+        RedrawState::WaitingForVBlank { .. } => unreachable!(),
+        RedrawState::WaitingForEstimatedVBlank(token)
+        | RedrawState::WaitingForEstimatedVBlankAndQueued(token) => {
+            output_state.redraw_state = RedrawState::WaitingForEstimatedVBlank(token);
+            return;
+        }
+    }
+
+    let now = get_monotonic_time();
+    let mut duration = target_presentation_time.saturating_sub(now);
+    if duration.is_zero() {
+        duration += output_state
+            .frame_clock
+            .refresh_interval()
+            .unwrap_or(Duration::from_micros(16_667));
+    }
+
+    let timer = Timer::from_duration(duration);
+    let token = niri
+        .event_loop
+        .insert_source(timer, move |_, _, data| {
+            on_estimated_vblank_timer(&mut data.niri, &output);
+            TimeoutAction::Drop
+        })
+        .unwrap();
+    output_state.redraw_state = RedrawState::WaitingForEstimatedVBlank(token);
+}
+
+fn on_estimated_vblank_timer(niri: &mut Niri, output: &Output) {
+    let Some(output_state) = niri.output_state.get_mut(output) else {
+        error!("missing output state for {}", output.name());
+        return;
+    };
+
+    // Feed the presentation time into the frame clock so that the next target is correct.
+    output_state.frame_clock.presented(get_monotonic_time());
+    output_state.frame_callback_sequence = output_state.frame_callback_sequence.wrapping_add(1);
+
+    match mem::replace(&mut output_state.redraw_state, RedrawState::Idle) {
+        RedrawState::Idle => unreachable!(),
+        RedrawState::Queued => unreachable!(),
+        RedrawState::WaitingForVBlank { .. } => unreachable!(),
+        RedrawState::WaitingForEstimatedVBlank(_) => (),
+        // The timer fired just in front of a redraw.
+        RedrawState::WaitingForEstimatedVBlankAndQueued(_) => {
+            output_state.redraw_state = RedrawState::Queued;
+            return;
+        }
+    }
+
+    if output_state.unfinished_animations_remain {
+        niri.queue_redraw(output);
+    } else {
+        niri.send_frame_callbacks(output);
+    }
+}
+
+fn ipc_output(output: &Output) -> niri_ipc::Output {
+    let physical_properties = output.physical_properties();
+    let mode = output.current_mode().unwrap();
+
+    niri_ipc::Output {
+        name: output.name(),
+        make: physical_properties.make,
+        model: physical_properties.model,
+        serial: None,
+        physical_size: None,
+        modes: vec![niri_ipc::Mode {
+            width: mode.size.w.clamp(0, u16::MAX as i32) as u16,
+            height: mode.size.h.clamp(0, u16::MAX as i32) as u16,
+            refresh_rate: mode.refresh as u32,
+            is_preferred: true,
+        }],
+        current_mode: Some(0),
+        is_custom_mode: true,
+        vrr_supported: false,
+        vrr_enabled: false,
+        logical: Some(logical_output(output)),
+    }
+}
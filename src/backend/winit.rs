@@ -1,13 +1,17 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::iter::zip;
 use std::mem;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 use niri_config::{Config, OutputName};
 use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::damage::OutputDamageTracker;
-use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::backend::renderer::element::{Kind, RenderElementStates};
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::backend::renderer::sync::SyncPoint;
 use smithay::backend::renderer::{DebugFlags, ImportDma, ImportEgl, Renderer};
 use smithay::backend::winit::{self, WinitEvent, WinitGraphicsBackend};
 use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
@@ -15,22 +19,39 @@ use smithay::reexports::calloop::LoopHandle;
 use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
 use smithay::reexports::winit::dpi::LogicalSize;
 use smithay::reexports::winit::window::Window;
+use smithay::utils::{Physical, Point, Rectangle, Scale, Size, Transform};
 use smithay::wayland::presentation::Refresh;
 
 use super::{IpcOutputMap, OutputId, RenderResult};
-use crate::niri::{Niri, RedrawState, State};
+use crate::niri::{Niri, OutputRenderElements, RedrawState, State};
 use crate::render_helpers::debug::draw_damage;
-use crate::render_helpers::{resources, shaders, RenderTarget};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::render_helpers::{render_to_texture, resources, shaders, RenderTarget};
 use crate::utils::{get_monotonic_time, logical_output};
 
 pub struct Winit {
     config: Rc<RefCell<Config>>,
-    output: Output,
+    /// Outputs shown in the window, from left to right.
+    outputs: Vec<Output>,
     backend: WinitGraphicsBackend<GlesRenderer>,
     damage_tracker: OutputDamageTracker,
+    /// Per-output state when the window is split between several outputs.
+    split: HashMap<Output, SplitOutput>,
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
 }
 
+/// Output that takes up a part of the window.
+///
+/// Every output renders into its own texture, then the textures are drawn side by side into the
+/// window.
+struct SplitOutput {
+    damage_tracker: OutputDamageTracker,
+    frame: Option<TextureBuffer<GlesTexture>>,
+}
+
+/// Maximum number of outputs in the window.
+const MAX_OUTPUTS: u8 = 8;
+
 impl Winit {
     pub fn new(
         config: Rc<RefCell<Config>>,
@@ -44,101 +65,109 @@ impl Winit {
             .with_title("niri");
         let (backend, winit) = winit::init_from_attributes(builder)?;
 
-        let output = Output::new(
-            "winit".to_string(),
-            PhysicalProperties {
-                size: (0, 0).into(),
-                subpixel: Subpixel::Unknown,
-                make: "Smithay".into(),
-                model: "Winit".into(),
-                serial_number: "Unknown".into(),
-            },
-        );
+        let mut count = config.borrow().debug.nested_outputs.unwrap_or(1);
+        if !(1..=MAX_OUTPUTS).contains(&count) {
+            warn!("nested-outputs must be between 1 and {MAX_OUTPUTS}, got {count}");
+            count = count.clamp(1, MAX_OUTPUTS);
+        }
 
-        let mode = Mode {
-            size: backend.window_size(),
-            refresh: 60_000,
+        let window_size = backend.window_size();
+        let widths = split_width(window_size.w, usize::from(count));
+        let outputs = widths
+            .into_iter()
+            .enumerate()
+            .map(|(idx, width)| {
+                // A single output keeps the plain name.
+                let connector = if count == 1 {
+                    String::from("winit")
+                } else {
+                    format!("winit-{}", idx + 1)
+                };
+                create_output(connector, Size::from((width, window_size.h)))
+            })
+            .collect::<Vec<_>>();
+
+        let ipc_outputs = outputs
+            .iter()
+            .map(|output| (OutputId::next(), ipc_output(output)))
+            .collect();
+        let ipc_outputs = Arc::new(Mutex::new(ipc_outputs));
+
+        let damage_tracker = if count == 1 {
+            OutputDamageTracker::from_output(&outputs[0])
+        } else {
+            // Matches the transform that winit outputs get, see Niri::add_output().
+            OutputDamageTracker::new(window_size, 1., Transform::Flipped180)
         };
-        output.change_current_state(Some(mode), None, None, None);
-        output.set_preferred(mode);
-
-        output.user_data().insert_if_missing(|| OutputName {
-            connector: "winit".to_string(),
-            make: Some("Smithay".to_string()),
-            model: Some("Winit".to_string()),
-            serial: None,
-        });
-
-        let physical_properties = output.physical_properties();
-        let ipc_outputs = Arc::new(Mutex::new(HashMap::from([(
-            OutputId::next(),
-            niri_ipc::Output {
-                name: output.name(),
-                make: physical_properties.make,
-                model: physical_properties.model,
-                serial: None,
-                physical_size: None,
-                modes: vec![niri_ipc::Mode {
-                    width: backend.window_size().w.clamp(0, u16::MAX as i32) as u16,
-                    height: backend.window_size().h.clamp(0, u16::MAX as i32) as u16,
-                    refresh_rate: 60_000,
-                    is_preferred: true,
-                }],
-                current_mode: Some(0),
-                is_custom_mode: true,
-                vrr_supported: false,
-                vrr_enabled: false,
-                logical: Some(logical_output(&output)),
-            },
-        )])));
-
-        let damage_tracker = OutputDamageTracker::from_output(&output);
 
         event_loop
             .insert_source(winit, move |event, _, state| match event {
                 WinitEvent::Resized { size, .. } => {
-                    let winit = state.backend.winit();
-                    winit.output.change_current_state(
-                        Some(Mode {
-                            size,
-                            refresh: 60_000,
-                        }),
-                        None,
-                        None,
-                        None,
-                    );
-
-                    {
-                        let mut ipc_outputs = winit.ipc_outputs.lock().unwrap();
-                        let output = ipc_outputs.values_mut().next().unwrap();
-                        let mode = &mut output.modes[0];
-                        mode.width = size.w.clamp(0, u16::MAX as i32) as u16;
-                        mode.height = size.h.clamp(0, u16::MAX as i32) as u16;
-                        if let Some(logical) = output.logical.as_mut() {
-                            logical.width = size.w as u32;
-                            logical.height = size.h as u32;
-                        }
-                        state.niri.ipc_outputs_changed = true;
-                    }
-
-                    state.niri.output_resized(&winit.output);
+                    state.backend.winit().resized(&mut state.niri, size);
                 }
                 WinitEvent::Input(event) => state.process_input_event(event),
                 WinitEvent::Focus(_) => (),
-                WinitEvent::Redraw => state.niri.queue_redraw(&state.backend.winit().output),
+                WinitEvent::Redraw => {
+                    for output in &state.backend.winit().outputs {
+                        state.niri.queue_redraw(output);
+                    }
+                }
                 WinitEvent::CloseRequested => state.niri.stop_signal.stop(),
             })
             .unwrap();
 
         Ok(Self {
             config,
-            output,
+            outputs,
             backend,
             damage_tracker,
+            split: HashMap::new(),
             ipc_outputs,
         })
     }
 
+    fn resized(&mut self, niri: &mut Niri, size: Size<i32, Physical>) {
+        let widths = split_width(size.w, self.outputs.len());
+
+        {
+            let mut ipc_outputs = self.ipc_outputs.lock().unwrap();
+            for (output, width) in zip(&self.outputs, widths) {
+                let mode_size = Size::from((width, size.h));
+                output.change_current_state(
+                    Some(Mode {
+                        size: mode_size,
+                        refresh: 60_000,
+                    }),
+                    None,
+                    None,
+                    None,
+                );
+
+                let name = output.name();
+                let ipc_output = ipc_outputs
+                    .values_mut()
+                    .find(|output| output.name == name)
+                    .unwrap();
+                let mode = &mut ipc_output.modes[0];
+                mode.width = mode_size.w.clamp(0, u16::MAX as i32) as u16;
+                mode.height = mode_size.h.clamp(0, u16::MAX as i32) as u16;
+                if let Some(logical) = ipc_output.logical.as_mut() {
+                    logical.width = mode_size.w as u32;
+                    logical.height = mode_size.h as u32;
+                }
+            }
+            niri.ipc_outputs_changed = true;
+        }
+
+        if self.outputs.len() > 1 {
+            self.damage_tracker = OutputDamageTracker::new(size, 1., Transform::Flipped180);
+        }
+
+        for output in &self.outputs {
+            niri.output_resized(output);
+        }
+    }
+
     pub fn init(&mut self, niri: &mut Niri) {
         let renderer = self.backend.renderer();
         if let Err(err) = renderer.bind_wl_display(&niri.display_handle) {
@@ -162,7 +191,9 @@ impl Winit {
 
         niri.update_shaders();
 
-        niri.add_output(self.output.clone(), None, false);
+        for output in &self.outputs {
+            niri.add_output(output.clone(), None, false);
+        }
     }
 
     pub fn seat_name(&self) -> String {
@@ -194,21 +225,25 @@ impl Winit {
         }
 
         // Hand them over to winit.
-        let res = {
+        let (damage, states, sync) = if self.outputs.len() == 1 {
             let (renderer, mut framebuffer) = self.backend.bind().unwrap();
             // FIXME: currently impossible to call due to a mutable borrow.
             //
             // let age = self.backend.buffer_age().unwrap();
             let age = 0;
-            self.damage_tracker
+            let res = self
+                .damage_tracker
                 .render_output(renderer, &mut framebuffer, age, &elements, [0.; 4])
-                .unwrap()
+                .unwrap();
+            (res.damage.cloned(), res.states, res.sync)
+        } else {
+            self.render_split(output, &elements)
         };
 
-        niri.update_primary_scanout_output(output, &res.states);
+        niri.update_primary_scanout_output(output, &states);
 
         let rv;
-        if let Some(damage) = res.damage {
+        if let Some(damage) = damage {
             if self
                 .config
                 .borrow()
@@ -216,14 +251,14 @@ impl Winit {
                 .wait_for_frame_completion_before_queueing
             {
                 let _span = tracy_client::span!("wait for completion");
-                if let Err(err) = res.sync.wait() {
+                if let Err(err) = sync.wait() {
                     warn!("error waiting for frame completion: {err:?}");
                 }
             }
 
-            self.backend.submit(Some(damage)).unwrap();
+            self.backend.submit(Some(&damage)).unwrap();
 
-            let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &res.states);
+            let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &states);
             presentation_feedbacks.presented::<_, smithay::utils::Monotonic>(
                 get_monotonic_time(),
                 Refresh::Unknown,
@@ -256,6 +291,81 @@ impl Winit {
         rv
     }
 
+    /// Renders one output into its part of the window.
+    fn render_split(
+        &mut self,
+        output: &Output,
+        elements: &[OutputRenderElements<GlesRenderer>],
+    ) -> (
+        Option<Vec<Rectangle<i32, Physical>>>,
+        RenderElementStates,
+        SyncPoint,
+    ) {
+        let split = self
+            .split
+            .entry(output.clone())
+            .or_insert_with(|| SplitOutput {
+                damage_tracker: OutputDamageTracker::from_output(output),
+                frame: None,
+            });
+
+        let (damage, states) = split.damage_tracker.damage_output(1, elements).unwrap();
+        if damage.is_some() || split.frame.is_none() {
+            let renderer = self.backend.renderer();
+            let size = output.current_mode().unwrap().size;
+            let scale = Scale::from(output.current_scale().fractional_scale());
+            let res = render_to_texture(
+                renderer,
+                size,
+                scale,
+                Transform::Normal,
+                Fourcc::Abgr8888,
+                elements.iter().rev(),
+            );
+            match res {
+                Ok((texture, _)) => {
+                    let buffer = TextureBuffer::from_texture(
+                        renderer,
+                        texture,
+                        1.,
+                        Transform::Normal,
+                        vec![],
+                    );
+                    split.frame = Some(buffer);
+                }
+                Err(err) => warn!("error rendering output {}: {err:?}", output.name()),
+            }
+        }
+
+        // Unchanged outputs keep their texture, so only this output's part of the window gets
+        // damaged.
+        let mut x = 0;
+        let frames = self
+            .outputs
+            .iter()
+            .filter_map(|output| {
+                let loc = Point::from((f64::from(x), 0.));
+                x += output.current_mode().unwrap().size.w;
+                let buffer = self.split.get(output)?.frame.clone()?;
+                Some(TextureRenderElement::from_texture_buffer(
+                    buffer,
+                    loc,
+                    1.,
+                    None,
+                    None,
+                    Kind::Unspecified,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let (renderer, mut framebuffer) = self.backend.bind().unwrap();
+        let res = self
+            .damage_tracker
+            .render_output(renderer, &mut framebuffer, 0, &frames, [0.; 4])
+            .unwrap();
+        (res.damage.cloned(), states, res.sync)
+    }
+
     pub fn toggle_debug_tint(&mut self) {
         let renderer = self.backend.renderer();
         renderer.set_debug_flags(renderer.debug_flags() ^ DebugFlags::TINT);
@@ -275,3 +385,78 @@ impl Winit {
         self.ipc_outputs.clone()
     }
 }
+
+fn create_output(connector: String, size: Size<i32, Physical>) -> Output {
+    let output = Output::new(
+        connector.clone(),
+        PhysicalProperties {
+            size: (0, 0).into(),
+            subpixel: Subpixel::Unknown,
+            make: "Smithay".into(),
+            model: "Winit".into(),
+            serial_number: "Unknown".into(),
+        },
+    );
+
+    let mode = Mode {
+        size,
+        refresh: 60_000,
+    };
+    output.change_current_state(Some(mode), None, None, None);
+    output.set_preferred(mode);
+
+    output.user_data().insert_if_missing(|| OutputName {
+        connector,
+        make: Some("Smithay".to_string()),
+        model: Some("Winit".to_string()),
+        serial: None,
+    });
+
+    output
+}
+
+fn ipc_output(output: &Output) -> niri_ipc::Output {
+    let physical_properties = output.physical_properties();
+    let size = output.current_mode().unwrap().size;
+    niri_ipc::Output {
+        name: output.name(),
+        make: physical_properties.make,
+        model: physical_properties.model,
+        serial: None,
+        physical_size: None,
+        modes: vec![niri_ipc::Mode {
+            width: size.w.clamp(0, u16::MAX as i32) as u16,
+            height: size.h.clamp(0, u16::MAX as i32) as u16,
+            refresh_rate: 60_000,
+            is_preferred: true,
+        }],
+        current_mode: Some(0),
+        is_custom_mode: true,
+        vrr_supported: false,
+        vrr_enabled: false,
+        logical: Some(logical_output(output)),
+    }
+}
+
+/// Splits the window width between `count` outputs.
+///
+/// The last output gets the remainder.
+fn split_width(width: i32, count: usize) -> Vec<i32> {
+    let count = count.max(1);
+    let part = width / count as i32;
+    let mut widths = vec![part; count];
+    widths[count - 1] = width - part * (count as i32 - 1);
+    widths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_width_gives_remainder_to_last_output() {
+        assert_eq!(split_width(1280, 1), [1280]);
+        assert_eq!(split_width(1280, 2), [640, 640]);
+        assert_eq!(split_width(1001, 3), [333, 333, 335]);
+    }
+}
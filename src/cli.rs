@@ -1,9 +1,11 @@
 use std::ffi::OsString;
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
-use niri_ipc::{Action, OutputAction};
+use niri_ipc::{Action, ConfigValue, LogLevel, OutputAction};
 
 use crate::utils::version;
 
@@ -26,6 +28,21 @@ pub struct Cli {
     /// on a TTY as your non-main compositor instance, to avoid messing up the global environment.
     #[arg(long)]
     pub session: bool,
+    /// Run without a display, on virtual outputs.
+    ///
+    /// Useful for integration tests in CI and for sessions that are only accessed remotely, for
+    /// example through screencasting.
+    #[arg(long)]
+    pub headless: bool,
+    /// Virtual output to create with `--headless`, like `1920x1080` or `2560x1440@144`.
+    ///
+    /// Can be given several times. Defaults to a single 1920x1080 output.
+    #[arg(
+        long = "output",
+        value_name = "WIDTHxHEIGHT[@REFRESH]",
+        requires = "headless"
+    )]
+    pub outputs: Vec<HeadlessOutputSpec>,
     /// Command to run upon compositor startup.
     #[arg(last = true)]
     pub command: Vec<OsString>,
@@ -43,6 +60,13 @@ pub enum Sub {
         /// Format output as JSON.
         #[arg(short, long)]
         json: bool,
+        /// Keep running and print a JSON line whenever the result changes.
+        ///
+        /// Works with `focused-window` and `focused-workspace`. The first line has all fields, and
+        /// the following lines only have the fields that changed. A line of `null` means that
+        /// nothing is focused.
+        #[arg(short, long)]
+        watch: bool,
     },
     /// Validate the config file.
     Validate {
@@ -52,6 +76,9 @@ pub enum Sub {
         /// command line argument takes precedence.
         #[arg(short, long)]
         config: Option<PathBuf>,
+        /// Format output as JSON.
+        #[arg(short, long)]
+        json: bool,
     },
     /// Cause a panic to check if the backtraces are good.
     Panic,
@@ -75,6 +102,8 @@ pub enum Msg {
     FocusedOutput,
     /// Print information about the focused window.
     FocusedWindow,
+    /// Print information about the focused workspace.
+    FocusedWorkspace,
     /// Pick a window with the mouse and print information about it.
     PickWindow,
     /// Pick a color from the screen with the mouse.
@@ -101,6 +130,11 @@ pub enum Msg {
     },
     /// Start continuously receiving events from the compositor.
     EventStream,
+    /// Continuously print workspaces in the i3bar protocol.
+    ///
+    /// Use this as the status command of an i3bar-compatible bar.
+    #[command(name = "i3bar")]
+    I3bar,
     /// Print the version of the running niri instance.
     Version,
     /// Request an error from the running niri instance.
@@ -112,6 +146,211 @@ pub enum Msg {
     LayoutTree,
     /// List screencasts.
     Casts,
+    /// Create a virtual headless output.
+    ///
+    /// The output has no physical display behind it, but it can be screencast like any other
+    /// output.
+    CreateHeadlessOutput {
+        /// Width of the output in physical pixels.
+        #[arg()]
+        width: u16,
+        /// Height of the output in physical pixels.
+        #[arg()]
+        height: u16,
+        /// Scale of the output.
+        #[arg(long)]
+        scale: Option<f64>,
+        /// Name of the output.
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Destroy a virtual output created with `create-headless-output`.
+    DestroyOutput {
+        /// Output name.
+        #[arg()]
+        output: String,
+    },
+    /// Print the current value of a runtime-tunable config option.
+    GetConfigValue {
+        /// Path of the option, for example `layout.gaps`.
+        #[arg()]
+        path: String,
+    },
+    /// Change a runtime-tunable config option until the next config reload.
+    ///
+    /// Supported options: `layout.gaps`, `input.focus-follows-mouse`, `animations.slowdown`.
+    SetConfigValue {
+        /// Path of the option, for example `layout.gaps`.
+        #[arg()]
+        path: String,
+        /// New value: `true`, `false` or a number.
+        #[arg(allow_hyphen_values = true)]
+        value: ConfigValue,
+    },
+    /// Save a thumbnail of a workspace as a PNG image.
+    ///
+    /// Works for workspaces that are not currently visible too.
+    WorkspaceThumbnail {
+        /// Id of the workspace.
+        #[arg()]
+        id: u64,
+        /// Path to save the PNG image to.
+        ///
+        /// Required unless printing the response with `--json`.
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Maximum width and height of the thumbnail in pixels.
+        #[arg(long, default_value_t = 256)]
+        max_size: u16,
+    },
+    /// Print whether outputs scan out a fullscreen window directly, and why not.
+    DebugScanout,
+    /// Print recent log messages, oldest first.
+    ///
+    /// niri keeps the last few hundred messages at the info level or above in memory.
+    Logs {
+        /// Least severe level to print.
+        #[arg(long)]
+        level: Option<LogLevel>,
+        /// Print only this many of the most recent messages.
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Check the layout of every workspace for internal inconsistencies.
+    ///
+    /// Exits with an error if any are found.
+    DebugVerifyLayout,
+    /// Play back a macro of actions, key presses and waits.
+    ///
+    /// Requires `debug { allow-ipc-macros; }` in the config.
+    PlayMacro {
+        /// Path to a JSON file with the list of steps, or `-` for stdin.
+        #[arg()]
+        path: PathBuf,
+    },
+    /// Save the tiling tree of the focused workspace with the commands of its windows.
+    ExportWorkspace {
+        /// Path to save the JSON to.
+        ///
+        /// Prints the JSON if not set.
+        #[arg()]
+        path: Option<PathBuf>,
+    },
+    /// Rebuild a saved tiling tree and relaunch its windows into their places.
+    ImportWorkspace {
+        /// Path to a JSON file from `export-workspace`, or `-` for stdin.
+        #[arg()]
+        path: PathBuf,
+    },
+    /// List the saved answers to background and autostart requests from sandboxed apps.
+    BackgroundPermissions,
+    /// List binds that can't trigger because of other binds.
+    BindConflicts,
+    /// List the running processes that niri spawned.
+    SpawnedProcesses,
+    /// Print the shares of the children of a tiling container.
+    ///
+    /// Prints the container of the focused window if no container is given.
+    ContainerPercents {
+        /// Id of a window to print the container of.
+        #[arg(long, conflicts_with_all = ["path", "root"])]
+        id: Option<u64>,
+        /// Path of a container on the focused workspace, as comma-separated child indices from
+        /// the root container, e.g. `0,1`.
+        #[arg(long, value_delimiter = ',', conflicts_with = "root")]
+        path: Vec<usize>,
+        /// Print the root container of the focused workspace.
+        #[arg(long)]
+        root: bool,
+    },
+    /// Set the shares of the children of a tiling container.
+    ///
+    /// Sets the container of the focused window if no container is given. The shares are scaled
+    /// to add up to 1, so `2 1` and `0.667 0.333` do the same thing.
+    SetContainerPercents {
+        /// Share of every child of the container, in order.
+        #[arg(required = true)]
+        percents: Vec<f64>,
+        /// Id of a window to resize the container of.
+        #[arg(long, conflicts_with_all = ["path", "root"])]
+        id: Option<u64>,
+        /// Path of a container on the focused workspace, as comma-separated child indices from
+        /// the root container, e.g. `0,1`.
+        #[arg(long, value_delimiter = ',', conflicts_with = "root")]
+        path: Vec<usize>,
+        /// Resize the children of the root container of the focused workspace.
+        #[arg(long)]
+        root: bool,
+    },
+    /// Print the power saving state.
+    PowerSavingState,
+}
+
+/// Size and refresh rate of a virtual output in headless mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadlessOutputSpec {
+    pub width: u16,
+    pub height: u16,
+    /// Refresh rate in Hz.
+    pub refresh: Option<f64>,
+}
+
+impl Default for HeadlessOutputSpec {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            refresh: None,
+        }
+    }
+}
+
+impl FromStr for HeadlessOutputSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (size, refresh) = match s.split_once('@') {
+            Some((size, refresh)) => {
+                let refresh = refresh
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid refresh rate: {refresh}"))?;
+                if !(1. ..=1000.).contains(&refresh) {
+                    return Err(String::from("refresh rate must be between 1 and 1000"));
+                }
+                (size, Some(refresh))
+            }
+            None => (s, None),
+        };
+
+        let Some((width, height)) = size.split_once('x') else {
+            return Err(String::from("expected WIDTHxHEIGHT, for example 1920x1080"));
+        };
+        let width = width
+            .parse::<u16>()
+            .map_err(|_| format!("invalid width: {width}"))?;
+        let height = height
+            .parse::<u16>()
+            .map_err(|_| format!("invalid height: {height}"))?;
+        if width == 0 || height == 0 {
+            return Err(String::from("output size must be greater than zero"));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            refresh,
+        })
+    }
+}
+
+impl fmt::Display for HeadlessOutputSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)?;
+        if let Some(refresh) = self.refresh {
+            write!(f, "@{refresh}")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, clap::ValueEnum)]
@@ -138,3 +377,29 @@ impl TryFrom<CompletionShell> for Shell {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_headless_output_spec() {
+        let spec = "2560x1440@144".parse::<HeadlessOutputSpec>().unwrap();
+        assert_eq!(
+            spec,
+            HeadlessOutputSpec {
+                width: 2560,
+                height: 1440,
+                refresh: Some(144.),
+            }
+        );
+        assert_eq!(spec.to_string(), "2560x1440@144");
+
+        let spec = "1920x1080".parse::<HeadlessOutputSpec>().unwrap();
+        assert_eq!(spec.refresh, None);
+
+        assert!("1920".parse::<HeadlessOutputSpec>().is_err());
+        assert!("0x1080".parse::<HeadlessOutputSpec>().is_err());
+        assert!("1920x1080@0".parse::<HeadlessOutputSpec>().is_err());
+    }
+}
@@ -10,7 +10,7 @@ use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::element::memory::MemoryRenderBuffer;
 use smithay::input::pointer::{CursorIcon, CursorImageStatus, CursorImageSurfaceData};
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
-use smithay::utils::{IsAlive, Logical, Physical, Point, Transform};
+use smithay::utils::{IsAlive, Logical, Physical, Point, Size, Transform};
 use smithay::wayland::compositor::with_states;
 use xcursor::parser::{parse_xcursor, Image};
 use xcursor::CursorTheme;
@@ -18,6 +18,7 @@ use xcursor::CursorTheme;
 /// Some default looking `left_ptr` icon.
 static FALLBACK_CURSOR_DATA: &[u8] = include_bytes!("../resources/cursor.rgba");
 
+/// Named cursors by the size in physical pixels that they were loaded for.
 type XCursorCache = HashMap<(CursorIcon, i32), Option<Rc<XCursor>>>;
 
 pub struct CursorManager {
@@ -92,8 +93,8 @@ impl CursorManager {
         }
     }
 
-    /// Get the current rendering cursor.
-    pub fn get_render_cursor(&self, scale: i32) -> RenderCursor {
+    /// Get the current rendering cursor for an output with the given scale.
+    pub fn get_render_cursor(&self, scale: f64) -> RenderCursor {
         match self.cursor_image().clone() {
             CursorImageStatus::Hidden => RenderCursor::Hidden,
             CursorImageStatus::Surface(surface) => {
@@ -113,21 +114,32 @@ impl CursorManager {
         }
     }
 
-    fn get_render_cursor_named(&self, icon: CursorIcon, scale: i32) -> RenderCursor {
+    fn get_render_cursor_named(&self, icon: CursorIcon, scale: f64) -> RenderCursor {
+        let size = self.physical_size(scale);
         self.get_cursor_with_name(icon, scale)
             .map(|cursor| RenderCursor::Named {
                 icon,
+                size,
                 scale,
                 cursor,
             })
             .unwrap_or_else(|| RenderCursor::Named {
                 icon: Default::default(),
+                size,
                 scale,
                 cursor: self.get_default_cursor(scale),
             })
     }
 
-    pub fn is_current_cursor_animated(&self, scale: i32) -> bool {
+    /// Returns the nominal cursor size in physical pixels for an output with the given scale.
+    ///
+    /// Loading the cursor for the physical size rather than an integer scale keeps it sharp on
+    /// fractionally scaled outputs, and lets it go to the cursor plane without scaling.
+    fn physical_size(&self, scale: f64) -> i32 {
+        (f64::from(self.size) * scale).round() as i32
+    }
+
+    pub fn is_current_cursor_animated(&self, scale: f64) -> bool {
         match self.cursor_image() {
             CursorImageStatus::Hidden => false,
             CursorImageStatus::Surface(_) => false,
@@ -139,12 +151,11 @@ impl CursorManager {
     }
 
     /// Get named cursor for the given `icon` and `scale`.
-    pub fn get_cursor_with_name(&self, icon: CursorIcon, scale: i32) -> Option<Rc<XCursor>> {
+    pub fn get_cursor_with_name(&self, icon: CursorIcon, scale: f64) -> Option<Rc<XCursor>> {
         self.named_cursor_cache
             .borrow_mut()
-            .entry((icon, scale))
-            .or_insert_with_key(|(icon, scale)| {
-                let size = self.size as i32 * scale;
+            .entry((icon, self.physical_size(scale)))
+            .or_insert_with_key(|&(icon, size)| {
                 let mut cursor = Self::load_xcursor(&self.theme, icon.name(), size);
 
                 // Check alternative names to account for non-compliant themes.
@@ -162,7 +173,7 @@ impl CursorManager {
                 }
 
                 // The default cursor must always have a fallback.
-                if *icon == CursorIcon::Default && cursor.is_err() {
+                if icon == CursorIcon::Default && cursor.is_err() {
                     cursor = Ok(Self::fallback_cursor());
                 }
 
@@ -172,7 +183,7 @@ impl CursorManager {
     }
 
     /// Get default cursor.
-    pub fn get_default_cursor(&self, scale: i32) -> Rc<XCursor> {
+    pub fn get_default_cursor(&self, scale: f64) -> Rc<XCursor> {
         // The default cursor always has a fallback.
         self.get_cursor_with_name(CursorIcon::Default, scale)
             .unwrap()
@@ -271,11 +282,15 @@ pub enum RenderCursor {
     },
     Named {
         icon: CursorIcon,
-        scale: i32,
+        /// Size in physical pixels that the cursor was loaded for.
+        size: i32,
+        /// Scale of the output that the cursor is rendered on.
+        scale: f64,
         cursor: Rc<XCursor>,
     },
 }
 
+/// Cursor textures by the size in physical pixels that they were loaded for.
 type TextureCache = HashMap<(CursorIcon, i32), Vec<MemoryRenderBuffer>>;
 
 #[derive(Default)]
@@ -291,23 +306,25 @@ impl CursorTextureCache {
     pub fn get(
         &self,
         icon: CursorIcon,
-        scale: i32,
+        size: i32,
         cursor: &XCursor,
         idx: usize,
     ) -> MemoryRenderBuffer {
         self.cache
             .borrow_mut()
-            .entry((icon, scale))
+            .entry((icon, size))
             .or_insert_with(|| {
                 cursor
                     .frames()
                     .iter()
                     .map(|frame| {
+                        // The frames are already at the physical size, the render element sets
+                        // the logical size.
                         MemoryRenderBuffer::from_slice(
                             &frame.pixels_rgba,
                             Fourcc::Argb8888,
                             (frame.width as i32, frame.height as i32),
-                            scale,
+                            1,
                             Transform::Normal,
                             None,
                         )
@@ -366,4 +383,11 @@ impl XCursor {
     pub fn hotspot(image: &Image) -> Point<i32, Physical> {
         (image.xhot as i32, image.yhot as i32).into()
     }
+
+    /// Get the logical size of the given `image` drawn pixel for pixel at `scale`.
+    pub fn logical_size(image: &Image, scale: f64) -> Size<i32, Logical> {
+        Size::<f64, Physical>::from((f64::from(image.width), f64::from(image.height)))
+            .to_logical(scale)
+            .to_i32_round()
+    }
 }
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use zbus::fdo;
+use zbus::interface;
+use zbus::object_server::ObjectServer;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+use crate::utils::background_permissions::{self, PermissionKind};
+
+/// `NotifyBackground` result forbidding the app to run in the background.
+const RESULT_FORBID: u32 = 0;
+/// `NotifyBackground` result allowing the app to run in the background.
+const RESULT_ALLOW: u32 = 1;
+
+/// Portal response for a request that was cancelled before the user answered.
+const RESPONSE_CANCELLED: u32 = 2;
+
+/// `EnableAutostart` flag for apps that are started through D-Bus activation.
+const AUTOSTART_FLAG_DBUS_ACTIVATABLE: u32 = 1;
+
+pub struct Background {
+    to_niri: calloop::channel::Sender<BackgroundToNiri>,
+}
+
+pub enum BackgroundToNiri {
+    /// Get the app IDs of running windows and whether they're focused.
+    GetAppState(async_channel::Sender<HashMap<String, AppState>>),
+    /// Ask the user for a permission unless they already decided.
+    RequestPermission {
+        app_id: String,
+        name: Option<String>,
+        kind: PermissionKind,
+        /// Portal request object, if the request can be cancelled.
+        handle: Option<String>,
+        reply: async_channel::Sender<bool>,
+    },
+    /// Stop waiting for the answer to a portal request.
+    CancelRequest { handle: String },
+}
+
+/// Portal request object, closed by the portal frontend when the app cancels the request.
+struct Request {
+    handle: String,
+    to_niri: calloop::channel::Sender<BackgroundToNiri>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum AppState {
+    Running = 1,
+    Active = 2,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Background")]
+impl Background {
+    async fn get_app_state(&self) -> fdo::Result<HashMap<String, OwnedValue>> {
+        let (tx, rx) = async_channel::bounded(1);
+        if let Err(err) = self.to_niri.send(BackgroundToNiri::GetAppState(tx)) {
+            warn!("error sending message to niri: {err:?}");
+            return Err(fdo::Error::Failed("internal error".to_owned()));
+        }
+
+        let apps = rx.recv().await.map_err(|err| {
+            warn!("error receiving message from niri: {err:?}");
+            fdo::Error::Failed("internal error".to_owned())
+        })?;
+
+        Ok(apps
+            .into_iter()
+            .map(|(app_id, state)| {
+                let state = OwnedValue::try_from(Value::from(state as u32)).unwrap();
+                (app_id, state)
+            })
+            .collect())
+    }
+
+    async fn notify_background(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        handle: ObjectPath<'_>,
+        app_id: String,
+        name: String,
+    ) -> fdo::Result<(u32, HashMap<String, OwnedValue>)> {
+        let request = Request {
+            handle: handle.to_string(),
+            to_niri: self.to_niri.clone(),
+        };
+        if let Err(err) = server.at(&handle, request).await {
+            warn!("error creating request object: {err:?}");
+        }
+
+        let allowed = self
+            .request_permission(
+                app_id,
+                Some(name),
+                PermissionKind::Background,
+                Some(handle.to_string()),
+            )
+            .await;
+        let _ = server.remove::<Request, _>(&handle).await;
+
+        let Some(allowed) = allowed? else {
+            return Ok((RESPONSE_CANCELLED, HashMap::new()));
+        };
+
+        let result = if allowed { RESULT_ALLOW } else { RESULT_FORBID };
+        let results = HashMap::from([(
+            String::from("result"),
+            OwnedValue::try_from(Value::from(result)).unwrap(),
+        )]);
+        Ok((0, results))
+    }
+
+    async fn enable_autostart(
+        &self,
+        app_id: String,
+        enable: bool,
+        commandline: Vec<String>,
+        flags: u32,
+    ) -> fdo::Result<bool> {
+        // Turning autostart off needs no permission.
+        let enable = enable
+            && self
+                .request_permission(app_id.clone(), None, PermissionKind::Autostart, None)
+                .await?
+                .unwrap_or(false);
+
+        let commandline = enable.then_some(&commandline[..]);
+        let dbus_activatable = flags & AUTOSTART_FLAG_DBUS_ACTIVATABLE != 0;
+        if let Err(err) =
+            background_permissions::set_autostart(&app_id, commandline, dbus_activatable)
+        {
+            warn!("error updating autostart for {app_id}: {err:?}");
+            return Ok(false);
+        }
+
+        Ok(enable)
+    }
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Request")]
+impl Request {
+    async fn close(&self) {
+        let msg = BackgroundToNiri::CancelRequest {
+            handle: self.handle.clone(),
+        };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+        }
+    }
+}
+
+impl Background {
+    pub fn new(to_niri: calloop::channel::Sender<BackgroundToNiri>) -> Self {
+        Self { to_niri }
+    }
+
+    /// Asks niri for a permission, returning `None` if the request was cancelled.
+    async fn request_permission(
+        &self,
+        app_id: String,
+        name: Option<String>,
+        kind: PermissionKind,
+        handle: Option<String>,
+    ) -> fdo::Result<Option<bool>> {
+        let (tx, rx) = async_channel::bounded(1);
+        let msg = BackgroundToNiri::RequestPermission {
+            app_id,
+            name,
+            kind,
+            handle,
+            reply: tx,
+        };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return Err(fdo::Error::Failed("internal error".to_owned()));
+        }
+
+        // niri drops the reply sender when the request is cancelled.
+        Ok(rx.recv().await.ok())
+    }
+}
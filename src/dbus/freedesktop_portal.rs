@@ -0,0 +1,24 @@
+use zbus::blocking::Connection;
+use zbus::fdo::RequestNameFlags;
+
+use super::freedesktop_background::Background;
+use super::freedesktop_settings::Settings;
+
+pub const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+/// Starts the xdg-desktop-portal backend.
+///
+/// xdg-desktop-portal finds all interfaces of a backend under one bus name, so they have to be
+/// served from the same connection.
+pub fn start(background: Background, settings: Settings) -> anyhow::Result<Connection> {
+    let conn = Connection::session()?;
+    let flags = RequestNameFlags::AllowReplacement
+        | RequestNameFlags::ReplaceExisting
+        | RequestNameFlags::DoNotQueue;
+
+    conn.object_server().at(PORTAL_PATH, background)?;
+    conn.object_server().at(PORTAL_PATH, settings)?;
+    conn.request_name_with_flags("org.freedesktop.impl.portal.desktop.niri", flags)?;
+
+    Ok(conn)
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use niri_config::Color;
+use zbus::fdo;
+use zbus::interface;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedValue, Value};
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const ACCENT_COLOR_KEY: &str = "accent-color";
+
+/// Errors that the portal frontend understands.
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.freedesktop.portal.Error")]
+pub enum PortalError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    /// The setting doesn't exist, so the frontend asks the other backends.
+    NotFound(String),
+}
+
+pub struct Settings {
+    accent_color: Option<Color>,
+}
+
+#[interface(name = "org.freedesktop.impl.portal.Settings")]
+impl Settings {
+    async fn read_all(
+        &self,
+        namespaces: Vec<String>,
+    ) -> fdo::Result<HashMap<String, HashMap<String, OwnedValue>>> {
+        let mut rv = HashMap::new();
+
+        let matches = |namespace: &str| {
+            namespaces.is_empty()
+                || namespaces
+                    .iter()
+                    .any(|pattern| match pattern.strip_suffix('*') {
+                        Some(prefix) => namespace.starts_with(prefix),
+                        None => namespace == pattern,
+                    })
+        };
+
+        if let Some(color) = self.accent_color {
+            if matches(APPEARANCE_NAMESPACE) {
+                let value = OwnedValue::try_from(accent_color_value(Some(color))).unwrap();
+                let settings = HashMap::from([(ACCENT_COLOR_KEY.to_owned(), value)]);
+                rv.insert(APPEARANCE_NAMESPACE.to_owned(), settings);
+            }
+        }
+
+        Ok(rv)
+    }
+
+    async fn read(&self, namespace: &str, key: &str) -> Result<OwnedValue, PortalError> {
+        if namespace == APPEARANCE_NAMESPACE && key == ACCENT_COLOR_KEY {
+            if let Some(color) = self.accent_color {
+                return Ok(OwnedValue::try_from(accent_color_value(Some(color))).unwrap());
+            }
+        }
+
+        // Let the other portal backends answer.
+        Err(PortalError::NotFound(format!(
+            "setting {namespace}.{key} not found"
+        )))
+    }
+
+    #[zbus(signal)]
+    async fn setting_changed(
+        ctxt: &SignalEmitter<'_>,
+        namespace: &str,
+        key: &str,
+        value: Value<'_>,
+    ) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    async fn version(&self) -> u32 {
+        1
+    }
+}
+
+impl Settings {
+    pub fn new(accent_color: Option<Color>) -> Self {
+        Self { accent_color }
+    }
+
+    pub fn set_accent_color(&mut self, accent_color: Option<Color>) {
+        self.accent_color = accent_color;
+    }
+
+    pub async fn accent_color_changed(
+        ctxt: &SignalEmitter<'_>,
+        accent_color: Option<Color>,
+    ) -> zbus::Result<()> {
+        let value = accent_color_value(accent_color);
+        Self::setting_changed(ctxt, APPEARANCE_NAMESPACE, ACCENT_COLOR_KEY, value).await
+    }
+}
+
+/// Converts the accent color to its portal representation.
+///
+/// The portal has no alpha, and out-of-range components mean that the accent color is unset.
+fn accent_color_value(color: Option<Color>) -> Value<'static> {
+    let (r, g, b) = match color {
+        Some(color) => (f64::from(color.r), f64::from(color.g), f64::from(color.b)),
+        None => (-1., -1., -1.),
+    };
+    Value::from((r, g, b))
+}
+
+#[cfg(test)]
+mod tests {
+    use zbus::DBusError as _;
+
+    use super::*;
+
+    #[test]
+    fn unknown_settings_are_not_found() {
+        let settings = Settings::new(None);
+        let err =
+            async_io::block_on(settings.read(APPEARANCE_NAMESPACE, ACCENT_COLOR_KEY)).unwrap_err();
+        assert_eq!(err.name().as_str(), "org.freedesktop.portal.Error.NotFound");
+
+        let settings = Settings::new(Some(Color::new_unpremul(1., 0., 0., 1.)));
+        let err = async_io::block_on(settings.read("org.gnome.desktop.interface", "gtk-theme"))
+            .unwrap_err();
+        assert_eq!(err.name().as_str(), "org.freedesktop.portal.Error.NotFound");
+
+        let value = async_io::block_on(settings.read(APPEARANCE_NAMESPACE, ACCENT_COLOR_KEY));
+        assert!(value.is_ok());
+    }
+}
@@ -4,9 +4,12 @@ use zbus::object_server::Interface;
 use crate::niri::State;
 
 pub mod freedesktop_a11y;
+pub mod freedesktop_background;
 pub mod freedesktop_locale1;
 pub mod freedesktop_login1;
+pub mod freedesktop_portal;
 pub mod freedesktop_screensaver;
+pub mod freedesktop_settings;
 pub mod gnome_shell_introspect;
 pub mod gnome_shell_screenshot;
 pub mod mutter_display_config;
@@ -18,7 +21,9 @@ pub mod mutter_screen_cast;
 use mutter_screen_cast::ScreenCast;
 
 use self::freedesktop_a11y::KeyboardMonitor;
+use self::freedesktop_background::Background;
 use self::freedesktop_screensaver::ScreenSaver;
+use self::freedesktop_settings::Settings;
 use self::gnome_shell_introspect::Introspect;
 use self::mutter_display_config::DisplayConfig;
 use self::mutter_service_channel::ServiceChannel;
@@ -39,6 +44,7 @@ pub struct DBusServers {
     pub conn_login1: Option<Connection>,
     pub conn_locale1: Option<Connection>,
     pub conn_keyboard_monitor: Option<Connection>,
+    pub conn_portal: Option<Connection>,
 }
 
 impl DBusServers {
@@ -131,6 +137,24 @@ impl DBusServers {
                 dbus.conn_screen_cast = try_start(screen_cast);
             }
 
+            let (to_niri, from_background) = calloop::channel::channel();
+            niri.event_loop
+                .insert_source(from_background, move |event, _, state| match event {
+                    calloop::channel::Event::Msg(msg) => state.on_background_msg(msg),
+                    calloop::channel::Event::Closed => (),
+                })
+                .unwrap();
+            let background = Background::new(to_niri);
+            let settings = Settings::new(config.accent_color);
+            match freedesktop_portal::start(background, settings) {
+                Ok(conn) => {
+                    dbus.conn_portal = Some(conn);
+                }
+                Err(err) => {
+                    warn!("error starting xdg-desktop-portal backend: {err:?}");
+                }
+            }
+
             let keyboard_monitor = KeyboardMonitor::new();
             if let Some(x) = try_start(keyboard_monitor.clone()) {
                 dbus.conn_keyboard_monitor = Some(x);
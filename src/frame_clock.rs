@@ -8,6 +8,8 @@ pub struct FrameClock {
     last_presentation_time: Option<Duration>,
     refresh_interval_ns: Option<NonZeroU64>,
     vrr: bool,
+    /// Maximum number of frames per second to present, below the refresh rate.
+    max_render_rate: Option<f64>,
 }
 
 impl FrameClock {
@@ -23,6 +25,7 @@ impl FrameClock {
             last_presentation_time: None,
             refresh_interval_ns,
             vrr,
+            max_render_rate: None,
         }
     }
 
@@ -44,6 +47,14 @@ impl FrameClock {
         self.vrr
     }
 
+    pub fn set_max_render_rate(&mut self, max_render_rate: Option<f64>) {
+        self.max_render_rate = max_render_rate;
+    }
+
+    pub fn max_render_rate(&self) -> Option<f64> {
+        self.max_render_rate
+    }
+
     pub fn presented(&mut self, presentation_time: Duration) {
         if presentation_time.is_zero() {
             // Not interested in these.
@@ -89,10 +100,47 @@ impl FrameClock {
 
         // If VRR is enabled and more than one frame passed since last presentation, assume that we
         // can present immediately.
-        if self.vrr && to_next_ns > refresh_interval_ns {
+        let target = if self.vrr && to_next_ns > refresh_interval_ns {
             now
         } else {
             last_presentation_time + Duration::from_nanos(to_next_ns)
-        }
+        };
+
+        // Stay under the max render rate by skipping VBlanks.
+        let Some(max_render_rate) = self.max_render_rate else {
+            return target;
+        };
+        let min_interval = if self.vrr {
+            Duration::from_secs_f64(1. / max_render_rate)
+        } else {
+            // Present on every Nth VBlank. The small bias keeps e.g. 60 FPS on a 240 Hz panel at
+            // every 4th VBlank despite the refresh rate not being exactly 240 Hz.
+            let refresh_rate = 1_000_000_000. / refresh_interval_ns as f64;
+            let frames = (refresh_rate / max_render_rate - 0.01).ceil().max(1.) as u64;
+            Duration::from_nanos(frames * refresh_interval_ns)
+        };
+        target.max(last_presentation_time + min_interval)
+    }
+
+    /// Returns when to render the next frame if rendering now would go over the max render rate.
+    pub fn render_delayed_until(&self) -> Option<Duration> {
+        self.max_render_rate?;
+        let refresh_interval = self.refresh_interval()?;
+        let last_presentation_time = self.last_presentation_time?;
+
+        let target = self.next_presentation_time();
+        let render_at = if self.vrr {
+            // With VRR, a queued frame is presented right away, but no sooner than one refresh
+            // cycle after the previous one.
+            if target <= last_presentation_time + refresh_interval {
+                return None;
+            }
+            target
+        } else {
+            // Otherwise, it is presented at the next VBlank.
+            target.saturating_sub(refresh_interval)
+        };
+
+        (get_monotonic_time() < render_at).then_some(render_at)
     }
 }
@@ -1,5 +1,6 @@
 use std::collections::hash_map::Entry;
 
+use niri_config::FocusOnActivation;
 use niri_ipc::PositionChange;
 use smithay::backend::renderer::utils::on_commit_buffer_handler;
 use smithay::input::pointer::{CursorImageStatus, CursorImageSurfaceData};
@@ -19,12 +20,17 @@ use smithay::wayland::shm::{ShmHandler, ShmState};
 use smithay::{delegate_compositor, delegate_shm};
 
 use super::xdg_shell::add_mapped_toplevel_pre_commit_hook;
-use crate::handlers::XDG_ACTIVATION_TOKEN_TIMEOUT;
+use crate::handlers::{
+    is_valid_activation_token, should_focus_on_activation, spawn_target_workspace,
+};
 use crate::layout::{ActivateWindow, AddWindowTarget, LayoutElement as _};
 use crate::niri::{CastTarget, ClientState, LockState, State};
 use crate::utils::transaction::Transaction;
-use crate::utils::{is_mapped, send_scale_transform};
-use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped};
+use crate::utils::{get_monotonic_time, is_mapped, send_scale_transform};
+use crate::utils::{session_snapshot, workspace_export};
+use crate::window::{
+    is_picture_in_picture, InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped,
+};
 
 impl CompositorHandler for State {
     fn compositor_state(&mut self) -> &mut CompositorState {
@@ -145,11 +151,25 @@ impl CompositorHandler for State {
                         )
                     };
 
+                    // A window from spawn-on-workspace that activated before mapping goes to its
+                    // target workspace.
+                    let workspace_id = activation_token_data
+                        .as_ref()
+                        .and_then(spawn_target_workspace)
+                        .and_then(|n| self.niri.layout.find_workspace_by_name(n))
+                        .map(|(_, ws)| ws.id())
+                        .or(workspace_id);
+
                     // The GTK about dialog sets min/max size after the initial configure but
                     // before mapping, so we need to compute open_floating at the last possible
                     // moment, that is here.
                     let is_floating = rules.compute_open_floating(toplevel);
 
+                    // Browser picture-in-picture windows open floating in a corner.
+                    let is_picture_in_picture = !self.niri.config.borrow().picture_in_picture.off
+                        && is_picture_in_picture(toplevel);
+                    let is_floating = is_floating || is_picture_in_picture;
+
                     // Figure out if we should activate the window.
                     let activate = rules.open_focused.map(|focus| {
                         if focus {
@@ -159,19 +179,27 @@ impl CompositorHandler for State {
                         }
                     });
                     let activate = activate.unwrap_or_else(|| {
-                        // Check the token timestamp again in case the window took a while between
+                        // Check the token again in case the window took a while between
                         // requesting activation and mapping.
-                        let token = activation_token_data.filter(|token| {
-                            token.timestamp.elapsed() < XDG_ACTIVATION_TOKEN_TIMEOUT
+                        let focus = activation_token_data.as_ref().map(|token| {
+                            let valid = is_valid_activation_token(token);
+                            should_focus_on_activation(rules.focus_on_activation, valid)
                         });
-                        if token.is_some() {
-                            ActivateWindow::Yes
-                        } else {
-                            let config = self.niri.config.borrow();
-                            if config.debug.strict_new_window_focus_policy {
+                        match focus {
+                            Some(true) => ActivateWindow::Yes,
+                            // Don't let the activation request focus the new window either.
+                            Some(false)
+                                if rules.focus_on_activation == Some(FocusOnActivation::Never) =>
+                            {
                                 ActivateWindow::No
-                            } else {
-                                ActivateWindow::Smart
+                            }
+                            _ => {
+                                let config = self.niri.config.borrow();
+                                if config.debug.strict_new_window_focus_policy {
+                                    ActivateWindow::No
+                                } else {
+                                    ActivateWindow::Smart
+                                }
                             }
                         }
                     });
@@ -195,12 +223,33 @@ impl CompositorHandler for State {
                     // The mapped pre-commit hook deals with dma-bufs on its own.
                     self.remove_default_dmabuf_pre_commit_hook(surface);
                     let hook = add_mapped_toplevel_pre_commit_hook(toplevel);
-                    let mapped = Mapped::new(window, rules, hook);
+                    let mut mapped = Mapped::new(window, rules, hook);
                     let window = mapped.window.clone();
 
+                    let is_x11 = self
+                        .niri
+                        .satellite
+                        .as_ref()
+                        .is_some_and(|satellite| satellite.is_client(mapped.credentials()));
+                    mapped.set_is_x11(is_x11);
+                    mapped.set_scale_x11_to_output(
+                        self.niri.config.borrow().xwayland_satellite.scale_to_output,
+                    );
+
+                    // Windows for a workspace import go to the imported workspace, and windows of a
+                    // crashed session go back to where they were.
+                    let import_workspace_id = if parent.is_none() {
+                        workspace_export::claim_window(&mut self.niri, &mapped)
+                            .or_else(|| session_snapshot::claim_window(&mut self.niri, &mapped))
+                    } else {
+                        None
+                    };
+
                     let target = if let Some(p) = &parent {
                         // Open dialogs next to their parent window.
                         AddWindowTarget::NextTo(p)
+                    } else if let Some(id) = import_workspace_id {
+                        AddWindowTarget::Workspace(id)
                     } else if let Some(id) = workspace_id {
                         AddWindowTarget::Workspace(id)
                     } else if let Some(output) = &output {
@@ -208,6 +257,16 @@ impl CompositorHandler for State {
                     } else {
                         AddWindowTarget::Auto
                     };
+                    // Floating placement can depend on where the pointer is.
+                    let pointer_pos = self.niri.seat.get_pointer().unwrap().current_location();
+                    let pointer = self
+                        .niri
+                        .output_under(pointer_pos)
+                        .map(|(output, pos)| (output.clone(), pos));
+                    self.niri.layout.set_pointer_pos_for_placement(
+                        pointer.as_ref().map(|(output, pos)| (output, *pos)),
+                    );
+
                     let output = self.niri.layout.add_window(
                         mapped,
                         target,
@@ -231,6 +290,13 @@ impl CompositorHandler for State {
                         error!("layout is missing the window that we just added");
                     }
 
+                    if is_picture_in_picture {
+                        let corner = self.niri.picture_in_picture_corner(&window);
+                        self.niri
+                            .layout
+                            .set_window_picture_in_picture(&window, corner);
+                    }
+
                     if let Some(output) = output {
                         self.niri.layout.start_open_animation_for_window(&window);
 
@@ -243,6 +309,9 @@ impl CompositorHandler for State {
 
                         self.niri.queue_redraw(&output);
                     }
+
+                    workspace_export::window_opened(self);
+                    session_snapshot::window_opened(self);
                     return;
                 }
 
@@ -292,6 +361,7 @@ impl CompositorHandler for State {
                         .stop_casts_for_target(CastTarget::Window { id: id.get() });
 
                     self.niri.window_mru_ui.remove_window(id);
+                    self.niri.remember_picture_in_picture_corner(&window);
                     self.niri.layout.remove_window(&window, transaction.clone());
                     self.add_default_dmabuf_pre_commit_hook(surface);
 
@@ -342,6 +412,11 @@ impl CompositorHandler for State {
                 self.niri.window_mru_ui.update_window(&self.niri.layout, id);
                 self.niri.layout.update_window(&window, serial);
 
+                if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) {
+                    mapped.mark_activity(serial);
+                    mapped.record_commit(get_monotonic_time());
+                }
+
                 // Move the toplevel according to the attach offset.
                 if let Some(delta) = buffer_delta {
                     if delta.x != 0 || delta.y != 0 {
@@ -378,6 +453,12 @@ impl CompositorHandler for State {
                 .window_mru_ui
                 .update_window(&self.niri.layout, mapped.id());
             self.niri.layout.update_window(&window, None);
+
+            // Video players often show frames in a subsurface.
+            if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&root_surface) {
+                mapped.record_commit(get_monotonic_time());
+            }
+
             if let Some(output) = output {
                 self.niri.queue_redraw(&output);
                 self.niri.queue_redraw_mru_output();
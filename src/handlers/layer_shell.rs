@@ -12,7 +12,7 @@ use smithay::wayland::shell::xdg::PopupSurface;
 
 use crate::layer::{MappedLayer, ResolvedLayerRules};
 use crate::niri::State;
-use crate::utils::{is_mapped, output_size, send_scale_transform};
+use crate::utils::{is_mapped, output_size, send_scale_transform, watchdog};
 
 impl WlrLayerShellHandler for State {
     fn shell_state(&mut self) -> &mut WlrLayerShellState {
@@ -38,6 +38,7 @@ impl WlrLayerShellHandler for State {
         };
 
         let wl_surface = surface.wl_surface().clone();
+        watchdog::layer_surface_created(self, &wl_surface);
         let is_new = self.niri.unmapped_layer_surfaces.insert(wl_surface);
         assert!(is_new);
 
@@ -49,6 +50,7 @@ impl WlrLayerShellHandler for State {
     fn layer_destroyed(&mut self, surface: WlrLayerSurface) {
         let wl_surface = surface.wl_surface();
         self.niri.unmapped_layer_surfaces.remove(wl_surface);
+        watchdog::layer_surface_destroyed(self, wl_surface);
 
         let output = if let Some((output, mut map, layer)) =
             self.niri.layout.outputs().find_map(|o| {
@@ -9,6 +9,8 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use niri_config::workspace::WorkspaceName;
+use niri_config::{FocusOnActivation, OutputName};
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::drm::DrmNode;
 use smithay::backend::input::{InputEvent, TabletToolDescriptor};
@@ -61,8 +63,8 @@ use smithay::wayland::xdg_activation::{
     XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
 };
 use smithay::{
-    delegate_cursor_shape, delegate_data_control, delegate_data_device, delegate_dmabuf,
-    delegate_drm_lease, delegate_ext_data_control, delegate_fractional_scale,
+    delegate_content_type, delegate_cursor_shape, delegate_data_control, delegate_data_device,
+    delegate_dmabuf, delegate_drm_lease, delegate_ext_data_control, delegate_fractional_scale,
     delegate_idle_inhibit, delegate_idle_notify, delegate_input_method_manager,
     delegate_keyboard_shortcuts_inhibit, delegate_output, delegate_pointer_constraints,
     delegate_pointer_gestures, delegate_presentation, delegate_primary_selection,
@@ -88,7 +90,7 @@ use crate::protocols::virtual_pointer::{
     VirtualPointerInputBackend, VirtualPointerManagerState, VirtualPointerMotionAbsoluteEvent,
     VirtualPointerMotionEvent,
 };
-use crate::utils::{output_size, send_scale_transform};
+use crate::utils::{night_light, output_matches_name, output_size, send_scale_transform};
 use crate::{
     delegate_ext_workspace, delegate_foreign_toplevel, delegate_gamma_control,
     delegate_mutter_x11_interop, delegate_output_management, delegate_screencopy,
@@ -489,6 +491,7 @@ impl SecurityContextHandler for State {
                     client,
                     restricted: true,
                     credentials_unknown: false,
+                    security_context: Some(context.clone()),
                 });
             })
             .unwrap();
@@ -574,6 +577,22 @@ impl ForeignToplevelHandler for State {
             self.niri.layout.set_maximized(&window, false);
         }
     }
+
+    fn set_minimized(&mut self, wl_surface: WlSurface) {
+        if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&wl_surface) {
+            let window = mapped.window.clone();
+            self.niri.layout.minimize_window(&window);
+            self.niri.queue_redraw_all();
+        }
+    }
+
+    fn unset_minimized(&mut self, wl_surface: WlSurface) {
+        if let Some((mapped, _)) = self.niri.layout.find_window_and_output(&wl_surface) {
+            let window = mapped.window.clone();
+            self.niri.layout.unminimize_window(&window);
+            self.niri.queue_redraw_all();
+        }
+    }
 }
 delegate_foreign_toplevel!(State);
 
@@ -610,6 +629,25 @@ impl ExtWorkspaceHandler for State {
                 .move_workspace_to_output_by_id(old_idx, old_output, &output);
         }
     }
+
+    fn create_workspace(&mut self, name: String, output: Output) {
+        if name.is_empty() {
+            return;
+        }
+
+        let output_name = output.user_data().get::<OutputName>().unwrap();
+        let ws_config = niri_config::Workspace {
+            name: WorkspaceName(name),
+            open_on_output: Some(output_name.connector.clone()),
+            layout: None,
+        };
+        // Does nothing if a workspace with this name already exists.
+        self.niri.layout.ensure_named_workspace(&ws_config);
+    }
+
+    fn remove_workspace(&mut self, id: WorkspaceId) {
+        self.niri.layout.unname_workspace_by_id(id);
+    }
 }
 delegate_ext_workspace!(State);
 
@@ -711,12 +749,19 @@ delegate_drm_lease!(State);
 
 delegate_viewporter!(State);
 
+delegate_content_type!(State);
+
 impl GammaControlHandler for State {
     fn gamma_control_manager_state(&mut self) -> &mut GammaControlManagerState {
         &mut self.niri.gamma_control_manager_state
     }
 
     fn get_gamma_size(&mut self, output: &Output) -> Option<u32> {
+        // Virtual outputs have no gamma ramps.
+        if self.niri.virtual_outputs.contains(output) {
+            return None;
+        }
+
         match self.backend.tty().get_gamma_size(output) {
             Ok(0) => None, // Setting gamma is not supported.
             Ok(size) => Some(size),
@@ -731,6 +776,16 @@ impl GammaControlHandler for State {
     }
 
     fn set_gamma(&mut self, output: &Output, ramp: Option<Vec<u16>>) -> Option<()> {
+        // When the client lets go of the gamma, go back to the night light.
+        let temperature = self.niri.night_light_temperature;
+        let ramp = ramp.or_else(|| {
+            if temperature == night_light::NEUTRAL_TEMPERATURE {
+                return None;
+            }
+            let size = self.backend.tty().get_gamma_size(output).ok()?;
+            Some(night_light::gamma_ramp(size, temperature))
+        });
+
         match self.backend.tty().set_gamma(output, ramp) {
             Ok(()) => Some(()),
             Err(err) => {
@@ -744,6 +799,57 @@ delegate_gamma_control!(State);
 
 struct UrgentOnlyMarker;
 
+/// Marks tokens created with a serial that doesn't match recent input.
+struct InvalidSerialMarker;
+
+/// Marks tokens created by `spawn-on-workspace` with the name of the target workspace.
+struct SpawnOnWorkspaceMarker(String);
+
+/// Marks the token so that the window activating with it opens on the named workspace.
+pub fn mark_spawn_on_workspace(data: &XdgActivationTokenData, workspace: String) {
+    data.user_data
+        .insert_if_missing(|| SpawnOnWorkspaceMarker(workspace));
+}
+
+/// Returns the workspace that the window activating with this token should open on.
+pub fn spawn_target_workspace(data: &XdgActivationTokenData) -> Option<&str> {
+    data.user_data
+        .get::<SpawnOnWorkspaceMarker>()
+        .map(|marker| marker.0.as_str())
+}
+
+/// Marks tokens created for startup commands with the name of the target output.
+struct SpawnOnOutputMarker(String);
+
+/// Marks the token so that the window activating with it opens on the named output.
+pub fn mark_spawn_on_output(data: &XdgActivationTokenData, output: String) {
+    data.user_data
+        .insert_if_missing(|| SpawnOnOutputMarker(output));
+}
+
+/// Returns the output that the window activating with this token should open on.
+pub fn spawn_target_output(data: &XdgActivationTokenData) -> Option<&str> {
+    data.user_data
+        .get::<SpawnOnOutputMarker>()
+        .map(|marker| marker.0.as_str())
+}
+
+/// Returns whether the token came from recent user input.
+pub fn is_valid_activation_token(data: &XdgActivationTokenData) -> bool {
+    data.timestamp.elapsed() < XDG_ACTIVATION_TOKEN_TIMEOUT
+        && data.user_data.get::<UrgentOnlyMarker>().is_none()
+        && data.user_data.get::<InvalidSerialMarker>().is_none()
+}
+
+/// Returns whether an activation request should focus the window, rather than mark it urgent.
+pub fn should_focus_on_activation(policy: Option<FocusOnActivation>, valid_token: bool) -> bool {
+    match policy {
+        Some(FocusOnActivation::Always) => true,
+        Some(FocusOnActivation::Never) => false,
+        Some(FocusOnActivation::Smart) | None => valid_token,
+    }
+}
+
 impl XdgActivationHandler for State {
     fn activation_state(&mut self) -> &mut XdgActivationState {
         &mut self.niri.activation_state
@@ -786,7 +892,10 @@ impl XdgActivationHandler for State {
             return true;
         }
 
-        false
+        // Keep the token so that activating with it marks the window urgent, or focuses it if a
+        // window rule says so.
+        data.user_data.insert_if_missing(|| InvalidSerialMarker);
+        true
     }
 
     fn request_activation(
@@ -795,20 +904,71 @@ impl XdgActivationHandler for State {
         token_data: XdgActivationTokenData,
         surface: WlSurface,
     ) {
-        if token_data.timestamp.elapsed() < XDG_ACTIVATION_TOKEN_TIMEOUT {
-            if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&surface) {
-                let window = mapped.window.clone();
-                if token_data.user_data.get::<UrgentOnlyMarker>().is_some() {
-                    mapped.set_urgent(true);
-                    self.niri.queue_redraw_all();
+        let valid = is_valid_activation_token(&token_data);
+
+        // Windows from spawn-on-workspace commonly activate right after mapping, so this is the
+        // point where they move over to their target workspace.
+        let is_fresh = token_data.timestamp.elapsed() < XDG_ACTIVATION_TOKEN_TIMEOUT;
+        let target = spawn_target_workspace(&token_data)
+            .filter(|_| is_fresh)
+            .and_then(|name| {
+                self.niri
+                    .find_output_and_workspace_index(niri_config::WorkspaceReference::Name(
+                        name.to_owned(),
+                    ))
+            });
+        if let Some((output, index)) = target {
+            let window = self
+                .niri
+                .layout
+                .find_window_and_output(&surface)
+                .map(|(mapped, _)| mapped.window.clone());
+            if let Some(window) = window {
+                if let Some(output) = output {
+                    self.niri.layout.move_to_output(
+                        Some(&window),
+                        &output,
+                        Some(index),
+                        ActivateWindow::No,
+                    );
                 } else {
-                    self.niri.layout.activate_window(&window);
-                    self.niri.layer_shell_on_demand_focus = None;
-                    self.niri.queue_redraw_all();
+                    self.niri
+                        .layout
+                        .move_to_workspace(Some(&window), index, ActivateWindow::No);
                 }
-            } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(&surface) {
-                unmapped.activation_token_data = Some(token_data);
             }
+        } else if let Some(name) = spawn_target_output(&token_data).filter(|_| is_fresh) {
+            // Startup commands can target an output rather than a workspace.
+            let output = self
+                .niri
+                .global_space
+                .outputs()
+                .find(|output| output_matches_name(output, name))
+                .cloned();
+            let window = self
+                .niri
+                .layout
+                .find_window_and_output(&surface)
+                .filter(|(_, current)| current.as_ref() != output.as_ref())
+                .map(|(mapped, _)| mapped.window.clone());
+            if let (Some(output), Some(window)) = (output, window) {
+                self.niri
+                    .layout
+                    .move_to_output(Some(&window), &output, None, ActivateWindow::No);
+            }
+        }
+
+        if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&surface) {
+            let window = mapped.window.clone();
+            if should_focus_on_activation(mapped.rules().focus_on_activation, valid) {
+                self.niri.layout.activate_window(&window);
+                self.niri.layer_shell_on_demand_focus = None;
+            } else {
+                mapped.set_urgent(true);
+            }
+            self.niri.queue_redraw_all();
+        } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(&surface) {
+            unmapped.activation_token_data = Some(token_data);
         }
 
         self.niri.activation_state.remove_token(&token);
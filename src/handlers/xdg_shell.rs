@@ -28,7 +28,7 @@ use smithay::wayland::shell::kde::decoration::{KdeDecorationHandler, KdeDecorati
 use smithay::wayland::shell::wlr_layer::{self, Layer};
 use smithay::wayland::shell::xdg::decoration::XdgDecorationHandler;
 use smithay::wayland::shell::xdg::{
-    PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
+    PopupSurface, PositionerState, ShellClient, ToplevelSurface, XdgShellHandler, XdgShellState,
     XdgToplevelSurfaceData,
 };
 use smithay::wayland::xdg_foreign::{XdgForeignHandler, XdgForeignState};
@@ -37,6 +37,7 @@ use smithay::{
 };
 use tracing::field::Empty;
 
+use crate::handlers::{spawn_target_output, spawn_target_workspace};
 use crate::input::move_grab::MoveGrab;
 use crate::input::resize_grab::ResizeGrab;
 use crate::input::touch_resize_grab::TouchResizeGrab;
@@ -600,6 +601,21 @@ impl XdgShellHandler for State {
         }
     }
 
+    fn minimize_request(&mut self, toplevel: ToplevelSurface) {
+        // There's no minimized state in xdg-toplevel, so nothing to configure here. Unmapped
+        // windows can't be minimized.
+        if let Some((mapped, _)) = self
+            .niri
+            .layout
+            .find_window_and_output(toplevel.wl_surface())
+        {
+            let window = mapped.window.clone();
+            self.niri.layout.minimize_window(&window);
+            // FIXME: granular
+            self.niri.queue_redraw_all();
+        }
+    }
+
     fn fullscreen_request(
         &mut self,
         toplevel: ToplevelSurface,
@@ -792,6 +808,7 @@ impl XdgShellHandler for State {
         let was_active = active_window == Some(&window);
 
         self.niri.window_mru_ui.remove_window(id);
+        self.niri.remember_picture_in_picture_corner(&window);
         self.niri.layout.remove_window(&window, transaction.clone());
         self.add_default_dmabuf_pre_commit_hook(surface.wl_surface());
 
@@ -818,10 +835,24 @@ impl XdgShellHandler for State {
     }
 
     fn app_id_changed(&mut self, toplevel: ToplevelSurface) {
+        if let Some((mapped, _)) = self
+            .niri
+            .layout
+            .find_window_and_output_mut(toplevel.wl_surface())
+        {
+            mapped.on_title_changed();
+        }
         self.update_window_rules(&toplevel);
     }
 
     fn title_changed(&mut self, toplevel: ToplevelSurface) {
+        if let Some((mapped, _)) = self
+            .niri
+            .layout
+            .find_window_and_output_mut(toplevel.wl_surface())
+        {
+            mapped.on_title_changed();
+        }
         self.update_window_rules(&toplevel);
     }
 
@@ -840,6 +871,10 @@ impl XdgShellHandler for State {
             }
         }
     }
+
+    fn client_pong(&mut self, client: ShellClient) {
+        self.niri.on_client_pong(&client);
+    }
 }
 
 delegate_xdg_shell!(State);
@@ -957,12 +992,30 @@ impl State {
         };
 
         let config = self.niri.config.borrow();
-        let rules = ResolvedWindowRules::compute(
+        let mut rules = ResolvedWindowRules::compute(
             &config.window_rules,
             WindowRef::Unmapped(unmapped),
             self.niri.is_at_startup,
         );
 
+        // A window from spawn-on-workspace that activated early opens on its target workspace.
+        if let Some(name) = unmapped
+            .activation_token_data
+            .as_ref()
+            .and_then(spawn_target_workspace)
+        {
+            rules.open_on_workspace = Some(name.to_owned());
+        }
+
+        // Likewise for startup commands targeting an output.
+        if let Some(name) = unmapped
+            .activation_token_data
+            .as_ref()
+            .and_then(spawn_target_output)
+        {
+            rules.open_on_output = Some(name.to_owned());
+        }
+
         let Unmapped { window, state, .. } = unmapped;
 
         let InitialConfigureState::NotConfigured {
@@ -1308,10 +1361,18 @@ impl State {
         {
             if mapped.recompute_window_rules(window_rules, self.niri.is_at_startup) {
                 drop(config);
+                let floating = mapped.take_floating_change_from_rules();
                 let output = output.cloned();
                 let window = mapped.window.clone();
                 self.niri.layout.update_window(&window, None);
 
+                // Rules with match-on-change re-apply open-floating to live windows.
+                if let Some(floating) = floating {
+                    self.niri
+                        .layout
+                        .set_window_floating(Some(&window), floating);
+                }
+
                 if let Some(output) = output {
                     self.niri.queue_redraw(&output);
                 }
@@ -0,0 +1,168 @@
+//! Detection of binds that can't trigger because of other binds.
+//!
+//! Exact duplicates within a binds section are config errors, but some conflicts only show up
+//! once `Mod` is resolved to the actual modifier, and some come from keys that niri handles by
+//! itself.
+
+use std::collections::HashMap;
+
+use niri_config::{Bind, Config, Key, ModKey, Modifiers, Trigger};
+use niri_ipc::{BindConflict, BindConflictKind};
+use smithay::input::keyboard::Keysym;
+
+/// Keys that the resize mode takes over, see `hardcoded_resize_mode_bind()`.
+const RESIZE_MODE_KEYS: &[Keysym] = &[
+    Keysym::Escape,
+    Keysym::Return,
+    Keysym::KP_Enter,
+    Keysym::Left,
+    Keysym::Right,
+    Keysym::Up,
+    Keysym::Down,
+    Keysym::h,
+    Keysym::j,
+    Keysym::k,
+    Keysym::l,
+];
+
+/// Finds the binds that are shadowed by something else, the default mode first.
+///
+/// Binds of the recent windows switcher aren't checked since overriding them with general binds
+/// is expected.
+pub fn find(config: &Config, mod_key: ModKey) -> Vec<BindConflict> {
+    let mut rv = Vec::new();
+    find_in_mode(&mut rv, "default", &config.binds.0, mod_key);
+
+    let mut modes: Vec<_> = config.modes.iter().collect();
+    modes.sort_by_key(|(name, _)| *name);
+    for (name, binds) in modes {
+        find_in_mode(&mut rv, name, &binds.0, mod_key);
+    }
+
+    rv
+}
+
+fn find_in_mode<'a>(
+    rv: &mut Vec<BindConflict>,
+    mode: &str,
+    binds: impl IntoIterator<Item = &'a Bind>,
+    mod_key: ModKey,
+) {
+    // Binds are matched in order, so the first bind for a key combination wins.
+    let mut seen: HashMap<(Trigger, Modifiers), Key> = HashMap::new();
+
+    for bind in binds {
+        let key = bind.key;
+        let held = (key.trigger, held_modifiers(key, mod_key));
+
+        let kind = if let Some(earlier) = seen.get(&held) {
+            BindConflictKind::Duplicate {
+                shadowed_by: earlier.to_string(),
+            }
+        } else {
+            seen.insert(held, key);
+
+            if !is_taken_by_resize_mode(key, mod_key) {
+                continue;
+            }
+            BindConflictKind::ResizeMode
+        };
+
+        rv.push(BindConflict {
+            mode: mode.to_owned(),
+            key: key.to_string(),
+            kind,
+        });
+    }
+}
+
+/// Counts the conflicting binds that never trigger.
+pub fn count_unreachable(conflicts: &[BindConflict]) -> usize {
+    conflicts
+        .iter()
+        .filter(|conflict| matches!(conflict.kind, BindConflictKind::Duplicate { .. }))
+        .count()
+}
+
+/// Returns the modifiers that trigger the key, with `Mod` resolved.
+fn held_modifiers(key: Key, mod_key: ModKey) -> Modifiers {
+    let mut modifiers = key.modifiers;
+    if modifiers.contains(Modifiers::COMPOSITOR) {
+        modifiers.remove(Modifiers::COMPOSITOR);
+        modifiers |= mod_key.to_modifiers();
+    }
+    modifiers
+}
+
+fn is_taken_by_resize_mode(key: Key, mod_key: ModKey) -> bool {
+    let Trigger::Keysym(keysym) = key.trigger else {
+        return false;
+    };
+
+    // Shift makes the resize mode steps finer, other modifiers leave the keys to the binds.
+    (held_modifiers(key, mod_key) - Modifiers::SHIFT).is_empty()
+        && RESIZE_MODE_KEYS.contains(&keysym)
+}
+
+#[cfg(test)]
+mod tests {
+    use smithay::input::keyboard::ModifiersState;
+
+    use super::*;
+    use crate::input::hardcoded_resize_mode_bind;
+
+    fn conflicts(text: &str) -> Vec<BindConflict> {
+        let config = Config::parse_mem(text).unwrap();
+        find(&config, ModKey::Super)
+    }
+
+    #[test]
+    fn mod_and_spelled_out_modifier_conflict() {
+        let conflicts = conflicts(
+            r#"
+            binds {
+                Mod+T { spawn "alacritty"; }
+                Super+T { spawn "foot"; }
+                Alt+T { spawn "kitty"; }
+            }
+            "#,
+        );
+        assert_eq!(
+            conflicts,
+            [BindConflict {
+                mode: String::from("default"),
+                key: String::from("Super+t"),
+                kind: BindConflictKind::Duplicate {
+                    shadowed_by: String::from("Mod+t"),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn resize_mode_takes_unmodified_keys() {
+        let conflicts = conflicts(
+            r#"
+            mode "launch" {
+                Shift+Left { focus-column-left; }
+                Mod+Left { focus-column-left; }
+            }
+            "#,
+        );
+        assert_eq!(
+            conflicts,
+            [BindConflict {
+                mode: String::from("launch"),
+                key: String::from("Shift+Left"),
+                kind: BindConflictKind::ResizeMode,
+            }]
+        );
+    }
+
+    #[test]
+    fn resize_mode_keys_match_the_hardcoded_binds() {
+        for keysym in RESIZE_MODE_KEYS {
+            assert!(hardcoded_resize_mode_bind(*keysym, ModifiersState::default()).is_some());
+        }
+    }
+}
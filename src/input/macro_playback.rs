@@ -0,0 +1,245 @@
+//! Playback of macros sent over IPC.
+//!
+//! A macro is a list of actions, key presses and waits. Key presses go through the keyboard like
+//! real ones: they update the modifier state, trigger binds of the current binding mode, and reach
+//! the focused window when no bind matches.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::RegistrationToken;
+use niri_config::{Action, Key, ModKey, Modifiers, Trigger};
+use smithay::backend::input::KeyState;
+use smithay::input::keyboard::{Keycode, Keysym};
+
+use crate::niri::State;
+use crate::utils::get_monotonic_time;
+
+pub struct MacroPlayback {
+    steps: VecDeque<MacroStep>,
+    done: async_channel::Sender<Result<(), String>>,
+    /// Pending wait.
+    timer: Option<RegistrationToken>,
+}
+
+/// Validated macro step.
+#[derive(Debug, Clone)]
+pub enum MacroStep {
+    Action(Action),
+    Key(Key),
+    Wait(Duration),
+}
+
+impl TryFrom<niri_ipc::MacroStep> for MacroStep {
+    type Error = String;
+
+    fn try_from(step: niri_ipc::MacroStep) -> Result<Self, Self::Error> {
+        match step {
+            niri_ipc::MacroStep::Action(action) => Ok(Self::Action(Action::from(action))),
+            niri_ipc::MacroStep::Key { key } => {
+                let parsed = key
+                    .parse::<Key>()
+                    .map_err(|err| format!("invalid key {key:?}: {err}"))?;
+                if !matches!(parsed.trigger, Trigger::Keysym(_)) {
+                    return Err(format!("not a keyboard key: {key:?}"));
+                }
+                Ok(Self::Key(parsed))
+            }
+            niri_ipc::MacroStep::Wait { ms } => Ok(Self::Wait(Duration::from_millis(ms))),
+        }
+    }
+}
+
+/// Starts playing back a macro.
+///
+/// Sends the result to `done` once all steps have played or one of them failed.
+pub fn play(
+    state: &mut State,
+    steps: Vec<MacroStep>,
+    done: async_channel::Sender<Result<(), String>>,
+) {
+    if !state.niri.config.borrow().debug.allow_ipc_macros {
+        let msg = "macro playback is disabled, set debug { allow-ipc-macros; } to enable it";
+        let _ = done.send_blocking(Err(String::from(msg)));
+        return;
+    }
+
+    // Macros press keys, which could run any bind, so they can't respect the allowlist.
+    if state.niri.config.borrow().ipc.allowed_actions.is_some() {
+        let msg = "macro playback is disabled by ipc { allowed-actions }";
+        let _ = done.send_blocking(Err(String::from(msg)));
+        return;
+    }
+
+    if state.niri.macro_playback.is_some() {
+        let _ = done.send_blocking(Err(String::from("another macro is already playing")));
+        return;
+    }
+
+    state.niri.macro_playback = Some(MacroPlayback {
+        steps: steps.into(),
+        done,
+        timer: None,
+    });
+    advance(state);
+}
+
+fn finish(state: &mut State, result: Result<(), String>) {
+    let Some(playback) = state.niri.macro_playback.take() else {
+        return;
+    };
+
+    if let Some(token) = playback.timer {
+        state.niri.event_loop.remove(token);
+    }
+    let _ = playback.done.send_blocking(result);
+}
+
+/// Plays steps until the next wait or the end of the macro.
+fn advance(state: &mut State) {
+    loop {
+        let Some(playback) = &mut state.niri.macro_playback else {
+            return;
+        };
+        let Some(step) = playback.steps.pop_front() else {
+            finish(state, Ok(()));
+            return;
+        };
+
+        match step {
+            MacroStep::Action(action) => {
+                // Same as for IPC actions, let things like workspace clean-up run first.
+                state.niri.advance_animations();
+                state.do_action(action, false);
+            }
+            MacroStep::Key(key) => {
+                if let Err(err) = press_key(state, &key) {
+                    finish(state, Err(err));
+                    return;
+                }
+            }
+            MacroStep::Wait(duration) => {
+                let token = state
+                    .niri
+                    .event_loop
+                    .insert_source(Timer::from_duration(duration), |_, _, state| {
+                        if let Some(playback) = &mut state.niri.macro_playback {
+                            playback.timer = None;
+                        }
+                        advance(state);
+                        TimeoutAction::Drop
+                    })
+                    .unwrap();
+
+                match &mut state.niri.macro_playback {
+                    Some(playback) => playback.timer = Some(token),
+                    None => state.niri.event_loop.remove(token),
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Presses and releases a key combination.
+fn press_key(state: &mut State, key: &Key) -> Result<(), String> {
+    let Trigger::Keysym(keysym) = key.trigger else {
+        unreachable!("validated in MacroStep::try_from()");
+    };
+
+    let mod_key = state.backend.mod_key(&state.niri.config.borrow());
+
+    let mut keysyms = modifier_keysyms(key.modifiers, mod_key);
+    keysyms.push(keysym);
+
+    let keycodes = keysyms
+        .into_iter()
+        .map(|keysym| {
+            keycode_for_keysym(state, keysym)
+                .ok_or_else(|| format!("key {keysym:?} is not in the keyboard layout"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Same as real keys, so binds and key repeat work as usual. Releasing right away stops the
+    // repeat.
+    let time = get_monotonic_time().as_millis() as u32;
+    for &keycode in &keycodes {
+        state.handle_key(keycode, KeyState::Pressed, time, false);
+    }
+    for &keycode in keycodes.iter().rev() {
+        state.handle_key(keycode, KeyState::Released, time, false);
+    }
+
+    Ok(())
+}
+
+fn modifier_keysyms(modifiers: Modifiers, mod_key: ModKey) -> Vec<Keysym> {
+    let mut modifiers = modifiers;
+    if modifiers.contains(Modifiers::COMPOSITOR) {
+        modifiers.remove(Modifiers::COMPOSITOR);
+        modifiers |= mod_key.to_modifiers();
+    }
+
+    [
+        (Modifiers::CTRL, Keysym::Control_L),
+        (Modifiers::SHIFT, Keysym::Shift_L),
+        (Modifiers::ALT, Keysym::Alt_L),
+        (Modifiers::SUPER, Keysym::Super_L),
+        (Modifiers::ISO_LEVEL3_SHIFT, Keysym::ISO_Level3_Shift),
+        (Modifiers::ISO_LEVEL5_SHIFT, Keysym::ISO_Level5_Shift),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| modifiers.contains(*modifier))
+    .map(|(_, keysym)| keysym)
+    .collect()
+}
+
+/// Finds the key producing `keysym` without modifiers in the active layout.
+fn keycode_for_keysym(state: &mut State, keysym: Keysym) -> Option<Keycode> {
+    let keyboard = state.niri.seat.get_keyboard().unwrap();
+    keyboard.with_xkb_state(state, |context| {
+        let xkb = context.xkb().lock().unwrap();
+        let layout = xkb.active_layout();
+        // SAFETY: we're not changing the ref count.
+        let keymap = unsafe { xkb.keymap() };
+
+        (keymap.min_keycode().raw()..=keymap.max_keycode().raw())
+            .map(Keycode::new)
+            .find(|&keycode| {
+                xkb.raw_syms_for_key_in_layout(keycode, layout)
+                    .contains(&keysym)
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mod_key_maps_to_its_keysym() {
+        let key = "Mod+Shift+T".parse::<Key>().unwrap();
+        assert_eq!(
+            modifier_keysyms(key.modifiers, ModKey::Super),
+            [Keysym::Shift_L, Keysym::Super_L]
+        );
+        assert_eq!(
+            modifier_keysyms(key.modifiers, ModKey::Alt),
+            [Keysym::Shift_L, Keysym::Alt_L]
+        );
+    }
+
+    #[test]
+    fn only_keyboard_keys_are_allowed() {
+        let step = niri_ipc::MacroStep::Key {
+            key: String::from("Mod+MouseLeft"),
+        };
+        assert!(MacroStep::try_from(step).is_err());
+
+        let step = niri_ipc::MacroStep::Key {
+            key: String::from("Ctrl+Return"),
+        };
+        assert!(matches!(MacroStep::try_from(step), Ok(MacroStep::Key(_))));
+    }
+}
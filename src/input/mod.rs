@@ -6,8 +6,10 @@ use std::time::Duration;
 
 use calloop::timer::{TimeoutAction, Timer};
 use input::event::gesture::GestureEventCoordinates as _;
+use niri_config::workspace::WorkspaceName;
 use niri_config::{
-    Action, Bind, Binds, Config, Key, ModKey, Modifiers, MruDirection, SwitchBinds, Trigger, Xkb,
+    Action, Bind, Binds, Config, Key, ModKey, Modifiers, MruDirection, SwitchBinds,
+    TabBarDoubleClickAction, Trigger, Xkb,
 };
 use niri_ipc::LayoutSwitchTarget;
 use smithay::backend::input::{
@@ -19,6 +21,7 @@ use smithay::backend::input::{
     TabletToolTipState, TouchEvent,
 };
 use smithay::backend::libinput::LibinputInputBackend;
+use smithay::desktop::Window;
 use smithay::input::dnd::DnDGrab;
 use smithay::input::keyboard::{keysyms, FilterResult, Keysym, Layout, ModifiersState};
 use smithay::input::pointer::{
@@ -43,20 +46,26 @@ use touch_overview_grab::TouchOverviewGrab;
 use self::move_grab::MoveGrab;
 use self::resize_grab::ResizeGrab;
 use self::spatial_movement_grab::SpatialMovementGrab;
+use crate::cursor::CursorOverride;
 #[cfg(feature = "dbus")]
 use crate::dbus::freedesktop_a11y::KbMonBlock;
+use crate::handlers::mark_spawn_on_workspace;
 use crate::layout::tiling::ScrollDirection;
-use crate::cursor::CursorOverride;
-use crate::layout::{ActivateWindow, ContainerLayout, LayoutElement as _};
+use crate::layout::{ActivateWindow, ContainerLayout, HitType, LayoutElement as _};
 use crate::niri::{CastTarget, PointerVisibility, State};
 use crate::protocols::virtual_keyboard::VirtualKeyboard;
+use crate::ui::exit_confirm_dialog::PowerMenuEntry;
 use crate::ui::mru::{WindowMru, WindowMruUi};
+use crate::ui::resize_mode;
 use crate::ui::screenshot_ui::ScreenshotUi;
-use crate::utils::spawning::{spawn, spawn_sh};
+use crate::utils::backlight::{self, BrightnessChange};
+use crate::utils::spawning::{spawn_sh_with, spawn_with, SpawnOptions};
 use crate::utils::{center, get_monotonic_time, CastSessionId, ResizeEdge};
 use niri_ipc::SizeChange;
 
 pub mod backend_ext;
+pub mod bind_conflicts;
+pub mod macro_playback;
 pub mod move_grab;
 pub mod pick_color_grab;
 pub mod pick_window_grab;
@@ -135,6 +144,11 @@ impl State {
             if should_notify_activity(&event) {
                 self.niri.notify_activity();
             }
+
+            // Power on single outputs that were off, e.g. after their idle timeout.
+            if should_activate_monitors(&event) {
+                self.niri.power_on_outputs();
+            }
         } else {
             // Power on monitors if they were off.
             if should_activate_monitors(&event) {
@@ -153,8 +167,9 @@ impl State {
         let hide_hotkey_overlay =
             self.niri.hotkey_overlay.is_open() && should_hide_hotkey_overlay(&event);
 
-        let hide_exit_confirm_dialog =
-            self.niri.exit_confirm_dialog.is_open() && should_hide_exit_confirm_dialog(&event);
+        // The power menu is navigated with the keyboard, so keys don't hide it.
+        let hide_exit_confirm_dialog = self.niri.exit_confirm_dialog.is_open()
+            && should_hide_exit_confirm_dialog(&event, self.niri.exit_confirm_dialog.is_menu());
 
         let mut consumed_by_a11y = false;
         use InputEvent::*;
@@ -400,38 +415,20 @@ impl State {
             }
         }
 
-        let mod_key = self.backend.mod_key(&self.niri.config.borrow());
-
-        let serial = SERIAL_COUNTER.next_serial();
         let time = Event::time_msec(&event);
         let pressed = event.state() == KeyState::Pressed;
 
-        // Stop bind key repeat on any release. This won't work 100% correctly in cases like:
-        // 1. Press Mod
-        // 2. Press Left (repeat starts)
-        // 3. Press PgDown (new repeat starts)
-        // 4. Release Left (PgDown repeat stops)
-        // But it's good enough for now.
-        // FIXME: handle this properly.
-        if !pressed {
-            if let Some(token) = self.niri.bind_repeat_timer.take() {
-                self.niri.event_loop.remove(token);
-            }
-        }
-
         if pressed {
             self.hide_cursor_if_needed();
         }
 
-        let is_inhibiting_shortcuts = self.is_inhibiting_shortcuts();
-
         // Accessibility modifier grabs should override XKB state changes (e.g. Caps Lock), so we
-        // need to process them before keyboard.input() below.
+        // need to process them before keyboard.input() in handle_key().
         //
         // Other accessibility-grabbed keys should still update our XKB state, but not cause any
         // other changes.
         #[cfg(feature = "dbus")]
-        let block = {
+        let blocked_by_a11y = {
             let block = self.a11y_process_key(
                 Duration::from_millis(u64::from(time)),
                 event.key_code(),
@@ -445,27 +442,62 @@ impl State {
             if block == KbMonBlock::ModifierFirstPress {
                 return;
             }
-            block
+            block != KbMonBlock::Pass
         };
         #[cfg(not(feature = "dbus"))]
-        let _ = consumed_by_a11y;
+        let blocked_by_a11y = {
+            let _ = consumed_by_a11y;
+            false
+        };
+
+        self.handle_key(event.key_code(), event.state(), time, blocked_by_a11y);
+    }
+
+    /// Handles a key event through the keyboard: runs the matching bind, or forwards the key to
+    /// the focused client.
+    ///
+    /// Keys blocked by accessibility only update the XKB state.
+    pub fn handle_key(
+        &mut self,
+        key_code: Keycode,
+        key_state: KeyState,
+        time: u32,
+        blocked_by_a11y: bool,
+    ) {
+        let mod_key = self.backend.mod_key(&self.niri.config.borrow());
+
+        let serial = SERIAL_COUNTER.next_serial();
+        let pressed = key_state == KeyState::Pressed;
+
+        // Stop bind key repeat on any release. This won't work 100% correctly in cases like:
+        // 1. Press Mod
+        // 2. Press Left (repeat starts)
+        // 3. Press PgDown (new repeat starts)
+        // 4. Release Left (PgDown repeat stops)
+        // But it's good enough for now.
+        // FIXME: handle this properly.
+        if !pressed {
+            if let Some(token) = self.niri.bind_repeat_timer.take() {
+                self.niri.event_loop.remove(token);
+            }
+        }
+
+        let is_inhibiting_shortcuts = self.is_inhibiting_shortcuts();
 
         let Some(Some(bind)) = self.niri.seat.get_keyboard().unwrap().input(
             self,
-            event.key_code(),
-            event.state(),
+            key_code,
+            key_state,
             serial,
             time,
             |this, mods, keysym| {
-                let key_code = event.key_code();
                 let modified = keysym.modified_sym();
                 let raw = keysym.raw_latin_sym_or_raw_current_sym();
                 let modifiers = modifiers_from_state(*mods);
 
                 // After updating XKB state from accessibility-grabbed keys, return right away and
                 // don't handle them.
-                #[cfg(feature = "dbus")]
-                if block != KbMonBlock::Pass {
+                if blocked_by_a11y {
                     // HACK: there's a slight problem with this code. Here we filter out keys
                     // consumed by accessibility from getting sent to the Wayland client. However,
                     // the Wayland client can still receive these keys from the wl_keyboard
@@ -501,11 +533,29 @@ impl State {
                 }
 
                 if this.niri.exit_confirm_dialog.is_open() && pressed {
-                    if raw == Some(Keysym::Return) {
+                    // Don't send this press to any clients.
+                    this.niri.suppressed_keys.insert(key_code);
+
+                    if this.niri.exit_confirm_dialog.is_menu() {
+                        this.handle_power_menu_key(raw);
+                    } else if raw == Some(Keysym::Return) {
                         info!("quitting after confirming exit dialog");
                         this.niri.stop_signal.stop();
                     }
 
+                    return FilterResult::Intercept(None);
+                }
+
+                if this.niri.permission_prompt.is_open() && pressed && !this.niri.is_locked() {
+                    match raw {
+                        Some(Keysym::Return | Keysym::KP_Enter) => {
+                            this.niri.answer_permission_prompt(true);
+                        }
+                        Some(Keysym::n | Keysym::N) => this.niri.answer_permission_prompt(false),
+                        Some(Keysym::Escape) => this.niri.dismiss_permission_prompt(),
+                        _ => (),
+                    }
+
                     // Don't send this press to any clients.
                     this.niri.suppressed_keys.insert(key_code);
                     return FilterResult::Intercept(None);
@@ -542,6 +592,22 @@ impl State {
                     this.niri.screenshot_ui.set_space_down(pressed);
                 }
 
+                // The resize mode takes over the arrow keys before the configured binds.
+                if this.niri.resize_mode.is_active() && pressed && !this.niri.is_locked() {
+                    if let Some(bind) = raw.and_then(|raw| hardcoded_resize_mode_bind(raw, *mods)) {
+                        match bind.action {
+                            Action::SetWindowWidth(_) => this.niri.resize_mode.set_horizontal(true),
+                            Action::SetWindowHeight(_) => {
+                                this.niri.resize_mode.set_horizontal(false)
+                            }
+                            _ => (),
+                        }
+
+                        this.niri.suppressed_keys.insert(key_code);
+                        return FilterResult::Intercept(Some(bind));
+                    }
+                }
+
                 let res = {
                     let config = this.niri.config.borrow();
                     let bindings = make_binds_iter(
@@ -569,6 +635,11 @@ impl State {
                 if matches!(res, FilterResult::Forward) {
                     // If we didn't find any bind, try other hardcoded keys.
                     if this.niri.keyboard_focus.is_overview() && pressed {
+                        if this.handle_overview_search_key(modified, *mods) {
+                            this.niri.suppressed_keys.insert(key_code);
+                            return FilterResult::Intercept(None);
+                        }
+
                         if let Some(bind) = raw.and_then(|raw| hardcoded_overview_bind(raw, *mods))
                         {
                             this.niri.suppressed_keys.insert(key_code);
@@ -591,11 +662,104 @@ impl State {
             return;
         }
 
+        let focus_before = self.niri.layout.focus().map(|mapped| mapped.window.clone());
         self.handle_bind(bind.clone());
 
+        // Don't let focus-follows-mouse undo a keyboard focus change while the view moves.
+        if self.niri.layout.focus().map(|mapped| &mapped.window) != focus_before.as_ref() {
+            self.niri.cancel_focus_follows_mouse();
+            self.niri.focus_follows_mouse_suppressed = true;
+        }
+
         self.start_key_repeat(bind);
     }
 
+    /// Handles typing into the overview search.
+    ///
+    /// Returns `true` if the key was consumed.
+    fn handle_overview_search_key(&mut self, keysym: Keysym, mods: ModifiersState) -> bool {
+        if mods.ctrl || mods.alt || mods.logo {
+            return false;
+        }
+
+        let search = &mut self.niri.overview_search;
+        match keysym {
+            Keysym::Return | Keysym::KP_Enter if search.is_active() => {
+                if let Some(window) = search.best_match(&self.niri.layout) {
+                    self.focus_window(&window);
+                    self.niri.layout.close_overview();
+                }
+            }
+            Keysym::Escape if search.is_active() => {
+                search.clear();
+            }
+            Keysym::BackSpace if search.is_active() => {
+                search.pop_char();
+            }
+            _ => match keysym.key_char() {
+                // Don't start a query with whitespace, so that Space keeps working as a bind.
+                Some(c) if !c.is_control() && (search.is_active() || !c.is_whitespace()) => {
+                    search.push_char(c);
+                }
+                _ => return false,
+            },
+        }
+
+        self.niri.queue_redraw_all();
+        true
+    }
+
+    /// Handles a key press while the power menu is open.
+    fn handle_power_menu_key(&mut self, keysym: Option<Keysym>) {
+        let dialog = &mut self.niri.exit_confirm_dialog;
+        match keysym {
+            Some(Keysym::Up | Keysym::k) => dialog.move_selection(-1),
+            Some(Keysym::Down | Keysym::j | Keysym::Tab) => dialog.move_selection(1),
+            Some(Keysym::Return | Keysym::KP_Enter) => {
+                if let Some(entry) = dialog.selected_entry() {
+                    dialog.hide();
+                    self.activate_power_menu_entry(entry);
+                }
+            }
+            Some(Keysym::Escape) => {
+                dialog.hide();
+            }
+            _ => return,
+        }
+
+        self.niri.queue_redraw_all();
+
+        #[cfg(feature = "dbus")]
+        if self.niri.exit_confirm_dialog.is_open() {
+            self.niri.a11y_announce_power_menu();
+        }
+    }
+
+    fn activate_power_menu_entry(&mut self, entry: PowerMenuEntry) {
+        let config = self.niri.config.borrow();
+        let command = match entry {
+            PowerMenuEntry::Exit => &config.power_menu.exit,
+            PowerMenuEntry::Lock => &config.power_menu.lock,
+            PowerMenuEntry::Suspend => &config.power_menu.suspend,
+            PowerMenuEntry::Reboot => &config.power_menu.reboot,
+        };
+        let command = command.clone();
+        drop(config);
+
+        let action = match (entry, command) {
+            (_, Some(command)) => Action::Spawn(command),
+            (PowerMenuEntry::Exit, None) => Action::Quit(true),
+            (PowerMenuEntry::Lock, None) => {
+                Action::Spawn(vec![String::from("loginctl"), String::from("lock-session")])
+            }
+            (PowerMenuEntry::Suspend, None) => Action::Suspend,
+            (PowerMenuEntry::Reboot, None) => {
+                Action::Spawn(vec![String::from("systemctl"), String::from("reboot")])
+            }
+        };
+        self.do_action(action, false);
+    }
+
     fn start_key_repeat(&mut self, bind: Bind) {
         if !bind.repeat {
             return;
@@ -694,6 +858,14 @@ impl State {
             touch.cancel(self);
         }
 
+        // The action may move the focus away, so remember where the cursor was for the
+        // last-position warp mode.
+        self.remember_pointer_in_focused_tile();
+
+        if records_layout_change(&action) && !self.niri.screenshot_ui.is_open() {
+            self.niri.layout.record_layout_change();
+        }
+
         match action {
             Action::Quit(skip_confirmation) => {
                 if !skip_confirmation && self.niri.exit_confirm_dialog.show() {
@@ -704,6 +876,14 @@ impl State {
                 info!("quitting as requested");
                 self.niri.stop_signal.stop()
             }
+            Action::PowerMenu => {
+                if self.niri.exit_confirm_dialog.show_menu() {
+                    self.niri.queue_redraw_all();
+
+                    #[cfg(feature = "dbus")]
+                    self.niri.a11y_announce_power_menu();
+                }
+            }
             Action::ChangeVt(vt) => {
                 self.backend.change_vt(vt);
                 // Changing VT may not deliver the key releases, so clear the state.
@@ -720,6 +900,46 @@ impl State {
             Action::PowerOnMonitors => {
                 self.niri.activate_monitors(&mut self.backend);
             }
+            Action::PowerOffMonitor(output) => {
+                let output = match output {
+                    None => self.niri.layout.active_output().cloned(),
+                    Some(name) => self.niri.output_by_name_match(&name).cloned(),
+                };
+                if let Some(output) = output {
+                    self.niri.power_off_output(&output);
+                }
+            }
+            Action::PowerOnMonitor(output) => {
+                let output = match output {
+                    None => self.niri.layout.active_output().cloned(),
+                    Some(name) => self.niri.output_by_name_match(&name).cloned(),
+                };
+                if let Some(output) = output {
+                    self.niri.power_on_output(&output);
+                }
+            }
+            Action::SetOutputProfile(name) => {
+                if !self.apply_output_profile(Some(&name)) {
+                    warn!("output profile \"{name}\" not found");
+                }
+            }
+            Action::BrightnessUp(step) => {
+                let step = step.unwrap_or(backlight::DEFAULT_STEP);
+                self.niri.change_brightness(BrightnessChange::Up(step));
+            }
+            Action::BrightnessDown(step) => {
+                let step = step.unwrap_or(backlight::DEFAULT_STEP);
+                self.niri.change_brightness(BrightnessChange::Down(step));
+            }
+            Action::BrightnessSet(percent) => {
+                self.niri.change_brightness(BrightnessChange::Set(percent));
+            }
+            Action::TogglePowerSaving => {
+                self.toggle_power_saving();
+            }
+            Action::ToggleNightLight => {
+                self.toggle_night_light();
+            }
             Action::ToggleDebugTint => {
                 self.backend.toggle_debug_tint();
                 self.niri.queue_redraw_all();
@@ -731,13 +951,57 @@ impl State {
             Action::DebugToggleDamage => {
                 self.niri.debug_toggle_damage();
             }
+            Action::DebugToggleFrameStats => {
+                self.niri.debug_draw_frame_stats = !self.niri.debug_draw_frame_stats;
+                self.niri.queue_redraw_all();
+            }
             Action::Spawn(command) => {
                 let (token, _) = self.niri.activation_state.create_external_token(None);
-                spawn(command, Some(token.clone()));
+                let options = SpawnOptions {
+                    token: Some(token.clone()),
+                    env: self.niri.spawn_env(),
+                    systemd_scope: false,
+                };
+                spawn_with(command, options);
+            }
+            Action::SpawnSd(command) => {
+                let (token, _) = self.niri.activation_state.create_external_token(None);
+                let options = SpawnOptions {
+                    token: Some(token.clone()),
+                    env: self.niri.spawn_env(),
+                    systemd_scope: true,
+                };
+                spawn_with(command, options);
             }
             Action::SpawnSh(command) => {
                 let (token, _) = self.niri.activation_state.create_external_token(None);
-                spawn_sh(command, Some(token.clone()));
+                let options = SpawnOptions {
+                    token: Some(token.clone()),
+                    env: self.niri.spawn_env(),
+                    systemd_scope: false,
+                };
+                spawn_sh_with(command, options);
+            }
+            Action::SpawnOnWorkspace(workspace, command) => {
+                let ws_config = niri_config::Workspace {
+                    name: WorkspaceName(workspace.clone()),
+                    open_on_output: None,
+                    layout: None,
+                };
+                // Does nothing if a workspace with this name already exists.
+                self.niri.layout.ensure_named_workspace(&ws_config);
+                let reference = niri_config::WorkspaceReference::Name(workspace.clone());
+                self.do_action(Action::FocusWorkspace(reference), allow_when_locked);
+
+                let (token, data) = self.niri.activation_state.create_external_token(None);
+                let env = self.niri.spawn_env_on_workspace(&workspace);
+                mark_spawn_on_workspace(data, workspace);
+                let options = SpawnOptions {
+                    token: Some(token.clone()),
+                    env,
+                    systemd_scope: false,
+                };
+                spawn_with(command, options);
             }
             Action::DoScreenTransition(delay_ms) => {
                 self.backend.with_primary_renderer(|renderer| {
@@ -881,6 +1145,19 @@ impl State {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::ToggleWindowFullscreenSpan => {
+                let focus = self.niri.layout.focus().map(|m| m.window.clone());
+                if let Some(window) = focus {
+                    self.niri.toggle_window_fullscreen_span(&window);
+                }
+            }
+            Action::ToggleWindowFullscreenSpanById(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.toggle_window_fullscreen_span(&window);
+                }
+            }
             Action::MoveWindowToScratchpad => {
                 self.niri.layout.move_window_to_scratchpad(None);
                 // FIXME: granular
@@ -2121,6 +2398,15 @@ impl State {
             Action::SetLayoutTabbed => {
                 self.niri.layout.set_layout_mode(ContainerLayout::Tabbed);
             }
+            Action::SetTabBarPosition(position) => {
+                self.niri.layout.set_tab_bar_position(position.into());
+            }
+            Action::UndoLayoutChange => {
+                self.niri.layout.undo_layout_change();
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::SetColumnWidth(change) => {
                 if self.niri.screenshot_ui.is_open() {
                     self.niri.screenshot_ui.set_width(change);
@@ -2165,6 +2451,48 @@ impl State {
                     self.niri.layout.set_window_height(Some(&window), change);
                 }
             }
+            Action::ResizeGrow(direction, amount) => {
+                self.niri
+                    .layout
+                    .resize_window_edge(None, direction, amount, true);
+            }
+            Action::ResizeGrowById {
+                id,
+                direction,
+                amount,
+            } => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri
+                        .layout
+                        .resize_window_edge(Some(&window), direction, amount, true);
+                }
+            }
+            Action::ResizeShrink(direction, amount) => {
+                self.niri
+                    .layout
+                    .resize_window_edge(None, direction, amount, false);
+            }
+            Action::ResizeShrinkById {
+                id,
+                direction,
+                amount,
+            } => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri
+                        .layout
+                        .resize_window_edge(Some(&window), direction, amount, false);
+                }
+            }
+            Action::ToggleResizeMode => {
+                if !self.niri.resize_mode.stop() {
+                    self.niri.resize_mode.start();
+                }
+                self.niri.queue_redraw_all();
+            }
             Action::ResetWindowHeight => {
                 self.niri.layout.reset_window_height(None);
             }
@@ -2239,6 +2567,36 @@ impl State {
                     }
                 }
             }
+            Action::MoveWorkspaceToMonitorInDirection(direction) => {
+                self.do_action(Action::move_workspace_to_monitor(direction), allow_when_locked);
+            }
+            Action::MoveWorkspaceToMonitorInDirectionByRef {
+                direction,
+                reference,
+            } => {
+                if let Some((output, old_idx)) =
+                    self.niri.find_output_and_workspace_index(reference)
+                {
+                    // Without an output, the index refers to the active output.
+                    let current = output
+                        .clone()
+                        .or_else(|| self.niri.layout.active_output().cloned());
+                    let new_output = current
+                        .and_then(|current| self.niri.output_in_direction_of(&current, direction));
+                    if let Some(new_output) = new_output {
+                        if self.niri.layout.move_workspace_to_output_by_id(
+                            old_idx,
+                            output,
+                            &new_output,
+                        ) {
+                            // Cursor warp already calls `queue_redraw_all`
+                            if !self.maybe_warp_cursor_to_focus_centered() {
+                                self.move_cursor_to_output(&new_output);
+                            }
+                        }
+                    }
+                }
+            }
             Action::MoveWorkspaceToMonitor(new_output) => {
                 if let Some(new_output) = self.niri.output_by_name_match(&new_output).cloned() {
                     if self.niri.layout.move_workspace_to_output(&new_output)
@@ -2298,6 +2656,79 @@ impl State {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::ToggleWindowAlwaysOnTop => {
+                self.niri.layout.toggle_window_always_on_top(None);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleWindowAlwaysOnTopById(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.layout.toggle_window_always_on_top(Some(&window));
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::RaiseWindow => {
+                self.niri.layout.raise_window(None);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::RaiseWindowById(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.layout.raise_window(Some(&window));
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::LowerWindow => {
+                self.niri.layout.lower_window(None);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::LowerWindowById(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.layout.lower_window(Some(&window));
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ToggleWindowFollowFocus => {
+                self.niri.layout.toggle_window_follow_focus(None);
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleWindowFollowFocusById(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.layout.toggle_window_follow_focus(Some(&window));
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ToggleWindowPictureInPicture => {
+                let window = self.niri.layout.focus().map(|m| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.toggle_window_picture_in_picture(&window);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ToggleWindowPictureInPictureById(id) => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.toggle_window_picture_in_picture(&window);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
             Action::MoveWindowToFloating => {
                 self.niri.layout.set_window_floating(None, true);
                 // FIXME: granular
@@ -2344,6 +2775,12 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::ArrangeFloating(arrangement) => {
+                self.niri.layout.arrange_floating(arrangement);
+                self.maybe_warp_cursor_to_focus();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::MoveFloatingWindowById { id, x, y } => {
                 let window = if let Some(id) = id {
                     let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
@@ -2390,6 +2827,30 @@ impl State {
                     }
                 }
             }
+            Action::ToggleWindowScreencastBlockOut => {
+                let active_window = self
+                    .niri
+                    .layout
+                    .active_workspace_mut()
+                    .and_then(|ws| ws.active_window_mut());
+                if let Some(window) = active_window {
+                    window.toggle_screencast_block_out();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ToggleWindowScreencastBlockOutById(id) => {
+                let window = self
+                    .niri
+                    .layout
+                    .workspaces_mut()
+                    .find_map(|ws| ws.windows_mut().find(|w| w.id().get() == id));
+                if let Some(window) = window {
+                    window.toggle_screencast_block_out();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
             Action::SetDynamicCastWindow => {
                 let id = self
                     .niri
@@ -2475,6 +2936,10 @@ impl State {
                     watcher.load_config();
                 }
             }
+            Action::SetAccentColor(color) => match color.parse::<niri_config::Color>() {
+                Ok(color) => self.niri.set_accent_color(color),
+                Err(err) => warn!("error parsing accent color: {err:?}"),
+            },
             Action::MruConfirm => {
                 self.confirm_mru();
             }
@@ -3164,6 +3629,32 @@ impl State {
             }
             */
 
+            if button == Some(MouseButton::Left) && !pointer.is_grabbed() && !is_overview_open {
+                // Clicks on the unresponsive window prompt don't reach the window.
+                let location = pointer.current_location();
+                if let Some((output, pos_within_output)) = self.niri.output_under(location) {
+                    let output = output.clone();
+                    let prompt_hit = self
+                        .niri
+                        .layout
+                        .window_under(&output, pos_within_output)
+                        .and_then(|(mapped, hit)| {
+                            let HitType::Input { win_pos } = hit else {
+                                return None;
+                            };
+                            let geo_loc = mapped.window.geometry().loc.to_f64();
+                            let point = pos_within_output - win_pos - geo_loc;
+                            let button = mapped.unresponsive_button_at(point)?;
+                            Some((mapped.window.clone(), button))
+                        });
+                    if let Some((window, button)) = prompt_hit {
+                        self.answer_unresponsive_prompt(&window, button);
+                        self.niri.suppressed_buttons.insert(button_code);
+                        return;
+                    }
+                }
+            }
+
             if button == Some(MouseButton::Left) && !pointer.is_grabbed() && !is_overview_open {
                 let mod_down = modifiers_from_state(mods).contains(mod_key.to_modifiers());
                 if !mod_down {
@@ -3171,7 +3662,7 @@ impl State {
                         resize_hit_at_pointer(self)
                     {
                         if hit.is_floating {
-                            self.niri.layout.activate_window(&hit.window);
+                            self.activate_window_on_click(&hit.window);
                             if self.niri.layout.interactive_resize_begin_at(
                                 hit.window.clone(),
                                 hit.edges,
@@ -3326,8 +3817,18 @@ impl State {
                     // Resize handled above using resize-hit to ensure cursor and action match.
                 }
 
+                if button == Some(MouseButton::Left)
+                    && !pointer.is_grabbed()
+                    && !is_overview_open
+                    && self.on_tab_bar_click(&window, pointer.current_location())
+                {
+                    // FIXME: granular.
+                    self.niri.queue_redraw_all();
+                    return;
+                }
+
                 if !is_overview_open {
-                    self.niri.layout.activate_window(&window);
+                    self.activate_window_on_click(&window);
                 }
 
                 // FIXME: granular.
@@ -3399,6 +3900,62 @@ impl State {
         pointer.frame(self);
     }
 
+    /// Activates a clicked window, raising it unless click-to-raise is disabled.
+    fn activate_window_on_click(&mut self, window: &Window) {
+        if self.niri.config.borrow().input.disable_click_to_raise {
+            self.niri.layout.activate_window_without_raising(window);
+        } else {
+            self.niri.layout.activate_window(window);
+        }
+    }
+
+    /// Handles a left click, running the tab bar double-click action if this was one.
+    ///
+    /// Returns `true` if the click was consumed.
+    fn on_tab_bar_click(&mut self, window: &Window, location: Point<f64, Logical>) -> bool {
+        let action = self.niri.config.borrow().layout.tab_bar.double_click_action;
+        if action == TabBarDoubleClickAction::None {
+            return false;
+        }
+
+        let contents = self.niri.contents_under(location);
+        let on_tab = matches!(
+            contents.window,
+            Some((
+                _,
+                HitType::Activate {
+                    is_tab_indicator: true
+                }
+            ))
+        );
+        if !on_tab {
+            self.niri.tab_bar_last_click = None;
+            return false;
+        }
+
+        let time = get_monotonic_time();
+        let last = self.niri.tab_bar_last_click.replace((time, window.clone()));
+        let Some((last_time, last_window)) = last else {
+            return false;
+        };
+        if last_window != *window || time.saturating_sub(last_time) > DOUBLE_CLICK_TIME {
+            return false;
+        }
+
+        // Don't chain a third click into another double click.
+        self.niri.tab_bar_last_click = None;
+
+        self.niri.layout.activate_window(window);
+        match action {
+            TabBarDoubleClickAction::None => (),
+            TabBarDoubleClickAction::Fullscreen => self.niri.layout.toggle_fullscreen(window),
+            TabBarDoubleClickAction::Maximize => self.niri.layout.toggle_maximized(window),
+            TabBarDoubleClickAction::Collapse => self.niri.layout.toggle_split_layout(),
+        }
+
+        true
+    }
+
     fn on_pointer_axis<I: InputBackend>(&mut self, event: I::PointerAxisEvent) {
         let pointer = &self.niri.seat.get_pointer().unwrap();
 
@@ -4647,15 +5204,23 @@ impl State {
             let is_closed = evt.state() == SwitchState::On;
             trace!("lid switch {}", if is_closed { "closed" } else { "opened" });
             self.set_lid_closed(is_closed);
+        } else if switch == Switch::TabletMode {
+            self.set_tablet_mode(evt.state() == SwitchState::On);
         }
 
-        let action = {
+        let actions = {
             let bindings = &self.niri.config.borrow().switch_events;
-            find_configured_switch_action(bindings, switch, evt.state())
+            find_configured_switch_actions(bindings, switch, evt.state())
         };
 
-        if let Some(action) = action {
-            self.do_action(action, true);
+        for action in actions {
+            // Like spawn binds with allow-when-locked=true, switch events can spawn things while
+            // locked; other actions follow the usual rules.
+            let allow_when_locked = matches!(
+                action,
+                Action::Spawn(_) | Action::SpawnSh(_) | Action::SpawnSd(_)
+            );
+            self.do_action(action, allow_when_locked);
         }
     }
 
@@ -4835,11 +5400,11 @@ fn find_configured_bind<'a>(
     None
 }
 
-fn find_configured_switch_action(
+fn find_configured_switch_actions(
     bindings: &SwitchBinds,
     switch: Switch,
     state: SwitchState,
-) -> Option<Action> {
+) -> Vec<Action> {
     let switch_action = match (switch, state) {
         (Switch::Lid, SwitchState::Off) => &bindings.lid_open,
         (Switch::Lid, SwitchState::On) => &bindings.lid_close,
@@ -4849,7 +5414,8 @@ fn find_configured_switch_action(
     };
     switch_action
         .as_ref()
-        .map(|switch_action| Action::Spawn(switch_action.spawn.clone()))
+        .map(|switch_action| switch_action.actions.clone())
+        .unwrap_or_default()
 }
 
 fn modifiers_from_state(mods: ModifiersState) -> Modifiers {
@@ -4911,9 +5477,9 @@ fn should_hide_hotkey_overlay<I: InputBackend>(event: &InputEvent<I>) -> bool {
     }
 }
 
-fn should_hide_exit_confirm_dialog<I: InputBackend>(event: &InputEvent<I>) -> bool {
+fn should_hide_exit_confirm_dialog<I: InputBackend>(event: &InputEvent<I>, is_menu: bool) -> bool {
     match event {
-        InputEvent::Keyboard { event } if event.state() == KeyState::Pressed => true,
+        InputEvent::Keyboard { event } if event.state() == KeyState::Pressed => !is_menu,
         InputEvent::PointerButton { event } if event.state() == ButtonState::Pressed => true,
         InputEvent::PointerAxis { .. }
         | InputEvent::GestureSwipeBegin { .. }
@@ -4955,11 +5521,54 @@ fn allowed_when_locked(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::PowerOnMonitors
+            | Action::PowerOffMonitor(_)
+            | Action::PowerOnMonitor(_)
+            | Action::BrightnessUp(_)
+            | Action::BrightnessDown(_)
+            | Action::BrightnessSet(_)
+            | Action::ToggleNightLight
             | Action::SwitchLayout(_)
             | Action::ToggleKeyboardShortcutsInhibit
     )
 }
 
+/// Returns whether the action changes the tiling tree in a way that can be undone.
+fn records_layout_change(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::MoveColumnLeft
+            | Action::MoveColumnRight
+            | Action::MoveColumnToFirst
+            | Action::MoveColumnToLast
+            | Action::MoveColumnToIndex(_)
+            | Action::MoveWindowDown
+            | Action::MoveWindowUp
+            | Action::ConsumeOrExpelWindowLeft
+            | Action::ConsumeOrExpelWindowLeftById(_)
+            | Action::ConsumeOrExpelWindowRight
+            | Action::ConsumeOrExpelWindowRightById(_)
+            | Action::ConsumeWindowIntoColumn
+            | Action::ExpelWindowFromColumn
+            | Action::SwapWindowLeft
+            | Action::SwapWindowRight
+            | Action::ToggleColumnTabbedDisplay
+            | Action::SetColumnDisplay(_)
+            | Action::SplitHorizontal
+            | Action::SplitVertical
+            | Action::SetLayoutSplitH
+            | Action::SetLayoutSplitV
+            | Action::ToggleSplitLayout
+            | Action::SetLayoutStacked
+            | Action::SetLayoutTabbed
+            | Action::ToggleWindowFloating
+            | Action::ToggleWindowFloatingById(_)
+            | Action::MoveWindowToFloating
+            | Action::MoveWindowToFloatingById(_)
+            | Action::MoveWindowToTiling
+            | Action::MoveWindowToTilingById(_)
+    )
+}
+
 fn allowed_during_screenshot(action: &Action) -> bool {
     matches!(
         action,
@@ -4968,6 +5577,8 @@ fn allowed_during_screenshot(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::PowerOnMonitors
+            | Action::PowerOffMonitor(_)
+            | Action::PowerOnMonitor(_)
             // The screenshot UI can handle these.
             | Action::MoveColumnLeft
             | Action::MoveColumnLeftOrToMonitorLeft
@@ -5032,6 +5643,48 @@ fn hardcoded_overview_bind(raw: Keysym, mods: ModifiersState) -> Option<Bind> {
     })
 }
 
+fn hardcoded_resize_mode_bind(raw: Keysym, mods: ModifiersState) -> Option<Bind> {
+    let modifiers = modifiers_from_state(mods);
+    if !(modifiers - Modifiers::SHIFT).is_empty() {
+        return None;
+    }
+
+    // Shift makes the steps finer.
+    let step = if modifiers.contains(Modifiers::SHIFT) {
+        resize_mode::FINE_STEP
+    } else {
+        resize_mode::STEP
+    };
+
+    let mut repeat = true;
+    let action = match raw {
+        Keysym::Escape | Keysym::Return | Keysym::KP_Enter => {
+            repeat = false;
+            Action::ToggleResizeMode
+        }
+        Keysym::Left | Keysym::h => Action::SetWindowWidth(SizeChange::AdjustProportion(-step)),
+        Keysym::Right | Keysym::l => Action::SetWindowWidth(SizeChange::AdjustProportion(step)),
+        Keysym::Up | Keysym::k => Action::SetWindowHeight(SizeChange::AdjustProportion(-step)),
+        Keysym::Down | Keysym::j => Action::SetWindowHeight(SizeChange::AdjustProportion(step)),
+        _ => {
+            return None;
+        }
+    };
+
+    Some(Bind {
+        key: Key {
+            trigger: Trigger::Keysym(raw),
+            modifiers,
+        },
+        action,
+        repeat,
+        cooldown: None,
+        allow_when_locked: false,
+        allow_inhibiting: false,
+        hotkey_overlay_title: None,
+    })
+}
+
 pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::Device) {
     // According to Mutter code, this setting is specific to touchpads.
     let is_touchpad = device.config_tap_finger_count() > 0;
@@ -1,22 +1,31 @@
 use std::io::ErrorKind;
 use std::iter::Peekable;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, slice};
 
 use anyhow::{anyhow, bail, Context};
 use niri_config::OutputName;
 use niri_ipc::socket::Socket;
+use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
 use niri_ipc::{
-    Action, Cast, CastKind, CastTarget, Event, KeyboardLayouts, LayoutTree, LayoutTreeLayout,
-    LogicalOutput, Mode, Output, OutputConfigChanged, Overview, Request, Response, Transform,
-    Window, WindowLayout,
+    Action, BackgroundPermission, BindConflict, BindConflictKind, Cast, CastKind, CastTarget,
+    ConfigValue, ContainerAddress, ContainerPercents, Event, KeyboardLayouts, LayoutTree,
+    LayoutTreeLayout, LayoutVerification, LayoutViolationKind, LogLevel, LogMessage, LogicalOutput,
+    Mode, Output, OutputConfigChanged, OutputScanout, Overview, PowerSaving, Request, Response,
+    ScanoutBlocker, SpawnedProcess, Transform, Window, WindowLayout,
 };
-use serde_json::json;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
 
 use crate::cli::Msg;
 use crate::utils::version;
 
-pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
+pub fn handle_msg(mut msg: Msg, json: bool, watch: bool) -> anyhow::Result<()> {
+    if watch && !matches!(msg, Msg::FocusedWindow | Msg::FocusedWorkspace) {
+        bail!("--watch only works with focused-window and focused-workspace");
+    }
+
     // For actions taking paths, prepend the niri CLI's working directory.
     if let Msg::Action {
         action:
@@ -30,10 +39,22 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
         }
     }
 
+    let macro_steps = match &msg {
+        Msg::PlayMacro { path } => Some(read_json(path).context("error reading the macro")?),
+        _ => None,
+    };
+    let workspace_export = match &msg {
+        Msg::ImportWorkspace { path } => {
+            Some(read_json(path).context("error reading the workspace export")?)
+        }
+        _ => None,
+    };
+
     let request = match &msg {
         Msg::Version => Request::Version,
         Msg::Outputs => Request::Outputs,
         Msg::FocusedWindow => Request::FocusedWindow,
+        Msg::FocusedWorkspace => Request::Workspaces,
         Msg::FocusedOutput => Request::FocusedOutput,
         Msg::PickWindow => Request::PickWindow,
         Msg::PickColor => Request::PickColor,
@@ -47,11 +68,66 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
         Msg::Layers => Request::Layers,
         Msg::KeyboardLayouts => Request::KeyboardLayouts,
         Msg::EventStream => Request::EventStream,
+        Msg::I3bar => Request::EventStream,
         Msg::RequestError => Request::ReturnError,
         Msg::OverviewState => Request::OverviewState,
         Msg::LayoutTree => Request::LayoutTree,
         Msg::Casts => Request::Casts,
+        Msg::CreateHeadlessOutput {
+            width,
+            height,
+            scale,
+            name,
+        } => Request::CreateHeadlessOutput {
+            width: *width,
+            height: *height,
+            scale: *scale,
+            name: name.clone(),
+        },
+        Msg::DestroyOutput { output } => Request::DestroyOutput {
+            output: output.clone(),
+        },
+        Msg::GetConfigValue { path } => Request::GetConfigValue { path: path.clone() },
+        Msg::SetConfigValue { path, value } => Request::SetConfigValue {
+            path: path.clone(),
+            value: *value,
+        },
+        Msg::WorkspaceThumbnail { id, max_size, .. } => Request::WorkspaceThumbnail {
+            id: *id,
+            max_size: *max_size,
+        },
+        Msg::DebugScanout => Request::DebugScanout,
+        Msg::DebugVerifyLayout => Request::DebugVerifyLayout,
+        Msg::Logs { level, limit } => Request::RecentLogs {
+            level: *level,
+            limit: *limit,
+        },
+        Msg::PlayMacro { .. } => Request::PlayMacro {
+            steps: macro_steps.unwrap(),
+        },
+        Msg::ExportWorkspace { .. } => Request::ExportWorkspace,
+        Msg::ImportWorkspace { .. } => Request::ImportWorkspace {
+            export: workspace_export.unwrap(),
+        },
+        Msg::BackgroundPermissions => Request::BackgroundPermissions,
+        Msg::BindConflicts => Request::BindConflicts,
+        Msg::SpawnedProcesses => Request::SpawnedProcesses,
+        Msg::ContainerPercents { id, path, root } => Request::ContainerPercents {
+            container: container_address(*id, path, *root),
+        },
+        Msg::SetContainerPercents {
+            percents,
+            id,
+            path,
+            root,
+        } => Request::SetContainerPercents {
+            container: container_address(*id, path, *root),
+            percents: percents.clone(),
+        },
+        Msg::PowerSavingState => Request::PowerSavingState,
     };
+    // Watching follows the event stream rather than asking once.
+    let request = if watch { Request::EventStream } else { request };
 
     let mut socket = Socket::connect().context("error connecting to the niri socket")?;
 
@@ -112,6 +188,13 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
     let reply = result.context("error communicating with niri")?;
     let response = reply.map_err(|err_msg| anyhow!(err_msg).context("niri returned an error"))?;
 
+    if watch {
+        let Response::Handled = response else {
+            bail!("unexpected response: expected Handled, got {response:?}");
+        };
+        return watch_focused(&msg, socket);
+    }
+
     match msg {
         Msg::RequestError => {
             bail!("unexpected response: expected an error, got {response:?}");
@@ -183,6 +266,36 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("No window is focused.");
             }
         }
+        Msg::FocusedWorkspace => {
+            let Response::Workspaces(response) = response else {
+                bail!("unexpected response: expected Workspaces, got {response:?}");
+            };
+            let workspace = response.into_iter().find(|ws| ws.is_focused);
+
+            if json {
+                let workspace =
+                    serde_json::to_string(&workspace).context("error formatting response")?;
+                println!("{workspace}");
+                return Ok(());
+            }
+
+            let Some(ws) = workspace else {
+                println!("No workspace is focused.");
+                return Ok(());
+            };
+
+            println!("Workspace ID {}:", ws.id);
+            println!("  Index: {}", ws.idx);
+            if let Some(name) = &ws.name {
+                println!("  Name: \"{name}\"");
+            }
+            if let Some(output) = &ws.output {
+                println!("  Output: \"{output}\"");
+            }
+            if let Some(id) = ws.active_window_id {
+                println!("  Active window ID: {id}");
+            }
+        }
         Msg::Windows => {
             let Response::Windows(mut windows) = response else {
                 bail!("unexpected response: expected Windows, got {response:?}");
@@ -324,6 +437,70 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                 bail!("unexpected response: expected Handled, got {response:?}");
             };
         }
+        Msg::CreateHeadlessOutput { .. } => {
+            let Response::OutputCreated(name) = response else {
+                bail!("unexpected response: expected OutputCreated, got {response:?}");
+            };
+
+            if json {
+                let name = serde_json::to_string(&name).context("error formatting response")?;
+                println!("{name}");
+                return Ok(());
+            }
+
+            println!("Created output \"{name}\".");
+        }
+        Msg::DestroyOutput { .. } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::GetConfigValue { .. } => {
+            let Response::ConfigValue(value) = response else {
+                bail!("unexpected response: expected ConfigValue, got {response:?}");
+            };
+
+            if json {
+                let value = serde_json::to_string(&value).context("error formatting response")?;
+                println!("{value}");
+                return Ok(());
+            }
+
+            println!("{}", format_config_value(value));
+        }
+        Msg::SetConfigValue { .. } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::WorkspaceThumbnail { path, .. } => {
+            let Response::WorkspaceThumbnail(thumbnail) = response else {
+                bail!("unexpected response: expected WorkspaceThumbnail, got {response:?}");
+            };
+
+            if json {
+                let thumbnail =
+                    serde_json::to_string(&thumbnail).context("error formatting response")?;
+                println!("{thumbnail}");
+                return Ok(());
+            }
+
+            let Some(path) = path else {
+                bail!("specify --path to save the thumbnail, or use --json");
+            };
+
+            let png = thumbnail
+                .png()
+                .context("error decoding the thumbnail image")?;
+            std::fs::write(&path, png).context("error saving the thumbnail image")?;
+
+            println!(
+                "Saved {}×{} thumbnail to {}.",
+                thumbnail.width,
+                thumbnail.height,
+                path.display()
+            );
+        }
         Msg::Output { output, .. } => {
             let Response::OutputConfigChanged(response) = response else {
                 bail!("unexpected response: expected OutputConfigChanged, got {response:?}");
@@ -508,7 +685,52 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                     Event::CastStopped { stream_id } => {
                         println!("Cast stopped: stream id {stream_id}");
                     }
+                    Event::ConfigValueChanged { path, value } => {
+                        println!(
+                            "Config value {path} changed to {}",
+                            format_config_value(value)
+                        );
+                    }
+                    Event::CriticalClientRestarted { command, restarts } => {
+                        println!("Critical client restarted ({restarts} in a row): {command:?}");
+                    }
+                    Event::SwitchesChanged {
+                        lid_closed,
+                        tablet_mode,
+                    } => {
+                        println!(
+                            "Switches changed: lid closed {lid_closed}, tablet mode {tablet_mode}"
+                        );
+                    }
+                }
+            }
+        }
+        Msg::I3bar => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+
+            // The header, followed by an infinite array of status lines, see i3bar-protocol(7).
+            println!("{}", json!({ "version": 1 }));
+            println!("[");
+
+            let mut state = EventStreamState::default();
+            let mut last_line = None;
+            let mut read_event = socket.read_events();
+            loop {
+                let event = read_event().context("error reading event from niri")?;
+                state.apply(event);
+
+                let line = serde_json::to_string(&i3bar_blocks(&state))
+                    .context("error formatting status line")?;
+                // Most events don't change anything that we show.
+                if last_line.as_ref() == Some(&line) {
+                    continue;
                 }
+
+                let separator = if last_line.is_some() { "," } else { "" };
+                println!("{separator}{line}");
+                last_line = Some(line);
             }
         }
         Msg::OverviewState => {
@@ -565,11 +787,226 @@ pub fn handle_msg(mut msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!();
             }
         }
+        Msg::DebugScanout => {
+            let Response::DebugScanout(scanout) = response else {
+                bail!("unexpected response: expected DebugScanout, got {response:?}");
+            };
+
+            if json {
+                let scanout =
+                    serde_json::to_string(&scanout).context("error formatting response")?;
+                println!("{scanout}");
+                return Ok(());
+            }
+
+            let mut scanout = scanout.into_iter().collect::<Vec<_>>();
+            scanout.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            for (name, state) in scanout {
+                print_scanout(&name, &state);
+            }
+        }
+        Msg::Logs { .. } => {
+            let Response::RecentLogs(messages) = response else {
+                bail!("unexpected response: expected RecentLogs, got {response:?}");
+            };
+
+            if json {
+                let messages =
+                    serde_json::to_string(&messages).context("error formatting response")?;
+                println!("{messages}");
+                return Ok(());
+            }
+
+            let now_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as u64);
+            for message in messages {
+                print_log_message(&message, now_ms);
+            }
+        }
+        Msg::DebugVerifyLayout => {
+            let Response::DebugVerifyLayout(verification) = response else {
+                bail!("unexpected response: expected DebugVerifyLayout, got {response:?}");
+            };
+
+            let count = verification.violations.len();
+
+            if json {
+                let verification =
+                    serde_json::to_string(&verification).context("error formatting response")?;
+                println!("{verification}");
+            } else {
+                print_layout_verification(&verification);
+            }
+
+            if count > 0 {
+                bail!("found {count} layout problems");
+            }
+        }
+        Msg::PlayMacro { .. } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::ExportWorkspace { path } => {
+            let Response::WorkspaceExport(export) = response else {
+                bail!("unexpected response: expected WorkspaceExport, got {response:?}");
+            };
+
+            let export =
+                serde_json::to_string_pretty(&export).context("error formatting response")?;
+            if let Some(path) = path {
+                std::fs::write(&path, export).context("error saving the workspace export")?;
+                println!("Saved workspace to {}.", path.display());
+            } else {
+                println!("{export}");
+            }
+        }
+        Msg::ImportWorkspace { .. } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::BackgroundPermissions => {
+            let Response::BackgroundPermissions(permissions) = response else {
+                bail!("unexpected response: expected BackgroundPermissions, got {response:?}");
+            };
+
+            if json {
+                let permissions =
+                    serde_json::to_string(&permissions).context("error formatting response")?;
+                println!("{permissions}");
+                return Ok(());
+            }
+
+            if permissions.is_empty() {
+                println!("No apps asked for background permissions.");
+            }
+
+            for permission in &permissions {
+                print_background_permission(permission);
+            }
+        }
+        Msg::BindConflicts => {
+            let Response::BindConflicts(conflicts) = response else {
+                bail!("unexpected response: expected BindConflicts, got {response:?}");
+            };
+
+            if json {
+                let conflicts =
+                    serde_json::to_string(&conflicts).context("error formatting response")?;
+                println!("{conflicts}");
+                return Ok(());
+            }
+
+            if conflicts.is_empty() {
+                println!("No conflicting binds.");
+            }
+
+            for conflict in &conflicts {
+                print_bind_conflict(conflict);
+            }
+        }
+        Msg::SpawnedProcesses => {
+            let Response::SpawnedProcesses(processes) = response else {
+                bail!("unexpected response: expected SpawnedProcesses, got {response:?}");
+            };
+
+            if json {
+                let processes =
+                    serde_json::to_string(&processes).context("error formatting response")?;
+                println!("{processes}");
+                return Ok(());
+            }
+
+            if processes.is_empty() {
+                println!("No spawned processes are running.");
+            }
+
+            let now_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_millis() as u64);
+            for process in &processes {
+                print_spawned_process(process, now_ms);
+            }
+        }
+        Msg::ContainerPercents { .. } => {
+            let Response::ContainerPercents(percents) = response else {
+                bail!("unexpected response: expected ContainerPercents, got {response:?}");
+            };
+
+            if json {
+                let percents =
+                    serde_json::to_string(&percents).context("error formatting response")?;
+                println!("{percents}");
+                return Ok(());
+            }
+
+            print_container_percents(&percents);
+        }
+        Msg::SetContainerPercents { .. } => {
+            let Response::Handled = response else {
+                bail!("unexpected response: expected Handled, got {response:?}");
+            };
+        }
+        Msg::PowerSavingState => {
+            let Response::PowerSavingState(response) = response else {
+                bail!("unexpected response: expected PowerSavingState, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&response).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            let PowerSaving {
+                is_active,
+                on_battery,
+            } = response;
+            let state = if is_active { "on" } else { "off" };
+            let power = if on_battery { "battery" } else { "AC" };
+            println!("Power saving is {state}, running on {power}.");
+        }
     }
 
     Ok(())
 }
 
+fn container_address(id: Option<u64>, path: &[usize], root: bool) -> ContainerAddress {
+    if let Some(id) = id {
+        ContainerAddress::Window(id)
+    } else if root || !path.is_empty() {
+        ContainerAddress::Path(path.to_vec())
+    } else {
+        ContainerAddress::Focused
+    }
+}
+
+fn print_container_percents(percents: &ContainerPercents) {
+    let layout = match percents.layout {
+        LayoutTreeLayout::SplitH => "SplitH",
+        LayoutTreeLayout::SplitV => "SplitV",
+        LayoutTreeLayout::Tabbed => "Tabbed",
+        LayoutTreeLayout::Stacked => "Stacked",
+    };
+    let path = if percents.path.is_empty() {
+        String::from("root")
+    } else {
+        let path: Vec<_> = percents.path.iter().map(|idx| idx.to_string()).collect();
+        path.join(",")
+    };
+
+    println!(
+        "{layout} container at {path} on workspace {}:",
+        percents.workspace_id
+    );
+    for (idx, percent) in percents.percents.iter().enumerate() {
+        println!("  {idx}: {:.1}%", percent * 100.);
+    }
+}
+
 fn print_layout_tree(tree: &LayoutTree) {
     if let Some(id) = tree.workspace_id {
         if let Some(name) = &tree.workspace_name {
@@ -746,6 +1183,14 @@ fn print_window(window: &Window) {
         if window.is_floating { "yes" } else { "no" }
     );
 
+    if window.has_activity {
+        println!("  Has activity: yes");
+    }
+
+    if window.is_blocked_out_from_screencast {
+        println!("  Blocked out from screencasts: yes");
+    }
+
     if let Some(pid) = window.pid {
         println!("  PID: {pid}");
     } else {
@@ -765,10 +1210,19 @@ fn print_window(window: &Window) {
         tile_pos_in_workspace_view,
         window_offset_in_tile,
         is_sticky,
+        is_following_focus,
+        is_always_on_top,
+        pos_in_floating_stack,
     } = window.layout;
 
     println!("  Layout:");
     println!("    Is sticky: {}", if is_sticky { "yes" } else { "no" });
+    if is_following_focus {
+        println!("    Is following focus: yes");
+    }
+    if is_always_on_top {
+        println!("    Is always on top: yes");
+    }
     println!(
         "    Tile size: {} x {}",
         fmt_rounded(tile_size.0),
@@ -779,6 +1233,10 @@ fn print_window(window: &Window) {
         println!("    Scrolling position: column {}, tile {}", pos.0, pos.1);
     }
 
+    if let Some(pos) = pos_in_floating_stack {
+        println!("    Floating stack position: {pos}");
+    }
+
     if let Some(pos) = tile_pos_in_workspace_view {
         println!(
             "    Workspace-view position: {}, {}",
@@ -831,6 +1289,113 @@ fn print_cast(cast: &Cast) {
     }
 }
 
+fn print_scanout(name: &str, state: &OutputScanout) {
+    let mode = if state.direct {
+        "direct scanout"
+    } else {
+        "composited"
+    };
+    println!("Output {name}: {mode}");
+
+    if let Some(id) = state.window_id {
+        println!("  Candidate window: {id}");
+    }
+    if let Some(blocker) = state.blocker {
+        let reason = match blocker {
+            ScanoutBlocker::NoDrm => "not a DRM output",
+            ScanoutBlocker::Disabled => "disabled in the debug config",
+            ScanoutBlocker::NoFullscreenWindow => "no fullscreen window",
+            ScanoutBlocker::Overlay => "something is drawn on top of the fullscreen window",
+            ScanoutBlocker::Animation => "animation in progress",
+            ScanoutBlocker::NotOpaque => "window buffer doesn't cover the output opaquely",
+        };
+        println!("  Blocked: {reason}");
+    }
+}
+
+fn print_background_permission(permission: &BackgroundPermission) {
+    let decision = |allowed: Option<bool>| match allowed {
+        Some(true) => "allowed",
+        Some(false) => "denied",
+        None => "not asked",
+    };
+
+    println!("{}:", permission.app_id);
+    println!("  Run in background: {}", decision(permission.background));
+    println!("  Autostart: {}", decision(permission.autostart));
+}
+
+fn print_bind_conflict(conflict: &BindConflict) {
+    let reason = match &conflict.kind {
+        BindConflictKind::Duplicate { shadowed_by } => {
+            format!("never triggers, {shadowed_by} is bound to the same keys")
+        }
+        BindConflictKind::ResizeMode => String::from("doesn't trigger in the resize mode"),
+    };
+    println!("{} (mode {}): {reason}", conflict.key, conflict.mode);
+}
+
+fn print_spawned_process(process: &SpawnedProcess, now_ms: u64) {
+    let ago = now_ms.saturating_sub(process.started_ms) / 1000;
+    let (hours, minutes, seconds) = (ago / 3600, ago / 60 % 60, ago % 60);
+    println!(
+        "{:>8} {hours:>3}:{minutes:02}:{seconds:02} {}",
+        process.pgid,
+        process.command.join(" ")
+    );
+}
+
+fn print_log_message(message: &LogMessage, now_ms: u64) {
+    let ago = now_ms.saturating_sub(message.time_ms) as f64 / 1000.;
+    let level = match message.level {
+        LogLevel::Error => "ERROR",
+        LogLevel::Warn => " WARN",
+        LogLevel::Info => " INFO",
+    };
+    println!(
+        "{ago:>9.3}s ago {level} {}: {}",
+        message.target, message.message
+    );
+}
+
+fn print_layout_verification(verification: &LayoutVerification) {
+    println!(
+        "Checked {} workspaces with {} windows.",
+        verification.workspaces, verification.windows
+    );
+
+    if verification.violations.is_empty() {
+        println!("No problems found.");
+        return;
+    }
+
+    for violation in &verification.violations {
+        let kind = match violation.kind {
+            LayoutViolationKind::PercentSum => "child sizes",
+            LayoutViolationKind::DuplicateWindow => "duplicate window",
+            LayoutViolationKind::InvalidFocus => "invalid focus",
+            LayoutViolationKind::RedundantNesting => "redundant nesting",
+            LayoutViolationKind::BrokenLink => "broken link",
+            LayoutViolationKind::Cycle => "cycle",
+        };
+        print!("\n{kind}");
+        if let Some(id) = violation.workspace_id {
+            print!(" on workspace {id}");
+        }
+        if !violation.path.is_empty() {
+            print!(" at {:?}", violation.path);
+        }
+        println!(":\n  {}", violation.message);
+    }
+}
+
+fn format_config_value(value: ConfigValue) -> String {
+    match value {
+        ConfigValue::Bool(value) => value.to_string(),
+        ConfigValue::Number(value) => value.to_string(),
+    }
+}
+
 fn fmt_rounded(x: f64) -> String {
     let r = x.round();
     if (r - x).abs() <= 0.005 {
@@ -853,9 +1418,115 @@ fn ensure_absolute_path(path: &mut String) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Reads JSON from a file, or from stdin if the path is `-`.
+fn read_json<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let json = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin()).context("error reading stdin")?
+    } else {
+        std::fs::read_to_string(path).context("error reading the file")?
+    };
+    serde_json::from_str(&json).context("error parsing the JSON")
+}
+
+/// Follows the event stream and prints the fields of the focused window or workspace that change.
+fn watch_focused(msg: &Msg, socket: Socket) -> anyhow::Result<()> {
+    let mut state = EventStreamState::default();
+    let mut last = None;
+    let mut read_event = socket.read_events();
+    loop {
+        let event = read_event().context("error reading event from niri")?;
+        // Until the first full list arrives, nothing looks focused.
+        let is_full_list = match msg {
+            Msg::FocusedWindow => matches!(event, Event::WindowsChanged { .. }),
+            _ => matches!(event, Event::WorkspacesChanged { .. }),
+        };
+        state.apply(event);
+        if last.is_none() && !is_full_list {
+            continue;
+        }
+
+        let current = match msg {
+            Msg::FocusedWindow => {
+                let windows = &state.windows.windows;
+                json!(windows.values().find(|win| win.is_focused))
+            }
+            _ => {
+                let workspaces = &state.workspaces.workspaces;
+                json!(workspaces.values().find(|ws| ws.is_focused))
+            }
+        };
+        if let Some(diff) = json_diff(last.as_ref(), &current) {
+            println!("{diff}");
+            last = Some(current);
+        }
+    }
+}
+
+/// Returns the fields of `new` that differ from `old`, or `None` if nothing changed.
+///
+/// Anything other than two objects is returned in full.
+fn json_diff(old: Option<&Value>, new: &Value) -> Option<Value> {
+    match (old, new) {
+        (Some(old), new) if old == new => None,
+        (Some(Value::Object(old)), Value::Object(new)) => {
+            let changed = new
+                .iter()
+                .filter(|(key, value)| old.get(*key) != Some(value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            Some(Value::Object(changed))
+        }
+        _ => Some(new.clone()),
+    }
+}
+
+/// Builds an i3bar status line with a block per workspace and one for the focused window.
+fn i3bar_blocks(state: &EventStreamState) -> Vec<Value> {
+    let mut workspaces = state.workspaces.workspaces.values().collect::<Vec<_>>();
+    workspaces.sort_unstable_by(|a, b| a.output.cmp(&b.output).then(a.idx.cmp(&b.idx)));
+
+    let mut blocks = Vec::new();
+    for ws in workspaces {
+        // Same colors as the i3bar defaults for workspace buttons.
+        let (border, background, color) = if ws.is_urgent {
+            ("#2f343a", "#900000", "#ffffff")
+        } else if ws.is_focused {
+            ("#4c7899", "#285577", "#ffffff")
+        } else if ws.is_active {
+            ("#333333", "#5f676a", "#ffffff")
+        } else {
+            ("#333333", "#222222", "#888888")
+        };
+
+        let text = ws.name.clone().unwrap_or_else(|| ws.idx.to_string());
+        blocks.push(json!({
+            "name": "workspace",
+            "instance": ws.id.to_string(),
+            "full_text": text,
+            "urgent": ws.is_urgent,
+            "color": color,
+            "background": background,
+            "border": border,
+            "separator": false,
+        }));
+    }
+
+    let focused = state.windows.windows.values().find(|win| win.is_focused);
+    if let Some(win) = focused {
+        blocks.push(json!({
+            "name": "window",
+            "instance": win.id.to_string(),
+            "full_text": win.title.as_deref().unwrap_or(""),
+        }));
+    }
+
+    blocks
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_snapshot;
+    use niri_ipc::Workspace;
 
     use super::*;
 
@@ -869,4 +1540,57 @@ mod tests {
         assert_snapshot!(fmt_rounded(2.006), @"2.01");
         assert_snapshot!(fmt_rounded(2.1), @"2.10");
     }
+
+    #[test]
+    fn json_diff_keeps_changed_fields() {
+        let window = json!({ "id": 1, "title": "a", "is_floating": false });
+        assert_eq!(json_diff(None, &window), Some(window.clone()));
+        assert_eq!(json_diff(Some(&window), &window), None);
+
+        let renamed = json!({ "id": 1, "title": "b", "is_floating": false });
+        assert_eq!(
+            json_diff(Some(&window), &renamed),
+            Some(json!({ "title": "b" }))
+        );
+
+        assert_eq!(json_diff(Some(&window), &json!(null)), Some(json!(null)));
+        assert_eq!(json_diff(Some(&json!(null)), &window), Some(window.clone()));
+    }
+
+    #[test]
+    fn i3bar_blocks_follow_workspace_order() {
+        let workspace = |id, idx, output: &str| Workspace {
+            id,
+            idx,
+            name: None,
+            output: Some(String::from(output)),
+            is_urgent: false,
+            is_active: false,
+            is_focused: false,
+            active_window_id: None,
+        };
+        let workspaces = vec![
+            Workspace {
+                name: Some(String::from("chat")),
+                is_active: true,
+                ..workspace(3, 1, "HDMI-A-1")
+            },
+            workspace(2, 2, "DP-1"),
+            Workspace {
+                is_active: true,
+                is_focused: true,
+                ..workspace(1, 1, "DP-1")
+            },
+        ];
+
+        let mut state = EventStreamState::default();
+        state.apply(Event::WorkspacesChanged { workspaces });
+        let blocks = i3bar_blocks(&state);
+
+        let texts: Vec<_> = blocks.iter().map(|block| &block["full_text"]).collect();
+        assert_eq!(texts, [&json!("1"), &json!("2"), &json!("chat")]);
+        assert_eq!(blocks[0]["background"], "#285577");
+        assert_eq!(blocks[1]["background"], "#222222");
+        assert_eq!(blocks[2]["background"], "#5f676a");
+    }
 }
@@ -0,0 +1,1099 @@
+//! Socket speaking the i3 IPC protocol.
+//!
+//! i3 bars and scripts connect to this socket as they would to i3 or sway. Commands run as niri
+//! actions, while workspaces, outputs and the tree are put together from the event stream state
+//! and the container layout. Only the parts of the protocol that map onto niri are supported;
+//! other messages and commands reply with an error.
+
+use std::collections::HashMap;
+use std::io;
+use std::os::unix::net::UnixStream;
+
+use anyhow::{ensure, Context};
+use calloop::io::Async;
+use futures_util::io::AsyncReadExt;
+use futures_util::{select_biased, AsyncWrite, AsyncWriteExt, FutureExt as _};
+use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
+use niri_ipc::{
+    Action, Event, LayoutTreeLayout, LayoutTreeNode, Window, Workspace, WorkspaceReferenceArg,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::server::{run_action, ClientCtx};
+use crate::backend::IpcOutputMap;
+use crate::niri::State;
+use crate::utils::version;
+
+/// Name of the environment variable where i3 clients look for the socket.
+pub const SOCKET_PATH_ENV: &str = "I3SOCK";
+
+const MAGIC: &[u8] = b"i3-ipc";
+const HEADER_LEN: usize = MAGIC.len() + 8;
+// Don't let clients make us allocate arbitrary amounts of memory.
+const MAX_PAYLOAD_LEN: usize = 1024 * 1024;
+
+const RUN_COMMAND: u32 = 0;
+const GET_WORKSPACES: u32 = 1;
+const SUBSCRIBE: u32 = 2;
+const GET_OUTPUTS: u32 = 3;
+const GET_TREE: u32 = 4;
+const GET_MARKS: u32 = 5;
+const GET_VERSION: u32 = 7;
+const GET_BINDING_MODES: u32 = 8;
+
+const EVENT_WORKSPACE: u32 = 0x8000_0000;
+const EVENT_WINDOW: u32 = 0x8000_0003;
+
+// Nodes other than windows get ids above the range of window ids.
+const WORKSPACE_ID_BASE: u64 = 1 << 32;
+const OUTPUT_ID_BASE: u64 = 2 << 32;
+const CONTAINER_ID_BASE: u64 = 3 << 32;
+const ROOT_ID: u64 = 4 << 32;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+/// Events that a client subscribed to.
+#[derive(Debug, Clone, Copy)]
+struct Subscription {
+    workspace: bool,
+    window: bool,
+}
+
+/// State that i3 replies are put together from.
+struct View<'a> {
+    state: &'a EventStreamState,
+    outputs: &'a IpcOutputMap,
+}
+
+/// Turns niri events into i3 events.
+#[derive(Default)]
+struct EventTranslator {
+    state: EventStreamState,
+    /// Whether the initial workspace list arrived, after which workspace changes become events.
+    has_workspaces: bool,
+}
+
+/// i3 event to send once the niri event is applied to the state.
+enum Change {
+    Workspace {
+        change: &'static str,
+        id: u64,
+        old: Value,
+    },
+    Window {
+        change: &'static str,
+        id: u64,
+    },
+}
+
+pub(super) fn on_new_client(state: &mut State, stream: UnixStream) {
+    let _span = tracy_client::span!("i3::on_new_client");
+    trace!("new i3 IPC client connected");
+
+    let stream = match state.niri.event_loop.adapt_io(stream) {
+        Ok(stream) => stream,
+        Err(err) => {
+            warn!("error making i3 IPC stream async: {err:?}");
+            return;
+        }
+    };
+
+    let ctx = ClientCtx::new(state, false);
+    let future = async move {
+        if let Err(err) = handle_client(ctx, stream).await {
+            warn!("error handling i3 IPC client: {err:?}");
+        }
+    };
+    if let Err(err) = state.niri.scheduler.schedule(future) {
+        warn!("error scheduling i3 IPC stream future: {err:?}");
+    }
+}
+
+async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> anyhow::Result<()> {
+    let (mut read, mut write) = stream.split();
+
+    loop {
+        let mut header = [0; HEADER_LEN];
+        match read.read_exact(&mut header).await {
+            Ok(()) => (),
+            // Normal client disconnection.
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::UnexpectedEof | io::ErrorKind::BrokenPipe
+                ) =>
+            {
+                return Ok(())
+            }
+            Err(err) => return Err(err).context("error reading message header"),
+        }
+
+        let (len, kind) = parse_header(&header)?;
+        let mut payload = vec![0; len];
+        read.read_exact(&mut payload)
+            .await
+            .context("error reading message")?;
+
+        if kind == SUBSCRIBE {
+            let subscription = serde_json::from_slice::<Vec<String>>(&payload).map(|names| {
+                let has = |name: &str| names.iter().any(|x| x == name);
+                Subscription {
+                    workspace: has("workspace"),
+                    window: has("window"),
+                }
+            });
+            let reply = match &subscription {
+                Ok(_) => json!({ "success": true }),
+                Err(err) => json!({ "success": false, "error": err.to_string() }),
+            };
+            write
+                .write_all(&encode(kind, &reply))
+                .await
+                .context("error writing reply")?;
+
+            // i3 clients listen to events on a connection of their own, so it doesn't take
+            // requests anymore. Subscribing only to events that niri doesn't have keeps reading
+            // requests, so that the connection closes when the client goes away.
+            match subscription {
+                Ok(sub) if sub.workspace || sub.window => {
+                    return stream_events(&ctx, sub, write).await;
+                }
+                _ => continue,
+            }
+        }
+
+        let reply = process(&ctx, kind, &payload).await;
+        write
+            .write_all(&encode(kind, &reply))
+            .await
+            .context("error writing reply")?;
+    }
+}
+
+async fn process(ctx: &ClientCtx, kind: u32, payload: &[u8]) -> Value {
+    match kind {
+        RUN_COMMAND => run_command(ctx, &String::from_utf8_lossy(payload)).await,
+        GET_WORKSPACES => {
+            let state = ctx.event_stream_state.borrow();
+            let outputs = ctx.ipc_outputs.lock().unwrap();
+            let view = View::new(&state, &outputs);
+            view.workspaces()
+                .into_iter()
+                .map(|ws| view.workspace_info(ws))
+                .collect()
+        }
+        GET_OUTPUTS => {
+            let state = ctx.event_stream_state.borrow();
+            let outputs = ctx.ipc_outputs.lock().unwrap();
+            View::new(&state, &outputs).outputs_info()
+        }
+        GET_TREE => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let layouts: HashMap<_, _> = state
+                    .niri
+                    .layout
+                    .workspaces()
+                    .filter_map(|(_, _, ws)| Some((ws.id().get(), ws.layout_tree()?)))
+                    .collect();
+                let _ = tx.send_blocking(layouts);
+            });
+            let Ok(layouts) = rx.recv().await else {
+                return json!({ "success": false, "error": "error getting layout tree" });
+            };
+
+            let state = ctx.event_stream_state.borrow();
+            let outputs = ctx.ipc_outputs.lock().unwrap();
+            View::new(&state, &outputs).tree(&layouts)
+        }
+        GET_MARKS => json!([]),
+        GET_VERSION => {
+            let number = |s: &str| s.parse::<u32>().unwrap_or(0);
+            json!({
+                "major": number(env!("CARGO_PKG_VERSION_MAJOR")),
+                "minor": number(env!("CARGO_PKG_VERSION_MINOR")),
+                "patch": number(env!("CARGO_PKG_VERSION_PATCH")),
+                "human_readable": version(),
+                "loaded_config_file_name": "",
+            })
+        }
+        GET_BINDING_MODES => json!(["default"]),
+        _ => json!({ "success": false, "error": format!("unsupported message type {kind}") }),
+    }
+}
+
+async fn run_command(ctx: &ClientCtx, command: &str) -> Value {
+    let actions = {
+        let state = ctx.event_stream_state.borrow();
+        let workspaces: Vec<_> = state.workspaces.workspaces.values().cloned().collect();
+        parse_command(command, &workspaces)
+    };
+
+    let mut results = Vec::new();
+    for action in actions {
+        let result = match action {
+            Ok(action) => run_action(ctx, action).await,
+            Err(err) => Err(err),
+        };
+        results.push(match result {
+            Ok(()) => json!({ "success": true }),
+            Err(err) => json!({ "success": false, "error": err }),
+        });
+    }
+    Value::Array(results)
+}
+
+async fn stream_events(
+    ctx: &ClientCtx,
+    subscription: Subscription,
+    mut write: impl AsyncWrite + Unpin,
+) -> anyhow::Result<()> {
+    let (events, disconnect) = ctx.subscribe();
+    let mut translator = EventTranslator::default();
+
+    while let Ok(event) = events.recv().await {
+        let messages = {
+            let outputs = ctx.ipc_outputs.lock().unwrap();
+            translator.translate(event, &outputs)
+        };
+
+        for (kind, payload) in messages {
+            let wanted = match kind {
+                EVENT_WORKSPACE => subscription.workspace,
+                EVENT_WINDOW => subscription.window,
+                _ => false,
+            };
+            if !wanted {
+                continue;
+            }
+
+            let buf = encode(kind, &payload);
+            let res = select_biased! {
+                _ = disconnect.recv().fuse() => return Ok(()),
+                res = write.write_all(&buf).fuse() => res,
+            };
+
+            match res {
+                Ok(()) => (),
+                // Normal client disconnection.
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                res @ Err(_) => res.context("error writing event")?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_header(header: &[u8; HEADER_LEN]) -> anyhow::Result<(usize, u32)> {
+    let (magic, rest) = header.split_at(MAGIC.len());
+    ensure!(magic == MAGIC, "invalid magic string");
+
+    let (len, kind) = rest.split_at(4);
+    let len = u32::from_ne_bytes(len.try_into().unwrap());
+    let kind = u32::from_ne_bytes(kind.try_into().unwrap());
+
+    let len = usize::try_from(len).context("message is too long")?;
+    ensure!(len <= MAX_PAYLOAD_LEN, "message is too long");
+    Ok((len, kind))
+}
+
+fn encode(kind: u32, payload: &Value) -> Vec<u8> {
+    let payload = payload.to_string();
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(payload.as_bytes());
+    buf
+}
+
+/// Parses an i3 command list into niri actions, one per command.
+pub fn parse_command(command: &str, workspaces: &[Workspace]) -> Vec<Result<Action, String>> {
+    split_commands(command)
+        .into_iter()
+        .map(|command| {
+            parse_single_command(command, workspaces).map_err(|err| format!("{err}: {command}"))
+        })
+        .collect()
+}
+
+fn parse_single_command(command: &str, workspaces: &[Workspace]) -> Result<Action, String> {
+    if command.starts_with('[') {
+        return Err(String::from("criteria are not supported"));
+    }
+
+    let words = split_words(command);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let action = match words.as_slice() {
+        ["exec", ..] => {
+            // Commands go to the shell as written, quotes included.
+            let command = command.trim_start()["exec".len()..].trim_start();
+            let command = command.strip_prefix("--no-startup-id").unwrap_or(command);
+            let command = command.trim();
+            let command = match command.strip_prefix('"').and_then(|c| c.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\\\"", "\""),
+                None => String::from(command),
+            };
+            if command.is_empty() {
+                return Err(String::from("missing command"));
+            }
+            Action::SpawnSh { command }
+        }
+        ["kill"] => Action::CloseWindow { id: None },
+        ["focus", "left"] => Action::FocusColumnLeft {},
+        ["focus", "right"] => Action::FocusColumnRight {},
+        ["focus", "up"] => Action::FocusWindowUp {},
+        ["focus", "down"] => Action::FocusWindowDown {},
+        ["focus", "parent"] => Action::FocusParent {},
+        ["focus", "child"] => Action::FocusChild {},
+        ["focus", "floating"] => Action::FocusFloating {},
+        ["focus", "tiling"] => Action::FocusTiling {},
+        ["focus", "mode_toggle"] => Action::SwitchFocusBetweenFloatingAndTiling {},
+        ["move", "left"] => Action::SwapWindowLeft {},
+        ["move", "right"] => Action::SwapWindowRight {},
+        ["move", "up"] => Action::MoveWindowUp {},
+        ["move", "down"] => Action::MoveWindowDown {},
+        ["move", "container" | "window", "to", "workspace", rest @ ..]
+        | ["move", "to", "workspace", rest @ ..] => Action::MoveWindowToWorkspace {
+            window_id: None,
+            reference: workspace_reference(rest, workspaces)?,
+            focus: false,
+        },
+        ["workspace", "back_and_forth"] => Action::FocusWorkspacePrevious {},
+        ["workspace", "next" | "next_on_output"] => Action::FocusWorkspaceDown {},
+        ["workspace", "prev" | "prev_on_output"] => Action::FocusWorkspaceUp {},
+        ["workspace", rest @ ..] => Action::FocusWorkspace {
+            reference: workspace_reference(rest, workspaces)?,
+        },
+        ["layout", "splith"] => Action::SetLayoutSplitH {},
+        ["layout", "splitv"] => Action::SetLayoutSplitV {},
+        ["layout", "toggle", "split"] => Action::ToggleSplitLayout {},
+        ["layout", "stacking" | "stacked"] => Action::SetLayoutStacked {},
+        ["layout", "tabbed"] => Action::SetLayoutTabbed {},
+        ["split", "h" | "horizontal"] => Action::SplitHorizontal {},
+        ["split", "v" | "vertical"] => Action::SplitVertical {},
+        ["fullscreen"] | ["fullscreen", "toggle"] => Action::FullscreenWindow { id: None },
+        ["floating", "toggle"] => Action::ToggleWindowFloating { id: None },
+        ["floating", "enable"] => Action::MoveWindowToFloating { id: None },
+        ["floating", "disable"] => Action::MoveWindowToTiling { id: None },
+        ["reload"] => Action::LoadConfigFile {},
+        ["exit"] => Action::Quit {
+            skip_confirmation: false,
+        },
+        _ => return Err(String::from("unsupported command")),
+    };
+    Ok(action)
+}
+
+/// Resolves the workspace of a `workspace` or `move to workspace` command.
+fn workspace_reference(
+    words: &[&str],
+    workspaces: &[Workspace],
+) -> Result<WorkspaceReferenceArg, String> {
+    let words = words
+        .strip_prefix(&["--no-auto-back-and-forth"])
+        .unwrap_or(words);
+    let (by_number, words) = match words.strip_prefix(&["number"]) {
+        Some(words) => (true, words),
+        None => (false, words),
+    };
+    if words.is_empty() {
+        return Err(String::from("missing workspace"));
+    }
+
+    let name = words.join(" ");
+    let num = workspace_num(&name);
+    let matches = |ws: &&Workspace| {
+        if by_number {
+            num >= 0 && workspace_num(&workspace_name(ws)) == num
+        } else {
+            workspace_name(ws) == name
+        }
+    };
+
+    // Numbers and indices repeat across outputs, so prefer a workspace on the focused output.
+    let focused_output = workspaces
+        .iter()
+        .find(|ws| ws.is_focused)
+        .and_then(|ws| ws.output.as_deref());
+    let found = workspaces
+        .iter()
+        .filter(matches)
+        .min_by_key(|ws| (ws.output.as_deref() != focused_output, ws.id));
+    if let Some(ws) = found {
+        return Ok(WorkspaceReferenceArg::Id(ws.id));
+    }
+
+    // Fall back to an index, which goes to a new workspace past the last one.
+    match u8::try_from(num) {
+        Ok(idx) if idx > 0 => Ok(WorkspaceReferenceArg::Index(idx)),
+        _ => Err(format!("no workspace named {name}")),
+    }
+}
+
+/// Splits a command list at `,` and `;` outside of quotes.
+fn split_commands(command: &str) -> Vec<&str> {
+    let mut commands = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (idx, ch) in command.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match ch {
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            ',' | ';' if !quoted => {
+                commands.push(&command[start..idx]);
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    commands.push(&command[start..]);
+
+    commands
+        .into_iter()
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// Splits a command into words, removing quotes.
+fn split_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = command.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    word.get_or_insert_default().push(next);
+                }
+            }
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_default();
+            }
+            ch if ch.is_whitespace() && !quoted => words.extend(word.take()),
+            ch => word.get_or_insert_default().push(ch),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Returns the name that i3 clients see for the workspace.
+///
+/// Unnamed workspaces go by their index.
+fn workspace_name(ws: &Workspace) -> String {
+    ws.name.clone().unwrap_or_else(|| ws.idx.to_string())
+}
+
+/// Returns the number at the start of a workspace name like i3 does, or -1 if there's none.
+fn workspace_num(name: &str) -> i32 {
+    let digits = name.find(|c: char| !c.is_ascii_digit());
+    name[..digits.unwrap_or(name.len())].parse().unwrap_or(-1)
+}
+
+fn layout_name(layout: LayoutTreeLayout) -> (&'static str, &'static str) {
+    match layout {
+        LayoutTreeLayout::SplitH => ("splith", "horizontal"),
+        LayoutTreeLayout::SplitV => ("splitv", "vertical"),
+        LayoutTreeLayout::Tabbed => ("tabbed", "horizontal"),
+        LayoutTreeLayout::Stacked => ("stacked", "vertical"),
+    }
+}
+
+fn has_focus(node: &LayoutTreeNode) -> bool {
+    node.focused || node.children.iter().any(has_focus)
+}
+
+/// Returns the ids of the nodes with the one containing the focus first, like i3 focus lists.
+fn focus_order(nodes: &[Value], focused: Option<u64>) -> Vec<u64> {
+    let ids = nodes.iter().filter_map(|node| node["id"].as_u64());
+    let (mut first, rest): (Vec<_>, Vec<_>) = ids.partition(|id| Some(*id) == focused);
+    first.extend(rest);
+    first
+}
+
+/// Returns a node with the fields that all i3 nodes have.
+fn node(id: u64, kind: &str, name: Option<&str>, rect: Rect) -> Value {
+    json!({
+        "id": id,
+        "type": kind,
+        "name": name,
+        "layout": "splith",
+        "orientation": "none",
+        "focused": false,
+        "urgent": false,
+        "rect": rect,
+        "window": null,
+        "border": "none",
+        "fullscreen_mode": 0,
+        "sticky": false,
+        "marks": [],
+        "focus": [],
+        "nodes": [],
+        "floating_nodes": [],
+    })
+}
+
+impl Rect {
+    fn union(self, other: Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+}
+
+impl<'a> View<'a> {
+    fn new(state: &'a EventStreamState, outputs: &'a IpcOutputMap) -> Self {
+        Self { state, outputs }
+    }
+
+    /// Returns the enabled outputs sorted by name.
+    fn outputs(&self) -> Vec<&'a niri_ipc::Output> {
+        let mut outputs: Vec<_> = self
+            .outputs
+            .values()
+            .filter(|output| output.logical.is_some())
+            .collect();
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+        outputs
+    }
+
+    /// Returns the workspaces sorted by output and index.
+    fn workspaces(&self) -> Vec<&'a Workspace> {
+        let mut workspaces: Vec<_> = self.state.workspaces.workspaces.values().collect();
+        workspaces.sort_by(|a, b| (&a.output, a.idx).cmp(&(&b.output, b.idx)));
+        workspaces
+    }
+
+    fn windows_on(&self, ws: &Workspace) -> impl Iterator<Item = &'a Window> + '_ {
+        let id = ws.id;
+        let windows = self.state.windows.windows.values();
+        windows.filter(move |window| window.workspace_id == Some(id))
+    }
+
+    fn output_rect(&self, name: Option<&str>) -> Rect {
+        let output = self
+            .outputs
+            .values()
+            .find(|output| Some(output.name.as_str()) == name);
+        let Some(logical) = output.and_then(|output| output.logical) else {
+            return Rect::default();
+        };
+
+        Rect {
+            x: logical.x,
+            y: logical.y,
+            width: logical.width as i32,
+            height: logical.height as i32,
+        }
+    }
+
+    fn window_rect(&self, window: &Window) -> Rect {
+        let workspaces = &self.state.workspaces.workspaces;
+        let ws = window.workspace_id.and_then(|id| workspaces.get(&id));
+        let output = self.output_rect(ws.and_then(|ws| ws.output.as_deref()));
+
+        let (x, y) = window.layout.tile_pos_in_workspace_view.unwrap_or_default();
+        let (width, height) = window.layout.tile_size;
+        Rect {
+            x: output.x + x.round() as i32,
+            y: output.y + y.round() as i32,
+            width: width.round() as i32,
+            height: height.round() as i32,
+        }
+    }
+
+    /// Returns the workspace as in the `GET_WORKSPACES` reply.
+    fn workspace_info(&self, ws: &Workspace) -> Value {
+        let name = workspace_name(ws);
+        json!({
+            "id": WORKSPACE_ID_BASE + ws.id,
+            "num": workspace_num(&name),
+            "name": name,
+            "visible": ws.is_active,
+            "focused": ws.is_focused,
+            "urgent": ws.is_urgent,
+            "rect": self.output_rect(ws.output.as_deref()),
+            "output": ws.output,
+        })
+    }
+
+    fn outputs_info(&self) -> Value {
+        let mut outputs: Vec<_> = self.outputs.values().collect();
+        outputs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let outputs = outputs.into_iter().map(|output| {
+            let current_workspace = self
+                .state
+                .workspaces
+                .workspaces
+                .values()
+                .find(|ws| ws.is_active && ws.output.as_ref() == Some(&output.name))
+                .map(workspace_name);
+            json!({
+                "name": output.name,
+                "active": output.logical.is_some(),
+                "primary": false,
+                "rect": self.output_rect(Some(&output.name)),
+                "current_workspace": current_workspace,
+                "make": output.make,
+                "model": output.model,
+                "serial": output.serial,
+            })
+        });
+        outputs.collect()
+    }
+
+    fn workspace_node(&self, ws: &Workspace) -> Value {
+        let name = workspace_name(ws);
+        let rect = self.output_rect(ws.output.as_deref());
+        let mut node = node(WORKSPACE_ID_BASE + ws.id, "workspace", Some(&name), rect);
+        node["num"] = json!(workspace_num(&name));
+        node["output"] = json!(ws.output);
+        node["urgent"] = json!(ws.is_urgent);
+        node
+    }
+
+    fn window_node(&self, window: &Window, focused: bool) -> Value {
+        let kind = if window.is_floating {
+            "floating_con"
+        } else {
+            "con"
+        };
+        let title = window.title.as_deref();
+        let mut node = node(window.id, kind, title, self.window_rect(window));
+        node["focused"] = json!(focused);
+        node["urgent"] = json!(window.is_urgent);
+        node["sticky"] = json!(window.layout.is_sticky);
+        node["app_id"] = json!(window.app_id);
+        node["pid"] = json!(window.pid);
+        node["window_properties"] = json!({
+            "class": window.app_id,
+            "instance": window.app_id,
+            "title": title,
+        });
+        node
+    }
+
+    fn tree(&self, layouts: &HashMap<u64, LayoutTreeNode>) -> Value {
+        let mut next_id = CONTAINER_ID_BASE;
+        let workspaces = self.workspaces();
+
+        let mut output_nodes = Vec::new();
+        let mut focused_output = None;
+        let mut root_rect: Option<Rect> = None;
+        for (idx, output) in self.outputs().into_iter().enumerate() {
+            let id = OUTPUT_ID_BASE + idx as u64;
+            let rect = self.output_rect(Some(&output.name));
+            root_rect = Some(root_rect.map_or(rect, |r| r.union(rect)));
+
+            let mut active = None;
+            let mut nodes = Vec::new();
+            for ws in &workspaces {
+                if ws.output.as_ref() != Some(&output.name) {
+                    continue;
+                }
+
+                if ws.is_active {
+                    active = Some(WORKSPACE_ID_BASE + ws.id);
+                }
+                if ws.is_focused {
+                    focused_output = Some(id);
+                }
+                nodes.push(self.workspace_tree(ws, layouts.get(&ws.id), &mut next_id));
+            }
+
+            let mut node = node(id, "output", Some(&output.name), rect);
+            node["layout"] = json!("output");
+            node["focus"] = json!(focus_order(&nodes, active));
+            node["nodes"] = json!(nodes);
+            output_nodes.push(node);
+        }
+
+        let mut root = node(ROOT_ID, "root", Some("root"), root_rect.unwrap_or_default());
+        root["focus"] = json!(focus_order(&output_nodes, focused_output));
+        root["nodes"] = json!(output_nodes);
+        root
+    }
+
+    fn workspace_tree(
+        &self,
+        ws: &Workspace,
+        layout: Option<&LayoutTreeNode>,
+        next_id: &mut u64,
+    ) -> Value {
+        let mut node = self.workspace_node(ws);
+
+        let mut floating: Vec<_> = self
+            .windows_on(ws)
+            .filter(|window| window.is_floating)
+            .collect();
+        floating.sort_by_key(|window| window.layout.pos_in_floating_stack);
+        let focused_floating = floating
+            .iter()
+            .find(|window| window.is_focused)
+            .map(|window| window.id);
+
+        // The tiling tree keeps its focused node while a floating window has focus.
+        let tiling_focused = ws.is_focused && focused_floating.is_none();
+
+        let mut focus = Vec::new();
+        if let Some(root) = layout {
+            let children = match root.layout {
+                Some(_) => &root.children[..],
+                None => std::slice::from_ref(root),
+            };
+            let (nodes, _, focused) = self.tree_children(children, tiling_focused, next_id);
+            let layout = root.layout.unwrap_or(LayoutTreeLayout::SplitH);
+            let (layout, orientation) = layout_name(layout);
+            node["layout"] = json!(layout);
+            node["orientation"] = json!(orientation);
+            node["focused"] = json!(root.layout.is_some() && root.focused && tiling_focused);
+            focus = focus_order(&nodes, focused);
+            node["nodes"] = json!(nodes);
+        }
+
+        let floating: Vec<_> = floating
+            .into_iter()
+            .map(|window| self.window_node(window, window.is_focused))
+            .collect();
+        let floating_focus = focus_order(&floating, focused_floating);
+        if focused_floating.is_some() {
+            focus.splice(0..0, floating_focus);
+        } else {
+            focus.extend(floating_focus);
+        }
+        node["focus"] = json!(focus);
+        node["floating_nodes"] = json!(floating);
+        node
+    }
+
+    /// Converts tiling tree nodes, returning them with their bounding rect and the id of the
+    /// one containing the focus.
+    fn tree_children(
+        &self,
+        children: &[LayoutTreeNode],
+        tiling_focused: bool,
+        next_id: &mut u64,
+    ) -> (Vec<Value>, Option<Rect>, Option<u64>) {
+        let mut nodes = Vec::new();
+        let mut rect: Option<Rect> = None;
+        let mut focused = None;
+        for child in children {
+            let Some((node, child_rect)) = self.tree_node(child, tiling_focused, next_id) else {
+                continue;
+            };
+
+            if has_focus(child) {
+                focused = node["id"].as_u64();
+            }
+            rect = Some(rect.map_or(child_rect, |r| r.union(child_rect)));
+            nodes.push(node);
+        }
+        (nodes, rect, focused)
+    }
+
+    fn tree_node(
+        &self,
+        tree_node: &LayoutTreeNode,
+        tiling_focused: bool,
+        next_id: &mut u64,
+    ) -> Option<(Value, Rect)> {
+        let focused = tree_node.focused && tiling_focused;
+
+        let Some(layout) = tree_node.layout else {
+            let window = self.state.windows.windows.get(&tree_node.window_id?)?;
+            let node = self.window_node(window, focused);
+            return Some((node, self.window_rect(window)));
+        };
+
+        let id = *next_id;
+        *next_id += 1;
+
+        let (nodes, rect, focused_child) =
+            self.tree_children(&tree_node.children, tiling_focused, next_id);
+        let rect = rect.unwrap_or_default();
+        let (layout, orientation) = layout_name(layout);
+
+        let mut node = node(id, "con", None, rect);
+        node["layout"] = json!(layout);
+        node["orientation"] = json!(orientation);
+        node["focused"] = json!(focused);
+        node["focus"] = json!(focus_order(&nodes, focused_child));
+        node["nodes"] = json!(nodes);
+        Some((node, rect))
+    }
+}
+
+impl EventTranslator {
+    /// Applies the event to the state and returns the i3 events it amounts to.
+    fn translate(&mut self, event: Event, outputs: &IpcOutputMap) -> Vec<(u32, Value)> {
+        let mut messages = Vec::new();
+        let mut changes = Vec::new();
+
+        let view = View::new(&self.state, outputs);
+        let workspaces = &self.state.workspaces.workspaces;
+        let windows = &self.state.windows.windows;
+        match &event {
+            Event::WorkspacesChanged { workspaces: new } if self.has_workspaces => {
+                for ws in workspaces.values() {
+                    if !new.iter().any(|new| new.id == ws.id) {
+                        let current = view.workspace_node(ws);
+                        messages.push(workspace_event("empty", current, Value::Null));
+                    }
+                }
+                for ws in new {
+                    let change = match workspaces.get(&ws.id) {
+                        None => "init",
+                        Some(old) if old.name != ws.name => "rename",
+                        Some(_) => continue,
+                    };
+                    changes.push(Change::Workspace {
+                        change,
+                        id: ws.id,
+                        old: Value::Null,
+                    });
+                }
+            }
+            Event::WorkspaceActivated { id, focused: true } => {
+                let old = workspaces.values().find(|ws| ws.is_focused);
+                if old.is_none_or(|old| old.id != *id) {
+                    changes.push(Change::Workspace {
+                        change: "focus",
+                        id: *id,
+                        old: old.map_or(Value::Null, |old| view.workspace_node(old)),
+                    });
+                }
+            }
+            Event::WorkspaceUrgencyChanged { id, .. } => {
+                changes.push(Change::Workspace {
+                    change: "urgent",
+                    id: *id,
+                    old: Value::Null,
+                });
+            }
+            Event::WindowOpenedOrChanged { window } => {
+                let id = window.id;
+                match windows.get(&id) {
+                    None => changes.push(Change::Window { change: "new", id }),
+                    Some(old) => {
+                        if old.title != window.title {
+                            changes.push(Change::Window {
+                                change: "title",
+                                id,
+                            });
+                        }
+                        if old.is_floating != window.is_floating {
+                            changes.push(Change::Window {
+                                change: "floating",
+                                id,
+                            });
+                        }
+                        if old.workspace_id != window.workspace_id {
+                            changes.push(Change::Window { change: "move", id });
+                        }
+                    }
+                }
+                if window.is_focused && windows.get(&id).is_none_or(|old| !old.is_focused) {
+                    changes.push(Change::Window {
+                        change: "focus",
+                        id,
+                    });
+                }
+            }
+            Event::WindowClosed { id } => {
+                if let Some(window) = windows.get(id) {
+                    let container = view.window_node(window, false);
+                    messages.push(window_event("close", container));
+                }
+            }
+            Event::WindowFocusChanged { id: Some(id) } => {
+                changes.push(Change::Window {
+                    change: "focus",
+                    id: *id,
+                });
+            }
+            Event::WindowUrgencyChanged { id, .. } => {
+                changes.push(Change::Window {
+                    change: "urgent",
+                    id: *id,
+                });
+            }
+            _ => (),
+        }
+
+        self.has_workspaces |= matches!(event, Event::WorkspacesChanged { .. });
+        self.state.apply(event);
+
+        let view = View::new(&self.state, outputs);
+        for change in changes {
+            match change {
+                Change::Workspace { change, id, old } => {
+                    if let Some(ws) = self.state.workspaces.workspaces.get(&id) {
+                        let current = view.workspace_node(ws);
+                        messages.push(workspace_event(change, current, old));
+                    }
+                }
+                Change::Window { change, id } => {
+                    if let Some(window) = self.state.windows.windows.get(&id) {
+                        let container = view.window_node(window, window.is_focused);
+                        messages.push(window_event(change, container));
+                    }
+                }
+            }
+        }
+
+        messages
+    }
+}
+
+fn workspace_event(change: &str, current: Value, old: Value) -> (u32, Value) {
+    let payload = json!({ "change": change, "current": current, "old": old });
+    (EVENT_WORKSPACE, payload)
+}
+
+fn window_event(change: &str, container: Value) -> (u32, Value) {
+    let payload = json!({ "change": change, "container": container });
+    (EVENT_WINDOW, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn workspace(id: u64, idx: u8, name: Option<&str>, output: &str) -> Workspace {
+        Workspace {
+            id,
+            idx,
+            name: name.map(String::from),
+            output: Some(String::from(output)),
+            is_urgent: false,
+            is_active: false,
+            is_focused: false,
+            active_window_id: None,
+        }
+    }
+
+    fn parse(command: &str, workspaces: &[Workspace]) -> Value {
+        let actions = parse_command(command, workspaces);
+        let actions = actions.into_iter().map(|action| match action {
+            Ok(action) => serde_json::to_value(action).unwrap(),
+            Err(err) => json!({ "error": err }),
+        });
+        actions.collect()
+    }
+
+    #[test]
+    fn commands_map_to_actions() {
+        let actions = parse(
+            r#"focus left; layout tabbed, exec --no-startup-id notify-send "a, b"; [class="x"] kill"#,
+            &[],
+        );
+        assert_eq!(
+            actions,
+            json!([
+                { "FocusColumnLeft": {} },
+                { "SetLayoutTabbed": {} },
+                { "SpawnSh": { "command": r#"notify-send "a, b""# } },
+                { "error": r#"criteria are not supported: [class="x"] kill"# },
+            ])
+        );
+
+        let actions = parse("split v; layout toggle split; floating enable; bogus", &[]);
+        assert_eq!(
+            actions,
+            json!([
+                { "SplitVertical": {} },
+                { "ToggleSplitLayout": {} },
+                { "MoveWindowToFloating": { "id": null } },
+                { "error": "unsupported command: bogus" },
+            ])
+        );
+    }
+
+    #[test]
+    fn workspaces_resolve_by_name_and_number() {
+        let mut workspaces = [
+            workspace(1, 1, None, "DP-1"),
+            workspace(2, 2, Some("2: web"), "DP-1"),
+            workspace(3, 1, None, "HDMI-A-1"),
+        ];
+        workspaces[2].is_focused = true;
+
+        let actions = parse(
+            r#"workspace "2: web"; workspace number 2; workspace 1; move container to workspace number 5"#,
+            &workspaces,
+        );
+        assert_eq!(
+            actions,
+            json!([
+                { "FocusWorkspace": { "reference": { "Id": 2 } } },
+                { "FocusWorkspace": { "reference": { "Id": 2 } } },
+                { "FocusWorkspace": { "reference": { "Id": 3 } } },
+                {
+                    "MoveWindowToWorkspace": {
+                        "window_id": null,
+                        "reference": { "Index": 5 },
+                        "focus": false,
+                    }
+                },
+            ])
+        );
+
+        let actions = parse("workspace music", &workspaces);
+        assert_eq!(
+            actions,
+            json!([{ "error": "no workspace named music: workspace music" }])
+        );
+    }
+
+    #[test]
+    fn workspace_numbers_come_from_names() {
+        assert_eq!(workspace_num("3"), 3);
+        assert_eq!(workspace_num("10: mail"), 10);
+        assert_eq!(workspace_num("web"), -1);
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let message = encode(GET_TREE, &json!([]));
+        let header: [u8; HEADER_LEN] = message[..HEADER_LEN].try_into().unwrap();
+        assert_eq!(parse_header(&header).unwrap(), (2, GET_TREE));
+        assert_eq!(&message[HEADER_LEN..], b"[]");
+    }
+
+    /// Runs the fuzz corpus, where inputs of fixed fuzzer crashes go too.
+    #[test]
+    fn fuzz_corpus_doesnt_panic() {
+        let workspaces = [workspace(1, 1, Some("1: web"), "DP-1")];
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/corpus/i3_command");
+        for entry in fs::read_dir(dir).unwrap() {
+            let data = fs::read(entry.unwrap().path()).unwrap();
+            parse_command(&String::from_utf8_lossy(&data), &workspaces);
+        }
+    }
+}
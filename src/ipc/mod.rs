@@ -1,2 +1,3 @@
 pub mod client;
+pub mod i3;
 pub mod server;
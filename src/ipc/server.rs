@@ -17,8 +17,8 @@ use futures_util::{select_biased, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Fu
 use niri_config::OutputName;
 use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
 use niri_ipc::{
-    Action, Event, KeyboardLayouts, OutputConfigChanged, Overview, Reply, Request, Response,
-    Timestamp, WindowLayout, Workspace,
+    Action, ConfigValue, ContainerAddress, Event, KeyboardLayouts, OutputConfigChanged, Overview,
+    PowerSaving, Reply, Request, Response, Timestamp, WindowLayout, Workspace,
 };
 use smithay::desktop::layer_map_for_output;
 use smithay::input::pointer::{
@@ -32,10 +32,13 @@ use smithay::wayland::shell::wlr_layer::{KeyboardInteractivity, Layer};
 
 use crate::backend::IpcOutputMap;
 use crate::cursor::CursorOverride;
+use crate::input::macro_playback::{self, MacroStep};
 use crate::input::pick_window_grab::PickWindowGrab;
+use crate::ipc::i3;
 use crate::layout::workspace::WorkspaceId;
+use crate::layout::{ContainerTarget, Layout};
 use crate::niri::State;
-use crate::utils::{version, with_toplevel_role};
+use crate::utils::{recent_logs, spawning, version, with_toplevel_role, workspace_export};
 use crate::window::Mapped;
 
 // If an event stream client fails to read events fast enough that we accumulate more than this
@@ -47,16 +50,22 @@ pub struct IpcServer {
     ///
     /// This is `None` when creating `IpcServer` without a socket.
     pub socket_path: Option<PathBuf>,
+    /// Path to the read-only IPC socket, which only accepts requests that don't change anything.
+    pub read_only_socket_path: Option<PathBuf>,
+    /// Path to the socket speaking the i3 IPC protocol, if enabled in the config.
+    pub i3_socket_path: Option<PathBuf>,
     event_streams: Rc<RefCell<Vec<EventStreamSender>>>,
     event_stream_state: Rc<RefCell<EventStreamState>>,
 }
 
-struct ClientCtx {
-    event_loop: LoopHandle<'static, State>,
-    scheduler: Scheduler<()>,
-    ipc_outputs: Arc<Mutex<IpcOutputMap>>,
+pub(super) struct ClientCtx {
+    /// Whether the client connected to the read-only socket.
+    read_only: bool,
+    pub(super) event_loop: LoopHandle<'static, State>,
+    pub(super) scheduler: Scheduler<()>,
+    pub(super) ipc_outputs: Arc<Mutex<IpcOutputMap>>,
     event_streams: Rc<RefCell<Vec<EventStreamSender>>>,
-    event_stream_state: Rc<RefCell<EventStreamState>>,
+    pub(super) event_stream_state: Rc<RefCell<EventStreamState>>,
 }
 
 struct EventStreamClient {
@@ -74,40 +83,44 @@ impl IpcServer {
     pub fn start(
         event_loop: &LoopHandle<'static, State>,
         wayland_socket_name: Option<&OsStr>,
+        i3_socket: bool,
     ) -> anyhow::Result<Self> {
         let _span = tracy_client::span!("Ipc::start");
 
-        let socket_path = if let Some(wayland_socket_name) = wayland_socket_name {
-            let wayland_socket_name = wayland_socket_name.to_string_lossy();
-            let socket_name = format!("niri.{wayland_socket_name}.{}.sock", process::id());
-            let mut socket_path = socket_dir();
-            socket_path.push(socket_name);
-
-            let listener = UnixListener::bind(&socket_path).context("error binding socket")?;
-            listener
-                .set_nonblocking(true)
-                .context("error setting socket to non-blocking")?;
-
-            let source = Generic::new(listener, Interest::READ, Mode::Level);
-            event_loop
-                .insert_source(source, |_, socket, state| {
-                    match socket.accept() {
-                        Ok((stream, _)) => on_new_ipc_client(state, stream),
-                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => (),
-                        Err(e) => return Err(e),
-                    }
-
-                    Ok(PostAction::Continue)
-                })
-                .unwrap();
-
-            Some(socket_path)
-        } else {
-            None
-        };
+        let mut socket_path = None;
+        let mut read_only_socket_path = None;
+        let mut i3_socket_path = None;
+        if let Some(name) = wayland_socket_name {
+            let name = format!("niri.{}.{}", name.to_string_lossy(), process::id());
+
+            let path = socket_dir().join(format!("{name}.sock"));
+            listen(event_loop, &path, |state, stream| {
+                on_new_ipc_client(state, stream, false)
+            })
+            .context("error binding socket")?;
+            socket_path = Some(path);
+
+            let path = socket_dir().join(format!("{name}.read-only.sock"));
+            listen(event_loop, &path, |state, stream| {
+                on_new_ipc_client(state, stream, true)
+            })
+            .context("error binding read-only socket")?;
+            read_only_socket_path = Some(path);
+
+            // The i3 socket is optional, so niri IPC keeps working if it fails.
+            if i3_socket {
+                let path = socket_dir().join(format!("{name}.i3.sock"));
+                match listen(event_loop, &path, i3::on_new_client) {
+                    Ok(()) => i3_socket_path = Some(path),
+                    Err(err) => warn!("error binding i3 socket: {err:?}"),
+                }
+            }
+        }
 
         Ok(Self {
             socket_path,
+            read_only_socket_path,
+            i3_socket_path,
             event_streams: Rc::new(RefCell::new(Vec::new())),
             event_stream_state: Rc::new(RefCell::new(EventStreamState::default())),
         })
@@ -139,12 +152,45 @@ impl IpcServer {
 
 impl Drop for IpcServer {
     fn drop(&mut self) {
-        if let Some(socket_path) = &self.socket_path {
-            let _ = unlink(socket_path);
+        let socket_paths = [
+            &self.socket_path,
+            &self.read_only_socket_path,
+            &self.i3_socket_path,
+        ];
+        for socket_path in socket_paths {
+            if let Some(socket_path) = socket_path {
+                let _ = unlink(socket_path);
+            }
         }
     }
 }
 
+fn listen(
+    event_loop: &LoopHandle<'static, State>,
+    socket_path: &Path,
+    on_client: impl Fn(&mut State, UnixStream) + 'static,
+) -> anyhow::Result<()> {
+    let listener = UnixListener::bind(socket_path)?;
+    listener
+        .set_nonblocking(true)
+        .context("error setting socket to non-blocking")?;
+
+    let source = Generic::new(listener, Interest::READ, Mode::Level);
+    event_loop
+        .insert_source(source, move |_, socket, state| {
+            match socket.accept() {
+                Ok((stream, _)) => on_client(state, stream),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => (),
+                Err(e) => return Err(e),
+            }
+
+            Ok(PostAction::Continue)
+        })
+        .unwrap();
+
+    Ok(())
+}
+
 fn socket_dir() -> PathBuf {
     BaseDirs::new()
         .as_ref()
@@ -153,7 +199,7 @@ fn socket_dir() -> PathBuf {
         .unwrap_or_else(env::temp_dir)
 }
 
-fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
+fn on_new_ipc_client(state: &mut State, stream: UnixStream, read_only: bool) {
     let _span = tracy_client::span!("on_new_ipc_client");
     trace!("new IPC client connected");
 
@@ -165,16 +211,7 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
         }
     };
 
-    let ipc_server = state.niri.ipc_server.as_ref().unwrap();
-
-    let ctx = ClientCtx {
-        event_loop: state.niri.event_loop.clone(),
-        scheduler: state.niri.scheduler.clone(),
-        ipc_outputs: state.backend.ipc_outputs(),
-        event_streams: ipc_server.event_streams.clone(),
-        event_stream_state: ipc_server.event_stream_state.clone(),
-    };
-
+    let ctx = ClientCtx::new(state, read_only);
     let future = async move {
         if let Err(err) = handle_client(ctx, stream).await {
             warn!("error handling IPC client: {err:?}");
@@ -185,6 +222,51 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
     }
 }
 
+impl ClientCtx {
+    pub(super) fn new(state: &State, read_only: bool) -> Self {
+        let ipc_server = state.niri.ipc_server.as_ref().unwrap();
+        Self {
+            read_only,
+            event_loop: state.niri.event_loop.clone(),
+            scheduler: state.niri.scheduler.clone(),
+            ipc_outputs: state.backend.ipc_outputs(),
+            event_streams: ipc_server.event_streams.clone(),
+            event_stream_state: ipc_server.event_stream_state.clone(),
+        }
+    }
+
+    /// Subscribes to the event stream.
+    ///
+    /// The stream starts with events that replicate the current state. The second receiver fires
+    /// when the stream gets dropped for reading events too slowly.
+    pub(super) fn subscribe(&self) -> (Receiver<Event>, Receiver<()>) {
+        let (events_tx, events_rx) = async_channel::bounded(EVENT_STREAM_BUFFER_SIZE);
+        let (disconnect_tx, disconnect_rx) = async_channel::bounded(1);
+
+        // Send the initial state.
+        {
+            let state = self.event_stream_state.borrow();
+            for event in state.replicate() {
+                events_tx
+                    .try_send(event)
+                    .expect("initial event burst had more events than buffer size");
+            }
+        }
+
+        // Add it to the list.
+        {
+            let mut streams = self.event_streams.borrow_mut();
+            let sender = EventStreamSender {
+                events: events_tx,
+                disconnect: disconnect_tx,
+            };
+            streams.push(sender);
+        }
+
+        (events_rx, disconnect_rx)
+    }
+}
+
 async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> anyhow::Result<()> {
     let (read, mut write) = stream.split();
     let mut read = BufReader::new(read);
@@ -203,9 +285,7 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
             }
         }
 
-        let request = serde_json::from_slice(&buf)
-            .context("error parsing request")
-            .map_err(|err| err.to_string());
+        let request = parse_request(&buf, ctx.read_only);
         let requested_error = matches!(request, Ok(Request::ReturnError));
         let requested_event_stream = matches!(request, Ok(Request::EventStream));
 
@@ -226,13 +306,12 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
         write.write_all(&buf).await.context("error writing reply")?;
 
         if requested_event_stream {
-            let (events_tx, events_rx) = async_channel::bounded(EVENT_STREAM_BUFFER_SIZE);
-            let (disconnect_tx, disconnect_rx) = async_channel::bounded(1);
+            let (events, disconnect) = ctx.subscribe();
 
             // Spawn a task for the client.
             let client = EventStreamClient {
-                events: events_rx,
-                disconnect: disconnect_rx,
+                events,
+                disconnect,
                 write: Box::new(write) as _,
             };
             let future = async move {
@@ -244,32 +323,34 @@ async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> an
                 warn!("error scheduling IPC event stream future: {err:?}");
             }
 
-            // Send the initial state.
-            {
-                let state = ctx.event_stream_state.borrow();
-                for event in state.replicate() {
-                    events_tx
-                        .try_send(event)
-                        .expect("initial event burst had more events than buffer size");
-                }
-            }
-
-            // Add it to the list.
-            {
-                let mut streams = ctx.event_streams.borrow_mut();
-                let sender = EventStreamSender {
-                    events: events_tx,
-                    disconnect: disconnect_tx,
-                };
-                streams.push(sender);
-            }
-
             return Ok(());
         }
     }
 }
 
+/// Parses a request line, refusing requests that the socket doesn't allow.
+///
+/// This runs on untrusted input before anything reaches the compositor, so the fuzz targets in
+/// `fuzz/` go through it.
+pub fn parse_request(buf: &[u8], read_only: bool) -> Result<Request, String> {
+    let request = serde_json::from_slice(buf)
+        .context("error parsing request")
+        .map_err(|err| err.to_string())?;
+
+    if read_only && !is_read_only(&request) {
+        return Err(String::from(
+            "this request is not allowed on the read-only socket",
+        ));
+    }
+
+    Ok(request)
+}
+
 async fn process(ctx: &ClientCtx, request: Request) -> Reply {
+    if let Some(permission) = request_permission(&request) {
+        check_permission(ctx, permission).await?;
+    }
+
     let response = match request {
         Request::ReturnError => return Err(String::from("example compositor error")),
         Request::Version => Response::Version(version()),
@@ -380,23 +461,7 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             Response::PickedColor(color)
         }
         Request::Action(action) => {
-            validate_action(&action)?;
-
-            let (tx, rx) = async_channel::bounded(1);
-
-            let action = niri_config::Action::from(action);
-            ctx.event_loop.insert_idle(move |state| {
-                // Make sure some logic like workspace clean-up has a chance to run before doing
-                // actions.
-                state.niri.advance_animations();
-                state.do_action(action, false);
-                let _ = tx.send_blocking(());
-            });
-
-            // Wait until the action has been processed before returning. This is important for a
-            // few actions, for instance for DoScreenTransition this wait ensures that the screen
-            // contents were sampled into the texture.
-            let _ = rx.recv().await;
+            run_action(ctx, action).await?;
             Response::Handled
         }
         Request::Output { output, action } => {
@@ -405,8 +470,11 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let ipc_outputs = ctx.ipc_outputs.lock().unwrap();
             let found = ipc_outputs
                 .values()
-                .any(|o| OutputName::from_ipc_output(o).matches(&output));
-            let response = if found {
+                .find(|o| OutputName::from_ipc_output(o).matches(&output));
+            if let Some(ipc_output) = found {
+                check_mode_is_supported(ipc_output, &action)?;
+            }
+            let response = if found.is_some() {
                 OutputConfigChanged::Applied
             } else {
                 OutputConfigChanged::OutputWasMissing
@@ -466,11 +534,311 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let casts = state.casts.casts.values().cloned().collect();
             Response::Casts(casts)
         }
+        Request::CreateHeadlessOutput {
+            width,
+            height,
+            scale,
+            name,
+        } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.create_headless_output(width, height, scale, None, name);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            let name = result.map_err(|_| String::from("error creating output"))??;
+            Response::OutputCreated(name)
+        }
+        Request::DestroyOutput { output } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.destroy_output(&output);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error destroying output"))??;
+            Response::Handled
+        }
+        Request::GetConfigValue { path } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.niri.config.borrow().tunable_value(&path);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            let value = result.map_err(|_| String::from("error getting config value"))??;
+            Response::ConfigValue(value)
+        }
+        Request::SetConfigValue { path, value } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.set_config_value(&path, value);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error setting config value"))??;
+            Response::Handled
+        }
+        Request::WorkspaceThumbnail { id, max_size } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state
+                    .backend
+                    .with_primary_renderer(|renderer| {
+                        state.niri.workspace_thumbnail(renderer, id, max_size)
+                    })
+                    .unwrap_or_else(|| Err(anyhow::anyhow!("no renderer available")))
+                    .map_err(|err| format!("{err:#}"));
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            let thumbnail =
+                result.map_err(|_| String::from("error getting workspace thumbnail"))??;
+            Response::WorkspaceThumbnail(thumbnail)
+        }
+        Request::DebugScanout => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let scanout = state
+                    .niri
+                    .output_state
+                    .iter()
+                    .map(|(output, state)| (output.name(), state.scanout.to_ipc()))
+                    .collect();
+                let _ = tx.send_blocking(scanout);
+            });
+            let result = rx.recv().await;
+            let scanout = result.map_err(|_| String::from("error getting scanout state"))?;
+            Response::DebugScanout(scanout)
+        }
+        Request::RecentLogs { level, limit } => {
+            Response::RecentLogs(recent_logs::recent(level, limit))
+        }
+        Request::DebugVerifyLayout => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let verification = state.niri.layout.check_invariants();
+                let _ = tx.send_blocking(verification);
+            });
+            let result = rx.recv().await;
+            let verification = result.map_err(|_| String::from("error verifying the layout"))?;
+            Response::DebugVerifyLayout(verification)
+        }
+        Request::PlayMacro { steps } => {
+            let steps = steps
+                .into_iter()
+                .map(|step| {
+                    if let niri_ipc::MacroStep::Action(action) = &step {
+                        validate_action(action)?;
+                    }
+                    MacroStep::try_from(step)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                macro_playback::play(state, steps, tx);
+            });
+
+            // Reply once the whole macro has played.
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error playing the macro"))??;
+            Response::Handled
+        }
+        Request::ExportWorkspace => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let export = workspace_export::export(&state.niri);
+                let _ = tx.send_blocking(export);
+            });
+            let result = rx.recv().await;
+            let export = result.map_err(|_| String::from("error exporting workspace"))?;
+            Response::WorkspaceExport(export)
+        }
+        Request::ImportWorkspace { export } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = workspace_export::import(state, export);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error importing workspace"))??;
+            Response::Handled
+        }
+        Request::BackgroundPermissions => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let permissions = state.niri.background_permissions.to_ipc();
+                let _ = tx.send_blocking(permissions);
+            });
+            let result = rx.recv().await;
+            let permissions =
+                result.map_err(|_| String::from("error getting background permissions"))?;
+            Response::BackgroundPermissions(permissions)
+        }
+        Request::BindConflicts => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let conflicts = state.niri.bind_conflicts.clone();
+                let _ = tx.send_blocking(conflicts);
+            });
+            let result = rx.recv().await;
+            let conflicts = result.map_err(|_| String::from("error getting bind conflicts"))?;
+            Response::BindConflicts(conflicts)
+        }
+        Request::SpawnedProcesses => Response::SpawnedProcesses(spawning::spawned_processes()),
+        Request::ContainerPercents { container } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result =
+                    with_container_target(&mut state.niri.layout, &container, |layout, target| {
+                        layout.container_percents(target)
+                    });
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            let percents =
+                result.map_err(|_| String::from("error getting container percents"))??;
+            Response::ContainerPercents(percents)
+        }
+        Request::SetContainerPercents {
+            container,
+            percents,
+        } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result =
+                    with_container_target(&mut state.niri.layout, &container, |layout, target| {
+                        layout.set_container_percents(target, &percents)
+                    });
+                if result.is_ok() {
+                    state.niri.queue_redraw_all();
+                }
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error setting container percents"))??;
+            Response::Handled
+        }
+        Request::PowerSavingState => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let power_saving = PowerSaving {
+                    is_active: state.niri.power_saving,
+                    on_battery: state.niri.on_battery,
+                };
+                let _ = tx.send_blocking(power_saving);
+            });
+            let result = rx.recv().await;
+            let power_saving =
+                result.map_err(|_| String::from("error getting power saving state"))?;
+            Response::PowerSavingState(power_saving)
+        }
     };
 
     Ok(response)
 }
 
+/// Runs the action unless the config doesn't allow it over IPC.
+pub(super) async fn run_action(ctx: &ClientCtx, action: Action) -> Result<(), String> {
+    let (name, action) = prepare_action(action)?;
+
+    let (tx, rx) = async_channel::bounded(1);
+    ctx.event_loop.insert_idle(move |state| {
+        if !state.niri.config.borrow().ipc.allows_action(&name) {
+            let msg = format!("action {name} is not in ipc {{ allowed-actions }}");
+            let _ = tx.send_blocking(Err(msg));
+            return;
+        }
+
+        // Make sure some logic like workspace clean-up has a chance to run before doing actions.
+        state.niri.advance_animations();
+        state.do_action(action, false);
+        let _ = tx.send_blocking(Ok(()));
+    });
+
+    // Wait until the action has been processed before returning. This is important for a few
+    // actions, for instance for DoScreenTransition this wait ensures that the screen contents were
+    // sampled into the texture.
+    if let Ok(Err(msg)) = rx.recv().await {
+        return Err(msg);
+    }
+    Ok(())
+}
+
+/// Fails unless the config allows requests needing this permission over IPC.
+async fn check_permission(ctx: &ClientCtx, permission: &'static str) -> Result<(), String> {
+    let (tx, rx) = async_channel::bounded(1);
+    ctx.event_loop.insert_idle(move |state| {
+        let result = check_permission_in(&state.niri.config.borrow().ipc, permission);
+        let _ = tx.send_blocking(result);
+    });
+    let result = rx.recv().await;
+    result.map_err(|_| String::from("error checking ipc permissions"))?
+}
+
+fn check_permission_in(ipc: &niri_config::Ipc, permission: &str) -> Result<(), String> {
+    if !ipc.allows_action(permission) {
+        return Err(format!("{permission} is not in ipc {{ allowed-actions }}"));
+    }
+    Ok(())
+}
+
+/// Returns the name in `ipc { allowed-actions }` that the request needs, if any.
+///
+/// Actions are checked by their own names when they run. Requests that only read the state need
+/// no permission.
+fn request_permission(request: &Request) -> Option<&'static str> {
+    match request {
+        Request::Output { .. } => Some("output"),
+        Request::CreateHeadlessOutput { .. } | Request::DestroyOutput { .. } => {
+            Some("headless-output")
+        }
+        Request::SetConfigValue { .. } => Some("set-config-value"),
+        Request::SetContainerPercents { .. } => Some("set-container-percents"),
+        Request::PickWindow => Some("pick-window"),
+        Request::PickColor => Some("pick-color"),
+        Request::WorkspaceThumbnail { .. } => Some("workspace-thumbnail"),
+        // Importing launches the applications of the workspace.
+        Request::ImportWorkspace { .. } => Some("spawn"),
+        Request::Version
+        | Request::Outputs
+        | Request::Workspaces
+        | Request::Windows
+        | Request::Layers
+        | Request::KeyboardLayouts
+        | Request::FocusedOutput
+        | Request::FocusedWindow
+        | Request::Action(_)
+        | Request::EventStream
+        | Request::ReturnError
+        | Request::OverviewState
+        | Request::LayoutTree
+        | Request::Casts
+        | Request::GetConfigValue { .. }
+        | Request::DebugScanout
+        | Request::DebugVerifyLayout
+        | Request::RecentLogs { .. }
+        // Macros are refused altogether while the allowlist is set.
+        | Request::PlayMacro { .. }
+        | Request::ExportWorkspace
+        | Request::BackgroundPermissions
+        | Request::BindConflicts
+        | Request::SpawnedProcesses
+        | Request::ContainerPercents { .. }
+        | Request::PowerSavingState => None,
+    }
+}
+
+/// Checks an IPC action and converts it into a config action for `State::do_action()`.
+///
+/// Also returns the action name to check against the allowlist.
+pub fn prepare_action(action: Action) -> Result<(String, niri_config::Action), String> {
+    validate_action(&action)?;
+    let name = action.name();
+    Ok((name, niri_config::Action::from(action)))
+}
+
 fn validate_action(action: &Action) -> Result<(), String> {
     if let Action::Screenshot { path, .. }
     | Action::ScreenshotScreen { path, .. }
@@ -488,6 +856,49 @@ fn validate_action(action: &Action) -> Result<(), String> {
     Ok(())
 }
 
+/// Returns whether the request can go through the read-only socket.
+///
+/// Read-only requests don't change anything and don't show the contents of the screen.
+fn is_read_only(request: &Request) -> bool {
+    match request {
+        Request::Version
+        | Request::Outputs
+        | Request::Workspaces
+        | Request::Windows
+        | Request::Layers
+        | Request::KeyboardLayouts
+        | Request::FocusedOutput
+        | Request::FocusedWindow
+        | Request::EventStream
+        | Request::ReturnError
+        | Request::OverviewState
+        | Request::LayoutTree
+        | Request::Casts
+        | Request::GetConfigValue { .. }
+        | Request::DebugScanout
+        | Request::DebugVerifyLayout
+        | Request::BindConflicts
+        | Request::ContainerPercents { .. }
+        | Request::PowerSavingState => true,
+        Request::PickWindow
+        | Request::PickColor
+        | Request::Action(_)
+        | Request::Output { .. }
+        | Request::CreateHeadlessOutput { .. }
+        | Request::DestroyOutput { .. }
+        | Request::SetConfigValue { .. }
+        | Request::WorkspaceThumbnail { .. }
+        | Request::PlayMacro { .. }
+        | Request::ImportWorkspace { .. }
+        | Request::SetContainerPercents { .. }
+        // These show logs, command lines and app ids, which can contain private data.
+        | Request::RecentLogs { .. }
+        | Request::ExportWorkspace
+        | Request::BackgroundPermissions
+        | Request::SpawnedProcesses => false,
+    }
+}
+
 async fn handle_event_stream_client(client: EventStreamClient) -> anyhow::Result<()> {
     let EventStreamClient {
         events,
@@ -515,6 +926,26 @@ async fn handle_event_stream_client(client: EventStreamClient) -> anyhow::Result
     Ok(())
 }
 
+/// Resolves a container address from IPC and runs `f` with it.
+fn with_container_target<T>(
+    layout: &mut Layout<Mapped>,
+    address: &ContainerAddress,
+    f: impl FnOnce(&mut Layout<Mapped>, ContainerTarget<Mapped>) -> Result<T, String>,
+) -> Result<T, String> {
+    match address {
+        ContainerAddress::Focused => f(layout, ContainerTarget::Window(None)),
+        ContainerAddress::Path(path) => f(layout, ContainerTarget::Path(path)),
+        ContainerAddress::Window(id) => {
+            let window = layout
+                .windows()
+                .find(|(_, m)| m.id().get() == *id)
+                .map(|(_, m)| m.window.clone())
+                .ok_or_else(|| format!("window {id} not found"))?;
+            f(layout, ContainerTarget::Window(Some(&window)))
+        }
+    }
+}
+
 fn make_ipc_window(
     mapped: &Mapped,
     workspace_id: Option<WorkspaceId>,
@@ -522,13 +953,18 @@ fn make_ipc_window(
 ) -> niri_ipc::Window {
     with_toplevel_role(mapped.toplevel(), |role| niri_ipc::Window {
         id: mapped.id().get(),
-        title: role.title.clone(),
+        title: mapped
+            .formatted_title()
+            .map(str::to_owned)
+            .or_else(|| role.title.clone()),
         app_id: role.app_id.clone(),
         pid: mapped.credentials().map(|c| c.pid),
         workspace_id: workspace_id.map(|id| id.get()),
         is_focused: mapped.is_focused(),
         is_floating: mapped.is_floating(),
         is_urgent: mapped.is_urgent(),
+        has_activity: mapped.has_activity(),
+        is_blocked_out_from_screencast: mapped.is_blocked_out_from_screencast(),
         layout,
         focus_timestamp: mapped.get_focus_timestamp().map(Timestamp::from),
     })
@@ -721,11 +1157,15 @@ impl State {
             };
 
             let workspace_id = ws_id.map(|id| id.get());
-            let mut changed =
-                ipc_win.workspace_id != workspace_id || ipc_win.is_floating != mapped.is_floating();
+            let mut changed = ipc_win.workspace_id != workspace_id
+                || ipc_win.is_floating != mapped.is_floating()
+                || ipc_win.has_activity != mapped.has_activity()
+                || ipc_win.is_blocked_out_from_screencast
+                    != mapped.is_blocked_out_from_screencast();
 
             changed |= with_toplevel_role(mapped.toplevel(), |role| {
-                ipc_win.title != role.title || ipc_win.app_id != role.app_id
+                let title = mapped.formatted_title().or(role.title.as_deref());
+                ipc_win.title.as_deref() != title || ipc_win.app_id != role.app_id
             });
 
             if changed {
@@ -809,6 +1249,28 @@ impl State {
         server.send_event(event);
     }
 
+    pub fn ipc_refresh_switches(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.switches;
+        let lid_closed = self.niri.is_lid_closed;
+        let tablet_mode = self.niri.is_tablet_mode;
+
+        if state.lid_closed == lid_closed && state.tablet_mode == tablet_mode {
+            return;
+        }
+
+        let event = Event::SwitchesChanged {
+            lid_closed,
+            tablet_mode,
+        };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
     pub fn ipc_refresh_casts(&mut self) {
         let Some(server) = &self.niri.ipc_server else {
             return;
@@ -935,6 +1397,31 @@ impl State {
         server.send_event(event);
     }
 
+    pub fn ipc_config_value_changed(&mut self, path: &str, value: ConfigValue) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+        let mut state = server.event_stream_state.borrow_mut();
+
+        let event = Event::ConfigValueChanged {
+            path: path.to_owned(),
+            value,
+        };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
+    pub fn ipc_critical_client_restarted(&mut self, command: Vec<String>, restarts: u32) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+        let mut state = server.event_stream_state.borrow_mut();
+
+        let event = Event::CriticalClientRestarted { command, restarts };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
+
     pub fn ipc_screenshot_taken(&mut self, path: Option<String>) {
         let Some(server) = &self.niri.ipc_server else {
             return;
@@ -946,3 +1433,179 @@ impl State {
         server.send_event(event);
     }
 }
+
+/// Checks that a connected output supports the requested mode.
+///
+/// Without this, the backend would silently fall back to the preferred mode.
+fn check_mode_is_supported(
+    output: &niri_ipc::Output,
+    action: &niri_ipc::OutputAction,
+) -> Result<(), String> {
+    let niri_ipc::OutputAction::Mode {
+        mode: niri_ipc::ModeToSet::Specific(mode),
+    } = action
+    else {
+        return Ok(());
+    };
+
+    // Nothing to check against.
+    if output.modes.is_empty() {
+        return Ok(());
+    }
+
+    let refresh = mode.refresh.map(|r| (r * 1000.).round() as u32);
+    let supported = output.modes.iter().any(|m| {
+        m.width == mode.width
+            && m.height == mode.height
+            && refresh.is_none_or(|refresh| m.refresh_rate == refresh)
+    });
+    if supported {
+        return Ok(());
+    }
+
+    let available: Vec<_> = output
+        .modes
+        .iter()
+        .map(|m| {
+            let refresh = m.refresh_rate / 1000;
+            let frac = m.refresh_rate % 1000;
+            format!("{}x{}@{refresh}.{frac:03}", m.width, m.height)
+        })
+        .collect();
+    let refresh = mode.refresh.map(|r| format!("@{r:.3}")).unwrap_or_default();
+    Err(format!(
+        "output {} does not support mode {}x{}{refresh}, available modes: {}",
+        output.name,
+        mode.width,
+        mode.height,
+        available.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn action_names_match_config() {
+        let name = |action: Action| action.name();
+        assert_eq!(name(Action::FocusWorkspaceDown {}), "focus-workspace-down");
+        assert_eq!(name(Action::ToggleOverview {}), "toggle-overview");
+        let quit = Action::Quit {
+            skip_confirmation: true,
+        };
+        assert_eq!(name(quit), "quit");
+    }
+
+    #[test]
+    fn requests_are_checked_before_processing() {
+        assert!(parse_request(b"not json\n", false).is_err());
+        assert!(parse_request(b"\"Version\"\n", true).is_ok());
+
+        let quit = br#"{"Action":{"Quit":{"skip_confirmation":true}}}"#;
+        assert!(parse_request(quit, false).is_ok());
+        assert!(parse_request(quit, true).is_err());
+
+        let screenshot = |path: &str| Action::Screenshot {
+            show_pointer: true,
+            path: Some(String::from(path)),
+        };
+        assert!(prepare_action(screenshot("relative.png")).is_err());
+        let (name, _) = prepare_action(screenshot("/tmp/absolute.png")).unwrap();
+        assert_eq!(name, "screenshot");
+    }
+
+    /// Checks that the request is refused unless its permission is in the allowlist.
+    fn check_needs_permission(request: &str, permission: &str) {
+        let request = parse_request(request.as_bytes(), false).unwrap();
+        assert_eq!(request_permission(&request), Some(permission));
+        assert!(niri_config::IPC_PERMISSIONS.contains(&permission));
+
+        let mut ipc = niri_config::Ipc::default();
+        assert!(check_permission_in(&ipc, permission).is_ok());
+
+        ipc.allowed_actions = Some(vec![String::from("focus-workspace")]);
+        assert!(check_permission_in(&ipc, permission).is_err());
+
+        ipc.allowed_actions = Some(vec![String::from(permission)]);
+        assert!(check_permission_in(&ipc, permission).is_ok());
+    }
+
+    #[test]
+    fn import_needs_spawn() {
+        let request = r#"{"ImportWorkspace":{"export":{"name":null,"root":null}}}"#;
+        check_needs_permission(request, "spawn");
+    }
+
+    #[test]
+    fn output_needs_permission() {
+        let request = r#"{"Output":{"output":"DP-1","action":"Off"}}"#;
+        check_needs_permission(request, "output");
+    }
+
+    #[test]
+    fn headless_outputs_need_permission() {
+        check_needs_permission(
+            r#"{"CreateHeadlessOutput":{"width":1920,"height":1080}}"#,
+            "headless-output",
+        );
+        check_needs_permission(
+            r#"{"DestroyOutput":{"output":"HEADLESS-1"}}"#,
+            "headless-output",
+        );
+    }
+
+    #[test]
+    fn set_config_value_needs_permission() {
+        let request = r#"{"SetConfigValue":{"path":"layout.gaps","value":{"Number":8.0}}}"#;
+        check_needs_permission(request, "set-config-value");
+    }
+
+    #[test]
+    fn set_container_percents_needs_permission() {
+        let request = r#"{"SetContainerPercents":{"container":"Focused","percents":[50.0,50.0]}}"#;
+        check_needs_permission(request, "set-container-percents");
+    }
+
+    #[test]
+    fn picking_needs_permission() {
+        check_needs_permission(r#""PickWindow""#, "pick-window");
+        check_needs_permission(r#""PickColor""#, "pick-color");
+    }
+
+    #[test]
+    fn workspace_thumbnail_needs_permission() {
+        let request = r#"{"WorkspaceThumbnail":{"id":1,"max_size":256}}"#;
+        check_needs_permission(request, "workspace-thumbnail");
+    }
+
+    #[test]
+    fn reading_needs_no_permission() {
+        let request = parse_request(br#""Workspaces""#, false).unwrap();
+        assert_eq!(request_permission(&request), None);
+    }
+
+    #[test]
+    fn private_requests_need_full_socket() {
+        assert!(parse_request(br#""SpawnedProcesses""#, true).is_err());
+        assert!(parse_request(br#""ExportWorkspace""#, true).is_err());
+        assert!(parse_request(br#""BackgroundPermissions""#, true).is_err());
+        assert!(parse_request(br#""BindConflicts""#, true).is_ok());
+    }
+
+    /// Runs the fuzz corpus, where inputs of fixed fuzzer crashes go too.
+    #[test]
+    fn fuzz_corpus_doesnt_panic() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/corpus/ipc_request");
+        for entry in fs::read_dir(dir).unwrap() {
+            let data = fs::read(entry.unwrap().path()).unwrap();
+            for read_only in [false, true] {
+                if let Ok(Request::Action(action)) = parse_request(&data, read_only) {
+                    let _ = prepare_action(action);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,554 @@
+//! Workspace background: the background color with an optional gradient and image on top.
+//!
+//! The background goes beneath the tiles, so it shows through the gaps and in the overview. The
+//! image is decoded and scaled on a separate thread and shared between all workspaces through
+//! [`BackgroundImages`], which forgets it on config reload.
+
+use std::cell::{RefCell, RefMut};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::iter::zip;
+use std::path::PathBuf;
+use std::thread;
+
+use anyhow::{ensure, Context as _};
+use niri_config::{BackgroundImage, BackgroundImageMode, CornerRadius, Gradient};
+use pangocairo::cairo::{self, ImageSurface};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::reexports::calloop::channel::Sender;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Logical, Point, Rectangle, Size, Transform};
+
+use crate::niri_render_elements;
+use crate::render_helpers::border::BorderRenderElement;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{expand_home, to_physical_precise_round};
+
+#[derive(Debug)]
+pub struct Background {
+    color: SolidColorBuffer,
+    gradient: Option<Gradient>,
+    gradient_element: BorderRenderElement,
+    image: Option<BackgroundImage>,
+    size: Size<f64, Logical>,
+    scale: f64,
+}
+
+/// Background images shared between all workspaces.
+///
+/// Workspaces showing the same image at the same size share one scaled copy. Until the loader
+/// thread finishes with an image, backgrounds show without it.
+pub struct BackgroundImages {
+    images: RefCell<HashMap<ImageKey, ImageState>>,
+    /// Bumped when the images are cleared, so that loads started before that are ignored.
+    generation: u64,
+    to_niri: Sender<LoadedImage>,
+}
+
+/// Image scaled to a background size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ImageKey {
+    path: String,
+    mode: BackgroundImageMode,
+    width: i32,
+    height: i32,
+    /// Bits of the `f64` output scale.
+    scale: u64,
+}
+
+enum ImageState {
+    Loading,
+    Failed,
+    /// Scaled pixels waiting to be uploaded on the next render.
+    Loaded(Vec<u8>),
+    Uploaded(TextureBuffer<GlesTexture>),
+}
+
+/// Image scaled on the loader thread.
+pub struct LoadedImage {
+    key: ImageKey,
+    generation: u64,
+    pixels: anyhow::Result<Vec<u8>>,
+}
+
+niri_render_elements! {
+    BackgroundRenderElement => {
+        SolidColor = SolidColorRenderElement,
+        Gradient = BorderRenderElement,
+        Image = PrimaryGpuTextureRenderElement,
+    }
+}
+
+impl Background {
+    pub fn new(size: Size<f64, Logical>, scale: f64, config: &niri_config::Layout) -> Self {
+        let mut rv = Self {
+            color: SolidColorBuffer::new(size, config.background_color),
+            gradient: config.background_gradient,
+            gradient_element: BorderRenderElement::empty(),
+            image: config.background_image.clone(),
+            size,
+            scale,
+        };
+        rv.update_gradient();
+        rv
+    }
+
+    pub fn update_config(&mut self, config: &niri_config::Layout) {
+        self.color.set_color(config.background_color);
+        self.image = config.background_image.clone();
+        self.gradient = config.background_gradient;
+        self.update_gradient();
+    }
+
+    pub fn update_size(&mut self, size: Size<f64, Logical>, scale: f64) {
+        if self.size == size && self.scale == scale {
+            return;
+        }
+
+        self.size = size;
+        self.scale = scale;
+        self.color.resize(size);
+        self.update_gradient();
+    }
+
+    pub fn update_shaders(&mut self) {
+        self.gradient_element.damage_all();
+    }
+
+    fn update_gradient(&mut self) {
+        let Some(gradient) = self.gradient else {
+            return;
+        };
+
+        let area = Rectangle::from_size(self.size);
+        self.gradient_element.update(
+            self.size,
+            area,
+            gradient.in_,
+            gradient.from,
+            gradient.to,
+            (f32::from(gradient.angle) - 90.).to_radians(),
+            area,
+            0.,
+            CornerRadius::default(),
+            self.scale as f32,
+            1.,
+        );
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        images: &BackgroundImages,
+        push: &mut dyn FnMut(BackgroundRenderElement),
+    ) {
+        if let Some(elem) = self.render_image(renderer, images) {
+            push(elem.into());
+        }
+
+        if self.gradient.is_some() && BorderRenderElement::has_shader(renderer) {
+            let elem = self
+                .gradient_element
+                .clone()
+                .with_location(Point::new(0., 0.));
+            push(elem.into());
+        }
+
+        let elem = SolidColorRenderElement::from_buffer(
+            &self.color,
+            Point::new(0., 0.),
+            1.,
+            Kind::Unspecified,
+        );
+        push(elem.into());
+    }
+
+    fn render_image<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        images: &BackgroundImages,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let image = self.image.as_ref()?;
+        let key = ImageKey::new(image, self.size, self.scale)?;
+        let buffer = images.texture(renderer.as_gles_renderer(), &key, self.scale)?;
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            Point::new(0., 0.),
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+
+    #[cfg(test)]
+    pub fn verify_invariants(&self, size: Size<f64, Logical>, config: &niri_config::Layout) {
+        assert_eq!(self.size, size);
+        assert_eq!(self.color.size(), size);
+        assert_eq!(
+            self.color.color().components(),
+            config.background_color.to_array_unpremul(),
+        );
+        assert_eq!(self.gradient, config.background_gradient);
+        assert_eq!(self.image, config.background_image);
+    }
+}
+
+impl BackgroundImages {
+    pub fn new(to_niri: Sender<LoadedImage>) -> Self {
+        Self {
+            images: RefCell::new(HashMap::new()),
+            generation: 0,
+            to_niri,
+        }
+    }
+
+    /// Forgets all images, so that they are loaded from disk again.
+    pub fn clear(&mut self) {
+        self.images.get_mut().clear();
+        self.generation += 1;
+    }
+
+    /// Stores an image from the loader thread.
+    ///
+    /// Returns whether the image is still in use, so the backgrounds need a redraw.
+    pub fn finish_loading(&mut self, loaded: LoadedImage) -> bool {
+        if loaded.generation != self.generation {
+            return false;
+        }
+
+        let Some(state) = self.images.get_mut().get_mut(&loaded.key) else {
+            return false;
+        };
+        if !matches!(state, ImageState::Loading) {
+            return false;
+        }
+
+        *state = match loaded.pixels {
+            Ok(pixels) => ImageState::Loaded(pixels),
+            Err(err) => {
+                warn!(
+                    "error loading background image {:?}: {err:?}",
+                    loaded.key.path
+                );
+                ImageState::Failed
+            }
+        };
+        true
+    }
+
+    fn texture(
+        &self,
+        renderer: &mut GlesRenderer,
+        key: &ImageKey,
+        scale: f64,
+    ) -> Option<TextureBuffer<GlesTexture>> {
+        let mut state = self.state(key);
+
+        if let ImageState::Loaded(pixels) = &*state {
+            let _span = tracy_client::span!("BackgroundImages::upload");
+
+            let buffer = TextureBuffer::from_memory(
+                renderer,
+                pixels,
+                Fourcc::Argb8888,
+                (key.width, key.height),
+                false,
+                scale,
+                Transform::Normal,
+                Vec::new(),
+            );
+            *state = match buffer {
+                Ok(buffer) => ImageState::Uploaded(buffer),
+                Err(err) => {
+                    warn!("error uploading background image: {err:?}");
+                    ImageState::Failed
+                }
+            };
+        }
+
+        match &*state {
+            ImageState::Uploaded(buffer) => Some(buffer.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the state of the image, starting to load it if it's new.
+    fn state(&self, key: &ImageKey) -> RefMut<'_, ImageState> {
+        RefMut::map(self.images.borrow_mut(), |images| {
+            images
+                .entry(key.clone())
+                .or_insert_with(|| self.start_loading(key.clone()))
+        })
+    }
+
+    fn start_loading(&self, key: ImageKey) -> ImageState {
+        let to_niri = self.to_niri.clone();
+        let generation = self.generation;
+
+        let res = thread::Builder::new()
+            .name("Background Image Loader".to_owned())
+            .spawn(move || {
+                let _span = tracy_client::span!("background image loader");
+
+                let pixels = load(&key.path).and_then(|source| scale(&source, &key));
+                let _ = to_niri.send(LoadedImage {
+                    key,
+                    generation,
+                    pixels,
+                });
+            });
+
+        match res {
+            Ok(_) => ImageState::Loading,
+            Err(err) => {
+                warn!("error spawning a thread to load the background image: {err:?}");
+                ImageState::Failed
+            }
+        }
+    }
+}
+
+impl ImageKey {
+    fn new(image: &BackgroundImage, size: Size<f64, Logical>, scale: f64) -> Option<Self> {
+        let width: i32 = to_physical_precise_round(scale, size.w);
+        let height: i32 = to_physical_precise_round(scale, size.h);
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        Some(Self {
+            path: image.path.clone(),
+            mode: image.mode,
+            width,
+            height,
+            scale: scale.to_bits(),
+        })
+    }
+}
+
+/// Loads a PNG image into a surface that cairo can paint.
+fn load(path: &str) -> anyhow::Result<ImageSurface> {
+    let _span = tracy_client::span!("background::load");
+
+    let path = PathBuf::from(path);
+    let path = expand_home(&path)?.unwrap_or(path);
+    let data = fs::read(&path).context("error reading file")?;
+
+    let mut decoder = png::Decoder::new(Cursor::new(data));
+    decoder.set_transformations(
+        png::Transformations::normalize_to_color8() | png::Transformations::ALPHA,
+    );
+    let mut reader = decoder.read_info().context("error decoding PNG")?;
+    let len = reader.output_buffer_size().context("image is too large")?;
+    let mut pixels = vec![0; len];
+    let info = reader
+        .next_frame(&mut pixels)
+        .context("error decoding PNG")?;
+
+    let channels = match info.color_type {
+        png::ColorType::Rgba => 4,
+        png::ColorType::GrayscaleAlpha => 2,
+        other => anyhow::bail!("unexpected color type after decoding: {other:?}"),
+    };
+
+    let width = i32::try_from(info.width).context("image is too wide")?;
+    let height = i32::try_from(info.height).context("image is too tall")?;
+    ensure!(width > 0 && height > 0, "image is empty");
+
+    let mut surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let stride = surface.stride() as usize;
+    {
+        let mut data = surface.data()?;
+        let rows = pixels[..info.buffer_size()].chunks_exact(info.line_size);
+        for (src, dst) in zip(rows, data.chunks_exact_mut(stride)) {
+            for (src, dst) in zip(src.chunks_exact(channels), dst.chunks_exact_mut(4)) {
+                let [r, g, b, a] = match *src {
+                    [r, g, b, a] => [r, g, b, a],
+                    [l, a] => [l, l, l, a],
+                    _ => unreachable!(),
+                };
+
+                // Cairo wants premultiplied alpha in native-endian ARGB.
+                let premul = |c: u8| (u32::from(c) * u32::from(a) + 127) / 255;
+                let pixel = (u32::from(a) << 24) | (premul(r) << 16) | (premul(g) << 8) | premul(b);
+                dst.copy_from_slice(&pixel.to_ne_bytes());
+            }
+        }
+    }
+    surface.mark_dirty();
+
+    Ok(surface)
+}
+
+/// Scales the image to the background size, returning premultiplied native-endian ARGB pixels.
+fn scale(source: &ImageSurface, key: &ImageKey) -> anyhow::Result<Vec<u8>> {
+    let _span = tracy_client::span!("background::scale");
+
+    let (width, height) = (key.width, key.height);
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+
+    match key.mode {
+        BackgroundImageMode::Fill | BackgroundImageMode::Fit => {
+            let (w, h) = (f64::from(width), f64::from(height));
+            let (src_w, src_h) = (f64::from(source.width()), f64::from(source.height()));
+            let zoom = if key.mode == BackgroundImageMode::Fill {
+                f64::max(w / src_w, h / src_h)
+            } else {
+                f64::min(w / src_w, h / src_h)
+            };
+
+            // Center the image, so that fill crops and fit pads equally on both sides.
+            cr.translate((w - src_w * zoom) / 2., (h - src_h * zoom) / 2.);
+            cr.scale(zoom, zoom);
+            cr.set_source_surface(source, 0., 0.)?;
+            cr.source().set_filter(cairo::Filter::Good);
+        }
+        BackgroundImageMode::Tile => {
+            cr.set_source_surface(source, 0., 0.)?;
+            cr.source().set_extend(cairo::Extend::Repeat);
+        }
+    }
+    cr.paint()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    Ok(data.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::{Duration, Instant};
+
+    use smithay::reexports::calloop::channel::{self, Event};
+    use smithay::reexports::calloop::EventLoop;
+    use xshell::Shell;
+
+    use super::*;
+    use crate::utils::write_png_rgba8;
+
+    struct Harness {
+        event_loop: EventLoop<'static, Vec<LoadedImage>>,
+        images: BackgroundImages,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let event_loop = EventLoop::try_new().unwrap();
+            let (to_niri, from_loader) = channel::channel();
+            event_loop
+                .handle()
+                .insert_source(from_loader, |event, _, loaded: &mut Vec<LoadedImage>| {
+                    if let Event::Msg(image) = event {
+                        loaded.push(image);
+                    }
+                })
+                .unwrap();
+
+            Self {
+                event_loop,
+                images: BackgroundImages::new(to_niri),
+            }
+        }
+
+        /// Waits for the loader threads to send `count` images.
+        fn wait_loaded(&mut self, count: usize) -> Vec<LoadedImage> {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let mut loaded = Vec::new();
+            while loaded.len() < count {
+                assert!(Instant::now() < deadline, "images didn't load in time");
+                let timeout = Some(Duration::from_millis(100));
+                self.event_loop.dispatch(timeout, &mut loaded).unwrap();
+            }
+            assert_eq!(loaded.len(), count);
+            loaded
+        }
+    }
+
+    fn image_key(path: &str, size: (f64, f64)) -> ImageKey {
+        let image = BackgroundImage {
+            path: path.to_owned(),
+            mode: BackgroundImageMode::Fill,
+        };
+        ImageKey::new(&image, Size::from(size), 1.).unwrap()
+    }
+
+    /// Writes an opaque red PNG and returns its path.
+    fn write_red_png(sh: &Shell) -> String {
+        let path = sh.current_dir().join("red.png");
+        let file = File::create(&path).unwrap();
+        write_png_rgba8(file, 2, 1, &[255, 0, 0, 255, 255, 0, 0, 255]).unwrap();
+        path.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn same_image_and_size_load_once() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        sh.change_dir(temp_dir.path());
+        let path = write_red_png(&sh);
+
+        let mut harness = Harness::new();
+        let key = image_key(&path, (4., 2.));
+        assert!(matches!(*harness.images.state(&key), ImageState::Loading));
+        assert!(matches!(*harness.images.state(&key), ImageState::Loading));
+        assert_eq!(harness.images.images.borrow().len(), 1);
+
+        let [loaded] = harness.wait_loaded(1).try_into().ok().unwrap();
+        assert!(harness.images.finish_loading(loaded));
+
+        let ImageState::Loaded(pixels) = &*harness.images.state(&key) else {
+            panic!("image should be loaded");
+        };
+        assert_eq!(pixels.len(), 4 * 2 * 4);
+        assert_eq!(pixels[..4], 0xffff0000u32.to_ne_bytes());
+
+        // A different size is scaled separately.
+        let other = image_key(&path, (8., 4.));
+        assert!(matches!(*harness.images.state(&other), ImageState::Loading));
+        assert_eq!(harness.images.images.borrow().len(), 2);
+    }
+
+    #[test]
+    fn clearing_ignores_loads_in_flight() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        sh.change_dir(temp_dir.path());
+        let path = write_red_png(&sh);
+
+        let mut harness = Harness::new();
+        let key = image_key(&path, (4., 2.));
+        drop(harness.images.state(&key));
+        harness.images.clear();
+
+        // Requesting the image again after a reload loads it from disk again.
+        drop(harness.images.state(&key));
+        let mut loaded = harness.wait_loaded(2);
+        loaded.sort_by_key(|image| image.generation);
+        let [stale, fresh] = loaded.try_into().ok().unwrap();
+        assert!(!harness.images.finish_loading(stale));
+        assert!(harness.images.finish_loading(fresh));
+        assert!(matches!(*harness.images.state(&key), ImageState::Loaded(_)));
+    }
+
+    #[test]
+    fn missing_image_fails_once() {
+        let mut harness = Harness::new();
+        let key = image_key("/nonexistent/background.png", (4., 2.));
+        drop(harness.images.state(&key));
+
+        let [loaded] = harness.wait_loaded(1).try_into().ok().unwrap();
+        assert!(loaded.pixels.is_err());
+        assert!(harness.images.finish_loading(loaded));
+        assert!(matches!(*harness.images.state(&key), ImageState::Failed));
+    }
+}
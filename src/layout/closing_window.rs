@@ -3,6 +3,7 @@ use std::rc::Rc;
 
 use anyhow::Context as _;
 use glam::{Mat3, Vec2};
+use niri_config::animations::WindowAnimStyle;
 use niri_config::BlockOutFrom;
 use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::element::utils::{
@@ -14,6 +15,7 @@ use smithay::backend::renderer::Texture;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size, Transform};
 use smithay::wayland::compositor::{Blocker, BlockerState};
 
+use super::opening_window::OpenCloseStyle;
 use crate::animation::Animation;
 use crate::niri_render_elements;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
@@ -50,6 +52,9 @@ pub struct ClosingWindow {
     /// The closing animation.
     anim_state: AnimationState,
 
+    /// Look of the closing animation without a custom shader.
+    style: OpenCloseStyle,
+
     /// Random seed for the shader.
     random_seed: f32,
 }
@@ -93,6 +98,7 @@ impl ClosingWindow {
         pos: Point<f64, Logical>,
         blocker: TransactionBlocker,
         anim: Animation,
+        style: OpenCloseStyle,
     ) -> anyhow::Result<Self> {
         let _span = tracy_client::span!("ClosingWindow::new");
 
@@ -134,6 +140,7 @@ impl ClosingWindow {
             buffer_offset,
             blocked_out_buffer_offset,
             anim_state: AnimationState::new(blocker, anim),
+            style,
             random_seed: fastrand::f32(),
         })
     }
@@ -245,10 +252,16 @@ impl ClosingWindow {
             .into();
         }
 
+        let (fade, zoom) = match self.style.style {
+            WindowAnimStyle::Popin => (clamped_progress, (1. - clamped_progress) / 5. + 0.8),
+            WindowAnimStyle::Scale => (0., 1. - clamped_progress),
+            WindowAnimStyle::Fade | WindowAnimStyle::Slide => (clamped_progress, 1.),
+        };
+
         let elem = TextureRenderElement::from_texture_buffer(
             buffer.clone(),
             Point::from((0., 0.)),
-            1. - clamped_progress as f32,
+            1. - fade as f32,
             None,
             None,
             Kind::Unspecified,
@@ -260,10 +273,10 @@ impl ClosingWindow {
         let elem = RescaleRenderElement::from_element(
             elem,
             (center - offset).to_physical_precise_round(scale),
-            ((1. - clamped_progress) / 5. + 0.8).max(0.),
+            zoom.max(0.),
         );
 
-        let mut location = self.pos + offset;
+        let mut location = self.pos + offset + self.style.slide_offset(self.geo_size, progress);
         location.x -= view_rect.loc.x;
         let elem = RelocateRenderElement::from_element(
             elem,
@@ -11,18 +11,23 @@
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use std::time::Duration;
 
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
 use smithay::utils::{Logical, Point, Rectangle, Size};
 use smithay::wayland::compositor::{Blocker, BlockerState};
 
+use super::tab_bar::{tab_bar_row_height, tabs_in_single_row, TabBarInset};
 use super::tile::Tile;
 use super::{LayoutElement, Options};
-use super::tab_bar::tab_bar_row_height;
-use crate::window::Mapped;
-use crate::utils::transaction::{Transaction, TransactionBlocker};
-use niri_config::BlockOutFrom;
-use niri_ipc::{LayoutTreeLayout, LayoutTreeNode};
+use crate::animation::Clock;
+use crate::utils::round_logical_in_physical_max1;
+use crate::utils::transaction::{Transaction, TransactionBlocker, TIME_LIMIT};
+use crate::window::{Mapped, ResolvedWindowRules};
+use niri_config::{BlockOutFrom, TabIndicatorPosition};
+use niri_ipc::{
+    ColumnDisplay, LayoutTreeLayout, LayoutTreeNode, LayoutViolation, LayoutViolationKind,
+};
 
 // ============================================================================
 // SlotMap Key Types
@@ -64,6 +69,7 @@ pub struct TabBarTab {
     pub title: String,
     pub is_focused: bool,
     pub is_urgent: bool,
+    pub has_activity: bool,
     pub block_out_from: Option<BlockOutFrom>,
 }
 
@@ -71,14 +77,40 @@ pub struct TabBarTab {
 pub struct TabBarInfo {
     pub path: Vec<usize>,
     pub layout: Layout,
+    pub position: TabIndicatorPosition,
     pub rect: Rectangle<f64, Logical>,
     pub row_height: f64,
     pub tabs: Vec<TabBarTab>,
 }
 
+impl TabBarInfo {
+    /// Whether the tabs sit next to each other in a single row.
+    pub fn is_single_row(&self) -> bool {
+        tabs_in_single_row(self.layout, self.position)
+    }
+}
+
 const MIN_CHILD_PERCENT: f64 = 0.05;
 const MOVE_ANIMATION_THRESHOLD: f64 = 0.1;
 
+/// Scales the percentages to add up to 1, checking that every child keeps a usable share.
+pub fn normalized_percents(percents: &[f64]) -> Result<Vec<f64>, String> {
+    if percents.iter().any(|p| !p.is_finite() || *p <= 0.) {
+        return Err(String::from("percents must be positive numbers"));
+    }
+
+    let sum: f64 = percents.iter().sum();
+    let percents: Vec<f64> = percents.iter().map(|p| p / sum).collect();
+    let min = MIN_CHILD_PERCENT - f64::EPSILON;
+    if percents.iter().any(|p| *p < min) {
+        return Err(format!(
+            "every child needs a share of at least {MIN_CHILD_PERCENT}"
+        ));
+    }
+
+    Ok(percents)
+}
+
 /// Node type in the container tree
 #[derive(Debug)]
 pub enum NodeData<W: LayoutElement> {
@@ -95,6 +127,17 @@ pub enum DetachedNode<W: LayoutElement> {
     Leaf(Tile<W>),
 }
 
+/// Shape of a subtree to rebuild from existing windows, like a saved layout.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeTemplate<Id> {
+    Container {
+        layout: Layout,
+        /// Children with their share of the container.
+        children: Vec<(f64, TreeTemplate<Id>)>,
+    },
+    Leaf(Id),
+}
+
 #[derive(Debug)]
 pub struct DetachedContainer<W: LayoutElement> {
     layout: Layout,
@@ -102,6 +145,7 @@ pub struct DetachedContainer<W: LayoutElement> {
     child_percents: Vec<f64>,
     focus_stack: Vec<usize>,
     preserve_on_single: bool,
+    tab_bar_position: Option<TabIndicatorPosition>,
 }
 
 /// Container data stored in slotmap
@@ -115,10 +159,17 @@ pub struct ContainerData {
     focus_stack: Vec<NodeKey>,
     /// Preserve container even if it has a single child (explicit split).
     preserve_on_single: bool,
+    /// Edge of the container for the tab bar, overriding the `tab-bar` config.
+    tab_bar_position: Option<TabIndicatorPosition>,
     /// Relative sizes of children (sum normalized to 1.0 for split layouts)
     child_percents: Vec<f64>,
     /// Cached geometry for rendering
     geometry: Rectangle<f64, Logical>,
+    /// Layout mode currently shown on screen.
+    ///
+    /// Lags behind `layout` while a layout change waits for the windows to commit their new sizes,
+    /// so that tab bars flip together with the windows.
+    applied_layout: Layout,
 }
 
 /// Cached layout information for a leaf tile.
@@ -142,7 +193,8 @@ pub(super) struct InsertParentInfo {
 struct LayoutData {
     leaf_layouts: Vec<LeafLayoutInfo>,
     container_geometries: HashMap<NodeKey, Rectangle<f64, Logical>>,
-    tab_bar_offsets: HashMap<NodeKey, f64>,
+    container_layouts: HashMap<NodeKey, Layout>,
+    tab_bar_insets: HashMap<NodeKey, TabBarInset>,
     titlebar_flags: HashMap<NodeKey, bool>,
 }
 
@@ -150,6 +202,8 @@ struct LayoutData {
 struct PendingLayout {
     data: LayoutData,
     blocker: TransactionBlocker,
+    /// Time after which the layout is applied even if some windows haven't acked it yet.
+    deadline: Duration,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -190,10 +244,15 @@ pub struct ContainerTree<W: LayoutElement> {
     pending_relayout: bool,
     /// View size (output size)
     view_size: Size<f64, Logical>,
-    /// Working area (view_size minus gaps/bars)
+    /// Working area (view_size minus layer-shell exclusive zones and the safe area insets)
     working_area: Rectangle<f64, Logical>,
+    /// Whether the working area changed since the last applied layout, so tiles should animate
+    /// to their new positions.
+    working_area_changed: bool,
     /// Display scale
     scale: f64,
+    /// Clock for the pending layout deadline
+    clock: Clock,
     /// Layout options
     options: Rc<Options>,
     /// Generation counter for cache invalidation.
@@ -207,7 +266,7 @@ pub struct ContainerTree<W: LayoutElement> {
 #[derive(Debug, Clone, Copy)]
 pub(super) struct PreviewLeafGeometry {
     pub rect: Rectangle<f64, Logical>,
-    pub tab_bar_offset: f64,
+    pub tab_bar: TabBarInset,
 }
 
 // ============================================================================
@@ -222,8 +281,10 @@ impl ContainerData {
             children: Vec::new(),
             focus_stack: Vec::new(),
             preserve_on_single: false,
+            tab_bar_position: None,
             child_percents: Vec::new(),
             geometry: Rectangle::from_size(Size::from((0.0, 0.0))),
+            applied_layout: layout,
         }
     }
 
@@ -474,6 +535,17 @@ impl ContainerData {
         self.normalize_child_percents();
     }
 
+    /// Replaces all child percentages; there must be one per child.
+    pub fn set_child_percents(&mut self, percents: &[f64]) -> bool {
+        if percents.len() != self.children.len() {
+            return false;
+        }
+
+        self.child_percents = percents.to_vec();
+        self.normalize_child_percents();
+        true
+    }
+
     pub fn set_child_percent_pair(&mut self, idx: usize, neighbor_idx: usize, percent: f64) -> bool {
         if self.child_percents.len() != self.children.len() {
             self.recalculate_percentages();
@@ -524,6 +596,11 @@ impl ContainerData {
         self.geometry = geometry;
     }
 
+    /// Layout mode currently shown on screen.
+    pub fn applied_layout(&self) -> Layout {
+        self.applied_layout
+    }
+
     /// Get geometry
     pub fn geometry(&self) -> Rectangle<f64, Logical> {
         self.geometry
@@ -578,6 +655,39 @@ impl<W: LayoutElement> DetachedNode<W> {
             }
         }
     }
+
+    /// Builds a subtree from a template, taking the tiles with `take_tile`.
+    ///
+    /// Leaves without a tile are left out. Containers left with a single child are replaced by
+    /// it, and empty ones are left out.
+    pub fn from_template(
+        template: TreeTemplate<W::Id>,
+        take_tile: &mut impl FnMut(&W::Id) -> Option<Tile<W>>,
+    ) -> Option<Self> {
+        match template {
+            TreeTemplate::Leaf(id) => take_tile(&id).map(DetachedNode::Leaf),
+            TreeTemplate::Container { layout, children } => {
+                let (percents, mut children): (Vec<_>, Vec<_>) = children
+                    .into_iter()
+                    .filter_map(|(percent, child)| {
+                        Some((percent, Self::from_template(child, take_tile)?))
+                    })
+                    .unzip();
+
+                match children.len() {
+                    0 => None,
+                    1 => children.pop(),
+                    _ => Some(DetachedNode::Container(DetachedContainer::from_parts(
+                        layout,
+                        children,
+                        percents,
+                        Vec::new(),
+                        false,
+                    ))),
+                }
+            }
+        }
+    }
 }
 
 impl<W: LayoutElement> DetachedContainer<W> {
@@ -588,6 +698,7 @@ impl<W: LayoutElement> DetachedContainer<W> {
             child_percents: Vec::new(),
             focus_stack: Vec::new(),
             preserve_on_single: false,
+            tab_bar_position: None,
         };
         container.ensure_focus_stack();
         container.recalculate_percentages();
@@ -607,6 +718,7 @@ impl<W: LayoutElement> DetachedContainer<W> {
             child_percents,
             focus_stack,
             preserve_on_single,
+            tab_bar_position: None,
         };
         container.normalize_child_percents();
         container.ensure_focus_stack();
@@ -679,6 +791,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         view_size: Size<f64, Logical>,
         working_area: Rectangle<f64, Logical>,
         scale: f64,
+        clock: Clock,
         options: Rc<Options>,
     ) -> Self {
         Self {
@@ -694,7 +807,9 @@ impl<W: LayoutElement> ContainerTree<W> {
             pending_relayout: false,
             view_size,
             working_area,
+            working_area_changed: false,
             scale,
+            clock,
             options,
             generation: 0,
             focus_path_cache: RefCell::new((u64::MAX, None, Vec::new())),
@@ -706,27 +821,23 @@ impl<W: LayoutElement> ContainerTree<W> {
         let root_rect = self.layout_area();
         let Some(root_key) = self.root else {
             if let Some(layout) = self.pending_layout {
-                let (rect, tab_bar_offset) =
-                    self.preview_child_rect(layout, root_rect, 1, &[1.0], 0, true);
-                return Some(PreviewLeafGeometry {
-                    rect,
-                    tab_bar_offset,
-                });
+                let position = self.options.layout.tab_bar.position;
+                let (rect, tab_bar) =
+                    self.preview_child_rect(layout, position, root_rect, 1, &[1.0], 0, true);
+                return Some(PreviewLeafGeometry { rect, tab_bar });
             }
             return Some(PreviewLeafGeometry {
                 rect: root_rect,
-                tab_bar_offset: 0.0,
+                tab_bar: TabBarInset::NONE,
             });
         };
 
         if matches!(self.get_node(root_key), Some(NodeData::Leaf(_))) {
             let percents = self.preview_inserted_child_percents(&[], 1, 1);
-            let (rect, tab_bar_offset) =
-                self.preview_child_rect(Layout::SplitH, root_rect, 2, &percents, 1, true);
-            return Some(PreviewLeafGeometry {
-                rect,
-                tab_bar_offset,
-            });
+            let position = self.options.layout.tab_bar.position;
+            let (rect, tab_bar) =
+                self.preview_child_rect(Layout::SplitH, position, root_rect, 2, &percents, 1, true);
+            return Some(PreviewLeafGeometry { rect, tab_bar });
         }
 
         let focus_path = self.focus_path();
@@ -752,8 +863,9 @@ impl<W: LayoutElement> ContainerTree<W> {
             child_count,
             insert_idx,
         );
-        let (rect, tab_bar_offset) = self.preview_child_rect(
+        let (rect, tab_bar) = self.preview_child_rect(
             parent.layout(),
+            self.tab_bar_position(parent_key),
             parent_rect,
             child_count + 1,
             &percents,
@@ -761,10 +873,7 @@ impl<W: LayoutElement> ContainerTree<W> {
             true,
         );
 
-        Some(PreviewLeafGeometry {
-            rect,
-            tab_bar_offset,
-        })
+        Some(PreviewLeafGeometry { rect, tab_bar })
     }
 
 
@@ -788,6 +897,7 @@ impl<W: LayoutElement> ContainerTree<W> {
             );
             let (child_rect, _) = self.preview_child_rect(
                 container.layout(),
+                self.tab_bar_position(node_key),
                 rect,
                 container.child_count(),
                 &percents,
@@ -859,12 +969,13 @@ impl<W: LayoutElement> ContainerTree<W> {
     fn preview_child_rect(
         &self,
         layout: Layout,
+        tab_bar_position: TabIndicatorPosition,
         rect: Rectangle<f64, Logical>,
         child_count: usize,
         percents: &[f64],
         child_idx: usize,
         child_is_leaf: bool,
-    ) -> (Rectangle<f64, Logical>, f64) {
+    ) -> (Rectangle<f64, Logical>, TabBarInset) {
         let gap = self.options.layout.gaps;
         match layout {
             Layout::SplitH => {
@@ -892,12 +1003,12 @@ impl<W: LayoutElement> ContainerTree<W> {
                             Point::from((cursor_x, rect.loc.y)),
                             Size::from((width, rect.size.h)),
                         );
-                        let tab_bar_offset = if child_is_leaf && split_bar_height > 0.0 {
-                            split_bar_height
+                        let tab_bar = if child_is_leaf {
+                            TabBarInset::top(split_bar_height)
                         } else {
-                            0.0
+                            TabBarInset::NONE
                         };
-                        return (child_rect, tab_bar_offset);
+                        return (child_rect, tab_bar);
                     }
                     used_width += width;
                     if idx + 1 < child_count {
@@ -930,12 +1041,12 @@ impl<W: LayoutElement> ContainerTree<W> {
                             Point::from((rect.loc.x, cursor_y)),
                             Size::from((rect.size.w, height)),
                         );
-                        let tab_bar_offset = if child_is_leaf && split_bar_height > 0.0 {
-                            split_bar_height
+                        let tab_bar = if child_is_leaf {
+                            TabBarInset::top(split_bar_height)
                         } else {
-                            0.0
+                            TabBarInset::NONE
                         };
-                        return (child_rect, tab_bar_offset);
+                        return (child_rect, tab_bar);
                     }
                     used_height += height;
                     if idx + 1 < child_count {
@@ -952,34 +1063,17 @@ impl<W: LayoutElement> ContainerTree<W> {
                     inner_rect.size.h = (inner_rect.size.h - gap * 2.0).max(0.0);
                 }
 
-                let bar_row_height = self.tab_bar_row_height();
-                let mut tab_offset = 0.0;
-                if bar_row_height > 0.0 && child_count > 0 {
-                    let bar_height = match layout {
-                        Layout::Tabbed => bar_row_height,
-                        Layout::Stacked => bar_row_height * child_count as f64,
-                        _ => 0.0,
-                    };
-                    let total_bar_height = (bar_height + self.tab_bar_spacing())
-                        .min(inner_rect.size.h)
-                        .max(0.0);
-                    tab_offset = total_bar_height;
-                }
-
+                let tab_bar = self.tab_bar_inset(layout, tab_bar_position, inner_rect, child_count);
                 if child_is_leaf {
-                    return (inner_rect, tab_offset);
+                    return (inner_rect, tab_bar);
                 }
 
-                let mut content_rect = inner_rect;
-                if tab_offset > 0.0 {
-                    content_rect.loc.y += tab_offset;
-                    content_rect.size.h = (content_rect.size.h - tab_offset).max(0.0);
-                }
-                return (content_rect, 0.0);
+                let (_, content_rect) = tab_bar.split(inner_rect);
+                return (content_rect, TabBarInset::NONE);
             }
         }
 
-        (rect, 0.0)
+        (rect, TabBarInset::NONE)
     }
 
     // ========================================================================
@@ -1308,6 +1402,9 @@ impl<W: LayoutElement> ContainerTree<W> {
         view_size: Size<f64, Logical>,
         working_area: Rectangle<f64, Logical>,
     ) {
+        // Only animate panels coming and going, not the workspace changing outputs.
+        self.working_area_changed |=
+            self.view_size == view_size && self.working_area != working_area;
         self.view_size = view_size;
         self.working_area = working_area;
     }
@@ -1320,6 +1417,9 @@ impl<W: LayoutElement> ContainerTree<W> {
         scale: f64,
         options: Rc<Options>,
     ) {
+        // Only animate panels coming and going, not the workspace changing outputs.
+        self.working_area_changed |=
+            self.view_size == view_size && self.working_area != working_area;
         self.view_size = view_size;
         self.working_area = working_area;
         self.scale = scale;
@@ -1459,6 +1559,196 @@ impl<W: LayoutElement> ContainerTree<W> {
         }
     }
 
+    /// Checks the tree structure for inconsistencies.
+    ///
+    /// Unlike the assertions in tests, this doesn't panic, so it can run on a live compositor.
+    /// Returned violations don't have a workspace id set.
+    pub fn check_invariants(&self) -> Vec<LayoutViolation> {
+        let mut violations = Vec::new();
+
+        if let Some(root_key) = self.root {
+            if self.parents.get(root_key).copied().flatten().is_some() {
+                violations.push(violation(
+                    LayoutViolationKind::BrokenLink,
+                    Vec::new(),
+                    String::from("root node has a parent"),
+                ));
+            }
+
+            let mut path = Vec::new();
+            let mut visited = HashSet::new();
+            self.check_node_invariants(root_key, &mut path, &mut visited, &mut violations);
+
+            // Paths to nodes are meaningless in a cyclic tree, and looking them up may not end.
+            if violations
+                .iter()
+                .any(|v| v.kind == LayoutViolationKind::Cycle)
+            {
+                return violations;
+            }
+
+            if visited.len() != self.nodes.len() {
+                violations.push(violation(
+                    LayoutViolationKind::BrokenLink,
+                    Vec::new(),
+                    format!(
+                        "{} of {} nodes are unreachable from the root",
+                        self.nodes.len().saturating_sub(visited.len()),
+                        self.nodes.len()
+                    ),
+                ));
+            }
+        } else if !self.nodes.is_empty() {
+            violations.push(violation(
+                LayoutViolationKind::BrokenLink,
+                Vec::new(),
+                format!("tree has {} nodes but no root", self.nodes.len()),
+            ));
+        }
+
+        if let Some(key) = self.focused_key {
+            match self.get_node(key) {
+                Some(NodeData::Leaf(_)) => {
+                    if self.find_node_path(key).is_none() {
+                        violations.push(violation(
+                            LayoutViolationKind::InvalidFocus,
+                            Vec::new(),
+                            String::from("focused window is unreachable from the root"),
+                        ));
+                    }
+                }
+                Some(NodeData::Container(_)) => {
+                    violations.push(violation(
+                        LayoutViolationKind::InvalidFocus,
+                        self.find_node_path(key).unwrap_or_default(),
+                        String::from("focused node is a container rather than a window"),
+                    ));
+                }
+                None => {
+                    violations.push(violation(
+                        LayoutViolationKind::InvalidFocus,
+                        Vec::new(),
+                        String::from("focused node doesn't exist"),
+                    ));
+                }
+            }
+        }
+
+        if let Some(key) = self.selected_key {
+            if self.find_node_path(key).is_none() {
+                violations.push(violation(
+                    LayoutViolationKind::InvalidFocus,
+                    Vec::new(),
+                    String::from("selected node is missing or unreachable from the root"),
+                ));
+            }
+        }
+
+        violations
+    }
+
+    fn check_node_invariants(
+        &self,
+        key: NodeKey,
+        path: &mut Vec<usize>,
+        visited: &mut HashSet<NodeKey>,
+        violations: &mut Vec<LayoutViolation>,
+    ) {
+        let Some(node) = self.get_node(key) else {
+            violations.push(violation(
+                LayoutViolationKind::BrokenLink,
+                path.clone(),
+                String::from("node doesn't exist"),
+            ));
+            return;
+        };
+
+        if !visited.insert(key) {
+            violations.push(violation(
+                LayoutViolationKind::Cycle,
+                path.clone(),
+                String::from("node is reachable more than once"),
+            ));
+            return;
+        }
+
+        let NodeData::Container(container) = node else {
+            return;
+        };
+
+        if container.child_percents.len() != container.children.len() {
+            violations.push(violation(
+                LayoutViolationKind::PercentSum,
+                path.clone(),
+                format!(
+                    "container has {} children but {} sizes",
+                    container.children.len(),
+                    container.child_percents.len()
+                ),
+            ));
+        } else if matches!(container.layout, Layout::SplitH | Layout::SplitV)
+            && !container.children.is_empty()
+        {
+            let sum: f64 = container.child_percents.iter().sum();
+            let all_valid = container
+                .child_percents
+                .iter()
+                .all(|percent| percent.is_finite() && *percent >= 0.);
+            if !all_valid || (sum - 1.).abs() > 1e-6 {
+                violations.push(violation(
+                    LayoutViolationKind::PercentSum,
+                    path.clone(),
+                    format!(
+                        "child sizes {:?} add up to {sum} instead of 1",
+                        container.child_percents
+                    ),
+                ));
+            }
+        }
+
+        if let Some(focused) = container
+            .focus_stack
+            .iter()
+            .find(|focused| !container.children.contains(*focused))
+        {
+            violations.push(violation(
+                LayoutViolationKind::InvalidFocus,
+                path.clone(),
+                format!("container remembers focus on {focused:?}, which isn't its child"),
+            ));
+        }
+
+        if let [child_key] = container.children[..] {
+            if let Some(child) = self.get_container(child_key) {
+                if child.layout == container.layout {
+                    violations.push(violation(
+                        LayoutViolationKind::RedundantNesting,
+                        path.clone(),
+                        format!(
+                            "container has a single child container with the same {:?} layout",
+                            container.layout
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for (idx, &child_key) in container.children.iter().enumerate() {
+            path.push(idx);
+
+            if self.parents.get(child_key).copied().flatten() != Some(key) {
+                violations.push(violation(
+                    LayoutViolationKind::BrokenLink,
+                    path.clone(),
+                    String::from("node doesn't link back to its parent"),
+                ));
+            }
+
+            self.check_node_invariants(child_key, path, visited, violations);
+            path.pop();
+        }
+    }
+
     /// Current focus path within the tree.
     /// Uses cached path when generation and focused_key haven't changed.
     pub fn focus_path(&self) -> Vec<usize> {
@@ -1567,7 +1857,10 @@ impl<W: LayoutElement> ContainerTree<W> {
 
     /// Calculate and apply layout to the tree, with control over resize animation.
     pub fn layout_with_resize_animation(&mut self, animate_resize: bool) {
-        let animate = !self.options.animations.off;
+        // Positions are only cached for the movement and open animations, so skip that when both
+        // are off.
+        let anims = &self.options.animations;
+        let animate = !anims.off && !(anims.window_movement.0.off && anims.window_open.anim.off);
         self.layout_with_animations(animate, animate_resize);
     }
 
@@ -1588,6 +1881,8 @@ impl<W: LayoutElement> ContainerTree<W> {
         self.pending_layouts = None;
         self.pending_transaction = None;
         self.pending_relayout = false;
+        // Tiles animate below whether the working area changed or not.
+        self.working_area_changed = false;
 
         self.prev_positions_cache.clear();
         if animate {
@@ -1608,26 +1903,38 @@ impl<W: LayoutElement> ContainerTree<W> {
                 true,
                 animate,
                 animate_resize,
-                0.0,
+                TabBarInset::NONE,
                 false,
             );
         }
 
         if animate {
+            let anims = &self.options.animations;
+            let animate_move = !anims.window_movement.0.off;
+            let animate_open = !anims.window_open.anim.off;
+
             // Iterate by index to avoid cloning leaf_layouts
             for i in 0..self.leaf_layouts.len() {
                 let key = self.leaf_layouts[i].key;
                 let rect_loc = self.leaf_layouts[i].rect.loc;
                 let prev_loc = self.prev_positions_cache.get(&key).copied();
+                let insert_edge = prev_loc
+                    .is_none()
+                    .then(|| self.insert_edge(&self.leaf_layouts[i].path));
                 if let Some(tile) = self.get_tile_mut(key) {
+                    if let Some(edge) = insert_edge {
+                        tile.set_insert_edge(edge);
+                    }
+
                     if let Some(prev_loc) = prev_loc {
                         let delta = prev_loc - rect_loc;
-                        if delta.x.abs() > MOVE_ANIMATION_THRESHOLD
-                            || delta.y.abs() > MOVE_ANIMATION_THRESHOLD
+                        if animate_move
+                            && (delta.x.abs() > MOVE_ANIMATION_THRESHOLD
+                                || delta.y.abs() > MOVE_ANIMATION_THRESHOLD)
                         {
                             tile.animate_move_from(delta);
                         }
-                    } else {
+                    } else if animate_open {
                         tile.start_open_animation();
                     }
                 }
@@ -1641,8 +1948,19 @@ impl<W: LayoutElement> ContainerTree<W> {
         !self.options.disable_transactions
     }
 
+    /// Area for the tiles: the working area, minus the struts, minus the outer gaps.
+    ///
+    /// Struts add up with layer-shell exclusive zones on every edge, so a panel and a strut on the
+    /// same edge both push the tiles away.
     pub fn layout_area(&self) -> Rectangle<f64, Logical> {
         let mut area = self.working_area;
+
+        let struts = self.options.layout.struts;
+        area.loc.x += struts.left.0;
+        area.loc.y += struts.top.0;
+        area.size.w = (area.size.w - struts.left.0 - struts.right.0).max(0.0);
+        area.size.h = (area.size.h - struts.top.0 - struts.bottom.0).max(0.0);
+
         let gap = self.options.layout.gaps;
         if gap > 0.0 {
             area.loc.x += gap;
@@ -1726,6 +2044,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         self.pending_layouts = Some(PendingLayout {
             data,
             blocker: transaction.blocker(),
+            deadline: self.clock.now_unadjusted() + TIME_LIMIT,
         });
         drop(transaction);
         if should_apply_now && self.apply_pending_layouts_if_ready() {
@@ -1738,7 +2057,10 @@ impl<W: LayoutElement> ContainerTree<W> {
         let Some(pending) = &self.pending_layouts else {
             return false;
         };
-        if pending.blocker.state() != BlockerState::Released {
+        // Don't wait forever for windows that never ack their new size.
+        if pending.blocker.state() != BlockerState::Released
+            && self.clock.now_unadjusted() < pending.deadline
+        {
             return false;
         }
         let pending = self.pending_layouts.take().unwrap();
@@ -1751,6 +2073,13 @@ impl<W: LayoutElement> ContainerTree<W> {
         self.pending_layouts.is_some()
     }
 
+    /// Returns the time after which the pending layout applies without waiting for acks.
+    pub fn pending_layout_deadline(&self) -> Option<Duration> {
+        self.pending_layouts
+            .as_ref()
+            .map(|pending| pending.deadline)
+    }
+
     pub fn take_pending_relayout(&mut self) -> bool {
         std::mem::take(&mut self.pending_relayout)
     }
@@ -1759,7 +2088,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         &self,
         tile: &Tile<W>,
         tile_size: Size<f64, Logical>,
-        tab_offset: f64,
+        tab_bar: TabBarInset,
     ) -> LayoutRequest {
         if tile.window().pending_sizing_mode().is_fullscreen() {
             LayoutRequest {
@@ -1774,7 +2103,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         } else {
             LayoutRequest {
                 mode: LayoutRequestMode::Normal,
-                size: tile.requested_window_size_for_tile(tile_size, tab_offset),
+                size: tile.requested_window_size_for_tile(tile_size, tab_bar),
             }
         }
     }
@@ -1783,13 +2112,22 @@ impl<W: LayoutElement> ContainerTree<W> {
         let mut data = LayoutData {
             leaf_layouts: Vec::new(),
             container_geometries: HashMap::new(),
-            tab_bar_offsets: HashMap::new(),
+            container_layouts: HashMap::new(),
+            tab_bar_insets: HashMap::new(),
             titlebar_flags: HashMap::new(),
         };
 
         let mut path = Vec::new();
         let area = self.layout_area();
-        self.collect_layout_node(root_key, area, &mut path, true, 0.0, false, &mut data);
+        self.collect_layout_node(
+            root_key,
+            area,
+            &mut path,
+            true,
+            TabBarInset::NONE,
+            false,
+            &mut data,
+        );
         data
     }
 
@@ -1799,19 +2137,19 @@ impl<W: LayoutElement> ContainerTree<W> {
         rect: Rectangle<f64, Logical>,
         path: &mut Vec<usize>,
         visible: bool,
-        tab_bar_offset: f64,
+        tab_bar: TabBarInset,
         draw_titlebar: bool,
         data: &mut LayoutData,
     ) {
         let (layout, child_count, focused_idx, child_percents_sum) = match self.get_node(node_key) {
             Some(NodeData::Leaf(tile)) => {
-                let (offset, show_titlebar) = if tile.window().pending_sizing_mode().is_fullscreen()
+                let (inset, show_titlebar) = if tile.window().pending_sizing_mode().is_fullscreen()
                 {
-                    (0.0, false)
+                    (TabBarInset::NONE, false)
                 } else {
-                    (tab_bar_offset, draw_titlebar)
+                    (tab_bar, draw_titlebar)
                 };
-                data.tab_bar_offsets.insert(node_key, offset);
+                data.tab_bar_insets.insert(node_key, inset);
                 data.titlebar_flags.insert(node_key, show_titlebar);
                 data.leaf_layouts.push(LeafLayoutInfo {
                     key: node_key,
@@ -1823,6 +2161,7 @@ impl<W: LayoutElement> ContainerTree<W> {
             }
             Some(NodeData::Container(container)) => {
                 data.container_geometries.insert(node_key, rect);
+                data.container_layouts.insert(node_key, container.layout());
                 let percents = container.child_percents_slice();
                 let sum: f64 = percents.iter().copied().sum();
                 (
@@ -1851,22 +2190,22 @@ impl<W: LayoutElement> ContainerTree<W> {
                 };
                 let available_width = (rect.size.w - total_gap).max(0.0);
 
-                // Pre-compute normalized percentages
-                let percents: Vec<f64> = self.get_normalized_child_percents(node_key, child_count, child_percents_sum);
+                // Distribute the space according to the percentages, honoring min/max sizes.
+                let sizes = self.split_child_sizes(
+                    node_key,
+                    child_count,
+                    child_percents_sum,
+                    available_width,
+                    true,
+                );
 
                 let mut cursor_x = rect.loc.x;
-                let mut used_width = 0.0;
 
                 for idx in 0..child_count {
                     let Some(child_key) = self.get_container_child_at(node_key, idx) else {
                         continue;
                     };
-                    let percent = percents.get(idx).copied().unwrap_or(1.0 / child_count as f64);
-                    let width = if idx == child_count - 1 {
-                        (available_width - used_width).max(0.0)
-                    } else {
-                        (available_width * percent).max(0.0)
-                    };
+                    let width = sizes[idx];
 
                     let child_rect = Rectangle::new(
                         Point::from((cursor_x, rect.loc.y)),
@@ -1874,20 +2213,19 @@ impl<W: LayoutElement> ContainerTree<W> {
                     );
 
                     path.push(idx);
-                    let (child_offset, child_titlebar) =
+                    let (child_tab_bar, child_titlebar) =
                         self.split_child_titlebar(child_key, split_bar_height);
                     self.collect_layout_node(
                         child_key,
                         child_rect,
                         path,
                         visible,
-                        child_offset,
+                        child_tab_bar,
                         child_titlebar,
                         data,
                     );
                     path.pop();
 
-                    used_width += width;
                     if idx + 1 < child_count {
                         cursor_x += width + gap;
                     }
@@ -1902,22 +2240,22 @@ impl<W: LayoutElement> ContainerTree<W> {
                 };
                 let available_height = (rect.size.h - total_gap).max(0.0);
 
-                // Pre-compute normalized percentages
-                let percents: Vec<f64> = self.get_normalized_child_percents(node_key, child_count, child_percents_sum);
+                // Distribute the space according to the percentages, honoring min/max sizes.
+                let sizes = self.split_child_sizes(
+                    node_key,
+                    child_count,
+                    child_percents_sum,
+                    available_height,
+                    false,
+                );
 
                 let mut cursor_y = rect.loc.y;
-                let mut used_height = 0.0;
 
                 for idx in 0..child_count {
                     let Some(child_key) = self.get_container_child_at(node_key, idx) else {
                         continue;
                     };
-                    let percent = percents.get(idx).copied().unwrap_or(1.0 / child_count as f64);
-                    let height = if idx == child_count - 1 {
-                        (available_height - used_height).max(0.0)
-                    } else {
-                        (available_height * percent).max(0.0)
-                    };
+                    let height = sizes[idx];
 
                     let child_rect = Rectangle::new(
                         Point::from((rect.loc.x, cursor_y)),
@@ -1925,20 +2263,19 @@ impl<W: LayoutElement> ContainerTree<W> {
                     );
 
                     path.push(idx);
-                    let (child_offset, child_titlebar) =
+                    let (child_tab_bar, child_titlebar) =
                         self.split_child_titlebar(child_key, split_bar_height);
                     self.collect_layout_node(
                         child_key,
                         child_rect,
                         path,
                         visible,
-                        child_offset,
+                        child_tab_bar,
                         child_titlebar,
                         data,
                     );
                     path.pop();
 
-                    used_height += height;
                     if idx + 1 < child_count {
                         cursor_y += height + gap;
                     }
@@ -1953,19 +2290,9 @@ impl<W: LayoutElement> ContainerTree<W> {
                     inner_rect.size.h = (inner_rect.size.h - gap * 2.0).max(0.0);
                 }
 
-                let bar_row_height = self.tab_bar_row_height();
-                let mut tab_offset = 0.0;
-                if bar_row_height > 0.0 && child_count > 0 {
-                    let bar_height = match layout {
-                        Layout::Tabbed => bar_row_height,
-                        Layout::Stacked => bar_row_height * child_count as f64,
-                        _ => 0.0,
-                    };
-                    let total_bar_height = (bar_height + self.tab_bar_spacing())
-                        .min(inner_rect.size.h)
-                        .max(0.0);
-                    tab_offset = total_bar_height;
-                }
+                let tab_bar_position = self.tab_bar_position(node_key);
+                let tab_bar = self.tab_bar_inset(layout, tab_bar_position, inner_rect, child_count);
+                let (_, content_rect) = tab_bar.split(inner_rect);
 
                 let focused_idx = focused_idx.unwrap_or(0).min(child_count.saturating_sub(1));
 
@@ -1976,22 +2303,17 @@ impl<W: LayoutElement> ContainerTree<W> {
                     path.push(idx);
                     let child_visible = visible && idx == focused_idx;
                     let is_leaf = matches!(self.get_node(child_key), Some(NodeData::Leaf(_)));
-                    let (child_rect, child_offset) = if is_leaf {
-                        (inner_rect, tab_offset)
+                    let (child_rect, child_tab_bar) = if is_leaf {
+                        (inner_rect, tab_bar)
                     } else {
-                        let mut content_rect = inner_rect;
-                        if tab_offset > 0.0 {
-                            content_rect.loc.y += tab_offset;
-                            content_rect.size.h = (content_rect.size.h - tab_offset).max(0.0);
-                        }
-                        (content_rect, 0.0)
+                        (content_rect, TabBarInset::NONE)
                     };
                     self.collect_layout_node(
                         child_key,
                         child_rect,
                         path,
                         child_visible,
-                        child_offset,
+                        child_tab_bar,
                         false,
                         data,
                     );
@@ -2007,19 +2329,22 @@ impl<W: LayoutElement> ContainerTree<W> {
             let Some(tile) = self.get_tile(info.key) else {
                 continue;
             };
-            let request =
-                self.layout_request_for(tile, info.rect.size, tile.tab_bar_offset());
+            let request = self.layout_request_for(tile, info.rect.size, tile.tab_bar_inset());
             current.insert(info.key, request);
         }
 
         let mut changed = HashSet::new();
         for info in &data.leaf_layouts {
-            let offset = data.tab_bar_offsets.get(&info.key).copied().unwrap_or(0.0);
+            let inset = data
+                .tab_bar_insets
+                .get(&info.key)
+                .copied()
+                .unwrap_or(TabBarInset::NONE);
             let Some(tile) = self.get_tile(info.key) else {
                 changed.insert(info.key);
                 continue;
             };
-            let request = self.layout_request_for(tile, info.rect.size, offset);
+            let request = self.layout_request_for(tile, info.rect.size, inset);
             if current.get(&info.key).map_or(true, |old| *old != request) {
                 changed.insert(info.key);
             }
@@ -2039,15 +2364,19 @@ impl<W: LayoutElement> ContainerTree<W> {
             let Some(tile) = self.get_tile_mut(info.key) else {
                 continue;
             };
-            let offset = data.tab_bar_offsets.get(&info.key).copied().unwrap_or(0.0);
+            let inset = data
+                .tab_bar_insets
+                .get(&info.key)
+                .copied()
+                .unwrap_or(TabBarInset::NONE);
             let show_titlebar = data
                 .titlebar_flags
                 .get(&info.key)
                 .copied()
                 .unwrap_or(false);
-            let old_offset = tile.tab_bar_offset();
+            let old_inset = tile.tab_bar_inset();
             let old_titlebar = tile.draw_titlebar();
-            tile.set_tab_bar_offset(offset);
+            tile.set_tab_bar_inset(inset);
             tile.set_draw_titlebar(show_titlebar);
 
             let tx = changed
@@ -2062,20 +2391,51 @@ impl<W: LayoutElement> ContainerTree<W> {
                 tile.request_tile_size(size, animate_resize, tx);
             }
 
-            tile.set_tab_bar_offset(old_offset);
+            tile.set_tab_bar_inset(old_inset);
             tile.set_draw_titlebar(old_titlebar);
         }
     }
 
     fn apply_layout_data(&mut self, data: LayoutData) {
+        // When a panel appears or goes away, slide the tiles over rather than have them jump.
+        // Changes within the tree animate on their own.
+        if std::mem::take(&mut self.working_area_changed) {
+            let anims = &self.options.animations;
+            if !anims.off && !anims.window_movement.0.off {
+                let prev_positions = self
+                    .leaf_layouts
+                    .iter()
+                    .map(|info| (info.key, info.rect.loc))
+                    .collect::<HashMap<_, _>>();
+                for info in &data.leaf_layouts {
+                    let Some(prev_loc) = prev_positions.get(&info.key) else {
+                        continue;
+                    };
+                    let delta = *prev_loc - info.rect.loc;
+                    if delta.x.abs() > MOVE_ANIMATION_THRESHOLD
+                        || delta.y.abs() > MOVE_ANIMATION_THRESHOLD
+                    {
+                        if let Some(tile) = self.get_tile_mut(info.key) {
+                            tile.animate_move_from(delta);
+                        }
+                    }
+                }
+            }
+        }
+
         for (key, rect) in data.container_geometries {
             if let Some(NodeData::Container(container)) = self.get_node_mut(key) {
                 container.set_geometry(rect);
             }
         }
-        for (key, offset) in data.tab_bar_offsets {
+        for (key, layout) in data.container_layouts {
+            if let Some(NodeData::Container(container)) = self.get_node_mut(key) {
+                container.applied_layout = layout;
+            }
+        }
+        for (key, inset) in data.tab_bar_insets {
             if let Some(tile) = self.get_tile_mut(key) {
-                tile.set_tab_bar_offset(offset);
+                tile.set_tab_bar_inset(inset);
             }
         }
         for (key, show_titlebar) in data.titlebar_flags {
@@ -2095,7 +2455,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         visible: bool,
         animate: bool,
         animate_resize: bool,
-        tab_bar_offset: f64,
+        tab_bar: TabBarInset,
         draw_titlebar: bool,
     ) {
         // We need to work around borrow checker by getting info first
@@ -2103,16 +2463,13 @@ impl<W: LayoutElement> ContainerTree<W> {
             Some(NodeData::Leaf(_)) => {
                 // Handle leaf
                 if let Some(NodeData::Leaf(tile)) = self.get_node_mut(node_key) {
-                    let (offset, show_titlebar) = if tile
-                        .window()
-                        .pending_sizing_mode()
-                        .is_fullscreen()
-                    {
-                        (0.0, false)
-                    } else {
-                        (tab_bar_offset, draw_titlebar)
-                    };
-                    tile.set_tab_bar_offset(offset);
+                    let (inset, show_titlebar) =
+                        if tile.window().pending_sizing_mode().is_fullscreen() {
+                            (TabBarInset::NONE, false)
+                        } else {
+                            (tab_bar, draw_titlebar)
+                        };
+                    tile.set_tab_bar_inset(inset);
                     tile.set_draw_titlebar(show_titlebar);
                     let size = Size::from((rect.size.w, rect.size.h));
                     if tile.window().pending_sizing_mode().is_fullscreen() {
@@ -2148,6 +2505,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         // Update container geometry
         if let Some(NodeData::Container(container)) = self.get_node_mut(node_key) {
             container.set_geometry(rect);
+            container.applied_layout = layout;
         }
 
         if child_count == 0 {
@@ -2167,22 +2525,22 @@ impl<W: LayoutElement> ContainerTree<W> {
                 };
                 let available_width = (rect.size.w - total_gap).max(0.0);
 
-                // Pre-compute normalized percentages
-                let percents: Vec<f64> = self.get_normalized_child_percents(node_key, child_count, child_percents_sum);
+                // Distribute the space according to the percentages, honoring min/max sizes.
+                let sizes = self.split_child_sizes(
+                    node_key,
+                    child_count,
+                    child_percents_sum,
+                    available_width,
+                    true,
+                );
 
                 let mut cursor_x = rect.loc.x;
-                let mut used_width = 0.0;
 
                 for idx in 0..child_count {
                     let Some(child_key) = self.get_container_child_at(node_key, idx) else {
                         continue;
                     };
-                    let percent = percents.get(idx).copied().unwrap_or(1.0 / child_count as f64);
-                    let width = if idx == child_count - 1 {
-                        (available_width - used_width).max(0.0)
-                    } else {
-                        (available_width * percent).max(0.0)
-                    };
+                    let width = sizes[idx];
 
                     let child_rect = Rectangle::new(
                         Point::from((cursor_x, rect.loc.y)),
@@ -2190,7 +2548,7 @@ impl<W: LayoutElement> ContainerTree<W> {
                     );
 
                     path.push(idx);
-                    let (child_offset, child_titlebar) =
+                    let (child_tab_bar, child_titlebar) =
                         self.split_child_titlebar(child_key, split_bar_height);
                     self.layout_node(
                         child_key,
@@ -2199,12 +2557,11 @@ impl<W: LayoutElement> ContainerTree<W> {
                         visible,
                         animate,
                         animate_resize,
-                        child_offset,
+                        child_tab_bar,
                         child_titlebar,
                     );
                     path.pop();
 
-                    used_width += width;
                     if idx + 1 < child_count {
                         cursor_x += width + gap;
                     }
@@ -2220,22 +2577,22 @@ impl<W: LayoutElement> ContainerTree<W> {
                 };
                 let available_height = (rect.size.h - total_gap).max(0.0);
 
-                // Pre-compute normalized percentages
-                let percents: Vec<f64> = self.get_normalized_child_percents(node_key, child_count, child_percents_sum);
+                // Distribute the space according to the percentages, honoring min/max sizes.
+                let sizes = self.split_child_sizes(
+                    node_key,
+                    child_count,
+                    child_percents_sum,
+                    available_height,
+                    false,
+                );
 
                 let mut cursor_y = rect.loc.y;
-                let mut used_height = 0.0;
 
                 for idx in 0..child_count {
                     let Some(child_key) = self.get_container_child_at(node_key, idx) else {
                         continue;
                     };
-                    let percent = percents.get(idx).copied().unwrap_or(1.0 / child_count as f64);
-                    let height = if idx == child_count - 1 {
-                        (available_height - used_height).max(0.0)
-                    } else {
-                        (available_height * percent).max(0.0)
-                    };
+                    let height = sizes[idx];
 
                     let child_rect = Rectangle::new(
                         Point::from((rect.loc.x, cursor_y)),
@@ -2243,7 +2600,7 @@ impl<W: LayoutElement> ContainerTree<W> {
                     );
 
                     path.push(idx);
-                    let (child_offset, child_titlebar) =
+                    let (child_tab_bar, child_titlebar) =
                         self.split_child_titlebar(child_key, split_bar_height);
                     self.layout_node(
                         child_key,
@@ -2252,12 +2609,11 @@ impl<W: LayoutElement> ContainerTree<W> {
                         visible,
                         animate,
                         animate_resize,
-                        child_offset,
+                        child_tab_bar,
                         child_titlebar,
                     );
                     path.pop();
 
-                    used_height += height;
                     if idx + 1 < child_count {
                         cursor_y += height + gap;
                     }
@@ -2273,19 +2629,9 @@ impl<W: LayoutElement> ContainerTree<W> {
                     inner_rect.size.h = (inner_rect.size.h - gap * 2.0).max(0.0);
                 }
 
-                let bar_row_height = self.tab_bar_row_height();
-                let mut tab_offset = 0.0;
-                if bar_row_height > 0.0 && child_count > 0 {
-                    let bar_height = match layout {
-                        Layout::Tabbed => bar_row_height,
-                        Layout::Stacked => bar_row_height * child_count as f64,
-                        _ => 0.0,
-                    };
-                    let total_bar_height = (bar_height + self.tab_bar_spacing())
-                        .min(inner_rect.size.h)
-                        .max(0.0);
-                    tab_offset = total_bar_height;
-                }
+                let tab_bar_position = self.tab_bar_position(node_key);
+                let tab_bar = self.tab_bar_inset(layout, tab_bar_position, inner_rect, child_count);
+                let (_, content_rect) = tab_bar.split(inner_rect);
 
                 let focused_idx = focused_idx.unwrap_or(0).min(child_count.saturating_sub(1));
 
@@ -2296,15 +2642,10 @@ impl<W: LayoutElement> ContainerTree<W> {
                     path.push(idx);
                     let child_visible = visible && idx == focused_idx;
                     let is_leaf = matches!(self.get_node(child_key), Some(NodeData::Leaf(_)));
-                    let (child_rect, child_offset) = if is_leaf {
-                        (inner_rect, tab_offset)
+                    let (child_rect, child_tab_bar) = if is_leaf {
+                        (inner_rect, tab_bar)
                     } else {
-                        let mut content_rect = inner_rect;
-                        if tab_offset > 0.0 {
-                            content_rect.loc.y += tab_offset;
-                            content_rect.size.h = (content_rect.size.h - tab_offset).max(0.0);
-                        }
-                        (content_rect, 0.0)
+                        (content_rect, TabBarInset::NONE)
                     };
                     self.layout_node(
                         child_key,
@@ -2313,7 +2654,7 @@ impl<W: LayoutElement> ContainerTree<W> {
                         child_visible,
                         animate,
                         animate_resize,
-                        child_offset,
+                        child_tab_bar,
                         false,
                     );
                     path.pop();
@@ -2358,20 +2699,119 @@ impl<W: LayoutElement> ContainerTree<W> {
         }
     }
 
+    /// Compute child sizes along the split axis of a container.
+    ///
+    /// Space is distributed according to the child percentages, but every child is kept within
+    /// its min/max size, coming from the windows and their window rules.
+    fn split_child_sizes(
+        &self,
+        container_key: NodeKey,
+        child_count: usize,
+        percents_sum: f64,
+        available: f64,
+        horizontal: bool,
+    ) -> Vec<f64> {
+        let mut percents =
+            self.get_normalized_child_percents(container_key, child_count, percents_sum);
+        percents.resize(child_count, 1.0 / child_count.max(1) as f64);
+
+        let limits: Vec<_> = (0..child_count)
+            .map(|idx| {
+                self.get_container_child_at(container_key, idx)
+                    .map_or((0.0, 0.0), |key| self.node_size_limits(key, horizontal))
+            })
+            .collect();
+
+        distribute_with_limits(available, &percents, &limits)
+    }
+
+    /// Min and max size of a node along one axis.
+    ///
+    /// A max size of 0 means unlimited, same as for windows.
+    fn node_size_limits(&self, node_key: NodeKey, horizontal: bool) -> (f64, f64) {
+        match self.get_node(node_key) {
+            Some(NodeData::Leaf(tile)) => {
+                if tile.window().pending_sizing_mode().is_fullscreen() {
+                    return (0.0, 0.0);
+                }
+
+                let min = tile.min_size_nonfullscreen();
+                let max = tile.max_size_nonfullscreen();
+                if horizontal {
+                    (min.w, max.w)
+                } else {
+                    (min.h, max.h)
+                }
+            }
+            Some(NodeData::Container(container)) => {
+                let child_count = container.child_count();
+                if child_count == 0 {
+                    return (0.0, 0.0);
+                }
+
+                let gap = self.options.layout.gaps;
+                let limits = container
+                    .children()
+                    .iter()
+                    .map(|&key| self.node_size_limits(key, horizontal));
+
+                match (container.layout(), horizontal) {
+                    (Layout::SplitH, true) | (Layout::SplitV, false) => {
+                        // Children are laid out along this axis, so their sizes add up.
+                        let gaps = gap * (child_count as f64 - 1.0);
+                        let mut min = gaps;
+                        let mut max = gaps;
+                        let mut bounded = true;
+                        for (child_min, child_max) in limits {
+                            min += child_min;
+                            if child_max > 0.0 {
+                                max += child_max;
+                            } else {
+                                bounded = false;
+                            }
+                        }
+                        (min, if bounded { max.max(min) } else { 0.0 })
+                    }
+                    (layout, _) => {
+                        // Children share the full size along this axis.
+                        let extra = match layout {
+                            Layout::Tabbed | Layout::Stacked => gap * 2.0,
+                            Layout::SplitH | Layout::SplitV => 0.0,
+                        };
+                        let mut min: f64 = 0.0;
+                        let mut max: f64 = 0.0;
+                        let mut bounded = true;
+                        for (child_min, child_max) in limits {
+                            min = min.max(child_min);
+                            if child_max > 0.0 {
+                                max = max.max(child_max);
+                            } else {
+                                bounded = false;
+                            }
+                        }
+                        let max = if bounded { max.max(min) + extra } else { 0.0 };
+                        (min + extra, max)
+                    }
+                }
+            }
+            None => (0.0, 0.0),
+        }
+    }
+
     fn split_child_titlebar(
         &self,
         child_key: NodeKey,
         split_bar_height: f64,
-    ) -> (f64, bool) {
+    ) -> (TabBarInset, bool) {
         if split_bar_height <= 0.0 {
-            return (0.0, false);
+            return (TabBarInset::NONE, false);
         }
 
         let is_leaf = matches!(self.get_node(child_key), Some(NodeData::Leaf(_)));
         if is_leaf {
-            (split_bar_height, true)
+            (TabBarInset::top(split_bar_height), true)
         } else {
-            (0.0, false)
+            (TabBarInset::NONE, false)
         }
     }
 
@@ -2379,21 +2819,51 @@ impl<W: LayoutElement> ContainerTree<W> {
         0.0
     }
 
-    fn tab_bar_rect(
+    /// Returns the edge of the container that its tab bar sits on.
+    fn tab_bar_position(&self, container_key: NodeKey) -> TabIndicatorPosition {
+        self.get_container(container_key)
+            .and_then(|container| container.tab_bar_position)
+            .unwrap_or(self.options.layout.tab_bar.position)
+    }
+
+    /// Returns the space that the tab bar of a tabbed or stacked container takes in the area.
+    fn tab_bar_inset(
         &self,
         layout: Layout,
-        rect: Rectangle<f64, Logical>,
+        position: TabIndicatorPosition,
+        area: Rectangle<f64, Logical>,
         tab_count: usize,
-    ) -> Option<(Rectangle<f64, Logical>, f64)> {
-        if tab_count == 0 {
-            return None;
+    ) -> TabBarInset {
+        let row_height = self.tab_bar_row_height();
+        if row_height <= 0.0 || tab_count == 0 {
+            return TabBarInset::NONE;
         }
 
-        let row_height = self.tab_bar_row_height();
-        if row_height <= 0.0 {
-            return None;
+        let (size, max) = match (layout, position) {
+            (_, TabIndicatorPosition::Left | TabIndicatorPosition::Right) => {
+                let width = self.options.layout.tab_bar.side_width;
+                (
+                    round_logical_in_physical_max1(self.scale, width),
+                    area.size.w,
+                )
+            }
+            (Layout::Tabbed, _) => (row_height, area.size.h),
+            (Layout::Stacked, _) => (row_height * tab_count as f64, area.size.h),
+            _ => return TabBarInset::NONE,
+        };
+        TabBarInset {
+            size: (size + self.tab_bar_spacing()).min(max).max(0.0),
+            position,
         }
+    }
 
+    fn tab_bar_rect(
+        &self,
+        layout: Layout,
+        position: TabIndicatorPosition,
+        rect: Rectangle<f64, Logical>,
+        tab_count: usize,
+    ) -> Option<(Rectangle<f64, Logical>, f64)> {
         let gap = self.options.layout.gaps;
         let mut inner_rect = rect;
         if gap > 0.0 {
@@ -2403,29 +2873,13 @@ impl<W: LayoutElement> ContainerTree<W> {
             inner_rect.size.h = (inner_rect.size.h - gap * 2.0).max(0.0);
         }
 
-        let spacing = self.tab_bar_spacing();
-        let base_height = match layout {
-            Layout::Tabbed => row_height,
-            Layout::Stacked => row_height * tab_count as f64,
-            _ => 0.0,
-        };
-        let bar_height = (base_height + spacing).min(inner_rect.size.h).max(0.0);
-        if bar_height <= 0.0 {
+        let tab_bar = self.tab_bar_inset(layout, position, inner_rect, tab_count);
+        if tab_bar.is_none() {
             return None;
         }
 
-        let bar_rect = Rectangle::new(
-            inner_rect.loc,
-            Size::from((inner_rect.size.w, bar_height)),
-        );
-
-        let actual_row_height = if layout == Layout::Stacked {
-            row_height
-        } else {
-            row_height
-        };
-
-        Some((bar_rect, actual_row_height))
+        let (bar_rect, _) = tab_bar.split(inner_rect);
+        Some((bar_rect, self.tab_bar_row_height()))
     }
 
     /// Get all windows in the tree (depth-first traversal)
@@ -2494,10 +2948,16 @@ impl<W: LayoutElement> ContainerTree<W> {
             return;
         };
 
-        if visible && matches!(container.layout, Layout::Tabbed | Layout::Stacked) {
-            if let Some((rect, row_height)) =
-                self.tab_bar_rect(container.layout, container.geometry, container.children.len())
-            {
+        // Use the layout on screen, so that the tab bar doesn't flip before the windows do.
+        let layout = container.applied_layout;
+        let position = self.tab_bar_position(node_key);
+        if visible && matches!(layout, Layout::Tabbed | Layout::Stacked) {
+            if let Some((rect, row_height)) = self.tab_bar_rect(
+                layout,
+                position,
+                container.geometry,
+                container.children.len(),
+            ) {
                 let focused_idx = container.focused_child_index().unwrap_or(0);
                 let tabs = container
                     .children
@@ -2510,6 +2970,7 @@ impl<W: LayoutElement> ContainerTree<W> {
                             title,
                             is_focused: idx == focused_idx,
                             is_urgent: self.subtree_has_urgent(child_key),
+                            has_activity: self.subtree_has_activity(child_key),
                             block_out_from,
                         }
                     })
@@ -2517,7 +2978,8 @@ impl<W: LayoutElement> ContainerTree<W> {
 
                 out.push(TabBarInfo {
                     path: path.clone(),
-                    layout: container.layout,
+                    layout,
+                    position,
                     rect,
                     row_height,
                     tabs,
@@ -2528,7 +2990,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         let focused_idx = container.focused_child_index().unwrap_or(0);
         for (idx, &child_key) in container.children.iter().enumerate() {
             path.push(idx);
-            let child_visible = match container.layout {
+            let child_visible = match layout {
                 Layout::Tabbed | Layout::Stacked => idx == focused_idx,
                 _ => true,
             };
@@ -2580,6 +3042,17 @@ impl<W: LayoutElement> ContainerTree<W> {
         }
     }
 
+    fn subtree_has_activity(&self, node_key: NodeKey) -> bool {
+        match self.get_node(node_key) {
+            Some(NodeData::Leaf(tile)) => tile.window().has_activity(),
+            Some(NodeData::Container(container)) => container
+                .children
+                .iter()
+                .any(|&child_key| self.subtree_has_activity(child_key)),
+            None => false,
+        }
+    }
+
     fn subtree_has_urgent(&self, node_key: NodeKey) -> bool {
         match self.get_node(node_key) {
             Some(NodeData::Leaf(tile)) => tile.window().is_urgent(),
@@ -3055,6 +3528,38 @@ impl<W: LayoutElement> ContainerTree<W> {
         )
     }
 
+    /// Sets up the container that a newly opened window went into, following its window rules.
+    ///
+    /// The layout only applies when the window has the tree to itself, so that it doesn't change
+    /// the layout around other windows. The tab bar position applies to the container of the
+    /// window unless that container already has its own.
+    pub fn apply_window_rules(
+        &mut self,
+        window_id: &W::Id,
+        layout: Option<Layout>,
+        tab_bar_position: Option<TabIndicatorPosition>,
+    ) {
+        let Some(mut path) = self.find_window(window_id) else {
+            return;
+        };
+        if path.is_empty() {
+            match layout {
+                Some(layout) if self.ensure_root_container_with_layout(layout) => path = vec![0],
+                _ => return,
+            }
+        }
+
+        let parent_path = &path[..path.len() - 1];
+        let Some(parent_key) = self.node_key_for_path_or_root(parent_path) else {
+            return;
+        };
+        if let Some(container) = self.get_container_mut(parent_key) {
+            if container.tab_bar_position.is_none() {
+                container.tab_bar_position = tab_bar_position;
+            }
+        }
+    }
+
     fn ensure_root_container_with_layout(&mut self, layout: Layout) -> bool {
         if let Some(root_key) = self.root {
             if matches!(self.get_node(root_key), Some(NodeData::Leaf(_))) {
@@ -3251,6 +3756,28 @@ impl<W: LayoutElement> ContainerTree<W> {
         self.set_focused_layout(next)
     }
 
+    /// Sets the tab bar position of the focused container.
+    ///
+    /// Like layout changes, this applies to the container of the focused window. Other containers
+    /// keep their own positions, or keep following the config.
+    pub fn set_focused_tab_bar_position(&mut self, position: TabIndicatorPosition) -> bool {
+        let focus_path = self.focus_path();
+        let target_key = if focus_path.is_empty() {
+            self.node_key_for_path_or_root(&[])
+        } else {
+            self.node_key_for_path_or_root(&focus_path[..focus_path.len() - 1])
+        };
+        let Some(container) = target_key.and_then(|key| self.get_container_mut(key)) else {
+            return false;
+        };
+
+        if container.tab_bar_position == Some(position) {
+            return false;
+        }
+        container.tab_bar_position = Some(position);
+        true
+    }
+
     /// Layout of the container that currently owns the focused leaf (if any).
     pub fn focused_layout(&self) -> Option<Layout> {
         let focus_path = self.focus_path();
@@ -3324,6 +3851,22 @@ impl<W: LayoutElement> ContainerTree<W> {
         ))
     }
 
+    /// Edge of the parent container that the leaf at `path` sits at.
+    ///
+    /// New leaves go after the focused one, so this is the trailing edge unless the leaf is first.
+    pub fn insert_edge(&self, path: &[usize]) -> Direction {
+        let Some((&idx, parent_path)) = path.split_last() else {
+            return Direction::Down;
+        };
+
+        match self.container_info(parent_path) {
+            Some((Layout::SplitH, _, count)) if count > 1 && idx == 0 => Direction::Left,
+            Some((Layout::SplitH, _, count)) if count > 1 => Direction::Right,
+            Some((Layout::SplitV, _, count)) if count > 1 && idx == 0 => Direction::Up,
+            _ => Direction::Down,
+        }
+    }
+
     pub fn child_rect_at(
         &self,
         parent_path: &[usize],
@@ -3347,6 +3890,7 @@ impl<W: LayoutElement> ContainerTree<W> {
         let percents = self.get_normalized_child_percents(container_key, child_count, percents_sum);
         let (rect, _) = self.preview_child_rect(
             container.layout(),
+            self.tab_bar_position(container_key),
             container.geometry(),
             child_count,
             &percents,
@@ -3462,6 +4006,26 @@ impl<W: LayoutElement> ContainerTree<W> {
         }
     }
 
+    /// Layout and child percentages of the container at the path.
+    pub fn child_percents_at(&self, path: &[usize]) -> Option<(Layout, Vec<f64>)> {
+        let key = if path.is_empty() {
+            self.root?
+        } else {
+            self.get_node_key_at_path(path)?
+        };
+
+        let container = self.get_container(key)?;
+        let percents = (0..container.child_count())
+            .map(|idx| container.child_percent(idx))
+            .collect();
+        Some((container.layout(), percents))
+    }
+
+    pub fn set_child_percents_at(&mut self, path: &[usize], percents: &[f64]) -> bool {
+        self.container_at_path_mut(path)
+            .is_some_and(|container| container.set_child_percents(percents))
+    }
+
     pub fn container_at_path_mut(&mut self, path: &[usize]) -> Option<&mut ContainerData> {
         let key = if path.is_empty() {
             self.root?
@@ -3658,13 +4222,15 @@ impl<W: LayoutElement> ContainerTree<W> {
                     .iter()
                     .filter_map(|key| index_by_key.get(key).copied())
                     .collect();
-                DetachedNode::Container(DetachedContainer::from_parts(
+                let mut detached = DetachedContainer::from_parts(
                     container.layout,
                     children,
                     container.child_percents,
                     focus_stack,
                     container.preserve_on_single,
-                ))
+                );
+                detached.tab_bar_position = container.tab_bar_position;
+                DetachedNode::Container(detached)
             }
         }
     }
@@ -3693,6 +4259,7 @@ impl<W: LayoutElement> ContainerTree<W> {
                         .filter_map(|idx| node.children.get(*idx).copied())
                         .collect();
                     node.preserve_on_single = container.preserve_on_single;
+                    node.tab_bar_position = container.tab_bar_position;
                     if node.child_percents.len() != node.children.len() {
                         node.recalculate_percentages();
                     } else {
@@ -3779,6 +4346,45 @@ impl<W: LayoutElement> ContainerTree<W> {
         self.insert_key_at_root(index, node_key, focus);
     }
 
+    /// Insert a detached subtree after the root-level children.
+    ///
+    /// An empty tree takes the subtree as its root, so that a saved tree comes back with the same
+    /// shape.
+    pub fn append_subtree(&mut self, subtree: DetachedNode<W>, focus: bool) {
+        if self.root.is_some() {
+            self.insert_subtree_at_root(usize::MAX, subtree, focus);
+            return;
+        }
+
+        let node_key = self.insert_subtree(subtree);
+        self.set_parent(node_key, None);
+        self.root = Some(node_key);
+        self.focus_first_leaf();
+    }
+
+    /// Returns the shape of the tree, for saving it.
+    pub fn template(&self) -> Option<TreeTemplate<W::Id>> {
+        self.node_template(self.root?)
+    }
+
+    fn node_template(&self, node_key: NodeKey) -> Option<TreeTemplate<W::Id>> {
+        match self.get_node(node_key)? {
+            NodeData::Leaf(tile) => Some(TreeTemplate::Leaf(tile.window().id().clone())),
+            NodeData::Container(container) => Some(TreeTemplate::Container {
+                layout: container.layout(),
+                children: container
+                    .children
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, child_key)| {
+                        let child = self.node_template(*child_key)?;
+                        Some((container.child_percent(idx), child))
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
     /// Focus nth (1-based) leaf within the given root child.
     pub fn focus_leaf_in_root_child(&mut self, child_idx: usize, leaf_idx: usize) -> bool {
         self.clear_focus_history();
@@ -4760,6 +5366,23 @@ impl<W: LayoutElement> ContainerTree<W> {
         true
     }
 
+    /// Makes the root a child of its first child container, for testing the invariant checks.
+    #[cfg(test)]
+    pub(crate) fn link_root_into_cycle(&mut self) {
+        let root_key = self.root.unwrap();
+        let child_key = self
+            .get_container(root_key)
+            .unwrap()
+            .children
+            .iter()
+            .copied()
+            .find(|key| self.get_container(*key).is_some())
+            .unwrap();
+        let child = self.get_container_mut(child_key).unwrap();
+        child.children.push(root_key);
+        child.child_percents.push(0.);
+    }
+
     #[cfg(test)]
     pub(crate) fn debug_tree(&self) -> String
     where
@@ -4854,7 +5477,16 @@ impl ContainerTree<Mapped> {
     }
 }
 
-fn layout_to_ipc(layout: Layout) -> LayoutTreeLayout {
+fn violation(kind: LayoutViolationKind, path: Vec<usize>, message: String) -> LayoutViolation {
+    LayoutViolation {
+        kind,
+        workspace_id: None,
+        path,
+        message,
+    }
+}
+
+pub fn layout_to_ipc(layout: Layout) -> LayoutTreeLayout {
     match layout {
         Layout::SplitH => LayoutTreeLayout::SplitH,
         Layout::SplitV => LayoutTreeLayout::SplitV,
@@ -4863,6 +5495,115 @@ fn layout_to_ipc(layout: Layout) -> LayoutTreeLayout {
     }
 }
 
+pub fn layout_from_ipc(layout: LayoutTreeLayout) -> Layout {
+    match layout {
+        LayoutTreeLayout::SplitH => Layout::SplitH,
+        LayoutTreeLayout::SplitV => Layout::SplitV,
+        LayoutTreeLayout::Tabbed => Layout::Tabbed,
+        LayoutTreeLayout::Stacked => Layout::Stacked,
+    }
+}
+
+/// Returns the container layout and tab bar position that the window rules ask for, as taken by
+/// [`ContainerTree::apply_window_rules()`].
+pub(super) fn container_window_rules(
+    rules: &ResolvedWindowRules,
+) -> (Option<Layout>, Option<TabIndicatorPosition>) {
+    let layout =
+        (rules.default_column_display == Some(ColumnDisplay::Tabbed)).then_some(Layout::Tabbed);
+    (layout, rules.tab_bar_position)
+}
+
+/// Distribute `available` space proportionally to `percents`, keeping every child within its
+/// `(min, max)` limits (a max of 0 means unlimited).
+///
+/// Space taken or freed by clamped children is redistributed among the rest. When even the min
+/// sizes don't fit, they are scaled down proportionally.
+pub(super) fn distribute_with_limits(
+    available: f64,
+    percents: &[f64],
+    limits: &[(f64, f64)],
+) -> Vec<f64> {
+    let count = percents.len();
+    let available = available.max(0.0);
+
+    let clamp = |size: f64, (min, max): (f64, f64)| {
+        let size = size.max(min);
+        if max > 0.0 {
+            size.min(max.max(min))
+        } else {
+            size
+        }
+    };
+
+    let min_total: f64 = limits.iter().map(|(min, _)| min).sum();
+    if min_total >= available {
+        if min_total <= 0.0 {
+            return vec![0.0; count];
+        }
+        return limits
+            .iter()
+            .map(|(min, _)| available * min / min_total)
+            .collect();
+    }
+
+    let mut sizes = vec![0.0; count];
+    let mut fixed = vec![false; count];
+
+    loop {
+        let free: Vec<usize> = (0..count).filter(|&idx| !fixed[idx]).collect();
+        if free.is_empty() {
+            break;
+        }
+
+        let fixed_total: f64 = (0..count)
+            .filter(|&idx| fixed[idx])
+            .map(|idx| sizes[idx])
+            .sum();
+        let remaining = available - fixed_total;
+        let percents_total: f64 = free.iter().map(|&idx| percents[idx]).sum();
+        for &idx in &free {
+            sizes[idx] = if percents_total > f64::EPSILON {
+                remaining * percents[idx] / percents_total
+            } else {
+                remaining / free.len() as f64
+            };
+        }
+
+        // Total amount by which clamping would change the sizes.
+        let adjustment: f64 = free
+            .iter()
+            .map(|&idx| clamp(sizes[idx], limits[idx]) - sizes[idx])
+            .sum();
+
+        // Fix the children violating their limits in the direction of the adjustment, then
+        // redistribute the rest of the space among the others.
+        let mut any_fixed = false;
+        for &idx in &free {
+            let clamped = clamp(sizes[idx], limits[idx]);
+            let violates = if adjustment > f64::EPSILON {
+                clamped > sizes[idx]
+            } else if adjustment < -f64::EPSILON {
+                clamped < sizes[idx]
+            } else {
+                clamped != sizes[idx]
+            };
+
+            if violates {
+                sizes[idx] = clamped;
+                fixed[idx] = true;
+                any_fixed = true;
+            }
+        }
+
+        if !any_fixed {
+            break;
+        }
+    }
+
+    sizes
+}
+
 // ============================================================================
 // Additional helper implementations
 // ============================================================================
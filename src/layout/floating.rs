@@ -2,10 +2,13 @@ use std::cell::RefCell;
 use std::cmp::max;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::time::Duration;
 
 use niri_config::utils::MergeWith as _;
-use niri_config::{PresetSize, RelativeTo};
-use niri_ipc::{PositionChange, SizeChange, WindowLayout};
+use niri_config::{FloatingPlacement, PresetSize, RelativeTo, TabIndicatorPosition};
+use niri_ipc::{
+    FloatingArrangement, PositionChange, ResizeAmount, ResizeDirection, SizeChange, WindowLayout,
+};
 use log::warn;
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
@@ -13,9 +16,11 @@ use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size};
 
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
 use super::container::{
-    ContainerTree, DetachedNode, Direction, InsertParentInfo, Layout, LeafLayoutInfo, TabBarInfo,
+    container_window_rules, ContainerTree, DetachedNode, Direction, InsertParentInfo, Layout,
+    LeafLayoutInfo, TabBarInfo,
 };
 use super::focus_ring::FocusRingEdges;
+use super::opening_window::OpenCloseStyle;
 use super::tile::{Tile, TileRenderElement, TileRenderSnapshot};
 use super::tiling::ColumnWidth;
 use super::workspace::{InteractiveResize, ResolvedSize};
@@ -43,6 +48,9 @@ use crate::window::ResolvedWindowRules;
 /// By how many logical pixels the directional move commands move floating windows.
 pub const DIRECTIONAL_MOVE_PX: f64 = 50.;
 
+/// By how many logical pixels cascaded floating windows are offset from each other.
+const CASCADE_STEP_PX: f64 = 32.;
+
 /// Space for floating windows.
 #[derive(Debug)]
 pub struct FloatingSpace<W: LayoutElement> {
@@ -84,6 +92,9 @@ pub struct FloatingSpace<W: LayoutElement> {
     /// Whether this workspace is active (for tab bar styling).
     is_active: bool,
 
+    /// Pointer position, if the pointer is over this space, for placing new windows.
+    pointer_pos: Option<Point<f64, Logical>>,
+
     /// Cached tab bar textures keyed by container id and path.
     tab_bar_cache: RefCell<HashMap<(u64, Vec<usize>), TabBarCacheEntry>>,
 
@@ -346,6 +357,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
             tab_bar_cache: RefCell::new(HashMap::new()),
             tab_bar_cache_alt: RefCell::new(HashMap::new()),
             is_active: false,
+            pointer_pos: None,
         }
     }
 
@@ -401,6 +413,13 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.tiles().any(Tile::are_transitions_ongoing) || !self.closing_windows.is_empty()
     }
 
+    pub fn pending_layout_deadline(&self) -> Option<Duration> {
+        self.containers
+            .iter()
+            .filter_map(|container| container.tree.pending_layout_deadline())
+            .min()
+    }
+
     pub fn update_render_elements(&mut self, is_active: bool, view_rect: Rectangle<f64, Logical>) {
         self.is_active = is_active;
         let active = self.active_window_id.clone();
@@ -584,6 +603,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
             let layout = WindowLayout {
                 tile_pos_in_workspace_view: Some(pos.into()),
+                pos_in_floating_stack: self.idx_of(tile.window().id()).map(|idx| idx + 1),
                 ..tile.ipc_layout_template()
             };
             (tile, layout)
@@ -695,7 +715,10 @@ impl<W: LayoutElement> FloatingSpace<W> {
         // unfullscreen it.
         let animate = !tile.is_scratchpad();
         {
-            let floating_size = tile.floating_window_size;
+            // Without a remembered floating size, fall back to the default floating size rule.
+            let floating_size = tile
+                .floating_window_size
+                .or_else(|| tile.window().rules().default_floating_window_size());
             let win = tile.window_mut();
             let mut size = if !win.pending_sizing_mode().is_normal() {
                 // If the window was fullscreen or maximized without a floating size, ask for (0, 0).
@@ -723,6 +746,14 @@ impl<W: LayoutElement> FloatingSpace<W> {
     fn add_tile_at(&mut self, mut idx: usize, mut tile: Tile<W>, activate: bool) {
         let win_id = self.prepare_tile_for_floating(&mut tile);
 
+        // Place the tile before activating it, so that smart placement sees the previously
+        // focused window.
+        let tile_size = tile.tile_size();
+        let pos = self
+            .stored_or_default_tile_pos(&tile)
+            .unwrap_or_else(|| self.placement_pos(tile_size));
+        let rect = Rectangle::new(pos, tile_size);
+
         if activate || self.containers.is_empty() {
             self.active_window_id = Some(win_id.clone());
         }
@@ -740,19 +771,16 @@ impl<W: LayoutElement> FloatingSpace<W> {
             }
         }
 
-        let tile_size = tile.tile_size();
-        let pos = self
-            .stored_or_default_tile_pos(&tile)
-            .unwrap_or_else(|| center_preferring_top_left_in_area(self.working_area, tile_size));
-        let rect = Rectangle::new(pos, tile_size);
-
         let mut tree = ContainerTree::new(
             rect.size,
             Rectangle::from_size(rect.size),
             self.scale,
+            self.clock.clone(),
             self.container_tree_options(&self.options),
         );
+        let (layout, tab_bar_position) = container_window_rules(tile.window().rules());
         tree.insert_leaf_at(0, tile, activate);
+        tree.apply_window_rules(&win_id, layout, tab_bar_position);
         if activate {
             tree.focus_window_by_id(&win_id);
         }
@@ -768,6 +796,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
         self.containers.insert(idx, container);
         self.bring_up_descendants_of(idx);
+        self.restack_always_on_top();
     }
 
     pub(super) fn add_tile_to_active_container(&mut self, tile: Tile<W>, activate: bool) -> bool {
@@ -877,6 +906,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
             rect.size,
             Rectangle::from_size(rect.size),
             self.scale,
+            self.clock.clone(),
             self.container_tree_options(&self.options),
         );
         tree.insert_subtree_at_root(0, subtree, activate);
@@ -903,6 +933,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
         self.containers.insert(0, container);
         self.bring_up_descendants_of(0);
+        self.restack_always_on_top();
     }
 
     fn bring_up_descendants_of(&mut self, idx: usize) {
@@ -1052,13 +1083,16 @@ impl<W: LayoutElement> FloatingSpace<W> {
             .find(|(tile, _)| tile.window().id() == id)
             .unwrap();
 
+        let style = tile.close_style();
         let Some(snapshot) = tile.take_unmap_snapshot() else {
             return;
         };
 
         let tile_size = tile.tile_size();
 
-        self.start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, blocker);
+        self.start_close_animation_for_tile(
+            renderer, snapshot, tile_size, tile_pos, blocker, style,
+        );
     }
 
     pub fn activate_window_without_raising(&mut self, id: &W::Id) -> bool {
@@ -1071,13 +1105,54 @@ impl<W: LayoutElement> FloatingSpace<W> {
     }
 
     pub fn activate_window(&mut self, id: &W::Id) -> bool {
+        if !self.raise_window(id) {
+            return false;
+        }
+
+        self.active_window_id = Some(id.clone());
+        true
+    }
+
+    /// Raises a window above the other windows without activating it.
+    ///
+    /// Returns `false` if the window isn't in this space.
+    pub fn raise_window(&mut self, id: &W::Id) -> bool {
         let Some(idx) = self.idx_of(id) else {
             return false;
         };
 
         self.raise_container(idx, 0);
-        self.active_window_id = Some(id.clone());
         self.bring_up_descendants_of(0);
+        self.restack_always_on_top();
+
+        true
+    }
+
+    /// Lowers a window below the other windows without changing the focus.
+    ///
+    /// A dialog only goes down to right above its parent. Returns `false` if the window isn't in
+    /// this space.
+    pub fn lower_window(&mut self, id: &W::Id) -> bool {
+        let Some(idx) = self.idx_of(id) else {
+            return false;
+        };
+
+        let container = self.containers.remove(idx);
+        self.containers.push(container);
+
+        let last = self.containers.len() - 1;
+        let windows = self.containers[last].tree.all_windows();
+        let parent_idx = self.containers[..last].iter().rposition(|container| {
+            let parents = container.tree.all_windows();
+            windows
+                .iter()
+                .any(|win| parents.iter().any(|parent| win.is_child_of(parent)))
+        });
+        if let Some(parent_idx) = parent_idx {
+            self.raise_container(last, parent_idx);
+        }
+
+        self.restack_always_on_top();
 
         true
     }
@@ -1089,6 +1164,45 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.containers.insert(to_idx, container);
     }
 
+    /// Moves containers with always-on-top windows above the rest, keeping the order otherwise.
+    fn restack_always_on_top(&mut self) {
+        self.containers.sort_by_cached_key(|container| {
+            !container
+                .tree
+                .all_tiles()
+                .iter()
+                .any(|tile| tile.is_always_on_top())
+        });
+    }
+
+    pub fn has_always_on_top(&self) -> bool {
+        self.tiles().any(|tile| tile.is_always_on_top())
+    }
+
+    /// Toggles whether a window stays above the other windows.
+    ///
+    /// Returns `false` if the window isn't in this space.
+    pub fn toggle_window_always_on_top(&mut self, id: &W::Id) -> bool {
+        let Some(tile) = self.tiles().find(|tile| tile.window().id() == id) else {
+            return false;
+        };
+        let always_on_top = !tile.is_always_on_top();
+        self.set_window_always_on_top(id, always_on_top)
+    }
+
+    /// Sets whether a window stays above the other windows.
+    ///
+    /// Returns `false` if the window isn't in this space.
+    pub fn set_window_always_on_top(&mut self, id: &W::Id, always_on_top: bool) -> bool {
+        let Some(tile) = self.tiles_mut().find(|tile| tile.window().id() == id) else {
+            return false;
+        };
+        tile.set_always_on_top(always_on_top);
+
+        self.restack_always_on_top();
+        true
+    }
+
     pub fn start_close_animation_for_tile(
         &mut self,
         renderer: &mut GlesRenderer,
@@ -1096,6 +1210,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
         tile_size: Size<f64, Logical>,
         tile_pos: Point<f64, Logical>,
         blocker: TransactionBlocker,
+        style: OpenCloseStyle,
     ) {
         let anim = Animation::new(
             self.clock.clone(),
@@ -1113,7 +1228,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
 
         let scale = Scale::from(self.scale);
         let res = ClosingWindow::new(
-            renderer, snapshot, scale, tile_size, tile_pos, blocker, anim,
+            renderer, snapshot, scale, tile_size, tile_pos, blocker, anim, style,
         );
         match res {
             Ok(closing) => {
@@ -1647,6 +1762,16 @@ impl<W: LayoutElement> FloatingSpace<W> {
         }
     }
 
+    pub fn set_tab_bar_position(&mut self, position: TabIndicatorPosition) {
+        let Some(idx) = self.active_container_idx() else {
+            return;
+        };
+        let tree = &mut self.containers[idx].tree;
+        if tree.set_focused_tab_bar_position(position) {
+            tree.layout();
+        }
+    }
+
     fn move_container_to(&mut self, idx: usize, new_pos: Point<f64, Logical>, animate: bool) {
         if animate {
             self.move_container_and_animate(idx, new_pos);
@@ -1733,6 +1858,88 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.move_container_to(idx, pos, animate);
     }
 
+    /// Moves one edge of a window, keeping the opposite edge in place.
+    pub fn resize_window_edge(
+        &mut self,
+        id: Option<&W::Id>,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+        grow: bool,
+    ) {
+        let horizontal = matches!(direction, ResizeDirection::Left | ResizeDirection::Right);
+        let delta = match amount {
+            ResizeAmount::Fixed(px) => px,
+            ResizeAmount::Proportion(ppt) => {
+                let available = if horizontal {
+                    self.working_area.size.w
+                } else {
+                    self.working_area.size.h
+                };
+                (available * ppt / 100.).round() as i32
+            }
+        };
+        let delta = if grow { delta } else { -delta };
+        let change = SizeChange::AdjustFixed(delta);
+        let shift = PositionChange::AdjustFixed(f64::from(-delta));
+        let none = PositionChange::AdjustFixed(0.);
+
+        match direction {
+            ResizeDirection::Left => {
+                self.set_window_width(id, change, true);
+                self.move_window(id, shift, none, true);
+            }
+            ResizeDirection::Right => self.set_window_width(id, change, true),
+            ResizeDirection::Up => {
+                self.set_window_height(id, change, true);
+                self.move_window(id, none, shift, true);
+            }
+            ResizeDirection::Down => self.set_window_height(id, change, true),
+        }
+    }
+
+    pub fn arrange(&mut self, arrangement: FloatingArrangement) {
+        let area = self.working_area;
+
+        match arrangement {
+            FloatingArrangement::Cascade => {
+                let step = Point::from((CASCADE_STEP_PX, CASCADE_STEP_PX));
+                let mut pos = area.loc;
+                // Go from the bottom, so that the topmost window ends up at the front.
+                for idx in (0..self.containers.len()).rev() {
+                    let size = self.containers[idx].data.size;
+                    if pos.x + size.w > area.loc.x + area.size.w
+                        || pos.y + size.h > area.loc.y + area.size.h
+                    {
+                        // Out of room, start a new stack from the corner.
+                        pos = area.loc;
+                    }
+                    self.move_container_and_animate(idx, pos);
+                    pos += step;
+                }
+            }
+            FloatingArrangement::Grid => {
+                // Fill the grid in the reading order of the current positions.
+                let mut order: Vec<usize> = (0..self.containers.len()).collect();
+                order.sort_by(|a, b| {
+                    let a = self.containers[*a].data.logical_pos;
+                    let b = self.containers[*b].data.logical_pos;
+                    a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+                });
+
+                let cells = grid_cells(area, order.len(), self.options.layout.gaps);
+                for (idx, cell) in order.into_iter().zip(cells) {
+                    let w = SizeChange::SetFixed(cell.size.w.round() as i32);
+                    let h = SizeChange::SetFixed(cell.size.h.round() as i32);
+                    self.resize_container_dimension(idx, w, true, true);
+                    self.resize_container_dimension(idx, h, false, true);
+                    self.move_container_and_animate(idx, cell.loc);
+                }
+            }
+        }
+
+        self.interactive_resize_end(None);
+    }
+
     pub fn center_window(&mut self, id: Option<&W::Id>) {
         let Some(id) = id.or(self.active_window_id.as_ref()).cloned() else {
             return;
@@ -1806,6 +2013,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
         view_rect: Rectangle<f64, Logical>,
         target: RenderTarget,
         focus_ring: bool,
+        only_always_on_top: bool,
     ) -> Vec<FloatingSpaceRenderElement<R>> {
         let tile_count = self.tiles().count();
         let estimated_capacity = tile_count * 4 + self.closing_windows.len() + tile_count / 2;
@@ -1815,13 +2023,19 @@ impl<W: LayoutElement> FloatingSpace<W> {
         // Draw the closing windows on top of the other windows.
         //
         // FIXME: I guess this should rather preserve the stacking order when the window is closed.
-        for closing in self.closing_windows.iter().rev() {
-            let elem = closing.render(renderer.as_gles_renderer(), view_rect, scale, target);
-            elements.push(elem.into());
+        if !only_always_on_top {
+            for closing in self.closing_windows.iter().rev() {
+                let elem = closing.render(renderer.as_gles_renderer(), view_rect, scale, target);
+                elements.push(elem.into());
+            }
         }
 
         let active = self.active_window_id.clone();
         for (tile, tile_pos) in self.tiles_with_render_positions() {
+            if only_always_on_top && !tile.is_always_on_top() {
+                continue;
+            }
+
             // Skip tiles entirely outside the viewport (culling)
             let tile_rect = Rectangle::new(tile_pos, tile.tile_size());
             if !tile_rect.overlaps(view_rect) {
@@ -1841,6 +2055,11 @@ impl<W: LayoutElement> FloatingSpace<W> {
             );
         }
 
+        // Tab bars are left out when only the always-on-top windows show.
+        if only_always_on_top {
+            return elements;
+        }
+
         if !self.options.layout.tab_bar.off {
             let mut cache = self.tab_bar_cache.borrow_mut();
             let mut next_cache = self.tab_bar_cache_alt.borrow_mut();
@@ -1854,17 +2073,52 @@ impl<W: LayoutElement> FloatingSpace<W> {
                 for info in container.tree.tab_bar_layouts() {
                     let mut info = info.clone();
                     if gap > 0.0 && info.path.is_empty() {
-                        info.rect.loc.x -= gap;
-                        info.rect.loc.y -= gap;
-                        info.rect.size.w = (info.rect.size.w + gap * 2.0).max(0.0);
+                        match info.position {
+                            TabIndicatorPosition::Top | TabIndicatorPosition::Bottom => {
+                                info.rect.loc.x -= gap;
+                                info.rect.size.w = (info.rect.size.w + gap * 2.0).max(0.0);
+                            }
+                            TabIndicatorPosition::Left | TabIndicatorPosition::Right => {
+                                info.rect.loc.y -= gap;
+                                info.rect.size.h = (info.rect.size.h + gap * 2.0).max(0.0);
+                            }
+                        }
+                        match info.position {
+                            TabIndicatorPosition::Top => info.rect.loc.y -= gap,
+                            TabIndicatorPosition::Bottom => info.rect.loc.y += gap,
+                            TabIndicatorPosition::Left => info.rect.loc.x -= gap,
+                            TabIndicatorPosition::Right => info.rect.loc.x += gap,
+                        }
                     }
+                    // Keep the tab bar within the border of the focused tile.
                     let inset = self.tab_bar_border_inset(&container.tree, &info);
                     if inset > 0.0 {
-                        let inset_x = inset.min(info.rect.size.w / 2.0);
-                        let inset_y = inset.min(info.rect.size.h);
-                        info.rect.loc.x += inset_x;
-                        info.rect.size.w = (info.rect.size.w - inset_x * 2.0).max(0.0);
-                        info.rect.loc.y += inset_y;
+                        match info.position {
+                            TabIndicatorPosition::Top | TabIndicatorPosition::Bottom => {
+                                let inset_x = inset.min(info.rect.size.w / 2.0);
+                                info.rect.loc.x += inset_x;
+                                info.rect.size.w = (info.rect.size.w - inset_x * 2.0).max(0.0);
+                            }
+                            TabIndicatorPosition::Left | TabIndicatorPosition::Right => {
+                                let inset_y = inset.min(info.rect.size.h / 2.0);
+                                info.rect.loc.y += inset_y;
+                                info.rect.size.h = (info.rect.size.h - inset_y * 2.0).max(0.0);
+                            }
+                        }
+                        match info.position {
+                            TabIndicatorPosition::Top => {
+                                info.rect.loc.y += inset.min(info.rect.size.h);
+                            }
+                            TabIndicatorPosition::Bottom => {
+                                info.rect.loc.y -= inset.min(info.rect.size.h);
+                            }
+                            TabIndicatorPosition::Left => {
+                                info.rect.loc.x += inset.min(info.rect.size.w);
+                            }
+                            TabIndicatorPosition::Right => {
+                                info.rect.loc.x -= inset.min(info.rect.size.w);
+                            }
+                        }
                     }
                     info.rect.loc += container.data.logical_pos;
                     let key = (container.id, info.path.clone());
@@ -1882,7 +2136,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
                         _ => match render_tab_bar(
                             gles,
                             &tab_bar_config,
-                            info.layout,
+                            info.is_single_row(),
                             info.rect,
                             info.row_height,
                             &info.tabs,
@@ -1942,7 +2196,21 @@ impl<W: LayoutElement> FloatingSpace<W> {
         focus_ring: bool,
         push: &mut dyn FnMut(FloatingSpaceRenderElement<R>),
     ) {
-        for elem in self.render_elements(renderer, view_rect, target, focus_ring) {
+        for elem in self.render_elements(renderer, view_rect, target, focus_ring, false) {
+            push(elem);
+        }
+    }
+
+    /// Renders only the always-on-top windows, for when the rest of the space is hidden.
+    pub fn render_always_on_top<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        view_rect: Rectangle<f64, Logical>,
+        target: RenderTarget,
+        focus_ring: bool,
+        push: &mut dyn FnMut(FloatingSpaceRenderElement<R>),
+    ) {
+        for elem in self.render_elements(renderer, view_rect, target, focus_ring, true) {
             push(elem);
         }
     }
@@ -2238,6 +2506,10 @@ impl<W: LayoutElement> FloatingSpace<W> {
         height: Option<PresetSize>,
         rules: &ResolvedWindowRules,
     ) -> Size<i32, Logical> {
+        if let Some(size) = rules.default_floating_window_size() {
+            return size;
+        }
+
         let border = self.options.layout.border.merged_with(&rules.border);
 
         let resolve = |size: Option<PresetSize>, working_area_size: f64| {
@@ -2301,6 +2573,30 @@ impl<W: LayoutElement> FloatingSpace<W> {
         })
     }
 
+    /// Position for a new tile without a stored or default position.
+    fn placement_pos(&self, size: Size<f64, Logical>) -> Point<f64, Logical> {
+        let existing: Vec<_> = self
+            .containers
+            .iter()
+            .map(|container| Rectangle::new(container.data.logical_pos, container.data.size))
+            .collect();
+        let focused = self.active_container_idx().map(|idx| existing[idx]);
+
+        placement_pos(
+            self.options.layout.floating_placement,
+            self.working_area,
+            size,
+            &existing,
+            focused,
+            self.pointer_pos,
+            self.options.layout.gaps,
+        )
+    }
+
+    pub fn set_pointer_pos(&mut self, pos: Option<Point<f64, Logical>>) {
+        self.pointer_pos = pos;
+    }
+
     #[cfg(test)]
     pub fn view_size(&self) -> Size<f64, Logical> {
         self.view_size
@@ -2315,6 +2611,14 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.scale
     }
 
+    #[cfg(test)]
+    pub fn tab_bar_layouts(&self) -> Vec<TabBarInfo> {
+        self.containers
+            .iter()
+            .flat_map(|container| container.tree.tab_bar_layouts())
+            .collect()
+    }
+
     #[cfg(test)]
     pub fn clock(&self) -> &Clock {
         &self.clock
@@ -2394,3 +2698,222 @@ fn resolve_preset_size(preset: PresetSize, view_size: f64) -> ResolvedSize {
         PresetSize::Fixed(width) => ResolvedSize::Window(f64::from(width)),
     }
 }
+
+/// Computes where a new floating window without a position hint goes.
+///
+/// `existing` holds the rectangles of the windows already in the floating space, and `focused`
+/// is the one among them that has focus.
+fn placement_pos(
+    placement: FloatingPlacement,
+    area: Rectangle<f64, Logical>,
+    size: Size<f64, Logical>,
+    existing: &[Rectangle<f64, Logical>],
+    focused: Option<Rectangle<f64, Logical>>,
+    pointer: Option<Point<f64, Logical>>,
+    gap: f64,
+) -> Point<f64, Logical> {
+    let center = center_preferring_top_left_in_area(area, size);
+    let clamp = |loc: Point<f64, Logical>| {
+        let mut rect = Rectangle::new(loc, size);
+        clamp_preferring_top_left_in_area(area, &mut rect);
+        rect.loc
+    };
+
+    match placement {
+        FloatingPlacement::Center => center,
+        FloatingPlacement::UnderCursor => match pointer {
+            Some(pointer) => clamp(pointer - size.downscale(2.).to_point()),
+            None => center,
+        },
+        FloatingPlacement::Cascade => {
+            let step = Point::from((CASCADE_STEP_PX, CASCADE_STEP_PX));
+            let mut pos = area.loc;
+            // Step past windows already sitting at the candidate position.
+            while existing.iter().any(|rect| {
+                let diff = rect.loc - pos;
+                diff.x.abs() < 1. && diff.y.abs() < 1.
+            }) {
+                pos += step;
+                if pos.x + size.w > area.loc.x + area.size.w
+                    || pos.y + size.h > area.loc.y + area.size.h
+                {
+                    // Out of room, start over from the corner.
+                    return area.loc;
+                }
+            }
+            pos
+        }
+        FloatingPlacement::Smart => {
+            let overlap = |a: Rectangle<f64, Logical>, b: Rectangle<f64, Logical>| {
+                a.intersection(b)
+                    .map_or(0., |rect| rect.size.w * rect.size.h)
+            };
+
+            // Try the center first, then the corners, then next to every existing window.
+            let mut candidates = vec![
+                center,
+                area.loc,
+                area.loc + Point::from((area.size.w - size.w, 0.)),
+                area.loc + Point::from((0., area.size.h - size.h)),
+                area.loc + area.size.to_point() - size.to_point(),
+            ];
+            for rect in existing {
+                candidates.push(rect.loc + Point::from((rect.size.w + gap, 0.)));
+                candidates.push(rect.loc + Point::from((-size.w - gap, 0.)));
+                candidates.push(rect.loc + Point::from((0., rect.size.h + gap)));
+                candidates.push(rect.loc + Point::from((0., -size.h - gap)));
+            }
+
+            // Covering the focused window or the pointer matters most, then covering any other
+            // windows. On ties, the earlier candidate wins.
+            let cost = |loc: Point<f64, Logical>| {
+                let rect = Rectangle::new(loc, size);
+                let mut covered = focused.map_or(0., |focused| overlap(rect, focused));
+                if pointer.is_some_and(|pointer| rect.contains(pointer)) {
+                    covered += size.w * size.h;
+                }
+                let others: f64 = existing.iter().map(|other| overlap(rect, *other)).sum();
+                (covered, others)
+            };
+
+            let mut best = center;
+            let mut best_cost = cost(center);
+            for loc in candidates.into_iter().map(clamp) {
+                let cost = cost(loc);
+                if cost.0 < best_cost.0 || (cost.0 == best_cost.0 && cost.1 < best_cost.1) {
+                    best = loc;
+                    best_cost = cost;
+                }
+            }
+            best
+        }
+    }
+}
+
+/// Splits the area into a grid with room for `count` windows, in reading order.
+fn grid_cells(
+    area: Rectangle<f64, Logical>,
+    count: usize,
+    gap: f64,
+) -> Vec<Rectangle<f64, Logical>> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let cols = (count as f64).sqrt().ceil() as usize;
+    let rows = count.div_ceil(cols);
+    let w = f64::max(1., (area.size.w - gap * (cols + 1) as f64) / cols as f64);
+    let h = f64::max(1., (area.size.h - gap * (rows + 1) as f64) / rows as f64);
+
+    (0..count)
+        .map(|i| {
+            let (row, col) = ((i / cols) as f64, (i % cols) as f64);
+            let loc = area.loc + Point::from((gap + (w + gap) * col, gap + (h + gap) * row));
+            Rectangle::new(loc, Size::from((w, h)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_cells_fill_area() {
+        let area = Rectangle::new(Point::from((0., 30.)), Size::from((1000., 970.)));
+        assert!(grid_cells(area, 0, 10.).is_empty());
+
+        let cells = grid_cells(area, 1, 10.);
+        let cell = Rectangle::new(Point::from((10., 40.)), Size::from((980., 950.)));
+        assert_eq!(cells, [cell]);
+
+        // Three windows go into a two by two grid, leaving the last cell empty.
+        let cells = grid_cells(area, 3, 10.);
+        let size = Size::from((485., 470.));
+        assert_eq!(
+            cells,
+            [
+                Rectangle::new(Point::from((10., 40.)), size),
+                Rectangle::new(Point::from((505., 40.)), size),
+                Rectangle::new(Point::from((10., 520.)), size),
+            ]
+        );
+    }
+
+    #[test]
+    fn placement_avoids_focused_and_pointer() {
+        let area = Rectangle::from_size(Size::from((1000., 1000.)));
+        let size = Size::from((200., 200.));
+        let focused = Rectangle::new(Point::from((350., 350.)), Size::from((300., 300.)));
+
+        // Nothing to avoid, so smart placement centers.
+        let pos = placement_pos(FloatingPlacement::Smart, area, size, &[], None, None, 16.);
+        assert_eq!(pos, Point::from((400., 400.)));
+
+        // The center is taken by the focused window, so go to the first free corner.
+        let pos = placement_pos(
+            FloatingPlacement::Smart,
+            area,
+            size,
+            &[focused],
+            Some(focused),
+            None,
+            16.,
+        );
+        assert_eq!(pos, Point::from((0., 0.)));
+
+        // The pointer is in the top-left corner, so go to the next one.
+        let pos = placement_pos(
+            FloatingPlacement::Smart,
+            area,
+            size,
+            &[focused],
+            Some(focused),
+            Some(Point::from((50., 50.))),
+            16.,
+        );
+        assert_eq!(pos, Point::from((800., 0.)));
+    }
+
+    #[test]
+    fn placement_under_cursor_and_cascade() {
+        let area = Rectangle::new(Point::from((0., 30.)), Size::from((1000., 970.)));
+        let size = Size::from((200., 100.));
+
+        let pos = placement_pos(
+            FloatingPlacement::UnderCursor,
+            area,
+            size,
+            &[],
+            None,
+            Some(Point::from((500., 500.))),
+            0.,
+        );
+        assert_eq!(pos, Point::from((400., 450.)));
+
+        // Clamped to the working area near the edge.
+        let pos = placement_pos(
+            FloatingPlacement::UnderCursor,
+            area,
+            size,
+            &[],
+            None,
+            Some(Point::from((10., 10.))),
+            0.,
+        );
+        assert_eq!(pos, Point::from((0., 30.)));
+
+        let first = Rectangle::new(Point::from((0., 30.)), size);
+        let second = Rectangle::new(Point::from((32., 62.)), size);
+        let pos = placement_pos(
+            FloatingPlacement::Cascade,
+            area,
+            size,
+            &[first, second],
+            None,
+            None,
+            0.,
+        );
+        assert_eq!(pos, Point::from((64., 94.)));
+    }
+}
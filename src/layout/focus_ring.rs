@@ -1,6 +1,7 @@
 use std::iter::zip;
+use std::time::Duration;
 
-use niri_config::{CornerRadius, Gradient, GradientRelativeTo};
+use niri_config::{CornerRadius, Gradient, GradientAnimation, GradientRelativeTo};
 use smithay::backend::renderer::element::{Element as _, Kind};
 use smithay::utils::{Logical, Point, Rectangle, Size};
 
@@ -60,6 +61,12 @@ pub struct FocusRing {
     config: niri_config::FocusRing,
     thicken_corners: bool,
     edges: FocusRingEdges,
+    /// Time that `animate="rotate"` gradients turn by.
+    gradient_time: Duration,
+    /// Angle that `animate="follow-focus"` gradients point at, in degrees.
+    focus_angle: Option<f32>,
+    /// Whether a gradient in use turns continuously.
+    is_rotating: bool,
 }
 
 niri_render_elements! {
@@ -90,6 +97,9 @@ impl FocusRing {
             config,
             thicken_corners: true,
             edges: FocusRingEdges::all(),
+            gradient_time: Duration::ZERO,
+            focus_angle: None,
+            is_rotating: false,
         }
     }
 
@@ -103,6 +113,17 @@ impl FocusRing {
         }
     }
 
+    /// Sets the state of animated gradients for the next [`Self::update_render_elements()`].
+    pub fn set_gradient_animation(&mut self, time: Duration, focus_angle: Option<f32>) {
+        self.gradient_time = time;
+        self.focus_angle = focus_angle;
+    }
+
+    /// Whether the ring has to be redrawn every frame for its gradient.
+    pub fn is_rotating(&self) -> bool {
+        self.is_rotating && !self.config.off
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn update_render_elements(
         &mut self,
@@ -174,6 +195,10 @@ impl FocusRing {
 
         self.use_border_shader =
             radius != CornerRadius::default() || gradient.is_some() || indicator_gradient.is_some();
+        self.is_rotating = [gradient, indicator_gradient]
+            .iter()
+            .flatten()
+            .any(|gradient| gradient.animate == GradientAnimation::Rotate);
 
         // Set the defaults for solid color + rounded corners.
         let base_gradient = gradient.unwrap_or_else(|| Gradient::from(color));
@@ -274,13 +299,14 @@ impl FocusRing {
                 } else {
                     (&base_gradient, base_gradient_area)
                 };
+                let angle = gradient_angle(gradient, self.gradient_time, self.focus_angle);
                 border.update(
                     size,
                     Rectangle::new(gradient_area.loc - loc, gradient_area.size),
                     gradient.in_,
                     gradient.from,
                     gradient.to,
-                    ((gradient.angle as f32) - 90.).to_radians(),
+                    angle,
                     Rectangle::new(full_rect.loc - loc, full_rect.size),
                     rounded_corner_border_width,
                     radius,
@@ -302,7 +328,7 @@ impl FocusRing {
                 base_gradient.in_,
                 base_gradient.from,
                 base_gradient.to,
-                ((base_gradient.angle as f32) - 90.).to_radians(),
+                gradient_angle(&base_gradient, self.gradient_time, self.focus_angle),
                 Rectangle::new(full_rect.loc - self.locations[0], full_rect.size),
                 rounded_corner_border_width,
                 radius,
@@ -391,3 +417,20 @@ impl FocusRing {
         &self.config
     }
 }
+
+/// Returns the angle to draw the gradient at, in radians.
+fn gradient_angle(gradient: &Gradient, time: Duration, focus_angle: Option<f32>) -> f32 {
+    let angle = f32::from(gradient.angle);
+    let angle = match gradient.animate {
+        GradientAnimation::None => angle,
+        GradientAnimation::Rotate => {
+            let period = u128::from(gradient.period_ms.max(1));
+            let progress = (time.as_millis() % period) as f32 / period as f32;
+            angle + progress * 360.
+        }
+        GradientAnimation::FollowFocus => focus_angle.unwrap_or(angle),
+    };
+
+    // Gradient angles follow CSS, where 0 degrees points up.
+    (angle - 90.).to_radians()
+}
@@ -39,9 +39,14 @@ use std::time::Duration;
 use monitor::{InsertHint, InsertPosition, InsertWorkspace, MonitorAddWindowTarget};
 use niri_config::utils::MergeWith as _;
 use niri_config::{
-    Config, CornerRadius, LayoutPart, PresetSize, Workspace as WorkspaceConfig, WorkspaceReference,
+    Config, Corner, CornerRadius, FocusFollowsMouseWindows, LayoutPart, PresetSize,
+    TabIndicatorPosition, Workspace as WorkspaceConfig, WorkspaceIndices, WorkspaceReference,
+};
+use niri_ipc::{
+    ColumnDisplay, ContainerPercents, FloatingArrangement, LayoutTree, LayoutVerification,
+    LayoutViolation, LayoutViolationKind, PositionChange, ResizeAmount, ResizeDirection,
+    SizeChange, WindowLayout,
 };
-use niri_ipc::{ColumnDisplay, LayoutTree, PositionChange, SizeChange, WindowLayout};
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::utils::RescaleRenderElement;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
@@ -53,8 +58,8 @@ use tile::{Tile, TileRenderElement};
 use tiling::{Column, ColumnWidth};
 use workspace::{WorkspaceAddWindowTarget, WorkspaceId};
 
-use self::container::InsertParentInfo;
 pub use self::container::Layout as ContainerLayout;
+use self::container::{InsertParentInfo, TreeTemplate};
 pub use self::monitor::MonitorRenderElement;
 use self::monitor::{Monitor, WorkspaceSwitch};
 use self::workspace::{OutputId, Workspace};
@@ -71,11 +76,12 @@ use crate::render_helpers::{BakedBuffer, RenderTarget};
 use crate::rubber_band::RubberBand;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
 use crate::utils::{
-    ensure_min_max_size_maybe_zero, output_matches_name, output_size,
+    ensure_min_max_size_maybe_zero, output_matches_name, output_size, round_logical_in_physical,
     round_logical_in_physical_max1, ResizeEdge,
 };
 use crate::window::ResolvedWindowRules;
 
+pub mod background;
 pub mod closing_window;
 pub mod container;
 pub mod floating;
@@ -108,6 +114,9 @@ const INTERACTIVE_MOVE_ALPHA: f64 = 0.75;
 /// Amount of touchpad movement to toggle the overview.
 const OVERVIEW_GESTURE_MOVEMENT: f64 = 300.;
 
+/// Number of layout changes that can be undone.
+const UNDO_HISTORY_LEN: usize = 32;
+
 const OVERVIEW_GESTURE_RUBBER_BAND: RubberBand = RubberBand {
     stiffness: 0.5,
     limit: 0.05,
@@ -169,6 +178,15 @@ pub trait LayoutElement {
         None
     }
 
+    /// Updates the title if it depends on where the element is, or on its marks.
+    fn refresh_title(
+        &mut self,
+        _workspace_name: Option<&str>,
+        _workspace_idx: usize,
+        _marks: &[String],
+    ) {
+    }
+
     /// Visual size of the element.
     ///
     /// This is what the user would consider the size, i.e. excluding CSD shadows and whatnot.
@@ -260,6 +278,15 @@ pub trait LayoutElement {
     fn is_ignoring_opacity_window_rule(&self) -> bool;
 
     fn is_urgent(&self) -> bool;
+    fn has_activity(&self) -> bool;
+
+    /// Renders the prompt shown over the window while its client is unresponsive, if any.
+    fn render_unresponsive_prompt(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: f64,
+        font: Option<&str>,
+    ) -> Option<TextureBuffer<GlesTexture>>;
 
     fn configure_intent(&self) -> ConfigureIntent;
     fn send_pending_configure(&mut self);
@@ -362,6 +389,18 @@ pub struct Layout<W: LayoutElement> {
     overview_progress: Option<OverviewProgress>,
     /// Hidden scratchpad windows (round-robin queue).
     scratchpad: VecDeque<Tile<W>>,
+    /// Minimized windows, with where to put them back.
+    minimized: Vec<MinimizedTile<W>>,
+    /// Tiling trees from before recent structural changes, oldest first.
+    undo_history: VecDeque<(WorkspaceId, TreeTemplate<W::Id>)>,
+    /// Window that follows the focus across outputs as a small always-on-top tile.
+    follow_focus: Option<FollowFocus<W::Id>>,
+    /// Picture-in-picture windows.
+    picture_in_picture: Vec<W::Id>,
+    /// Whether the tablet mode layout overrides are in effect.
+    tablet_mode: bool,
+    /// Whether power saving is on.
+    power_saving: bool,
     /// Configurable properties of the layout.
     options: Rc<Options>,
 }
@@ -390,6 +429,9 @@ pub struct Options {
     pub animations: niri_config::Animations,
     pub gestures: niri_config::Gestures,
     pub overview: niri_config::Overview,
+    pub ui_font: Option<String>,
+    /// Whether to skip window shadows to save power.
+    pub disable_shadows: bool,
     // Debug flags.
     pub disable_resize_throttling: bool,
     pub disable_transactions: bool,
@@ -474,6 +516,16 @@ pub struct DndData<W: LayoutElement> {
     pointer_pos_within_output: Point<f64, Logical>,
     /// Ongoing DnD hold to activate something.
     hold: Option<DndHold<W>>,
+    /// Ongoing DnD hover at a monitor edge to switch workspaces.
+    edge_hover: Option<DndEdgeHover>,
+}
+
+#[derive(Debug)]
+struct DndEdgeHover {
+    /// Whether the hover is at the top edge, rather than the bottom edge.
+    is_top: bool,
+    /// Time when we started hovering, or when the last switch happened.
+    start_time: Duration,
 }
 
 #[derive(Debug)]
@@ -533,6 +585,27 @@ pub struct RemovedTile<W: LayoutElement> {
     is_floating: bool,
 }
 
+/// Window hidden by a minimize request.
+#[derive(Debug)]
+struct MinimizedTile<W: LayoutElement> {
+    tile: Tile<W>,
+    /// Workspace the window was on.
+    workspace_id: WorkspaceId,
+    /// Spot in the tiling tree the window was taken out of, if it was tiled.
+    origin: Option<InsertParentInfo>,
+    width: ColumnWidth,
+    is_full_width: bool,
+    is_floating: bool,
+}
+
+/// Window that follows the focus across outputs.
+#[derive(Debug)]
+struct FollowFocus<Id> {
+    window: Id,
+    /// Whether the window was always on top before it started following, to restore afterwards.
+    was_always_on_top: bool,
+}
+
 /// Whether to activate a newly added window.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum ActivateWindow {
@@ -559,6 +632,15 @@ pub enum AddWindowTarget<'a, W: LayoutElement> {
     NextTo(&'a W::Id),
 }
 
+/// Tiling container to look up with `container_percents()`.
+#[derive(Debug)]
+pub enum ContainerTarget<'a, W: LayoutElement> {
+    /// Container of this window, or of the focused node.
+    Window(Option<&'a W::Id>),
+    /// Container at this path on the active workspace.
+    Path(&'a [usize]),
+}
+
 /// Type of the window hit from `window_under()`.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HitType {
@@ -704,11 +786,18 @@ impl HitType {
 
 impl Options {
     fn from_config(config: &Config) -> Self {
+        let mut layout = config.layout.clone();
+        if let Some(accent) = config.accent_color {
+            layout.apply_accent_color(accent);
+        }
+
         Self {
-            layout: config.layout.clone(),
+            layout,
             animations: config.animations.clone(),
             gestures: config.gestures,
-            overview: config.overview,
+            overview: config.overview.clone(),
+            ui_font: config.ui_font.clone(),
+            disable_shadows: false,
             disable_resize_throttling: config.debug.disable_resize_throttling,
             disable_transactions: config.debug.disable_transactions,
             deactivate_unfocused_windows: config.debug.deactivate_unfocused_windows,
@@ -724,6 +813,17 @@ impl Options {
 
     fn adjusted_for_scale(mut self, scale: f64) -> Self {
         self.layout.gaps = round_logical_in_physical_max1(scale, self.layout.gaps);
+
+        let struts = &mut self.layout.struts;
+        for strut in [
+            &mut struts.left,
+            &mut struts.right,
+            &mut struts.top,
+            &mut struts.bottom,
+        ] {
+            strut.0 = round_logical_in_physical(scale, strut.0);
+        }
+
         self
     }
 }
@@ -759,6 +859,12 @@ impl<W: LayoutElement> Layout<W> {
             overview_open: false,
             overview_progress: None,
             scratchpad: VecDeque::new(),
+            minimized: Vec::new(),
+            undo_history: VecDeque::new(),
+            follow_focus: None,
+            picture_in_picture: Vec::new(),
+            tablet_mode: false,
+            power_saving: false,
             options: Rc::new(options),
         }
     }
@@ -785,6 +891,12 @@ impl<W: LayoutElement> Layout<W> {
             overview_open: false,
             overview_progress: None,
             scratchpad: VecDeque::new(),
+            minimized: Vec::new(),
+            undo_history: VecDeque::new(),
+            follow_focus: None,
+            picture_in_picture: Vec::new(),
+            tablet_mode: false,
+            power_saving: false,
             options: opts,
         }
     }
@@ -842,12 +954,15 @@ impl<W: LayoutElement> Layout<W> {
 
                 // If we stopped a workspace switch, then we might need to clean up workspaces.
                 // Also if empty_workspace_above_first is set and there are only 2 workspaces left,
-                // both will be empty and one of them needs to be removed. clean_up_workspaces
-                // takes care of this.
+                // both will be empty and one of them needs to be removed. With keep-gaps, the
+                // removed workspaces may have been the only thing keeping the gaps above them.
+                // clean_up_workspaces takes care of this.
 
                 if stopped_primary_ws_switch
                     || (primary.options.layout.empty_workspace_above_first
                         && primary.workspaces.len() == 2)
+                    || (primary.options.layout.workspace_indices == WorkspaceIndices::KeepGaps
+                        && primary.workspace_switch.is_none())
                 {
                     primary.clean_up_workspaces();
                 }
@@ -978,6 +1093,21 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Sets the pointer position used for placing new floating windows.
+    ///
+    /// The position is within the output, and only applies to the workspaces on that output.
+    pub fn set_pointer_pos_for_placement(
+        &mut self,
+        pointer: Option<(&Output, Point<f64, Logical>)>,
+    ) {
+        for ws in self.workspaces_mut() {
+            let pos = pointer
+                .filter(|(output, _)| ws.current_output() == Some(*output))
+                .map(|(_, pos)| pos);
+            ws.set_pointer_pos_for_placement(pos);
+        }
+    }
+
     /// Adds a new window to the layout.
     ///
     /// Returns an output that the window was added to, if there were any outputs.
@@ -1223,12 +1353,27 @@ impl<W: LayoutElement> Layout<W> {
             });
         }
 
+        if let Some(idx) = self
+            .minimized
+            .iter()
+            .position(|minimized| minimized.tile.window().id() == window)
+        {
+            let minimized = self.minimized.remove(idx);
+            return Some(RemovedTile {
+                tile: minimized.tile,
+                width: minimized.width,
+                is_full_width: minimized.is_full_width,
+                is_floating: minimized.is_floating,
+            });
+        }
+
         if let Some(mon) = self
             .monitors_mut()
             .find(|mon| mon.has_sticky_window(window))
         {
             let mut removed = mon.take_sticky_window(window)?;
             removed.tile.set_sticky(false);
+            removed.tile.set_follows_focus(false);
 
             if mon.sticky_is_active()
                 && mon.sticky_active_window_id().is_some_and(|id| id == window)
@@ -1242,34 +1387,16 @@ impl<W: LayoutElement> Layout<W> {
         match &mut self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
-                    for (idx, ws) in mon.workspaces.iter_mut().enumerate() {
+                    for ws in &mut mon.workspaces {
                         if ws.has_window(window) {
                             let removed = ws.remove_tile(window, transaction);
 
-                            // Clean up empty workspaces that are not active and not last.
-                            if !ws.has_windows_or_name()
-                                && idx != mon.active_workspace_idx
-                                && idx != mon.workspaces.len() - 1
-                                && mon.workspace_switch.is_none()
-                            {
-                                mon.workspaces.remove(idx);
-
-                                if idx < mon.active_workspace_idx {
-                                    mon.active_workspace_idx -= 1;
-                                }
+                            // Clean up empty workspaces that are not active and not last,
+                            // following the workspace-indices policy.
+                            if !ws.has_windows_or_name() && mon.workspace_switch.is_none() {
+                                mon.clean_up_workspaces();
                             }
 
-                            // Special case handling when empty_workspace_above_first is set and all
-                            // workspaces are empty.
-                            if mon.options.layout.empty_workspace_above_first
-                                && mon.workspaces.len() == 2
-                                && mon.workspace_switch.is_none()
-                            {
-                                assert!(!mon.workspaces[0].has_windows_or_name());
-                                assert!(!mon.workspaces[1].has_windows_or_name());
-                                mon.workspaces.remove(1);
-                                mon.active_workspace_idx = 0;
-                            }
                             return Some(removed);
                         }
                     }
@@ -1318,8 +1445,7 @@ impl<W: LayoutElement> Layout<W> {
         }
 
         if let Some(tile) = self
-            .scratchpad
-            .iter_mut()
+            .hidden_tiles_mut()
             .find(|tile| tile.window().id() == window)
         {
             if let Some(serial) = serial {
@@ -1389,6 +1515,55 @@ impl<W: LayoutElement> Layout<W> {
         None
     }
 
+    /// Saves the tiling tree of the active workspace so that the next change can be undone.
+    pub fn record_layout_change(&mut self) {
+        let Some(ws) = self.active_workspace() else {
+            return;
+        };
+        let Some(template) = ws.tree_template() else {
+            return;
+        };
+
+        let entry = (ws.id(), template);
+        if self.undo_history.back() == Some(&entry) {
+            return;
+        }
+
+        if self.undo_history.len() == UNDO_HISTORY_LEN {
+            self.undo_history.pop_front();
+        }
+        self.undo_history.push_back(entry);
+    }
+
+    /// Restores the last recorded tiling tree of the active workspace.
+    ///
+    /// Windows closed since then are left out, and windows opened since then stay where they are.
+    pub fn undo_layout_change(&mut self) {
+        let Some(ws) = self.active_workspace() else {
+            return;
+        };
+        let ws_id = ws.id();
+        let current = ws.tree_template();
+
+        // Skip over records matching the current tree, e.g. from actions that did nothing.
+        while let Some(idx) = self.undo_history.iter().rposition(|(id, _)| *id == ws_id) {
+            let (_, template) = self.undo_history.remove(idx).unwrap();
+            if current.as_ref() != Some(&template) {
+                if let Some(ws) = self.active_workspace_mut() {
+                    ws.restore_tree(template);
+                }
+                return;
+            }
+        }
+    }
+
+    /// Rebuilds a saved tiling tree on a workspace from windows on it.
+    pub fn rebuild_workspace_tree(&mut self, ws_id: WorkspaceId, template: TreeTemplate<W::Id>) {
+        if let Some(ws) = self.workspaces_mut().find(|ws| ws.id() == ws_id) {
+            ws.rebuild_tree(template);
+        }
+    }
+
     pub fn find_workspace_by_name(&self, workspace_name: &str) -> Option<(usize, &Workspace<W>)> {
         match &self.monitor_set {
             MonitorSet::Normal { ref monitors, .. } => {
@@ -1422,20 +1597,49 @@ impl<W: LayoutElement> Layout<W> {
         &mut self,
         reference: WorkspaceReference,
     ) -> Option<&mut Workspace<W>> {
-        if let WorkspaceReference::Index(index) = reference {
-            self.active_monitor().and_then(|m| {
+        match reference {
+            WorkspaceReference::Index(index) => self.active_monitor().and_then(|m| {
                 let index = index.saturating_sub(1) as usize;
                 m.workspaces.get_mut(index)
-            })
-        } else {
-            self.workspaces_mut().find(|ws| match &reference {
-                WorkspaceReference::Name(ref_name) => ws
-                    .name
+            }),
+            WorkspaceReference::Name(ref_name) => self.workspaces_mut().find(|ws| {
+                ws.name
                     .as_ref()
-                    .is_some_and(|name| name.eq_ignore_ascii_case(ref_name)),
-                WorkspaceReference::Id(id) => ws.id().get() == *id,
-                WorkspaceReference::Index(_) => unreachable!(),
-            })
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&ref_name))
+            }),
+            WorkspaceReference::Id(id) => self.workspaces_mut().find(|ws| ws.id().get() == id),
+            WorkspaceReference::Relative(_)
+            | WorkspaceReference::NextNonEmpty
+            | WorkspaceReference::PrevNonEmpty => {
+                let index = self.resolve_relative_workspace_ref(&reference)?;
+                self.active_monitor()?.workspaces.get_mut(index)
+            }
+        }
+    }
+
+    /// Resolves a relative workspace reference to a workspace index on the active monitor.
+    ///
+    /// Offsets wrap around the workspace list. Returns `None` for absolute references, and for
+    /// `NextNonEmpty`/`PrevNonEmpty` when no other workspace on the monitor has windows.
+    pub fn resolve_relative_workspace_ref(&self, reference: &WorkspaceReference) -> Option<usize> {
+        let mon = self.active_monitor_ref()?;
+        let len = mon.workspaces.len();
+        let active = mon.active_workspace_idx();
+
+        match reference {
+            WorkspaceReference::Relative(offset) => {
+                let index = (active as i64 + i64::from(*offset)).rem_euclid(len as i64);
+                Some(index as usize)
+            }
+            WorkspaceReference::NextNonEmpty => (1..len)
+                .map(|i| (active + i) % len)
+                .find(|&idx| mon.workspaces[idx].has_windows()),
+            WorkspaceReference::PrevNonEmpty => (1..len)
+                .map(|i| (active + len - i) % len)
+                .find(|&idx| mon.workspaces[idx].has_windows()),
+            WorkspaceReference::Id(_)
+            | WorkspaceReference::Index(_)
+            | WorkspaceReference::Name(_) => None,
         }
     }
 
@@ -1508,8 +1712,7 @@ impl<W: LayoutElement> Layout<W> {
         }
 
         if let Some(window) = self
-            .scratchpad
-            .iter()
+            .hidden_tiles()
             .find(|tile| tile.window().is_wl_surface(wl_surface))
             .map(|tile| tile.window())
         {
@@ -1564,7 +1767,10 @@ impl<W: LayoutElement> Layout<W> {
 
         // Check scratchpad with immutable borrow
         let location = if matches!(location, Location::NotFound) {
-            if self.scratchpad.iter().any(|tile| tile.window().is_wl_surface(wl_surface)) {
+            if self
+                .hidden_tiles()
+                .any(|tile| tile.window().is_wl_surface(wl_surface))
+            {
                 Location::Scratchpad
             } else {
                 Location::NotFound
@@ -1610,8 +1816,7 @@ impl<W: LayoutElement> Layout<W> {
             }
             Location::Scratchpad => {
                 if let Some(window) = self
-                    .scratchpad
-                    .iter_mut()
+                    .hidden_tiles_mut()
                     .find(|tile| tile.window().is_wl_surface(wl_surface))
                     .map(|tile| tile.window_mut())
                 {
@@ -1680,13 +1885,41 @@ impl<W: LayoutElement> Layout<W> {
         0.
     }
 
+    /// Returns whether focus-follows-mouse focuses the window, or it needs a click.
+    ///
+    /// The window rule takes precedence over limiting focus-follows-mouse to tiled or floating
+    /// windows.
+    pub fn focus_follows_mouse_applies_to(
+        &self,
+        window: &W::Id,
+        windows: Option<FocusFollowsMouseWindows>,
+    ) -> bool {
+        let Some((ws, mapped)) = self.workspaces().find_map(|(_, _, ws)| {
+            let mapped = ws.windows().find(|win| win.id() == window)?;
+            Some((ws, mapped))
+        }) else {
+            // The interactively moved window is not on any workspace.
+            return true;
+        };
+
+        if let Some(ffm) = mapped.rules().focus_follows_mouse {
+            return ffm;
+        }
+
+        match windows {
+            None => true,
+            Some(FocusFollowsMouseWindows::Tiled) => !ws.is_floating(window),
+            Some(FocusFollowsMouseWindows::Floating) => ws.is_floating(window),
+        }
+    }
+
     pub fn should_trigger_focus_follows_mouse_on(&self, window: &W::Id) -> bool {
         // During an animation, it's easy to trigger focus-follows-mouse on the previous workspace,
         // especially when clicking to switch workspace on a bar of some kind. This cancels the
         // workspace switch, which is annoying and not intended.
         //
-        // This function allows focus-follows-mouse to trigger only on the animation target
-        // workspace.
+        // This function keeps focus-follows-mouse from triggering until the animation is over,
+        // since the windows are still moving under the pointer.
         if self
             .interactive_move
             .as_ref()
@@ -1714,12 +1947,36 @@ impl<W: LayoutElement> Layout<W> {
             return true;
         };
 
-        // During a gesture, focus-follows-mouse does not cause any unintended workspace switches.
-        if let Some(WorkspaceSwitch::Gesture(_)) = mon.workspace_switch {
-            return true;
+        match mon.workspace_switch {
+            // During a gesture, focus-follows-mouse does not cause any unintended workspace
+            // switches.
+            Some(WorkspaceSwitch::Gesture(_)) => true,
+            Some(WorkspaceSwitch::Animation(_)) => false,
+            None => ws_idx == mon.active_workspace_idx,
+        }
+    }
+
+    /// Returns whether the window is another tab of the tabbed or stacked container that the
+    /// focused window is in.
+    pub fn is_in_focused_tabs(&self, window: &W::Id) -> bool {
+        let Some(ws) = self.active_workspace() else {
+            return false;
+        };
+        if !ws.has_window(window) {
+            return false;
         }
 
-        ws_idx == mon.active_workspace_idx
+        let Some(focused) = ws.container_path(None) else {
+            return false;
+        };
+        if ws.container_path(Some(window)).as_ref() != Some(&focused) {
+            return false;
+        }
+
+        matches!(
+            ws.container_percents(&focused),
+            Some((ContainerLayout::Tabbed | ContainerLayout::Stacked, _))
+        )
     }
 
     pub fn activate_window(&mut self, window: &W::Id) {
@@ -1732,6 +1989,12 @@ impl<W: LayoutElement> Layout<W> {
             return;
         }
 
+        // Activating a minimized window brings it back. This calls back into activate_window().
+        if self.is_minimized(window) {
+            self.unminimize_window(window);
+            return;
+        }
+
         let MonitorSet::Normal {
             monitors,
             active_monitor_idx,
@@ -1899,6 +2162,25 @@ impl<W: LayoutElement> Layout<W> {
         moving_window.chain(mon_windows)
     }
 
+    /// Returns windows that can't be seen.
+    ///
+    /// These are windows on workspaces out of view, background tabs, and windows behind a
+    /// fullscreen window.
+    pub fn occluded_windows(&self) -> Vec<W::Id> {
+        match &self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors
+                .iter()
+                .flat_map(|mon| mon.occluded_windows())
+                .map(|win| win.id().clone())
+                .collect(),
+            MonitorSet::NoOutputs { workspaces } => workspaces
+                .iter()
+                .flat_map(|ws| ws.windows())
+                .map(|win| win.id().clone())
+                .collect(),
+        }
+    }
+
     pub fn with_windows(
         &self,
         mut f: impl FnMut(&W, Option<&Output>, Option<WorkspaceId>, WindowLayout),
@@ -1909,7 +2191,7 @@ impl<W: LayoutElement> Layout<W> {
             f(move_.tile.window(), Some(&move_.output), None, layout);
         }
 
-        for tile in &self.scratchpad {
+        for tile in self.hidden_tiles() {
             let layout = tile.ipc_layout_template();
             f(tile.window(), None, None, layout);
         }
@@ -1944,7 +2226,7 @@ impl<W: LayoutElement> Layout<W> {
             f(move_.tile.window_mut(), Some(&move_.output));
         }
 
-        for tile in &mut self.scratchpad {
+        for tile in self.hidden_tiles_mut() {
             f(tile.window_mut(), None);
         }
 
@@ -2725,6 +3007,41 @@ impl<W: LayoutElement> Layout<W> {
         compute_overview_zoom(&self.options, progress)
     }
 
+    /// Checks the layout of every workspace for inconsistencies.
+    ///
+    /// Unlike `verify_invariants()` in tests, this doesn't panic, so it can run on a live
+    /// compositor.
+    pub fn check_invariants(&self) -> LayoutVerification {
+        let mut workspaces = 0;
+        let mut violations = Vec::new();
+        let mut seen: Vec<&W::Id> = Vec::new();
+
+        for (_, _, ws) in self.workspaces() {
+            workspaces += 1;
+            violations.extend(ws.check_invariants());
+
+            for tile in ws.tiles() {
+                let id = tile.window().id();
+                if seen.contains(&id) {
+                    violations.push(LayoutViolation {
+                        kind: LayoutViolationKind::DuplicateWindow,
+                        workspace_id: Some(ws.id().get()),
+                        path: Vec::new(),
+                        message: format!("window {id:?} appears more than once"),
+                    });
+                } else {
+                    seen.push(id);
+                }
+            }
+        }
+
+        LayoutVerification {
+            workspaces,
+            windows: seen.len(),
+            violations,
+        }
+    }
+
     #[cfg(test)]
     fn verify_invariants(&self) {
         use std::collections::HashSet;
@@ -3048,6 +3365,8 @@ impl<W: LayoutElement> Layout<W> {
             }
         }
 
+        self.dnd_edge_hover();
+
         if let Some(OverviewProgress::Animation(anim)) = &mut self.overview_progress {
             if anim.is_done() {
                 if self.overview_open {
@@ -3073,6 +3392,15 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Returns the earliest time when a pending container layout applies even without acks.
+    ///
+    /// Nothing may refresh the layout by then, so the caller needs to arm a timer for it.
+    pub fn pending_layout_deadline(&self) -> Option<Duration> {
+        self.workspaces()
+            .filter_map(|(_, _, ws)| ws.pending_layout_deadline())
+            .min()
+    }
+
     pub fn are_animations_ongoing(&self, output: Option<&Output>) -> bool {
         // Keep advancing animations if we might need to scroll the view.
         if let Some(dnd) = &self.dnd {
@@ -3216,7 +3544,7 @@ impl<W: LayoutElement> Layout<W> {
                     let position = if move_.is_floating {
                         InsertPosition::Floating
                     } else {
-                        ws.scrolling_insert_position(pos_within_workspace)
+                        ws.scrolling_insert_position(pos_within_workspace, zoom)
                     };
 
                     let rules = move_.tile.window().rules();
@@ -3301,7 +3629,32 @@ impl<W: LayoutElement> Layout<W> {
             }
         }
 
-        self.update_options(Options::from_config(config));
+        let mut options = Options::from_config(config);
+        if self.tablet_mode {
+            options = options.with_merged_layout(config.switch_events.tablet_mode_layout.as_ref());
+        }
+        options.disable_shadows = self.power_saving && !config.power_saving.keep_shadows;
+        self.update_options(options);
+    }
+
+    /// Turns the tablet mode layout overrides on or off.
+    pub fn set_tablet_mode(&mut self, config: &Config, tablet_mode: bool) {
+        if self.tablet_mode == tablet_mode {
+            return;
+        }
+
+        self.tablet_mode = tablet_mode;
+        self.update_config(config);
+    }
+
+    /// Turns the power saving adjustments on or off.
+    pub fn set_power_saving(&mut self, config: &Config, power_saving: bool) {
+        if self.power_saving == power_saving {
+            return;
+        }
+
+        self.power_saving = power_saving;
+        self.update_config(config);
     }
 
     fn update_options(&mut self, options: Options) {
@@ -3423,6 +3776,12 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    pub fn set_tab_bar_position(&mut self, position: TabIndicatorPosition) {
+        if let Some(workspace) = self.active_workspace_mut() {
+            workspace.set_tab_bar_position(position);
+        }
+    }
+
     pub fn set_column_width(&mut self, change: SizeChange) {
         let Some(workspace) = self.active_workspace_mut() else {
             return;
@@ -3468,6 +3827,96 @@ impl<W: LayoutElement> Layout<W> {
         workspace.set_window_height(window, change);
     }
 
+    /// Returns the shares of the children of a tiling container.
+    pub fn container_percents(
+        &self,
+        target: ContainerTarget<W>,
+    ) -> Result<ContainerPercents, String> {
+        let (ws, path) = self.find_container(target)?;
+        let Some((layout, percents)) = ws.container_percents(&path) else {
+            return Err(String::from("no container at this path"));
+        };
+
+        Ok(ContainerPercents {
+            workspace_id: ws.id().get(),
+            path,
+            layout: container::layout_to_ipc(layout),
+            percents,
+        })
+    }
+
+    /// Sets the shares of the children of a tiling container.
+    pub fn set_container_percents(
+        &mut self,
+        target: ContainerTarget<W>,
+        percents: &[f64],
+    ) -> Result<(), String> {
+        let (ws, path) = self.find_container(target)?;
+        let ws_id = ws.id();
+
+        let ws = self.workspaces_mut().find(|ws| ws.id() == ws_id).unwrap();
+        ws.set_container_percents(&path, percents)
+    }
+
+    fn find_container(
+        &self,
+        target: ContainerTarget<W>,
+    ) -> Result<(&Workspace<W>, Vec<usize>), String> {
+        let active_workspace = || {
+            self.active_workspace()
+                .ok_or_else(|| String::from("no focused workspace"))
+        };
+
+        match target {
+            ContainerTarget::Window(window) => {
+                let ws = match window {
+                    Some(id) => self
+                        .workspaces()
+                        .map(|(_, _, ws)| ws)
+                        .find(|ws| ws.has_window(id))
+                        .ok_or_else(|| String::from("window not found"))?,
+                    None => active_workspace()?,
+                };
+                let path = ws
+                    .container_path(window)
+                    .ok_or_else(|| String::from("the window isn't in a tiling container"))?;
+                Ok((ws, path))
+            }
+            ContainerTarget::Path(path) => Ok((active_workspace()?, path.to_vec())),
+        }
+    }
+
+    /// Returns the focused window's share of its parent splits, horizontally and vertically.
+    pub fn focused_window_shares(&self) -> (Option<f64>, Option<f64>) {
+        self.active_workspace()
+            .map_or((None, None), |ws| ws.window_shares(None))
+    }
+
+    pub fn resize_window_edge(
+        &mut self,
+        window: Option<&W::Id>,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+        grow: bool,
+    ) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
+            if window.is_none() || window == Some(move_.tile.window().id()) {
+                return;
+            }
+        }
+
+        let workspace = if let Some(window) = window {
+            self.workspaces_mut().find(|ws| ws.has_window(window))
+        } else {
+            self.active_workspace_mut()
+        };
+
+        let Some(workspace) = workspace else {
+            return;
+        };
+        workspace.resize_window_edge(window, direction, amount, grow);
+    }
+
     pub fn reset_window_height(&mut self, window: Option<&W::Id>) {
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
             if window.is_none() || window == Some(move_.tile.window().id()) {
@@ -3567,6 +4016,18 @@ impl<W: LayoutElement> Layout<W> {
             .focus()
             .is_some_and(|win| win.id() == &target);
 
+        // A window following the focus stays where it is, as a regular sticky window.
+        if let Some(follow) = self.follow_focus.take_if(|follow| follow.window == target) {
+            if let Some(mon) = self
+                .monitors_mut()
+                .find(|mon| mon.has_sticky_window(&target))
+            {
+                mon.set_sticky_window_always_on_top(&target, follow.was_always_on_top);
+                mon.set_sticky_window_follows_focus(&target, false);
+            }
+            return;
+        }
+
         if let Some(mon) = self
             .monitors_mut()
             .find(|mon| mon.has_sticky_window(&target))
@@ -3584,6 +4045,298 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Toggles whether a floating window stays above the other windows on its output.
+    ///
+    /// Tiled windows are left alone.
+    pub fn toggle_window_always_on_top(&mut self, window: Option<&W::Id>) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if window.is_none() || window == Some(move_.tile.window().id()) {
+                return;
+            }
+        }
+
+        let target = match window {
+            Some(id) => id.clone(),
+            None => match self.focus() {
+                Some(win) => win.id().clone(),
+                None => return,
+            },
+        };
+
+        for mon in self.monitors_mut() {
+            if mon.toggle_sticky_window_always_on_top(&target) {
+                return;
+            }
+        }
+
+        for ws in self.workspaces_mut() {
+            if ws.has_window(&target) {
+                ws.toggle_window_always_on_top(&target);
+                return;
+            }
+        }
+    }
+
+    /// Raises a floating window above the other floating windows without focusing it.
+    ///
+    /// Tiled windows are left alone.
+    pub fn raise_window(&mut self, window: Option<&W::Id>) {
+        let Some(target) = self.floating_stack_target(window) else {
+            return;
+        };
+
+        for mon in self.monitors_mut() {
+            if mon.raise_sticky_window(&target) {
+                return;
+            }
+        }
+
+        for ws in self.workspaces_mut() {
+            if ws.has_window(&target) {
+                ws.raise_window(&target);
+                return;
+            }
+        }
+    }
+
+    /// Lowers a floating window below the other floating windows without changing the focus.
+    ///
+    /// Tiled windows are left alone.
+    pub fn lower_window(&mut self, window: Option<&W::Id>) {
+        let Some(target) = self.floating_stack_target(window) else {
+            return;
+        };
+
+        for mon in self.monitors_mut() {
+            if mon.lower_sticky_window(&target) {
+                return;
+            }
+        }
+
+        for ws in self.workspaces_mut() {
+            if ws.has_window(&target) {
+                ws.lower_window(&target);
+                return;
+            }
+        }
+    }
+
+    fn floating_stack_target(&self, window: Option<&W::Id>) -> Option<W::Id> {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if window.is_none() || window == Some(move_.tile.window().id()) {
+                return None;
+            }
+        }
+
+        match window {
+            Some(id) => Some(id.clone()),
+            None => self.focus().map(|win| win.id().clone()),
+        }
+    }
+
+    /// Toggles whether a window follows the focus.
+    ///
+    /// A following window becomes a small always-on-top floating tile in the corner of the focused
+    /// output, shown above every workspace, and moves over whenever another output gets focused.
+    /// Unlike a sticky window, it isn't tied to one output. Only one window follows the focus at a
+    /// time.
+    pub fn toggle_window_follow_focus(&mut self, window: Option<&W::Id>) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if window.is_none() || window == Some(move_.tile.window().id()) {
+                return;
+            }
+        }
+
+        let target = match window {
+            Some(id) => id.clone(),
+            None => match self.focus() {
+                Some(win) => win.id().clone(),
+                None => return,
+            },
+        };
+
+        let target_is_active = self.focus().is_some_and(|win| win.id() == &target);
+
+        // Stop following with the previous window, or with the target itself.
+        if let Some(follow) = self.follow_focus.take() {
+            let id = follow.window;
+            let is_active = self.focus().is_some_and(|win| win.id() == &id);
+            if let Some(mon) = self.monitors_mut().find(|mon| mon.has_sticky_window(&id)) {
+                mon.set_sticky_window_always_on_top(&id, follow.was_always_on_top);
+                mon.remove_sticky_window(&id, is_active);
+            }
+
+            if id == target {
+                return;
+            }
+        }
+
+        if !self.monitors().any(|mon| mon.has_sticky_window(&target)) {
+            let options = self.options.clone();
+            let Some(ws) = self.workspaces_mut().find(|ws| ws.has_window(&target)) else {
+                return;
+            };
+            if !ws.is_floating(&target) {
+                ws.set_window_floating(Some(&target), true);
+            }
+
+            if let Some(width) = options.layout.preset_column_widths.first() {
+                ws.set_window_width(Some(&target), SizeChange::from(*width));
+            }
+            if let Some(height) = options.layout.preset_window_heights.first() {
+                ws.set_window_height(Some(&target), SizeChange::from(*height));
+            }
+
+            let Some(mon) = self.monitors_mut().find(|mon| mon.has_window(&target)) else {
+                return;
+            };
+            if !mon.add_sticky_window(&target, target_is_active) {
+                return;
+            }
+        }
+
+        let Some(mon) = self
+            .monitors_mut()
+            .find(|mon| mon.has_sticky_window(&target))
+        else {
+            return;
+        };
+        let was_always_on_top = mon
+            .sticky_floating
+            .tiles()
+            .find(|tile| tile.window().id() == &target)
+            .is_some_and(|tile| tile.is_always_on_top());
+        mon.set_sticky_window_follows_focus(&target, true);
+        mon.set_sticky_window_always_on_top(&target, true);
+        mon.move_sticky_window_to_corner(&target, Corner::BottomRight, true);
+        self.follow_focus = Some(FollowFocus {
+            window: target,
+            was_always_on_top,
+        });
+    }
+
+    pub fn follow_focus_window(&self) -> Option<&W::Id> {
+        self.follow_focus.as_ref().map(|follow| &follow.window)
+    }
+
+    /// Moves the window following the focus over to the active output.
+    fn refresh_follow_focus(&mut self) {
+        let Some(id) = self
+            .follow_focus
+            .as_ref()
+            .map(|follow| follow.window.clone())
+        else {
+            return;
+        };
+
+        let MonitorSet::Normal {
+            monitors,
+            active_monitor_idx,
+            ..
+        } = &mut self.monitor_set
+        else {
+            return;
+        };
+
+        let Some(src_idx) = monitors.iter().position(|mon| mon.has_sticky_window(&id)) else {
+            // The window closed or went back to a workspace.
+            self.follow_focus = None;
+            return;
+        };
+
+        let new_idx = *active_monitor_idx;
+        if src_idx == new_idx {
+            return;
+        }
+
+        let was_active = monitors[src_idx].sticky_is_active()
+            && monitors[src_idx]
+                .sticky_active_window_id()
+                .is_some_and(|active| *active == id);
+        let mut removed = monitors[src_idx].take_sticky_window(&id).unwrap();
+        if was_active {
+            monitors[src_idx].clear_sticky_focus();
+        }
+
+        removed.tile.floating_pos = None;
+        monitors[new_idx].add_sticky_tile(removed.tile, false);
+        monitors[new_idx].move_sticky_window_to_corner(&id, Corner::BottomRight, false);
+    }
+
+    /// Turns a window into a picture-in-picture window.
+    ///
+    /// Picture-in-picture windows are floating, always on top, and sticky, placed in a corner of
+    /// the working area.
+    pub fn set_window_picture_in_picture(&mut self, window: &W::Id, corner: Corner) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if window == move_.tile.window().id() {
+                return;
+            }
+        }
+
+        if !self.monitors().any(|mon| mon.has_sticky_window(window)) {
+            let target_is_active = self.focus().is_some_and(|win| win.id() == window);
+            let Some(ws) = self.workspaces_mut().find(|ws| ws.has_window(window)) else {
+                return;
+            };
+            if !ws.is_floating(window) {
+                ws.set_window_floating(Some(window), true);
+            }
+
+            let Some(mon) = self.monitors_mut().find(|mon| mon.has_window(window)) else {
+                return;
+            };
+            if !mon.add_sticky_window(window, target_is_active) {
+                return;
+            }
+        }
+
+        let Some(mon) = self
+            .monitors_mut()
+            .find(|mon| mon.has_sticky_window(window))
+        else {
+            return;
+        };
+        mon.set_sticky_window_always_on_top(window, true);
+        mon.move_sticky_window_to_corner(window, corner, true);
+
+        if !self.picture_in_picture.contains(window) {
+            self.picture_in_picture.push(window.clone());
+        }
+    }
+
+    /// Turns a picture-in-picture window back into a regular floating window on the active
+    /// workspace.
+    pub fn unset_window_picture_in_picture(&mut self, window: &W::Id) {
+        let Some(idx) = self.picture_in_picture.iter().position(|id| id == window) else {
+            return;
+        };
+        self.picture_in_picture.remove(idx);
+
+        let is_active = self.focus().is_some_and(|win| win.id() == window);
+        if let Some(mon) = self
+            .monitors_mut()
+            .find(|mon| mon.has_sticky_window(window))
+        {
+            mon.set_sticky_window_always_on_top(window, false);
+            mon.remove_sticky_window(window, is_active);
+        }
+    }
+
+    pub fn is_picture_in_picture(&self, window: &W::Id) -> bool {
+        self.picture_in_picture.contains(window)
+    }
+
+    /// Returns the corner that a picture-in-picture window is currently closest to.
+    pub fn picture_in_picture_corner(&self, window: &W::Id) -> Option<Corner> {
+        if !self.is_picture_in_picture(window) {
+            return None;
+        }
+
+        self.monitors()
+            .find_map(|mon| mon.sticky_window_corner(window))
+    }
+
     pub fn set_window_floating(&mut self, window: Option<&W::Id>, floating: bool) {
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
             if window.is_none() || window == Some(move_.tile.window().id()) {
@@ -3630,6 +4383,13 @@ impl<W: LayoutElement> Layout<W> {
         workspace.switch_focus_floating_tiling();
     }
 
+    pub fn arrange_floating(&mut self, arrangement: FloatingArrangement) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.arrange_floating(arrangement);
+    }
+
     pub fn move_window_to_scratchpad(&mut self, window: Option<&W::Id>) {
         if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
             if window.is_none() || window == Some(move_.tile.window().id()) {
@@ -3722,6 +4482,90 @@ impl<W: LayoutElement> Layout<W> {
         self.scratchpad = scratchpad;
     }
 
+    /// Hides a window until it is unminimized or activated.
+    ///
+    /// Tiled windows remember their spot in the tiling tree to come back to it.
+    pub fn minimize_window(&mut self, window: &W::Id) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if move_.tile.window().id() == window {
+                return;
+            }
+        }
+
+        let Some(ws) = self.workspaces_mut().find(|ws| ws.has_window(window)) else {
+            return;
+        };
+
+        let workspace_id = ws.id();
+        let origin = ws.scrolling_insert_parent_info(window);
+        let removed = ws.remove_tile(window, Transaction::new());
+        let mut tile = removed.tile;
+        tile.stop_move_animations();
+
+        self.minimized.push(MinimizedTile {
+            tile,
+            workspace_id,
+            origin,
+            width: removed.width,
+            is_full_width: removed.is_full_width,
+            is_floating: removed.is_floating,
+        });
+    }
+
+    /// Puts a minimized window back where it was and activates it.
+    pub fn unminimize_window(&mut self, window: &W::Id) {
+        let Some(idx) = self
+            .minimized
+            .iter()
+            .position(|minimized| minimized.tile.window().id() == window)
+        else {
+            return;
+        };
+        let minimized = self.minimized.remove(idx);
+
+        // The workspace may be gone by now, in which case go to the active one.
+        let ws_id = minimized.workspace_id;
+        let ws = if self.workspaces().any(|(_, _, ws)| ws.id() == ws_id) {
+            self.workspaces_mut().find(|ws| ws.id() == ws_id)
+        } else {
+            self.active_workspace_mut()
+        };
+        let Some(ws) = ws else {
+            return;
+        };
+
+        let origin = (ws.id() == ws_id).then_some(minimized.origin).flatten();
+        ws.add_minimized_tile(
+            minimized.tile,
+            origin.as_ref(),
+            minimized.width,
+            minimized.is_full_width,
+            minimized.is_floating,
+        );
+
+        self.activate_window(window);
+    }
+
+    pub fn is_minimized(&self, window: &W::Id) -> bool {
+        self.minimized
+            .iter()
+            .any(|minimized| minimized.tile.window().id() == window)
+    }
+
+    /// Tiles not in any workspace: the hidden scratchpad and minimized windows.
+    fn hidden_tiles(&self) -> impl Iterator<Item = &Tile<W>> {
+        let minimized = self.minimized.iter().map(|minimized| &minimized.tile);
+        self.scratchpad.iter().chain(minimized)
+    }
+
+    fn hidden_tiles_mut(&mut self) -> impl Iterator<Item = &mut Tile<W>> {
+        let minimized = self
+            .minimized
+            .iter_mut()
+            .map(|minimized| &mut minimized.tile);
+        self.scratchpad.iter_mut().chain(minimized)
+    }
+
     pub fn mark_focused(&mut self, mark: String, mode: MarkMode) {
         let Some(focused) = self.focus().map(|win| win.id().clone()) else {
             return;
@@ -4113,6 +4957,33 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Sets the area that a fullscreen window covers, relative to its own output.
+    ///
+    /// `None` means the window covers only its own output. Returns `true` if the span changed.
+    pub fn set_fullscreen_span(
+        &mut self,
+        id: &W::Id,
+        span: Option<Rectangle<f64, Logical>>,
+    ) -> bool {
+        for ws in self.workspaces_mut() {
+            if let Some(tile) = ws.tiles_mut().find(|tile| tile.window().id() == id) {
+                return tile.set_fullscreen_span(span);
+            }
+        }
+
+        false
+    }
+
+    /// Returns visible fullscreen tiles that span beyond their own output.
+    pub fn fullscreen_span_tiles(&self) -> impl Iterator<Item = (&Output, &Tile<W>)> + '_ {
+        self.monitors().filter_map(|mon| {
+            let tile = mon.active_workspace_ref().fullscreen_tile()?;
+            tile.fullscreen_span()
+                .is_some()
+                .then_some((&mon.output, tile))
+        })
+    }
+
     pub fn toggle_windowed_fullscreen(&mut self, id: &W::Id) {
         let (_, window) = self.windows().find(|(_, win)| win.id() == id).unwrap();
         if window.pending_sizing_mode().is_fullscreen() {
@@ -4965,7 +5836,7 @@ impl<W: LayoutElement> Layout<W> {
                                     let pos_within_workspace =
                                         (move_.pointer_pos_within_output - geo.loc).downscale(zoom);
                                     let ws = &mut mon.workspaces[ws_idx];
-                                    ws.scrolling_insert_position(pos_within_workspace)
+                                    ws.scrolling_insert_position(pos_within_workspace, zoom)
                                 };
 
                                 (position, Some(geo.loc))
@@ -4992,7 +5863,7 @@ impl<W: LayoutElement> Layout<W> {
                         let position = if move_.is_floating {
                             InsertPosition::Floating
                         } else {
-                            ws.scrolling_insert_position(Point::from((0., 0.)))
+                            ws.scrolling_insert_position(Point::from((0., 0.)), zoom)
                         };
 
                         let insert_ws = InsertWorkspace::Existing(ws.id());
@@ -5225,6 +6096,7 @@ impl<W: LayoutElement> Layout<W> {
             output,
             pointer_pos_within_output,
             hold: None,
+            edge_hover: self.dnd.as_mut().and_then(|dnd| dnd.edge_hover.take()),
         });
 
         if begin_gesture {
@@ -5234,6 +6106,62 @@ impl<W: LayoutElement> Layout<W> {
         }
     }
 
+    /// Switches workspaces while a DnD hovers at the top or bottom edge of a monitor.
+    fn dnd_edge_hover(&mut self) {
+        let config = self.options.gestures.dnd_edge_hover;
+        // The overview has its own DnD edge scrolling.
+        if config.off || self.overview_open {
+            return;
+        }
+
+        let Some(dnd) = &self.dnd else {
+            return;
+        };
+        let output = dnd.output.clone();
+        let pos = dnd.pointer_pos_within_output;
+
+        let Some(view_size) = self.monitor_for_output(&output).map(|mon| mon.view_size()) else {
+            return;
+        };
+        let trigger_size = config.trigger_size.min(view_size.h / 2.);
+        let is_top = if pos.y < trigger_size {
+            true
+        } else if view_size.h - pos.y <= trigger_size {
+            false
+        } else {
+            if let Some(dnd) = &mut self.dnd {
+                dnd.edge_hover = None;
+            }
+            return;
+        };
+
+        let now = self.clock.now_unadjusted();
+        let dnd = self.dnd.as_mut().unwrap();
+        if dnd.edge_hover.as_ref().is_none_or(|h| h.is_top != is_top) {
+            dnd.edge_hover = Some(DndEdgeHover {
+                is_top,
+                start_time: now,
+            });
+        }
+        let hover = dnd.edge_hover.as_mut().unwrap();
+
+        let delay = Duration::from_millis(u64::from(config.delay_ms));
+        if now.saturating_sub(hover.start_time) < delay {
+            return;
+        }
+
+        // Keep switching one workspace at a time while the pointer stays at the edge.
+        hover.start_time = now;
+        dnd.hold = None;
+
+        let mon = self.monitor_for_output_mut(&output).unwrap();
+        if is_top {
+            mon.switch_workspace_up();
+        } else {
+            mon.switch_workspace_down();
+        }
+    }
+
     pub fn dnd_end(&mut self) {
         if self.dnd.is_none() {
             return;
@@ -5639,6 +6567,7 @@ impl<W: LayoutElement> Layout<W> {
 
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
             if move_.tile.window().id() == window {
+                let style = move_.tile.close_style();
                 let Some(snapshot) = move_.tile.take_unmap_snapshot() else {
                     return;
                 };
@@ -5661,7 +6590,9 @@ impl<W: LayoutElement> Layout<W> {
                     .unwrap();
 
                 let tile_pos = tile_pos - ws_geo.loc;
-                ws.start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, blocker);
+                ws.start_close_animation_for_tile(
+                    renderer, snapshot, tile_size, tile_pos, blocker, style,
+                );
                 return;
             }
         }
@@ -5730,6 +6661,15 @@ impl<W: LayoutElement> Layout<W> {
 
         self.is_active = is_active;
 
+        self.refresh_follow_focus();
+
+        // Forget picture-in-picture windows that closed or stopped being sticky.
+        if !self.picture_in_picture.is_empty() {
+            let mut picture_in_picture = mem::take(&mut self.picture_in_picture);
+            picture_in_picture.retain(|id| self.monitors().any(|mon| mon.has_sticky_window(id)));
+            self.picture_in_picture = picture_in_picture;
+        }
+
         let mut ongoing_scrolling_dnd = self.dnd.is_some().then_some(true);
 
         if let Some(InteractiveMoveState::Moving(move_)) = &mut self.interactive_move {
@@ -5783,6 +6723,7 @@ impl<W: LayoutElement> Layout<W> {
                     for (ws_idx, ws) in mon.workspaces.iter_mut().enumerate() {
                         let is_focused = is_active && ws_idx == mon.active_workspace_idx;
                         ws.refresh(is_active, is_focused);
+                        ws.refresh_titles(ws_idx);
 
                         if ongoing_scrolling_dnd.is_none() {
                             // Cancel the view offset gesture after workspace switches, moves, etc.
@@ -5797,8 +6738,9 @@ impl<W: LayoutElement> Layout<W> {
                 }
             }
             MonitorSet::NoOutputs { workspaces, .. } => {
-                for ws in workspaces {
+                for (ws_idx, ws) in workspaces.iter_mut().enumerate() {
                     ws.refresh(false, false);
+                    ws.refresh_titles(ws_idx);
                     ws.view_offset_gesture_end(None);
                 }
             }
@@ -5882,18 +6824,14 @@ impl<W: LayoutElement> Layout<W> {
                 .map(move |win| (Some(mon), win))
         });
 
-        let scratchpad = self
-            .scratchpad
-            .iter()
-            .map(|tile| (None, tile.window()));
+        let hidden = self.hidden_tiles().map(|tile| (None, tile.window()));
 
-        moving_window.chain(rest).chain(sticky).chain(scratchpad)
+        moving_window.chain(rest).chain(sticky).chain(hidden)
     }
 
     fn tile_has_mark(&self, id: &W::Id, mark: &str) -> bool {
         if self
-            .scratchpad
-            .iter()
+            .hidden_tiles()
             .any(|tile| tile.window().id() == id && tile.has_mark(mark))
         {
             return true;
@@ -5919,8 +6857,7 @@ impl<W: LayoutElement> Layout<W> {
         let mut f = Some(f);
 
         if let Some(tile) = self
-            .scratchpad
-            .iter_mut()
+            .hidden_tiles_mut()
             .find(|tile| tile.window().id() == id)
         {
             f.take().unwrap()(tile);
@@ -5960,7 +6897,7 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     fn remove_mark_everywhere(&mut self, mark: &str) {
-        for tile in &mut self.scratchpad {
+        for tile in self.hidden_tiles_mut() {
             tile.remove_mark(mark);
         }
 
@@ -1,12 +1,15 @@
+use std::cell::RefCell;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::iter::zip;
 use std::rc::Rc;
 use std::time::Duration;
 
-use niri_config::{CornerRadius, LayoutPart};
+use niri_config::{Corner, CornerRadius, LayoutPart, WorkspaceIndices};
 use smithay::backend::renderer::element::utils::{
     CropRenderElement, Relocate, RelocateRenderElement, RescaleRenderElement,
 };
+use smithay::backend::renderer::element::Kind;
 use smithay::output::Output;
 use smithay::utils::{Logical, Point, Rectangle, Size};
 
@@ -23,9 +26,12 @@ use super::{compute_overview_zoom, ActivateWindow, HitType, LayoutElement, Optio
 use crate::animation::{Animation, Clock};
 use crate::input::swipe_tracker::SwipeTracker;
 use crate::niri_render_elements;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
-use crate::render_helpers::solid_color::SolidColorRenderElement;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::text::CachedText;
+use crate::render_helpers::texture::TextureRenderElement;
 use crate::render_helpers::RenderTarget;
 use crate::rubber_band::RubberBand;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
@@ -96,6 +102,10 @@ pub struct Monitor<W: LayoutElement> {
     pub(super) sticky_floating: FloatingSpace<W>,
     /// Whether sticky windows are focused on this monitor.
     sticky_is_active: bool,
+    /// Black drawn over the backdrop in the overview.
+    backdrop_dim_buffer: SolidColorBuffer,
+    /// Labels under the workspaces in the overview, rendered while it is open.
+    workspace_labels: RefCell<HashMap<WorkspaceId, CachedText>>,
 }
 
 #[derive(Debug)]
@@ -354,7 +364,7 @@ impl<W: LayoutElement> Monitor<W> {
 
         let scale = output.current_scale();
         let view_size = output_size(&output);
-        let working_area = compute_working_area(&output);
+        let working_area = compute_working_area(&output, options.layout.safe_area);
         let sticky_floating = FloatingSpace::new(
             view_size,
             working_area,
@@ -407,6 +417,8 @@ impl<W: LayoutElement> Monitor<W> {
             layout_config,
             sticky_floating,
             sticky_is_active: false,
+            backdrop_dim_buffer: SolidColorBuffer::new(view_size, [0., 0., 0., 1.]),
+            workspace_labels: RefCell::new(HashMap::new()),
         }
     }
 
@@ -517,7 +529,7 @@ impl<W: LayoutElement> Monitor<W> {
         };
 
         removed.tile.set_scratchpad(false);
-        removed.tile.set_sticky(true);
+        removed.tile.set_sticky(!removed.tile.follows_focus());
 
         self.sticky_floating.add_tile(removed.tile, activate);
         if activate {
@@ -529,7 +541,7 @@ impl<W: LayoutElement> Monitor<W> {
 
     pub fn add_sticky_tile(&mut self, mut tile: Tile<W>, activate: bool) {
         tile.set_scratchpad(false);
-        tile.set_sticky(true);
+        tile.set_sticky(!tile.follows_focus());
 
         self.sticky_floating.add_tile(tile, activate);
         if activate {
@@ -550,6 +562,7 @@ impl<W: LayoutElement> Monitor<W> {
 
         let mut removed = self.sticky_floating.remove_tile(window);
         removed.tile.set_sticky(false);
+        removed.tile.set_follows_focus(false);
 
         let ws_id = self.workspaces[self.active_workspace_idx].id();
         let activate = if activate {
@@ -605,6 +618,91 @@ impl<W: LayoutElement> Monitor<W> {
         self.sticky_floating.move_window(id, x, y, animate);
     }
 
+    /// Moves a sticky window into a corner of the working area.
+    pub fn move_sticky_window_to_corner(&mut self, window: &W::Id, corner: Corner, animate: bool) {
+        let Some(tile) = self
+            .sticky_floating
+            .tiles()
+            .find(|tile| tile.window().id() == window)
+        else {
+            return;
+        };
+
+        let size = tile.tile_expected_or_current_size();
+        let area = self.sticky_floating.working_area();
+        let gap = self.options.layout.gaps;
+        let x = match corner {
+            Corner::TopLeft | Corner::BottomLeft => gap,
+            Corner::TopRight | Corner::BottomRight => area.size.w - size.w - gap,
+        };
+        let y = match corner {
+            Corner::TopLeft | Corner::TopRight => gap,
+            Corner::BottomLeft | Corner::BottomRight => area.size.h - size.h - gap,
+        };
+        let x = f64::max(x, 0.);
+        let y = f64::max(y, 0.);
+
+        self.sticky_floating.move_window(
+            Some(window),
+            niri_ipc::PositionChange::SetFixed(x),
+            niri_ipc::PositionChange::SetFixed(y),
+            animate,
+        );
+    }
+
+    /// Sets whether a window above the workspaces follows the focus across outputs, rather than
+    /// being sticky to this output.
+    pub fn set_sticky_window_follows_focus(&mut self, id: &W::Id, follows_focus: bool) -> bool {
+        let Some(tile) = self
+            .sticky_floating
+            .tiles_mut()
+            .find(|tile| tile.window().id() == id)
+        else {
+            return false;
+        };
+
+        tile.set_follows_focus(follows_focus);
+        tile.set_sticky(!follows_focus);
+        true
+    }
+
+    pub fn toggle_sticky_window_always_on_top(&mut self, id: &W::Id) -> bool {
+        self.sticky_floating.toggle_window_always_on_top(id)
+    }
+
+    pub fn raise_sticky_window(&mut self, id: &W::Id) -> bool {
+        self.sticky_floating.raise_window(id)
+    }
+
+    pub fn lower_sticky_window(&mut self, id: &W::Id) -> bool {
+        self.sticky_floating.lower_window(id)
+    }
+
+    pub fn set_sticky_window_always_on_top(&mut self, id: &W::Id, always_on_top: bool) -> bool {
+        self.sticky_floating
+            .set_window_always_on_top(id, always_on_top)
+    }
+
+    /// Returns the corner of the working area closest to the center of a sticky window.
+    pub fn sticky_window_corner(&self, window: &W::Id) -> Option<Corner> {
+        let (tile, pos) = self
+            .sticky_floating
+            .tiles_with_offsets()
+            .find(|(tile, _)| tile.window().id() == window)?;
+
+        let area = self.sticky_floating.working_area();
+        let size = tile.tile_size();
+        let left = pos.x - area.loc.x + size.w / 2. < area.size.w / 2.;
+        let top = pos.y - area.loc.y + size.h / 2. < area.size.h / 2.;
+        let corner = match (left, top) {
+            (true, true) => Corner::TopLeft,
+            (false, true) => Corner::TopRight,
+            (true, false) => Corner::BottomLeft,
+            (false, false) => Corner::BottomRight,
+        };
+        Some(corner)
+    }
+
     pub fn center_sticky_window(&mut self, id: Option<&W::Id>) {
         self.sticky_floating.center_window(id);
     }
@@ -655,6 +753,20 @@ impl<W: LayoutElement> Monitor<W> {
         workspace_windows.chain(sticky_windows)
     }
 
+    /// Returns windows that can't be seen, either covered or on workspaces out of view.
+    pub fn occluded_windows(&self) -> impl Iterator<Item = &W> {
+        let visible: Vec<_> = self
+            .workspaces_with_render_geo()
+            .map(|(ws, _)| ws.id())
+            .collect();
+        self.workspaces.iter().flat_map(move |ws| {
+            let out_of_view = !visible.contains(&ws.id());
+            let all = ws.windows().filter(move |_| out_of_view);
+            let covered = ws.occluded_windows().filter(move |_| !out_of_view);
+            all.chain(covered)
+        })
+    }
+
     pub fn sticky_windows(&self) -> impl Iterator<Item = &W> {
         self.sticky_floating.tiles().map(|tile| tile.window())
     }
@@ -1018,16 +1130,25 @@ impl<W: LayoutElement> Monitor<W> {
         } else {
             0
         };
+        let keep_gaps = self.options.layout.workspace_indices == WorkspaceIndices::KeepGaps;
         for idx in (range_start..self.workspaces.len() - 1).rev() {
             if self.active_workspace_idx == idx {
                 continue;
             }
 
-            if !self.workspaces[idx].has_windows_or_name() {
-                self.workspaces.remove(idx);
-                if self.active_workspace_idx > idx {
-                    self.active_workspace_idx -= 1;
-                }
+            if self.workspaces[idx].has_windows_or_name() {
+                continue;
+            }
+
+            // Keep the gap if removing this workspace would change the indices of the
+            // workspaces below it.
+            if keep_gaps && self.is_gap(idx) {
+                continue;
+            }
+
+            self.workspaces.remove(idx);
+            if self.active_workspace_idx > idx {
+                self.active_workspace_idx -= 1;
             }
         }
 
@@ -1041,6 +1162,17 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Returns whether an active or non-empty workspace comes after the workspace at `idx`.
+    ///
+    /// The last workspace is always empty, so it doesn't count.
+    fn is_gap(&self, idx: usize) -> bool {
+        let last = self.workspaces.len() - 1;
+        (idx + 1..last).any(|i| i == self.active_workspace_idx)
+            || self.workspaces[idx + 1..last]
+                .iter()
+                .any(|ws| ws.has_windows_or_name())
+    }
+
     pub fn unname_workspace(&mut self, id: WorkspaceId) -> bool {
         let Some(ws) = self.workspaces.iter_mut().find(|ws| ws.id() == id) else {
             return false;
@@ -1273,6 +1405,7 @@ impl<W: LayoutElement> Monitor<W> {
                     .take_sticky_window(window_id)
                     .expect("sticky window should exist");
                 removed.tile.set_sticky(false);
+                removed.tile.set_follows_focus(false);
 
                 self.add_tile(
                     removed.tile,
@@ -1565,13 +1698,13 @@ impl<W: LayoutElement> Monitor<W> {
             match hint.workspace {
                 InsertWorkspace::Existing(ws_id) => {
                     if let Some(ws) = self.workspaces.iter().find(|ws| ws.id() == ws_id) {
-                        if let Some(mut area) = ws.insert_hint_area(&hint.position) {
+                        let zoom = self.overview_zoom();
+                        if let Some(mut area) = ws.insert_hint_area(&hint.position, zoom) {
                             let scale = ws.scale().fractional_scale();
                             let view_size = ws.view_size();
 
                             // Make sure the hint is at least partially visible.
                             if matches!(&hint.position, InsertPosition::NewColumn(_)) {
-                                let zoom = self.overview_zoom();
                                 let geo = insert_hint_ws_geo.unwrap();
                                 let geo = geo.downscale(zoom);
 
@@ -1659,6 +1792,8 @@ impl<W: LayoutElement> Monitor<W> {
             }
         }
 
+        self.working_area = compute_working_area(&self.output, options.layout.safe_area);
+
         for ws in &mut self.workspaces {
             ws.update_config(options.clone());
         }
@@ -1673,8 +1808,17 @@ impl<W: LayoutElement> Monitor<W> {
         self.insert_hint_element
             .update_config(options.layout.insert_hint);
 
+        let indices_changed =
+            self.options.layout.workspace_indices != options.layout.workspace_indices;
+
         self.base_options = base_options;
         self.options = options;
+
+        // Going from keep-gaps to fill-gaps should close the gaps right away rather than on the
+        // next workspace change.
+        if indices_changed && self.workspace_switch.is_none() {
+            self.clean_up_workspaces();
+        }
     }
 
     pub fn update_layout_config(&mut self, layout_config: Option<niri_config::LayoutPart>) -> bool {
@@ -1701,7 +1845,8 @@ impl<W: LayoutElement> Monitor<W> {
     pub fn update_output_size(&mut self) {
         self.scale = self.output.current_scale();
         self.view_size = output_size(&self.output);
-        self.working_area = compute_working_area(&self.output);
+        self.working_area = compute_working_area(&self.output, self.options.layout.safe_area);
+        self.backdrop_dim_buffer.resize(self.view_size);
 
         for ws in &mut self.workspaces {
             ws.update_output_size();
@@ -1982,6 +2127,35 @@ impl<W: LayoutElement> Monitor<W> {
             .filter(move |(_ws, geo)| geo.intersection(output_geo).is_some())
     }
 
+    /// Returns the visible windows with their rectangles within the output.
+    pub fn visible_windows_with_render_rects(&self) -> Vec<(&W, Rectangle<f64, Logical>)> {
+        let zoom = self.overview_zoom();
+        let window_rect = |tile: &Tile<W>, tile_pos: Point<f64, Logical>, ws_loc| {
+            let loc = ws_loc + (tile_pos + tile.window_loc()).upscale(zoom);
+            Rectangle::new(loc, tile.window_size().upscale(zoom))
+        };
+
+        let mut rv = Vec::new();
+
+        if self.sticky_is_visible() {
+            if let Some(geo) = self.active_workspace_render_geo() {
+                for (tile, tile_pos) in self.sticky_floating.tiles_with_render_positions() {
+                    rv.push((tile.window(), window_rect(tile, tile_pos, geo.loc)));
+                }
+            }
+        }
+
+        for (ws, geo) in self.workspaces_with_render_geo() {
+            for (tile, tile_pos, visible) in ws.tiles_with_render_positions() {
+                if visible {
+                    rv.push((tile.window(), window_rect(tile, tile_pos, geo.loc)));
+                }
+            }
+        }
+
+        rv
+    }
+
     pub fn workspaces_with_render_geo_idx(
         &self,
     ) -> impl Iterator<Item = ((usize, &Workspace<W>), Rectangle<f64, Logical>)> {
@@ -2385,6 +2559,79 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    pub fn render_workspace_labels<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        push: &mut dyn FnMut(PrimaryGpuTextureRenderElement),
+    ) {
+        let mut labels = self.workspace_labels.borrow_mut();
+
+        let config = &self.options.overview.workspace_labels;
+        let progress = self.overview_progress.as_ref().map(|p| p.clamped_value());
+        let Some(progress) = progress.filter(|_| !config.off) else {
+            // Free the textures while the overview is closed.
+            labels.clear();
+            return;
+        };
+        let alpha = progress.clamp(0., 1.) as f32;
+
+        let _span = tracy_client::span!("Monitor::render_workspace_labels");
+
+        let scale = self.scale.fractional_scale();
+        let zoom = self.overview_zoom();
+        let gap = self.workspace_gap(zoom);
+
+        labels.retain(|id, _| self.workspaces.iter().any(|ws| ws.id() == *id));
+
+        for ((idx, ws), geo) in self.workspaces_with_render_geo_idx() {
+            // Unnamed workspaces go by their index, like in `focus-workspace`.
+            let text = match ws.name() {
+                Some(name) => name.clone(),
+                None => (idx + 1).to_string(),
+            };
+
+            let label = labels.entry(ws.id()).or_default();
+            let renderer = renderer.as_gles_renderer();
+            let Some(buffer) = label.update(renderer, &text, &config.font, config.color, scale)
+            else {
+                continue;
+            };
+
+            // Center the label in the gap under the workspace.
+            let size = buffer.logical_size();
+            let x = geo.loc.x + (geo.size.w - size.w) / 2.;
+            let y = geo.loc.y + geo.size.h + (gap - size.h).max(0.) / 2.;
+            let location = Point::from((x, y));
+            let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer,
+                location,
+                alpha,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            push(PrimaryGpuTextureRenderElement(elem));
+        }
+    }
+
+    /// Returns the black drawn over the backdrop in the overview, if any.
+    pub fn render_backdrop_dim(&self) -> Option<SolidColorRenderElement> {
+        let progress = self.overview_progress.as_ref()?.clamped_value();
+        let alpha = self.options.overview.backdrop_dim * progress.clamp(0., 1.);
+        if alpha <= 0. {
+            return None;
+        }
+
+        Some(SolidColorRenderElement::from_buffer(
+            &self.backdrop_dim_buffer,
+            Point::from((0., 0.)),
+            alpha as f32,
+            Kind::Unspecified,
+        ))
+    }
+
     pub fn workspace_switch_gesture_begin(&mut self, is_touchpad: bool) {
         let center_idx = self.active_workspace_idx;
         let current_idx = self.workspace_render_idx();
@@ -2713,12 +2960,14 @@ impl<W: LayoutElement> Monitor<W> {
 
         // If there's no workspace switch in progress, there can't be any non-last non-active
         // empty workspaces. If empty_workspace_above_first is set then the first workspace
-        // will be empty too.
+        // will be empty too. With keep-gaps, empty workspaces can stay only as gaps before
+        // the active or a non-empty workspace.
         let pre_skip = if self.options.layout.empty_workspace_above_first {
             1
         } else {
             0
         };
+        let keep_gaps = self.options.layout.workspace_indices == WorkspaceIndices::KeepGaps;
         if self.workspace_switch.is_none() {
             for (idx, ws) in self
                 .workspaces
@@ -2729,7 +2978,7 @@ impl<W: LayoutElement> Monitor<W> {
                 // skip last
                 .skip(1)
             {
-                if idx != self.active_workspace_idx {
+                if idx != self.active_workspace_idx && !(keep_gaps && self.is_gap(idx)) {
                     assert!(
                         ws.has_windows_or_name(),
                         "non-active workspace can't be empty and unnamed except the last one"
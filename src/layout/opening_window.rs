@@ -3,6 +3,7 @@ use std::rc::Rc;
 
 use anyhow::Context as _;
 use glam::{Mat3, Vec2};
+use niri_config::animations::WindowAnimStyle;
 use smithay::backend::renderer::element::utils::{
     Relocate, RelocateRenderElement, RescaleRenderElement,
 };
@@ -11,6 +12,7 @@ use smithay::backend::renderer::gles::{GlesRenderer, Uniform};
 use smithay::backend::renderer::Texture;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
 
+use super::container::Direction;
 use crate::animation::Animation;
 use crate::niri_render_elements;
 use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenData, OffscreenRenderElement};
@@ -20,10 +22,36 @@ use crate::render_helpers::shaders::{mat3_uniform, ProgramType, Shaders};
 #[derive(Debug)]
 pub struct OpenAnimation {
     anim: Animation,
+    style: OpenCloseStyle,
     random_seed: f32,
     buffer: OffscreenBuffer,
 }
 
+/// Look of a window opening or closing animation without a custom shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenCloseStyle {
+    pub style: WindowAnimStyle,
+    /// Edge that the window slides in from and out to with [`WindowAnimStyle::Slide`].
+    pub edge: Direction,
+}
+
+impl OpenCloseStyle {
+    /// Offset of a sliding window, where `amount` goes from 0 at rest to 1 fully slid out.
+    pub fn slide_offset(&self, size: Size<f64, Logical>, amount: f64) -> Point<f64, Logical> {
+        if self.style != WindowAnimStyle::Slide {
+            return Point::from((0., 0.));
+        }
+
+        let offset = match self.edge {
+            Direction::Left => Point::from((-size.w, 0.)),
+            Direction::Right => Point::from((size.w, 0.)),
+            Direction::Up => Point::from((0., -size.h)),
+            Direction::Down => Point::from((0., size.h)),
+        };
+        offset.upscale(amount)
+    }
+}
+
 niri_render_elements! {
     OpeningWindowRenderElement => {
         Offscreen = RelocateRenderElement<RescaleRenderElement<OffscreenRenderElement>>,
@@ -32,9 +60,10 @@ niri_render_elements! {
 }
 
 impl OpenAnimation {
-    pub fn new(anim: Animation) -> Self {
+    pub fn new(anim: Animation, style: OpenCloseStyle) -> Self {
         Self {
             anim,
+            style,
             random_seed: fastrand::f32(),
             buffer: OffscreenBuffer::default(),
         }
@@ -122,15 +151,21 @@ impl OpenAnimation {
             return Ok((elem.into(), data));
         }
 
-        let elem = elem.with_alpha(clamped_progress as f32 * alpha);
+        let (fade, zoom) = match self.style.style {
+            WindowAnimStyle::Popin => (clamped_progress, progress / 2. + 0.5),
+            WindowAnimStyle::Scale => (1., progress),
+            WindowAnimStyle::Fade | WindowAnimStyle::Slide => (clamped_progress, 1.),
+        };
+        let elem = elem.with_alpha(fade as f32 * alpha);
 
         let center = geo_size.to_point().downscale(2.);
         let elem = RescaleRenderElement::from_element(
             elem,
             center.to_physical_precise_round(scale),
-            (progress / 2. + 0.5).max(0.),
+            zoom.max(0.),
         );
 
+        let location = location + self.style.slide_offset(geo_size, 1. - progress);
         let elem = RelocateRenderElement::from_element(
             elem,
             location.to_physical_precise_round(scale),
@@ -1,18 +1,94 @@
 use std::borrow::Cow;
 
 use anyhow::{bail, Context, Result};
-use niri_config::{Color, TabBar};
+use niri_config::{Color, TabBar, TabIndicatorPosition};
 use pangocairo::cairo::{self, ImageSurface};
-use pangocairo::pango::{self, Alignment, EllipsizeMode, FontDescription};
+use pangocairo::pango::{self, Alignment, EllipsizeMode};
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::reexports::gbm::Format as Fourcc;
-use smithay::utils::{Logical, Rectangle, Size, Transform};
+use smithay::utils::{Logical, Point, Rectangle, Size, Transform};
 
 use super::container::{Layout, TabBarInfo, TabBarTab};
+use crate::render_helpers::text::{
+    create_layout, font_for_scale, measure_font_height_px, set_source_color,
+};
 use crate::render_helpers::texture::TextureBuffer;
 use crate::render_helpers::RenderTarget;
 use crate::utils::{round_logical_in_physical_max1, to_physical_precise_round};
 
+/// Space that a tab bar or a title bar takes up within a tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabBarInset {
+    /// Thickness of the bar.
+    pub size: f64,
+    /// Edge of the tile that the bar sits on.
+    pub position: TabIndicatorPosition,
+}
+
+impl TabBarInset {
+    pub const NONE: Self = Self::top(0.);
+
+    pub const fn top(size: f64) -> Self {
+        Self {
+            size,
+            position: TabIndicatorPosition::Top,
+        }
+    }
+
+    pub fn is_none(self) -> bool {
+        self.size <= 0.
+    }
+
+    /// Returns how much the bar adds to the size of the tile.
+    pub fn extent(self) -> Size<f64, Logical> {
+        match self.position {
+            TabIndicatorPosition::Top | TabIndicatorPosition::Bottom => Size::from((0., self.size)),
+            TabIndicatorPosition::Left | TabIndicatorPosition::Right => Size::from((self.size, 0.)),
+        }
+    }
+
+    /// Returns how far the bar pushes the contents of the tile.
+    pub fn offset(self) -> Point<f64, Logical> {
+        match self.position {
+            TabIndicatorPosition::Top => Point::from((0., self.size)),
+            TabIndicatorPosition::Left => Point::from((self.size, 0.)),
+            TabIndicatorPosition::Bottom | TabIndicatorPosition::Right => Point::from((0., 0.)),
+        }
+    }
+
+    /// Splits the area into the bar and the rest.
+    pub fn split(
+        self,
+        area: Rectangle<f64, Logical>,
+    ) -> (Rectangle<f64, Logical>, Rectangle<f64, Logical>) {
+        let mut bar = area;
+        let mut rest = area;
+        match self.position {
+            TabIndicatorPosition::Top | TabIndicatorPosition::Bottom => {
+                let size = self.size.clamp(0., area.size.h);
+                bar.size.h = size;
+                rest.size.h -= size;
+                if self.position == TabIndicatorPosition::Top {
+                    rest.loc.y += size;
+                } else {
+                    bar.loc.y += rest.size.h;
+                }
+            }
+            TabIndicatorPosition::Left | TabIndicatorPosition::Right => {
+                let size = self.size.clamp(0., area.size.w);
+                bar.size.w = size;
+                rest.size.w -= size;
+                if self.position == TabIndicatorPosition::Left {
+                    rest.loc.x += size;
+                } else {
+                    bar.loc.x += rest.size.w;
+                }
+            }
+        }
+        (bar, rest)
+    }
+}
+
 fn sanitize_title(title: &str) -> Cow<'_, str> {
     if title.chars().all(|ch| !ch.is_control()) {
         let trimmed = title.trim();
@@ -39,41 +115,27 @@ fn sanitize_title(title: &str) -> Cow<'_, str> {
     }
 }
 
-fn font_description_for_scale(config: &TabBar, scale: f64) -> FontDescription {
-    let mut font = FontDescription::from_string(&config.font);
-    let base_size = font.size() as f64;
-    let size = if base_size > 0.0 {
-        base_size
-    } else {
-        let fallback_px = parse_font_size(&config.font).unwrap_or(12.0);
-        fallback_px * pango::SCALE as f64
-    };
-    let size = to_physical_precise_round::<f64>(scale, size).max(1.0);
-    font.set_absolute_size(size);
-    font
-}
-
-fn measure_font_height_px(font: &FontDescription) -> Option<i32> {
-    let surface = ImageSurface::create(cairo::Format::ARgb32, 1, 1).ok()?;
-    let cr = cairo::Context::new(&surface).ok()?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(font));
-    layout.set_text("Ag");
-    let (_w, h_px) = layout.pixel_size();
-    (h_px > 0).then_some(h_px)
+/// Returns whether the tabs sit next to each other in a single row.
+///
+/// Tab bars on the side list the tabs one per row, like stacked layouts do.
+pub fn tabs_in_single_row(layout: Layout, position: TabIndicatorPosition) -> bool {
+    layout == Layout::Tabbed
+        && matches!(
+            position,
+            TabIndicatorPosition::Top | TabIndicatorPosition::Bottom
+        )
 }
 
 pub fn tab_bar_row_height(config: &TabBar, scale: f64) -> f64 {
     let mut height = config.height;
     if height <= 0.0 {
-        let font = font_description_for_scale(config, scale);
+        let font = font_for_scale(&config.font, scale);
         if let Some(h_px) = measure_font_height_px(&font) {
             let font_height = (h_px as f64) / scale;
             height = font_height + config.padding_y * 2.0;
         }
         if height <= 0.0 {
-            let font_height = parse_font_size(&config.font).unwrap_or(12.0);
+            let font_height = f64::from(font.size()) / f64::from(pango::SCALE) / scale;
             height = font_height + config.padding_y * 2.0;
         }
     }
@@ -81,32 +143,6 @@ pub fn tab_bar_row_height(config: &TabBar, scale: f64) -> f64 {
     round_logical_in_physical_max1(scale, height)
 }
 
-fn parse_font_size(font: &str) -> Option<f64> {
-    let mut last = None;
-    let mut buf = String::new();
-    for ch in font.chars() {
-        if ch.is_ascii_digit() || ch == '.' {
-            buf.push(ch);
-        } else if !buf.is_empty() {
-            if let Ok(val) = buf.parse::<f64>() {
-                last = Some(val);
-            }
-            buf.clear();
-        }
-    }
-    if !buf.is_empty() {
-        if let Ok(val) = buf.parse::<f64>() {
-            last = Some(val);
-        }
-    }
-    last
-}
-
-fn set_source_color(cr: &cairo::Context, color: Color) {
-    let [r, g, b, a] = color.to_array_unpremul();
-    cr.set_source_rgba(f64::from(r), f64::from(g), f64::from(b), f64::from(a));
-}
-
 fn tab_colors(
     config: &TabBar,
     tab: &TabBarTab,
@@ -127,13 +163,16 @@ pub struct TabBarTabState {
     pub title: String,
     pub is_focused: bool,
     pub is_urgent: bool,
+    pub has_activity: bool,
     pub block_out: bool,
+    pub has_block_out_indicator: bool,
 }
 
 /// Common tab bar state for caching
 #[derive(Debug, Clone, PartialEq)]
 pub struct TabBarState {
     pub layout: Layout,
+    pub position: TabIndicatorPosition,
     pub size: Size<f64, Logical>,
     pub row_height: f64,
     pub scale: f64,
@@ -164,12 +203,15 @@ pub fn tab_bar_state_from_info(
             title: tab.title.clone(),
             is_focused: tab.is_focused && is_active,
             is_urgent: tab.is_urgent,
+            has_activity: tab.has_activity,
             block_out: target.should_block_out(tab.block_out_from),
+            has_block_out_indicator: tab.block_out_from.is_some(),
         })
         .collect();
 
     TabBarState {
         layout: info.layout,
+        position: info.position,
         size: info.rect.size,
         row_height: info.row_height,
         scale,
@@ -186,7 +228,7 @@ pub struct TabBarRenderOutput {
 pub fn render_tab_bar(
     renderer: &mut GlesRenderer,
     config: &TabBar,
-    layout: Layout,
+    single_row: bool,
     rect: Rectangle<f64, Logical>,
     row_height: f64,
     tabs: &[TabBarTab],
@@ -208,7 +250,7 @@ pub fn render_tab_bar(
         to_physical_precise_round::<i32>(scale, config.separator_width).max(0);
     let border_width_px: i32 = to_physical_precise_round::<i32>(scale, config.border_width).max(0);
 
-    let mut font = font_description_for_scale(config, scale);
+    let mut font = font_for_scale(&config.font, scale);
     let font_height_px = measure_font_height_px(&font).unwrap_or(row_height_px);
 
     let min_padding_y = row_height_px.saturating_sub(1) / 2;
@@ -223,7 +265,7 @@ pub fn render_tab_bar(
         font.set_absolute_size(new_size);
     }
 
-    let tab_widths = if layout == Layout::Tabbed {
+    let tab_widths = if single_row {
         let tab_count_i32 = tab_count as i32;
         let base = width_px / tab_count_i32;
         let mut widths = vec![base.max(1); tab_count];
@@ -241,17 +283,15 @@ pub fn render_tab_bar(
     set_source_color(&cr, config.inactive_bg);
     cr.paint()?;
 
-    let text_layout = pangocairo::functions::create_layout(&cr);
-    text_layout.context().set_round_glyph_positions(false);
+    let text_layout = create_layout(&cr, &font);
     text_layout.set_single_paragraph_mode(true);
-    text_layout.set_font_description(Some(&font));
     text_layout.set_ellipsize(EllipsizeMode::End);
     text_layout.set_alignment(Alignment::Left);
 
     let mut cursor_x = 0;
     for (idx, tab) in tabs.iter().enumerate() {
         let width = tab_widths[idx];
-        let (x, y, w, h) = if layout == Layout::Tabbed {
+        let (x, y, w, h) = if single_row {
             (cursor_x, 0, width, row_height_px)
         } else {
             (0, idx as i32 * row_height_px, width_px, row_height_px)
@@ -287,13 +327,56 @@ pub fn render_tab_bar(
             cr.fill()?;
         }
 
+        let text_area_height = (h - padding_y_px * 2).max(1);
+
+        // Windows hidden from screencasts get a dot at the end of their tab. When the tab bar
+        // itself is blocked out, the dot takes the background color like the title.
+        let mut indicator_width = 0;
+        if tab.block_out_from.is_some() {
+            let diameter = (text_area_height * 2 / 5).max(2);
+            let right = x + w - tab_padding_x;
+            if right - diameter > x + tab_padding_x {
+                let radius = f64::from(diameter) / 2.;
+                set_source_color(&cr, fg);
+                cr.arc(
+                    f64::from(right) - radius,
+                    f64::from(y + h / 2),
+                    radius,
+                    0.,
+                    std::f64::consts::TAU,
+                );
+                cr.fill()?;
+                indicator_width = diameter + tab_padding_x.max(diameter / 2);
+            }
+        }
+
+        // Windows that drew something while in the background get a small dot before their
+        // title, like the tmux activity flag.
+        let mut activity_width = 0;
+        if tab.has_activity && !tab.is_focused {
+            let diameter = (text_area_height / 4).max(2);
+            let left = x + tab_padding_x;
+            if left + diameter < x + w - tab_padding_x - indicator_width {
+                let radius = f64::from(diameter) / 2.;
+                set_source_color(&cr, fg);
+                cr.arc(
+                    f64::from(left) + radius,
+                    f64::from(y + h / 2),
+                    radius,
+                    0.,
+                    std::f64::consts::TAU,
+                );
+                cr.fill()?;
+                activity_width = diameter + tab_padding_x.max(diameter);
+            }
+        }
+
         let title = sanitize_title(&tab.title);
-        let text_width = (w - tab_padding_x * 2).max(1);
+        let text_width = (w - tab_padding_x * 2 - indicator_width - activity_width).max(1);
         text_layout.set_width(text_width * pango::SCALE);
         text_layout.set_text(&title);
         let (_tw, th) = text_layout.pixel_size();
-        let text_x = x + tab_padding_x;
-        let text_area_height = (h - padding_y_px * 2).max(1);
+        let text_x = x + tab_padding_x + activity_width;
         let text_y = y + padding_y_px + ((text_area_height - th) / 2).max(0);
 
         cr.save()?;
@@ -307,7 +390,7 @@ pub fn render_tab_bar(
 
         if separator_width_px > 0 && idx + 1 < tab_count {
             set_source_color(&cr, config.separator_color);
-            if layout == Layout::Tabbed {
+            if single_row {
                 cr.rectangle(
                     f64::from(x + w - separator_width_px),
                     f64::from(y),
@@ -328,9 +411,10 @@ pub fn render_tab_bar(
         cursor_x += w;
     }
 
-    let row_count = if layout == Layout::Tabbed { 1 } else { tab_count };
+    let row_count = if single_row { 1 } else { tab_count };
     let extra_height = height_px - row_height_px.saturating_mul(row_count as i32);
-    if extra_height > 0 {
+    // Fill the rounding leftover below the tabs, but not the empty space of a side bar.
+    if extra_height > 0 && extra_height < row_height_px {
         let focused = tabs.iter().find(|tab| tab.is_focused).unwrap_or(&tabs[0]);
         let (bg, _fg, _border) = tab_colors(config, focused, is_active_workspace);
         set_source_color(&cr, bg);
@@ -3,21 +3,25 @@ use std::cell::{Cell, OnceCell, RefCell};
 use niri_config::utils::{Flag, MergeWith as _};
 use niri_config::workspace::WorkspaceName;
 use niri_config::{
-    Config, FloatOrInt, OutputName, Struts, TabIndicatorLength,
-    TabIndicatorPosition, WorkspaceReference,
+    Config, FloatOrInt, OutputName, SafeArea, Struts, TabIndicatorLength, TabIndicatorPosition,
+    WorkspaceReference,
 };
 use insta::assert_snapshot;
 use proptest::prelude::*;
 use proptest_derive::Arbitrary;
+use serde::Deserialize;
 use smithay::output::{Mode, PhysicalProperties, Subpixel};
 use smithay::utils::{Logical, Point, Rectangle, Size};
 
 use super::*;
-use super::container::{ContainerTree, Direction, Layout as ContainerLayout};
+use super::container::{ContainerTree, DetachedNode, Direction, Layout as ContainerLayout};
 use super::tile::Tile;
+use super::workspace::{audit_tile_geometry, GeometryIssue};
 
 mod animations;
 mod fullscreen;
+mod geometry;
+mod scripts;
 
 impl<W: LayoutElement> Default for Layout<W> {
     fn default() -> Self {
@@ -78,18 +82,23 @@ struct TestWindowInner {
 #[derive(Debug, Clone)]
 struct TestWindow(Rc<TestWindowInner>);
 
-#[derive(Debug, Clone, Arbitrary)]
+#[derive(Debug, Clone, Arbitrary, Deserialize)]
 struct TestWindowParams {
     #[proptest(strategy = "1..=5usize")]
     id: usize,
     #[proptest(strategy = "arbitrary_parent_id()")]
+    #[serde(default)]
     parent_id: Option<usize>,
+    #[serde(default)]
     is_floating: bool,
     #[proptest(strategy = "arbitrary_bbox()")]
+    #[serde(default = "scripts::default_bbox", deserialize_with = "scripts::rect")]
     bbox: Rectangle<i32, Logical>,
     #[proptest(strategy = "arbitrary_min_max_size()")]
+    #[serde(skip)]
     min_max_size: (Size<i32, Logical>, Size<i32, Logical>),
     #[proptest(strategy = "prop::option::of(arbitrary_rules())")]
+    #[serde(skip)]
     rules: Option<ResolvedWindowRules>,
 }
 
@@ -312,6 +321,19 @@ impl LayoutElement for TestWindow {
     fn is_urgent(&self) -> bool {
         false
     }
+
+    fn has_activity(&self) -> bool {
+        false
+    }
+
+    fn render_unresponsive_prompt(
+        &self,
+        _renderer: &mut GlesRenderer,
+        _scale: f64,
+        _font: Option<&str>,
+    ) -> Option<TextureBuffer<GlesTexture>> {
+        None
+    }
 }
 
 fn arbitrary_size() -> impl Strategy<Value = Size<i32, Logical>> {
@@ -348,6 +370,22 @@ fn arbitrary_position_change() -> impl Strategy<Value = PositionChange> {
     ]
 }
 
+fn arbitrary_resize_direction() -> impl Strategy<Value = ResizeDirection> {
+    prop_oneof![
+        Just(ResizeDirection::Left),
+        Just(ResizeDirection::Right),
+        Just(ResizeDirection::Up),
+        Just(ResizeDirection::Down),
+    ]
+}
+
+fn arbitrary_resize_amount() -> impl Strategy<Value = ResizeAmount> {
+    prop_oneof![
+        (0..10000).prop_map(ResizeAmount::Fixed),
+        (0f64..200f64).prop_map(ResizeAmount::Proportion),
+    ]
+}
+
 fn arbitrary_min_max() -> impl Strategy<Value = (i32, i32)> {
     prop_oneof![
         Just((0, 0)),
@@ -405,6 +443,13 @@ fn arbitrary_resize_edge() -> impl Strategy<Value = ResizeEdge> {
     ]
 }
 
+fn arbitrary_floating_arrangement() -> impl Strategy<Value = FloatingArrangement> {
+    prop_oneof![
+        Just(FloatingArrangement::Cascade),
+        Just(FloatingArrangement::Grid),
+    ]
+}
+
 fn arbitrary_scale() -> impl Strategy<Value = f64> {
     prop_oneof![Just(1.), Just(1.5), Just(2.),]
 }
@@ -442,7 +487,10 @@ fn arbitrary_mark_mode() -> impl Strategy<Value = MarkMode> {
     ]
 }
 
-#[derive(Debug, Clone, Arbitrary)]
+/// Layout operation for tests.
+///
+/// Ops can also be loaded from JSON, see the `scripts` module.
+#[derive(Debug, Clone, Arbitrary, Deserialize)]
 enum Op {
     AddOutput(#[proptest(strategy = "1..=5usize")] usize),
     AddScaledOutput {
@@ -451,6 +499,7 @@ enum Op {
         #[proptest(strategy = "arbitrary_scale()")]
         scale: f64,
         #[proptest(strategy = "prop::option::of(arbitrary_layout_part().prop_map(Box::new))")]
+        #[serde(skip)]
         layout_config: Option<Box<niri_config::LayoutPart>>,
     },
     RemoveOutput(#[proptest(strategy = "1..=5usize")] usize),
@@ -459,6 +508,7 @@ enum Op {
         #[proptest(strategy = "1..=5usize")]
         id: usize,
         #[proptest(strategy = "prop::option::of(arbitrary_layout_part().prop_map(Box::new))")]
+        #[serde(skip)]
         layout_config: Option<Box<niri_config::LayoutPart>>,
     },
     AddNamedWorkspace {
@@ -467,6 +517,7 @@ enum Op {
         #[proptest(strategy = "prop::option::of(1..=5usize)")]
         output_name: Option<usize>,
         #[proptest(strategy = "prop::option::of(arbitrary_layout_part().prop_map(Box::new))")]
+        #[serde(skip)]
         layout_config: Option<Box<niri_config::LayoutPart>>,
     },
     UnnameWorkspace {
@@ -477,6 +528,7 @@ enum Op {
         #[proptest(strategy = "1..=5usize")]
         ws_name: usize,
         #[proptest(strategy = "prop::option::of(arbitrary_layout_part().prop_map(Box::new))")]
+        #[serde(skip)]
         layout_config: Option<Box<niri_config::LayoutPart>>,
     },
     AddWindow {
@@ -546,7 +598,11 @@ enum Op {
     },
     ConsumeWindowIntoColumn,
     ExpelWindowFromColumn,
-    SwapWindowInDirection(#[proptest(strategy = "arbitrary_scroll_direction()")] ScrollDirection),
+    SwapWindowInDirection(
+        #[proptest(strategy = "arbitrary_scroll_direction()")]
+        #[serde(deserialize_with = "scripts::scroll_direction")]
+        ScrollDirection,
+    ),
     ToggleColumnTabbedDisplay,
     SetColumnDisplay(#[proptest(strategy = "arbitrary_column_display()")] ColumnDisplay),
     CenterColumn,
@@ -650,6 +706,15 @@ enum Op {
         #[proptest(strategy = "proptest::option::of(1..=5usize)")]
         id: Option<usize>,
     },
+    ResizeWindowEdge {
+        #[proptest(strategy = "proptest::option::of(1..=5usize)")]
+        id: Option<usize>,
+        #[proptest(strategy = "arbitrary_resize_direction()")]
+        direction: ResizeDirection,
+        #[proptest(strategy = "arbitrary_resize_amount()")]
+        amount: ResizeAmount,
+        grow: bool,
+    },
     ExpandColumnToAvailableWidth,
     ToggleWindowFloating {
         #[proptest(strategy = "proptest::option::of(1..=5usize)")]
@@ -663,6 +728,7 @@ enum Op {
     FocusFloating,
     FocusTiling,
     SwitchFocusFloatingTiling,
+    ArrangeFloating(#[proptest(strategy = "arbitrary_floating_arrangement()")] FloatingArrangement),
     MoveFloatingWindow {
         #[proptest(strategy = "proptest::option::of(1..=5usize)")]
         id: Option<usize>,
@@ -682,6 +748,7 @@ enum Op {
         #[proptest(strategy = "1..=5usize")]
         id: usize,
         #[proptest(strategy = "proptest::option::of(arbitrary_size())")]
+        #[serde(default, deserialize_with = "scripts::size")]
         size: Option<Size<i32, Logical>>,
     },
     Communicate(#[proptest(strategy = "1..=5usize")] usize),
@@ -772,6 +839,7 @@ enum Op {
         #[proptest(strategy = "1..=5usize")]
         window: usize,
         #[proptest(strategy = "arbitrary_resize_edge()")]
+        #[serde(deserialize_with = "scripts::resize_edge")]
         edges: ResizeEdge,
     },
     InteractiveResizeUpdate {
@@ -789,6 +857,7 @@ enum Op {
     ToggleOverview,
     UpdateConfig {
         #[proptest(strategy = "arbitrary_layout_part().prop_map(Box::new)")]
+        #[serde(skip)]
         layout_config: Box<niri_config::LayoutPart>,
     },
     // Container tree operations (i3-like)
@@ -801,11 +870,19 @@ enum Op {
     SetLayoutTabbed,
     SetLayoutStacked,
     ToggleSplitLayout,
+    SetTabBarPosition(
+        #[proptest(strategy = "arbitrary_tab_indicator_position()")]
+        #[serde(deserialize_with = "scripts::tab_indicator_position")]
+        TabIndicatorPosition,
+    ),
+    RecordLayoutChange,
+    UndoLayoutChange,
     // Mark operations
     MarkFocused {
         #[proptest(strategy = "1..=3usize")]
         mark_id: usize,
         #[proptest(strategy = "arbitrary_mark_mode()")]
+        #[serde(deserialize_with = "scripts::mark_mode")]
         mode: MarkMode,
     },
     // Scratchpad operations
@@ -814,6 +891,8 @@ enum Op {
         id: Option<usize>,
     },
     ScratchpadShow,
+    MinimizeWindow(#[proptest(strategy = "1..=5usize")] usize),
+    UnminimizeWindow(#[proptest(strategy = "1..=5usize")] usize),
 }
 
 impl Op {
@@ -1407,6 +1486,15 @@ impl Op {
                 let id = id.filter(|id| layout.has_window(id));
                 layout.reset_window_height(id.as_ref());
             }
+            Op::ResizeWindowEdge {
+                id,
+                direction,
+                amount,
+                grow,
+            } => {
+                let id = id.filter(|id| layout.has_window(id));
+                layout.resize_window_edge(id.as_ref(), direction, amount, grow);
+            }
             Op::ExpandColumnToAvailableWidth => layout.expand_column_to_available_width(),
             Op::ToggleWindowFloating { id } => {
                 let id = id.filter(|id| layout.has_window(id));
@@ -1425,6 +1513,9 @@ impl Op {
             Op::SwitchFocusFloatingTiling => {
                 layout.switch_focus_floating_tiling();
             }
+            Op::ArrangeFloating(arrangement) => {
+                layout.arrange_floating(arrangement);
+            }
             Op::MoveFloatingWindow { id, x, y, animate } => {
                 let id = id.filter(|id| layout.has_window(id));
                 layout.move_floating_window(id.as_ref(), x, y, animate);
@@ -1696,6 +1787,9 @@ impl Op {
             Op::SetLayoutTabbed => layout.set_layout_mode(ContainerLayout::Tabbed),
             Op::SetLayoutStacked => layout.set_layout_mode(ContainerLayout::Stacked),
             Op::ToggleSplitLayout => layout.toggle_split_layout(),
+            Op::SetTabBarPosition(position) => layout.set_tab_bar_position(position),
+            Op::RecordLayoutChange => layout.record_layout_change(),
+            Op::UndoLayoutChange => layout.undo_layout_change(),
             // Mark operations
             Op::MarkFocused { mark_id, mode } => {
                 layout.mark_focused(format!("mark{mark_id}"), mode);
@@ -1706,6 +1800,8 @@ impl Op {
                 layout.move_window_to_scratchpad(id.as_ref());
             }
             Op::ScratchpadShow => layout.scratchpad_show(),
+            Op::MinimizeWindow(id) => layout.minimize_window(&id),
+            Op::UnminimizeWindow(id) => layout.unminimize_window(&id),
         }
     }
 }
@@ -2155,6 +2251,217 @@ fn sticky_moves_across_workspaces_on_output() {
     assert!(workspace.has_window(&id));
     assert!(!window_layout(&layout, id).is_sticky);
 }
+
+#[test]
+fn always_on_top_stays_above_other_floating_windows() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                ..TestWindowParams::new(1)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                ..TestWindowParams::new(2)
+            },
+        },
+    ];
+    let mut layout = check_ops(ops);
+    let topmost = |layout: &Layout<TestWindow>| {
+        let ws = layout.active_workspace().unwrap();
+        let (tile, _, _) = ws.tiles_with_render_positions().next().unwrap();
+        *tile.window().id()
+    };
+    assert_eq!(topmost(&layout), 2);
+
+    layout.toggle_window_always_on_top(Some(&1));
+    assert!(window_layout(&layout, 1).is_always_on_top);
+    assert_eq!(topmost(&layout), 1);
+
+    // Focusing another floating window doesn't raise it above.
+    layout.activate_window(&2);
+    assert_eq!(topmost(&layout), 1);
+
+    layout.toggle_window_always_on_top(Some(&1));
+    assert!(!window_layout(&layout, 1).is_always_on_top);
+    layout.activate_window(&2);
+    assert_eq!(topmost(&layout), 2);
+}
+
+#[test]
+fn raise_and_lower_keep_focus() {
+    let floating = |id| Op::AddWindow {
+        params: TestWindowParams {
+            is_floating: true,
+            ..TestWindowParams::new(id)
+        },
+    };
+    let ops = [Op::AddOutput(1), floating(1), floating(2), floating(3)];
+    let mut layout = check_ops(ops);
+    let stack_pos =
+        |layout: &Layout<TestWindow>, id| window_layout(layout, id).pos_in_floating_stack;
+    assert_eq!(stack_pos(&layout, 3), Some(1));
+    assert_eq!(stack_pos(&layout, 1), Some(3));
+
+    layout.raise_window(Some(&1));
+    assert_eq!(stack_pos(&layout, 1), Some(1));
+    assert_eq!(stack_pos(&layout, 3), Some(2));
+    assert_eq!(layout.focus().map(|win| *win.id()), Some(3));
+
+    layout.lower_window(None);
+    assert_eq!(stack_pos(&layout, 3), Some(3));
+    assert_eq!(layout.focus().map(|win| *win.id()), Some(3));
+}
+
+#[test]
+fn follow_focus_moves_to_focused_output() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output1 = make_test_output("output1");
+    let output2 = make_test_output("output2");
+    layout.add_output(output1.clone(), None);
+    layout.add_output(output2.clone(), None);
+
+    let params = TestWindowParams::new(1);
+    let id = params.id;
+    layout.add_window(
+        TestWindow::new(params),
+        AddWindowTarget::Auto,
+        None,
+        None,
+        false,
+        false,
+        ActivateWindow::Yes,
+    );
+
+    // Tiled windows become floating when they start following the focus.
+    layout.toggle_window_follow_focus(None);
+    assert_eq!(layout.follow_focus_window(), Some(&id));
+    assert!(window_layout(&layout, id).is_following_focus);
+
+    layout.focus_output(&output2);
+    layout.refresh(true);
+    let mon = layout.active_monitor_ref().unwrap();
+    assert_eq!(mon.output(), &output2);
+    assert!(mon.has_sticky_window(&id));
+
+    layout.focus_output(&output1);
+    layout.refresh(true);
+    assert!(layout.active_monitor_ref().unwrap().has_sticky_window(&id));
+
+    // The same action stops following and puts the window back on the workspace.
+    layout.toggle_window_follow_focus(Some(&id));
+    assert_eq!(layout.follow_focus_window(), None);
+    assert!(!window_layout(&layout, id).is_following_focus);
+    assert!(!window_layout(&layout, id).is_sticky);
+    let workspace = layout.active_workspace().expect("active workspace");
+    assert!(workspace.has_window(&id));
+    assert!(workspace.is_floating(&id));
+}
+
+#[test]
+fn follow_focus_differs_from_sticky() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output1 = make_test_output("output1");
+    let output2 = make_test_output("output2");
+    layout.add_output(output1.clone(), None);
+    layout.add_output(output2.clone(), None);
+
+    for id in [1, 2] {
+        layout.add_window(
+            TestWindow::new(TestWindowParams::new(id)),
+            AddWindowTarget::Auto,
+            None,
+            None,
+            false,
+            true,
+            ActivateWindow::Yes,
+        );
+    }
+
+    layout.toggle_window_sticky(Some(&1));
+    layout.toggle_window_follow_focus(Some(&2));
+
+    let sticky = window_layout(&layout, 1);
+    assert!(sticky.is_sticky);
+    assert!(!sticky.is_following_focus);
+    assert!(!sticky.is_always_on_top);
+
+    let following = window_layout(&layout, 2);
+    assert!(!following.is_sticky);
+    assert!(following.is_following_focus);
+    assert!(following.is_always_on_top);
+
+    // Only the following window moves to the newly focused output.
+    layout.focus_output(&output2);
+    layout.refresh(true);
+    let mon = layout.active_monitor_ref().unwrap();
+    assert_eq!(mon.output(), &output2);
+    assert!(mon.has_sticky_window(&2));
+    assert!(!mon.has_sticky_window(&1));
+
+    // Making the following window sticky pins it to its current output.
+    layout.toggle_window_sticky(Some(&2));
+    assert_eq!(layout.follow_focus_window(), None);
+    let pinned = window_layout(&layout, 2);
+    assert!(pinned.is_sticky);
+    assert!(!pinned.is_following_focus);
+    assert!(!pinned.is_always_on_top);
+
+    layout.focus_output(&output1);
+    layout.refresh(true);
+    assert!(!layout.active_monitor_ref().unwrap().has_sticky_window(&2));
+}
+
+#[test]
+fn picture_in_picture_is_sticky_on_top_in_corner() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output = make_test_output("output1");
+    layout.add_output(output.clone(), None);
+
+    let params = TestWindowParams::new(1);
+    let id = params.id;
+    layout.add_window(
+        TestWindow::new(params),
+        AddWindowTarget::Auto,
+        None,
+        None,
+        false,
+        false,
+        ActivateWindow::Yes,
+    );
+
+    layout.set_window_picture_in_picture(&id, Corner::TopLeft);
+    assert!(layout.is_picture_in_picture(&id));
+    let win = window_layout(&layout, id);
+    assert!(win.is_sticky);
+    assert!(win.is_always_on_top);
+    assert_eq!(layout.picture_in_picture_corner(&id), Some(Corner::TopLeft));
+
+    layout.set_window_picture_in_picture(&id, Corner::BottomRight);
+    assert_eq!(
+        layout.picture_in_picture_corner(&id),
+        Some(Corner::BottomRight)
+    );
+
+    // Going back leaves a regular floating window on the active workspace.
+    layout.unset_window_picture_in_picture(&id);
+    assert!(!layout.is_picture_in_picture(&id));
+    let win = window_layout(&layout, id);
+    assert!(!win.is_sticky);
+    assert!(!win.is_always_on_top);
+    let workspace = layout.active_workspace().expect("active workspace");
+    assert!(workspace.is_floating(&id));
+}
+
 #[test]
 fn scratchpad_show_hides_visible_then_shows_next() {
     let options = Options::from_config(&Config::default());
@@ -3032,6 +3339,133 @@ fn move_workspace_to_output() {
     assert!(monitors[1].workspaces[0].has_windows());
 }
 
+#[test]
+fn keep_workspace_rule_keeps_empty_workspace() {
+    let rules = ResolvedWindowRules {
+        keep_workspace: Some(true),
+        ..ResolvedWindowRules::default()
+    };
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams {
+                rules: Some(rules),
+                ..TestWindowParams::new(0)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::MoveWindowToWorkspaceDown(true),
+        Op::CloseWindow(0),
+    ];
+
+    let layout = check_ops(ops);
+
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+
+    // The empty workspace where the window was stays, unlike a regular empty workspace.
+    assert_eq!(monitors[0].active_workspace_idx, 1);
+    assert_eq!(monitors[0].workspaces.len(), 3);
+    assert!(!monitors[0].workspaces[0].has_windows());
+    assert!(monitors[0].workspaces[0].has_windows_or_name());
+}
+
+#[test]
+fn kept_workspace_is_cleaned_up_after_reuse() {
+    let rules = ResolvedWindowRules {
+        keep_workspace: Some(true),
+        ..ResolvedWindowRules::default()
+    };
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams {
+                rules: Some(rules),
+                ..TestWindowParams::new(0)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::MoveWindowToWorkspaceDown(true),
+        Op::CloseWindow(0),
+        // Reuse the kept workspace for a regular window, then clear it again.
+        Op::FocusWorkspaceUp,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::CloseWindow(2),
+        Op::FocusWorkspaceDown,
+        Op::CompleteAnimations,
+    ];
+
+    let layout = check_ops(ops);
+
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+
+    assert_eq!(monitors[0].active_workspace_idx, 0);
+    assert_eq!(monitors[0].workspaces.len(), 2);
+    assert!(monitors[0].workspaces[0].has_windows());
+}
+
+#[test]
+fn relative_workspace_references_wrap_around() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspace(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+    ];
+
+    let layout = check_ops(ops);
+    let resolve = |reference| layout.resolve_relative_workspace_ref(&reference);
+
+    // Workspaces: [1], [2] (active), [].
+    assert_eq!(resolve(WorkspaceReference::Relative(1)), Some(2));
+    assert_eq!(resolve(WorkspaceReference::Relative(2)), Some(0));
+    assert_eq!(resolve(WorkspaceReference::Relative(-4)), Some(0));
+    assert_eq!(resolve(WorkspaceReference::NextNonEmpty), Some(0));
+    assert_eq!(resolve(WorkspaceReference::PrevNonEmpty), Some(0));
+    assert_eq!(resolve(WorkspaceReference::Index(1)), None);
+}
+
+#[test]
+fn occluded_windows_include_background_tabs_and_other_workspaces() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::SetLayoutTabbed,
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::MoveWindowToWorkspaceDown(false),
+    ];
+
+    let layout = check_ops(ops);
+
+    let occluded = layout.occluded_windows();
+    let focused = *layout.focus().unwrap().id();
+    assert!(occluded.contains(&3));
+    assert!(!occluded.contains(&focused));
+    assert_eq!(occluded.len(), 2);
+}
+
 #[test]
 fn open_right_of_on_different_workspace() {
     let ops = [
@@ -3431,7 +3865,7 @@ fn interactive_move_onto_first_empty_workspace() {
 }
 
 #[test]
-fn output_active_workspace_is_preserved() {
+fn overview_interactive_move_onto_other_workspace() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
@@ -3441,24 +3875,42 @@ fn output_active_workspace_is_preserved() {
         Op::AddWindow {
             params: TestWindowParams::new(2),
         },
-        Op::RemoveOutput(1),
-        Op::AddOutput(1),
+        Op::FocusWorkspaceUp,
+        Op::ToggleOverview,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::InteractiveMoveBegin {
+            window: 1,
+            output_idx: 1,
+            px: 640.,
+            py: 360.,
+        },
+        // At 0.5 zoom, the second workspace starts at y = 576.
+        Op::InteractiveMoveUpdate {
+            window: 1,
+            dx: 0.,
+            dy: 290.,
+            output_idx: 1,
+            px: 640.,
+            py: 650.,
+        },
+        Op::InteractiveMoveEnd { window: 1 },
     ];
 
     let layout = check_ops(ops);
-
-    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
-        unreachable!()
+    let ws_idx = |id: usize| {
+        let (_, idx, _) = layout
+            .workspaces()
+            .find(|(_, _, ws)| ws.has_window(&id))
+            .unwrap();
+        idx
     };
-
-    assert_eq!(monitors[0].active_workspace_idx, 1);
+    assert_eq!(ws_idx(1), ws_idx(2));
 }
 
 #[test]
-fn output_active_workspace_is_preserved_with_other_outputs() {
+fn overview_interactive_move_onto_new_workspace() {
     let ops = [
         Op::AddOutput(1),
-        Op::AddOutput(2),
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
@@ -3466,14 +3918,131 @@ fn output_active_workspace_is_preserved_with_other_outputs() {
         Op::AddWindow {
             params: TestWindowParams::new(2),
         },
-        Op::RemoveOutput(1),
-        Op::AddOutput(1),
+        Op::FocusWorkspaceUp,
+        Op::ToggleOverview,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::InteractiveMoveBegin {
+            window: 1,
+            output_idx: 1,
+            px: 640.,
+            py: 360.,
+        },
+        // Above the first workspace, which starts at y = 180 at 0.5 zoom.
+        Op::InteractiveMoveUpdate {
+            window: 1,
+            dx: 0.,
+            dy: -260.,
+            output_idx: 1,
+            px: 640.,
+            py: 100.,
+        },
+        Op::InteractiveMoveEnd { window: 1 },
     ];
 
     let layout = check_ops(ops);
-
-    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
-        unreachable!()
+    let ws_idx = |id: usize| {
+        let (_, idx, _) = layout
+            .workspaces()
+            .find(|(_, _, ws)| ws.has_window(&id))
+            .unwrap();
+        idx
+    };
+    assert_eq!(ws_idx(1), 0);
+    assert_ne!(ws_idx(1), ws_idx(2));
+}
+
+#[test]
+fn overview_drop_zones_keep_their_size_on_screen() {
+    use super::monitor::{InsertPosition, SplitIndicator};
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusWorkspaceUp,
+        Op::ToggleOverview,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::InteractiveMoveBegin {
+            window: 1,
+            output_idx: 1,
+            px: 640.,
+            py: 360.,
+        },
+        // 30 px below the top of the second workspace on screen, which is 60 px inside the
+        // workspace at 0.5 zoom: past the unscaled top border zone, within the scaled one.
+        Op::InteractiveMoveUpdate {
+            window: 1,
+            dx: 0.,
+            dy: 246.,
+            output_idx: 1,
+            px: 640.,
+            py: 606.,
+        },
+    ];
+
+    let layout = check_ops(ops);
+    let MonitorSet::Normal { monitors, .. } = &layout.monitor_set else {
+        unreachable!()
+    };
+    let hint = monitors[0].insert_hint.as_ref().unwrap();
+    assert_eq!(
+        hint.position,
+        InsertPosition::SplitRoot {
+            direction: Direction::Up,
+            indicator: SplitIndicator::LayoutBorder,
+        }
+    );
+}
+
+#[test]
+fn output_active_workspace_is_preserved() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::RemoveOutput(1),
+        Op::AddOutput(1),
+    ];
+
+    let layout = check_ops(ops);
+
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+
+    assert_eq!(monitors[0].active_workspace_idx, 1);
+}
+
+#[test]
+fn output_active_workspace_is_preserved_with_other_outputs() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddOutput(2),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::RemoveOutput(1),
+        Op::AddOutput(1),
+    ];
+
+    let layout = check_ops(ops);
+
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
     };
 
     assert_eq!(monitors[1].active_workspace_idx, 1);
@@ -4197,12 +4766,338 @@ fn preset_column_width_fixed_correct_with_border() {
     let bordered_width = win.requested_size().unwrap().w;
     assert!(bordered_width <= base_width);
 
-    // Preset widths are ignored in i3-style tiling, so toggling doesn't change size.
+    // A lone window fills its split, so toggling doesn't change size.
     layout.toggle_width(true);
     let win = layout.windows().next().unwrap().1;
     assert_eq!(win.requested_size().unwrap().w, bordered_width);
 }
 
+#[test]
+fn preset_column_width_cycles_share_within_split() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output = make_test_output("output-test");
+    layout.add_output(output.clone(), None);
+
+    for id in [1, 2] {
+        layout.add_window(
+            TestWindow::new(TestWindowParams::new(id)),
+            AddWindowTarget::Auto,
+            None,
+            None,
+            false,
+            false,
+            ActivateWindow::Yes,
+        );
+    }
+
+    let share = |layout: &Layout<TestWindow>| {
+        let w1 = f64::from(requested_width(layout, 1));
+        let w2 = f64::from(requested_width(layout, 2));
+        w2 / (w1 + w2)
+    };
+    assert!((share(&layout) - 0.5).abs() < 0.02);
+
+    // The default presets are 1/3, 1/2 and 2/3 of the split.
+    layout.toggle_width(true);
+    assert!((share(&layout) - 2. / 3.).abs() < 0.02);
+
+    layout.toggle_width(true);
+    assert!((share(&layout) - 1. / 3.).abs() < 0.02);
+
+    layout.toggle_width(false);
+    assert!((share(&layout) - 2. / 3.).abs() < 0.02);
+}
+
+#[test]
+fn resize_grow_and_shrink_move_one_edge() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output = make_test_output("output-test");
+    layout.add_output(output.clone(), None);
+
+    for id in [1, 2, 3] {
+        layout.add_window(
+            TestWindow::new(TestWindowParams::new(id)),
+            AddWindowTarget::Auto,
+            None,
+            None,
+            false,
+            false,
+            ActivateWindow::Yes,
+        );
+    }
+
+    let widths =
+        |layout: &Layout<TestWindow>| [1, 2, 3].map(|id| f64::from(requested_width(layout, id)));
+    let [w1, w2, w3] = widths(&layout);
+    let total = w1 + w2 + w3;
+
+    // Growing the left edge of the middle window takes space from its left neighbor only.
+    layout.resize_window_edge(
+        Some(&2),
+        ResizeDirection::Left,
+        ResizeAmount::Proportion(10.),
+        true,
+    );
+    let [new_w1, new_w2, new_w3] = widths(&layout);
+    assert!((new_w1 - (w1 - total * 0.1)).abs() < 2.);
+    assert!((new_w2 - (w2 + total * 0.1)).abs() < 2.);
+    assert!((new_w3 - w3).abs() < 1.);
+
+    // Shrinking its right edge gives the space to the right neighbor.
+    layout.resize_window_edge(
+        Some(&2),
+        ResizeDirection::Right,
+        ResizeAmount::Fixed(50),
+        false,
+    );
+    let [_, newer_w2, newer_w3] = widths(&layout);
+    assert!((newer_w2 - (new_w2 - 50.)).abs() < 2.);
+    assert!((newer_w3 - (new_w3 + 50.)).abs() < 2.);
+
+    // There's nothing to the right of the last window.
+    let before = widths(&layout);
+    layout.resize_window_edge(
+        Some(&3),
+        ResizeDirection::Right,
+        ResizeAmount::Fixed(50),
+        true,
+    );
+    assert_eq!(widths(&layout), before);
+}
+
+#[test]
+fn focused_window_shares_follow_width_changes() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output = make_test_output("output-test");
+    layout.add_output(output.clone(), None);
+    assert_eq!(layout.focused_window_shares(), (None, None));
+
+    for id in [1, 2] {
+        layout.add_window(
+            TestWindow::new(TestWindowParams::new(id)),
+            AddWindowTarget::Auto,
+            None,
+            None,
+            false,
+            false,
+            ActivateWindow::Yes,
+        );
+    }
+
+    let (width, height) = layout.focused_window_shares();
+    assert!((width.unwrap() - 0.5).abs() < 1e-6);
+    assert_eq!(height, None);
+
+    layout.set_window_width(None, SizeChange::AdjustProportion(10.));
+    let (width, _) = layout.focused_window_shares();
+    assert!((width.unwrap() - 0.6).abs() < 1e-6);
+}
+
+#[test]
+fn set_container_percents_scales_shares() {
+    let options = Options::from_config(&Config::default());
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output = make_test_output("output-test");
+    layout.add_output(output.clone(), None);
+
+    for id in [1, 2, 3] {
+        layout.add_window(
+            TestWindow::new(TestWindowParams::new(id)),
+            AddWindowTarget::Auto,
+            None,
+            None,
+            false,
+            false,
+            ActivateWindow::Yes,
+        );
+    }
+
+    let percents = layout
+        .container_percents(ContainerTarget::Window(Some(&2)))
+        .unwrap();
+    assert_eq!(percents.path, Vec::<usize>::new());
+    assert_eq!(percents.percents.len(), 3);
+
+    layout
+        .set_container_percents(ContainerTarget::Path(&[]), &[2., 1., 1.])
+        .unwrap();
+    let percents = layout
+        .container_percents(ContainerTarget::Window(None))
+        .unwrap();
+    for (percent, expected) in percents.percents.iter().zip([0.5, 0.25, 0.25]) {
+        assert!((percent - expected).abs() < 1e-6);
+    }
+
+    // Wrong number of shares, and a share that would be too small.
+    assert!(layout
+        .set_container_percents(ContainerTarget::Path(&[]), &[1., 1.])
+        .is_err());
+    assert!(layout
+        .set_container_percents(ContainerTarget::Path(&[]), &[100., 1., 1.])
+        .is_err());
+    assert!(layout
+        .container_percents(ContainerTarget::Path(&[5]))
+        .is_err());
+}
+
+#[test]
+fn auto_split_alternates_along_the_longer_side() {
+    let options = Options {
+        layout: niri_config::Layout {
+            auto_split: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusWindow(2),
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+    ];
+    let layout = check_ops_with_options(options, ops);
+
+    // The wide output splits horizontally, then the tall half splits vertically.
+    let root = layout
+        .container_percents(ContainerTarget::Window(Some(&1)))
+        .unwrap();
+    assert_eq!(root.path, Vec::<usize>::new());
+    assert_eq!(root.layout, niri_ipc::LayoutTreeLayout::SplitH);
+
+    let nested = layout
+        .container_percents(ContainerTarget::Window(Some(&3)))
+        .unwrap();
+    assert_eq!(nested.path, vec![1]);
+    assert_eq!(nested.layout, niri_ipc::LayoutTreeLayout::SplitV);
+}
+
+#[test]
+fn focused_tabs_cover_tab_siblings_only() {
+    let options = Options {
+        layout: niri_config::Layout {
+            auto_split: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusWindow(2),
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::FocusWindow(3),
+    ];
+    let mut layout = check_ops_with_options(options, ops);
+
+    // Windows 2 and 3 share the nested split container, which doesn't count.
+    assert!(!layout.is_in_focused_tabs(&2));
+    assert!(!layout.is_in_focused_tabs(&1));
+
+    check_ops_on_layout(&mut layout, [Op::SetLayoutTabbed]);
+    assert!(layout.is_in_focused_tabs(&2));
+    assert!(!layout.is_in_focused_tabs(&1));
+
+    check_ops_on_layout(&mut layout, [Op::SetLayoutStacked]);
+    assert!(layout.is_in_focused_tabs(&2));
+}
+
+#[test]
+fn keep_gaps_keeps_indices_of_workspaces_below() {
+    let ops = || {
+        [
+            Op::AddOutput(1),
+            Op::AddWindow {
+                params: TestWindowParams::new(1),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                params: TestWindowParams::new(2),
+            },
+            Op::FocusWorkspaceDown,
+            Op::AddWindow {
+                params: TestWindowParams::new(3),
+            },
+            Op::CompleteAnimations,
+            Op::CloseWindow(2),
+        ]
+    };
+    let workspace_count = |layout: Layout<TestWindow>| {
+        let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+            unreachable!()
+        };
+        monitors[0].workspaces.len()
+    };
+
+    assert_eq!(workspace_count(check_ops(ops())), 3);
+
+    let options = Options {
+        layout: niri_config::Layout {
+            workspace_indices: niri_config::WorkspaceIndices::KeepGaps,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(workspace_count(check_ops_with_options(options, ops())), 4);
+}
+
+#[test]
+fn switching_to_fill_gaps_closes_gaps() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::CompleteAnimations,
+        Op::CloseWindow(2),
+    ];
+
+    let options = Options {
+        layout: niri_config::Layout {
+            workspace_indices: niri_config::WorkspaceIndices::KeepGaps,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut layout = check_ops_with_options(options, ops);
+    layout.update_options(Options::default());
+    layout.verify_invariants();
+
+    let MonitorSet::Normal { monitors, .. } = layout.monitor_set else {
+        unreachable!()
+    };
+    assert_eq!(monitors[0].workspaces.len(), 3);
+}
+
 #[test]
 fn preset_column_width_reset_after_set_width() {
     let ops = [
@@ -4484,24 +5379,273 @@ fn tabs_with_different_border() {
 }
 
 #[test]
-fn expel_pending_left_from_fullscreen_tabbed_column() {
+fn undo_layout_change_restores_tree() {
     let ops = [
         Op::AddOutput(1),
         Op::AddWindow {
             params: TestWindowParams::new(1),
         },
-        Op::FullscreenWindow(1),
-        Op::Communicate(1),
-        // 1 is now fullscreen, view_offset_to_restore is set.
-        Op::ToggleColumnTabbedDisplay,
         Op::AddWindow {
             params: TestWindowParams::new(2),
         },
-        Op::ConsumeOrExpelWindowLeft { id: Some(2) },
-        // 2 is consumed into a fullscreen column, fullscreen is requested but not applied.
-        //
-        // Now, get it back out while keeping it focused.
-        //
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+    ];
+    let mut layout = check_ops(ops);
+    let tree = |layout: &Layout<TestWindow>| layout.active_workspace().unwrap().tree_template();
+    let initial = tree(&layout);
+
+    let ops = [
+        Op::RecordLayoutChange,
+        Op::SplitVertical,
+        Op::RecordLayoutChange,
+        Op::MoveColumnLeft,
+        // Undo skips over records of the current tree.
+        Op::RecordLayoutChange,
+    ];
+    check_ops_on_layout(&mut layout, ops);
+    assert_ne!(tree(&layout), initial);
+
+    check_ops_on_layout(&mut layout, [Op::UndoLayoutChange, Op::UndoLayoutChange]);
+    assert_eq!(tree(&layout), initial);
+
+    // Nothing left to undo.
+    check_ops_on_layout(&mut layout, [Op::UndoLayoutChange]);
+    assert_eq!(tree(&layout), initial);
+}
+
+#[test]
+fn unminimize_restores_tree_position() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::SplitVertical,
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+    ];
+    let mut layout = check_ops(ops);
+    let tree = |layout: &Layout<TestWindow>| layout.active_workspace().unwrap().tree_template();
+    let initial = tree(&layout);
+
+    check_ops_on_layout(&mut layout, [Op::MinimizeWindow(2)]);
+    assert!(layout.is_minimized(&2));
+    assert!(layout.has_window(&2));
+    assert!(!layout.active_workspace().unwrap().has_window(&2));
+    assert_ne!(layout.focus().map(|win| win.id()), Some(&2));
+
+    check_ops_on_layout(&mut layout, [Op::UnminimizeWindow(2)]);
+    assert!(!layout.is_minimized(&2));
+    assert_eq!(tree(&layout), initial);
+    assert_eq!(layout.focus().map(|win| win.id()), Some(&2));
+
+    // Activating a minimized window also brings it back.
+    check_ops_on_layout(&mut layout, [Op::MinimizeWindow(3)]);
+    layout.activate_window(&3);
+    assert!(!layout.is_minimized(&3));
+    assert_eq!(tree(&layout), initial);
+}
+
+#[test]
+fn focus_follows_mouse_limited_to_tiled_windows() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                ..TestWindowParams::new(2)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                rules: Some(ResolvedWindowRules {
+                    focus_follows_mouse: Some(true),
+                    ..ResolvedWindowRules::default()
+                }),
+                ..TestWindowParams::new(3)
+            },
+        },
+    ];
+
+    let layout = check_ops(ops);
+
+    let tiled = Some(FocusFollowsMouseWindows::Tiled);
+    assert!(layout.focus_follows_mouse_applies_to(&1, tiled));
+    assert!(!layout.focus_follows_mouse_applies_to(&2, tiled));
+    // The window rule overrides the global setting.
+    assert!(layout.focus_follows_mouse_applies_to(&3, tiled));
+
+    let floating = Some(FocusFollowsMouseWindows::Floating);
+    assert!(!layout.focus_follows_mouse_applies_to(&1, floating));
+    assert!(layout.focus_follows_mouse_applies_to(&2, floating));
+    assert!(layout.focus_follows_mouse_applies_to(&1, None));
+}
+
+#[test]
+fn safe_area_shrinks_working_area_and_fullscreen() {
+    let options = Options {
+        layout: niri_config::Layout {
+            safe_area: SafeArea {
+                left: FloatOrInt(10.),
+                right: FloatOrInt(0.),
+                top: FloatOrInt(40.),
+                bottom: FloatOrInt(0.),
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+
+    let output = make_test_output("output-test");
+    layout.add_output(output.clone(), None);
+
+    let params = TestWindowParams::new(1);
+    let id = params.id;
+    layout.add_window(
+        TestWindow::new(params),
+        AddWindowTarget::Auto,
+        None,
+        None,
+        false,
+        false,
+        ActivateWindow::Yes,
+    );
+
+    let workspace = layout.active_workspace().expect("active workspace");
+    assert_eq!(
+        workspace.working_area(),
+        Rectangle::new(Point::new(10., 40.), Size::from((1270., 680.)))
+    );
+
+    layout.set_fullscreen(&id, true);
+    let size = layout
+        .windows()
+        .find(|(_, win)| *win.id() == id)
+        .and_then(|(_, win)| win.requested_size())
+        .expect("expected requested size");
+    assert_eq!(size, Size::from((1270, 680)));
+}
+
+#[test]
+fn dnd_edge_hover_switches_workspace() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::DndUpdate {
+            output_idx: 1,
+            px: 640.,
+            py: 719.,
+        },
+        Op::AdvanceAnimations { msec_delta: 0 },
+        Op::AdvanceAnimations { msec_delta: 300 },
+    ];
+    let mut layout = check_ops(ops);
+    let active_idx =
+        |layout: &Layout<TestWindow>| layout.active_monitor_ref().unwrap().active_workspace_idx();
+    // Not yet past the delay.
+    assert_eq!(active_idx(&layout), 0);
+
+    check_ops_on_layout(&mut layout, [Op::AdvanceAnimations { msec_delta: 300 }]);
+    assert_eq!(active_idx(&layout), 1);
+
+    // Moving away from the edge and back restarts the delay.
+    let ops = [
+        Op::DndUpdate {
+            output_idx: 1,
+            px: 640.,
+            py: 360.,
+        },
+        Op::AdvanceAnimations { msec_delta: 0 },
+        Op::DndUpdate {
+            output_idx: 1,
+            px: 640.,
+            py: 0.,
+        },
+        Op::AdvanceAnimations { msec_delta: 0 },
+        Op::AdvanceAnimations { msec_delta: 300 },
+    ];
+    check_ops_on_layout(&mut layout, ops);
+    assert_eq!(active_idx(&layout), 1);
+
+    check_ops_on_layout(&mut layout, [Op::AdvanceAnimations { msec_delta: 300 }]);
+    assert_eq!(active_idx(&layout), 0);
+}
+
+#[test]
+fn struts_add_up_with_safe_area() {
+    let requested_size = |struts: Struts| {
+        let options = Options {
+            layout: niri_config::Layout {
+                safe_area: SafeArea {
+                    left: FloatOrInt(10.),
+                    right: FloatOrInt(0.),
+                    top: FloatOrInt(40.),
+                    bottom: FloatOrInt(0.),
+                },
+                struts,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut layout = Layout::with_options(Clock::with_time(Duration::ZERO), options);
+        layout.add_output(make_test_output("output-test"), None);
+        layout.add_window(
+            TestWindow::new(TestWindowParams::new(1)),
+            AddWindowTarget::Auto,
+            None,
+            None,
+            false,
+            false,
+            ActivateWindow::Yes,
+        );
+
+        let (_, win) = layout.windows().next().unwrap();
+        win.requested_size().expect("expected requested size")
+    };
+
+    let without = requested_size(Struts::default());
+    let with = requested_size(Struts {
+        left: FloatOrInt(100.),
+        right: FloatOrInt(50.),
+        top: FloatOrInt(20.),
+        bottom: FloatOrInt(0.),
+    });
+    assert_eq!(without.w - with.w, 150);
+    assert_eq!(without.h - with.h, 20);
+}
+
+#[test]
+fn expel_pending_left_from_fullscreen_tabbed_column() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FullscreenWindow(1),
+        Op::Communicate(1),
+        // 1 is now fullscreen, view_offset_to_restore is set.
+        Op::ToggleColumnTabbedDisplay,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: Some(2) },
+        // 2 is consumed into a fullscreen column, fullscreen is requested but not applied.
+        //
+        // Now, get it back out while keeping it focused.
+        //
         // Importantly, we expel it *left*, which results in adding a new column with the exact
         // same active_column_idx.
         Op::FocusWindow(2),
@@ -4686,7 +5830,13 @@ impl TreeHarness {
         let view_size = Size::from((800.0, 600.0));
         let working_area = Rectangle::from_size(view_size);
         let scale = 1.0;
-        let tree = ContainerTree::new(view_size, working_area, scale, options.clone());
+        let tree = ContainerTree::new(
+            view_size,
+            working_area,
+            scale,
+            clock.clone(),
+            options.clone(),
+        );
         Self {
             tree,
             options,
@@ -4719,6 +5869,484 @@ impl TreeHarness {
         );
         self.tree.append_leaf(tile, true);
     }
+
+    /// Runs a tree script, returning an error describing the first failing line.
+    ///
+    /// A tree script describes a tree scenario one line at a time, so that regression cases for
+    /// tree manipulation don't need a new test function each:
+    ///
+    /// ```text
+    /// # Comments and blank lines are ignored.
+    /// add 1 2
+    /// focus left
+    /// split v
+    /// add 3
+    /// | SplitH
+    /// |   SplitV
+    /// |     Window 1
+    /// |     Window 3 *
+    /// |   Window 2
+    /// ```
+    ///
+    /// Operations:
+    ///
+    /// - `add <id>...` and `append <id>...` open windows through `insert_window()` and
+    ///   `append_leaf()`,
+    /// - `remove <id>`,
+    /// - `split <layout>` and `layout <layout>`, where layout is `h`, `v`, `tabbed` or `stacked`,
+    /// - `toggle-split`,
+    /// - `focus <direction>` and `move <direction>`, where direction is `left`, `right`, `up` or
+    ///   `down`,
+    /// - `focus-window <id>`, `focus-root-child <index>`, `focus-parent` and `focus-child`.
+    ///
+    /// Operations must succeed, unless prefixed with `!`, in which case they must do nothing. A
+    /// run of lines starting with `|` is the expected `debug_tree()` output at that point.
+    fn run_script(&mut self, script: &str) -> Result<(), String> {
+        self.run_script_lines(
+            script
+                .lines()
+                .enumerate()
+                .map(|(idx, line)| (idx + 1, line)),
+        )
+    }
+
+    fn run_script_lines<'a>(
+        &mut self,
+        lines: impl IntoIterator<Item = (usize, &'a str)>,
+    ) -> Result<(), String> {
+        let mut expected = String::new();
+        let mut expected_line = 0;
+
+        for (line_number, line) in lines {
+            let line = line.trim();
+
+            if let Some(tree_line) = line.strip_prefix('|') {
+                if expected.is_empty() {
+                    expected_line = line_number;
+                }
+                expected.push_str(tree_line.strip_prefix(' ').unwrap_or(tree_line));
+                expected.push('\n');
+                continue;
+            }
+
+            self.check_script_tree(&mut expected, expected_line)?;
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            self.run_script_op(line)
+                .map_err(|err| format!("line {line_number}: {line}: {err}"))?;
+        }
+
+        self.check_script_tree(&mut expected, expected_line)
+    }
+
+    fn check_script_tree(&self, expected: &mut String, line_number: usize) -> Result<(), String> {
+        if expected.is_empty() {
+            return Ok(());
+        }
+
+        let actual = self.tree.debug_tree();
+        let expected = std::mem::take(expected);
+        if actual != expected {
+            return Err(format!(
+                "line {line_number}: tree mismatch\nexpected:\n{expected}actual:\n{actual}"
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn run_script_op(&mut self, op: &str) -> Result<(), String> {
+        let (should_succeed, op) = match op.strip_prefix('!') {
+            Some(op) => (false, op.trim_start()),
+            None => (true, op),
+        };
+
+        let words: Vec<_> = op.split_whitespace().collect();
+        let succeeded = match words[..] {
+            ["add", ref ids @ ..] | ["append", ref ids @ ..] if !ids.is_empty() => {
+                for id in ids {
+                    let id = parse_script_id(id)?;
+                    if words[0] == "add" {
+                        self.add_window(id);
+                    } else {
+                        self.append_window(id);
+                    }
+                }
+                true
+            }
+            ["remove", id] => self.tree.remove_window(&parse_script_id(id)?).is_some(),
+            ["split", layout] => self.tree.split_focused(parse_script_layout(layout)?),
+            ["layout", layout] => self.tree.set_focused_layout(parse_script_layout(layout)?),
+            ["toggle-split"] => self.tree.toggle_split_layout(),
+            ["focus", direction] => self
+                .tree
+                .focus_in_direction(parse_script_direction(direction)?),
+            ["move", direction] => self
+                .tree
+                .move_in_direction(parse_script_direction(direction)?),
+            ["focus-window", id] => self.tree.focus_window_by_id(&parse_script_id(id)?),
+            ["focus-root-child", idx] => self.tree.focus_root_child(parse_script_id(idx)?),
+            ["focus-parent"] => self.tree.focus_parent(),
+            ["focus-child"] => self.tree.focus_child(),
+            _ => return Err(String::from("unknown operation")),
+        };
+
+        match (should_succeed, succeeded) {
+            (true, false) => Err(String::from("operation did nothing")),
+            (false, true) => Err(String::from("operation was expected to do nothing")),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn parse_script_id(id: &str) -> Result<usize, String> {
+    id.parse().map_err(|_| format!("invalid number: {id}"))
+}
+
+fn parse_script_layout(layout: &str) -> Result<ContainerLayout, String> {
+    match layout {
+        "h" => Ok(ContainerLayout::SplitH),
+        "v" => Ok(ContainerLayout::SplitV),
+        "tabbed" => Ok(ContainerLayout::Tabbed),
+        "stacked" => Ok(ContainerLayout::Stacked),
+        _ => Err(format!("invalid layout: {layout}")),
+    }
+}
+
+fn parse_script_direction(direction: &str) -> Result<Direction, String> {
+    match direction {
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        _ => Err(format!("invalid direction: {direction}")),
+    }
+}
+
+#[track_caller]
+fn check_tree_script(script: &str) {
+    if let Err(err) = TreeHarness::new().run_script(script) {
+        panic!("{err}");
+    }
+}
+
+/// Runs every scenario in `tests/tree-scripts.txt`.
+///
+/// Each scenario starts with a `=== <name>` header and runs on a fresh tree.
+#[test]
+fn tree_scripts() {
+    const SCRIPTS: &str = include_str!("tests/tree-scripts.txt");
+
+    let mut scenarios: Vec<(&str, Vec<(usize, &str)>)> = Vec::new();
+    for (idx, line) in SCRIPTS.lines().enumerate() {
+        if let Some(name) = line.strip_prefix("===") {
+            scenarios.push((name.trim(), Vec::new()));
+        } else if let Some((_, lines)) = scenarios.last_mut() {
+            lines.push((idx + 1, line));
+        } else {
+            let line = line.trim();
+            assert!(
+                line.is_empty() || line.starts_with('#'),
+                "line {}: expected a scenario header",
+                idx + 1
+            );
+        }
+    }
+    assert!(!scenarios.is_empty());
+
+    let mut failures = String::new();
+    for (name, lines) in scenarios {
+        if let Err(err) = TreeHarness::new().run_script_lines(lines) {
+            failures += &format!("{name}: {err}\n");
+        }
+    }
+    assert!(failures.is_empty(), "failed tree scripts:\n{failures}");
+}
+
+#[test]
+fn focus_parent_then_child_in_tree_script() {
+    check_tree_script(
+        "
+        add 1 2
+        focus left
+        split v
+        add 3
+        focus-parent
+        # A focused container doesn't mark any window.
+        | SplitH
+        |   SplitV
+        |     Window 1
+        |     Window 3
+        |   Window 2
+        focus-child
+        | SplitH
+        |   SplitV
+        |     Window 1
+        |     Window 3 *
+        |   Window 2
+        ",
+    );
+}
+
+#[test]
+fn tree_script_reports_failures() {
+    let err = TreeHarness::new()
+        .run_script("add 1\nfocus-window 2")
+        .unwrap_err();
+    assert_snapshot!(err, @"line 2: focus-window 2: operation did nothing");
+
+    let err = TreeHarness::new()
+        .run_script("add 1 2\n| SplitV\n|   Window 1\n|   Window 2 *")
+        .unwrap_err();
+    assert_snapshot!(err, @r"
+    line 2: tree mismatch
+    expected:
+    SplitV
+      Window 1
+      Window 2 *
+    actual:
+    SplitH
+      Window 1
+      Window 2 *
+    ");
+
+    let err = TreeHarness::new().run_script("frobnicate").unwrap_err();
+    assert_snapshot!(err, @"line 1: frobnicate: unknown operation");
+}
+
+#[test]
+fn pending_layout_applies_after_deadline() {
+    let mut harness = TreeHarness::new();
+    harness.add_window(1);
+    harness.tree.layout();
+    assert!(!harness.tree.has_pending_layouts());
+
+    // Hold on to the transaction, as if a window never acked its new size.
+    let transaction = Transaction::new();
+    harness.tree.set_pending_transaction(transaction.clone());
+    harness.add_window(2);
+    harness.tree.layout();
+    assert!(harness.tree.has_pending_layouts());
+    assert!(!harness.tree.apply_pending_layouts_if_ready());
+    assert_eq!(harness.tree.leaf_layouts().len(), 1);
+
+    let deadline = crate::utils::transaction::TIME_LIMIT;
+    assert_eq!(harness.tree.pending_layout_deadline(), Some(deadline));
+
+    harness.clock.set_unadjusted(deadline);
+    assert!(harness.tree.apply_pending_layouts_if_ready());
+    assert_eq!(harness.tree.leaf_layouts().len(), 2);
+    assert_eq!(harness.tree.pending_layout_deadline(), None);
+
+    drop(transaction);
+}
+
+#[test]
+fn tab_bar_flips_with_pending_layout() {
+    let mut harness = TreeHarness::new();
+    harness.add_window(1);
+    harness.add_window(2);
+    harness.tree.set_focused_layout(ContainerLayout::Tabbed);
+    harness.tree.layout();
+    assert!(!harness.tree.has_pending_layouts());
+    assert_eq!(harness.tree.tab_bar_layouts().len(), 1);
+
+    // Hold on to the transaction, as if the windows haven't committed their new sizes yet.
+    let transaction = Transaction::new();
+    harness.tree.set_pending_transaction(transaction.clone());
+    harness.tree.set_focused_layout(ContainerLayout::SplitH);
+    harness.tree.layout();
+    assert!(harness.tree.has_pending_layouts());
+
+    // The tab bar stays until the windows are resized.
+    assert_eq!(harness.tree.tab_bar_layouts().len(), 1);
+
+    drop(transaction);
+    assert!(harness.tree.apply_pending_layouts_if_ready());
+    assert!(harness.tree.tab_bar_layouts().is_empty());
+}
+
+#[test]
+fn window_rules_set_up_tabbed_container_with_side_tab_bar() {
+    let mut harness = TreeHarness::new();
+    harness.add_window(1);
+    harness.tree.apply_window_rules(
+        &1,
+        Some(ContainerLayout::Tabbed),
+        Some(TabIndicatorPosition::Left),
+    );
+    harness.tree.layout();
+
+    let tab_bars = harness.tree.tab_bar_layouts();
+    assert_eq!(tab_bars.len(), 1);
+    assert_eq!(tab_bars[0].layout, ContainerLayout::Tabbed);
+    assert_eq!(tab_bars[0].position, TabIndicatorPosition::Left);
+    assert!(!tab_bars[0].is_single_row());
+    assert_eq!(tab_bars[0].rect.size.w, 200.);
+
+    // The layout doesn't change for windows opening next to others.
+    harness.add_window(2);
+    let tree = &mut harness.tree;
+    tree.apply_window_rules(&2, Some(ContainerLayout::SplitV), None);
+    tree.layout();
+    assert_eq!(tree.tab_bar_layouts()[0].layout, ContainerLayout::Tabbed);
+}
+
+#[test]
+fn window_rules_set_up_containers_of_opened_windows() {
+    let rules = ResolvedWindowRules {
+        default_column_display: Some(ColumnDisplay::Tabbed),
+        tab_bar_position: Some(TabIndicatorPosition::Left),
+        ..ResolvedWindowRules::default()
+    };
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams {
+                rules: Some(rules.clone()),
+                ..TestWindowParams::new(1)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                rules: Some(rules),
+                ..TestWindowParams::new(2)
+            },
+        },
+    ];
+    let layout = check_ops(ops);
+    let ws = layout.active_workspace().unwrap();
+
+    let positions = |tab_bars: Vec<super::container::TabBarInfo>| {
+        tab_bars
+            .iter()
+            .map(|info| (info.layout, info.position))
+            .collect::<Vec<_>>()
+    };
+    let expected = [(ContainerLayout::Tabbed, TabIndicatorPosition::Left)];
+    assert_eq!(positions(ws.scrolling().tab_bar_layouts()), expected);
+    assert_eq!(positions(ws.floating().tab_bar_layouts()), expected);
+}
+
+#[test]
+fn tab_bar_position_is_per_container() {
+    let mut harness = TreeHarness::new();
+    harness.add_window(1);
+    harness.add_window(2);
+    harness.tree.split_focused(ContainerLayout::Tabbed);
+    harness.add_window(3);
+    assert!(harness.tree.focus_window_by_id(&1));
+    harness.tree.split_focused(ContainerLayout::Stacked);
+    harness.add_window(4);
+    assert!(harness
+        .tree
+        .set_focused_tab_bar_position(TabIndicatorPosition::Right));
+    assert!(!harness
+        .tree
+        .set_focused_tab_bar_position(TabIndicatorPosition::Right));
+    harness.tree.layout();
+
+    let positions: Vec<_> = harness
+        .tree
+        .tab_bar_layouts()
+        .iter()
+        .map(|info| (info.layout, info.position))
+        .collect();
+    assert_eq!(
+        positions,
+        [
+            (ContainerLayout::Stacked, TabIndicatorPosition::Right),
+            (ContainerLayout::Tabbed, TabIndicatorPosition::Top),
+        ]
+    );
+}
+
+#[test]
+fn tree_rebuilds_from_template() {
+    let mut saved = TreeHarness::new();
+    saved.add_window(1);
+    saved.add_window(2);
+    saved.tree.split_focused(ContainerLayout::SplitV);
+    saved.add_window(3);
+    let template = saved.tree.template().unwrap();
+
+    let rebuild = |ids: &[usize]| {
+        let mut harness = TreeHarness::new();
+        let mut tiles = ids
+            .iter()
+            .map(|&id| {
+                let window = TestWindow::new(TestWindowParams::new(id));
+                let tile = Tile::new(
+                    window,
+                    harness.view_size,
+                    harness.scale,
+                    harness.clock.clone(),
+                    harness.options.clone(),
+                );
+                (id, tile)
+            })
+            .collect::<std::collections::HashMap<_, _>>();
+        let subtree = DetachedNode::from_template(template.clone(), &mut |id| tiles.remove(id));
+        harness.tree.append_subtree(subtree.unwrap(), false);
+        harness.tree.debug_tree()
+    };
+
+    assert_snapshot!(
+        rebuild(&[1, 2, 3]).as_str(),
+        @"SplitH
+  Window 1 *
+  SplitV
+    Window 2
+    Window 3
+"
+    );
+
+    // Without window 2, its container collapses into window 3.
+    assert_snapshot!(
+        rebuild(&[1, 3]).as_str(),
+        @"SplitH
+  Window 1 *
+  Window 3
+"
+    );
+}
+
+#[test]
+fn check_invariants_accepts_nested_tree() {
+    let mut harness = TreeHarness::new();
+    harness.add_window(1);
+    harness.add_window(2);
+    harness.tree.split_focused(ContainerLayout::SplitV);
+    harness.add_window(3);
+    harness.tree.split_focused(ContainerLayout::Tabbed);
+    harness.add_window(4);
+    assert_eq!(harness.tree.check_invariants(), []);
+
+    harness.tree.remove_window(&2);
+    harness.tree.remove_window(&3);
+    assert_eq!(harness.tree.check_invariants(), []);
+}
+
+#[test]
+fn check_invariants_reports_cycle() {
+    let mut harness = TreeHarness::new();
+    harness.add_window(1);
+    harness.add_window(2);
+    harness.tree.split_focused(ContainerLayout::SplitV);
+    harness.add_window(3);
+    harness.tree.link_root_into_cycle();
+
+    let violations = harness.tree.check_invariants();
+    let cycle = violations
+        .iter()
+        .find(|v| v.kind == LayoutViolationKind::Cycle)
+        .unwrap();
+    assert_eq!(cycle.path, [1, 2]);
 }
 
 #[test]
@@ -5415,7 +7043,7 @@ fn insert_position_empty_workspace_returns_new_column() {
 
     // For an empty workspace, insert position should be NewColumn(0)
     let pos = Point::from((100.0, 100.0));
-    let insert_pos = workspace.scrolling_insert_position(pos);
+    let insert_pos = workspace.scrolling_insert_position(pos, 1.);
 
     assert!(matches!(insert_pos, InsertPosition::NewColumn(0)));
 }
@@ -5447,7 +7075,7 @@ fn insert_position_with_window_on_top_edge() {
 
     // Position at top edge should indicate SplitRoot with Up direction
     let pos = Point::from((100.0, 0.0));
-    let insert_pos = workspace.scrolling_insert_position(pos);
+    let insert_pos = workspace.scrolling_insert_position(pos, 1.);
 
     // Should be SplitRoot { direction: Up, ... }
     match insert_pos {
@@ -5486,7 +7114,7 @@ fn insert_position_with_window_on_bottom_edge() {
     // Position at bottom edge should indicate SplitRoot with Down direction
     // Use a very large Y to be at the bottom
     let pos = Point::from((100.0, 10000.0));
-    let insert_pos = workspace.scrolling_insert_position(pos);
+    let insert_pos = workspace.scrolling_insert_position(pos, 1.);
 
     // Should be SplitRoot { direction: Down, ... }
     match insert_pos {
@@ -5524,7 +7152,7 @@ fn insert_position_center_of_window() {
     // Position in the center of the window area should result in Swap or Split
     // (depending on exact position relative to the window)
     let pos = Point::from((640.0, 360.0)); // center of 1280x720
-    let insert_pos = workspace.scrolling_insert_position(pos);
+    let insert_pos = workspace.scrolling_insert_position(pos, 1.);
 
     // Should be either Swap or Split (both are valid for center area)
     assert!(
@@ -5534,6 +7162,251 @@ fn insert_position_center_of_window() {
     );
 }
 
+#[test]
+fn default_floating_size_rule() {
+    let rules = ResolvedWindowRules {
+        default_floating_size: Some(niri_config::FloatingSize {
+            width: 400,
+            height: 300,
+        }),
+        ..ResolvedWindowRules::default()
+    };
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams {
+                is_floating: true,
+                rules: Some(rules.clone()),
+                ..TestWindowParams::new(1)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams {
+                rules: Some(rules),
+                ..TestWindowParams::new(2)
+            },
+        },
+    ];
+    let mut layout = check_ops(ops);
+
+    // Opening floating uses the rule size.
+    let size = |layout: &Layout<TestWindow>, id: usize| {
+        let (_, win) = layout.windows().find(|(_, win)| *win.id() == id).unwrap();
+        win.requested_size().unwrap()
+    };
+    assert_eq!(size(&layout, 1), Size::from((400, 300)));
+
+    // Moving from tiling to floating without a stored floating size also uses the rule size.
+    let ops = [Op::Communicate(2), Op::ToggleWindowFloating { id: Some(2) }];
+    check_ops_on_layout(&mut layout, ops);
+    assert_eq!(size(&layout, 2), Size::from((400, 300)));
+}
+
+#[test]
+fn distribute_with_limits_honors_min_max() {
+    use super::container::distribute_with_limits;
+
+    // No limits: plain percentages.
+    let sizes = distribute_with_limits(1000., &[0.5, 0.5], &[(0., 0.), (0., 0.)]);
+    assert_eq!(sizes, [500., 500.]);
+
+    // A min size takes space from the other children.
+    let sizes =
+        distribute_with_limits(1000., &[0.5, 0.25, 0.25], &[(700., 0.), (0., 0.), (0., 0.)]);
+    assert_eq!(sizes, [700., 150., 150.]);
+
+    // A max size gives space to the other children.
+    let sizes = distribute_with_limits(1000., &[0.5, 0.5], &[(0., 200.), (0., 0.)]);
+    assert_eq!(sizes, [200., 800.]);
+
+    // Min sizes that don't fit are scaled down.
+    let sizes = distribute_with_limits(1000., &[0.5, 0.5], &[(1500., 0.), (500., 0.)]);
+    assert_eq!(sizes, [750., 250.]);
+}
+
+#[test]
+fn split_honors_window_min_size() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams {
+                min_max_size: (Size::from((900, 0)), Size::from((0, 0))),
+                ..TestWindowParams::new(1)
+            },
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::Communicate(1),
+        Op::Communicate(2),
+    ];
+    let layout = check_ops(ops);
+
+    let width = |id: usize| {
+        let (_, win) = layout.windows().find(|(_, win)| *win.id() == id).unwrap();
+        win.requested_size().unwrap().w
+    };
+    assert!(width(1) >= 900, "min width not honored: {}", width(1));
+    assert!(
+        width(2) < 1280 - 900,
+        "sibling did not shrink: {}",
+        width(2)
+    );
+}
+
+#[test]
+fn geometry_audit_reports_issues() {
+    let rect =
+        |x: f64, y: f64, w: f64, h: f64| Rectangle::new(Point::from((x, y)), Size::from((w, h)));
+
+    // At 1.5 scale, the first two edges round to different physical pixels, and the last tile
+    // sticks out at the bottom.
+    let rects = [
+        rect(0., 0., 100.2, 300.),
+        rect(100.4, 0., 299.6, 150.),
+        rect(100.4, 150., 299.6, 160.),
+    ];
+    let issues = audit_tile_geometry(&rects, rect(0., 0., 400., 300.), 0., 1.5);
+    assert_eq!(
+        issues,
+        [
+            GeometryIssue::OutOfBounds { tile: 2 },
+            GeometryIssue::Seam {
+                first: 0,
+                second: 1,
+                vertical: true,
+                distance: 1,
+            },
+        ]
+    );
+
+    let rects = [rect(0., 0., 100., 100.), rect(90., 0., 100., 100.)];
+    let issues = audit_tile_geometry(&rects, rect(0., 0., 300., 100.), 8., 1.);
+    assert_eq!(
+        issues,
+        [GeometryIssue::Overlap {
+            first: 0,
+            second: 1
+        }]
+    );
+
+    // 8 logical px gaps are 10 physical px at 1.25 scale.
+    let rects = [rect(0., 0., 100., 100.), rect(108., 0., 100., 100.)];
+    let issues = audit_tile_geometry(&rects, rect(0., 0., 300., 100.), 8., 1.25);
+    assert!(issues.is_empty(), "{issues:?}");
+}
+
+#[test]
+fn geometry_audit_nested_splits() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::SplitVertical,
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::Communicate(1),
+        Op::Communicate(2),
+        Op::Communicate(3),
+    ];
+    let layout = check_ops(ops);
+
+    let ws = layout.active_workspace().unwrap();
+    let (rects, _) = ws.scrolling().visible_leaf_rects();
+    assert_eq!(rects.len(), 3);
+    let issues = ws.audit_geometry();
+    assert!(issues.is_empty(), "{issues:?}");
+}
+
+/// Container tree ops, together with the ops that add and remove the trees and move windows
+/// between them.
+///
+/// In `any::<Op>()` the container ops are a handful out of over a hundred variants, so they
+/// rarely come up next to each other.
+fn arbitrary_container_op() -> impl Strategy<Value = Op> {
+    let tree_op = prop_oneof![
+        Just(Op::FocusParent),
+        Just(Op::FocusChild),
+        Just(Op::SplitHorizontal),
+        Just(Op::SplitVertical),
+        Just(Op::SetLayoutSplitH),
+        Just(Op::SetLayoutSplitV),
+        Just(Op::SetLayoutTabbed),
+        Just(Op::SetLayoutStacked),
+        Just(Op::ToggleSplitLayout),
+        arbitrary_tab_indicator_position().prop_map(Op::SetTabBarPosition),
+    ];
+
+    let focus_op = prop_oneof![
+        Just(Op::FocusColumnLeft),
+        Just(Op::FocusColumnRight),
+        Just(Op::FocusWindowUp),
+        Just(Op::FocusWindowDown),
+        Just(Op::MoveColumnLeft),
+        Just(Op::MoveColumnRight),
+        Just(Op::MoveWindowUp),
+        Just(Op::MoveWindowDown),
+    ];
+
+    let window_op = prop_oneof![
+        any::<TestWindowParams>().prop_map(|params| Op::AddWindow { params }),
+        (1..=5usize).prop_map(Op::CloseWindow),
+        (1..=5usize).prop_map(Op::Communicate),
+        Just(Op::CompleteAnimations),
+    ];
+
+    let output_op = prop_oneof![
+        (1..=5usize).prop_map(Op::AddOutput),
+        (1..=5usize).prop_map(Op::RemoveOutput),
+        (1..=5usize).prop_map(Op::FocusOutput),
+    ];
+
+    let coord = || -20000f64..20000f64;
+    let move_op = prop_oneof![
+        (1..=5usize, 1..=5usize, coord(), coord()).prop_map(|(window, output_idx, px, py)| {
+            Op::InteractiveMoveBegin {
+                window,
+                output_idx,
+                px,
+                py,
+            }
+        }),
+        (1..=5usize, coord(), coord(), 1..=5usize, coord(), coord()).prop_map(
+            |(window, dx, dy, output_idx, px, py)| Op::InteractiveMoveUpdate {
+                window,
+                dx,
+                dy,
+                output_idx,
+                px,
+                py,
+            }
+        ),
+        (1..=5usize).prop_map(|window| Op::InteractiveMoveEnd { window }),
+    ];
+
+    prop_oneof![
+        4 => tree_op,
+        2 => focus_op,
+        3 => window_op,
+        1 => output_op,
+        1 => move_op,
+    ]
+}
+
+fn arbitrary_ops() -> impl Strategy<Value = Vec<Op>> {
+    let op = prop_oneof![
+        3 => any::<Op>(),
+        1 => arbitrary_container_op(),
+    ];
+    prop::collection::vec(op, 0..100)
+}
+
 proptest! {
     #![proptest_config(ProptestConfig {
         cases: if std::env::var_os("RUN_SLOW_TESTS").is_none() {
@@ -5547,7 +7420,7 @@ proptest! {
 
     #[test]
     fn random_operations_dont_panic(
-        ops: Vec<Op>,
+        ops in arbitrary_ops(),
         layout_config in arbitrary_layout_part(),
     ) {
         // eprintln!("{ops:?}");
@@ -1096,3 +1096,39 @@ fn width_resize_and_cancel_of_column_to_the_left() {
     200 × 200 at x:100 y:  0
     ");
 }
+
+#[test]
+fn window_movement_off_skips_tree_move_animation() {
+    let mut options = make_options();
+    options.animations.window_movement.0.off = true;
+
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::Communicate(1),
+        Op::Communicate(2),
+        Op::CompleteAnimations,
+        // Opening a third window moves the second one to the left.
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+    ];
+    let mut layout = check_ops_with_options(options, ops);
+
+    let positions = |layout: &Layout<TestWindow>| {
+        let ws = layout.active_workspace().unwrap();
+        let mut tiles: Vec<_> = ws.tiles_with_render_positions().collect();
+        tiles.sort_by_key(|(tile, _, _)| tile.window().id());
+        tiles.into_iter().map(|(_, pos, _)| pos).collect::<Vec<_>>()
+    };
+
+    // With the movement animation off, the windows are at their final positions right away.
+    let before = positions(&layout);
+    Op::CompleteAnimations.apply(&mut layout);
+    assert_eq!(before, positions(&layout));
+}
@@ -658,3 +658,34 @@ fn removing_only_fullscreen_tile_updates_view_offset() {
     // FIXME: currently, removing a tile doesn't cause the view offset to update.
     assert_snapshot!(layout.active_workspace().unwrap().scrolling().view_pos(), @"0");
 }
+
+#[test]
+fn fullscreen_span_requests_span_size() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FullscreenWindow(1),
+        Op::Communicate(1),
+        Op::CompleteAnimations,
+    ];
+    let mut layout = check_ops(ops);
+    assert_eq!(layout.fullscreen_span_tiles().count(), 0);
+
+    let span = Rectangle::new(Point::from((-1280., 0.)), Size::from((2560., 720.)));
+    assert!(layout.set_fullscreen_span(&1, Some(span)));
+    assert!(!layout.set_fullscreen_span(&1, Some(span)));
+    layout.update_render_elements(None);
+
+    let (output, tile) = layout.fullscreen_span_tiles().next().unwrap();
+    assert_eq!(output.name(), "output1");
+    assert_eq!(tile.fullscreen_loc(), Point::from((-1280., 0.)));
+    assert_eq!(requested_width(&layout, 1), 2560);
+
+    // Clearing the span goes back to the output size.
+    assert!(layout.set_fullscreen_span(&1, None));
+    layout.update_render_elements(None);
+    assert_eq!(layout.fullscreen_span_tiles().count(), 0);
+    assert_eq!(requested_width(&layout, 1), 1280);
+}
@@ -0,0 +1,207 @@
+//! Tile geometry of canonical container trees at common output scales.
+//!
+//! Every scale gets the same 1280x720 physical output, so the snapshots show how the logical
+//! rectangles and the rounded tab bar sizes change with the scale. A change to the container
+//! layout pass or to `round_logical_in_physical_max1` shows up here as a snapshot diff.
+
+use std::fmt::Write as _;
+
+use insta::assert_snapshot;
+
+use super::*;
+
+const SCALES: [f64; 4] = [1., 1.25, 1.5, 2.];
+
+fn harness(scale: f64) -> TreeHarness {
+    let mut options = Options::from_config(&Config::default());
+    // Lay the tree out right away instead of waiting for the windows to commit.
+    options.disable_transactions = true;
+    // Bar sizes that don't land on whole physical pixels at every scale.
+    options.layout.tab_bar.height = 21.;
+    options.layout.tab_bar.side_width = 150.;
+    let options = Rc::new(options);
+
+    let clock = Clock::with_time(Duration::ZERO);
+    let view_size = Size::from((1280. / scale, 720. / scale));
+    let working_area = Rectangle::from_size(view_size);
+    let tree = ContainerTree::new(
+        view_size,
+        working_area,
+        scale,
+        clock.clone(),
+        options.clone(),
+    );
+    TreeHarness {
+        tree,
+        options,
+        clock,
+        view_size,
+        scale,
+    }
+}
+
+/// Builds the tree at every scale and lists the rectangles of its tiles.
+fn geometry(build: impl Fn(&mut TreeHarness)) -> String {
+    let mut out = String::new();
+    for scale in SCALES {
+        let mut harness = harness(scale);
+        build(&mut harness);
+        harness.tree.layout();
+
+        writeln!(out, "scale {scale}").unwrap();
+        for info in harness.tree.leaf_layouts() {
+            let tile = harness.tree.get_tile(info.key).unwrap();
+            let Rectangle { loc, size } = info.rect;
+            write!(
+                out,
+                "  window {}: [{}, {}, {}, {}]",
+                tile.window().id(),
+                loc.x,
+                loc.y,
+                size.w,
+                size.h
+            )
+            .unwrap();
+
+            let bar = tile.tab_bar_inset();
+            if !bar.is_none() {
+                write!(out, ", {:?} bar {}", bar.position, bar.size).unwrap();
+            }
+            if !info.visible {
+                out.push_str(", hidden");
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[test]
+fn split_of_three() {
+    let geometry = geometry(|harness| {
+        harness.add_window(1);
+        harness.add_window(2);
+        harness.add_window(3);
+    });
+    assert_snapshot!(
+        geometry.as_str(),
+        @"scale 1
+  window 1: [16, 16, 405.33333333333337, 688]
+  window 2: [437.33333333333337, 16, 405.33333333333337, 688]
+  window 3: [858.6666666666667, 16, 405.3333333333333, 688]
+scale 1.25
+  window 1: [16, 16, 320.00000000000006, 544]
+  window 2: [352.00000000000006, 16, 320.00000000000006, 544]
+  window 3: [688.0000000000001, 16, 320, 544]
+scale 1.5
+  window 1: [16, 16, 263.11111111111114, 448]
+  window 2: [295.11111111111114, 16, 263.11111111111114, 448]
+  window 3: [574.2222222222223, 16, 263.1111111111111, 448]
+scale 2
+  window 1: [16, 16, 192.00000000000003, 328]
+  window 2: [224.00000000000003, 16, 192.00000000000003, 328]
+  window 3: [432.00000000000006, 16, 192, 328]
+"
+    );
+}
+
+#[test]
+fn nested_split() {
+    let geometry = geometry(|harness| {
+        harness.add_window(1);
+        harness.add_window(2);
+        harness.tree.split_focused(ContainerLayout::SplitV);
+        harness.add_window(3);
+    });
+    assert_snapshot!(
+        geometry.as_str(),
+        @"scale 1
+  window 1: [16, 16, 616, 688]
+  window 2: [648, 16, 616, 336]
+  window 3: [648, 368, 616, 336]
+scale 1.25
+  window 1: [16, 16, 488, 544]
+  window 2: [520, 16, 488, 264]
+  window 3: [520, 296, 488, 264]
+scale 1.5
+  window 1: [16, 16, 402.6666666666667, 448]
+  window 2: [434.6666666666667, 16, 402.6666666666667, 216]
+  window 3: [434.6666666666667, 248, 402.6666666666667, 216]
+scale 2
+  window 1: [16, 16, 296, 328]
+  window 2: [328, 16, 296, 156]
+  window 3: [328, 188, 296, 156]
+"
+    );
+}
+
+#[test]
+fn split_in_tabbed_with_side_bar() {
+    let geometry = geometry(|harness| {
+        harness.add_window(1);
+        harness.add_window(2);
+        harness.tree.split_focused(ContainerLayout::Tabbed);
+        harness
+            .tree
+            .set_focused_tab_bar_position(TabIndicatorPosition::Left);
+        harness.add_window(4);
+        harness.tree.focus_window_by_id(&2);
+        harness.tree.split_focused(ContainerLayout::SplitV);
+        harness.add_window(3);
+    });
+    assert_snapshot!(
+        geometry.as_str(),
+        @"scale 1
+  window 1: [16, 16, 616, 688]
+  window 2: [814, 32, 434, 320]
+  window 3: [814, 368, 434, 320]
+  window 4: [664, 32, 584, 656], Left bar 150, hidden
+scale 1.25
+  window 1: [16, 16, 488, 544]
+  window 2: [686.4, 32, 305.6, 248]
+  window 3: [686.4, 296, 305.6, 248]
+  window 4: [536, 32, 456, 512], Left bar 150.4, hidden
+scale 1.5
+  window 1: [16, 16, 402.6666666666667, 448]
+  window 2: [600.6666666666667, 32, 220.66666666666669, 200]
+  window 3: [600.6666666666667, 248, 220.66666666666669, 200]
+  window 4: [450.6666666666667, 32, 370.6666666666667, 416], Left bar 150, hidden
+scale 2
+  window 1: [16, 16, 296, 328]
+  window 2: [494, 32, 114, 140]
+  window 3: [494, 188, 114, 140]
+  window 4: [344, 32, 264, 296], Left bar 150, hidden
+"
+    );
+}
+
+#[test]
+fn stacked_under_window() {
+    let geometry = geometry(|harness| {
+        harness.add_window(1);
+        harness.tree.set_focused_layout(ContainerLayout::SplitV);
+        harness.add_window(2);
+        harness.tree.split_focused(ContainerLayout::Stacked);
+        harness.add_window(3);
+    });
+    assert_snapshot!(
+        geometry.as_str(),
+        @"scale 1
+  window 1: [16, 16, 1248, 336]
+  window 2: [32, 384, 1216, 304], Top bar 42, hidden
+  window 3: [32, 384, 1216, 304], Top bar 42
+scale 1.25
+  window 1: [16, 16, 992, 264]
+  window 2: [32, 312, 960, 232], Top bar 41.6, hidden
+  window 3: [32, 312, 960, 232], Top bar 41.6
+scale 1.5
+  window 1: [16, 16, 821.3333333333334, 216]
+  window 2: [32, 264, 789.3333333333334, 184], Top bar 42.666666666666664, hidden
+  window 3: [32, 264, 789.3333333333334, 184], Top bar 42.666666666666664
+scale 2
+  window 1: [16, 16, 608, 156]
+  window 2: [32, 204, 576, 124], Top bar 42, hidden
+  window 3: [32, 204, 576, 124], Top bar 42
+"
+    );
+}
@@ -0,0 +1,182 @@
+//! Layout scenarios written as JSON.
+//!
+//! A scenario is a JSON array of `Op`s in serde's default enum representation: ops without fields
+//! are strings, and other ops are objects with the op name as the only key. Field names are the
+//! same as in `Op`.
+//!
+//! ```json
+//! [
+//!     {"AddOutput": 1},
+//!     {"AddWindow": {"params": {"id": 1}}},
+//!     {"AddWindow": {"params": {"id": 2, "bbox": [0, 0, 300, 200]}}},
+//!     {"SwapWindowInDirection": "Left"},
+//!     "FocusParent"
+//! ]
+//! ```
+//!
+//! Every file in `tests/layout-scripts` runs in the `layout_scripts` test, so a scenario attached
+//! to a bug report becomes a regression test once it's copied there. Set `NIRI_LAYOUT_SCRIPT` to
+//! the path of a scenario to run just that file instead.
+//!
+//! Layout config parts can't be set from scripts, so ops that take one use the default config.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+use super::*;
+
+const SCRIPTS_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/layout/tests/layout-scripts"
+);
+
+fn parse_name<'de, D: Deserializer<'de>, T: Copy>(
+    deserializer: D,
+    values: &[(&str, T)],
+) -> Result<T, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    values
+        .iter()
+        .find(|(value, _)| *value == name)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| D::Error::custom(format!("unknown value: {name}")))
+}
+
+pub(super) fn scroll_direction<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<ScrollDirection, D::Error> {
+    parse_name(
+        deserializer,
+        &[
+            ("Left", ScrollDirection::Left),
+            ("Right", ScrollDirection::Right),
+            ("Up", ScrollDirection::Up),
+            ("Down", ScrollDirection::Down),
+        ],
+    )
+}
+
+pub(super) fn mark_mode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<MarkMode, D::Error> {
+    parse_name(
+        deserializer,
+        &[
+            ("Replace", MarkMode::Replace),
+            ("Add", MarkMode::Add),
+            ("Toggle", MarkMode::Toggle),
+        ],
+    )
+}
+
+pub(super) fn tab_indicator_position<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<TabIndicatorPosition, D::Error> {
+    parse_name(
+        deserializer,
+        &[
+            ("Left", TabIndicatorPosition::Left),
+            ("Right", TabIndicatorPosition::Right),
+            ("Top", TabIndicatorPosition::Top),
+            ("Bottom", TabIndicatorPosition::Bottom),
+        ],
+    )
+}
+
+/// Parses edges in the bitflags text format, like `"BOTTOM | RIGHT"`.
+pub(super) fn resize_edge<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<ResizeEdge, D::Error> {
+    let edges = String::deserialize(deserializer)?;
+    edges.parse().map_err(D::Error::custom)
+}
+
+/// Parses a rectangle from `[x, y, w, h]`.
+pub(super) fn rect<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Rectangle<i32, Logical>, D::Error> {
+    let (x, y, w, h) = <(i32, i32, i32, i32)>::deserialize(deserializer)?;
+    Ok(Rectangle::new(Point::from((x, y)), Size::from((w, h))))
+}
+
+pub(super) fn default_bbox() -> Rectangle<i32, Logical> {
+    TestWindowParams::new(0).bbox
+}
+
+/// Parses an optional size from `[w, h]`.
+pub(super) fn size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Size<i32, Logical>>, D::Error> {
+    let size = Option::<(i32, i32)>::deserialize(deserializer)?;
+    Ok(size.map(Size::from))
+}
+
+fn load(path: &Path) -> Result<Vec<Op>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("error reading: {err}"))?;
+    serde_json::from_str(&text).map_err(|err| format!("error parsing: {err}"))
+}
+
+fn script_paths() -> Vec<PathBuf> {
+    if let Some(path) = std::env::var_os("NIRI_LAYOUT_SCRIPT") {
+        return vec![PathBuf::from(path)];
+    }
+
+    let mut paths: Vec<_> = fs::read_dir(SCRIPTS_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn layout_scripts() {
+    let paths = script_paths();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let ops = load(&path).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+        eprintln!("running {}", path.display());
+        check_ops(ops);
+    }
+}
+
+#[test]
+fn script_fields_have_defaults() {
+    let ops: Vec<Op> = serde_json::from_str(
+        r#"[
+            {"AddWindow": {"params": {"id": 1}}},
+            {"SetForcedSize": {"id": 1, "size": [300, 200]}},
+            {"InteractiveResizeBegin": {"window": 1, "edges": "BOTTOM | RIGHT"}},
+            {"AddScaledOutput": {"id": 2, "scale": 1.5}},
+            "FocusParent"
+        ]"#,
+    )
+    .unwrap();
+
+    let Op::AddWindow { params } = &ops[0] else {
+        panic!("expected AddWindow, got {:?}", ops[0]);
+    };
+    assert_eq!(params.bbox, default_bbox());
+    assert_eq!(params.parent_id, None);
+    assert!(!params.is_floating);
+
+    assert!(matches!(
+        ops[1],
+        Op::SetForcedSize { id: 1, size: Some(size) } if size == Size::from((300, 200))
+    ));
+    assert!(matches!(
+        ops[2],
+        Op::InteractiveResizeBegin { edges, .. } if edges == ResizeEdge::BOTTOM_RIGHT
+    ));
+    assert!(matches!(
+        ops[3],
+        Op::AddScaledOutput {
+            layout_config: None,
+            ..
+        }
+    ));
+    assert!(matches!(ops[4], Op::FocusParent));
+}
@@ -1,22 +1,23 @@
 use core::f64;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
 use niri_config::utils::MergeWith as _;
-use niri_config::{Color, CornerRadius, GradientInterpolation, TabBar};
+use niri_config::{Color, CornerRadius, GradientInterpolation, ShadowRule, TabBar};
 use niri_ipc::WindowLayout;
 use smithay::backend::renderer::element::{Element, Kind};
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexProgram, GlesTexture};
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
 use smithay::wayland::compositor::{Blocker, BlockerState};
 
-use super::container::{Layout, TabBarTab};
+use super::container::{Direction, TabBarTab};
 use super::focus_ring::{
     FocusRing, FocusRingEdges, FocusRingIndicatorEdge, FocusRingRenderElement, FocusRingState,
 };
-use super::opening_window::{OpenAnimation, OpeningWindowRenderElement};
+use super::opening_window::{OpenAnimation, OpenCloseStyle, OpeningWindowRenderElement};
 use super::shadow::Shadow;
-use super::tab_bar::{render_tab_bar, TabBarRenderOutput};
+use super::tab_bar::{render_tab_bar, TabBarInset, TabBarRenderOutput};
 use super::{
     HitType, LayoutElement, LayoutElementRenderElement, LayoutElementRenderSnapshot, Options,
     SizeFrac, RESIZE_ANIMATION_THRESHOLD,
@@ -38,7 +39,8 @@ use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
 use crate::render_helpers::RenderTarget;
 use crate::utils::transaction::Transaction;
 use crate::utils::{
-    baba_is_float_offset, round_logical_in_physical, round_logical_in_physical_max1,
+    baba_is_float_offset, inset_by_safe_area, round_logical_in_physical,
+    round_logical_in_physical_max1,
 };
 
 /// Toplevel window with decorations.
@@ -71,6 +73,10 @@ pub struct Tile<W: LayoutElement> {
     pub(super) is_scratchpad: bool,
     /// Whether this tile is sticky (floating across workspaces on an output).
     pub(super) is_sticky: bool,
+    /// Whether this tile follows the focus across outputs, which it does instead of being sticky.
+    follows_focus: bool,
+    /// Whether this tile stays above other floating and fullscreen windows while floating.
+    is_always_on_top: bool,
 
     /// Marks assigned to this tile.
     marks: Vec<String>,
@@ -100,6 +106,11 @@ pub struct Tile<W: LayoutElement> {
     /// The animation upon opening a window.
     open_animation: Option<OpenAnimation>,
 
+    /// Edge of the parent container that this tile was inserted at.
+    ///
+    /// The slide open and close animations go through this edge.
+    insert_edge: Direction,
+
     /// The animation of the window resizing.
     resize_animation: Option<ResizeAnimation>,
 
@@ -115,6 +126,11 @@ pub struct Tile<W: LayoutElement> {
     /// The animation of the tile's opacity.
     pub(super) alpha_animation: Option<AlphaAnimation>,
 
+    /// Angle that `animate="follow-focus"` gradients point at, in degrees.
+    ///
+    /// Kept after the animation is done, since its value is the current angle.
+    focus_angle_animation: Option<Animation>,
+
     /// Offset during the initial interactive move rubberband.
     pub(super) interactive_move_offset: Point<f64, Logical>,
 
@@ -128,8 +144,13 @@ pub struct Tile<W: LayoutElement> {
     ///
     /// Used as the fullscreen target size.
     view_size: Size<f64, Logical>,
-    /// Extra vertical offset for tabbed/stacked layouts (tab bar height).
-    tab_bar_offset: f64,
+    /// Area this tile covers when fullscreen across several outputs.
+    ///
+    /// Relative to the tile's own output. When set, used instead of the view size as the
+    /// fullscreen target.
+    fullscreen_span: Option<Rectangle<f64, Logical>>,
+    /// Space for the tab bar in tabbed/stacked layouts, or for the title bar in split layouts.
+    tab_bar: TabBarInset,
     /// Whether this tile draws its own title bar (split layouts).
     draw_titlebar: bool,
     /// Cached title bar render data.
@@ -157,7 +178,7 @@ niri_render_elements! {
         Border = BorderRenderElement,
         Shadow = ShadowRenderElement,
         ClippedSurface = ClippedSurfaceRenderElement<R>,
-        TitleBar = PrimaryGpuTextureRenderElement,
+        Texture = PrimaryGpuTextureRenderElement,
         Offscreen = OffscreenRenderElement,
         ExtraDamage = ExtraDamage,
     }
@@ -303,7 +324,8 @@ impl<W: LayoutElement> Tile<W> {
         let rules = window.rules();
         let border_config = options.layout.border.merged_with(&rules.border);
         let focus_ring_config = options.layout.focus_ring.merged_with(&rules.focus_ring);
-        let shadow_config = options.layout.shadow.merged_with(&rules.shadow);
+        let shadow_config = shadow_config(&options, &rules.shadow);
+        let is_always_on_top = rules.open_always_on_top == Some(true);
         let sizing_mode = window.sizing_mode();
 
         Self {
@@ -316,6 +338,8 @@ impl<W: LayoutElement> Tile<W> {
             restore_to_floating: false,
             is_scratchpad: false,
             is_sticky: false,
+            follows_focus: false,
+            is_always_on_top,
             marks: Vec::new(),
             pending_maximized,
             floating_window_size: None,
@@ -323,16 +347,19 @@ impl<W: LayoutElement> Tile<W> {
             floating_preset_width_idx: None,
             floating_preset_height_idx: None,
             open_animation: None,
+            insert_edge: Direction::Down,
             resize_animation: None,
             pending_resize: None,
             move_x_animation: None,
             move_y_animation: None,
             alpha_animation: None,
+            focus_angle_animation: None,
             interactive_move_offset: Point::from((0., 0.)),
             unmap_snapshot: None,
             rounded_corner_damage: Default::default(),
             view_size,
-            tab_bar_offset: 0.0,
+            fullscreen_span: None,
+            tab_bar: TabBarInset::NONE,
             draw_titlebar: false,
             titlebar_cache: RefCell::new(None),
             render_active: false,
@@ -376,16 +403,17 @@ impl<W: LayoutElement> Tile<W> {
         focus_ring_config.width = round_max1(focus_ring_config.width);
         self.focus_ring.update_config(focus_ring_config);
 
-        let shadow_config = self.options.layout.shadow.merged_with(&rules.shadow);
+        let shadow_config = shadow_config(&self.options, &rules.shadow);
         self.shadow.update_config(shadow_config);
     }
 
-    pub(super) fn set_tab_bar_offset(&mut self, offset: f64) {
-        self.tab_bar_offset = offset.max(0.0);
+    pub(super) fn set_tab_bar_inset(&mut self, mut inset: TabBarInset) {
+        inset.size = inset.size.max(0.0);
+        self.tab_bar = inset;
     }
 
-    pub(super) fn tab_bar_offset(&self) -> f64 {
-        self.tab_bar_offset
+    pub(super) fn tab_bar_inset(&self) -> TabBarInset {
+        self.tab_bar
     }
 
     pub(super) fn set_draw_titlebar(&mut self, draw: bool) {
@@ -433,15 +461,15 @@ impl<W: LayoutElement> Tile<W> {
 
                 let mut tile_size = animate_from.size;
                 if prev_sizing_mode.is_fullscreen() {
-                    tile_size.w = f64::max(tile_size.w, self.view_size.w);
-                    tile_size.h = f64::max(tile_size.h, self.view_size.h);
+                    tile_size.w = f64::max(tile_size.w, self.fullscreen_size().w);
+                    tile_size.h = f64::max(tile_size.h, self.fullscreen_size().h);
                 } else if prev_sizing_mode.is_normal() && !self.border.is_off() {
                     let width = self.border.width();
                     tile_size.w += width * 2.;
                     tile_size.h += width * 2.;
                 }
-                if prev_sizing_mode.is_normal() && self.tab_bar_offset > 0.0 {
-                    tile_size.h += self.tab_bar_offset;
+                if prev_sizing_mode.is_normal() {
+                    tile_size += self.tab_bar.extent();
                 }
 
                 tile_size.w = tile_size_from.w + (tile_size.w - tile_size_from.w) * val;
@@ -475,15 +503,15 @@ impl<W: LayoutElement> Tile<W> {
                 // Compute like in tile_size().
                 let mut tile_size = size;
                 if prev_sizing_mode.is_fullscreen() {
-                    tile_size.w = f64::max(tile_size.w, self.view_size.w);
-                    tile_size.h = f64::max(tile_size.h, self.view_size.h);
+                    tile_size.w = f64::max(tile_size.w, self.fullscreen_size().w);
+                    tile_size.h = f64::max(tile_size.h, self.fullscreen_size().h);
                 } else if prev_sizing_mode.is_normal() && !self.border.is_off() {
                     let width = self.border.width();
                     tile_size.w += width * 2.;
                     tile_size.h += width * 2.;
                 }
-                if prev_sizing_mode.is_normal() && self.tab_bar_offset > 0.0 {
-                    tile_size.h += self.tab_bar_offset;
+                if prev_sizing_mode.is_normal() {
+                    tile_size += self.tab_bar.extent();
                 }
 
                 let fullscreen_from = if prev_sizing_mode.is_fullscreen() {
@@ -558,7 +586,7 @@ impl<W: LayoutElement> Tile<W> {
         focus_ring_config.width = round_max1(focus_ring_config.width);
         self.focus_ring.update_config(focus_ring_config);
 
-        let shadow_config = self.options.layout.shadow.merged_with(&rules.shadow);
+        let shadow_config = shadow_config(&self.options, &rules.shadow);
         self.shadow.update_config(shadow_config);
 
         let window_size = self.window_size();
@@ -602,7 +630,14 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
-        self.are_transitions_ongoing() || self.window.rules().baba_is_float == Some(true)
+        self.are_transitions_ongoing()
+            || self.window.rules().baba_is_float == Some(true)
+            || self
+                .focus_angle_animation
+                .as_ref()
+                .is_some_and(|anim| !anim.is_done())
+            || (!self.options.animations.off
+                && (self.border.is_rotating() || self.focus_ring.is_rotating()))
     }
 
     pub fn are_transitions_ongoing(&self) -> bool {
@@ -641,7 +676,7 @@ impl<W: LayoutElement> Tile<W> {
         let mut draw_border_with_background = rules
             .draw_border_with_background
             .unwrap_or_else(|| !self.window.has_ssd());
-        if self.tab_bar_offset > 0.0 {
+        if !self.tab_bar.is_none() {
             draw_border_with_background = false;
         }
         let mut draw_focus_ring_with_background = if self.border.is_off() {
@@ -649,7 +684,7 @@ impl<W: LayoutElement> Tile<W> {
         } else {
             false
         };
-        if self.tab_bar_offset > 0.0 {
+        if !self.tab_bar.is_none() {
             draw_focus_ring_with_background = false;
         }
         let border_is_border = !draw_border_with_background && !self.border.is_off();
@@ -672,6 +707,21 @@ impl<W: LayoutElement> Tile<W> {
         border_window_size.w -= border_width * 2.;
         border_window_size.h -= border_width * 2.;
 
+        // Rotating gradients stand still with animations off.
+        let gradient_time = if self.options.animations.off {
+            Duration::ZERO
+        } else {
+            self.clock.now()
+        };
+        let focus_angle = self
+            .focus_angle_animation
+            .as_ref()
+            .map(|anim| anim.value() as f32);
+        self.border
+            .set_gradient_animation(gradient_time, focus_angle);
+        self.focus_ring
+            .set_gradient_animation(gradient_time, focus_angle);
+
         let radius = rules
             .geometry_corner_radius
             .map_or(CornerRadius::default(), |radius| {
@@ -733,7 +783,7 @@ impl<W: LayoutElement> Tile<W> {
         target: RenderTarget,
         push: &mut dyn FnMut(TileRenderElement<R>),
     ) {
-        if !self.draw_titlebar || self.tab_bar_offset <= 0.0 {
+        if !self.draw_titlebar || self.tab_bar.is_none() {
             return;
         }
 
@@ -741,7 +791,7 @@ impl<W: LayoutElement> Tile<W> {
         let tile_size = self.animated_tile_size();
         let inner_width = (tile_size.w - border_width * 2.0).max(0.0);
         let inner_height = (tile_size.h - border_width * 2.0).max(0.0);
-        let bar_height = self.tab_bar_offset.min(inner_height);
+        let bar_height = self.tab_bar.size.min(inner_height);
 
         if inner_width <= 0.0 || bar_height <= 0.0 {
             return;
@@ -780,6 +830,8 @@ impl<W: LayoutElement> Tile<W> {
                     title,
                     is_focused,
                     is_urgent,
+                    // The window is visible anyway, so activity doesn't need an indicator.
+                    has_activity: false,
                     block_out_from,
                 }];
 
@@ -787,7 +839,7 @@ impl<W: LayoutElement> Tile<W> {
                 match render_tab_bar(
                     gles,
                     &state.config,
-                    Layout::Tabbed,
+                    true,
                     rect,
                     state.row_height,
                     &tabs,
@@ -816,7 +868,7 @@ impl<W: LayoutElement> Tile<W> {
             None,
             Kind::Unspecified,
         );
-        push(TileRenderElement::TitleBar(PrimaryGpuTextureRenderElement(elem)));
+        push(PrimaryGpuTextureRenderElement(elem).into());
     }
 
     pub fn scale(&self) -> f64 {
@@ -839,13 +891,38 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn start_open_animation(&mut self) {
-        self.open_animation = Some(OpenAnimation::new(Animation::new(
+        let anim = Animation::new(
             self.clock.clone(),
             0.,
             1.,
             0.,
             self.options.animations.window_open.anim,
-        )));
+        );
+        self.open_animation = Some(OpenAnimation::new(anim, self.open_style()));
+    }
+
+    pub fn stop_open_animation(&mut self) {
+        self.open_animation = None;
+    }
+
+    pub fn set_insert_edge(&mut self, edge: Direction) {
+        self.insert_edge = edge;
+    }
+
+    pub fn open_style(&self) -> OpenCloseStyle {
+        let style = self.window.rules().open_animation;
+        OpenCloseStyle {
+            style: style.unwrap_or(self.options.animations.window_open.style),
+            edge: self.insert_edge,
+        }
+    }
+
+    pub fn close_style(&self) -> OpenCloseStyle {
+        let style = self.window.rules().close_animation;
+        OpenCloseStyle {
+            style: style.unwrap_or(self.options.animations.window_close.style),
+            edge: self.insert_edge,
+        }
     }
 
     pub fn resize_animation(&self) -> Option<&Animation> {
@@ -856,6 +933,21 @@ impl<W: LayoutElement> Tile<W> {
         self.resize_animation = None;
     }
 
+    /// Turns `animate="follow-focus"` gradients to point at the angle, in degrees.
+    pub fn animate_focus_angle(&mut self, angle: f64) {
+        let config = self.options.animations.window_movement.0;
+        let anim = match &self.focus_angle_animation {
+            Some(anim) => {
+                // Turn the shorter way around.
+                let current = anim.value();
+                let delta = (angle - current + 180.).rem_euclid(360.) - 180.;
+                Animation::new(self.clock.clone(), current, current + delta, 0., config)
+            }
+            None => Animation::new(self.clock.clone(), angle, angle, 0., config),
+        };
+        self.focus_angle_animation = Some(anim);
+    }
+
     pub fn animate_move_from(&mut self, from: Point<f64, Logical>) {
         self.animate_move_x_from(from.x);
         self.animate_move_y_from(from.y);
@@ -972,11 +1064,32 @@ impl<W: LayoutElement> Tile<W> {
         self.is_sticky = sticky;
     }
 
+    pub(super) fn follows_focus(&self) -> bool {
+        self.follows_focus
+    }
+
+    pub(super) fn set_follows_focus(&mut self, follows_focus: bool) {
+        self.follows_focus = follows_focus;
+    }
+
+    pub(super) fn is_always_on_top(&self) -> bool {
+        self.is_always_on_top
+    }
+
+    pub(super) fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.is_always_on_top = always_on_top;
+    }
+
     #[allow(dead_code)]
     pub(super) fn marks(&self) -> &[String] {
         &self.marks
     }
 
+    pub(super) fn refresh_title(&mut self, workspace_name: Option<&str>, workspace_idx: usize) {
+        self.window
+            .refresh_title(workspace_name, workspace_idx, &self.marks);
+    }
+
     pub(super) fn has_mark(&self, mark: &str) -> bool {
         self.marks.iter().any(|m| m == mark)
     }
@@ -1064,7 +1177,9 @@ impl<W: LayoutElement> Tile<W> {
 
         let window_size = self.animated_window_size();
         let target_size = self.animated_tile_size();
-        let available_height = (target_size.h - self.tab_bar_offset).max(0.0);
+        let bar_extent = self.tab_bar.extent();
+        let available_width = (target_size.w - bar_extent.w).max(0.0);
+        let available_height = (target_size.h - bar_extent.h).max(0.0);
 
         // Center the window within its tile.
         //
@@ -1074,8 +1189,19 @@ impl<W: LayoutElement> Tile<W> {
         //   the tile size matches the window.
         // - During animations, the window remains centered within the tile; this is important for
         //   the to/from fullscreen animation.
-        loc.x += (target_size.w - window_size.w) / 2.;
-        loc.y += self.tab_bar_offset + (available_height - window_size.h) / 2.;
+        loc += self.tab_bar.offset();
+        loc.x += (available_width - window_size.w) / 2.;
+        loc.y += (available_height - window_size.h) / 2.;
+
+        // In fullscreen, center the window within the safe area instead.
+        if self.fullscreen_span.is_none() {
+            let safe_area = self.options.layout.safe_area;
+            let offset = Point::from((
+                safe_area.left.0 - safe_area.right.0,
+                safe_area.top.0 - safe_area.bottom.0,
+            ));
+            loc += offset.downscale(2.).upscale(self.fullscreen_progress());
+        }
 
         // Round to physical pixels.
         loc = loc
@@ -1091,8 +1217,8 @@ impl<W: LayoutElement> Tile<W> {
         if self.sizing_mode.is_fullscreen() {
             // Normally we'd just return the fullscreen size here, but this makes things a bit
             // nicer if a fullscreen window is bigger than the fullscreen size for some reason.
-            size.w = f64::max(size.w, self.view_size.w);
-            size.h = f64::max(size.h, self.view_size.h);
+            size.w = f64::max(size.w, self.fullscreen_size().w);
+            size.h = f64::max(size.h, self.fullscreen_size().h);
             return size;
         }
 
@@ -1100,9 +1226,7 @@ impl<W: LayoutElement> Tile<W> {
             size.w += width * 2.;
             size.h += width * 2.;
         }
-        if self.tab_bar_offset > 0.0 {
-            size.h += self.tab_bar_offset;
-        }
+        size += self.tab_bar.extent();
 
         size
     }
@@ -1113,8 +1237,8 @@ impl<W: LayoutElement> Tile<W> {
         if self.sizing_mode.is_fullscreen() {
             // Normally we'd just return the fullscreen size here, but this makes things a bit
             // nicer if a fullscreen window is bigger than the fullscreen size for some reason.
-            size.w = f64::max(size.w, self.view_size.w);
-            size.h = f64::max(size.h, self.view_size.h);
+            size.w = f64::max(size.w, self.fullscreen_size().w);
+            size.h = f64::max(size.h, self.fullscreen_size().h);
             return size;
         }
 
@@ -1122,9 +1246,7 @@ impl<W: LayoutElement> Tile<W> {
             size.w += width * 2.;
             size.h += width * 2.;
         }
-        if self.tab_bar_offset > 0.0 {
-            size.h += self.tab_bar_offset;
-        }
+        size += self.tab_bar.extent();
 
         size
     }
@@ -1235,6 +1357,9 @@ impl<W: LayoutElement> Tile<W> {
             tile_pos_in_workspace_view: None,
             window_offset_in_tile: self.window_loc().into(),
             is_sticky: self.is_sticky(),
+            is_following_focus: self.follows_focus(),
+            is_always_on_top: self.is_always_on_top(),
+            pos_in_floating_stack: None,
         }
     }
 
@@ -1277,8 +1402,10 @@ impl<W: LayoutElement> Tile<W> {
             size.w = f64::max(1., size.w - width * 2.);
             size.h = f64::max(1., size.h - width * 2.);
         }
-        if self.tab_bar_offset > 0.0 {
-            size.h = f64::max(1., size.h - self.tab_bar_offset);
+        if !self.tab_bar.is_none() {
+            let extent = self.tab_bar.extent();
+            size.w = f64::max(1., size.w - extent.w);
+            size.h = f64::max(1., size.h - extent.h);
         }
 
         // The size request has to be i32 unfortunately, due to Wayland. We floor here instead of
@@ -1295,7 +1422,7 @@ impl<W: LayoutElement> Tile<W> {
     pub(super) fn requested_window_size_for_tile(
         &self,
         mut size: Size<f64, Logical>,
-        tab_bar_offset: f64,
+        tab_bar: TabBarInset,
     ) -> Size<i32, Logical> {
         // Match request_tile_size() sizing logic without mutating state.
         if !self.border.is_off() {
@@ -1303,18 +1430,20 @@ impl<W: LayoutElement> Tile<W> {
             size.w = f64::max(1., size.w - width * 2.);
             size.h = f64::max(1., size.h - width * 2.);
         }
-        if tab_bar_offset > 0.0 {
-            size.h = f64::max(1., size.h - tab_bar_offset);
+        if !tab_bar.is_none() {
+            let extent = tab_bar.extent();
+            size.w = f64::max(1., size.w - extent.w);
+            size.h = f64::max(1., size.h - extent.h);
         }
         size.to_i32_floor()
     }
 
     pub fn tile_width_for_window_width(&self, size: f64) -> f64 {
-        if self.border.is_off() {
-            size
-        } else {
-            size + self.border.width() * 2.
+        let mut size = size;
+        if !self.border.is_off() {
+            size += self.border.width() * 2.;
         }
+        size + self.tab_bar.extent().w
     }
 
     pub fn tile_height_for_window_height(&self, size: f64) -> f64 {
@@ -1322,10 +1451,11 @@ impl<W: LayoutElement> Tile<W> {
         if !self.border.is_off() {
             size += self.border.width() * 2.;
         }
-        size + self.tab_bar_offset
+        size + self.tab_bar.extent().h
     }
 
     pub fn window_width_for_tile_width(&self, size: f64) -> f64 {
+        let size = size - self.tab_bar.extent().w;
         if self.border.is_off() {
             size
         } else {
@@ -1334,7 +1464,7 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn window_height_for_tile_height(&self, size: f64) -> f64 {
-        let size = size - self.tab_bar_offset;
+        let size = size - self.tab_bar.extent().h;
         if self.border.is_off() {
             size
         } else {
@@ -1360,7 +1490,7 @@ impl<W: LayoutElement> Tile<W> {
     pub fn request_fullscreen(&mut self, animate: bool, transaction: Option<Transaction>) {
         self.record_pending_resize(transaction.as_ref());
         self.window.request_size(
-            self.view_size.to_i32_round(),
+            self.fullscreen_window_size().to_i32_round(),
             SizingMode::Fullscreen,
             animate,
             transaction,
@@ -1380,9 +1510,7 @@ impl<W: LayoutElement> Tile<W> {
             size.w += width * 2.;
             size.h += width * 2.;
         }
-        if self.tab_bar_offset > 0.0 {
-            size.h += self.tab_bar_offset;
-        }
+        size += self.tab_bar.extent();
 
         size
     }
@@ -1401,8 +1529,12 @@ impl<W: LayoutElement> Tile<W> {
                 size.h += width * 2.;
             }
         }
-        if self.tab_bar_offset > 0.0 && size.h > 0.0 {
-            size.h += self.tab_bar_offset;
+        let extent = self.tab_bar.extent();
+        if size.w > 0. {
+            size.w += extent.w;
+        }
+        if size.h > 0. {
+            size.h += extent.h;
         }
 
         size
@@ -1413,7 +1545,7 @@ impl<W: LayoutElement> Tile<W> {
             return Point::from((0., 0.));
         }
 
-        let y = baba_is_float_offset(self.clock.now(), self.view_size.h);
+        let y = baba_is_float_offset(self.clock.now(), self.fullscreen_size().h);
         let y = round_logical_in_physical(self.scale, y);
         Point::from((0., y))
     }
@@ -1487,6 +1619,23 @@ impl<W: LayoutElement> Tile<W> {
             &mut |elem| push(elem.into()),
         );
 
+        // The unresponsive prompt goes over the window contents.
+        if let Some(buffer) = self.window.render_unresponsive_prompt(
+            renderer.as_gles_renderer(),
+            self.scale,
+            self.options.ui_font.as_deref(),
+        ) {
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer,
+                window_render_loc,
+                win_alpha,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            push(PrimaryGpuTextureRenderElement(elem).into());
+        }
+
         // If we're resizing, try to render a shader, or a fallback.
         let mut pushed_resize = false;
         if let Some(resize) = &self.resize_animation {
@@ -1811,6 +1960,45 @@ impl<W: LayoutElement> Tile<W> {
         self.view_size
     }
 
+    /// Returns the fullscreen target size, taking the fullscreen span into account.
+    pub fn fullscreen_size(&self) -> Size<f64, Logical> {
+        self.fullscreen_span.map_or(self.view_size, |span| span.size)
+    }
+
+    /// Returns the size requested from a fullscreen window.
+    ///
+    /// This excludes the output safe area, which remains covered by the fullscreen backdrop.
+    /// Fullscreen spans ignore the safe area since they cover several outputs.
+    fn fullscreen_window_size(&self) -> Size<f64, Logical> {
+        let size = self.fullscreen_size();
+        if self.fullscreen_span.is_some() {
+            return size;
+        }
+
+        inset_by_safe_area(Rectangle::from_size(size), self.options.layout.safe_area).size
+    }
+
+    /// Returns the location of the fullscreen tile relative to its view.
+    pub fn fullscreen_loc(&self) -> Point<f64, Logical> {
+        self.fullscreen_span.map_or_else(Point::default, |span| span.loc)
+    }
+
+    pub fn fullscreen_span(&self) -> Option<Rectangle<f64, Logical>> {
+        self.fullscreen_span
+    }
+
+    /// Sets the area this tile should cover when fullscreen, relative to its output.
+    ///
+    /// Returns `true` if the span changed.
+    pub fn set_fullscreen_span(&mut self, span: Option<Rectangle<f64, Logical>>) -> bool {
+        if self.fullscreen_span == span {
+            return false;
+        }
+
+        self.fullscreen_span = span;
+        true
+    }
+
     #[cfg(test)]
     pub fn verify_invariants(&self) {
         use approx::assert_abs_diff_eq;
@@ -1825,6 +2013,13 @@ impl<W: LayoutElement> Tile<W> {
     }
 }
 
+fn shadow_config(options: &Options, rule: &ShadowRule) -> niri_config::Shadow {
+    let mut config = options.layout.shadow.merged_with(rule);
+    // Shadows are expensive to draw, so they can go away to save power.
+    config.on &= !options.disable_shadows;
+    config
+}
+
 // ============================================================================
 // Generic Tile Iterators
 // ============================================================================
@@ -15,15 +15,17 @@ use std::rc::Rc;
 use std::time::Duration;
 
 use niri_config::utils::MergeWith as _;
-use niri_config::{Border, HideEdgeBorders, PresetSize, TabBar};
-use niri_ipc::{ColumnDisplay, LayoutTreeNode, SizeChange};
+use niri_config::{Border, HideEdgeBorders, PresetSize, TabBar, TabIndicatorPosition};
+use niri_ipc::{
+    ColumnDisplay, LayoutTreeNode, LayoutViolation, ResizeAmount, ResizeDirection, SizeChange,
+};
 use smithay::backend::renderer::element::Kind;
 use smithay::utils::{Logical, Physical, Point, Rectangle, Scale, Size};
 
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
 use super::container::{
-    ContainerTree, DetachedContainer, DetachedNode, Direction, InsertParentInfo, Layout,
-    LeafLayoutInfo,
+    container_window_rules, normalized_percents, ContainerTree, DetachedContainer, DetachedNode,
+    Direction, InsertParentInfo, Layout, LeafLayoutInfo, TreeTemplate,
 };
 use super::monitor::{InsertPosition, SplitIndicator};
 use super::focus_ring::{FocusRingEdges, FocusRingIndicatorEdge};
@@ -76,6 +78,8 @@ pub struct TilingSpace<W: LayoutElement> {
     fullscreen_window: Option<W::Id>,
     /// Windows in the closing animation.
     closing_windows: Vec<ClosingWindow>,
+    /// Focused window and the center of its tile, as of the last render elements update.
+    last_focus: Option<(W::Id, Point<f64, Logical>)>,
 }
 
 #[derive(Debug, Clone)]
@@ -703,7 +707,13 @@ impl<W: LayoutElement> TilingSpace<W> {
         clock: Clock,
         options: Rc<Options>,
     ) -> Self {
-        let tree = ContainerTree::new(view_size, working_area, scale, options.clone());
+        let tree = ContainerTree::new(
+            view_size,
+            working_area,
+            scale,
+            clock.clone(),
+            options.clone(),
+        );
 
         Self {
             tree,
@@ -718,6 +728,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             is_active: false,
             fullscreen_window: None,
             closing_windows: Vec::new(),
+            last_focus: None,
         }
     }
 
@@ -738,6 +749,11 @@ impl<W: LayoutElement> TilingSpace<W> {
         self.tree.focused_window_mut()
     }
 
+    pub fn fullscreen_tile(&self) -> Option<&Tile<W>> {
+        let id = self.fullscreen_window.as_ref()?;
+        self.tiles().find(|tile| tile.window().id() == id)
+    }
+
     pub fn is_active_pending_fullscreen(&self) -> bool {
         self.tree
             .focused_tile()
@@ -763,6 +779,10 @@ impl<W: LayoutElement> TilingSpace<W> {
         &self.options
     }
 
+    pub fn check_invariants(&self) -> Vec<LayoutViolation> {
+        self.tree.check_invariants()
+    }
+
     pub fn verify_invariants(&self) {
         debug_assert!(
             self.tree.leaf_layouts().len() <= self.tree.window_count(),
@@ -793,6 +813,34 @@ impl<W: LayoutElement> TilingSpace<W> {
         self.tree.layout();
     }
 
+    /// Splits the focused tile along its longer side so that the next window takes half of it.
+    fn auto_split_focused(&mut self) {
+        // New windows in tabs and stacks become tabs, same as without auto-split.
+        if matches!(
+            self.tree.focused_layout(),
+            Some(Layout::Tabbed | Layout::Stacked)
+        ) {
+            return;
+        }
+
+        // Go by the laid out rectangle since the window may not have resized yet.
+        let focus_path = self.tree.focus_path();
+        let leaves = self
+            .tree
+            .pending_leaf_layouts()
+            .unwrap_or(self.tree.leaf_layouts());
+        let Some(leaf) = leaves.iter().find(|leaf| leaf.path == focus_path) else {
+            return;
+        };
+        let size = leaf.rect.size;
+        let layout = if size.w >= size.h {
+            Layout::SplitH
+        } else {
+            Layout::SplitV
+        };
+        self.tree.split_focused(layout);
+    }
+
     pub fn remove_window(&mut self, window: &W) -> Option<RemovedTile<W>> {
         let window_id = window.id();
         let tile = self.tree.remove_window(&window_id)?;
@@ -848,6 +896,8 @@ impl<W: LayoutElement> TilingSpace<W> {
         let estimated_capacity = tile_count * 4 + self.closing_windows.len() + tile_count / 2;
         let mut elements = Vec::with_capacity(estimated_capacity);
         let mut active_elements = Vec::with_capacity(8);
+        // Shadows go below all tiles and tab bars, so that they don't bleed over adjacent tiles.
+        let mut shadow_elements = Vec::new();
         let scale = Scale::from(self.scale);
         let focus_path = self.tree.focus_path();
         let fullscreen_id = self.fullscreen_window.as_ref();
@@ -872,10 +922,7 @@ impl<W: LayoutElement> TilingSpace<W> {
                 let mut pos = info.rect.loc + tile.render_offset();
                 pos = pos.to_physical_precise_round(scale).to_logical(scale);
                 if is_fullscreen_tile {
-                    pos = Point::from((0.0, 0.0));
-                }
-                if is_fullscreen_tile {
-                    pos = Point::from((0.0, 0.0));
+                    pos = tile.fullscreen_loc();
                 }
 
                 let is_focused = self.is_active && info.path == focus_path;
@@ -886,7 +933,11 @@ impl<W: LayoutElement> TilingSpace<W> {
                     &mut elements
                 };
                 tile.render(renderer, pos, draw_focus, is_focused, target, &mut |elem| {
-                    target_elements.push(TilingSpaceRenderElement::from(elem));
+                    if matches!(elem, TileRenderElement::Shadow(_)) {
+                        shadow_elements.push(TilingSpaceRenderElement::from(elem));
+                    } else {
+                        target_elements.push(TilingSpaceRenderElement::from(elem));
+                    }
                 });
             }
         }
@@ -916,7 +967,7 @@ impl<W: LayoutElement> TilingSpace<W> {
                     _ => match render_tab_bar(
                         gles,
                         &tab_bar_config,
-                        info.layout,
+                        info.is_single_row(),
                         info.rect,
                         info.row_height,
                         &info.tabs,
@@ -964,6 +1015,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             self.tab_bar_cache.borrow_mut().clear();
         }
 
+        elements.extend(shadow_elements);
         elements
     }
 
@@ -1060,6 +1112,7 @@ impl<W: LayoutElement> TilingSpace<W> {
                 (edges, indicator_edge)
             })
             .collect();
+        self.update_focus_angle(&render_layouts, &focus_path);
 
         for info in state_layouts {
             // Use O(1) key lookup instead of O(depth) path lookup.
@@ -1080,7 +1133,6 @@ impl<W: LayoutElement> TilingSpace<W> {
                     self.working_area.size,
                     &self.options,
                     fullscreen_id,
-                    self.view_size,
                 );
             }
         }
@@ -1092,14 +1144,13 @@ impl<W: LayoutElement> TilingSpace<W> {
 
                 let mut pos = info.rect.loc + tile.render_offset();
                 pos = pos.to_physical_precise_round(scale).to_logical(scale);
+                if is_fullscreen_tile {
+                    pos = tile.fullscreen_loc();
+                }
 
                 let mut tile_view_rect = workspace_view;
                 tile_view_rect.loc -= pos;
 
-                if is_fullscreen_tile {
-                    tile_view_rect = workspace_view;
-                }
-
                 let show_tile = fullscreen_id.map_or(info.visible, |_| is_fullscreen_tile);
                 if show_tile {
                     let is_focused = is_active && info.path == focus_path;
@@ -1465,6 +1516,13 @@ impl<W: LayoutElement> TilingSpace<W> {
         }
     }
 
+    /// Set the tab bar position of the focused container.
+    pub fn set_tab_bar_position(&mut self, position: TabIndicatorPosition) {
+        if self.tree.set_focused_tab_bar_position(position) {
+            self.tree.layout();
+        }
+    }
+
     /// Set the width of the currently focused root-level column
     pub fn set_column_width(&mut self, change: SizeChange) {
         let Some(idx) = self.tree.focused_root_index() else {
@@ -1518,34 +1576,11 @@ impl<W: LayoutElement> TilingSpace<W> {
         let currently = self.is_fullscreen(window);
         let _ = self.set_fullscreen(window.id(), !currently);
     }
+    /// Cycles the share of the selected node within its closest horizontal split through the
+    /// preset column widths.
     pub fn toggle_width(&mut self, forwards: bool) {
-        let Some(idx) = self.tree.focused_root_index() else {
-            return;
-        };
-
-        let Some((layout, rect, child_count)) = self.tree.container_info(&[]) else {
-            return;
-        };
-        if layout != Layout::SplitH || child_count == 0 {
-            return;
-        }
-
-        let available = self.available_span(rect.size.w, child_count);
-        if available <= 0.0 {
-            return;
-        }
-
-        let current_percent = self.tree.child_percent_at(&[], idx).unwrap_or(1.0);
-        let presets = &self.options.layout.preset_column_widths;
-
-        if let Some(percent) = self.cycle_presets(available, current_percent, presets, forwards) {
-            if self
-                .tree
-                .set_child_percent_at(&[], idx, Layout::SplitH, percent)
-            {
-                self.tree.layout();
-            }
-        }
+        let presets = self.options.layout.preset_column_widths.clone();
+        self.toggle_window_dimension(None, Layout::SplitH, &presets, forwards);
     }
 
     /// View offset (not used in i3-style layout, always 0).
@@ -1564,6 +1599,31 @@ impl<W: LayoutElement> TilingSpace<W> {
         self.tree.focused_root_index().unwrap_or(0)
     }
 
+    /// Layout rects of the tiles currently shown, along with the area they must fit in.
+    ///
+    /// Hidden tabs, as well as fullscreen and maximized tiles, are skipped.
+    #[cfg(test)]
+    pub fn visible_leaf_rects(&self) -> (Vec<Rectangle<f64, Logical>>, Rectangle<f64, Logical>) {
+        let rects = self
+            .tree
+            .leaf_layouts()
+            .iter()
+            .filter(|info| info.visible)
+            .filter(|info| {
+                self.tree.get_tile(info.key).is_some_and(|tile| {
+                    !tile.pending_maximized && !tile.window().pending_sizing_mode().is_fullscreen()
+                })
+            })
+            .map(|info| info.rect)
+            .collect();
+        (rects, self.layout_area())
+    }
+
+    #[cfg(test)]
+    pub fn tab_bar_layouts(&self) -> Vec<super::container::TabBarInfo> {
+        self.tree.tab_bar_layouts()
+    }
+
     fn layout_area(&self) -> Rectangle<f64, Logical> {
         let mut area = self.working_area;
         let gap = self.options.layout.gaps;
@@ -1713,20 +1773,27 @@ impl<W: LayoutElement> TilingSpace<W> {
         )
     }
 
+    /// Thickness of the layout border drop zones, kept at the same size on screen when the
+    /// workspace is zoomed out in the overview.
+    fn drop_layout_border(zoom: f64) -> f64 {
+        Self::DROP_LAYOUT_BORDER / zoom
+    }
+
     /// Determine insert position from pointer location
-    pub(super) fn insert_position(&self, pos: Point<f64, Logical>) -> InsertPosition {
+    pub(super) fn insert_position(&self, pos: Point<f64, Logical>, zoom: f64) -> InsertPosition {
         if self.tree.is_empty() {
             return InsertPosition::NewColumn(0);
         }
 
+        let border = Self::drop_layout_border(zoom);
         let layout_area = self.layout_area();
-        if pos.y < layout_area.loc.y + Self::DROP_LAYOUT_BORDER {
+        if pos.y < layout_area.loc.y + border {
             return InsertPosition::SplitRoot {
                 direction: Direction::Up,
                 indicator: SplitIndicator::LayoutBorder,
             };
         }
-        if pos.y > layout_area.loc.y + layout_area.size.h - Self::DROP_LAYOUT_BORDER {
+        if pos.y > layout_area.loc.y + layout_area.size.h - border {
             return InsertPosition::SplitRoot {
                 direction: Direction::Down,
                 indicator: SplitIndicator::LayoutBorder,
@@ -1743,14 +1810,14 @@ impl<W: LayoutElement> TilingSpace<W> {
             .unwrap_or(Layout::SplitH);
 
         if matches!(parent_layout, Layout::SplitH | Layout::Tabbed) {
-            if pos.y < rect.loc.y + Self::DROP_LAYOUT_BORDER {
+            if pos.y < rect.loc.y + border {
                 return InsertPosition::Split {
                     path,
                     direction: Direction::Up,
                     indicator: SplitIndicator::LayoutBorder,
                 };
             }
-            if pos.y > rect.loc.y + rect.size.h - Self::DROP_LAYOUT_BORDER {
+            if pos.y > rect.loc.y + rect.size.h - border {
                 return InsertPosition::Split {
                     path,
                     direction: Direction::Down,
@@ -1758,14 +1825,14 @@ impl<W: LayoutElement> TilingSpace<W> {
                 };
             }
         } else if matches!(parent_layout, Layout::SplitV | Layout::Stacked) {
-            if pos.x < rect.loc.x + Self::DROP_LAYOUT_BORDER {
+            if pos.x < rect.loc.x + border {
                 return InsertPosition::Split {
                     path,
                     direction: Direction::Left,
                     indicator: SplitIndicator::LayoutBorder,
                 };
             }
-            if pos.x > rect.loc.x + rect.size.w - Self::DROP_LAYOUT_BORDER {
+            if pos.x > rect.loc.x + rect.size.w - border {
                 return InsertPosition::Split {
                     path,
                     direction: Direction::Right,
@@ -1791,6 +1858,7 @@ impl<W: LayoutElement> TilingSpace<W> {
     pub(super) fn insert_hint_area(
         &self,
         position: &InsertPosition,
+        zoom: f64,
     ) -> Option<Rectangle<f64, Logical>> {
         match position {
             InsertPosition::NewColumn(_) => Some(self.layout_area()),
@@ -1806,7 +1874,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             } => {
                 let rect = self.leaf_rect_for_path(path)?;
                 let thickness = match indicator {
-                    SplitIndicator::LayoutBorder => Self::DROP_LAYOUT_BORDER,
+                    SplitIndicator::LayoutBorder => Self::drop_layout_border(zoom),
                     SplitIndicator::Center => {
                         f64::min(rect.size.w, rect.size.h) * Self::DROP_CENTER_RATIO
                     }
@@ -1816,7 +1884,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             InsertPosition::SplitRoot { direction, indicator } => {
                 let rect = self.layout_area();
                 let thickness = match indicator {
-                    SplitIndicator::LayoutBorder => Self::DROP_LAYOUT_BORDER,
+                    SplitIndicator::LayoutBorder => Self::drop_layout_border(zoom),
                     SplitIndicator::Center => {
                         f64::min(rect.size.w, rect.size.h) * Self::DROP_CENTER_RATIO
                     }
@@ -1863,57 +1931,53 @@ impl<W: LayoutElement> TilingSpace<W> {
                 .position(|tab| tab.is_focused)
                 .unwrap_or(0);
 
-            let tab_idx = match info.layout {
-                Layout::Tabbed => {
-                    if pos_px.y >= row_height_px {
-                        focused_idx
-                    } else if let Some(widths) = cache.get(&info.path).and_then(|entry| {
-                        if entry.tab_widths_px.len() == tab_count {
-                            Some(entry.tab_widths_px.as_slice())
-                        } else {
-                            None
+            let tab_idx = if info.is_single_row() {
+                if pos_px.y >= row_height_px {
+                    focused_idx
+                } else if let Some(widths) = cache.get(&info.path).and_then(|entry| {
+                    if entry.tab_widths_px.len() == tab_count {
+                        Some(entry.tab_widths_px.as_slice())
+                    } else {
+                        None
+                    }
+                }) {
+                    let mut cursor = 0;
+                    let mut found = None;
+                    for (idx, width) in widths.iter().enumerate() {
+                        let end = cursor + *width;
+                        if pos_px.x < end {
+                            found = Some(idx);
+                            break;
                         }
-                    }) {
-                        let mut cursor = 0;
-                        let mut found = None;
-                        for (idx, width) in widths.iter().enumerate() {
-                            let end = cursor + *width;
-                            if pos_px.x < end {
-                                found = Some(idx);
-                                break;
-                            }
-                            cursor = end;
+                        cursor = end;
+                    }
+                    found.unwrap_or_else(|| tab_count.saturating_sub(1))
+                } else {
+                    let base = width_px / tab_count as i32;
+                    let mut cursor = 0;
+                    let mut found = None;
+                    for idx in 0..tab_count {
+                        let mut width = base;
+                        if idx + 1 == tab_count {
+                            width += width_px - base * tab_count as i32;
                         }
-                        found.unwrap_or_else(|| tab_count.saturating_sub(1))
-                    } else {
-                        let base = width_px / tab_count as i32;
-                        let mut cursor = 0;
-                        let mut found = None;
-                        for idx in 0..tab_count {
-                            let mut width = base;
-                            if idx + 1 == tab_count {
-                                width += width_px - base * tab_count as i32;
-                            }
-                            let end = cursor + width;
-                            if pos_px.x < end {
-                                found = Some(idx);
-                                break;
-                            }
-                            cursor = end;
+                        let end = cursor + width;
+                        if pos_px.x < end {
+                            found = Some(idx);
+                            break;
                         }
-                        found.unwrap_or_else(|| tab_count.saturating_sub(1))
+                        cursor = end;
                     }
+                    found.unwrap_or_else(|| tab_count.saturating_sub(1))
                 }
-                Layout::Stacked => {
-                    let stack_height_px = row_height_px * tab_count as i32;
-                    if pos_px.y >= stack_height_px {
-                        focused_idx
-                    } else {
-                        let max_idx = tab_count.saturating_sub(1) as i32;
-                        (pos_px.y / row_height_px).min(max_idx) as usize
-                    }
+            } else {
+                let stack_height_px = row_height_px * tab_count as i32;
+                if pos_px.y >= stack_height_px {
+                    focused_idx
+                } else {
+                    let max_idx = tab_count.saturating_sub(1) as i32;
+                    (pos_px.y / row_height_px).min(max_idx) as usize
                 }
-                _ => continue,
             };
 
             if let Some(window) = self.tree.window_for_tab(&info.path, tab_idx) {
@@ -1949,9 +2013,9 @@ impl<W: LayoutElement> TilingSpace<W> {
                     continue;
                 }
 
-                // For fullscreen tiles, use (0,0) as base position since they cover the entire screen
+                // Fullscreen tiles cover the entire screen (or their span across outputs).
                 let base_pos = if is_fullscreen_tile {
-                    Point::from((0.0, 0.0))
+                    tile.fullscreen_loc()
                 } else {
                     info.rect.loc
                 };
@@ -2035,6 +2099,17 @@ impl<W: LayoutElement> TilingSpace<W> {
         TileRenderPositionsMut::new(self, round)
     }
 
+    /// Returns tiles that can't be seen: background tabs, and tiles behind a fullscreen window.
+    pub fn occluded_tiles(&self) -> impl Iterator<Item = &Tile<W>> + '_ {
+        let fullscreen_id = self.fullscreen_window.as_ref();
+        self.display_layouts().iter().filter_map(move |info| {
+            let tile = self.tree.get_tile(info.key)?;
+            let is_fullscreen_tile = fullscreen_id.is_some_and(|id| id == tile.window().id());
+            let covered = !info.visible || fullscreen_id.is_some();
+            (covered && !is_fullscreen_tile).then_some(tile)
+        })
+    }
+
     pub fn tiles_with_ipc_layouts(
         &self,
     ) -> impl Iterator<Item = (&Tile<W>, niri_ipc::WindowLayout)> + '_ {
@@ -2066,6 +2141,10 @@ impl<W: LayoutElement> TilingSpace<W> {
             || !self.closing_windows.is_empty()
     }
 
+    pub fn pending_layout_deadline(&self) -> Option<Duration> {
+        self.tree.pending_layout_deadline()
+    }
+
     pub fn update_shaders(&mut self) {
         for tile in self.tiles_mut() {
             tile.update_shaders();
@@ -2089,6 +2168,9 @@ impl<W: LayoutElement> TilingSpace<W> {
         _is_full_width: bool,
         _height: Option<WindowHeight>,
     ) {
+        let window_id = tile.window().id().clone();
+        let (layout, tab_bar_position) = container_window_rules(tile.window().rules());
+
         if let Some(index) = col_idx {
             self.tree.insert_leaf_at(index, tile, activate);
         } else if self.tree.is_empty() {
@@ -2100,12 +2182,17 @@ impl<W: LayoutElement> TilingSpace<W> {
                 .map(|tile| tile.window().id().clone());
 
             if let Some(id) = focused_id {
+                if self.options.layout.auto_split {
+                    self.auto_split_focused();
+                }
                 let inserted = self.tree.insert_leaf_after(&id, tile, activate);
                 assert!(inserted, "failed to insert tile after focused window");
             } else {
                 self.tree.append_leaf(tile, activate);
             }
         }
+        self.tree
+            .apply_window_rules(&window_id, layout, tab_bar_position);
         self.sync_fullscreen_window();
         self.tree.layout();
     }
@@ -2155,6 +2242,16 @@ impl<W: LayoutElement> TilingSpace<W> {
         self.tree.layout();
     }
 
+    pub fn append_subtree(&mut self, subtree: DetachedNode<W>, focus: bool) {
+        self.tree.append_subtree(subtree, focus);
+        self.sync_fullscreen_window();
+        self.tree.layout();
+    }
+
+    pub fn tree_template(&self) -> Option<TreeTemplate<W::Id>> {
+        self.tree.template()
+    }
+
     pub(super) fn insert_parent_info_for_window(
         &self,
         window: &W::Id,
@@ -2331,8 +2428,10 @@ impl<W: LayoutElement> TilingSpace<W> {
             size.w = f64::max(1.0, size.w - width);
             size.h = f64::max(1.0, size.h - width);
         }
-        if preview.tab_bar_offset > 0.0 {
-            size.h = f64::max(1.0, size.h - preview.tab_bar_offset);
+        if !preview.tab_bar.is_none() {
+            let extent = preview.tab_bar.extent();
+            size.w = f64::max(1.0, size.w - extent.w);
+            size.h = f64::max(1.0, size.h - extent.h);
         }
 
         size.to_i32_floor()
@@ -2619,7 +2718,112 @@ impl<W: LayoutElement> TilingSpace<W> {
         }
     }
 
+    /// Returns the path of the container holding the window, or the focused node.
+    pub fn container_path(&self, window: Option<&W::Id>) -> Option<Vec<usize>> {
+        let mut path = self.window_path(window)?;
+        // An empty path means that the window is the root, outside of any container.
+        path.pop()?;
+        Some(path)
+    }
+
+    pub fn container_percents(&self, path: &[usize]) -> Option<(Layout, Vec<f64>)> {
+        self.tree.child_percents_at(path)
+    }
+
+    pub fn set_container_percents(
+        &mut self,
+        path: &[usize],
+        percents: &[f64],
+    ) -> Result<(), String> {
+        let Some((_, current)) = self.tree.child_percents_at(path) else {
+            return Err(String::from("no container at this path"));
+        };
+        if percents.len() != current.len() {
+            return Err(format!(
+                "the container has {} children, got {} percents",
+                current.len(),
+                percents.len()
+            ));
+        }
+
+        let percents = normalized_percents(percents)?;
+        if self.tree.set_child_percents_at(path, &percents) {
+            self.tree.layout();
+        }
+        Ok(())
+    }
+
+    /// Returns the window's share of its closest horizontal and vertical split ancestors.
+    pub fn window_shares(&self, window: Option<&W::Id>) -> (Option<f64>, Option<f64>) {
+        let Some(path) = self.window_path(window) else {
+            return (None, None);
+        };
+
+        let share = |layout| {
+            let (parent_path, child_idx) =
+                self.tree.find_parent_with_layout(path.clone(), layout)?;
+            self.tree
+                .child_percent_at(parent_path.as_slice(), child_idx)
+        };
+
+        (share(Layout::SplitH), share(Layout::SplitV))
+    }
+
+    /// Moves one edge of a window, like i3's `resize grow|shrink <direction>`.
+    ///
+    /// The space is traded with the neighbor on that side in the closest ancestor split that has
+    /// one, so the opposite edge stays in place.
+    pub fn resize_window_edge(
+        &mut self,
+        window: Option<&W::Id>,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+        grow: bool,
+    ) {
+        let Some(path) = self.window_path(window) else {
+            return;
+        };
+
+        let (edge, layout) = match direction {
+            ResizeDirection::Left => (ResizeEdge::LEFT, Layout::SplitH),
+            ResizeDirection::Right => (ResizeEdge::RIGHT, Layout::SplitH),
+            ResizeDirection::Up => (ResizeEdge::TOP, Layout::SplitV),
+            ResizeDirection::Down => (ResizeEdge::BOTTOM, Layout::SplitV),
+        };
+        let Some(target) = self.fallback_resize_target(&path, edge, layout) else {
+            return;
+        };
+        let Some((available, _)) = self.container_available_span(&target.parent_path, layout)
+        else {
+            return;
+        };
+
+        let delta = match amount {
+            ResizeAmount::Fixed(px) => f64::from(px) / available,
+            ResizeAmount::Proportion(ppt) => ppt / 100.,
+        };
+        let delta = if grow { delta } else { -delta };
+
+        let current_percent = self
+            .tree
+            .child_percent_at(target.parent_path.as_slice(), target.child_idx)
+            .unwrap_or(1.0);
+
+        if self.tree.set_child_percent_pair_at(
+            target.parent_path.as_slice(),
+            target.child_idx,
+            target.neighbor_idx,
+            layout,
+            current_percent + delta,
+        ) {
+            self.tree.layout();
+        }
+    }
+
     pub fn set_fullscreen(&mut self, window: &W::Id, is_fullscreen: bool) -> bool {
+        let anims = &self.options.animations;
+        let animate = !anims.off && !anims.window_resize.anim.off;
+
         if is_fullscreen {
             if self
                 .fullscreen_window
@@ -2636,7 +2840,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             if let Some(path) = self.tree.find_window(window) {
                 if let Some(tile) = self.tree.tile_at_path_mut(&path) {
                     tile.pending_maximized |= tile.window().pending_sizing_mode().is_maximized();
-                    tile.request_fullscreen(!self.options.animations.off, None);
+                    tile.request_fullscreen(animate, None);
                 }
             }
 
@@ -2660,17 +2864,9 @@ impl<W: LayoutElement> TilingSpace<W> {
             }
 
             if tile.pending_maximized {
-                tile.request_maximized(
-                    self.working_area.size,
-                    !self.options.animations.off,
-                    None,
-                );
+                tile.request_maximized(self.working_area.size, animate, None);
             } else {
-                tile.request_tile_size(
-                    self.working_area.size,
-                    !self.options.animations.off,
-                    None,
-                );
+                tile.request_tile_size(self.working_area.size, animate, None);
             }
 
             self.fullscreen_window = None;
@@ -2743,7 +2939,9 @@ impl<W: LayoutElement> TilingSpace<W> {
         let Some(path) = self.tree.find_window(_id) else {
             return false;
         };
+        let edge = self.tree.insert_edge(&path);
         if let Some(tile) = self.tree.tile_at_path_mut(&path) {
+            tile.set_insert_edge(edge);
             tile.start_open_animation();
             return true;
         }
@@ -2781,6 +2979,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             return;
         };
 
+        let style = tile.close_style();
         let Some(snapshot) = tile.take_unmap_snapshot() else {
             return;
         };
@@ -2811,6 +3010,7 @@ impl<W: LayoutElement> TilingSpace<W> {
             tile_pos,
             blocker,
             anim,
+            style,
         );
         match res {
             Ok(closing) => {
@@ -2837,6 +3037,7 @@ impl<W: LayoutElement> TilingSpace<W> {
         };
         let focus_path = self.tree.focus_path();
         let fullscreen_id = self.fullscreen_window.as_ref();
+        let combined_intent = self.combined_configure_intent();
 
         for info in layouts {
             // Use O(1) key lookup instead of O(depth) path lookup.
@@ -2859,11 +3060,45 @@ impl<W: LayoutElement> TilingSpace<W> {
                     self.working_area.size,
                     &self.options,
                     fullscreen_id,
-                    self.view_size,
+                    combined_intent,
                 );
             }
         }
     }
+
+    /// Computes the configure intent shared by all tiles.
+    ///
+    /// A layout change resizes several windows under a single transaction. If one of them is
+    /// throttled and skips the configure, it misses the transaction, and the new layout shows up
+    /// before that window resized, leaving seams. So, like a scrolling column, throttle the whole
+    /// tree together.
+    ///
+    /// Returns `None` when each window should be throttled individually.
+    fn combined_configure_intent(&self) -> Option<ConfigureIntent> {
+        if self.options.disable_resize_throttling {
+            return Some(ConfigureIntent::CanSend);
+        }
+
+        // If transactions are disabled, also disable combined throttling, for more intuitive
+        // behavior.
+        if self.options.disable_transactions {
+            return None;
+        }
+
+        let intent = self.tree.all_tiles().into_iter().fold(
+            ConfigureIntent::NotNeeded,
+            |intent, tile| match (intent, tile.window().configure_intent()) {
+                (_, ConfigureIntent::ShouldSend) => ConfigureIntent::ShouldSend,
+                (ConfigureIntent::NotNeeded, tile_intent) => tile_intent,
+                (ConfigureIntent::CanSend, ConfigureIntent::Throttled) => {
+                    ConfigureIntent::Throttled
+                }
+                (intent, _) => intent,
+            },
+        );
+        Some(intent)
+    }
+
     pub fn render_above_top_layer(&self) -> bool {
         false
     }
@@ -2915,6 +3150,29 @@ impl TilingSpace<crate::window::Mapped> {
 }
 
 impl<W: LayoutElement> TilingSpace<W> {
+    /// Turns follow-focus gradients of a newly focused tile towards the tile focused before.
+    fn update_focus_angle(&mut self, layouts: &[LeafLayoutInfo], focus_path: &[usize]) {
+        let Some(info) = layouts.iter().find(|info| info.path == focus_path) else {
+            return;
+        };
+        let Some(tile) = self.tree.get_tile_mut(info.key) else {
+            return;
+        };
+
+        let id = tile.window().id().clone();
+        let center = info.rect.loc + info.rect.size.downscale(2.).to_point();
+        if let Some((last_id, last_center)) = &self.last_focus {
+            let delta = *last_center - center;
+            if *last_id != id && delta != Point::from((0., 0.)) {
+                // Gradient angles follow CSS: 0 degrees points up and they go clockwise.
+                let angle = delta.x.atan2(-delta.y).to_degrees();
+                tile.animate_focus_angle(angle);
+            }
+        }
+        self.last_focus = Some((id, center));
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn update_window_state(
         tile: &mut Tile<W>,
         info: &LeafLayoutInfo,
@@ -2926,19 +3184,23 @@ impl<W: LayoutElement> TilingSpace<W> {
         working_area_size: Size<f64, Logical>,
         options: &Options,
         fullscreen_id: Option<&W::Id>,
-        view_size: Size<f64, Logical>,
+        combined_intent: Option<ConfigureIntent>,
     ) {
         let window_id = tile.window().id().clone();
         let is_focused_tile = info.path == focus_path;
         let is_fullscreen_tile = fullscreen_id.is_some_and(|id| id == &window_id);
 
         let target_size: Size<f64, Logical> = if is_fullscreen_tile {
-            view_size
+            tile.fullscreen_size()
         } else {
             Size::from((info.rect.size.w, info.rect.size.h))
         };
         if request_size {
-            tile.request_tile_size(target_size, false, None);
+            if is_fullscreen_tile {
+                tile.request_fullscreen(false, None);
+            } else {
+                tile.request_tile_size(target_size, false, None);
+            }
         }
 
         let window = tile.window_mut();
@@ -2961,7 +3223,7 @@ impl<W: LayoutElement> TilingSpace<W> {
         let border_config = options.layout.border.merged_with(&window.rules().border);
 
         let bounds = if is_fullscreen_tile {
-            view_size.to_i32_floor()
+            target_size.to_i32_floor()
         } else {
             let max_bounds = compute_toplevel_bounds(
                 border_config,
@@ -2978,7 +3240,13 @@ impl<W: LayoutElement> TilingSpace<W> {
 
         window.set_bounds(bounds);
 
-        match window.configure_intent() {
+        // Changes other than the size, like activation, go out right away.
+        let intent = match (window.configure_intent(), combined_intent) {
+            (ConfigureIntent::ShouldSend, _) => ConfigureIntent::ShouldSend,
+            (_, Some(intent)) => intent,
+            (intent, None) => intent,
+        };
+        match intent {
             ConfigureIntent::CanSend | ConfigureIntent::ShouldSend => {
                 window.send_pending_configure();
             }
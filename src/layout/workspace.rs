@@ -4,10 +4,13 @@ use std::time::Duration;
 
 use niri_config::utils::MergeWith as _;
 use niri_config::{
-    CornerRadius, OutputName, PresetSize, Workspace as WorkspaceConfig,
+    CornerRadius, OutputName, PresetSize, SafeArea, TabIndicatorPosition,
+    Workspace as WorkspaceConfig,
+};
+use niri_ipc::{
+    ColumnDisplay, FloatingArrangement, LayoutTreeNode, LayoutViolation, PositionChange,
+    ResizeAmount, ResizeDirection, SizeChange, WindowLayout,
 };
-use niri_ipc::{ColumnDisplay, LayoutTreeNode, PositionChange, SizeChange, WindowLayout};
-use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::{layer_map_for_output, Window};
 use smithay::input::pointer::CursorIcon;
@@ -18,10 +21,12 @@ use smithay::utils::{Logical, Point, Rectangle, Serial, Size, Transform};
 use smithay::wayland::compositor::with_states;
 use smithay::wayland::shell::xdg::SurfaceCachedState;
 
-use super::container::{Direction, InsertParentInfo, Layout};
+use super::background::{Background, BackgroundImages, BackgroundRenderElement};
+use super::container::{DetachedNode, Direction, InsertParentInfo, Layout, TreeTemplate};
 use super::floating::{
     compute_toplevel_bounds, FloatingResizeResult, FloatingSpace, FloatingSpaceRenderElement,
 };
+use super::opening_window::OpenCloseStyle;
 use super::shadow::Shadow;
 use super::tile::{Tile, TileRenderSnapshot};
 use super::tiling::{Column, ColumnWidth, ScrollDirection, TilingSpace, TilingSpaceRenderElement};
@@ -33,13 +38,12 @@ use crate::animation::Clock;
 use crate::niri_render_elements;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::shadow::ShadowRenderElement;
-use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::RenderTarget;
 use crate::utils::id::IdCounter;
 use crate::utils::transaction::{Transaction, TransactionBlocker};
 use crate::utils::{
     center_preferring_top_left_in_area, ensure_min_max_size, ensure_min_max_size_maybe_zero,
-    output_size, send_scale_transform, ResizeEdge,
+    inset_by_safe_area, output_size, send_scale_transform, ResizeEdge,
 };
 use crate::window::ResolvedWindowRules;
 
@@ -93,7 +97,7 @@ pub struct Workspace<W: LayoutElement> {
     shadow: Shadow,
 
     /// This workspace's background.
-    background_buffer: SolidColorBuffer,
+    background: Background,
 
     /// Clock for driving animations.
     pub(super) clock: Clock,
@@ -107,6 +111,11 @@ pub struct Workspace<W: LayoutElement> {
     /// Optional name of this workspace.
     pub(super) name: Option<String>,
 
+    /// Whether a window with the `keep-workspace` rule was the last to leave this workspace.
+    ///
+    /// Such workspaces aren't cleaned up when empty, like named ones, until unnamed.
+    kept: bool,
+
     /// Layout config overrides for this workspace.
     layout_config: Option<niri_config::LayoutPart>,
 
@@ -265,7 +274,7 @@ impl<W: LayoutElement> Workspace<W> {
         );
 
         let view_size = output_size(&output);
-        let working_area = compute_working_area(&output);
+        let working_area = compute_working_area(&output, base_options.layout.safe_area);
 
         let scrolling = TilingSpace::new(
             view_size,
@@ -296,12 +305,13 @@ impl<W: LayoutElement> Workspace<W> {
             view_size,
             working_area,
             shadow: Shadow::new(shadow_config),
-            background_buffer: SolidColorBuffer::new(view_size, options.layout.background_color),
+            background: Background::new(view_size, scale.fractional_scale(), &options.layout),
             output: Some(output),
             clock,
             base_options,
             options,
             name: config.map(|c| c.name.0),
+            kept: false,
             layout_config,
             id: WorkspaceId::next(),
         }
@@ -361,11 +371,12 @@ impl<W: LayoutElement> Workspace<W> {
             view_size,
             working_area,
             shadow: Shadow::new(shadow_config),
-            background_buffer: SolidColorBuffer::new(view_size, options.layout.background_color),
+            background: Background::new(view_size, scale.fractional_scale(), &options.layout),
             clock,
             base_options,
             options,
             name: config.map(|c| c.name.0),
+            kept: false,
             layout_config,
             id: WorkspaceId::next(),
         }
@@ -385,10 +396,12 @@ impl<W: LayoutElement> Workspace<W> {
 
     pub fn unname(&mut self) {
         self.name = None;
+        self.kept = false;
     }
 
+    /// Returns whether this workspace must not be cleaned up when it isn't active.
     pub fn has_windows_or_name(&self) -> bool {
-        self.has_windows() || self.name.is_some()
+        self.has_windows() || self.name.is_some() || self.kept
     }
 
     pub fn scale(&self) -> smithay::output::Scale {
@@ -408,6 +421,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.are_transitions_ongoing() || self.floating.are_transitions_ongoing()
     }
 
+    /// Returns the earliest time when a pending container layout applies even without acks.
+    pub fn pending_layout_deadline(&self) -> Option<Duration> {
+        let scrolling = self.scrolling.pending_layout_deadline();
+        let floating = self.floating.pending_layout_deadline();
+        scrolling.into_iter().chain(floating).min()
+    }
+
     pub fn update_render_elements(&mut self, is_active: bool) {
         self.scrolling
             .update_render_elements(is_active && !self.floating_is_active.get());
@@ -433,6 +453,10 @@ impl<W: LayoutElement> Workspace<W> {
                 .adjusted_for_scale(scale),
         );
 
+        if let Some(output) = &self.output {
+            self.working_area = compute_working_area(output, base_options.layout.safe_area);
+        }
+
         self.scrolling.update_config(
             self.view_size,
             self.working_area,
@@ -451,8 +475,7 @@ impl<W: LayoutElement> Workspace<W> {
             compute_workspace_shadow_config(options.overview.workspace_shadow, self.view_size);
         self.shadow.update_config(shadow_config);
 
-        self.background_buffer
-            .set_color(options.layout.background_color);
+        self.background.update_config(&options.layout);
 
         self.base_options = base_options;
         self.options = options;
@@ -471,6 +494,7 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.update_shaders();
         self.floating.update_shaders();
         self.shadow.update_shaders();
+        self.background.update_shaders();
     }
 
     pub fn windows(&self) -> impl Iterator<Item = &W> + '_ {
@@ -507,6 +531,11 @@ impl<W: LayoutElement> Workspace<W> {
         self.output.as_ref()
     }
 
+    /// Sets the pointer position within the workspace for placing new floating windows.
+    pub fn set_pointer_pos_for_placement(&mut self, pos: Option<Point<f64, Logical>>) {
+        self.floating.set_pointer_pos(pos);
+    }
+
     pub fn active_window(&self) -> Option<&W> {
         if self.floating_is_active.get() {
             self.floating.active_window()
@@ -527,6 +556,10 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.is_active_pending_fullscreen()
     }
 
+    pub fn fullscreen_tile(&self) -> Option<&Tile<W>> {
+        self.scrolling.fullscreen_tile()
+    }
+
     pub fn set_output(&mut self, output: Option<Output>) {
         if self.output == output {
             return;
@@ -566,7 +599,7 @@ impl<W: LayoutElement> Workspace<W> {
         let scale = output.current_scale();
         let transform = output.current_transform();
         let view_size = output_size(output);
-        let working_area = compute_working_area(output);
+        let working_area = compute_working_area(output, self.base_options.layout.safe_area);
         self.set_view_size(scale, transform, view_size, working_area);
     }
 
@@ -614,7 +647,7 @@ impl<W: LayoutElement> Workspace<W> {
             self.shadow.update_config(shadow_config);
         }
 
-        self.background_buffer.resize(size);
+        self.background.update_size(size, scale.fractional_scale());
 
         if scale_transform_changed {
             for window in self.windows() {
@@ -869,6 +902,16 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
+    /// Updates whether to keep this workspace after windows left it.
+    ///
+    /// Only the windows that clear the workspace decide, so a kept workspace reused by other
+    /// windows goes away once they leave.
+    fn update_kept(&mut self, keep: bool) {
+        if !self.has_windows() {
+            self.kept = keep;
+        }
+    }
+
     pub fn remove_tile(&mut self, id: &W::Id, transaction: Transaction) -> RemovedTile<W> {
         let mut from_floating = false;
         let removed = if self.floating.has_window(id) {
@@ -881,6 +924,7 @@ impl<W: LayoutElement> Workspace<W> {
         if let Some(output) = &self.output {
             removed.tile.window().output_leave(output);
         }
+        self.update_kept(removed.tile.window().rules().keep_workspace == Some(true));
 
         self.update_focus_floating_tiling_after_removing(from_floating);
 
@@ -898,6 +942,7 @@ impl<W: LayoutElement> Workspace<W> {
         if let Some(output) = &self.output {
             removed.tile.window().output_leave(output);
         }
+        self.update_kept(removed.tile.window().rules().keep_workspace == Some(true));
 
         self.update_focus_floating_tiling_after_removing(from_floating);
 
@@ -917,6 +962,11 @@ impl<W: LayoutElement> Workspace<W> {
                 tile.window().output_leave(output);
             }
         }
+        self.update_kept(
+            column
+                .tiles()
+                .any(|tile| tile.window().rules().keep_workspace == Some(true)),
+        );
 
         self.update_focus_floating_tiling_after_removing(from_floating);
 
@@ -1375,6 +1425,51 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
+    /// Returns the path of the tiling container holding the window, or the focused node.
+    pub fn container_path(&self, window: Option<&W::Id>) -> Option<Vec<usize>> {
+        if self.is_floating_target(window) {
+            None
+        } else {
+            self.scrolling.container_path(window)
+        }
+    }
+
+    pub fn container_percents(&self, path: &[usize]) -> Option<(Layout, Vec<f64>)> {
+        self.scrolling.container_percents(path)
+    }
+
+    pub fn set_container_percents(
+        &mut self,
+        path: &[usize],
+        percents: &[f64],
+    ) -> Result<(), String> {
+        self.scrolling.set_container_percents(path, percents)
+    }
+
+    pub fn window_shares(&self, window: Option<&W::Id>) -> (Option<f64>, Option<f64>) {
+        if self.is_floating_target(window) {
+            (None, None)
+        } else {
+            self.scrolling.window_shares(window)
+        }
+    }
+
+    pub fn resize_window_edge(
+        &mut self,
+        window: Option<&W::Id>,
+        direction: ResizeDirection,
+        amount: ResizeAmount,
+        grow: bool,
+    ) {
+        if self.is_floating_target(window) {
+            self.floating
+                .resize_window_edge(window, direction, amount, grow);
+        } else {
+            self.scrolling
+                .resize_window_edge(window, direction, amount, grow);
+        }
+    }
+
     pub fn reset_window_height(&mut self, window: Option<&W::Id>) {
         if self.is_floating_target(window) {
             return;
@@ -1453,6 +1548,14 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
+    pub fn set_tab_bar_position(&mut self, position: TabIndicatorPosition) {
+        if self.floating_is_active.get() {
+            self.floating.set_tab_bar_position(position);
+        } else {
+            self.scrolling.set_tab_bar_position(position);
+        }
+    }
+
     pub fn set_fullscreen(&mut self, window: &W::Id, is_fullscreen: bool) {
         let mut restore_to_floating = false;
         if self.floating.has_window(window) {
@@ -1582,6 +1685,61 @@ impl<W: LayoutElement> Workspace<W> {
         self.set_maximized(window, !current);
     }
 
+    /// Returns the shape of the tiling tree, for saving it.
+    pub fn tree_template(&self) -> Option<TreeTemplate<W::Id>> {
+        self.scrolling.tree_template()
+    }
+
+    /// Rebuilds a saved tiling tree from windows on this workspace.
+    ///
+    /// The windows in the template leave their current place, floating or tiled, and come back as
+    /// a subtree after the existing tiled windows. Windows not on this workspace are left out.
+    pub fn rebuild_tree(&mut self, template: TreeTemplate<W::Id>) {
+        let scrolling = &mut self.scrolling;
+        let floating = &mut self.floating;
+        let mut take_tile = |id: &W::Id| {
+            if floating.has_window(id) {
+                let mut tile = floating.remove_tile(id).tile;
+                tile.set_scratchpad(false);
+                Some(tile)
+            } else if scrolling.tiles().any(|tile| tile.window().id() == id) {
+                let mut removed = scrolling.remove_tile(id, Transaction::new());
+                removed.tile.stop_move_animations();
+                Some(removed.tile)
+            } else {
+                None
+            }
+        };
+
+        let Some(subtree) = DetachedNode::from_template(template, &mut take_tile) else {
+            return;
+        };
+        self.scrolling.append_subtree(subtree, false);
+
+        if self.floating.is_empty() {
+            self.floating_is_active = FloatingActive::No;
+        }
+    }
+
+    /// Rearranges the windows into a tree from before, animating them from their old positions.
+    pub fn restore_tree(&mut self, template: TreeTemplate<W::Id>) {
+        let old_positions = self
+            .tiles_with_render_positions()
+            .map(|(tile, pos, _)| (tile.window().id().clone(), pos))
+            .collect::<Vec<_>>();
+
+        self.rebuild_tree(template);
+
+        for (tile, pos) in self.tiles_with_render_positions_mut(false) {
+            let id = tile.window().id();
+            if let Some((_, old_pos)) = old_positions.iter().find(|(old_id, _)| old_id == id) {
+                // The windows were already open, so they only move.
+                tile.stop_open_animation();
+                tile.animate_move_from(*old_pos - pos);
+            }
+        }
+    }
+
     pub fn toggle_window_floating(&mut self, id: Option<&W::Id>) {
         let active_id = self.active_window().map(|win| win.id().clone());
         let target_is_active = id.is_none_or(|id| Some(id) == active_id.as_ref());
@@ -1653,14 +1811,13 @@ impl<W: LayoutElement> Workspace<W> {
                 removed.tile.floating_pos = None;
 
                 // Set a default size if the window doesn't have a stored floating size.
-                // Using sway's defaults: 50% width × 75% height
+                // Unless a window rule says otherwise, use sway's defaults: 50% width × 75% height
                 if removed.tile.floating_window_size.is_none() {
                     let working_size = self.floating.working_area().size;
-                    let mut size = Size::from((
-                        working_size.w * 0.5,
-                        working_size.h * 0.75,
-                    ))
-                    .to_i32_floor();
+                    let rules_size = removed.tile.window().rules().default_floating_window_size();
+                    let mut size = rules_size.unwrap_or_else(|| {
+                        Size::from((working_size.w * 0.5, working_size.h * 0.75)).to_i32_floor()
+                    });
 
                     // Apply min/max size constraints
                     let min_size = removed.tile.window().min_size();
@@ -1712,11 +1869,10 @@ impl<W: LayoutElement> Workspace<W> {
 
             if tile.floating_window_size.is_none() {
                 let working_size = self.floating.working_area().size;
-                let mut size = Size::from((
-                    working_size.w * 0.5,
-                    working_size.h * 0.75,
-                ))
-                .to_i32_floor();
+                let rules_size = tile.window().rules().default_floating_window_size();
+                let mut size = rules_size.unwrap_or_else(|| {
+                    Size::from((working_size.w * 0.5, working_size.h * 0.75)).to_i32_floor()
+                });
 
                 let min_size = tile.window().min_size();
                 let max_size = tile.window().max_size();
@@ -1742,6 +1898,53 @@ impl<W: LayoutElement> Workspace<W> {
         Some(tile)
     }
 
+    /// Adds back a minimized tile, into its old spot in the tiling tree if there is one.
+    pub(super) fn add_minimized_tile(
+        &mut self,
+        tile: Tile<W>,
+        origin: Option<&InsertParentInfo>,
+        width: ColumnWidth,
+        is_full_width: bool,
+        is_floating: bool,
+    ) {
+        match origin {
+            Some(origin) if !is_floating => {
+                self.enter_output_for_window(tile.window());
+                self.scrolling
+                    .insert_tile_with_parent_info(origin, tile, true);
+                self.floating_is_active = FloatingActive::No;
+            }
+            _ => self.add_tile(
+                tile,
+                WorkspaceAddWindowTarget::Auto,
+                ActivateWindow::Yes,
+                width,
+                is_full_width,
+                is_floating,
+            ),
+        }
+    }
+
+    pub fn toggle_window_always_on_top(&mut self, id: &W::Id) -> bool {
+        self.floating.toggle_window_always_on_top(id)
+    }
+
+    pub fn raise_window(&mut self, id: &W::Id) -> bool {
+        if !self.floating.raise_window(id) {
+            return false;
+        }
+
+        // Show the floating windows above a focused fullscreen tiled window.
+        if self.floating_is_active == FloatingActive::No {
+            self.floating_is_active = FloatingActive::NoButRaised;
+        }
+        true
+    }
+
+    pub fn lower_window(&mut self, id: &W::Id) -> bool {
+        self.floating.lower_window(id)
+    }
+
     pub fn add_scratchpad_tile(&mut self, mut tile: Tile<W>, activate: bool) {
         tile.set_scratchpad(true);
         tile.window_mut().set_floating(true);
@@ -1773,6 +1976,10 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
+    pub fn arrange_floating(&mut self, arrangement: FloatingArrangement) {
+        self.floating.arrange(arrangement);
+    }
+
     pub fn switch_focus_floating_tiling(&mut self) {
         if self.floating.is_empty() {
             // If floating is empty, keep focus on scrolling.
@@ -1892,7 +2099,8 @@ impl<W: LayoutElement> Workspace<W> {
 
         let floating = self.floating.tiles_with_render_positions();
         let visible = self.is_floating_visible();
-        let floating = floating.map(move |(tile, pos)| (tile, pos, visible));
+        let floating =
+            floating.map(move |(tile, pos)| (tile, pos, visible || tile.is_always_on_top()));
 
         floating.chain(scrolling)
     }
@@ -1906,6 +2114,18 @@ impl<W: LayoutElement> Workspace<W> {
         floating.chain(scrolling)
     }
 
+    /// Returns windows that are fully covered by other windows on this workspace.
+    pub fn occluded_windows(&self) -> impl Iterator<Item = &W> + '_ {
+        let floating_visible = self.is_floating_visible();
+        let floating = self
+            .floating
+            .tiles()
+            .filter(move |tile| !floating_visible && !tile.is_always_on_top());
+        floating
+            .chain(self.scrolling.occluded_tiles())
+            .map(Tile::window)
+    }
+
     pub fn tiles_with_ipc_layouts(&self) -> impl Iterator<Item = (&Tile<W>, WindowLayout)> {
         let scrolling = self.scrolling.tiles_with_ipc_layouts();
         let floating = self.floating.tiles_with_ipc_layouts();
@@ -1949,12 +2169,21 @@ impl<W: LayoutElement> Workspace<W> {
         focus_ring: bool,
         push: &mut dyn FnMut(WorkspaceRenderElement<R>),
     ) {
+        let view_rect = Rectangle::from_size(self.view_size);
+        let floating_focus_ring = focus_ring && self.floating_is_active();
+
         if !self.is_floating_visible() {
+            // Always-on-top windows stay visible above a fullscreen window.
+            self.floating.render_always_on_top(
+                renderer,
+                view_rect,
+                target,
+                floating_focus_ring,
+                &mut |elem| push(elem.into()),
+            );
             return;
         }
 
-        let view_rect = Rectangle::from_size(self.view_size);
-        let floating_focus_ring = focus_ring && self.floating_is_active();
         self.floating.render(
             renderer,
             view_rect,
@@ -1972,13 +2201,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.shadow.render(renderer, Point::from((0., 0.)), push);
     }
 
-    pub fn render_background(&self) -> SolidColorRenderElement {
-        SolidColorRenderElement::from_buffer(
-            &self.background_buffer,
-            Point::new(0., 0.),
-            1.,
-            Kind::Unspecified,
-        )
+    pub fn render_background<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        images: &BackgroundImages,
+        push: &mut dyn FnMut(BackgroundRenderElement),
+    ) {
+        self.background.render(renderer, images, push);
     }
 
     pub fn render_above_top_layer(&self) -> bool {
@@ -2043,9 +2272,11 @@ impl<W: LayoutElement> Workspace<W> {
         tile_size: Size<f64, Logical>,
         tile_pos: Point<f64, Logical>,
         blocker: TransactionBlocker,
+        style: OpenCloseStyle,
     ) {
-        self.floating
-            .start_close_animation_for_tile(renderer, snapshot, tile_size, tile_pos, blocker);
+        self.floating.start_close_animation_for_tile(
+            renderer, snapshot, tile_size, tile_pos, blocker, style,
+        );
     }
 
     pub fn start_open_animation(&mut self, id: &W::Id) -> bool {
@@ -2054,14 +2285,14 @@ impl<W: LayoutElement> Workspace<W> {
 
     pub fn window_under(&self, pos: Point<f64, Logical>) -> Option<(&W, HitType)> {
         // This logic is consistent with tiles_with_render_positions().
-        if self.is_floating_visible() {
-            if let Some(rv) = self
-                .floating
-                .tiles_with_render_positions()
-                .find_map(|(tile, tile_pos)| HitType::hit_tile(tile, tile_pos, pos))
-            {
-                return Some(rv);
-            }
+        let floating_visible = self.is_floating_visible();
+        if let Some(rv) = self
+            .floating
+            .tiles_with_render_positions()
+            .filter(|(tile, _)| floating_visible || tile.is_always_on_top())
+            .find_map(|(tile, tile_pos)| HitType::hit_tile(tile, tile_pos, pos))
+        {
+            return Some(rv);
         }
 
         self.scrolling.window_under(pos)
@@ -2115,6 +2346,14 @@ impl<W: LayoutElement> Workspace<W> {
             .refresh(is_active && self.floating_is_active.get(), is_focused);
     }
 
+    /// Updates the window titles that depend on the workspace or the marks.
+    pub fn refresh_titles(&mut self, ws_idx: usize) {
+        let name = self.name.as_deref();
+        for tile in self.scrolling.tiles_mut().chain(self.floating.tiles_mut()) {
+            tile.refresh_title(name, ws_idx);
+        }
+    }
+
     pub fn scroll_amount_to_activate(&self, window: &W::Id) -> f64 {
         if self.floating.has_window(window) {
             return 0.;
@@ -2155,15 +2394,20 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
-    pub(super) fn scrolling_insert_position(&self, pos: Point<f64, Logical>) -> InsertPosition {
-        self.scrolling.insert_position(pos)
+    pub(super) fn scrolling_insert_position(
+        &self,
+        pos: Point<f64, Logical>,
+        zoom: f64,
+    ) -> InsertPosition {
+        self.scrolling.insert_position(pos, zoom)
     }
 
     pub(super) fn insert_hint_area(
         &self,
         position: &InsertPosition,
+        zoom: f64,
     ) -> Option<Rectangle<f64, Logical>> {
-        self.scrolling.insert_hint_area(position)
+        self.scrolling.insert_hint_area(position, zoom)
     }
 
     pub fn view_offset_gesture_begin(&mut self, is_touchpad: bool) {
@@ -2266,6 +2510,18 @@ impl<W: LayoutElement> Workspace<W> {
         self.layout_config.as_ref()
     }
 
+    /// Checks the tiling tree for inconsistencies, see [`ContainerTree::check_invariants()`].
+    ///
+    /// [`ContainerTree::check_invariants()`]: super::container::ContainerTree::check_invariants
+    pub fn check_invariants(&self) -> Vec<LayoutViolation> {
+        let id = self.id.get();
+        let mut violations = self.scrolling.check_invariants();
+        for violation in &mut violations {
+            violation.workspace_id = Some(id);
+        }
+        violations
+    }
+
     #[cfg(test)]
     pub fn scrolling(&self) -> &TilingSpace<W> {
         &self.scrolling
@@ -2295,11 +2551,8 @@ impl<W: LayoutElement> Workspace<W> {
         assert!(self.view_size.w > 0.);
         assert!(self.view_size.h > 0.);
 
-        assert_eq!(self.background_buffer.size(), self.view_size);
-        assert_eq!(
-            self.background_buffer.color().components(),
-            options.layout.background_color.to_array_unpremul(),
-        );
+        self.background
+            .verify_invariants(self.view_size, &options.layout);
 
         assert_eq!(self.view_size, self.scrolling.view_size());
         assert_eq!(self.working_area, self.scrolling.parent_area());
@@ -2348,7 +2601,159 @@ impl<W: LayoutElement> Workspace<W> {
                 );
             }
         }
+
+        if std::env::var_os("AUDIT_LAYOUT_GEOMETRY").is_some() {
+            let issues = self.audit_geometry();
+            assert!(issues.is_empty(), "tile geometry audit failed: {issues:#?}");
+        }
     }
+
+    /// Checks the tiled layout for seams, overlaps and out-of-bounds tiles in physical pixels.
+    ///
+    /// [`verify_invariants()`](Self::verify_invariants) runs this when the `AUDIT_LAYOUT_GEOMETRY`
+    /// environment variable is set.
+    #[cfg(test)]
+    pub fn audit_geometry(&self) -> Vec<GeometryIssue> {
+        let (rects, bounds) = self.scrolling.visible_leaf_rects();
+        let scale = self.scale.fractional_scale();
+        audit_tile_geometry(&rects, bounds, self.options.layout.gaps, scale)
+    }
+}
+
+/// Problem found by [`audit_tile_geometry()`].
+///
+/// Tiles are referred to by their index in the audited slice.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryIssue {
+    /// Neighboring tiles aren't a whole, non-zero number of gaps apart (or aren't touching when
+    /// there are no gaps), leaving a sliver of background or a missing gap.
+    Seam {
+        first: usize,
+        second: usize,
+        /// Whether the seam runs vertically, i.e. the tiles are side by side.
+        vertical: bool,
+        /// Distance between the edges in physical pixels.
+        distance: i64,
+    },
+    /// Two tiles cover some of the same physical pixels.
+    Overlap { first: usize, second: usize },
+    /// A tile sticks out of the layout area.
+    OutOfBounds { tile: usize },
+}
+
+/// Tile edges rounded to physical pixels.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy)]
+struct PhysicalEdges {
+    left: i64,
+    right: i64,
+    top: i64,
+    bottom: i64,
+}
+
+#[cfg(test)]
+impl PhysicalEdges {
+    fn new(rect: Rectangle<f64, Logical>, scale: f64) -> Self {
+        // Round every edge on its own, like rendering at this scale does.
+        let round = |x: f64| (x * scale).round() as i64;
+        Self {
+            left: round(rect.loc.x),
+            right: round(rect.loc.x + rect.size.w),
+            top: round(rect.loc.y),
+            bottom: round(rect.loc.y + rect.size.h),
+        }
+    }
+}
+
+/// Checks tile rects for seams, overlaps and tiles outside of `bounds` at the given scale.
+///
+/// Neighboring tiles must be apart by a whole number of `gap`s after rounding to physical
+/// pixels: more than one gap can separate tiles in nested containers, e.g. around a tabbed
+/// container, but anything else shows up as a seam.
+#[cfg(test)]
+pub fn audit_tile_geometry(
+    rects: &[Rectangle<f64, Logical>],
+    bounds: Rectangle<f64, Logical>,
+    gap: f64,
+    scale: f64,
+) -> Vec<GeometryIssue> {
+    let mut issues = Vec::new();
+
+    let gap = (gap * scale).round() as i64;
+    let bounds = PhysicalEdges::new(bounds, scale);
+    let edges: Vec<_> = rects
+        .iter()
+        .map(|r| PhysicalEdges::new(*r, scale))
+        .collect();
+
+    for (idx, e) in edges.iter().enumerate() {
+        if e.left < bounds.left
+            || e.right > bounds.right
+            || e.top < bounds.top
+            || e.bottom > bounds.bottom
+        {
+            issues.push(GeometryIssue::OutOfBounds { tile: idx });
+        }
+    }
+
+    let overlap = |a0: i64, a1: i64, b0: i64, b1: i64| a1.min(b1) - a0.max(b0) > 0;
+
+    for (first, a) in edges.iter().enumerate() {
+        for (second, b) in edges.iter().enumerate().skip(first + 1) {
+            if overlap(a.left, a.right, b.left, b.right)
+                && overlap(a.top, a.bottom, b.top, b.bottom)
+            {
+                issues.push(GeometryIssue::Overlap { first, second });
+            }
+        }
+    }
+
+    let is_seam = |distance: i64| {
+        if gap == 0 {
+            distance != 0
+        } else {
+            distance == 0 || distance % gap != 0
+        }
+    };
+
+    for vertical in [true, false] {
+        for (first, a) in edges.iter().enumerate() {
+            // Find the closest tile facing the right (or bottom) edge.
+            let neighbor = edges
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| {
+                    if vertical {
+                        b.left >= a.right && overlap(a.top, a.bottom, b.top, b.bottom)
+                    } else {
+                        b.top >= a.bottom && overlap(a.left, a.right, b.left, b.right)
+                    }
+                })
+                .map(|(second, b)| {
+                    let distance = if vertical {
+                        b.left - a.right
+                    } else {
+                        b.top - a.bottom
+                    };
+                    (second, distance)
+                })
+                .min_by_key(|(_, distance)| *distance);
+
+            if let Some((second, distance)) = neighbor {
+                if is_seam(distance) {
+                    issues.push(GeometryIssue::Seam {
+                        first,
+                        second,
+                        vertical,
+                        distance,
+                    });
+                }
+            }
+        }
+    }
+
+    issues
 }
 
 impl Workspace<crate::window::Mapped> {
@@ -2357,8 +2762,17 @@ impl Workspace<crate::window::Mapped> {
     }
 }
 
-pub(super) fn compute_working_area(output: &Output) -> Rectangle<f64, Logical> {
-    layer_map_for_output(output).non_exclusive_zone().to_f64()
+pub(super) fn compute_working_area(
+    output: &Output,
+    safe_area: SafeArea,
+) -> Rectangle<f64, Logical> {
+    let working_area = layer_map_for_output(output).non_exclusive_zone().to_f64();
+
+    // Layer-shell exclusive zones may or may not cover the safe area, so intersect.
+    let safe_area = inset_by_safe_area(Rectangle::from_size(output_size(output)), safe_area);
+    working_area
+        .intersection(safe_area)
+        .unwrap_or_else(|| Rectangle::new(safe_area.loc, Size::default()))
 }
 
 fn compute_workspace_shadow_config(
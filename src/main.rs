@@ -7,28 +7,41 @@ use std::io::{self, Write};
 use std::os::fd::FromRawFd;
 use std::path::PathBuf;
 use std::process::Command;
+use std::time::Duration;
 use std::{env, mem};
 
+use calloop::timer::{TimeoutAction, Timer};
 use calloop::EventLoop;
 use clap::{CommandFactory, Parser};
 use clap_complete::Shell;
 use clap_complete_nushell::Nushell;
 use directories::ProjectDirs;
-use niri::cli::{Cli, CompletionShell, Sub};
+use niri::cli::{Cli, CompletionShell, HeadlessOutputSpec, Sub};
 #[cfg(feature = "dbus")]
 use niri::dbus;
+use niri::handlers::{mark_spawn_on_output, mark_spawn_on_workspace};
+use niri::input::bind_conflicts;
 use niri::ipc::client::handle_msg;
+use niri::ipc::i3;
 use niri::niri::State;
+use niri::utils::config_check;
+use niri::utils::recent_logs::RecentLogsLayer;
 use niri::utils::spawning::{
-    spawn, spawn_sh, store_and_increase_nofile_rlimit, CHILD_DISPLAY, CHILD_ENV,
+    spawn, spawn_with, store_and_increase_nofile_rlimit, SpawnOptions, CHILD_DISPLAY, CHILD_ENV,
     REMOVE_ENV_RUST_BACKTRACE, REMOVE_ENV_RUST_LIB_BACKTRACE,
 };
-use niri::utils::{cause_panic, version, watcher, xwayland, IS_SYSTEMD_SERVICE};
+use niri::utils::{
+    cause_panic, session_snapshot, version, watchdog, watcher, xwayland, IS_SYSTEMD_SERVICE,
+};
 use niri_config::{Config, ConfigPath};
-use niri_ipc::socket::SOCKET_PATH_ENV;
+use niri_ipc::socket::{Socket, READ_ONLY_SOCKET_PATH_ENV, SOCKET_PATH_ENV};
+use niri_ipc::{Request, Response};
 use portable_atomic::Ordering;
 use sd_notify::NotifyState;
+use serde_json::json;
 use smithay::reexports::wayland_server::Display;
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
 use tracing_subscriber::EnvFilter;
 
 const DEFAULT_LOG_FILTER: &str = "niri=debug,smithay::backend::renderer::gles=error";
@@ -51,10 +64,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let directives = env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LOG_FILTER.to_owned());
     let env_filter = EnvFilter::builder().parse_lossy(directives);
-    tracing_subscriber::fmt()
-        .compact()
-        .with_writer(io::stderr)
-        .with_env_filter(env_filter)
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .compact()
+                .with_writer(io::stderr),
+        )
+        .with(RecentLogsLayer)
         .init();
 
     if env::var_os("NOTIFY_SOCKET").is_some() {
@@ -98,15 +115,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Handle subcommands.
     if let Some(subcommand) = cli.subcommand {
         match subcommand {
-            Sub::Validate { config } => {
+            Sub::Validate { config, json } => {
                 tracy_client::Client::start();
 
-                config_path(config).load().config?;
-                info!("config is valid");
+                validate(config, json)?;
                 return Ok(());
             }
-            Sub::Msg { msg, json } => {
-                handle_msg(msg, json)?;
+            Sub::Msg { msg, json, watch } => {
+                handle_msg(msg, json, watch)?;
                 return Ok(());
             }
             Sub::Panic => cause_panic(),
@@ -174,12 +190,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         event_loop.handle(),
         event_loop.get_signal(),
         display,
-        false,
+        cli.headless,
         true,
         cli.session,
     )
     .unwrap();
 
+    if cli.headless {
+        setup_headless_outputs(&mut state, &cli.outputs);
+    }
+
     // Set WAYLAND_DISPLAY for children.
     let socket_name = state.niri.socket_name.as_deref().unwrap();
     env::set_var("WAYLAND_DISPLAY", socket_name);
@@ -193,6 +213,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let socket_path = ipc.socket_path.as_deref().unwrap();
         env::set_var(SOCKET_PATH_ENV, socket_path);
         info!("IPC listening on: {}", socket_path.to_string_lossy());
+
+        let socket_path = ipc.read_only_socket_path.as_deref().unwrap();
+        env::set_var(READ_ONLY_SOCKET_PATH_ENV, socket_path);
+        info!(
+            "read-only IPC listening on: {}",
+            socket_path.to_string_lossy()
+        );
+
+        if let Some(socket_path) = &ipc.i3_socket_path {
+            env::set_var(i3::SOCKET_PATH_ENV, socket_path);
+            info!("i3 IPC listening on: {}", socket_path.to_string_lossy());
+        }
     }
 
     // Setup xwayland-satellite integration.
@@ -241,16 +273,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     watcher::setup(&mut state, &config_path, config_includes);
+    // Before spawning anything so that reopened windows can go back to their workspaces.
+    session_snapshot::setup(&mut state);
 
     // Spawn commands from cli and auto-start.
     spawn(cli.command, None);
 
+    let mut critical = Vec::new();
     for elem in spawn_at_startup {
-        spawn(elem.command, None);
+        if elem.critical {
+            critical.push(elem.command);
+        } else {
+            let target = StartupTarget {
+                workspace: elem.workspace,
+                output: elem.output,
+            };
+            spawn_at_startup(&mut state, elem.command, target, elem.delay_ms);
+        }
     }
     for elem in spawn_sh_at_startup {
-        spawn_sh(elem.command, None);
+        let command = vec![String::from("sh"), String::from("-c"), elem.command];
+        if elem.critical {
+            critical.push(command);
+        } else {
+            let target = StartupTarget {
+                workspace: elem.workspace,
+                output: elem.output,
+            };
+            spawn_at_startup(&mut state, command, target, elem.delay_ms);
+        }
     }
+    watchdog::setup(&mut state, critical);
 
     // Show the config error notification right away if needed.
     if config_errored {
@@ -258,6 +311,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         state.ipc_config_loaded(true);
     } else if let Some(path) = config_created_at {
         state.niri.config_error_notification.show_created(path);
+    } else {
+        let unreachable = bind_conflicts::count_unreachable(&state.niri.bind_conflicts);
+        if unreachable > 0 {
+            state
+                .niri
+                .config_error_notification
+                .show_bind_conflicts(unreachable);
+        }
     }
 
     // Run the compositor.
@@ -265,9 +326,83 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .run(None, &mut state, |state| state.refresh_and_flush_clients())
         .unwrap();
 
+    session_snapshot::clean_exit();
+
     Ok(())
 }
 
+fn setup_headless_outputs(state: &mut State, outputs: &[HeadlessOutputSpec]) {
+    // Without a renderer, screencasts and screenshots won't work, but clients still can.
+    if let Err(err) = state.backend.headless().add_renderer() {
+        warn!("error creating the headless renderer: {err:?}");
+    }
+
+    let default = [HeadlessOutputSpec::default()];
+    let outputs = if outputs.is_empty() {
+        &default[..]
+    } else {
+        outputs
+    };
+    for spec in outputs {
+        let refresh = spec.refresh.map(|hz| (hz * 1000.).round() as i32);
+        match state.create_headless_output(spec.width, spec.height, None, refresh, None) {
+            Ok(name) => info!("created headless output {name} ({spec})"),
+            Err(err) => warn!("error creating headless output {spec}: {err}"),
+        }
+    }
+}
+
+/// Where the first window of a startup command should open.
+struct StartupTarget {
+    workspace: Option<String>,
+    output: Option<String>,
+}
+
+fn spawn_at_startup(
+    state: &mut State,
+    command: Vec<String>,
+    target: StartupTarget,
+    delay_ms: Option<u64>,
+) {
+    let Some(delay_ms) = delay_ms.filter(|ms| *ms > 0) else {
+        spawn_targeted(state, command, target);
+        return;
+    };
+
+    let mut pending = Some((command, target));
+    let timer = Timer::from_duration(Duration::from_millis(delay_ms));
+    let res = state
+        .niri
+        .event_loop
+        .insert_source(timer, move |_, _, state| {
+            if let Some((command, target)) = pending.take() {
+                spawn_targeted(state, command, target);
+            }
+            TimeoutAction::Drop
+        });
+    if let Err(err) = res {
+        warn!("error scheduling a startup command: {err:?}");
+    }
+}
+
+fn spawn_targeted(state: &mut State, command: Vec<String>, target: StartupTarget) {
+    let mut options = SpawnOptions::default();
+
+    // Route the first window through an activation token, like spawn-on-workspace does.
+    if target.workspace.is_some() || target.output.is_some() {
+        let (token, data) = state.niri.activation_state.create_external_token(None);
+        if let Some(workspace) = target.workspace {
+            mark_spawn_on_workspace(data, workspace);
+        }
+        if let Some(output) = target.output {
+            mark_spawn_on_output(data, output);
+        }
+        options.token = Some(token.clone());
+    }
+
+    spawn_with(command, options);
+}
+
 fn import_environment() {
     let variables = [
         "WAYLAND_DISPLAY",
@@ -275,6 +410,8 @@ fn import_environment() {
         "XDG_CURRENT_DESKTOP",
         "XDG_SESSION_TYPE",
         SOCKET_PATH_ENV,
+        READ_ONLY_SOCKET_PATH_ENV,
+        i3::SOCKET_PATH_ENV,
     ]
     .join(" ");
 
@@ -358,6 +495,49 @@ fn config_path(cli_path: Option<PathBuf>) -> ConfigPath {
     }
 }
 
+fn validate(path: Option<PathBuf>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let res = config_path(path).load().config;
+    let outputs = connected_outputs();
+
+    if json {
+        let (errors, warnings) = match &res {
+            Ok(config) => (Vec::new(), config_check::check(config, outputs.as_deref())),
+            Err(err) => (vec![niri_config::error::render_plain(err)], Vec::new()),
+        };
+        let report = json!({
+            "valid": res.is_ok(),
+            "errors": errors,
+            "warnings": warnings,
+            "checked_outputs": outputs.is_some(),
+        });
+        println!("{report}");
+
+        if res.is_err() {
+            return Err("config is not valid".into());
+        }
+        return Ok(());
+    }
+
+    let config = res?;
+    if outputs.is_none() {
+        info!("niri is not running, skipping the output name checks");
+    }
+    for issue in config_check::check(&config, outputs.as_deref()) {
+        warn!("{}", issue.message);
+    }
+    info!("config is valid");
+    Ok(())
+}
+
+/// Returns the outputs of the running niri instance, if any.
+fn connected_outputs() -> Option<Vec<niri_ipc::Output>> {
+    let mut socket = Socket::connect().ok()?;
+    match socket.send(Request::Outputs).ok()? {
+        Ok(Response::Outputs(outputs)) => Some(outputs.into_values().collect()),
+        _ => None,
+    }
+}
+
 fn notify_fd() -> anyhow::Result<()> {
     let fd = match env::var("NOTIFY_FD") {
         Ok(notify_fd) => notify_fd.parse()?,
@@ -15,8 +15,8 @@ use anyhow::{bail, ensure, Context};
 use calloop::futures::Scheduler;
 use niri_config::debug::PreviewRender;
 use niri_config::{
-    Config, FloatOrInt, Key, ModKey, Modifiers, OutputName, TrackLayout, WarpMouseToFocusMode,
-    WorkspaceReference, Xkb,
+    Color, Config, Corner, FloatOrInt, Key, ModKey, Modifiers, OutputName, TrackLayout,
+    WarpMouseToFocusMode, WorkspaceReference, Xkb, TUNABLE_PATHS,
 };
 use smithay::backend::allocator::Fourcc;
 use smithay::backend::input::Keycode;
@@ -74,6 +74,7 @@ use smithay::wayland::compositor::{
     with_states, with_surface_tree_downward, CompositorClientState, CompositorHandler,
     CompositorState, HookId, SurfaceData, TraversalAction,
 };
+use smithay::wayland::content_type::ContentTypeState;
 use smithay::wayland::cursor_shape::CursorShapeManagerState;
 use smithay::wayland::dmabuf::DmabufState;
 use smithay::wayland::fractional_scale::FractionalScaleManagerState;
@@ -88,7 +89,7 @@ use smithay::wayland::pointer_constraints::{with_pointer_constraint, PointerCons
 use smithay::wayland::pointer_gestures::PointerGesturesState;
 use smithay::wayland::presentation::PresentationState;
 use smithay::wayland::relative_pointer::RelativePointerManagerState;
-use smithay::wayland::security_context::SecurityContextState;
+use smithay::wayland::security_context::{SecurityContext, SecurityContextState};
 use smithay::wayland::selection::data_device::{set_data_device_selection, DataDeviceState};
 use smithay::wayland::selection::ext_data_control::DataControlState as ExtDataControlState;
 use smithay::wayland::selection::primary_selection::PrimarySelectionState;
@@ -97,7 +98,7 @@ use smithay::wayland::session_lock::{LockSurface, SessionLockManagerState, Sessi
 use smithay::wayland::shell::kde::decoration::KdeDecorationState;
 use smithay::wayland::shell::wlr_layer::{self, Layer, WlrLayerShellState};
 use smithay::wayland::shell::xdg::decoration::XdgDecorationState;
-use smithay::wayland::shell::xdg::XdgShellState;
+use smithay::wayland::shell::xdg::{ShellClient, XdgShellState};
 use smithay::wayland::shm::ShmState;
 #[cfg(test)]
 use smithay::wayland::single_pixel_buffer::SinglePixelBufferState;
@@ -113,9 +114,12 @@ use smithay::wayland::xdg_foreign::XdgForeignState;
 use crate::a11y::A11y;
 use crate::animation::Clock;
 use crate::backend::tty::SurfaceDmabufFeedback;
+use crate::backend::virtual_output::{self, VirtualOutputs};
 use crate::backend::{Backend, Headless, RenderResult, Tty, Winit};
 use crate::cursor::{CursorManager, CursorOverride, CursorTextureCache, RenderCursor, XCursor};
 #[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_background::{AppState, BackgroundToNiri};
+#[cfg(feature = "dbus")]
 use crate::dbus::freedesktop_locale1::Locale1ToNiri;
 #[cfg(feature = "dbus")]
 use crate::dbus::freedesktop_login1::Login1ToNiri;
@@ -125,6 +129,8 @@ use crate::dbus::gnome_shell_introspect::{self, IntrospectToNiri, NiriToIntrospe
 use crate::dbus::gnome_shell_screenshot::{NiriToScreenshot, ScreenshotToNiri};
 use crate::frame_clock::FrameClock;
 use crate::handlers::{configure_lock_surface, XDG_ACTIVATION_TOKEN_TIMEOUT};
+use crate::input::bind_conflicts;
+use crate::input::macro_playback::MacroPlayback;
 use crate::input::pick_color_grab::PickColorGrab;
 // TODO i3-conversion: Re-enable if needed
 // use crate::input::scroll_swipe_gesture::ScrollSwipeGesture;
@@ -177,8 +183,9 @@ use crate::input::{
 use crate::ipc::server::IpcServer;
 use crate::layer::mapped::LayerSurfaceRenderElement;
 use crate::layer::MappedLayer;
+use crate::layout::background::{BackgroundImages, BackgroundRenderElement};
 use crate::layout::tile::TileRenderElement;
-use crate::layout::workspace::{Workspace, WorkspaceId};
+use crate::layout::workspace::{Workspace, WorkspaceId, WorkspaceRenderElement};
 use crate::layout::{
     HitType, Layout, LayoutElement as _, LayoutElementRenderElement, MonitorRenderElement,
 };
@@ -204,19 +211,35 @@ use crate::render_helpers::{
 use crate::screencasting::Screencasting;
 use crate::ui::config_error_notification::ConfigErrorNotification;
 use crate::ui::exit_confirm_dialog::{ExitConfirmDialog, ExitConfirmDialogRenderElement};
+use crate::ui::frame_stats::FrameStatsOverlay;
 use crate::ui::hotkey_overlay::HotkeyOverlay;
+use crate::ui::idle_dim::IdleDim;
 use crate::ui::mru::{MruCloseRequest, WindowMruUi, WindowMruUiRenderElement};
+use crate::ui::osd::Osd;
+use crate::ui::overview_search::OverviewSearch;
+use crate::ui::permission_prompt::PermissionPrompt;
+use crate::ui::resize_mode::ResizeMode;
 use crate::ui::screen_transition::{self, ScreenTransition};
 use crate::ui::screenshot_ui::{OutputScreenshot, ScreenshotUi, ScreenshotUiRenderElement};
+use crate::ui::tab_preview::{TabPreview, TabPreviewRenderElement};
+use crate::ui::unresponsive_prompt::PromptButton;
+use crate::utils::accent_color::AccentTransition;
+use crate::utils::background_permissions::BackgroundPermissions;
+use crate::utils::backlight::{Backlight, BrightnessChange};
+use crate::utils::pings::PendingPings;
 use crate::utils::scale::{closest_representable_scale, guess_monitor_scale};
+use crate::utils::session_snapshot::{SessionRestore, SnapshotWrites};
 use crate::utils::spawning::{CHILD_DISPLAY, CHILD_ENV};
 use crate::utils::vblank_throttle::VBlankThrottle;
+use crate::utils::watchdog::Watchdog;
 use crate::utils::watcher::Watcher;
+use crate::utils::workspace_export::WorkspaceImport;
 use crate::utils::xwayland::satellite::Satellite;
 use crate::utils::{
-    center, center_f64, expand_home, get_monotonic_time, ipc_transform_to_smithay, is_mapped,
-    logical_output, make_screenshot_path, output_matches_name, output_size, panel_orientation,
-    send_scale_transform, write_png_rgba8, xwayland,
+    battery, center, center_f64, expand_home, get_monotonic_time, ipc_transform_to_smithay,
+    is_mapped, logical_output, make_screenshot_path, night_light, output_matches_name, output_size,
+    panel_orientation, send_scale_transform, video_cadence, with_toplevel_role, write_png_rgba8,
+    xwayland,
 };
 use crate::window::mapped::MappedId;
 use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef};
@@ -238,6 +261,11 @@ pub struct Niri {
     /// (and transient changes dropped).
     pub config_file_output_config: niri_config::Outputs,
 
+    /// Name of the currently applied output profile.
+    pub active_output_profile: Option<String>,
+    /// Connected outputs that the output profile was last picked for.
+    pub output_profile_outputs: Option<Vec<String>>,
+
     pub config_file_watcher: Option<Watcher>,
 
     pub event_loop: LoopHandle<'static, State>,
@@ -264,6 +292,8 @@ pub struct Niri {
     // Each workspace corresponds to a Space. Each workspace generally has one Output mapped to it,
     // however it may have none (when there are no outputs connected) or multiple (when mirroring).
     pub layout: Layout<Mapped>,
+    /// Workspace background images, shared between all workspaces.
+    pub background_images: BackgroundImages,
 
     // This space does not actually contain any windows, but all outputs are mapped into it
     // according to their global position.
@@ -302,6 +332,8 @@ pub struct Niri {
     /// Libinput guarantees that the lid switch starts in open state, and if it was closed during
     /// startup, libinput will immediately send a closed event.
     pub is_lid_closed: bool,
+    /// Whether a convertible laptop is in tablet mode.
+    pub is_tablet_mode: bool,
 
     pub devices: HashSet<input::Device>,
     pub tablets: HashMap<input::Device, TabletData>,
@@ -319,6 +351,7 @@ pub struct Niri {
     pub screencopy_state: ScreencopyManagerState,
     pub output_management_state: OutputManagementManagerState,
     pub viewporter_state: ViewporterState,
+    pub content_type_state: ContentTypeState,
     pub xdg_foreign_state: XdgForeignState,
     pub shm_state: ShmState,
     pub output_manager_state: OutputManagerState,
@@ -366,8 +399,38 @@ pub struct Niri {
     pub bind_repeat_timer: Option<RegistrationToken>,
     pub keyboard_focus: KeyboardFocus,
     pub layer_shell_on_demand_focus: Option<LayerSurface>,
+    /// Pending focus-follows-mouse activation with a hover delay.
+    pub focus_follows_mouse_timer: Option<RegistrationToken>,
+    /// Whether focus-follows-mouse waits for the layout to settle after a keyboard focus change.
+    pub focus_follows_mouse_suppressed: bool,
     pub idle_inhibiting_surfaces: HashSet<WlSurface>,
     pub is_fdo_idle_inhibited: Arc<AtomicBool>,
+    /// Whether idle is inhibited as of the last `refresh_idle_inhibit()`.
+    pub is_idle_inhibited: bool,
+    /// Monotonic time of the last user activity.
+    pub last_activity: Duration,
+    pub idle_dim_timer: Option<RegistrationToken>,
+    /// Re-checks fullscreen video playback, so that it can stop without further commits.
+    pub fullscreen_video_timer: Option<RegistrationToken>,
+    /// Refreshes the layout when a pending container layout stops waiting for acks.
+    pub pending_layout_timer: Option<RegistrationToken>,
+    /// Night light state forced by the toggle action, overriding the schedule.
+    pub night_light_forced: Option<bool>,
+    /// Color temperature currently applied to the outputs.
+    pub night_light_temperature: u16,
+    pub night_light_timer: Option<RegistrationToken>,
+    /// Power saving state forced by the toggle action, overriding the battery state.
+    pub power_saving_forced: Option<bool>,
+    /// Whether power saving is currently on.
+    pub power_saving: bool,
+    /// Whether the system runs on battery.
+    pub on_battery: bool,
+    pub power_saving_timer: Option<RegistrationToken>,
+    /// Pings sent to clients with windows that weren't answered yet.
+    pub pending_pings: PendingPings<ClientId, ShellClient>,
+    pub ping_timer: Option<RegistrationToken>,
+    /// Backlight of the built-in display, if any.
+    pub backlight: Option<Backlight>,
     pub keyboard_shortcuts_inhibiting_surfaces: HashMap<WlSurface, KeyboardShortcutsInhibitor>,
 
     /// Most recent XKB settings from org.freedesktop.locale1.
@@ -408,6 +471,12 @@ pub struct Niri {
     /// Used for limiting the notify to once per iteration, so that it's not spammed with high
     /// resolution mice.
     pub notified_activity_this_iteration: bool,
+    /// Windows that can't be seen, for throttling their frame callbacks.
+    ///
+    /// Computed on first use and cleared whenever the layout may have changed since, so that
+    /// sending frame callbacks to every output during a refresh cycle doesn't walk the layout
+    /// again.
+    pub occluded_windows: Option<HashSet<Window>>,
     pub pointer_inside_hot_corner: bool,
     pub tablet_cursor_location: Option<Point<f64, Logical>>,
     pub gesture_swipe_3f_cumulative: Option<(f64, f64)>,
@@ -420,6 +489,8 @@ pub struct Niri {
     pub horizontal_finger_scroll_tracker: ScrollTracker,
     pub mods_with_finger_scroll_binds: HashSet<Modifiers>,
     pub binding_mode: String,
+    /// Binds of the config that are shadowed by something else.
+    pub bind_conflicts: Vec<niri_ipc::BindConflict>,
 
     pub lock_state: LockState,
 
@@ -428,17 +499,31 @@ pub struct Niri {
 
     pub screenshot_ui: ScreenshotUi,
     pub config_error_notification: ConfigErrorNotification,
+    /// Readout of levels such as the brightness.
+    pub osd: Osd,
     pub hotkey_overlay: HotkeyOverlay,
     pub exit_confirm_dialog: ExitConfirmDialog,
+    pub overview_search: OverviewSearch,
+    pub resize_mode: ResizeMode,
+    pub permission_prompt: PermissionPrompt,
+    pub tab_preview: TabPreview,
+    pub tab_preview_timer: Option<RegistrationToken>,
+    /// Time and window of the last click on a tab, for detecting double clicks.
+    pub tab_bar_last_click: Option<(Duration, Window)>,
+    /// Corners that picture-in-picture windows were last left in, by app id.
+    pub picture_in_picture_corners: HashMap<String, Corner>,
 
     pub window_mru_ui: WindowMruUi,
     pub pending_mru_commit: Option<PendingMruCommit>,
 
     pub pick_window: Option<async_channel::Sender<Option<MappedId>>>,
     pub pick_color: Option<async_channel::Sender<Option<niri_ipc::PickedColor>>>,
+    pub macro_playback: Option<MacroPlayback>,
 
     pub debug_draw_opaque_regions: bool,
     pub debug_draw_damage: bool,
+    pub debug_draw_frame_stats: bool,
+    pub frame_stats_overlay: FrameStatsOverlay,
 
     #[cfg(feature = "dbus")]
     pub dbus: Option<crate::dbus::DBusServers>,
@@ -451,8 +536,21 @@ pub struct Niri {
 
     pub ipc_server: Option<IpcServer>,
     pub ipc_outputs_changed: bool,
+    pub virtual_outputs: VirtualOutputs,
 
     pub satellite: Option<Satellite>,
+    /// Watchdog respawning crashed critical clients.
+    pub watchdog: Option<Watchdog>,
+    /// Ongoing import of a workspace tiling tree.
+    pub workspace_import: Option<WorkspaceImport>,
+    /// Ongoing placement of windows from a crashed session.
+    pub session_restore: Option<SessionRestore>,
+    /// Session snapshot writes, see [`session_snapshot`](crate::utils::session_snapshot).
+    pub session_snapshot_writes: SnapshotWrites,
+    /// Saved answers to background portal requests.
+    pub background_permissions: BackgroundPermissions,
+    /// Ongoing fade to an accent color set at runtime.
+    pub accent_transition: Option<AccentTransition>,
 
     #[cfg(feature = "xdp-gnome-screencast")]
     pub casting: Screencasting,
@@ -493,6 +591,8 @@ pub struct OutputState {
     pub on_demand_vrr_enabled: bool,
     // After the last redraw, some ongoing animations still remain.
     pub unfinished_animations_remain: bool,
+    /// A redraw was put off to stay under the max render rate.
+    pub redraw_deferred: bool,
     /// Last sequence received in a vblank event.
     pub last_drm_sequence: Option<u32>,
     pub vblank_throttle: VBlankThrottle,
@@ -523,8 +623,24 @@ pub struct OutputState {
     pub lock_surface: Option<LockSurface>,
     pub lock_color_buffer: SolidColorBuffer,
     screen_transition: Option<ScreenTransition>,
+    /// Darkening layer while the output is dimmed for inactivity.
+    idle_dim: Option<IdleDim>,
+    /// Whether this output alone is powered off, through an action or the idle timeout.
+    ///
+    /// Powered off outputs aren't rendered, so their frame clocks stay suspended.
+    pub powered_off: bool,
+    /// Night light tint for outputs where gamma can't be set, and its alpha.
+    night_light_tint: Option<(SolidColorBuffer, f32)>,
+    /// Whether the output shows a fullscreen window that is playing video.
+    pub plays_fullscreen_video: bool,
+    /// Last time the output was seen playing fullscreen video.
+    ///
+    /// Video counts as activity for dimming this output, but not for the others.
+    last_video_activity: Duration,
     /// Damage tracker used for the debug damage visualization.
     pub debug_damage_tracker: OutputDamageTracker,
+    /// Direct scanout state after the last frame.
+    pub scanout: ScanoutState,
 }
 
 #[derive(Debug, Default)]
@@ -542,6 +658,34 @@ pub enum RedrawState {
     WaitingForEstimatedVBlankAndQueued(RegistrationToken),
 }
 
+/// Direct scanout state of an output.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanoutState {
+    /// Fullscreen window whose buffer can be scanned out, or why there's none.
+    pub candidate: Result<MappedId, niri_ipc::ScanoutBlocker>,
+    /// Whether the last frame showed a client buffer directly on the primary plane.
+    pub direct: bool,
+}
+
+impl Default for ScanoutState {
+    fn default() -> Self {
+        Self {
+            candidate: Err(niri_ipc::ScanoutBlocker::NoDrm),
+            direct: false,
+        }
+    }
+}
+
+impl ScanoutState {
+    pub fn to_ipc(self) -> niri_ipc::OutputScanout {
+        niri_ipc::OutputScanout {
+            direct: self.direct,
+            window_id: self.candidate.ok().map(|id| id.get()),
+            blocker: self.candidate.err(),
+        }
+    }
+}
+
 pub struct PopupGrabState {
     pub root: WlSurface,
     pub grab: PopupGrab<State>,
@@ -613,6 +757,8 @@ pub enum CenterCoords {
     Both,
     // Force centering even if the cursor is already in the rectangle.
     BothAlways,
+    // Go back to where the cursor last was in the window, or to the center.
+    LastPosition,
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -788,6 +934,12 @@ impl State {
         state.ipc_keyboard_layouts_changed();
         // Focus the default monitor if set by the user.
         state.focus_default_monitor();
+        // Start following the night light schedule.
+        state.update_night_light();
+        // Start following the battery state.
+        state.update_power_saving();
+        // Start pinging clients.
+        state.update_unresponsive_windows();
 
         Ok(state)
     }
@@ -803,6 +955,8 @@ impl State {
         // build up (the 1 second frame callback timer will call this line).
         self.niri.advance_animations();
 
+        // The refresh and the animations could have changed what's occluded.
+        self.niri.occluded_windows = None;
         self.niri.redraw_queued_outputs(&mut self.backend);
 
         {
@@ -817,6 +971,7 @@ impl State {
         self.niri.clock.clear();
         self.niri.pointer_inactivity_timer_got_reset = false;
         self.niri.notified_activity_this_iteration = false;
+        self.niri.occluded_windows = None;
     }
 
     // We monitor both libinput and logind: libinput is always there (including without DBus), but
@@ -829,6 +984,269 @@ impl State {
         debug!("laptop lid {}", if is_closed { "closed" } else { "opened" });
         self.niri.is_lid_closed = is_closed;
         self.backend.on_output_config_changed(&mut self.niri);
+        self.ipc_refresh_switches();
+    }
+
+    pub fn set_tablet_mode(&mut self, is_tablet_mode: bool) {
+        if self.niri.is_tablet_mode == is_tablet_mode {
+            return;
+        }
+
+        debug!("tablet mode {}", if is_tablet_mode { "on" } else { "off" });
+        self.niri.is_tablet_mode = is_tablet_mode;
+
+        let config = self.niri.config.borrow();
+        self.niri.layout.set_tablet_mode(&config, is_tablet_mode);
+        drop(config);
+        self.niri.queue_redraw_all();
+
+        self.ipc_refresh_switches();
+    }
+
+    /// Updates the night light temperature and schedules the next update.
+    pub fn update_night_light(&mut self) {
+        let _span = tracy_client::span!("State::update_night_light");
+
+        if let Some(token) = self.niri.night_light_timer.take() {
+            self.niri.event_loop.remove(token);
+        }
+
+        let config = self.niri.config.borrow().night_light;
+        let scheduled = night_light::local_minutes_of_day()
+            .map_or(0., |now| night_light::scheduled_strength(&config, now));
+        self.niri.night_light_forced =
+            night_light::still_forced(self.niri.night_light_forced, scheduled);
+        let strength = match self.niri.night_light_forced {
+            Some(true) => 1.,
+            Some(false) => 0.,
+            None => scheduled,
+        };
+
+        let temperature = night_light::temperature(&config, strength);
+        if temperature != self.niri.night_light_temperature {
+            self.niri.night_light_temperature = temperature;
+
+            let outputs: Vec<_> = self.niri.output_state.keys().cloned().collect();
+            for output in outputs {
+                self.apply_night_light_to(&output);
+            }
+        }
+
+        if config.from.is_some() && config.to.is_some() {
+            let timer = Timer::from_duration(night_light::UPDATE_INTERVAL);
+            let token = self
+                .niri
+                .event_loop
+                .insert_source(timer, |_, _, state| {
+                    state.niri.night_light_timer = None;
+                    state.update_night_light();
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+            self.niri.night_light_timer = Some(token);
+        }
+    }
+
+    /// Turns the night light on or off until the schedule catches up.
+    pub fn toggle_night_light(&mut self) {
+        let config = self.niri.config.borrow().night_light;
+        let scheduled = night_light::local_minutes_of_day()
+            .map_or(0., |now| night_light::scheduled_strength(&config, now));
+        let on = self.niri.night_light_temperature == night_light::NEUTRAL_TEMPERATURE;
+
+        // Once the toggle agrees with the schedule, the schedule takes over again.
+        self.niri.night_light_forced = night_light::still_forced(Some(on), scheduled);
+        self.update_night_light();
+    }
+
+    /// Updates the battery state and turns power saving on or off to match.
+    pub fn update_power_saving(&mut self) {
+        let _span = tracy_client::span!("State::update_power_saving");
+
+        if let Some(token) = self.niri.power_saving_timer.take() {
+            self.niri.event_loop.remove(token);
+        }
+
+        let on_battery = battery::is_on_battery();
+        if self.niri.on_battery != on_battery {
+            debug!("{}", if on_battery { "on battery" } else { "on AC" });
+            self.niri.on_battery = on_battery;
+            // Plugging in or out hands control back to the battery state.
+            self.niri.power_saving_forced = None;
+        }
+
+        let config = self.niri.config.borrow().power_saving;
+        let automatic = config.on_battery && on_battery;
+        let power_saving = self.niri.power_saving_forced.unwrap_or(automatic);
+        if self.niri.power_saving != power_saving {
+            debug!("power saving {}", if power_saving { "on" } else { "off" });
+            self.niri.power_saving = power_saving;
+
+            let config = self.niri.config.borrow();
+            self.niri.layout.set_power_saving(&config, power_saving);
+            let rate = animation_rate(&config, power_saving);
+            drop(config);
+            self.niri.clock.set_rate(rate);
+            self.niri.queue_redraw_all();
+        }
+
+        let timer = Timer::from_duration(battery::POLL_INTERVAL);
+        let token = self
+            .niri
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.power_saving_timer = None;
+                state.update_power_saving();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.niri.power_saving_timer = Some(token);
+    }
+
+    /// Turns power saving on or off until the battery state changes.
+    pub fn toggle_power_saving(&mut self) {
+        let config = self.niri.config.borrow().power_saving;
+        let automatic = config.on_battery && self.niri.on_battery;
+        let on = !self.niri.power_saving;
+
+        self.niri.power_saving_forced = (on != automatic).then_some(on);
+        self.update_power_saving();
+    }
+
+    /// Pings clients with windows and marks the ones that take too long to answer.
+    pub fn update_unresponsive_windows(&mut self) {
+        let _span = tracy_client::span!("State::update_unresponsive_windows");
+
+        if let Some(token) = self.niri.ping_timer.take() {
+            self.niri.event_loop.remove(token);
+        }
+
+        let config = self.niri.config.borrow().unresponsive_windows;
+        if config.off {
+            self.niri.pending_pings.clear();
+            self.niri.refresh_unresponsive_windows();
+            return;
+        }
+
+        let now = get_monotonic_time();
+        let timeout = Duration::from_millis(u64::from(config.timeout_ms));
+        let pending = &mut self.niri.pending_pings;
+        pending.retain_clients(ShellClient::alive);
+        pending.check_timeouts(now, timeout);
+
+        // Ping every client with windows once, unless it still owes us a pong.
+        for (_, mapped) in self.niri.layout.windows() {
+            let toplevel = mapped.toplevel();
+            let Some(client) = toplevel.wl_surface().client() else {
+                continue;
+            };
+            let client_id = client.id();
+            if pending.is_pending(&client_id) {
+                continue;
+            }
+
+            let client = toplevel.client();
+            match client.send_ping(SERIAL_COUNTER.next_serial()) {
+                Ok(()) => pending.push(client_id, client, now),
+                Err(err) => debug!("error pinging client: {err:?}"),
+            }
+        }
+
+        self.niri.refresh_unresponsive_windows();
+
+        let interval = Duration::from_millis(u64::from(config.ping_interval_ms.max(100)));
+        let timer = Timer::from_duration(interval);
+        let token = self
+            .niri
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.ping_timer = None;
+                state.update_unresponsive_windows();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.niri.ping_timer = Some(token);
+    }
+
+    /// Handles a click on a button of the unresponsive window prompt.
+    pub fn answer_unresponsive_prompt(&mut self, window: &Window, button: PromptButton) {
+        let Some(client) = window.wl_surface().and_then(|surface| surface.client()) else {
+            return;
+        };
+
+        match button {
+            PromptButton::Wait => {
+                // Give the client another timeout to answer.
+                let now = get_monotonic_time();
+                self.niri.pending_pings.wait(&client.id(), now);
+                self.niri.refresh_unresponsive_windows();
+            }
+            PromptButton::ForceClose => {
+                info!("force closing an unresponsive client");
+
+                // The client isn't reading its socket anyway, so there's no point in sending it a
+                // protocol error first.
+                self.niri
+                    .display_handle
+                    .backend_handle()
+                    .kill_client(client.id(), DisconnectReason::ConnectionClosed);
+            }
+        }
+    }
+
+    /// Applies the current night light temperature to the output.
+    ///
+    /// Sets the gamma ramp where possible and falls back to drawing a tint otherwise. Outputs
+    /// where a client controls the gamma are left to that client.
+    pub fn apply_night_light_to(&mut self, output: &Output) {
+        let temperature = self.niri.night_light_temperature;
+        let is_neutral = temperature == night_light::NEUTRAL_TEMPERATURE;
+
+        // Virtual outputs aren't monitors, and clients controlling the gamma take precedence.
+        let is_free = !self.niri.virtual_outputs.contains(output)
+            && !self
+                .niri
+                .gamma_control_manager_state
+                .has_gamma_control(output);
+        let mut needs_tint = is_free && !is_neutral;
+
+        if is_free {
+            if let Backend::Tty(tty) = &mut self.backend {
+                match tty.get_gamma_size(output) {
+                    Ok(0) => (),
+                    Ok(size) => {
+                        let ramp =
+                            (!is_neutral).then(|| night_light::gamma_ramp(size, temperature));
+                        match tty.set_gamma(output, ramp) {
+                            Ok(()) => needs_tint = false,
+                            Err(err) => {
+                                warn!("error setting gamma for output {}: {err:?}", output.name());
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        debug!(
+                            "error getting gamma size for output {}: {err:?}",
+                            output.name()
+                        );
+                    }
+                }
+            }
+        }
+
+        let Some(state) = self.niri.output_state.get_mut(output) else {
+            return;
+        };
+        let tint = if needs_tint {
+            night_light::tint(temperature)
+        } else {
+            None
+        };
+        state.night_light_tint = tint.map(|([r, g, b], alpha)| {
+            let buffer = SolidColorBuffer::new(output_size(output), [r, g, b, 1.]);
+            (buffer, alpha)
+        });
+        self.niri.queue_redraw(output);
     }
 
     fn refresh(&mut self) {
@@ -853,8 +1271,10 @@ impl State {
         self.niri.cursor_manager.check_cursor_image_surface_alive();
         self.niri.refresh_pointer_outputs();
         self.niri.global_space.refresh();
+        self.niri.refresh_fullscreen_video();
         self.niri.refresh_idle_inhibit();
         self.refresh_pointer_contents();
+        self.niri.refresh_tab_preview();
         foreign_toplevel::refresh(self);
         ext_workspace::refresh(self);
 
@@ -867,6 +1287,7 @@ impl State {
         self.ipc_refresh_casts();
 
         self.niri.refresh_window_rules();
+        self.niri.refresh_fullscreen_spans();
         self.refresh_ipc_outputs();
         self.ipc_refresh_layout();
         self.ipc_refresh_keyboard_layout_index();
@@ -945,7 +1366,7 @@ impl State {
                     center_f64(rect)
                 }
             }
-            CenterCoords::Both => {
+            CenterCoords::Both | CenterCoords::LastPosition => {
                 if x_in_bound && y_in_bound {
                     return false;
                 } else {
@@ -961,30 +1382,71 @@ impl State {
     }
 
     pub fn move_cursor_to_focused_tile(&mut self, mode: CenterCoords) -> bool {
-        if !self.niri.keyboard_focus.is_layout() {
+        // Don't yank the pointer out of a drag or an interactive move or resize.
+        if self.niri.seat.get_pointer().unwrap().is_grabbed() || self.niri.dnd_icon.is_some() {
             return false;
         }
 
-        if self.niri.tablet_cursor_location.is_some() {
+        let Some(rect) = self.focused_tile_rect() else {
             return false;
+        };
+
+        if matches!(mode, CenterCoords::LastPosition) {
+            let last_pos = self
+                .niri
+                .layout
+                .focus()
+                .and_then(|m| m.last_pointer_pos().get());
+            if let Some(fraction) = last_pos {
+                if rect.contains(self.niri.seat.get_pointer().unwrap().current_location()) {
+                    return false;
+                }
+
+                let p =
+                    rect.loc + Point::from((fraction.x * rect.size.w, fraction.y * rect.size.h));
+                self.move_cursor(p);
+                return true;
+            }
         }
 
-        let Some(output) = self.niri.layout.active_output() else {
-            return false;
-        };
+        self.move_cursor_to_rect(rect, mode)
+    }
+
+    /// Returns the global rectangle of the focused tile if the cursor can be warped to it.
+    fn focused_tile_rect(&self) -> Option<Rectangle<f64, Logical>> {
+        if !self.niri.keyboard_focus.is_layout() {
+            return None;
+        }
+
+        if self.niri.tablet_cursor_location.is_some() {
+            return None;
+        }
+
+        let output = self.niri.layout.active_output()?;
         let monitor = self.niri.layout.monitor_for_output(output).unwrap();
 
-        let mut rv = false;
-        let rect = monitor.active_tile_visual_rectangle();
+        let mut rect = monitor.active_tile_visual_rectangle()?;
+        let output_geo = self.niri.global_space.output_geometry(output).unwrap();
+        rect.loc += output_geo.loc.to_f64();
+        Some(rect)
+    }
+
+    /// Remembers where the cursor is in the focused tile, for warping back to it later.
+    pub fn remember_pointer_in_focused_tile(&mut self) {
+        let Some(rect) = self.focused_tile_rect() else {
+            return;
+        };
 
-        if let Some(rect) = rect {
-            let output_geo = self.niri.global_space.output_geometry(output).unwrap();
-            let mut rect = rect;
-            rect.loc += output_geo.loc.to_f64();
-            rv = self.move_cursor_to_rect(rect, mode);
+        let pos = self.niri.seat.get_pointer().unwrap().current_location();
+        if !rect.contains(pos) || rect.size.w <= 0. || rect.size.h <= 0. {
+            return;
         }
 
-        rv
+        let offset = pos - rect.loc;
+        let fraction = Point::from((offset.x / rect.size.w, offset.y / rect.size.h));
+        if let Some(mapped) = self.niri.layout.focus() {
+            mapped.last_pointer_pos().set(Some(fraction));
+        }
     }
 
     pub fn focus_default_monitor(&mut self) {
@@ -1043,6 +1505,7 @@ impl State {
                 None => CenterCoords::Separately,
                 Some(WarpMouseToFocusMode::CenterXy) => CenterCoords::Both,
                 Some(WarpMouseToFocusMode::CenterXyAlways) => CenterCoords::BothAlways,
+                Some(WarpMouseToFocusMode::LastPosition) => CenterCoords::LastPosition,
             },
         };
         self.move_cursor_to_focused_tile(focused)
@@ -1055,6 +1518,7 @@ impl State {
                 None => CenterCoords::Both,
                 Some(WarpMouseToFocusMode::CenterXy) => CenterCoords::Both,
                 Some(WarpMouseToFocusMode::CenterXyAlways) => CenterCoords::BothAlways,
+                Some(WarpMouseToFocusMode::LastPosition) => CenterCoords::LastPosition,
             },
         };
         self.move_cursor_to_focused_tile(focused)
@@ -1467,6 +1931,9 @@ impl State {
 
         self.niri.config_error_notification.hide();
 
+        // The config file accent color takes over from the one set at runtime.
+        self.niri.accent_transition = None;
+
         // Find & orphan removed named workspaces.
         let mut removed_workspaces: Vec<String> = vec![];
         for ws in &self.niri.config.borrow().workspaces {
@@ -1479,6 +1946,8 @@ impl State {
         }
 
         self.niri.layout.update_config(&config);
+        // Pick up changes to the image files even if the config still points to the same paths.
+        self.niri.background_images.clear();
         for mapped in self.niri.mapped_layer_surfaces.values_mut() {
             mapped.update_config(&config);
         }
@@ -1488,7 +1957,7 @@ impl State {
             self.niri.layout.ensure_named_workspace(ws_config);
         }
 
-        let rate = 1.0 / config.animations.slowdown.max(0.001);
+        let rate = animation_rate(&config, self.niri.power_saving);
         self.niri.clock.set_rate(rate);
         self.niri
             .clock
@@ -1506,6 +1975,7 @@ impl State {
         let mut cursor_inactivity_timeout_changed = false;
         let mut recent_windows_changed = false;
         let mut xwls_changed = false;
+        let mut show_bind_conflicts = None;
         let mut old_config = self.niri.config.borrow_mut();
 
         // Reload the cursor.
@@ -1573,6 +2043,20 @@ impl State {
             self.niri.mods_with_wheel_binds = mods_with_wheel_binds(new_mod_key, active_binds);
             self.niri.mods_with_finger_scroll_binds =
                 mods_with_finger_scroll_binds(new_mod_key, active_binds);
+
+            // A held bind would keep repeating its old action.
+            if let Some(token) = self.niri.bind_repeat_timer.take() {
+                self.niri.event_loop.remove(token);
+            }
+
+            let conflicts = bind_conflicts::find(&config, new_mod_key);
+            if conflicts != self.niri.bind_conflicts {
+                let unreachable = bind_conflicts::count_unreachable(&conflicts);
+                if unreachable > 0 {
+                    show_bind_conflicts = Some(unreachable);
+                }
+                self.niri.bind_conflicts = conflicts;
+            }
         }
 
         if config.window_rules != old_config.window_rules {
@@ -1639,10 +2123,25 @@ impl State {
             recent_windows_changed = true;
         }
 
+        let output_profiles_changed = config.output_profiles != old_config.output_profiles;
+        #[cfg(feature = "dbus")]
+        let accent_color_changed = config.accent_color != old_config.accent_color;
+        let ui_font_changed = config.ui_font != old_config.ui_font;
+        let unresponsive_windows_changed =
+            config.unresponsive_windows != old_config.unresponsive_windows;
+
         if config.xwayland_satellite != old_config.xwayland_satellite {
             xwls_changed = true;
         }
 
+        let changed_config_values: Vec<_> = TUNABLE_PATHS
+            .iter()
+            .filter_map(|path| {
+                let value = config.tunable_value(path).ok()?;
+                (old_config.tunable_value(path).ok() != Some(value)).then_some((*path, value))
+            })
+            .collect();
+
         *old_config = config;
 
         if let Some(outputs) = preserved_output_config {
@@ -1652,6 +2151,12 @@ impl State {
         // Release the borrow.
         drop(old_config);
 
+        if let Some(count) = show_bind_conflicts {
+            self.niri
+                .config_error_notification
+                .show_bind_conflicts(count);
+        }
+
         // Now with a &mut self we can reload the xkb config.
         if let Some(mut xkb) = reload_xkb {
             let mut set_xkb_config = true;
@@ -1691,7 +2196,16 @@ impl State {
             self.backend.update_ignored_nodes_config(&mut self.niri);
         }
 
-        if output_config_changed {
+        let mut output_config_reloaded = false;
+        if output_profiles_changed
+            || (output_config_changed && self.niri.active_output_profile.is_some())
+        {
+            // Output profiles apply on top of the output config, so pick and apply one again.
+            self.niri.output_profile_outputs = None;
+            output_config_reloaded = self.refresh_output_profile();
+        }
+
+        if output_config_changed && !output_config_reloaded {
             self.reload_output_config();
         }
 
@@ -1717,10 +2231,27 @@ impl State {
             self.niri.window_mru_ui.update_binds();
         }
 
+        #[cfg(feature = "dbus")]
+        if accent_color_changed {
+            self.niri.on_accent_color_changed();
+        }
+
         if recent_windows_changed {
             self.niri.window_mru_ui.update_config();
         }
 
+        if ui_font_changed {
+            self.niri.exit_confirm_dialog.update_config();
+            self.niri.hotkey_overlay.update_config();
+            self.niri.config_error_notification.update_config();
+            self.niri.permission_prompt.update_config();
+            self.niri.osd.update_config();
+        }
+
+        if unresponsive_windows_changed {
+            self.update_unresponsive_windows();
+        }
+
         if xwls_changed {
             // If xwl-s was previously working and is now off, we don't try to kill it or stop
             // watching the sockets, for simplicity's sake.
@@ -1743,6 +2274,11 @@ impl State {
 
             // This won't change the systemd environment, but oh well.
             *CHILD_DISPLAY.write().unwrap() = display_name;
+
+            let scale_to_output = config.xwayland_satellite.scale_to_output;
+            self.niri.layout.with_windows_mut(|mapped, _| {
+                mapped.set_scale_x11_to_output(scale_to_output);
+            });
         }
 
         // Can't really update xdg-decoration settings since we have to hide the globals for CSD
@@ -1750,6 +2286,10 @@ impl State {
         // global suddenly appearing? Either way, right now it's live-reloaded in a sense that new
         // clients will use the new xdg-decoration setting.
 
+        for (path, value) in changed_config_values {
+            self.ipc_config_value_changed(path, value);
+        }
+
         self.niri.queue_redraw_all();
     }
 
@@ -1765,6 +2305,7 @@ impl State {
             let scale = config
                 .and_then(|c| c.scale)
                 .map(|s| s.0)
+                .or_else(|| virtual_output::requested_scale(output))
                 .unwrap_or_else(|| {
                     let size_mm = output.physical_properties().size;
                     let resolution = output.current_mode().unwrap().size;
@@ -1806,6 +2347,9 @@ impl State {
                     state.backdrop_buffer.set_color(backdrop_color);
                     recolored_outputs.push(output.clone());
                 }
+
+                let max_render_rate = config.and_then(|c| c.max_render_rate).map(|r| r.0);
+                state.frame_clock.set_max_render_rate(max_render_rate);
             }
 
             for mon in self.niri.layout.monitors_mut() {
@@ -1813,14 +2357,7 @@ impl State {
                     continue;
                 }
 
-                let mut layout_config = config.and_then(|c| c.layout.clone());
-                // Support the deprecated non-layout background-color key.
-                if let Some(layout) = &mut layout_config {
-                    if layout.background_color.is_none() {
-                        layout.background_color = config.and_then(|c| c.background_color);
-                    }
-                }
-
+                let layout_config = config.and_then(|c| c.layout_config());
                 if mon.update_layout_config(layout_config) {
                     // Also redraw these; if anything, the background color could've changed.
                     recolored_outputs.push(output.clone());
@@ -1840,6 +2377,9 @@ impl State {
         self.backend.on_output_config_changed(&mut self.niri);
 
         self.niri.reposition_outputs(None);
+        self.niri.update_idle_dim();
+        self.update_night_light();
+        self.update_power_saving();
 
         if let Some(touch) = self.niri.seat.get_touch() {
             touch.cancel(self);
@@ -1968,63 +2508,234 @@ impl State {
         self.reload_output_config();
     }
 
-    pub fn refresh_ipc_outputs(&mut self) {
-        if !self.niri.ipc_outputs_changed {
-            return;
+    pub fn set_config_value(
+        &mut self,
+        path: &str,
+        value: niri_ipc::ConfigValue,
+    ) -> Result<(), String> {
+        let mut config = self.niri.config.borrow_mut();
+        if config.tunable_value(path)? == value {
+            return Ok(());
         }
-        self.niri.ipc_outputs_changed = false;
+        config.set_tunable_value(path, value)?;
 
-        let _span = tracy_client::span!("State::refresh_ipc_outputs");
+        self.niri.layout.update_config(&config);
+        let rate = animation_rate(&config, self.niri.power_saving);
+        self.niri.clock.set_rate(rate);
+        drop(config);
 
-        for ipc_output in self.backend.ipc_outputs().lock().unwrap().values_mut() {
-            let logical = self
-                .niri
-                .global_space
-                .outputs()
-                .find(|output| output.name() == ipc_output.name)
-                .map(logical_output);
-            ipc_output.logical = logical;
+        self.niri.queue_redraw_all();
+        self.ipc_config_value_changed(path, value);
+        Ok(())
+    }
+
+    pub fn create_headless_output(
+        &mut self,
+        width: u16,
+        height: u16,
+        scale: Option<f64>,
+        refresh: Option<i32>,
+        name: Option<String>,
+    ) -> Result<String, String> {
+        if width == 0 || height == 0 {
+            return Err(String::from("output size must be greater than zero"));
+        }
+
+        if let Some(scale) = scale {
+            if !(0.1..=10.).contains(&scale) {
+                return Err(String::from("output scale must be between 0.1 and 10"));
+            }
         }
 
-        #[cfg(feature = "dbus")]
-        self.niri.on_ipc_outputs_changed();
+        let name = match name {
+            Some(name) => {
+                if self.niri.output_by_name_match(&name).is_some() {
+                    return Err(format!("output \"{name}\" already exists"));
+                }
+                name
+            }
+            None => self
+                .niri
+                .virtual_outputs
+                .next_name(|name| self.niri.output_by_name_match(name).is_some()),
+        };
 
-        let new_config = self.backend.ipc_outputs().lock().unwrap().clone();
-        self.niri.output_management_state.notify_changes(new_config);
+        let ipc_outputs = self.backend.ipc_outputs();
+        let mut ipc_outputs = ipc_outputs.lock().unwrap();
+        let refresh = refresh.unwrap_or(virtual_output::DEFAULT_REFRESH_RATE);
+        virtual_output::add(
+            &mut self.niri,
+            &mut ipc_outputs,
+            name.clone(),
+            (width, height),
+            scale,
+            refresh,
+        );
+
+        Ok(name)
     }
 
-    pub fn open_screenshot_ui(&mut self, show_pointer: bool, path: Option<String>) {
-        if self.niri.is_locked() || self.niri.screenshot_ui.is_open() {
-            return;
+    pub fn destroy_output(&mut self, name: &str) -> Result<(), String> {
+        let ipc_outputs = self.backend.ipc_outputs();
+        let mut ipc_outputs = ipc_outputs.lock().unwrap();
+        if virtual_output::remove(&mut self.niri, &mut ipc_outputs, name) {
+            Ok(())
+        } else if self.niri.output_by_name_match(name).is_some() {
+            Err(format!("output \"{name}\" is not a virtual output"))
+        } else {
+            Err(format!("output \"{name}\" not found"))
         }
+    }
 
-        // Redraw the pointer if hidden through cursor{} options
-        if self.niri.pointer_visibility == PointerVisibility::Hidden {
-            self.niri.pointer_visibility = PointerVisibility::Visible;
-            self.niri.queue_redraw_all();
+    /// Switches to the output profile matching the connected outputs, if they changed.
+    ///
+    /// Returns `true` if the output config was reloaded.
+    pub fn refresh_output_profile(&mut self) -> bool {
+        let connected: Vec<OutputName> = self
+            .backend
+            .ipc_outputs()
+            .lock()
+            .unwrap()
+            .values()
+            .map(OutputName::from_ipc_output)
+            .collect();
+
+        let mut connectors: Vec<String> = connected.iter().map(|n| n.connector.clone()).collect();
+        connectors.sort_unstable();
+        if self.niri.output_profile_outputs.as_ref() == Some(&connectors) {
+            return false;
         }
+        self.niri.output_profile_outputs = Some(connectors);
 
-        let default_output = self
-            .niri
-            .output_under_cursor()
-            .or_else(|| self.niri.layout.active_output().cloned());
-        let Some(default_output) = default_output else {
-            return;
-        };
+        let config = self.niri.config.borrow();
+        let profile = config
+            .output_profiles
+            .iter()
+            .find(|profile| profile.matches(&connected))
+            .map(|profile| profile.name.clone());
+        drop(config);
 
-        self.niri.update_render_elements(None);
+        if profile.is_none() && self.niri.active_output_profile.is_none() {
+            return false;
+        }
 
-        let Some(screenshots) = self
-            .backend
-            .with_primary_renderer(|renderer| self.niri.capture_screenshots(renderer).collect())
-        else {
-            return;
+        self.apply_output_profile(profile.as_deref())
+    }
+
+    /// Applies the output profile with this name, or the plain output config if `None`.
+    ///
+    /// Returns `false` if there's no profile with this name.
+    pub fn apply_output_profile(&mut self, name: Option<&str>) -> bool {
+        let mut config = self.niri.config.borrow_mut();
+        let profile = match name {
+            Some(name) => {
+                let Some(profile) = config.output_profiles.iter().find(|p| p.name == name) else {
+                    return false;
+                };
+                Some(profile.clone())
+            }
+            None => None,
         };
 
-        // Now that we captured the screenshots, clear grabs like drag-and-drop, etc.
-        self.niri.seat.get_pointer().unwrap().unset_grab(
-            self,
-            SERIAL_COUNTER.next_serial(),
+        // Profiles replace transient changes, same as reloading the output config from disk.
+        let mut outputs = self.niri.config_file_output_config.clone();
+        if let Some(profile) = &profile {
+            profile.apply_to(&mut outputs);
+        }
+        config.outputs = outputs;
+        drop(config);
+
+        if let Some(name) = name {
+            info!("applying output profile \"{name}\"");
+        }
+        self.niri.active_output_profile = name.map(String::from);
+        self.reload_output_config();
+
+        for ws in profile.iter().flat_map(|profile| &profile.workspaces) {
+            let Some(new_output) = self.niri.output_by_name_match(&ws.open_on_output).cloned()
+            else {
+                continue;
+            };
+            let reference = WorkspaceReference::Name(ws.name.clone());
+            if let Some((output, old_idx)) = self.niri.find_output_and_workspace_index(reference)
+            {
+                self.niri
+                    .layout
+                    .move_workspace_to_output_by_id(old_idx, output, &new_output);
+            }
+        }
+
+        self.niri.queue_redraw_all();
+        true
+    }
+
+    pub fn refresh_ipc_outputs(&mut self) {
+        if !self.niri.ipc_outputs_changed {
+            return;
+        }
+        self.niri.ipc_outputs_changed = false;
+
+        let _span = tracy_client::span!("State::refresh_ipc_outputs");
+
+        // Hotplugged monitors can switch the output profile.
+        self.refresh_output_profile();
+
+        {
+            let ipc_outputs = self.backend.ipc_outputs();
+            let mut ipc_outputs = ipc_outputs.lock().unwrap();
+            // Virtual outputs work on top of every backend, but only the TTY backend rebuilds its
+            // outputs, so fill them in here for the others.
+            self.niri.virtual_outputs.fill_ipc_outputs(&mut ipc_outputs);
+            for ipc_output in ipc_outputs.values_mut() {
+                let logical = self
+                    .niri
+                    .global_space
+                    .outputs()
+                    .find(|output| output.name() == ipc_output.name)
+                    .map(logical_output);
+                ipc_output.logical = logical;
+            }
+        }
+
+        #[cfg(feature = "dbus")]
+        self.niri.on_ipc_outputs_changed();
+
+        let new_config = self.backend.ipc_outputs().lock().unwrap().clone();
+        self.niri.output_management_state.notify_changes(new_config);
+    }
+
+    pub fn open_screenshot_ui(&mut self, show_pointer: bool, path: Option<String>) {
+        if self.niri.is_locked() || self.niri.screenshot_ui.is_open() {
+            return;
+        }
+
+        // Redraw the pointer if hidden through cursor{} options
+        if self.niri.pointer_visibility == PointerVisibility::Hidden {
+            self.niri.pointer_visibility = PointerVisibility::Visible;
+            self.niri.queue_redraw_all();
+        }
+
+        let default_output = self
+            .niri
+            .output_under_cursor()
+            .or_else(|| self.niri.layout.active_output().cloned());
+        let Some(default_output) = default_output else {
+            return;
+        };
+
+        self.niri.update_render_elements(None);
+
+        let Some(screenshots) = self
+            .backend
+            .with_primary_renderer(|renderer| self.niri.capture_screenshots(renderer).collect())
+        else {
+            return;
+        };
+
+        // Now that we captured the screenshots, clear grabs like drag-and-drop, etc.
+        self.niri.seat.get_pointer().unwrap().unset_grab(
+            self,
+            SERIAL_COUNTER.next_serial(),
             get_monotonic_time().as_millis() as u32,
         );
         if let Some(touch) = self.niri.seat.get_touch() {
@@ -2198,6 +2909,59 @@ impl State {
         }
     }
 
+    #[cfg(feature = "dbus")]
+    pub fn on_background_msg(&mut self, msg: BackgroundToNiri) {
+        use crate::utils::with_toplevel_role;
+
+        match msg {
+            BackgroundToNiri::GetAppState(tx) => {
+                let focus = self.niri.layout.focus().map(|mapped| mapped.window.clone());
+
+                let mut apps = HashMap::new();
+                self.niri.layout.with_windows(|mapped, _, _, _| {
+                    let app_id = with_toplevel_role(mapped.toplevel(), |role| role.app_id.clone());
+                    let Some(app_id) = app_id else {
+                        return;
+                    };
+
+                    let state = if focus.as_ref() == Some(&mapped.window) {
+                        AppState::Active
+                    } else {
+                        AppState::Running
+                    };
+                    let entry = apps.entry(app_id).or_insert(state);
+                    *entry = (*entry).max(state);
+                });
+
+                if let Err(err) = tx.send_blocking(apps) {
+                    warn!("error sending app state to background portal: {err:?}");
+                }
+            }
+            BackgroundToNiri::RequestPermission {
+                app_id,
+                name,
+                kind,
+                handle,
+                reply,
+            } => {
+                if let Some(allowed) = self.niri.background_permissions.get(&app_id, kind) {
+                    let _ = reply.send_blocking(allowed);
+                    return;
+                }
+
+                self.niri
+                    .permission_prompt
+                    .ask(app_id, name, kind, handle, reply);
+                self.niri.queue_redraw_all();
+            }
+            BackgroundToNiri::CancelRequest { handle } => {
+                if self.niri.permission_prompt.cancel(&handle) {
+                    self.niri.queue_redraw_all();
+                }
+            }
+        }
+    }
+
     #[cfg(feature = "dbus")]
     pub fn on_login1_msg(&mut self, msg: Login1ToNiri) {
         let Login1ToNiri::LidClosedChanged(is_closed) = msg;
@@ -2248,12 +3012,24 @@ impl Niri {
 
         let mut animation_clock = Clock::default();
 
-        let rate = 1.0 / config_.animations.slowdown.max(0.001);
+        let rate = animation_rate(&config_, false);
         animation_clock.set_rate(rate);
         animation_clock.set_complete_instantly(config_.animations.off);
 
         let layout = Layout::new(animation_clock.clone(), &config_);
 
+        let (to_niri, from_loader) = calloop::channel::channel();
+        event_loop
+            .insert_source(from_loader, |event, _, state| {
+                if let calloop::channel::Event::Msg(image) = event {
+                    if state.niri.background_images.finish_loading(image) {
+                        state.niri.queue_redraw_all();
+                    }
+                }
+            })
+            .unwrap();
+        let background_images = BackgroundImages::new(to_niri);
+
         let (blocker_cleared_tx, blocker_cleared_rx) = mpsc::channel();
 
         fn client_is_unrestricted(client: &Client) -> bool {
@@ -2347,6 +3123,7 @@ impl Niri {
         let screencopy_state =
             ScreencopyManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
         let viewporter_state = ViewporterState::new::<State>(&display_handle);
+        let content_type_state = ContentTypeState::new::<State>(&display_handle);
         let xdg_foreign_state = XdgForeignState::new::<State>(&display_handle);
 
         let is_tty = matches!(backend, Backend::Tty(_));
@@ -2411,11 +3188,20 @@ impl Niri {
         let mods_with_mouse_binds = mods_with_mouse_binds(mod_key, active_binds);
         let mods_with_wheel_binds = mods_with_wheel_binds(mod_key, active_binds);
         let mods_with_finger_scroll_binds = mods_with_finger_scroll_binds(mod_key, active_binds);
+        let bind_conflicts = bind_conflicts::find(&config_, mod_key);
 
         let screenshot_ui = ScreenshotUi::new(animation_clock.clone(), config.clone());
         let window_mru_ui = WindowMruUi::new(config.clone());
         let config_error_notification =
             ConfigErrorNotification::new(animation_clock.clone(), config.clone());
+        let osd = Osd::new(animation_clock.clone(), config.clone());
+
+        // Nested niri shouldn't control the backlight of the host.
+        let backlight = if matches!(backend, Backend::Tty(_)) {
+            Backlight::find()
+        } else {
+            None
+        };
 
         let mut hotkey_overlay = HotkeyOverlay::new(config.clone(), mod_key);
         if !config_.hotkey_overlay.skip_at_startup {
@@ -2446,13 +3232,15 @@ impl Niri {
                         client,
                         restricted: false,
                         credentials_unknown: false,
+                        security_context: None,
                     });
                 })
                 .unwrap();
             socket_name
         });
 
-        let ipc_server = match IpcServer::start(&event_loop, socket_name.as_deref()) {
+        let i3_socket = config_.ipc.i3_socket;
+        let ipc_server = match IpcServer::start(&event_loop, socket_name.as_deref(), i3_socket) {
             Ok(server) => Some(server),
             Err(err) => {
                 warn!("error starting IPC server: {err:?}");
@@ -2491,6 +3279,8 @@ impl Niri {
         let mut niri = Self {
             config,
             config_file_output_config,
+            active_output_profile: None,
+            output_profile_outputs: None,
             config_file_watcher: None,
 
             event_loop,
@@ -2504,6 +3294,7 @@ impl Niri {
             clock: animation_clock,
 
             layout,
+            background_images,
             global_space: Space::default(),
             sorted_outputs: Vec::default(),
             output_state: HashMap::new(),
@@ -2516,6 +3307,7 @@ impl Niri {
             blocker_cleared_rx,
             monitors_active: true,
             is_lid_closed: false,
+            is_tablet_mode: false,
 
             devices: HashSet::new(),
             tablets: HashMap::new(),
@@ -2532,6 +3324,7 @@ impl Niri {
             output_management_state,
             screencopy_state,
             viewporter_state,
+            content_type_state,
             xdg_foreign_state,
             text_input_state,
             input_method_state,
@@ -2570,8 +3363,25 @@ impl Niri {
             seat,
             keyboard_focus: KeyboardFocus::Layout { surface: None },
             layer_shell_on_demand_focus: None,
+            focus_follows_mouse_timer: None,
+            focus_follows_mouse_suppressed: false,
             idle_inhibiting_surfaces: HashSet::new(),
             is_fdo_idle_inhibited: Arc::new(AtomicBool::new(false)),
+            is_idle_inhibited: false,
+            last_activity: Duration::ZERO,
+            idle_dim_timer: None,
+            fullscreen_video_timer: None,
+            pending_layout_timer: None,
+            night_light_forced: None,
+            night_light_temperature: night_light::NEUTRAL_TEMPERATURE,
+            night_light_timer: None,
+            power_saving_forced: None,
+            power_saving: false,
+            on_battery: false,
+            power_saving_timer: None,
+            pending_pings: PendingPings::new(),
+            ping_timer: None,
+            backlight,
             keyboard_shortcuts_inhibiting_surfaces: HashMap::new(),
             xkb_from_locale1: None,
             reset_keymap: false,
@@ -2584,6 +3394,7 @@ impl Niri {
             pointer_inactivity_timer: None,
             pointer_inactivity_timer_got_reset: false,
             notified_activity_this_iteration: false,
+            occluded_windows: None,
             pointer_inside_hot_corner: false,
             tablet_cursor_location: None,
             gesture_swipe_3f_cumulative: None,
@@ -2598,23 +3409,35 @@ impl Niri {
             horizontal_finger_scroll_tracker: ScrollTracker::new(10),
             mods_with_finger_scroll_binds,
             binding_mode,
+            bind_conflicts,
 
             lock_state: LockState::Unlocked,
             locked_hint: None,
 
             screenshot_ui,
             config_error_notification,
+            osd,
             hotkey_overlay,
             exit_confirm_dialog,
+            overview_search: OverviewSearch::new(),
+            resize_mode: ResizeMode::new(config.clone()),
+            permission_prompt: PermissionPrompt::new(config.clone()),
+            tab_preview: TabPreview::new(config.clone()),
+            tab_preview_timer: None,
+            tab_bar_last_click: None,
+            picture_in_picture_corners: HashMap::new(),
 
             window_mru_ui,
             pending_mru_commit: None,
 
             pick_window: None,
             pick_color: None,
+            macro_playback: None,
 
             debug_draw_opaque_regions: false,
             debug_draw_damage: false,
+            debug_draw_frame_stats: false,
+            frame_stats_overlay: FrameStatsOverlay::default(),
 
             #[cfg(feature = "dbus")]
             dbus: None,
@@ -2627,8 +3450,15 @@ impl Niri {
 
             ipc_server,
             ipc_outputs_changed: false,
+            virtual_outputs: VirtualOutputs::default(),
 
             satellite: None,
+            watchdog: None,
+            workspace_import: None,
+            session_restore: None,
+            session_snapshot_writes: SnapshotWrites::default(),
+            background_permissions: BackgroundPermissions::load(),
+            accent_transition: None,
 
             #[cfg(feature = "xdp-gnome-screencast")]
             casting: screencasting,
@@ -2644,6 +3474,7 @@ impl Niri {
             client,
             restricted,
             credentials_unknown,
+            security_context,
         } = client;
 
         let config = self.config.borrow();
@@ -2653,6 +3484,7 @@ impl Niri {
             primary_selection_disabled: config.clipboard.disable_primary,
             restricted,
             credentials_unknown,
+            security_context,
         });
 
         if let Err(err) = self.display_handle.insert_client(client, data) {
@@ -2817,11 +3649,15 @@ impl Niri {
 
         let config = self.config.borrow();
         let c = config.outputs.find(name);
-        let scale = c.and_then(|c| c.scale).map(|s| s.0).unwrap_or_else(|| {
-            let size_mm = output.physical_properties().size;
-            let resolution = output.current_mode().unwrap().size;
-            guess_monitor_scale(size_mm, resolution)
-        });
+        let scale = c
+            .and_then(|c| c.scale)
+            .map(|s| s.0)
+            .or_else(|| virtual_output::requested_scale(&output))
+            .unwrap_or_else(|| {
+                let size_mm = output.physical_properties().size;
+                let resolution = output.current_mode().unwrap().size;
+                guess_monitor_scale(size_mm, resolution)
+            });
         let scale = closest_representable_scale(scale.clamp(0.1, 10.));
 
         let mut transform = panel_orientation(&output)
@@ -2839,13 +3675,10 @@ impl Niri {
             transform = Transform::Flipped180;
         }
 
-        let mut layout_config = c.and_then(|c| c.layout.clone());
-        // Support the deprecated non-layout background-color key.
-        if let Some(layout) = &mut layout_config {
-            if layout.background_color.is_none() {
-                layout.background_color = c.and_then(|c| c.background_color);
-            }
-        }
+        let layout_config = c.and_then(|c| c.layout_config());
+
+        let mut frame_clock = FrameClock::new(refresh_interval, vrr);
+        frame_clock.set_max_render_rate(c.and_then(|c| c.max_render_rate).map(|r| r.0));
         drop(config);
 
         // Set scale and transform before adding to the layout since that will read the output size.
@@ -2871,7 +3704,8 @@ impl Niri {
             redraw_state: RedrawState::Idle,
             on_demand_vrr_enabled: false,
             unfinished_animations_remain: false,
-            frame_clock: FrameClock::new(refresh_interval, vrr),
+            redraw_deferred: false,
+            frame_clock,
             last_drm_sequence: None,
             vblank_throttle: VBlankThrottle::new(self.event_loop.clone(), name.connector.clone()),
             frame_callback_sequence: 0,
@@ -2880,13 +3714,28 @@ impl Niri {
             lock_surface: None,
             lock_color_buffer: SolidColorBuffer::new(size, CLEAR_COLOR_LOCKED),
             screen_transition: None,
+            idle_dim: None,
+            powered_off: false,
+            night_light_tint: None,
+            plays_fullscreen_video: false,
+            last_video_activity: Duration::ZERO,
             debug_damage_tracker: OutputDamageTracker::from_output(&output),
+            scanout: ScanoutState::default(),
         };
         let rv = self.output_state.insert(output.clone(), state);
         assert!(rv.is_none(), "output was already tracked");
 
         // Must be last since it will call queue_redraw(output) which needs things to be filled-in.
         self.reposition_outputs(Some(&output));
+
+        self.update_idle_dim();
+
+        // Connecting resets the gamma, so the night light needs to be applied again.
+        if self.night_light_temperature != night_light::NEUTRAL_TEMPERATURE {
+            self.event_loop.insert_idle(move |state| {
+                state.apply_night_light_to(&output);
+            });
+        }
     }
 
     pub fn remove_output(&mut self, output: &Output) {
@@ -2988,6 +3837,12 @@ impl Niri {
             state.backdrop_buffer.resize(output_size);
 
             state.lock_color_buffer.resize(output_size);
+            if let Some(idle_dim) = &mut state.idle_dim {
+                idle_dim.resize(output_size);
+            }
+            if let Some((tint, _)) = &mut state.night_light_tint {
+                tint.resize(output_size);
+            }
             if let Some(lock_surface) = &state.lock_surface {
                 configure_lock_surface(lock_surface, output);
             }
@@ -3013,6 +3868,22 @@ impl Niri {
         self.queue_redraw(output);
     }
 
+    /// Changes the backlight brightness and shows the new level.
+    pub fn change_brightness(&mut self, change: BrightnessChange) {
+        let Some(backlight) = &mut self.backlight else {
+            debug!("no backlight to change the brightness of");
+            return;
+        };
+
+        match backlight.change(change, self.clock.now_unadjusted()) {
+            Ok(percent) => {
+                self.osd.show("Brightness", percent);
+                self.queue_redraw_all();
+            }
+            Err(err) => warn!("error changing brightness: {err:?}"),
+        }
+    }
+
     pub fn deactivate_monitors(&mut self, backend: &mut Backend) {
         if !self.monitors_active {
             return;
@@ -3030,9 +3901,69 @@ impl Niri {
         self.monitors_active = true;
         backend.set_monitors_active(true);
 
+        self.power_on_outputs();
         self.queue_redraw_all();
     }
 
+    /// Powers off a single output until the next input or until monitors are powered on.
+    pub fn power_off_output(&mut self, output: &Output) {
+        let Some(state) = self.output_state.get_mut(output) else {
+            return;
+        };
+        if state.powered_off {
+            return;
+        }
+
+        debug!("powering off output {}", output.name());
+        state.powered_off = true;
+
+        let output = output.clone();
+        self.event_loop.insert_idle(move |state| {
+            // Guard against the output powering on or disconnecting before the idle runs.
+            if state
+                .niri
+                .output_state
+                .get(&output)
+                .is_some_and(|s| s.powered_off)
+            {
+                state.backend.power_off_output(&output);
+            }
+        });
+    }
+
+    /// Powers on a single output that was powered off on its own.
+    ///
+    /// The backend turns the output back on with the next frame.
+    pub fn power_on_output(&mut self, output: &Output) {
+        let Some(state) = self.output_state.get_mut(output) else {
+            return;
+        };
+        if !state.powered_off {
+            return;
+        }
+
+        debug!("powering on output {}", output.name());
+        state.powered_off = false;
+        self.queue_redraw(output);
+
+        // The idle timeouts start over.
+        self.update_idle_dim();
+    }
+
+    /// Powers on all outputs that were powered off on their own.
+    pub fn power_on_outputs(&mut self) {
+        let outputs: Vec<_> = self
+            .output_state
+            .iter()
+            .filter(|(_, state)| state.powered_off)
+            .map(|(output, _)| output.clone())
+            .collect();
+
+        for output in outputs {
+            self.power_on_output(&output);
+        }
+    }
+
     pub fn output_under(&self, pos: Point<f64, Logical>) -> Option<(&Output, Point<f64, Logical>)> {
         let output = self.global_space.output_under(pos).next()?;
         let pos_within_output = pos
@@ -3253,10 +4184,35 @@ impl Niri {
             return None;
         }
 
+        if let Some((window, _)) = self.fullscreen_span_window_under(output, pos_within_output) {
+            return Some(window);
+        }
+
         let (window, _loc) = self.layout.window_under(output, pos_within_output)?;
         Some(window)
     }
 
+    /// Returns the fullscreen window spanning onto `output` from another output under the point.
+    fn fullscreen_span_window_under(
+        &self,
+        output: &Output,
+        pos_within_output: Point<f64, Logical>,
+    ) -> Option<(&Mapped, HitType)> {
+        let output_geo = self.global_space.output_geometry(output)?.to_f64();
+
+        self.layout
+            .fullscreen_span_tiles()
+            .filter(|(owner, _)| *owner != output)
+            .find_map(|(owner, tile)| {
+                let owner_geo = self.global_space.output_geometry(owner)?;
+                let mut span = tile.fullscreen_span().unwrap();
+                span.loc += owner_geo.loc.to_f64();
+
+                let tile_pos = span.loc - output_geo.loc;
+                HitType::hit_tile(tile, tile_pos, pos_within_output)
+            })
+    }
+
     /// Returns the window under the cursor to be activated.
     ///
     /// The cursor may be inside the window's activation region, but not within the window's input
@@ -3364,13 +4320,16 @@ impl Niri {
             let window = &mapped.window;
             let surface_and_pos = if let HitType::Input { win_pos } = hit {
                 let win_pos_within_output = win_pos;
+                // With force-scale, the window sees unscaled coordinates.
+                let pos_within_window =
+                    (pos_within_output - win_pos_within_output).downscale(mapped.force_scale());
                 window
-                    .surface_under(
-                        pos_within_output - win_pos_within_output,
-                        WindowSurfaceType::ALL,
-                    )
-                    .map(|(s, pos_within_window)| {
-                        (s, pos_within_window.to_f64() + win_pos_within_output)
+                    .surface_under(pos_within_window, WindowSurfaceType::ALL)
+                    .map(|(s, surface_pos)| {
+                        // Pointer events are relative to the surface location, so put the surface
+                        // where the pointer lands at its unscaled position.
+                        let pos_within_surface = pos_within_window - surface_pos.to_f64();
+                        (s, pos_within_output - pos_within_surface)
                     })
             } else {
                 None
@@ -3388,11 +4347,16 @@ impl Niri {
                 .window_under(output, pos_within_output)
                 .map(mapped_hit_data)
         };
+        let fullscreen_span_window_under = || {
+            self.fullscreen_span_window_under(output, pos_within_output)
+                .map(mapped_hit_data)
+        };
 
         let mon = self.layout.monitor_for_output(output).unwrap();
 
-        let mut under =
-            layer_popup_under(Layer::Overlay).or_else(|| layer_toplevel_under(Layer::Overlay));
+        let mut under = layer_popup_under(Layer::Overlay)
+            .or_else(|| layer_toplevel_under(Layer::Overlay))
+            .or_else(fullscreen_span_window_under);
 
         let is_overview_open = self.layout.is_overview_open();
 
@@ -3539,6 +4503,19 @@ impl Niri {
             .cloned()
     }
 
+    pub fn output_in_direction_of(
+        &self,
+        current: &Output,
+        direction: niri_ipc::OutputDirection,
+    ) -> Option<Output> {
+        match direction {
+            niri_ipc::OutputDirection::Left => self.output_left_of(current),
+            niri_ipc::OutputDirection::Right => self.output_right_of(current),
+            niri_ipc::OutputDirection::Up => self.output_up_of(current),
+            niri_ipc::OutputDirection::Down => self.output_down_of(current),
+        }
+    }
+
     pub fn output_left(&self) -> Option<Output> {
         let active = self.layout.active_output()?;
         self.output_left_of(active)
@@ -3582,12 +4559,54 @@ impl Niri {
                 let id = WorkspaceId::specific(id);
                 self.layout.find_workspace_by_id(id)?
             }
+            reference @ (WorkspaceReference::Relative(_)
+            | WorkspaceReference::NextNonEmpty
+            | WorkspaceReference::PrevNonEmpty) => {
+                let index = self.layout.resolve_relative_workspace_ref(&reference)?;
+                return Some((None, index));
+            }
         };
 
         let target_output = target_workspace.current_output();
         Some((target_output.cloned(), target_workspace_index))
     }
 
+    /// Returns the corner for a picture-in-picture window.
+    ///
+    /// This is the corner where the last picture-in-picture window of the same app was left, or
+    /// the configured one.
+    pub fn picture_in_picture_corner(&self, window: &Window) -> Corner {
+        let app_id = window
+            .toplevel()
+            .and_then(|toplevel| with_toplevel_role(toplevel, |role| role.app_id.clone()));
+        app_id
+            .and_then(|app_id| self.picture_in_picture_corners.get(&app_id).copied())
+            .unwrap_or_else(|| self.config.borrow().picture_in_picture.corner)
+    }
+
+    /// Remembers the corner of a picture-in-picture window for the next one of the same app.
+    pub fn remember_picture_in_picture_corner(&mut self, window: &Window) {
+        let Some(corner) = self.layout.picture_in_picture_corner(window) else {
+            return;
+        };
+        let app_id = window
+            .toplevel()
+            .and_then(|toplevel| with_toplevel_role(toplevel, |role| role.app_id.clone()));
+        if let Some(app_id) = app_id {
+            self.picture_in_picture_corners.insert(app_id, corner);
+        }
+    }
+
+    pub fn toggle_window_picture_in_picture(&mut self, window: &Window) {
+        if self.layout.is_picture_in_picture(window) {
+            self.remember_picture_in_picture_corner(window);
+            self.layout.unset_window_picture_in_picture(window);
+        } else {
+            let corner = self.picture_in_picture_corner(window);
+            self.layout.set_window_picture_in_picture(window, corner);
+        }
+    }
+
     pub fn find_window_by_id(&self, id: MappedId) -> Option<Window> {
         self.layout
             .windows()
@@ -3643,6 +4662,100 @@ impl Niri {
         state.lock_surface.as_ref().map(|s| s.wl_surface()).cloned()
     }
 
+    /// Answers the shown background portal prompt and saves the decision.
+    pub fn answer_permission_prompt(&mut self, allowed: bool) {
+        if let Some((app_id, kind)) = self.permission_prompt.answer(allowed) {
+            self.background_permissions.set(&app_id, kind, allowed);
+        }
+        self.queue_redraw_all();
+    }
+
+    /// Closes the shown background portal prompt without saving a decision.
+    pub fn dismiss_permission_prompt(&mut self) {
+        self.permission_prompt.answer(false);
+        self.queue_redraw_all();
+    }
+
+    /// Fades the focus ring, borders and tabs to a new accent color.
+    ///
+    /// The change lasts until the next config reload.
+    pub fn set_accent_color(&mut self, color: Color) {
+        if self.accent_color() == Some(color) {
+            return;
+        }
+
+        let from = match &self.accent_transition {
+            Some(transition) => transition.current(),
+            None => {
+                let accent_color = self.config.borrow().accent_color;
+                accent_color.unwrap_or_else(|| niri_config::FocusRing::default().active_color)
+            }
+        };
+        self.accent_transition = Some(AccentTransition::new(self.clock.clone(), from, color));
+
+        #[cfg(feature = "dbus")]
+        self.on_accent_color_changed();
+
+        self.queue_redraw_all();
+    }
+
+    /// Returns the variables describing the focus for commands spawned from binds.
+    pub fn spawn_env(&self) -> Vec<(String, String)> {
+        let Some(mon) = self.layout.active_monitor_ref() else {
+            return Vec::new();
+        };
+
+        let output = Some(mon.output_name().clone());
+        workspace_env(
+            output,
+            mon.active_workspace_idx(),
+            mon.active_workspace_ref(),
+        )
+    }
+
+    /// Returns the variables describing the named workspace for commands spawned onto it.
+    ///
+    /// Focusing the workspace can fail, so this doesn't rely on the focus.
+    pub fn spawn_env_on_workspace(&self, name: &str) -> Vec<(String, String)> {
+        let Some((idx, ws)) = self.layout.find_workspace_by_name(name) else {
+            return self.spawn_env();
+        };
+
+        let output = ws.current_output().map(|output| output.name());
+        workspace_env(output, idx, ws)
+    }
+
+    /// Returns the accent color, or the one it's fading to.
+    pub fn accent_color(&self) -> Option<Color> {
+        match &self.accent_transition {
+            Some(transition) => Some(transition.to()),
+            None => self.config.borrow().accent_color,
+        }
+    }
+
+    /// Forgets the pending ping of a client that answered it.
+    pub fn on_client_pong(&mut self, client: &ShellClient) {
+        if self.pending_pings.answer(|pending| pending == client) {
+            self.refresh_unresponsive_windows();
+        }
+    }
+
+    /// Shows or hides the prompt on windows following the pending pings.
+    pub fn refresh_unresponsive_windows(&mut self) {
+        let unresponsive: HashSet<_> = self.pending_pings.unresponsive().cloned().collect();
+
+        let mut changed = false;
+        self.layout.with_windows_mut(|mapped, _| {
+            let client = mapped.toplevel().wl_surface().client();
+            let is_unresponsive = client.is_some_and(|client| unresponsive.contains(&client.id()));
+            changed |= mapped.set_unresponsive(is_unresponsive);
+        });
+
+        if changed {
+            self.queue_redraw_all();
+        }
+    }
+
     /// Schedules an immediate redraw on all outputs if one is not already scheduled.
     pub fn queue_redraw_all(&mut self) {
         for state in self.output_state.values_mut() {
@@ -3654,6 +4767,40 @@ impl Niri {
     pub fn queue_redraw(&mut self, output: &Output) {
         let state = self.output_state.get_mut(output).unwrap();
         state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+
+        // Fullscreen windows spanning from this output are drawn on other outputs too.
+        for other in self.outputs_spanned_from(output) {
+            let state = self.output_state.get_mut(&other).unwrap();
+            state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+        }
+    }
+
+    /// Returns other outputs covered by fullscreen windows spanning from `output`.
+    fn outputs_spanned_from(&self, output: &Output) -> Vec<Output> {
+        let Some((_, tile)) = self
+            .layout
+            .fullscreen_span_tiles()
+            .find(|(owner, _)| *owner == output)
+        else {
+            return Vec::new();
+        };
+        let Some(own_geo) = self.global_space.output_geometry(output) else {
+            return Vec::new();
+        };
+
+        let mut span = tile.fullscreen_span().unwrap();
+        span.loc += own_geo.loc.to_f64();
+
+        self.global_space
+            .outputs()
+            .filter(|o| *o != output)
+            .filter(|o| {
+                self.global_space
+                    .output_geometry(o)
+                    .is_some_and(|geo| geo.to_f64().overlaps(span))
+            })
+            .cloned()
+            .collect()
     }
 
     pub fn redraw_queued_outputs(&mut self, backend: &mut Backend) {
@@ -3688,7 +4835,7 @@ impl Niri {
         let pointer_pos = pointer_pos - output_pos.to_f64();
 
         // Get the render cursor to draw.
-        let cursor_scale = output_scale.integer_scale();
+        let cursor_scale = output_scale.fractional_scale();
         let render_cursor = self.cursor_manager.get_render_cursor(cursor_scale);
 
         let output_scale = Scale::from(output.current_scale().fractional_scale());
@@ -3711,22 +4858,22 @@ impl Niri {
             }
             RenderCursor::Named {
                 icon,
+                size,
                 scale,
                 cursor,
             } => {
                 let (idx, frame) = cursor.frame(self.start_time.elapsed().as_millis() as u32);
-                let hotspot = XCursor::hotspot(frame).to_logical(scale);
-                let pointer_pos =
-                    (pointer_pos - hotspot.to_f64()).to_physical_precise_round(output_scale);
+                let hotspot = XCursor::hotspot(frame).to_f64().to_logical(scale);
+                let pointer_pos = (pointer_pos - hotspot).to_physical_precise_round(output_scale);
 
-                let texture = self.cursor_texture_cache.get(icon, scale, &cursor, idx);
+                let texture = self.cursor_texture_cache.get(icon, size, &cursor, idx);
                 match MemoryRenderBufferRenderElement::from_buffer(
                     renderer,
                     pointer_pos,
                     &texture,
                     None,
                     None,
-                    None,
+                    Some(XCursor::logical_size(frame, scale)),
                     Kind::Cursor,
                 ) {
                     Ok(element) => push(element.into()),
@@ -3921,7 +5068,7 @@ impl Niri {
 
                     // The default cursor is rendered at the right scale for each output, which
                     // means that it may have a different hotspot for each output.
-                    let output_scale = output.current_scale().integer_scale();
+                    let output_scale = output.current_scale().fractional_scale();
                     let cursor = self
                         .cursor_manager
                         .get_cursor_with_name(icon, output_scale)
@@ -3929,7 +5076,10 @@ impl Niri {
 
                     // For simplicity, we always use frame 0 for this computation. Let's hope the
                     // hotspot doesn't change between frames.
-                    let hotspot = XCursor::hotspot(&cursor.frames()[0]).to_logical(output_scale);
+                    let hotspot = XCursor::hotspot(&cursor.frames()[0])
+                        .to_f64()
+                        .to_logical(output_scale)
+                        .to_i32_round();
 
                     let surface_pos = pointer_pos.to_i32_round() - hotspot;
                     let bbox = bbox_from_surface_tree(surface, surface_pos);
@@ -3976,6 +5126,96 @@ impl Niri {
         };
 
         self.layout.refresh(layout_is_active);
+
+        // A window that never acks its new size doesn't commit either, so nothing else would
+        // refresh the layout when the pending layout times out.
+        if let Some(token) = self.pending_layout_timer.take() {
+            self.event_loop.remove(token);
+        }
+        if let Some(deadline) = self.layout.pending_layout_deadline() {
+            let timeout = deadline.saturating_sub(self.clock.now_unadjusted());
+            let token = self
+                .event_loop
+                .insert_source(Timer::from_duration(timeout), |_, _, state| {
+                    state.niri.pending_layout_timer = None;
+                    state.niri.queue_redraw_all();
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+            self.pending_layout_timer = Some(token);
+        }
+
+        // The search query only lives while the overview is open.
+        if !self.layout.is_overview_open() && self.overview_search.clear() {
+            self.queue_redraw_all();
+        }
+
+        // Locking the screen leaves the resize mode.
+        if self.is_locked() && self.resize_mode.stop() {
+            self.queue_redraw_all();
+        }
+    }
+
+    pub fn refresh_tab_preview(&mut self) {
+        let (enabled, delay_ms) = {
+            let config = self.config.borrow();
+            let tab_bar = &config.layout.tab_bar;
+            let enabled = tab_bar.hover_preview && !tab_bar.off;
+            (enabled, tab_bar.hover_preview_delay_ms)
+        };
+
+        let mut hovered = None;
+        if enabled && !self.layout.is_overview_open() {
+            if let (Some(output), Some((window, hit))) =
+                (&self.pointer_contents.output, &self.pointer_contents.window)
+            {
+                if matches!(
+                    hit,
+                    HitType::Activate {
+                        is_tab_indicator: true
+                    }
+                ) {
+                    let mapped = self.layout.windows().find(|(_, m)| m.window == *window);
+                    let output_geo = self.global_space.output_geometry(output);
+                    if let (Some((_, mapped)), Some(output_geo)) = (mapped, output_geo) {
+                        let pos = self.seat.get_pointer().unwrap().current_location()
+                            - output_geo.loc.to_f64();
+                        hovered = Some((mapped.id(), output.clone(), pos));
+                    }
+                }
+            }
+        }
+
+        let is_hovering = hovered.is_some();
+        let was_shown = self.tab_preview.is_shown();
+        if !self.tab_preview.set_hovered(hovered) {
+            return;
+        }
+
+        if let Some(token) = self.tab_preview_timer.take() {
+            self.event_loop.remove(token);
+        }
+        if was_shown {
+            self.queue_redraw_all();
+        }
+        if !is_hovering {
+            return;
+        }
+
+        let timer = Timer::from_duration(Duration::from_millis(u64::from(delay_ms)));
+        let token = self
+            .event_loop
+            .insert_source(timer, move |_, _, state| {
+                state.niri.tab_preview_timer = None;
+
+                if state.niri.tab_preview.show() {
+                    state.niri.queue_redraw_all();
+                }
+
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.tab_preview_timer = Some(token);
     }
 
     pub fn refresh_idle_inhibit(&mut self) {
@@ -3989,7 +5229,177 @@ impl Niri {
                     surface_primary_scanout_output(surface, states).is_some()
                 })
             });
-        self.idle_notifier_state.set_is_inhibited(is_inhibited);
+
+        // Fullscreen video inhibits idle for idle clients like an inhibitor would. It only keeps
+        // its own output from dimming though, which update_idle_dim() handles per output.
+        let plays_video = self
+            .output_state
+            .values()
+            .any(|state| state.plays_fullscreen_video);
+        self.idle_notifier_state
+            .set_is_inhibited(is_inhibited || plays_video);
+
+        if self.is_idle_inhibited != is_inhibited {
+            self.is_idle_inhibited = is_inhibited;
+            self.update_idle_dim();
+        }
+    }
+
+    /// Updates which outputs show a fullscreen window that is playing video.
+    ///
+    /// Such outputs get on-demand VRR, inhibit idle and don't dim for inactivity.
+    pub fn refresh_fullscreen_video(&mut self) {
+        let _span = tracy_client::span!("Niri::refresh_fullscreen_video");
+
+        if let Some(token) = self.fullscreen_video_timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let enabled = !self.config.borrow().fullscreen_video.off;
+        let now = self.clock.now_unadjusted();
+        // Commits are timestamped with the monotonic time.
+        let commit_now = get_monotonic_time();
+        let mut changed = Vec::new();
+        for (output, state) in &mut self.output_state {
+            let plays_video = enabled
+                && self
+                    .layout
+                    .monitor_for_output(output)
+                    .and_then(|mon| mon.active_workspace_ref().fullscreen_tile())
+                    .is_some_and(|tile| tile.window().is_playing_video(commit_now));
+
+            if plays_video {
+                state.last_video_activity = now;
+                if state.idle_dim.take().is_some() {
+                    changed.push(output.clone());
+                }
+            }
+
+            if state.plays_fullscreen_video != plays_video {
+                debug!(
+                    "fullscreen video {} on {}",
+                    if plays_video { "started" } else { "stopped" },
+                    output.name()
+                );
+                state.plays_fullscreen_video = plays_video;
+                changed.push(output.clone());
+            }
+        }
+
+        // A paused video stops committing, so nothing else would notice that playback stopped.
+        if self
+            .output_state
+            .values()
+            .any(|state| state.plays_fullscreen_video)
+        {
+            let token = self
+                .event_loop
+                .insert_source(
+                    Timer::from_duration(video_cadence::STOPPED_AFTER),
+                    |_, _, state| {
+                        state.niri.fullscreen_video_timer = None;
+                        state.niri.refresh_fullscreen_video();
+                        TimeoutAction::Drop
+                    },
+                )
+                .unwrap();
+            self.fullscreen_video_timer = Some(token);
+        }
+
+        if changed.is_empty() {
+            return;
+        }
+
+        // Redraw to refresh on-demand VRR, and reschedule dimming now that the activity changed.
+        for output in changed {
+            self.queue_redraw(&output);
+        }
+        self.refresh_idle_inhibit();
+        self.update_idle_dim();
+    }
+
+    /// Dims and powers off outputs whose idle timeouts have passed and schedules the next check.
+    pub fn update_idle_dim(&mut self) {
+        let _span = tracy_client::span!("Niri::update_idle_dim");
+
+        if let Some(token) = self.idle_dim_timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let now = self.clock.now_unadjusted();
+        if self.is_idle_inhibited {
+            // Inhibition counts as activity, so the timeout starts over once it ends.
+            self.last_activity = now;
+        }
+
+        let config = self.config.borrow();
+        let mut next_check: Option<Duration> = None;
+        let mut changed = Vec::new();
+        let mut to_power_off = Vec::new();
+        for (output, state) in &mut self.output_state {
+            let name = output.user_data().get::<OutputName>().unwrap();
+            let output_config = config.outputs.find(name);
+            let dim_config = output_config.and_then(|c| c.idle_dim);
+            let power_off_after_ms = output_config.and_then(|c| c.power_off_after_ms);
+
+            // Dimming was removed from the config.
+            if dim_config.is_none() && state.idle_dim.take().is_some() {
+                changed.push(output.clone());
+            }
+
+            if state.plays_fullscreen_video || state.powered_off {
+                continue;
+            }
+
+            let last_activity = self.last_activity.max(state.last_video_activity);
+            let idle_for = now.saturating_sub(last_activity);
+            // Checks whether the timeout passed, otherwise schedules a check for when it does.
+            let mut timeout_passed = |timeout_ms: u32| {
+                let timeout = Duration::from_millis(u64::from(timeout_ms));
+                match timeout.checked_sub(idle_for).filter(|d| !d.is_zero()) {
+                    Some(remaining) => {
+                        next_check = Some(next_check.map_or(remaining, |d| d.min(remaining)));
+                        false
+                    }
+                    None => true,
+                }
+            };
+
+            if let Some(dim_config) = dim_config {
+                if state.idle_dim.is_none() && timeout_passed(dim_config.timeout_ms) {
+                    let size = output_size(output);
+                    state.idle_dim = Some(IdleDim::new(size, dim_config, self.clock.clone()));
+                    changed.push(output.clone());
+                }
+            }
+
+            if let Some(timeout_ms) = power_off_after_ms {
+                if timeout_passed(timeout_ms) {
+                    to_power_off.push(output.clone());
+                }
+            }
+        }
+        drop(config);
+
+        for output in changed {
+            self.queue_redraw(&output);
+        }
+
+        for output in to_power_off {
+            self.power_off_output(&output);
+        }
+
+        if let Some(delay) = next_check {
+            let token = self
+                .event_loop
+                .insert_source(Timer::from_duration(delay), |_, _, state| {
+                    state.niri.idle_dim_timer = None;
+                    state.niri.update_idle_dim();
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+            self.idle_dim_timer = Some(token);
+        }
     }
 
     pub fn refresh_window_states(&mut self) {
@@ -4012,7 +5422,8 @@ impl Niri {
         let mut outputs = HashSet::new();
         self.layout.with_windows_mut(|mapped, output| {
             if mapped.recompute_window_rules_if_needed(window_rules, self.is_at_startup) {
-                windows.push(mapped.window.clone());
+                let floating = mapped.take_floating_change_from_rules();
+                windows.push((mapped.window.clone(), floating));
 
                 if let Some(output) = output {
                     outputs.insert(output.clone());
@@ -4026,8 +5437,11 @@ impl Niri {
         });
         drop(config);
 
-        for win in windows {
+        for (win, floating) in windows {
             self.layout.update_window(&win, None);
+            if let Some(floating) = floating {
+                self.layout.set_window_floating(Some(&win), floating);
+            }
             win.toplevel()
                 .expect("no X11 support")
                 .send_pending_configure();
@@ -4037,15 +5451,126 @@ impl Niri {
         }
     }
 
+    /// Toggles fullscreen spanning multiple outputs for the window.
+    ///
+    /// A window already spanning outputs goes out of fullscreen.
+    pub fn toggle_window_fullscreen_span(&mut self, window: &Window) {
+        let mut is_spanning = None;
+        self.layout.with_windows_mut(|mapped, _| {
+            if &mapped.window == window {
+                let spanning = mapped.pending_sizing_mode().is_fullscreen()
+                    && mapped.fullscreen_span_outputs().is_some();
+                mapped.set_fullscreen_span_requested(!spanning);
+                is_spanning = Some(spanning);
+            }
+        });
+
+        let Some(is_spanning) = is_spanning else {
+            return;
+        };
+
+        self.layout.set_fullscreen(window, !is_spanning);
+        // FIXME: granular
+        self.queue_redraw_all();
+    }
+
+    pub fn refresh_fullscreen_spans(&mut self) {
+        let _span = tracy_client::span!("Niri::refresh_fullscreen_spans");
+
+        let mut spans = Vec::new();
+        for (mon, mapped) in self.layout.windows() {
+            let span = mon.and_then(|mon| {
+                if !mapped.pending_sizing_mode().is_fullscreen() {
+                    return None;
+                }
+
+                let names = mapped.fullscreen_span_outputs()?;
+                self.fullscreen_span_for_output(mon.output(), names)
+            });
+            spans.push((mapped.window.clone(), span));
+        }
+
+        let mut changed = false;
+        for (window, span) in spans {
+            changed |= self.layout.set_fullscreen_span(&window, span);
+        }
+
+        // Spans cover several outputs, so any change needs a redraw on all of them.
+        if changed {
+            self.queue_redraw_all();
+        }
+
+        // Drop toggled spans from windows that left fullscreen.
+        self.layout.with_windows_mut(|mapped, _| {
+            if !mapped.pending_sizing_mode().is_fullscreen() {
+                mapped.set_fullscreen_span_requested(false);
+            }
+        });
+    }
+
+    /// Computes the area a fullscreen window on `output` should cover, relative to `output`.
+    ///
+    /// The area is the bounding box of the outputs matching `names` (or all outputs if empty)
+    /// that are connected to `output` through edge-adjacent outputs. Returns `None` if the area
+    /// is just `output` itself.
+    fn fullscreen_span_for_output(
+        &self,
+        output: &Output,
+        names: &[String],
+    ) -> Option<Rectangle<f64, Logical>> {
+        let own_geo = self.global_space.output_geometry(output)?;
+
+        let mut candidates: Vec<_> = self
+            .global_space
+            .outputs()
+            .filter(|o| *o != output)
+            .filter(|o| names.is_empty() || names.iter().any(|n| output_matches_name(o, n)))
+            .filter_map(|o| self.global_space.output_geometry(o))
+            .collect();
+
+        let mut span = own_geo;
+        let mut included = vec![own_geo];
+        while let Some(idx) = candidates
+            .iter()
+            .position(|geo| included.iter().any(|inc| inc.overlaps_or_touches(*geo)))
+        {
+            let geo = candidates.swap_remove(idx);
+            span = span.merge(geo);
+            included.push(geo);
+        }
+
+        if span == own_geo {
+            return None;
+        }
+
+        let mut span = span.to_f64();
+        span.loc -= own_geo.loc.to_f64();
+        Some(span)
+    }
+
     pub fn advance_animations(&mut self) {
         let _span = tracy_client::span!("Niri::advance_animations");
 
         self.layout.advance_animations();
         self.config_error_notification.advance_animations();
+        self.osd.advance_animations();
         self.exit_confirm_dialog.advance_animations();
         self.screenshot_ui.advance_animations();
         self.window_mru_ui.advance_animations();
 
+        if let Some(transition) = &mut self.accent_transition {
+            // Rebuilding the layout options is expensive, so only do it when the color moved.
+            if let Some(color) = transition.advance() {
+                let mut config = self.config.borrow_mut();
+                config.accent_color = Some(color);
+                self.layout.update_config(&config);
+            }
+
+            if transition.is_done() {
+                self.accent_transition = None;
+            }
+        }
+
         for state in self.output_state.values_mut() {
             if let Some(transition) = &mut state.screen_transition {
                 if transition.is_done() {
@@ -4131,11 +5656,31 @@ impl Niri {
 
         let output_scale = Scale::from(output.current_scale().fractional_scale());
 
-        // The pointer goes on the top.
+        // The night light tint stands in for the gamma ramp, so it covers everything on the
+        // monitor including the pointer.
+        if target == RenderTarget::Output {
+            let state = self.output_state.get(output).unwrap();
+            if let Some((tint, alpha)) = &state.night_light_tint {
+                push(
+                    SolidColorRenderElement::from_buffer(tint, (0., 0.), *alpha, Kind::Unspecified)
+                        .into(),
+                );
+            }
+        }
+
+        // Next, the pointer.
         if include_pointer && self.pointer_visibility.is_visible() {
             self.render_pointer(renderer, output, &mut |elem| push(elem.into()));
         }
 
+        // Next, the idle dimming, which only applies to what's shown on the monitor.
+        if target == RenderTarget::Output {
+            let state = self.output_state.get(output).unwrap();
+            if let Some(idle_dim) = &state.idle_dim {
+                push(idle_dim.render().into());
+            }
+        }
+
         // Next, the screen transition texture.
         {
             let state = self.output_state.get(output).unwrap();
@@ -4153,6 +5698,13 @@ impl Niri {
             push(element.into());
         }
 
+        // Next, the OSD, on the active output only.
+        if self.layout.active_output() == Some(output) {
+            if let Some(element) = self.osd.render(renderer, output) {
+                push(element.into());
+            }
+        }
+
         // If the session is locked, draw the lock surface.
         if self.is_locked() {
             let state = self.output_state.get(output).unwrap();
@@ -4200,11 +5752,37 @@ impl Niri {
             // Add the backdrop for outputs that were connected while the screenshot UI was open.
             push(backdrop);
 
-            return;
+            return;
+        }
+
+        // Draw the hotkey overlay on top.
+        if let Some(element) = self.hotkey_overlay.render(renderer, output) {
+            push(element.into());
+        }
+
+        // Then, the background portal prompt.
+        if let Some(element) = self.permission_prompt.render(renderer, output) {
+            push(element.into());
+        }
+
+        // Then, the resize mode readout.
+        if let Some(element) = self.resize_mode.render(renderer, &self.layout, output) {
+            push(element.into());
+        }
+
+        // Then, the frame stats debug overlay.
+        if self.debug_draw_frame_stats && target == RenderTarget::Output {
+            self.frame_stats_overlay.render(
+                renderer,
+                &self.layout,
+                output,
+                get_monotonic_time(),
+                &mut |elem| push(elem.into()),
+            );
         }
 
-        // Draw the hotkey overlay on top.
-        if let Some(element) = self.hotkey_overlay.render(renderer, output) {
+        // Then, the overview search query.
+        if let Some(element) = self.overview_search.render_query(renderer, output) {
             push(element.into());
         }
 
@@ -4214,6 +5792,12 @@ impl Niri {
                 push(elem.into())
             });
 
+        // Then, the tab hover preview.
+        self.tab_preview
+            .render(&self.layout, renderer, output, target, &mut |elem| {
+                push(elem.into())
+            });
+
         // Don't draw the focus ring on the workspaces while interactively moving above those
         // workspaces, since the interactively-moved window already has a focus ring.
         let focus_ring = !self.layout.interactive_move_is_moving_above_output(output);
@@ -4260,6 +5844,11 @@ impl Niri {
         push_popups_from_layer!(Layer::Overlay);
         push_normal_from_layer!(Layer::Overlay);
 
+        // Fullscreen windows spanning from neighboring outputs go above everything else here.
+        self.render_fullscreen_span_slices(renderer, output, target, &mut |elem| {
+            push(elem.into())
+        });
+
         // When rendering above the top layer, we put the regular monitor elements first.
         // Otherwise, we will render all layer-shell pop-ups and the top layer on top.
         if mon.render_above_top_layer() {
@@ -4282,7 +5871,9 @@ impl Niri {
 
             // We don't expect more than one workspace when render_above_top_layer().
             if let Some((ws, _geo)) = mon.workspaces_with_render_geo().next() {
-                push(ws.render_background().into());
+                ws.render_background(renderer, &self.background_images, &mut |elem| {
+                    push(elem.into())
+                });
             }
         } else {
             push_popups_from_layer!(Layer::Top);
@@ -4311,19 +5902,27 @@ impl Niri {
                 push_popups_from_layer!(Layer::Background, process!(geo));
             }
 
+            // Dim windows not matching the overview search on top of the workspaces.
+            self.overview_search
+                .render_dimming(&self.layout, output, &mut |elem| push(elem.into()));
+
             mon.render_workspaces(renderer, target, focus_ring, &mut |elem| push(elem.into()));
 
             for (ws, geo) in mon.workspaces_with_render_geo() {
                 push_normal_from_layer!(Layer::Bottom, process!(geo));
                 push_normal_from_layer!(Layer::Background, process!(geo));
 
-                process!(geo)(ws.render_background());
+                ws.render_background(renderer, &self.background_images, process!(geo));
             }
         }
 
+        mon.render_workspace_labels(renderer, &mut |elem| push(elem.into()));
         mon.render_workspace_shadows(renderer, &mut |elem| push(elem.into()));
 
-        // Then the backdrop.
+        // Then the backdrop, dimmed in the overview.
+        if let Some(elem) = mon.render_backdrop_dim() {
+            push(elem.into());
+        }
         push_popups_from_layer!(Layer::Background, true);
         push_normal_from_layer!(Layer::Background, true);
 
@@ -4349,6 +5948,46 @@ impl Niri {
         })
     }
 
+    /// Renders the parts of fullscreen windows from other outputs that span onto `output`.
+    fn render_fullscreen_span_slices<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        target: RenderTarget,
+        push: &mut dyn FnMut(RescaleRenderElement<TileRenderElement<R>>),
+    ) {
+        let Some(output_geo) = self.global_space.output_geometry(output) else {
+            return;
+        };
+        let output_geo = output_geo.to_f64();
+        let scale = Scale::from(output.current_scale().fractional_scale());
+
+        for (owner, tile) in self.layout.fullscreen_span_tiles() {
+            if owner == output {
+                continue;
+            }
+
+            let Some(owner_geo) = self.global_space.output_geometry(owner) else {
+                continue;
+            };
+
+            let mut span = tile.fullscreen_span().unwrap();
+            span.loc += owner_geo.loc.to_f64();
+            if !span.overlaps(output_geo) {
+                continue;
+            }
+
+            let location = span.loc - output_geo.loc;
+            tile.render(renderer, location, false, false, target, &mut |elem| {
+                push(RescaleRenderElement::from_element(
+                    elem,
+                    location.to_physical_precise_round(scale),
+                    1.,
+                ));
+            });
+        }
+    }
+
     fn render_layer_normal<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -4395,20 +6034,28 @@ impl Niri {
         self.update_render_elements(Some(output));
 
         let mut res = RenderResult::Skipped;
-        if self.monitors_active {
+        let is_powered_off = self.output_state[output].powered_off;
+        if self.monitors_active && !is_powered_off {
             let state = self.output_state.get_mut(output).unwrap();
             state.unfinished_animations_remain = self.layout.are_animations_ongoing(Some(output));
             state.unfinished_animations_remain |=
                 self.config_error_notification.are_animations_ongoing();
+            state.unfinished_animations_remain |= self.osd.are_animations_ongoing();
             state.unfinished_animations_remain |= self.exit_confirm_dialog.are_animations_ongoing();
             state.unfinished_animations_remain |= self.screenshot_ui.are_animations_ongoing();
             state.unfinished_animations_remain |= self.window_mru_ui.are_animations_ongoing();
             state.unfinished_animations_remain |= state.screen_transition.is_some();
+            state.unfinished_animations_remain |= self.accent_transition.is_some();
+            state.unfinished_animations_remain |=
+                state.idle_dim.as_ref().is_some_and(|d| d.is_animating());
+
+            // The frame stats overlay shows the time since the last commit, so keep it updating.
+            state.unfinished_animations_remain |= self.debug_draw_frame_stats;
 
             // Also keep redrawing if the current cursor is animated.
             state.unfinished_animations_remain |= self
                 .cursor_manager
-                .is_current_cursor_animated(output.current_scale().integer_scale());
+                .is_current_cursor_animated(output.current_scale().fractional_scale());
 
             // Also check layer surfaces.
             if !state.unfinished_animations_remain {
@@ -4440,7 +6087,7 @@ impl Niri {
         // Update the lock render state on successful render, or if monitors are inactive. When
         // monitors are inactive on a TTY, they have no framebuffer attached, so no sensitive data
         // from a last render will be visible.
-        if res != RenderResult::Skipped || !self.monitors_active {
+        if res != RenderResult::Skipped || !self.monitors_active || is_powered_off {
             state.lock_render_state = if is_locked {
                 LockRenderState::Locked
             } else {
@@ -4534,9 +6181,112 @@ impl Niri {
             }
         });
 
+        // Fullscreen video gets VRR without needing a window rule.
+        let plays_video = self
+            .output_state
+            .get(output)
+            .is_some_and(|state| state.plays_fullscreen_video);
+        let current = current || plays_video;
+
         backend.set_output_on_demand_vrr(self, output, current);
     }
 
+    /// Returns the window whose buffer can go directly on the primary plane of the output.
+    ///
+    /// That's the fullscreen window of the active workspace, as long as nothing is drawn on top of
+    /// it and nothing is animating.
+    pub fn direct_scanout_candidate(
+        &self,
+        output: &Output,
+    ) -> Result<&Mapped, niri_ipc::ScanoutBlocker> {
+        use niri_ipc::ScanoutBlocker;
+
+        if self.config.borrow().debug.disable_direct_scanout {
+            return Err(ScanoutBlocker::Disabled);
+        }
+
+        let mon = self
+            .layout
+            .monitor_for_output(output)
+            .ok_or(ScanoutBlocker::NoFullscreenWindow)?;
+        let tile = mon
+            .active_workspace_ref()
+            .fullscreen_tile()
+            .ok_or(ScanoutBlocker::NoFullscreenWindow)?;
+
+        if self.is_locked()
+            || self.screenshot_ui.is_open()
+            || self.exit_confirm_dialog.is_open()
+            || self.hotkey_overlay.is_open()
+            || self.window_mru_ui.is_open()
+            || self.overview_search.is_active()
+            || self.resize_mode.is_active()
+            || self.permission_prompt.is_open()
+            || self.tab_preview.is_shown()
+            || self.layout.is_overview_open()
+        {
+            return Err(ScanoutBlocker::Overlay);
+        }
+
+        let layer_map = layer_map_for_output(output);
+        if layer_map.layers_on(Layer::Overlay).next().is_some() {
+            return Err(ScanoutBlocker::Overlay);
+        }
+        // A stationary fullscreen window is drawn above the top layer, hiding it.
+        if !mon.render_above_top_layer() && layer_map.layers_on(Layer::Top).next().is_some() {
+            return Err(ScanoutBlocker::Overlay);
+        }
+
+        let state = self.output_state.get(output).unwrap();
+        if state.screen_transition.is_some()
+            || self.layout.are_animations_ongoing(Some(output))
+            || self.config_error_notification.are_animations_ongoing()
+        {
+            return Err(ScanoutBlocker::Animation);
+        }
+
+        Ok(tile.window())
+    }
+
+    /// Prepares the output elements for scanning out the fullscreen window buffer directly.
+    ///
+    /// Drops the elements hidden behind the window, so that the DRM compositor can put its buffer
+    /// on the primary plane. Elements on top, like the cursor, stay; when they get damaged in a
+    /// way that can't go on a separate plane, the DRM compositor falls back to composition.
+    pub fn prepare_direct_scanout<R: NiriRenderer>(
+        &self,
+        output: &Output,
+        elements: &mut Vec<OutputRenderElements<R>>,
+    ) -> Result<MappedId, niri_ipc::ScanoutBlocker> {
+        use niri_ipc::ScanoutBlocker;
+
+        let mapped = self.direct_scanout_candidate(output)?;
+
+        let scale = Scale::from(output.current_scale().fractional_scale());
+        let output_geo = Rectangle::from_size(output_size(output)).to_physical_precise_round(scale);
+
+        // The window may be drawn from a snapshot or an offscreen texture, in which case its
+        // surface element won't be there.
+        let id = Id::from_wayland_resource(mapped.toplevel().wl_surface());
+        let idx = elements
+            .iter()
+            .position(|elem| *elem.id() == id)
+            .ok_or(ScanoutBlocker::NotOpaque)?;
+
+        let elem = &elements[idx];
+        let geo = elem.geometry(scale);
+        let covers_output = elem
+            .opaque_regions(scale)
+            .iter()
+            .any(|rect| Rectangle::new(rect.loc + geo.loc, rect.size).contains_rect(output_geo));
+        if !covers_output {
+            return Err(ScanoutBlocker::NotOpaque);
+        }
+
+        elements.truncate(idx + 1);
+        Ok(mapped.id())
+    }
+
     pub fn update_primary_scanout_output(
         &self,
         output: &Output,
@@ -4766,16 +6516,8 @@ impl Niri {
         let state = self.output_state.get(output).unwrap();
         let sequence = state.frame_callback_sequence;
 
-        let should_send = |surface: &WlSurface, states: &SurfaceData| {
-            // Do the standard primary scanout output check. For pointer surfaces it deduplicates
-            // the frame callbacks across potentially multiple outputs, and for regular windows and
-            // layer-shell surfaces it avoids sending frame callbacks to invisible surfaces.
-            let current_primary_output = surface_primary_scanout_output(surface, states);
-            if current_primary_output.as_ref() != Some(output) {
-                return None;
-            }
-
-            // Next, check the throttling status.
+        let should_send_unchecked = |_surface: &WlSurface, states: &SurfaceData| {
+            // Check the throttling status.
             let frame_throttling_state = states
                 .data_map
                 .get_or_insert(SurfaceFrameThrottlingState::default);
@@ -4799,9 +6541,80 @@ impl Niri {
             }
         };
 
+        let should_send = |surface: &WlSurface, states: &SurfaceData| {
+            // Do the standard primary scanout output check. For pointer surfaces it deduplicates
+            // the frame callbacks across potentially multiple outputs, and for regular windows and
+            // layer-shell surfaces it avoids sending frame callbacks to invisible surfaces.
+            let current_primary_output = surface_primary_scanout_output(surface, states);
+            if current_primary_output.as_ref() != Some(output) {
+                return None;
+            }
+
+            should_send_unchecked(surface, states)
+        };
+
+        let should_send_occluded = |surface: &WlSurface, states: &SurfaceData| {
+            // The window isn't visible on this output, so skip the visibility part of the primary
+            // scanout output check. Still leave it to the other output if the window is visible
+            // there, like in the middle of an interactive move.
+            let current_primary_output = surface_primary_scanout_output(surface, states);
+            if current_primary_output.is_some_and(|primary| primary != *output) {
+                return None;
+            }
+
+            should_send_unchecked(surface, states)
+        };
+
         let frame_callback_time = get_monotonic_time();
 
+        let throttle_occluded = !self.config.borrow().occluded_windows.off;
+        let occluded = self
+            .occluded_windows
+            .get_or_insert_with(|| self.layout.occluded_windows().into_iter().collect());
+
+        // To save power, clients on outputs without focus redraw every other refresh cycle.
+        let skip_cycle =
+            self.power_saving && self.layout.active_output() != Some(output) && sequence % 2 == 1;
+
         for mapped in self.layout.windows_for_output_mut(output) {
+            if skip_cycle {
+                // Still send the frame callbacks that the window needs to respond to a
+                // configure.
+                mapped.send_frame(
+                    output,
+                    frame_callback_time,
+                    FRAME_CALLBACK_THROTTLE,
+                    |_, _| None,
+                );
+                continue;
+            }
+
+            if occluded.contains(&mapped.window) {
+                if mapped.rules().throttle_when_occluded == Some(false) {
+                    // Keep the window going at the output refresh rate, for example, a media
+                    // player that syncs its playback to frame callbacks.
+                    mapped.send_frame(
+                        output,
+                        frame_callback_time,
+                        FRAME_CALLBACK_THROTTLE,
+                        should_send_occluded,
+                    );
+                    continue;
+                }
+
+                if throttle_occluded {
+                    // Occluded windows get frame callbacks from the fallback timer. Only send
+                    // the ones that the window needs to respond to a configure.
+                    mapped.send_frame(
+                        output,
+                        frame_callback_time,
+                        FRAME_CALLBACK_THROTTLE,
+                        |_, _| None,
+                    );
+                    continue;
+                }
+            }
+
             mapped.send_frame(
                 output,
                 frame_callback_time,
@@ -4810,13 +6623,15 @@ impl Niri {
             );
         }
 
-        for surface in layer_map_for_output(output).layers() {
-            surface.send_frame(
-                output,
-                frame_callback_time,
-                FRAME_CALLBACK_THROTTLE,
-                should_send,
-            );
+        if !skip_cycle {
+            for surface in layer_map_for_output(output).layers() {
+                surface.send_frame(
+                    output,
+                    frame_callback_time,
+                    FRAME_CALLBACK_THROTTLE,
+                    should_send,
+                );
+            }
         }
 
         if let Some(surface) = &self.output_state[output].lock_surface {
@@ -4868,13 +6683,26 @@ impl Niri {
 
         let frame_callback_time = get_monotonic_time();
 
+        let config = self.config.borrow();
+        let occluded_throttle = (!config.occluded_windows.off).then(|| {
+            occluded_frame_callback_throttle(config.occluded_windows.frame_callback_interval_ms)
+        });
+        drop(config);
+        let occluded = self
+            .occluded_windows
+            .get_or_insert_with(|| self.layout.occluded_windows().into_iter().collect());
+
         self.layout.with_windows_mut(|mapped, _| {
-            mapped.send_frame(
-                output,
-                frame_callback_time,
-                FRAME_CALLBACK_THROTTLE,
-                |_, _| None,
-            );
+            let throttle = match occluded_throttle {
+                Some(throttle)
+                    if occluded.contains(&mapped.window)
+                        && mapped.rules().throttle_when_occluded != Some(false) =>
+                {
+                    throttle
+                }
+                _ => FRAME_CALLBACK_THROTTLE,
+            };
+            mapped.send_frame(output, frame_callback_time, throttle, |_, _| None);
         });
 
         for (output, state) in self.output_state.iter() {
@@ -5345,6 +7173,65 @@ impl Niri {
             .context("error saving screenshot")
     }
 
+    pub fn workspace_thumbnail(
+        &self,
+        renderer: &mut GlesRenderer,
+        id: u64,
+        max_size: u16,
+    ) -> anyhow::Result<niri_ipc::WorkspaceThumbnail> {
+        let _span = tracy_client::span!("Niri::workspace_thumbnail");
+
+        let ws_id = WorkspaceId::specific(id);
+        let (_, _, ws) = self
+            .layout
+            .workspaces()
+            .find(|(_, _, ws)| ws.id() == ws_id)
+            .context("workspace not found")?;
+
+        let scale = Scale::from(ws.scale().fractional_scale());
+        let ws_size: Size<i32, Physical> = ws.view_size().to_physical_precise_round(scale);
+        ensure!(ws_size.w > 0 && ws_size.h > 0, "workspace has zero size");
+
+        let max_size = max_size.clamp(1, niri_ipc::WorkspaceThumbnail::MAX_SIZE);
+        let longest_side = f64::from(ws_size.w.max(ws_size.h));
+        let zoom = f64::min(1., f64::from(max_size) / longest_side);
+        let mut size = ws_size.to_f64().upscale(zoom).to_i32_round();
+        size.w = size.w.max(1);
+        size.h = size.h.max(1);
+
+        let origin = Point::from((0, 0));
+        let mut elements: Vec<WorkspaceThumbnailRenderElement<GlesRenderer>> = Vec::new();
+        let mut push = |elem: WorkspaceRenderElement<GlesRenderer>| {
+            elements.push(RescaleRenderElement::from_element(elem, origin, zoom).into());
+        };
+        ws.render_floating(renderer, RenderTarget::ScreenCapture, false, &mut push);
+        ws.render_scrolling(renderer, RenderTarget::ScreenCapture, false, &mut push);
+        ws.render_background(renderer, &self.background_images, &mut |elem| {
+            elements.push(RescaleRenderElement::from_element(elem, origin, zoom).into());
+        });
+
+        let elements = elements.iter().rev();
+        let pixels = render_to_vec(
+            renderer,
+            size,
+            scale,
+            Transform::Normal,
+            Fourcc::Abgr8888,
+            elements,
+        )?;
+
+        let mut png = Vec::new();
+        write_png_rgba8(&mut png, size.w as u32, size.h as u32, &pixels)
+            .context("error encoding thumbnail image")?;
+
+        Ok(niri_ipc::WorkspaceThumbnail::from_png(
+            id,
+            size.w as u32,
+            size.h as u32,
+            &png,
+        ))
+    }
+
     pub fn save_screenshot(
         &self,
         size: Size<i32, Physical>,
@@ -5897,6 +7784,48 @@ impl Niri {
         }
     }
 
+    #[cfg(feature = "dbus")]
+    pub fn on_accent_color_changed(&self) {
+        let _span = tracy_client::span!("Niri::on_accent_color_changed");
+
+        let Some(dbus) = &self.dbus else { return };
+        let Some(conn_portal) = dbus.conn_portal.clone() else {
+            return;
+        };
+
+        let accent_color = self.accent_color();
+        let res = thread::Builder::new()
+            .name("Settings SettingChanged Emitter".to_owned())
+            .spawn(move || {
+                use crate::dbus::freedesktop_portal::PORTAL_PATH;
+                use crate::dbus::freedesktop_settings::Settings;
+                let _span = tracy_client::span!("SettingChanged");
+                let iface = match conn_portal
+                    .object_server()
+                    .interface::<_, Settings>(PORTAL_PATH)
+                {
+                    Ok(iface) => iface,
+                    Err(err) => {
+                        warn!("error getting Settings interface: {err:?}");
+                        return;
+                    }
+                };
+
+                async_io::block_on(async move {
+                    iface.get_mut().await.set_accent_color(accent_color);
+                    if let Err(err) =
+                        Settings::accent_color_changed(iface.signal_emitter(), accent_color).await
+                    {
+                        warn!("error emitting SettingChanged: {err:?}");
+                    }
+                });
+            });
+
+        if let Err(err) = res {
+            warn!("error spawning a thread to send SettingChanged: {err:?}");
+        }
+    }
+
     pub fn handle_focus_follows_mouse(&mut self, new_focus: &PointContents) {
         let Some(ffm) = self.config.borrow().input.focus_follows_mouse else {
             return;
@@ -5911,9 +7840,24 @@ impl Niri {
             return;
         }
 
+        // After a keyboard focus change, the view scrolls under the pointer, so wait for it to
+        // settle instead of focusing whatever passes by.
+        if self.focus_follows_mouse_suppressed {
+            if self.layout.are_animations_ongoing(new_focus.output.as_ref()) {
+                return;
+            }
+            self.focus_follows_mouse_suppressed = false;
+        }
+
         // Recompute the current pointer focus because we don't update it during animations.
         let current_focus = self.contents_under(pointer.current_location());
 
+        let window_changed = new_focus.window.as_ref().map(|(window, _)| window)
+            != current_focus.window.as_ref().map(|(window, _)| window);
+        if window_changed {
+            self.cancel_focus_follows_mouse();
+        }
+
         if let Some(output) = &new_focus.output {
             if current_focus.output.as_ref() != Some(output) {
                 self.layout.focus_output(output);
@@ -5938,14 +7882,32 @@ impl Niri {
                     return;
                 }
 
+                if !self
+                    .layout
+                    .focus_follows_mouse_applies_to(window, ffm.windows)
+                {
+                    return;
+                }
+
                 if let Some(threshold) = ffm.max_scroll_amount {
                     if self.layout.scroll_amount_to_activate(window) > threshold.0 {
                         return;
                     }
                 }
 
-                self.layout.activate_window_without_raising(window);
-                self.layer_shell_on_demand_focus = None;
+                if ffm.containers_only && self.layout.is_in_focused_tabs(window) {
+                    return;
+                }
+
+                match ffm.delay_ms {
+                    Some(delay_ms) if delay_ms > 0 => {
+                        self.schedule_focus_follows_mouse(window.clone(), delay_ms);
+                    }
+                    _ => {
+                        self.layout.activate_window_without_raising(window);
+                        self.layer_shell_on_demand_focus = None;
+                    }
+                }
             }
         }
 
@@ -5956,6 +7918,41 @@ impl Niri {
         }
     }
 
+    fn schedule_focus_follows_mouse(&mut self, window: Window, delay_ms: u16) {
+        self.cancel_focus_follows_mouse();
+
+        let timer = Timer::from_duration(Duration::from_millis(u64::from(delay_ms)));
+        let token = self
+            .event_loop
+            .insert_source(timer, move |_, _, state| {
+                state.niri.focus_follows_mouse_timer = None;
+
+                // Only focus the window if the pointer is still over it.
+                let pointer = state.niri.seat.get_pointer().unwrap();
+                if pointer.is_grabbed() {
+                    return TimeoutAction::Drop;
+                }
+                let under = state.niri.contents_under(pointer.current_location());
+                if under.window.is_some_and(|(under, _)| under == window) {
+                    state.niri.layout.activate_window_without_raising(&window);
+                    state.niri.layer_shell_on_demand_focus = None;
+                    // FIXME: granular.
+                    state.niri.queue_redraw_all();
+                }
+
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.focus_follows_mouse_timer = Some(token);
+    }
+
+    /// Cancels a pending delayed focus-follows-mouse activation.
+    pub fn cancel_focus_follows_mouse(&mut self) {
+        if let Some(token) = self.focus_follows_mouse_timer.take() {
+            self.event_loop.remove(token);
+        }
+    }
+
     pub fn do_screen_transition(&mut self, renderer: &mut GlesRenderer, delay_ms: Option<u16>) {
         let _span = tracy_client::span!("Niri::do_screen_transition");
 
@@ -6051,12 +8048,18 @@ impl Niri {
             let mut windows = vec![];
             self.layout.with_windows_mut(|mapped, _| {
                 if mapped.recompute_window_rules(window_rules, self.is_at_startup) {
-                    windows.push(mapped.window.clone());
+                    let floating = mapped.take_floating_change_from_rules();
+                    windows.push((mapped.window.clone(), floating));
                 }
             });
             let changed = !windows.is_empty();
-            for win in windows {
+            for (win, floating) in windows {
                 self.layout.update_window(&win, None);
+
+                // Rules with match-on-change re-apply open-floating to live windows.
+                if let Some(floating) = floating {
+                    self.layout.set_window_floating(Some(&win), floating);
+                }
             }
             changed
         };
@@ -6135,6 +8138,22 @@ impl Niri {
 
         self.idle_notifier_state.notify_activity(&self.seat);
 
+        self.last_activity = self.clock.now_unadjusted();
+
+        // Undim right away on input.
+        let mut undimmed = Vec::new();
+        for (output, state) in &mut self.output_state {
+            if state.idle_dim.take().is_some() {
+                undimmed.push(output.clone());
+            }
+        }
+        for output in undimmed {
+            self.queue_redraw(&output);
+        }
+        if !self.output_state.is_empty() && self.idle_dim_timer.is_none() {
+            self.update_idle_dim();
+        }
+
         self.notified_activity_this_iteration = true;
     }
 
@@ -6183,6 +8202,7 @@ pub struct NewClient {
     pub client: UnixStream,
     pub restricted: bool,
     pub credentials_unknown: bool,
+    pub security_context: Option<SecurityContext>,
 }
 
 pub struct ClientState {
@@ -6193,6 +8213,8 @@ pub struct ClientState {
     pub restricted: bool,
     /// We cannot retrieve this client's socket credentials.
     pub credentials_unknown: bool,
+    /// Security context that this client connected through, if any.
+    pub security_context: Option<SecurityContext>,
 }
 
 impl ClientData for ClientState {
@@ -6212,6 +8234,41 @@ fn scale_relocate_crop<E: Element>(
     CropRenderElement::from_element(elem, output_scale, ws_geo)
 }
 
+/// Returns the rate of the animation clock.
+fn animation_rate(config: &Config, power_saving: bool) -> f64 {
+    let mut slowdown = config.animations.slowdown;
+    if power_saving {
+        slowdown *= config.power_saving.animation_slowdown;
+    }
+    1.0 / slowdown.max(0.001)
+}
+
+/// Returns the frame callback throttle for occluded windows with the given interval.
+///
+/// Keeps the same leeway as [`FRAME_CALLBACK_THROTTLE`] to line up with the once-a-second timer.
+fn occluded_frame_callback_throttle(interval_ms: u32) -> Option<Duration> {
+    let interval = Duration::from_millis(u64::from(interval_ms));
+    Some(interval.saturating_sub(Duration::from_millis(5)))
+}
+
+/// Returns the variables describing a workspace at `idx` on its output.
+fn workspace_env(
+    output: Option<String>,
+    idx: usize,
+    ws: &Workspace<Mapped>,
+) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if let Some(output) = output {
+        env.push((String::from("NIRI_OUTPUT"), output));
+    }
+
+    // Unnamed workspaces go by their index on the monitor, like in `focus-workspace`.
+    let name = ws.name().cloned().unwrap_or_else(|| (idx + 1).to_string());
+    env.push((String::from("NIRI_WORKSPACE"), name));
+    env.push((String::from("NIRI_WORKSPACE_ID"), ws.id().get().to_string()));
+    env
+}
+
 niri_render_elements! {
     PointerRenderElements<R> => {
         Wayland = WaylandSurfaceRenderElement<R>,
@@ -6219,6 +8276,13 @@ niri_render_elements! {
     }
 }
 
+niri_render_elements! {
+    WorkspaceThumbnailRenderElement<R> => {
+        Workspace = RescaleRenderElement<WorkspaceRenderElement<R>>,
+        Background = RescaleRenderElement<BackgroundRenderElement>,
+    }
+}
+
 niri_render_elements! {
     WindowScreenshotRenderElement<R> => {
         Layout = LayoutElementRenderElement<R>,
@@ -6229,19 +8293,21 @@ niri_render_elements! {
 niri_render_elements! {
     OutputRenderElements<R> => {
         Monitor = MonitorRenderElement<R>,
+        Background = BackgroundRenderElement,
         RescaledTile = RescaleRenderElement<TileRenderElement<R>>,
         LayerSurface = LayerSurfaceRenderElement<R>,
         RelocatedLayerSurface = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
             LayerSurfaceRenderElement<R>
         >>>,
-        RelocatedColor = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
-            SolidColorRenderElement
+        RelocatedBackground = CropRenderElement<RelocateRenderElement<RescaleRenderElement<
+            BackgroundRenderElement
         >>>,
         Pointer = PointerRenderElements<R>,
         Wayland = WaylandSurfaceRenderElement<R>,
         SolidColor = SolidColorRenderElement,
         ScreenshotUi = ScreenshotUiRenderElement,
         WindowMruUi = WindowMruUiRenderElement<R>,
+        TabPreview = TabPreviewRenderElement<R>,
         ExitConfirmDialog = ExitConfirmDialogRenderElement,
         Texture = PrimaryGpuTextureRenderElement,
         // Used for the CPU-rendered panels.
@@ -9,6 +9,8 @@
 //! - Workspace id: name for named workspaces, unset for unnamed. Because ids in this protocol are
 //!   expected to be stable across sessions.
 //! - Workspace name: name for named workspaces, index for unnamed.
+//! - Creating a workspace adds a named workspace on the group's output. Removing a workspace
+//!   unnames it, so it goes away once it's empty, like with `unset-workspace-name`.
 
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -39,19 +41,26 @@ pub trait ExtWorkspaceHandler {
     fn ext_workspace_manager_state(&mut self) -> &mut ExtWorkspaceManagerState;
     fn activate_workspace(&mut self, id: WorkspaceId);
     fn assign_workspace(&mut self, ws_id: WorkspaceId, output: Output);
+    fn create_workspace(&mut self, name: String, output: Output);
+    fn remove_workspace(&mut self, id: WorkspaceId);
 }
 
 enum Action {
+    Create(String, WeakOutput),
     Assign(WorkspaceId, WeakOutput),
     Activate(WorkspaceId),
+    Remove(WorkspaceId),
 }
 
 impl Action {
     fn order(&self) -> u8 {
-        // First assign everything (move across outputs), then activate.
+        // First create new workspaces, then assign everything (move across outputs), then
+        // activate, and finally remove.
         match self {
-            Action::Assign(_, _) => 0,
-            Action::Activate(_) => 1,
+            Action::Create(_, _) => 0,
+            Action::Assign(_, _) => 1,
+            Action::Activate(_) => 2,
+            Action::Remove(_) => 3,
         }
     }
 }
@@ -419,7 +428,7 @@ impl ExtWorkspaceGroupData {
             .unwrap();
         manager.workspace_group(&group);
 
-        group.capabilities(ext_workspace_group_handle_v1::GroupCapabilities::empty());
+        group.capabilities(ext_workspace_group_handle_v1::GroupCapabilities::CreateWorkspace);
 
         for wl_output in output.client_outputs(client) {
             group.output_enter(&wl_output);
@@ -464,7 +473,8 @@ impl ExtWorkspaceData {
         workspace.state(self.state);
         workspace.capabilities(
             ext_workspace_handle_v1::WorkspaceCapabilities::Activate
-                | ext_workspace_handle_v1::WorkspaceCapabilities::Assign,
+                | ext_workspace_handle_v1::WorkspaceCapabilities::Assign
+                | ext_workspace_handle_v1::WorkspaceCapabilities::Remove,
         );
 
         self.instances.push(workspace);
@@ -566,12 +576,18 @@ where
 
                 for action in actions {
                     match action {
+                        Action::Create(name, output) => {
+                            if let Some(output) = output.upgrade() {
+                                state.create_workspace(name, output);
+                            }
+                        }
                         Action::Assign(ws_id, output) => {
                             if let Some(output) = output.upgrade() {
                                 state.assign_workspace(ws_id, output);
                             }
                         }
                         Action::Activate(id) => state.activate_workspace(id),
+                        Action::Remove(id) => state.remove_workspace(id),
                     }
                 }
             }
@@ -643,7 +659,10 @@ where
                     actions.push(Action::Assign(workspace, output.downgrade()));
                 }
             }
-            ext_workspace_handle_v1::Request::Remove => (),
+            ext_workspace_handle_v1::Request::Remove => {
+                let actions = protocol_state.instances.get_mut(data).unwrap();
+                actions.push(Action::Remove(workspace));
+            }
             ext_workspace_handle_v1::Request::Destroy => (),
             _ => unreachable!(),
         }
@@ -668,16 +687,29 @@ where
     D: ExtWorkspaceHandler,
 {
     fn request(
-        _state: &mut D,
+        state: &mut D,
         _client: &Client,
-        _resource: &ExtWorkspaceGroupHandleV1,
+        resource: &ExtWorkspaceGroupHandleV1,
         request: <ExtWorkspaceGroupHandleV1 as Resource>::Request,
-        _data: &ExtWorkspaceManagerV1,
+        data: &ExtWorkspaceManagerV1,
         _dhandle: &DisplayHandle,
         _data_init: &mut DataInit<'_, D>,
     ) {
         match request {
-            ext_workspace_group_handle_v1::Request::CreateWorkspace { .. } => (),
+            ext_workspace_group_handle_v1::Request::CreateWorkspace { workspace } => {
+                let protocol_state = state.ext_workspace_manager_state();
+                let Some(output) = protocol_state
+                    .workspace_groups
+                    .iter()
+                    .find(|(_, data)| data.instances.contains(resource))
+                    .map(|(output, _)| output.downgrade())
+                else {
+                    return;
+                };
+
+                let actions = protocol_state.instances.get_mut(data).unwrap();
+                actions.push(Action::Create(workspace, output));
+            }
             ext_workspace_group_handle_v1::Request::Destroy => (),
             _ => unreachable!(),
         }
@@ -36,6 +36,7 @@ pub mod shadow;
 pub mod snapshot;
 pub mod solid_color;
 pub mod surface;
+pub mod text;
 pub mod texture;
 
 /// What we're rendering for.
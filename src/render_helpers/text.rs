@@ -0,0 +1,232 @@
+//! Text rendering for compositor UI.
+//!
+//! Text is shaped with pango and drawn with cairo into a texture. Tab bars, title bars, dialogs,
+//! prompts, notifications, the hotkey overlay, the screenshot UI panel, the OSD and overview
+//! labels go through these helpers, so fonts are sized and rendered the same way, and the
+//! configured `ui-font` applies to all of them. The window switcher and the overview search bar
+//! still draw with their own fixed fonts.
+
+use niri_config::Color;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::{self, FontDescription, Layout};
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::Transform;
+
+use crate::render_helpers::texture::TextureBuffer;
+use crate::utils::to_physical_precise_round;
+
+/// Font of compositor UI unless the config sets `ui-font`.
+pub const DEFAULT_UI_FONT: &str = "sans 14px";
+
+/// Font size in logical pixels to use when the font string doesn't set one.
+const FALLBACK_SIZE: f64 = 12.;
+
+/// Returns the font for drawing at the given scale.
+pub fn font_for_scale(font: &str, scale: f64) -> FontDescription {
+    let mut desc = FontDescription::from_string(font);
+    let size = if desc.size() > 0 {
+        f64::from(desc.size())
+    } else {
+        parse_font_size(font).unwrap_or(FALLBACK_SIZE) * f64::from(pango::SCALE)
+    };
+    let size = to_physical_precise_round::<f64>(scale, size).max(1.);
+    desc.set_absolute_size(size);
+    desc
+}
+
+/// Returns the UI font for drawing at the given scale.
+///
+/// The configured font only needs to set what it wants to change, e.g. just the family, and the
+/// rest comes from the default.
+pub fn ui_font(ui_font: Option<&str>, scale: f64) -> FontDescription {
+    let mut desc = FontDescription::from_string(DEFAULT_UI_FONT);
+    if let Some(ui_font) = ui_font {
+        desc.merge(Some(&FontDescription::from_string(ui_font)), true);
+    }
+    font_for_scale(&desc.to_str(), scale)
+}
+
+/// Creates a layout for drawing with the font on the cairo context.
+pub fn create_layout(cr: &cairo::Context, font: &FontDescription) -> Layout {
+    let layout = pangocairo::functions::create_layout(cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(font));
+    layout
+}
+
+/// Returns the height of a line of text in the font, in physical pixels.
+pub fn measure_font_height_px(font: &FontDescription) -> Option<i32> {
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 1, 1).ok()?;
+    let cr = cairo::Context::new(&surface).ok()?;
+    let layout = create_layout(&cr, font);
+    layout.set_text("Ag");
+    let (_, height) = layout.pixel_size();
+    (height > 0).then_some(height)
+}
+
+pub fn set_source_color(cr: &cairo::Context, color: Color) {
+    let [r, g, b, a] = color.to_array_unpremul();
+    cr.set_source_rgba(f64::from(r), f64::from(g), f64::from(b), f64::from(a));
+}
+
+/// Renders a line of text into a texture of the same size.
+pub fn render_text(
+    renderer: &mut GlesRenderer,
+    text: &str,
+    font: &FontDescription,
+    color: Color,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("text::render_text");
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = create_layout(&cr, font);
+    layout.set_text(text);
+    let (width, height) = layout.pixel_size();
+    anyhow::ensure!(width > 0 && height > 0, "text has zero size");
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = create_layout(&cr, font);
+    layout.set_text(text);
+
+    set_source_color(&cr, color);
+    pangocairo::functions::show_layout(&cr, &layout);
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+/// Renders a line of white text on a box of the background color.
+///
+/// The padding around the text is in logical pixels.
+pub fn render_text_box(
+    renderer: &mut GlesRenderer,
+    text: &str,
+    font: &FontDescription,
+    background: Color,
+    padding: i32,
+    scale: f64,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("text::render_text_box");
+
+    let padding: i32 = to_physical_precise_round(scale, padding);
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = create_layout(&cr, font);
+    layout.set_text(text);
+
+    let (mut width, mut height) = layout.pixel_size();
+    width += padding * 2;
+    height += padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    set_source_color(&cr, background);
+    cr.paint()?;
+
+    cr.move_to(padding.into(), padding.into());
+    let layout = create_layout(&cr, font);
+    layout.set_text(text);
+
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &layout);
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+/// Rendered text, remembering what it was rendered from.
+#[derive(Debug, Default)]
+pub struct CachedText {
+    text: String,
+    font: String,
+    color: Option<Color>,
+    scale: f64,
+    buffer: Option<TextureBuffer<GlesTexture>>,
+}
+
+impl CachedText {
+    /// Returns the text texture, rendering it again if anything changed.
+    pub fn update(
+        &mut self,
+        renderer: &mut GlesRenderer,
+        text: &str,
+        font: &str,
+        color: Color,
+        scale: f64,
+    ) -> Option<TextureBuffer<GlesTexture>> {
+        let unchanged = self.buffer.is_some()
+            && self.text == text
+            && self.font == font
+            && self.color == Some(color)
+            && self.scale == scale;
+        if !unchanged {
+            self.text = text.to_owned();
+            self.font = font.to_owned();
+            self.color = Some(color);
+            self.scale = scale;
+
+            let desc = font_for_scale(font, scale);
+            self.buffer = render_text(renderer, text, &desc, color, scale)
+                .map_err(|err| warn!("error rendering text: {err:?}"))
+                .ok();
+        }
+
+        self.buffer.clone()
+    }
+}
+
+/// Returns the last number in the font string, which pango takes as the size.
+fn parse_font_size(font: &str) -> Option<f64> {
+    font.split(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+        .filter_map(|num| num.parse().ok())
+        .last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_size_from_string() {
+        assert_eq!(parse_font_size("sans 14px"), Some(14.));
+        assert_eq!(parse_font_size("Noto Sans Bold 10.5"), Some(10.5));
+        assert_eq!(parse_font_size("monospace"), None);
+    }
+
+    #[test]
+    fn ui_font_keeps_default_size() {
+        let font = ui_font(Some("Inter"), 2.);
+        assert_eq!(font.family().unwrap().as_str(), "Inter");
+        assert_eq!(font.size(), 28 * pango::SCALE);
+        assert!(font.is_size_absolute());
+    }
+}
@@ -0,0 +1,78 @@
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::ClientId;
+use super::*;
+
+fn create_window(f: &mut Fixture, id: ClientId) -> WlSurface {
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    surface
+}
+
+// Returns whether the first opened window shows the activity indicator.
+fn first_window_has_activity(f: &mut Fixture) -> bool {
+    let niri = f.niri();
+    let (_, mapped) = niri
+        .layout
+        .windows()
+        .min_by_key(|(_, mapped)| mapped.id().get())
+        .unwrap();
+    mapped.has_activity()
+}
+
+fn commit_new_buffer(f: &mut Fixture, id: ClientId, surface: &WlSurface, damage: bool) {
+    let window = f.client(id).window(surface);
+    window.attach_new_buffer();
+    if damage {
+        window.surface.damage_buffer(0, 0, i32::MAX, i32::MAX);
+    }
+    window.commit();
+    f.double_roundtrip(id);
+}
+
+#[test]
+fn new_content_while_unfocused_is_activity() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let surface1 = create_window(&mut f, id);
+    // Opening the second window focuses it and resizes the first one.
+    create_window(&mut f, id);
+
+    // Redrawing for the new configure doesn't count.
+    let window = f.client(id).window(&surface1);
+    window.attach_new_buffer();
+    window.surface.damage_buffer(0, 0, i32::MAX, i32::MAX);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+    assert!(!first_window_has_activity(&mut f));
+
+    // Neither does a new buffer without damage.
+    commit_new_buffer(&mut f, id, &surface1, false);
+    assert!(!first_window_has_activity(&mut f));
+
+    // New contents do.
+    commit_new_buffer(&mut f, id, &surface1, true);
+    assert!(first_window_has_activity(&mut f));
+}
+
+#[test]
+fn commits_while_focused_are_not_activity() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let id = f.add_client();
+
+    let surface = create_window(&mut f, id);
+    commit_new_buffer(&mut f, id, &surface, true);
+    assert!(!first_window_has_activity(&mut f));
+}
@@ -0,0 +1,169 @@
+use niri_config::Config;
+use niri_ipc::PositionChange;
+use smithay::utils::{Physical, Rectangle};
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::ClientId;
+use super::*;
+use crate::backend::headless::MAX_RECORDED_FRAMES;
+
+// Sets up a fixture with a renderer, an output, and a client.
+fn set_up() -> (Fixture, ClientId) {
+    let mut config = Config::default();
+    config.layout.gaps = 0.0;
+    config.animations.off = true;
+
+    let mut f = Fixture::with_config(config);
+    f.niri_state().backend.headless().add_renderer().unwrap();
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    (f, id)
+}
+
+fn create_window(f: &mut Fixture, id: ClientId, w: u16, h: u16) -> WlSurface {
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(w, h);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    surface
+}
+
+// Renders a frame on the output and returns the damage since the last call.
+//
+// Roundtrips can render frames too, so this collects the damage of all of them.
+fn render_frame(f: &mut Fixture) -> Vec<Rectangle<i32, Physical>> {
+    let output = f.niri_output(1);
+    f.niri_complete_animations();
+    f.niri().queue_redraw(&output);
+    f.niri_state().refresh_and_flush_clients();
+
+    let frames = f.niri_state().backend.headless().take_damage(&output);
+    assert!(!frames.is_empty(), "expected a rendered frame");
+    frames.into_iter().flatten().collect()
+}
+
+fn damaged_area(damage: &[Rectangle<i32, Physical>]) -> i32 {
+    damage.iter().map(|rect| rect.size.w * rect.size.h).sum()
+}
+
+// Returns tile rectangles in the order the windows were opened.
+fn tile_rects(f: &mut Fixture) -> Vec<Rectangle<i32, Physical>> {
+    let ws = f.niri().layout.active_workspace().unwrap();
+    let mut tiles: Vec<_> = ws.tiles_with_render_positions().collect();
+    tiles.sort_by_key(|(tile, _, _)| tile.window().id().get());
+    tiles
+        .into_iter()
+        .map(|(tile, pos, _)| Rectangle::new(pos, tile.tile_size()).to_physical_precise_round(1.))
+        .collect()
+}
+
+#[test]
+fn first_frame_damages_whole_output() {
+    let (mut f, _id) = set_up();
+
+    let damage = render_frame(&mut f);
+    assert_eq!(damaged_area(&damage), 1920 * 1080);
+}
+
+#[test]
+fn idle_frame_has_no_damage() {
+    let (mut f, id) = set_up();
+    create_window(&mut f, id, 100, 100);
+
+    render_frame(&mut f);
+
+    let damage = render_frame(&mut f);
+    assert_eq!(damage, []);
+}
+
+#[test]
+fn moving_floating_window_damages_only_window() {
+    let (mut f, id) = set_up();
+    create_window(&mut f, id, 100, 100);
+
+    f.niri().layout.toggle_window_floating(None);
+    f.double_roundtrip(id);
+
+    render_frame(&mut f);
+
+    f.niri().layout.move_floating_window(
+        None,
+        PositionChange::AdjustFixed(50.),
+        PositionChange::AdjustFixed(0.),
+        false,
+    );
+
+    // The old and the new window location, plus some room for the focus ring.
+    let damage = render_frame(&mut f);
+    assert!(!damage.is_empty());
+    assert!(
+        damaged_area(&damage) <= 200 * 150,
+        "too much damage: {damage:?}"
+    );
+}
+
+#[test]
+fn committing_window_damages_only_its_tile() {
+    let (mut f, id) = set_up();
+    create_window(&mut f, id, 100, 100);
+    let surface2 = create_window(&mut f, id, 100, 100);
+
+    render_frame(&mut f);
+
+    let rects = tile_rects(&mut f);
+    let (rect1, rect2) = (rects[0], rects[1]);
+
+    // Commit a new buffer to the second window.
+    let window = f.client(id).window(&surface2);
+    window.attach_new_buffer();
+    window.surface.damage_buffer(0, 0, i32::MAX, i32::MAX);
+    window.commit();
+    f.double_roundtrip(id);
+
+    let damage = render_frame(&mut f);
+    assert!(!damage.is_empty());
+    for rect in &damage {
+        assert!(
+            rect2.contains_rect(*rect),
+            "damage outside the tile: {rect:?}"
+        );
+        assert!(!rect1.overlaps(*rect), "damage on the other tile: {rect:?}");
+    }
+}
+
+#[test]
+fn recorded_damage_is_capped() {
+    let (mut f, _id) = set_up();
+    let output = f.niri_output(1);
+
+    for _ in 0..MAX_RECORDED_FRAMES * 2 {
+        f.niri().queue_redraw(&output);
+        f.niri_state().refresh_and_flush_clients();
+    }
+
+    let frames = f.niri_state().backend.headless().take_damage(&output);
+    assert_eq!(frames.len(), MAX_RECORDED_FRAMES);
+}
+
+#[test]
+fn removing_output_drops_its_damage() {
+    let (mut f, _id) = set_up();
+    f.add_output(2, (1280, 720));
+    let output = f.niri_output(2);
+
+    f.niri().queue_redraw(&output);
+    f.niri_state().refresh_and_flush_clients();
+
+    f.remove_output(2);
+    let headless = f.niri_state().backend.headless();
+    assert!(headless.take_damage(&output).is_empty());
+    assert_eq!(headless.ipc_outputs().lock().unwrap().len(), 1);
+}
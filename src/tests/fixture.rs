@@ -95,12 +95,20 @@ impl Fixture {
         state.backend.headless().add_output(niri, n, size);
     }
 
+    pub fn remove_output(&mut self, n: u8) {
+        let output = self.niri_output(n);
+        let state = self.niri_state();
+        let niri = &mut state.niri;
+        state.backend.headless().remove_output(niri, &output);
+    }
+
     pub fn add_client(&mut self) -> ClientId {
         let (sock1, sock2) = UnixStream::pair().unwrap();
         self.niri().insert_client(NewClient {
             client: sock1,
             restricted: false,
             credentials_unknown: false,
+            security_context: None,
         });
 
         let client = Client::new(sock2);
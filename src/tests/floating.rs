@@ -1364,3 +1364,43 @@ fn repeated_size_request() {
         @""
     );
 }
+
+#[test]
+fn match_on_change_rule_floats_and_restores() {
+    let rule = |title: &str| {
+        let config = format!(
+            r##"
+window-rule {{
+    match title="^{title}$"
+    match-on-change
+
+    open-floating true
+}}
+"##
+        );
+        Config::parse_mem(&config).unwrap()
+    };
+    let is_floating = |f: &mut Fixture| f.niri().layout.windows().next().unwrap().1.is_floating();
+
+    let (mut f, id, surface) = set_up_with_config(rule("pip"));
+    assert!(!is_floating(&mut f));
+
+    // The rule starts matching after a title change.
+    f.client(id).window(&surface).set_title("pip");
+    f.double_roundtrip(id);
+    assert!(is_floating(&mut f));
+
+    // The window goes back to tiling once the rule stops matching.
+    f.client(id).window(&surface).set_title("other");
+    f.double_roundtrip(id);
+    assert!(!is_floating(&mut f));
+
+    // Same when the config changes.
+    f.niri_state().reload_config(Ok(rule("other")));
+    f.double_roundtrip(id);
+    assert!(is_floating(&mut f));
+
+    f.niri_state().reload_config(Ok(Config::default()));
+    f.double_roundtrip(id);
+    assert!(!is_floating(&mut f));
+}
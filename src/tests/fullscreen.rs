@@ -1,10 +1,13 @@
 use client::ClientId;
 use insta::assert_snapshot;
+use smithay::reexports::wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::Layer;
+use smithay::reexports::wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::Anchor;
 use smithay::utils::Point;
 use wayland_client::protocol::wl_surface::WlSurface;
 
 use super::*;
 use crate::layout::LayoutElement as _;
+use crate::tests::client::LayerConfigureProps;
 
 // Sets up a fixture with two outputs and 100×100 window.
 fn set_up() -> (Fixture, ClientId, WlSurface) {
@@ -295,3 +298,56 @@ fn interactive_move_unmaximize_to_scrolling_restores_size() {
         @"size: 936 × 1048, bounds: 1920 × 1080, states: [Activated]"
     );
 }
+
+#[test]
+fn direct_scanout_candidate_ignores_top_layer_below_fullscreen() {
+    let (mut f, id, surface) = set_up();
+
+    let niri = f.niri();
+    let mapped = niri.layout.windows().next().unwrap().1;
+    let window_id = mapped.window.clone();
+    niri.layout.set_fullscreen(&window_id, true);
+    f.double_roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.set_size(1920, 1080);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+    f.niri_complete_animations();
+
+    let output = f.niri_output(1);
+    assert!(f.niri().direct_scanout_candidate(&output).is_ok());
+
+    // A bar on the top layer is hidden behind the fullscreen window.
+    let layer = f.client(id).create_layer(None, Layer::Top, "");
+    let layer_surface = layer.surface.clone();
+    layer.set_configure_props(LayerConfigureProps {
+        anchor: Some(Anchor::Left | Anchor::Right | Anchor::Top),
+        size: Some((0, 50)),
+        ..Default::default()
+    });
+    layer.commit();
+    f.roundtrip(id);
+
+    let layer = f.client(id).layer(&layer_surface);
+    layer.attach_new_buffer();
+    layer.ack_last_and_commit();
+    f.double_roundtrip(id);
+    f.niri_complete_animations();
+
+    assert!(f.niri().direct_scanout_candidate(&output).is_ok());
+
+    // Moving it to the overlay layer puts it on top of the window.
+    let layer = f.client(id).layer(&layer_surface);
+    layer.set_configure_props(LayerConfigureProps {
+        layer: Some(Layer::Overlay),
+        ..Default::default()
+    });
+    layer.commit();
+    f.double_roundtrip(id);
+
+    assert_eq!(
+        f.niri().direct_scanout_candidate(&output).err(),
+        Some(niri_ipc::ScanoutBlocker::Overlay)
+    );
+}
@@ -0,0 +1,139 @@
+use std::ffi::OsStr;
+use std::io::{Read as _, Write as _};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::thread;
+
+use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
+use niri_ipc::{Action, Event, Request};
+use tiri_ipc_client::blocking::{Client, EventStream};
+use tiri_ipc_client::{requests, Error, Reconnect};
+
+use super::*;
+use crate::ipc::server::IpcServer;
+
+fn start_ipc_server(f: &mut Fixture, name: &str) -> PathBuf {
+    let handle = f.niri().event_loop.clone();
+    let server = IpcServer::start(&handle, Some(OsStr::new(name)), false).unwrap();
+    let path = server.socket_path.clone().unwrap();
+    f.niri().ipc_server = Some(server);
+
+    // Fill in the event stream state.
+    f.dispatch();
+    path
+}
+
+// Runs the client on a separate thread while dispatching the compositor.
+fn run_client<T: Send + 'static>(
+    f: &mut Fixture,
+    client: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    let handle = thread::spawn(client);
+    while !handle.is_finished() {
+        f.dispatch();
+        thread::yield_now();
+    }
+    handle.join().unwrap()
+}
+
+#[test]
+fn typed_requests() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let path = start_ipc_server(&mut f, "tiri-ipc-client-typed-requests");
+
+    let (version, outputs, workspaces, windows) = run_client(&mut f, move || {
+        let mut client = Client::connect_to(&path).unwrap();
+        (
+            client.get(requests::Version).unwrap(),
+            client.get(requests::Outputs).unwrap(),
+            client.get(requests::Workspaces).unwrap(),
+            client.get(requests::Windows).unwrap(),
+        )
+    });
+
+    assert_eq!(version, crate::utils::version());
+    assert!(outputs.contains_key("headless-1"));
+    assert!(!workspaces.is_empty());
+    assert!(windows.is_empty());
+}
+
+#[test]
+fn event_stream_starts_with_full_state() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let path = start_ipc_server(&mut f, "tiri-ipc-client-event-stream");
+
+    let state = run_client(&mut f, move || {
+        let mut stream = EventStream::connect_to(path).unwrap();
+        stream.set_reconnect(Reconnect::NEVER);
+
+        let mut state = EventStreamState::default();
+        loop {
+            let event = stream.next_event().unwrap();
+            let is_workspaces = matches!(event, Event::WorkspacesChanged { .. });
+            state.apply(event);
+            if is_workspaces {
+                break state;
+            }
+        }
+    });
+
+    assert!(!state.workspaces.workspaces.is_empty());
+}
+
+#[test]
+fn read_only_socket_rejects_actions() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let handle = f.niri().event_loop.clone();
+    let name = OsStr::new("tiri-ipc-client-read-only");
+    let server = IpcServer::start(&handle, Some(name), false).unwrap();
+    let path = server.read_only_socket_path.clone().unwrap();
+    f.niri().ipc_server = Some(server);
+    f.dispatch();
+
+    let (version, action) = run_client(&mut f, move || {
+        let mut client = Client::connect_to(&path).unwrap();
+        let version = client.get(requests::Version).unwrap();
+        let action = client.request(Request::Action(Action::FocusWorkspaceDown {}));
+        (version, action)
+    });
+
+    assert_eq!(version, crate::utils::version());
+    assert!(matches!(action, Err(Error::Niri(_))));
+}
+
+#[test]
+fn i3_socket_lists_workspaces() {
+    let mut f = Fixture::new();
+    f.add_output(1, (1920, 1080));
+    let handle = f.niri().event_loop.clone();
+    let name = OsStr::new("tiri-ipc-client-i3");
+    let server = IpcServer::start(&handle, Some(name), true).unwrap();
+    let path = server.i3_socket_path.clone().unwrap();
+    f.niri().ipc_server = Some(server);
+    f.dispatch();
+
+    let reply = run_client(&mut f, move || {
+        let mut stream = UnixStream::connect(path).unwrap();
+
+        // GET_WORKSPACES with an empty payload.
+        let mut message = b"i3-ipc".to_vec();
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&1u32.to_ne_bytes());
+        stream.write_all(&message).unwrap();
+
+        let mut header = [0; 14];
+        stream.read_exact(&mut header).unwrap();
+        let len = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+        let mut payload = vec![0; len as usize];
+        stream.read_exact(&mut payload).unwrap();
+        serde_json::from_slice::<serde_json::Value>(&payload).unwrap()
+    });
+
+    let workspaces = reply.as_array().unwrap();
+    assert_eq!(workspaces[0]["name"], "1");
+    assert_eq!(workspaces[0]["focused"], true);
+    assert_eq!(workspaces[0]["output"], "headless-1");
+}
@@ -0,0 +1,72 @@
+use niri_config::{Config, Key};
+
+use super::*;
+use crate::input::macro_playback::{play, MacroStep};
+
+fn play_keys(f: &mut Fixture, keys: &[&str]) {
+    let steps = keys
+        .iter()
+        .map(|key| MacroStep::Key(key.parse::<Key>().unwrap()))
+        .collect();
+    let (tx, rx) = async_channel::unbounded();
+    play(f.niri_state(), steps, tx);
+    assert_eq!(rx.try_recv().unwrap(), Ok(()));
+}
+
+#[test]
+fn keys_respect_binding_modes() {
+    let config = r##"
+debug {
+    allow-ipc-macros
+}
+
+binds {
+    Mod+R { mode "overview"; }
+}
+
+mode "overview" {
+    O { toggle-overview; }
+    Escape { mode "default"; }
+}
+"##;
+    let config = Config::parse_mem(config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    // Outside of the mode, the key has no bind.
+    play_keys(&mut f, &["O"]);
+    assert!(!f.niri().layout.is_overview_open());
+
+    play_keys(&mut f, &["Mod+R", "O"]);
+    assert_eq!(f.niri().binding_mode, "overview");
+    assert!(f.niri().layout.is_overview_open());
+
+    play_keys(&mut f, &["Escape"]);
+    assert_eq!(f.niri().binding_mode, "default");
+}
+
+#[test]
+fn keys_update_modifiers_like_real_ones() {
+    let config = r##"
+debug {
+    allow-ipc-macros
+}
+
+binds {
+    Mod+Shift+O { toggle-overview; }
+}
+"##;
+    let config = Config::parse_mem(config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    // Without Shift, the bind doesn't match.
+    play_keys(&mut f, &["Mod+O"]);
+    assert!(!f.niri().layout.is_overview_open());
+
+    play_keys(&mut f, &["Mod+Shift+O"]);
+    assert!(f.niri().layout.is_overview_open());
+
+    // The bind key press was suppressed, and its release ended the suppression.
+    assert!(f.niri().suppressed_keys.is_empty());
+}
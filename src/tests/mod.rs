@@ -4,10 +4,14 @@ mod client;
 mod fixture;
 mod server;
 
+mod activity;
 mod animations;
 mod containers;
+mod damage;
 mod floating;
 mod fullscreen;
+mod ipc_client;
 mod layer_shell;
+mod macro_playback;
 mod transactions;
 mod window_opening;
@@ -17,25 +17,32 @@ use smithay::utils::{Point, Transform};
 use crate::animation::{Animation, Clock};
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{create_layout, ui_font};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
-use crate::utils::{output_size, to_physical_precise_round};
+use crate::utils::{output_safe_area, to_physical_precise_round};
 
 const PADDING: i32 = 8;
-const FONT: &str = "sans 14px";
 const BORDER: i32 = 4;
 
 pub struct ConfigErrorNotification {
     state: State,
     buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
-
-    // If set, this is a "Created config at {path}" notification. If unset, this is a config error
-    // notification.
-    created_path: Option<PathBuf>,
+    message: Message,
 
     clock: Clock,
     config: Rc<RefCell<Config>>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    /// The config failed to parse.
+    Error,
+    /// A default config was created at this path.
+    Created(PathBuf),
+    /// This many binds of the config never trigger.
+    BindConflicts(usize),
+}
+
 enum State {
     Hidden,
     Showing(Animation),
@@ -48,12 +55,16 @@ impl ConfigErrorNotification {
         Self {
             state: State::Hidden,
             buffers: RefCell::new(HashMap::new()),
-            created_path: None,
+            message: Message::Error,
             clock,
             config,
         }
     }
 
+    pub fn update_config(&mut self) {
+        self.buffers.borrow_mut().clear();
+    }
+
     fn animation(&self, from: f64, to: f64) -> Animation {
         let c = self.config.borrow();
         Animation::new(
@@ -65,30 +76,39 @@ impl ConfigErrorNotification {
         )
     }
 
-    pub fn show_created(&mut self, created_path: &Path) {
-        if self.created_path.as_deref() != Some(created_path) {
-            self.created_path = Some(created_path.to_owned());
+    fn set_message(&mut self, message: Message) {
+        if self.message != message {
+            self.message = message;
             self.buffers.borrow_mut().clear();
         }
+    }
 
+    pub fn show_created(&mut self, created_path: &Path) {
+        self.set_message(Message::Created(created_path.to_owned()));
         self.state = State::Showing(self.animation(0., 1.));
     }
 
     pub fn show(&mut self) {
-        let c = self.config.borrow();
-        if c.config_notification.disable_failed {
+        if self.config.borrow().config_notification.disable_failed {
             return;
         }
 
-        if self.created_path.is_some() {
-            self.created_path = None;
-            self.buffers.borrow_mut().clear();
-        }
+        self.set_message(Message::Error);
 
         // Show from scratch even if already showing to bring attention.
         self.state = State::Showing(self.animation(0., 1.));
     }
 
+    /// Shows a warning about `count` binds that never trigger.
+    pub fn show_bind_conflicts(&mut self, count: usize) {
+        if self.config.borrow().config_notification.disable_failed {
+            return;
+        }
+
+        self.set_message(Message::BindConflicts(count));
+        self.state = State::Showing(self.animation(0., 1.));
+    }
+
     pub fn hide(&mut self) {
         if matches!(self.state, State::Hidden) {
             return;
@@ -102,7 +122,7 @@ impl ConfigErrorNotification {
             State::Hidden => (),
             State::Showing(anim) => {
                 if anim.is_done() {
-                    let duration = if self.created_path.is_some() {
+                    let duration = if matches!(self.message, Message::Created(_)) {
                         // Make this quite a bit longer because it comes with a monitor modeset
                         // (can take a while) and an important hotkeys popup diverting the
                         // attention.
@@ -140,23 +160,27 @@ impl ConfigErrorNotification {
         }
 
         let scale = output.current_scale().fractional_scale();
-        let output_size = output_size(output);
-        let path = self.created_path.as_deref();
+        let config = self.config.borrow();
+        let safe_area = output_safe_area(&config, output);
+        let message = &self.message;
 
         let mut buffers = self.buffers.borrow_mut();
         let buffer = buffers
             .entry(NotNan::new(scale).unwrap())
-            .or_insert_with(move || render(renderer.as_gles_renderer(), scale, path).ok());
+            .or_insert_with(move || {
+                let font = ui_font(config.ui_font.as_deref(), scale);
+                render(renderer.as_gles_renderer(), scale, &font, message).ok()
+            });
         let buffer = buffer.clone()?;
 
         let size = buffer.logical_size();
-        let y_range = size.h + f64::from(PADDING) * 2.;
+        let y_range = size.h + f64::from(PADDING) * 2. + safe_area.loc.y;
 
-        let x = (output_size.w - size.w).max(0.) / 2.;
+        let x = safe_area.loc.x + (safe_area.size.w - size.w).max(0.) / 2.;
         let y = match &self.state {
             State::Hidden => unreachable!(),
             State::Showing(anim) | State::Hiding(anim) => -size.h + anim.value() * y_range,
-            State::Shown(_) => f64::from(PADDING) * 2.,
+            State::Shown(_) => safe_area.loc.y + f64::from(PADDING) * 2.,
         };
 
         let location = Point::from((x, y));
@@ -177,30 +201,28 @@ impl ConfigErrorNotification {
 fn render(
     renderer: &mut GlesRenderer,
     scale: f64,
-    created_path: Option<&Path>,
+    font: &FontDescription,
+    message: &Message,
 ) -> anyhow::Result<TextureBuffer<GlesTexture>> {
     let _span = tracy_client::span!("config_error_notification::render");
 
     let padding: i32 = to_physical_precise_round(scale, PADDING);
 
-    let mut text = error_text(true);
-    let mut border_color = (1., 0.3, 0.3);
-    if let Some(path) = created_path {
-        text = format!(
-            "Created a default config file at \
-             <span face='monospace' bgcolor='#000000'>{path:?}</span>",
-        );
-        border_color = (0.5, 1., 0.5);
+    let (text, border_color) = match message {
+        Message::Error => (error_text(true), (1., 0.3, 0.3)),
+        Message::Created(path) => (
+            format!(
+                "Created a default config file at \
+                 <span face='monospace' bgcolor='#000000'>{path:?}</span>",
+            ),
+            (0.5, 1., 0.5),
+        ),
+        Message::BindConflicts(count) => (bind_conflicts_text(*count), (1., 0.8, 0.3)),
     };
 
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
-
     let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
     let cr = cairo::Context::new(&surface)?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, font);
     layout.set_markup(&text);
 
     let (mut width, mut height) = layout.pixel_size();
@@ -213,9 +235,7 @@ fn render(
     cr.paint()?;
 
     cr.move_to(padding.into(), padding.into());
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, font);
     layout.set_markup(&text);
 
     cr.set_source_rgb(1., 1., 1.);
@@ -256,3 +276,14 @@ pub fn error_text(markup: bool) -> String {
 
     format!("Failed to parse the config file. Please run {command} to see the errors.")
 }
+
+fn bind_conflicts_text(count: usize) -> String {
+    let command = "<span face='monospace' bgcolor='#000000'>niri msg bind-conflicts</span>";
+
+    let binds = if count == 1 {
+        String::from("1 bind in the config never triggers")
+    } else {
+        format!("{count} binds in the config never trigger")
+    };
+    format!("{binds}. Please run {command} to see the conflicts.")
+}
@@ -6,7 +6,7 @@ use std::sync::Mutex;
 use niri_config::Config;
 use ordered_float::NotNan;
 use pangocairo::cairo::{self, ImageSurface};
-use pangocairo::pango::{Alignment, FontDescription};
+use pangocairo::pango::Alignment;
 use smithay::backend::renderer::element::utils::RescaleRenderElement;
 use smithay::backend::renderer::element::Kind;
 use smithay::output::Output;
@@ -19,17 +19,19 @@ use crate::render_helpers::memory::MemoryBuffer;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::text::{create_layout, ui_font};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
 use crate::utils::{output_size, to_physical_precise_round};
 
 const KEY_NAME: &str = "Enter";
 const PADDING: i32 = 16;
-const FONT: &str = "sans 14px";
 const BORDER: i32 = 8;
 const BACKDROP_COLOR: [f32; 4] = [0., 0., 0., 0.4];
 
 pub struct ExitConfirmDialog {
     state: State,
+    /// Selected entry when showing the power menu rather than the exit confirmation.
+    menu_selection: Option<usize>,
     buffers: RefCell<HashMap<NotNan<f64>, Option<MemoryBuffer>>>,
 
     clock: Clock,
@@ -54,9 +56,43 @@ enum State {
     Hiding(Animation),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerMenuEntry {
+    Exit,
+    Lock,
+    Suspend,
+    Reboot,
+}
+
+impl PowerMenuEntry {
+    pub const ALL: [Self; 4] = [Self::Exit, Self::Lock, Self::Suspend, Self::Reboot];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Exit => "Exit niri",
+            Self::Lock => "Lock",
+            Self::Suspend => "Suspend",
+            Self::Reboot => "Reboot",
+        }
+    }
+}
+
 impl ExitConfirmDialog {
     pub fn new(clock: Clock, config: Rc<RefCell<Config>>) -> Self {
-        let buffer = match render(1.) {
+        let mut rv = Self {
+            state: State::Hidden,
+            menu_selection: None,
+            buffers: RefCell::new(HashMap::new()),
+            clock,
+            config,
+        };
+        rv.update_config();
+        rv
+    }
+
+    pub fn update_config(&mut self) {
+        let font = self.config.borrow().ui_font.clone();
+        let buffer = match render(1., font.as_deref(), self.menu_selection) {
             Ok(x) => Some(x),
             Err(err) => {
                 warn!("error creating the exit confirm dialog: {err:?}");
@@ -64,12 +100,9 @@ impl ExitConfirmDialog {
             }
         };
 
-        Self {
-            state: State::Hidden,
-            buffers: RefCell::new(HashMap::from([(NotNan::new(1.).unwrap(), buffer)])),
-            clock,
-            config,
-        }
+        let buffers = self.buffers.get_mut();
+        buffers.clear();
+        buffers.insert(NotNan::new(1.).unwrap(), buffer);
     }
 
     pub fn can_show(&self) -> bool {
@@ -99,6 +132,48 @@ impl ExitConfirmDialog {
 
     /// Returns true if the dialog will be shown (even if it is already shown).
     pub fn show(&mut self) -> bool {
+        self.set_menu_selection(None);
+        self.open()
+    }
+
+    /// Shows the power menu instead of the exit confirmation.
+    ///
+    /// Returns true if the menu will be shown (even if it is already shown).
+    pub fn show_menu(&mut self) -> bool {
+        if self.menu_selection.is_none() {
+            self.set_menu_selection(Some(0));
+        }
+        self.open()
+    }
+
+    pub fn is_menu(&self) -> bool {
+        self.menu_selection.is_some()
+    }
+
+    /// Returns the selected power menu entry, if showing the power menu.
+    pub fn selected_entry(&self) -> Option<PowerMenuEntry> {
+        self.menu_selection.map(|idx| PowerMenuEntry::ALL[idx])
+    }
+
+    /// Moves the power menu selection by the given number of entries, wrapping around.
+    pub fn move_selection(&mut self, delta: isize) {
+        let Some(idx) = self.menu_selection else {
+            return;
+        };
+
+        let count = PowerMenuEntry::ALL.len() as isize;
+        let idx = (idx as isize + delta).rem_euclid(count) as usize;
+        self.set_menu_selection(Some(idx));
+    }
+
+    fn set_menu_selection(&mut self, selection: Option<usize>) {
+        if self.menu_selection != selection {
+            self.menu_selection = selection;
+            self.update_config();
+        }
+    }
+
+    fn open(&mut self) -> bool {
         if !self.can_show() {
             return false;
         }
@@ -164,6 +239,7 @@ impl ExitConfirmDialog {
 
         let scale = output.current_scale().fractional_scale();
         let output_size = output_size(output);
+        let config = self.config.borrow();
 
         let mut buffers = self.buffers.borrow_mut();
         let Some(fallback) = buffers[&NotNan::new(1.).unwrap()].clone() else {
@@ -173,7 +249,7 @@ impl ExitConfirmDialog {
 
         let buffer = buffers
             .entry(NotNan::new(scale).unwrap())
-            .or_insert_with(|| render(scale).ok());
+            .or_insert_with(|| render(scale, config.ui_font.as_deref(), self.menu_selection).ok());
         let buffer = buffer.as_ref().unwrap_or(&fallback);
 
         let size = buffer.logical_size();
@@ -222,21 +298,25 @@ impl ExitConfirmDialog {
     }
 }
 
-fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
+fn render(
+    scale: f64,
+    font: Option<&str>,
+    menu_selection: Option<usize>,
+) -> anyhow::Result<MemoryBuffer> {
     let _span = tracy_client::span!("exit_confirm_dialog::render");
 
-    let markup = text(true);
+    let markup = match menu_selection {
+        Some(selected) => menu_text(selected),
+        None => text(true),
+    };
 
     let padding: i32 = to_physical_precise_round(scale, PADDING);
 
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+    let font = ui_font(font, scale);
 
     let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
     let cr = cairo::Context::new(&surface)?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, &font);
     layout.set_alignment(Alignment::Center);
     layout.set_markup(&markup);
 
@@ -250,9 +330,7 @@ fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
     cr.paint()?;
 
     cr.move_to(padding.into(), padding.into());
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, &font);
     layout.set_alignment(Alignment::Center);
     layout.set_markup(&markup);
 
@@ -295,6 +373,22 @@ fn text(markup: bool) -> String {
     )
 }
 
+fn menu_text(selected: usize) -> String {
+    let mut text = String::new();
+    for (idx, entry) in PowerMenuEntry::ALL.into_iter().enumerate() {
+        let label = entry.label();
+        if idx == selected {
+            text.push_str(&format!(
+                "<span bgcolor='#2C2C2C'><b>  {label}  </b></span>\n"
+            ));
+        } else {
+            text.push_str(&format!("  {label}  \n"));
+        }
+    }
+    text.push_str("\nChoose with the arrow keys and press Enter.");
+    text
+}
+
 #[cfg(feature = "dbus")]
 pub fn a11y_node() -> accesskit::Node {
     let mut node = accesskit::Node::new(accesskit::Role::AlertDialog);
@@ -0,0 +1,105 @@
+//! Debug overlay with the commit rate of every visible window.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use niri_config::Color;
+use ordered_float::NotNan;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::output::Output;
+use smithay::utils::Point;
+
+use crate::layout::Layout;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{font_for_scale, render_text_box};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::window::Mapped;
+
+const PADDING: i32 = 4;
+const MARGIN: f64 = 4.;
+const FONT: &str = "monospace 12px";
+const BACKGROUND: Color = Color::new_unpremul(0., 0., 0., 0.7);
+
+/// How many rendered labels to keep around before starting over.
+///
+/// The commit age changes every frame, so labels don't stay useful for long.
+const MAX_CACHED: usize = 256;
+
+type Buffers = HashMap<(String, NotNan<f64>), Option<TextureBuffer<GlesTexture>>>;
+
+#[derive(Default)]
+pub struct FrameStatsOverlay {
+    buffers: RefCell<Buffers>,
+}
+
+impl FrameStatsOverlay {
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        layout: &Layout<Mapped>,
+        output: &Output,
+        now: Duration,
+        push: &mut dyn FnMut(PrimaryGpuTextureRenderElement),
+    ) {
+        let Some(mon) = layout.monitor_for_output(output) else {
+            return;
+        };
+
+        let scale = output.current_scale().fractional_scale();
+
+        let mut buffers = self.buffers.borrow_mut();
+        if buffers.len() > MAX_CACHED {
+            buffers.clear();
+        }
+
+        for (mapped, rect) in mon.visible_windows_with_render_rects() {
+            let text = stats_text(mapped, now);
+            let buffer = buffers
+                .entry((text, NotNan::new(scale).unwrap()))
+                .or_insert_with_key(|(text, _)| {
+                    let font = font_for_scale(FONT, scale);
+                    let renderer = renderer.as_gles_renderer();
+                    render_text_box(renderer, text, &font, BACKGROUND, PADDING, scale).ok()
+                });
+            let Some(buffer) = buffer.clone() else {
+                continue;
+            };
+
+            let location = rect.loc + Point::from((MARGIN, MARGIN));
+            let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer,
+                location,
+                1.,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            push(PrimaryGpuTextureRenderElement(elem));
+        }
+    }
+}
+
+fn stats_text(mapped: &Mapped, now: Duration) -> String {
+    let (rate, last_commit) = mapped.commit_stats(now);
+
+    let rate = match rate {
+        Some(rate) => format!("{rate:.0} fps"),
+        None => String::from("idle"),
+    };
+
+    let Some(last_commit) = last_commit else {
+        return rate;
+    };
+
+    let age = now.saturating_sub(last_commit);
+    if age < Duration::from_secs(1) {
+        format!("{rate}, {} ms ago", age.as_millis())
+    } else {
+        format!("{rate}, {:.1} s ago", age.as_secs_f64())
+    }
+}
@@ -7,7 +7,7 @@ use std::rc::Rc;
 
 use niri_config::{Action, Bind, Config, Key, ModKey, Modifiers, Trigger};
 use pangocairo::cairo::{self, ImageSurface};
-use pangocairo::pango::{AttrColor, AttrInt, AttrList, AttrString, FontDescription, Weight};
+use pangocairo::pango::{AttrColor, AttrInt, AttrList, AttrString, Weight};
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
 use smithay::input::keyboard::xkb::keysym_get_name;
@@ -17,12 +17,12 @@ use smithay::utils::{Scale, Transform};
 
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{create_layout, ui_font};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
-use crate::utils::{output_size, to_physical_precise_round};
+use crate::utils::{output_safe_area, to_physical_precise_round};
 
 const PADDING: i32 = 8;
 // const MARGIN: i32 = PADDING * 2;
-const FONT: &str = "sans 14px";
 const BORDER: i32 = 4;
 const LINE_INTERVAL: i32 = 2;
 const TITLE: &str = "Important Hotkeys";
@@ -75,6 +75,10 @@ impl HotkeyOverlay {
         self.buffers.borrow_mut().clear();
     }
 
+    pub fn update_config(&mut self) {
+        self.buffers.borrow_mut().clear();
+    }
+
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -85,7 +89,7 @@ impl HotkeyOverlay {
         }
 
         let scale = output.current_scale().fractional_scale();
-        let output_size = output_size(output);
+        let safe_area = output_safe_area(&self.config.borrow(), output);
 
         let mut buffers = self.buffers.borrow_mut();
         buffers.retain(|output, _| output.is_alive());
@@ -108,10 +112,10 @@ impl HotkeyOverlay {
         let buffer = rendered.buffer.as_ref()?;
 
         let size = buffer.logical_size();
-        let location = (output_size.to_f64().to_point() - size.to_point()).downscale(2.);
+        let location = safe_area.loc + (safe_area.size.to_point() - size.to_point()).downscale(2.);
         let mut location = location.to_physical_precise_round(scale).to_logical(scale);
-        location.x = f64::max(0., location.x);
-        location.y = f64::max(0., location.y);
+        location.x = f64::max(safe_area.loc.x, location.x);
+        location.y = f64::max(safe_area.loc.y, location.y);
 
         let elem = TextureRenderElement::from_texture_buffer(
             buffer.clone(),
@@ -281,7 +285,13 @@ fn collect_actions(config: &Config) -> Vec<&Action> {
 
     // Add the spawn actions.
     for bind in binds.iter().filter(|bind| {
-        matches!(bind.action, Action::Spawn(_) | Action::SpawnSh(_))
+        matches!(
+            bind.action,
+            Action::Spawn(_)
+                | Action::SpawnSd(_)
+                | Action::SpawnSh(_)
+                | Action::SpawnOnWorkspace(_, _)
+        )
             // Only show binds with Mod or Super to filter out stuff like volume up/down.
             && (bind.key.modifiers.contains(Modifiers::COMPOSITOR)
                 || bind.key.modifiers.contains(Modifiers::SUPER))
@@ -333,14 +343,11 @@ fn render(
         })
         .collect::<Vec<_>>();
 
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+    let font = ui_font(config.ui_font.as_deref(), scale);
 
     let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
     let cr = cairo::Context::new(&surface)?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, &font);
 
     let bold = AttrList::new();
     bold.insert(AttrInt::new_weight(Weight::Bold));
@@ -390,9 +397,7 @@ fn render(
     cr.paint()?;
 
     cr.move_to(padding.into(), padding.into());
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, &font);
 
     cr.set_source_rgb(1., 1., 1.);
 
@@ -459,6 +464,7 @@ fn render(
 fn action_name(action: &Action) -> String {
     match action {
         Action::Quit(_) => String::from("Exit niri"),
+        Action::PowerMenu => String::from("Show Power Menu"),
         Action::ShowHotkeyOverlay => String::from("Show Important Hotkeys"),
         Action::CloseWindow => String::from("Close Focused Window"),
         Action::FocusColumnLeft => String::from("Focus Column to the Left"),
@@ -482,7 +488,7 @@ fn action_name(action: &Action) -> String {
         }
         Action::ToggleOverview => String::from("Open the Overview"),
         Action::Screenshot(_, _) => String::from("Take a Screenshot"),
-        Action::Spawn(args) => format!(
+        Action::Spawn(args) | Action::SpawnSd(args) => format!(
             "Spawn <span face='monospace' bgcolor='#000000'>{}</span>",
             args.first().unwrap_or(&String::new())
         ),
@@ -491,6 +497,10 @@ fn action_name(action: &Action) -> String {
             // Fairly crude but should get the job done in most cases.
             command.split_ascii_whitespace().next().unwrap_or("")
         ),
+        Action::SpawnOnWorkspace(workspace, args) => format!(
+            "Spawn <span face='monospace' bgcolor='#000000'>{}</span> on {workspace}",
+            args.first().unwrap_or(&String::new())
+        ),
         _ => String::from("FIXME: Unknown"),
     }
 }
@@ -0,0 +1,63 @@
+//! Dimming of outputs after a period without input.
+//!
+//! The output fades to a darker shade once the idle timeout passes, and goes back to normal
+//! instantly on any input. This is meant as a warning before an idle daemon powers off the
+//! monitors.
+
+use std::time::Duration;
+
+use niri_config::output::IdleDim as IdleDimConfig;
+use smithay::backend::renderer::element::solid::{SolidColorBuffer, SolidColorRenderElement};
+use smithay::backend::renderer::element::Kind;
+use smithay::utils::{Logical, Size};
+
+use crate::animation::Clock;
+
+#[derive(Debug)]
+pub struct IdleDim {
+    /// Darkening layer drawn over the output.
+    buffer: SolidColorBuffer,
+    /// Monotonic time when the dimming started.
+    start_at: Duration,
+    duration: Duration,
+    /// Alpha of the darkening layer once fully dimmed.
+    max_alpha: f32,
+    clock: Clock,
+}
+
+impl IdleDim {
+    pub fn new(size: Size<f64, Logical>, config: IdleDimConfig, clock: Clock) -> Self {
+        Self {
+            buffer: SolidColorBuffer::new(size, [0., 0., 0., 1.]),
+            start_at: clock.now_unadjusted(),
+            duration: Duration::from_millis(u64::from(config.duration_ms)),
+            max_alpha: (1. - config.brightness.0 as f32).clamp(0., 1.),
+            clock,
+        }
+    }
+
+    pub fn resize(&mut self, size: Size<f64, Logical>) {
+        self.buffer.resize(size);
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.clock.now_unadjusted() < self.start_at + self.duration
+    }
+
+    pub fn render(&self) -> SolidColorRenderElement {
+        // Like the screen transition, this ignores animation slowdown.
+        let elapsed = self.clock.now_unadjusted().saturating_sub(self.start_at);
+        let progress = if self.duration.is_zero() {
+            1.
+        } else {
+            (elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.)
+        };
+
+        SolidColorRenderElement::from_buffer(
+            &self.buffer,
+            (0., 0.),
+            progress * self.max_alpha,
+            Kind::Unspecified,
+        )
+    }
+}
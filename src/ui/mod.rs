@@ -1,6 +1,14 @@
 pub mod config_error_notification;
 pub mod exit_confirm_dialog;
+pub mod frame_stats;
 pub mod hotkey_overlay;
+pub mod idle_dim;
 pub mod mru;
+pub mod osd;
+pub mod overview_search;
+pub mod permission_prompt;
+pub mod resize_mode;
 pub mod screen_transition;
 pub mod screenshot_ui;
+pub mod tab_preview;
+pub mod unresponsive_prompt;
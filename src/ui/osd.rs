@@ -0,0 +1,184 @@
+//! On-screen display of levels such as the brightness.
+//!
+//! The readout shows up at the bottom of the active output for a moment after every change.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Duration;
+
+use niri_config::Config;
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::FontDescription;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Point, Transform};
+
+use crate::animation::Clock;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{create_layout, ui_font};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_safe_area, to_physical_precise_round};
+
+const PADDING: i32 = 12;
+const BAR_WIDTH: i32 = 200;
+const BAR_HEIGHT: i32 = 6;
+/// Distance from the bottom of the output.
+const MARGIN: f64 = 64.;
+const SHOW_DURATION: Duration = Duration::from_millis(1500);
+
+pub struct Osd {
+    /// Label and level in percent, and when to hide.
+    shown: Option<(String, u8, Duration)>,
+    /// Rendered readout, by output scale.
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+    clock: Clock,
+    config: Rc<RefCell<Config>>,
+}
+
+impl Osd {
+    pub fn new(clock: Clock, config: Rc<RefCell<Config>>) -> Self {
+        Self {
+            shown: None,
+            buffers: RefCell::new(HashMap::new()),
+            clock,
+            config,
+        }
+    }
+
+    pub fn update_config(&mut self) {
+        self.buffers.borrow_mut().clear();
+    }
+
+    /// Shows the level, or updates it if already showing.
+    pub fn show(&mut self, label: &str, percent: u8) {
+        let hide_at = self.clock.now_unadjusted() + SHOW_DURATION;
+        let same = self
+            .shown
+            .as_ref()
+            .is_some_and(|(l, p, _)| l == label && *p == percent);
+        if !same {
+            self.buffers.borrow_mut().clear();
+        }
+        self.shown = Some((label.to_owned(), percent, hide_at));
+    }
+
+    pub fn advance_animations(&mut self) {
+        if let Some((_, _, hide_at)) = &self.shown {
+            if self.clock.now_unadjusted() >= *hide_at {
+                self.shown = None;
+                self.buffers.borrow_mut().clear();
+            }
+        }
+    }
+
+    pub fn are_animations_ongoing(&self) -> bool {
+        self.shown.is_some()
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let (label, percent, _) = self.shown.as_ref()?;
+
+        let scale = output.current_scale().fractional_scale();
+        let config = self.config.borrow();
+        let safe_area = output_safe_area(&config, output);
+
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| {
+                let font = ui_font(config.ui_font.as_deref(), scale);
+                render(renderer.as_gles_renderer(), scale, &font, label, *percent)
+                    .map_err(|err| warn!("error rendering OSD: {err:?}"))
+                    .ok()
+            });
+        let buffer = buffer.clone()?;
+
+        let size = buffer.logical_size();
+        let x = safe_area.loc.x + (safe_area.size.w - size.w).max(0.) / 2.;
+        let y = safe_area.loc.y + (safe_area.size.h - size.h - MARGIN).max(0.);
+
+        let location = Point::from((x, y));
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    font: &FontDescription,
+    label: &str,
+    percent: u8,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("osd::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let bar_width: i32 = to_physical_precise_round(scale, BAR_WIDTH);
+    let bar_height: i32 = to_physical_precise_round(scale, BAR_HEIGHT);
+
+    let text = format!("{label}  {percent}%");
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = create_layout(&cr, font);
+    layout.set_text(&text);
+    let (text_width, text_height) = layout.pixel_size();
+
+    let width = text_width.max(bar_width) + padding * 2;
+    let height = text_height + padding + bar_height + padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgba(0.1, 0.1, 0.1, 0.9);
+    cr.paint()?;
+
+    cr.move_to(f64::from((width - text_width) / 2), f64::from(padding));
+    let layout = create_layout(&cr, font);
+    layout.set_text(&text);
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    let bar_x = f64::from((width - bar_width) / 2);
+    let bar_y = f64::from(padding + text_height + padding);
+    cr.rectangle(bar_x, bar_y, f64::from(bar_width), f64::from(bar_height));
+    cr.set_source_rgb(0.3, 0.3, 0.3);
+    cr.fill()?;
+
+    let filled = f64::from(bar_width) * f64::from(percent.min(100)) / 100.;
+    cr.rectangle(bar_x, bar_y, filled, f64::from(bar_height));
+    cr.set_source_rgb(1., 1., 1.);
+    cr.fill()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
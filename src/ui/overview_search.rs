@@ -0,0 +1,328 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::FontDescription;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::desktop::Window;
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Point, Rectangle, Transform};
+
+use crate::layout::Layout;
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_size, to_physical_precise_round, with_toplevel_role};
+use crate::window::mapped::MappedId;
+use crate::window::Mapped;
+
+const PADDING: i32 = 12;
+const FONT: &str = "sans 16px";
+const BORDER: i32 = 4;
+const DIM_COLOR: [f32; 4] = [0., 0., 0., 0.6];
+
+/// Type-to-search in the overview.
+///
+/// Typing in the overview filters windows by title and app ID. Windows that don't match are
+/// dimmed, and Enter focuses the best match.
+pub struct OverviewSearch {
+    query: String,
+    /// Rendered query line, by output scale.
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+    /// Dimming overlays for windows that don't match.
+    dim_buffers: RefCell<HashMap<MappedId, SolidColorBuffer>>,
+}
+
+impl OverviewSearch {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            buffers: RefCell::new(HashMap::new()),
+            dim_buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.buffers.borrow_mut().clear();
+    }
+
+    /// Returns `true` if a character was removed.
+    pub fn pop_char(&mut self) -> bool {
+        let popped = self.query.pop().is_some();
+        self.buffers.borrow_mut().clear();
+        popped
+    }
+
+    /// Returns `true` if there was a query to clear.
+    pub fn clear(&mut self) -> bool {
+        if self.query.is_empty() {
+            return false;
+        }
+
+        self.query.clear();
+        self.buffers.borrow_mut().clear();
+        self.dim_buffers.borrow_mut().clear();
+        true
+    }
+
+    fn score(&self, mapped: &Mapped) -> Option<i32> {
+        let (title, app_id) = with_toplevel_role(mapped.toplevel(), |role| {
+            (role.title.clone(), role.app_id.clone())
+        });
+
+        let title = title.and_then(|title| fuzzy_score(&self.query, &title));
+        let app_id = app_id.and_then(|app_id| fuzzy_score(&self.query, &app_id));
+        title.max(app_id)
+    }
+
+    /// Returns whether the window matches the query; everything matches an empty query.
+    pub fn matches(&self, mapped: &Mapped) -> bool {
+        !self.is_active() || self.score(mapped).is_some()
+    }
+
+    /// Returns the window matching the query best.
+    ///
+    /// On ties, the first window in layout order wins.
+    pub fn best_match(&self, layout: &Layout<Mapped>) -> Option<Window> {
+        if !self.is_active() {
+            return None;
+        }
+
+        let mut best: Option<(i32, &Mapped)> = None;
+        for (_, mapped) in layout.windows() {
+            let Some(score) = self.score(mapped) else {
+                continue;
+            };
+            if best.is_none_or(|(best_score, _)| best_score < score) {
+                best = Some((score, mapped));
+            }
+        }
+
+        best.map(|(_, mapped)| mapped.window.clone())
+    }
+
+    /// Renders the overlays dimming windows that don't match the query.
+    ///
+    /// These go right above the workspaces on the output.
+    pub fn render_dimming(
+        &self,
+        layout: &Layout<Mapped>,
+        output: &Output,
+        push: &mut dyn FnMut(SolidColorRenderElement),
+    ) {
+        if !self.is_active() || !layout.is_overview_open() {
+            return;
+        }
+
+        let Some(mon) = layout.monitor_for_output(output) else {
+            return;
+        };
+        let zoom = mon.overview_zoom();
+
+        let mut dim_buffers = self.dim_buffers.borrow_mut();
+        for (ws, ws_geo) in mon.workspaces_with_render_geo() {
+            for (tile, tile_pos, visible) in ws.tiles_with_render_positions() {
+                let mapped = tile.window();
+                if !visible || self.matches(mapped) {
+                    continue;
+                }
+
+                let geo = Rectangle::new(
+                    ws_geo.loc + tile_pos.upscale(zoom),
+                    tile.tile_size().upscale(zoom),
+                );
+                let buffer = dim_buffers
+                    .entry(mapped.id())
+                    .or_insert_with(|| SolidColorBuffer::new(geo.size, DIM_COLOR));
+                buffer.resize(geo.size);
+
+                push(SolidColorRenderElement::from_buffer(
+                    buffer,
+                    geo.loc,
+                    1.,
+                    Kind::Unspecified,
+                ));
+            }
+        }
+    }
+
+    /// Renders the query line at the top of the output.
+    pub fn render_query<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if !self.is_active() {
+            return None;
+        }
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| {
+                render(renderer.as_gles_renderer(), scale, &self.query)
+                    .inspect_err(|err| warn!("error rendering overview search query: {err:?}"))
+                    .ok()
+            })
+            .clone()?;
+
+        let size = buffer.logical_size();
+        let x = (output_size.w - size.w).max(0.) / 2.;
+        let y = f64::from(PADDING) * 2.;
+        let location = Point::from((x, y))
+            .to_physical_precise_round(scale)
+            .to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+impl Default for OverviewSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scores how well `text` matches `query` as a case-insensitive subsequence.
+///
+/// Returns `None` if `text` doesn't contain all characters of `query` in order. Characters
+/// matched right after another match, or at the start of a word, score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    let mut prev: Option<char> = None;
+    let mut prev_matched = false;
+    let mut score = 0;
+
+    for q in query.chars().flat_map(char::to_lowercase) {
+        loop {
+            let c = text.next()?;
+            let matched = c == q;
+            if matched {
+                score += 1;
+                if prev_matched {
+                    score += 4;
+                }
+                if prev.is_none_or(|prev| !prev.is_alphanumeric()) {
+                    score += 8;
+                }
+            }
+
+            prev = Some(c);
+            prev_matched = matched;
+            if matched {
+                break;
+            }
+        }
+    }
+
+    Some(score)
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    query: &str,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("overview_search::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let text = format!("Search: {query}");
+
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_text(&text);
+
+    let (mut width, mut height) = layout.pixel_size();
+    width += padding * 2;
+    height += padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.paint()?;
+
+    cr.move_to(padding.into(), padding.into());
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_text(&text);
+
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    cr.move_to(0., 0.);
+    cr.line_to(width.into(), 0.);
+    cr.line_to(width.into(), height.into());
+    cr.line_to(0., height.into());
+    cr.line_to(0., 0.);
+    cr.set_source_rgb(0.5, 0.5, 0.5);
+    // Keep the border width even to avoid blurry edges.
+    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
+    cr.stroke()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_scores() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "Firefox"), None);
+        assert_eq!(fuzzy_score("fox", "Firefox"), Some(15));
+
+        // Prefers consecutive matches and word starts.
+        let fire = fuzzy_score("fire", "Firefox").unwrap();
+        let scattered = fuzzy_score("fire", "offline career").unwrap();
+        assert!(fire > scattered);
+
+        let word_start = fuzzy_score("t", "Mozilla Thunderbird").unwrap();
+        let inside = fuzzy_score("t", "kitty").unwrap();
+        assert!(word_start > inside);
+    }
+}
@@ -0,0 +1,309 @@
+//! Prompt for background portal permissions.
+//!
+//! Requests queue up and are shown one at a time in the middle of every output. Enter allows, N
+//! denies, and Escape closes the prompt without saving an answer, so the app gets asked again next
+//! time.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use niri_config::Config;
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::{Alignment, FontDescription};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::Transform;
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{create_layout, ui_font};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::background_permissions::PermissionKind;
+use crate::utils::{output_size, to_physical_precise_round};
+
+const PADDING: i32 = 16;
+const BORDER: i32 = 4;
+
+pub struct PermissionPrompt {
+    pending: VecDeque<PendingRequest>,
+    /// Rendered front request, by output scale.
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+    config: Rc<RefCell<Config>>,
+}
+
+struct PendingRequest {
+    app_id: String,
+    /// Human-readable app name.
+    name: String,
+    kind: PermissionKind,
+    /// Everyone waiting for the answer to this request, along with their portal request handle.
+    replies: Vec<(Option<String>, async_channel::Sender<bool>)>,
+}
+
+impl PermissionPrompt {
+    pub fn new(config: Rc<RefCell<Config>>) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            buffers: RefCell::new(HashMap::new()),
+            config,
+        }
+    }
+
+    pub fn update_config(&mut self) {
+        self.buffers.borrow_mut().clear();
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Queues a request, merging it with a pending one for the same app and permission.
+    pub fn ask(
+        &mut self,
+        app_id: String,
+        name: Option<String>,
+        kind: PermissionKind,
+        handle: Option<String>,
+        reply: async_channel::Sender<bool>,
+    ) {
+        if let Some(pending) = self
+            .pending
+            .iter_mut()
+            .find(|pending| pending.app_id == app_id && pending.kind == kind)
+        {
+            pending.replies.push((handle, reply));
+            return;
+        }
+
+        let name = name
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| app_id.clone());
+        self.pending.push_back(PendingRequest {
+            app_id,
+            name,
+            kind,
+            replies: vec![(handle, reply)],
+        });
+    }
+
+    /// Stops waiting on a cancelled portal request.
+    ///
+    /// Drops the request altogether once nobody waits for its answer. Returns whether anything
+    /// changed.
+    pub fn cancel(&mut self, handle: &str) -> bool {
+        let mut changed = false;
+        for pending in &mut self.pending {
+            let len = pending.replies.len();
+            pending
+                .replies
+                .retain(|(h, _)| h.as_deref() != Some(handle));
+            changed |= pending.replies.len() != len;
+        }
+
+        if let Some(front) = self.pending.front() {
+            if front.replies.is_empty() {
+                self.buffers.borrow_mut().clear();
+            }
+        }
+        self.pending.retain(|pending| !pending.replies.is_empty());
+
+        changed
+    }
+
+    /// Answers the shown request and moves on to the next one.
+    ///
+    /// Returns the app ID and the permission that were answered.
+    pub fn answer(&mut self, allowed: bool) -> Option<(String, PermissionKind)> {
+        let pending = self.pending.pop_front()?;
+        self.buffers.borrow_mut().clear();
+
+        for (_, reply) in pending.replies {
+            let _ = reply.send_blocking(allowed);
+        }
+
+        Some((pending.app_id, pending.kind))
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let pending = self.pending.front()?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| {
+                let font = ui_font(self.config.borrow().ui_font.as_deref(), scale);
+                render(
+                    renderer.as_gles_renderer(),
+                    scale,
+                    &font,
+                    &prompt_text(pending),
+                )
+                .inspect_err(|err| warn!("error rendering permission prompt: {err:?}"))
+                .ok()
+            })
+            .clone()?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let mut location = location.to_physical_precise_round(scale).to_logical(scale);
+        location.x = f64::max(0., location.x);
+        location.y = f64::max(0., location.y);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn prompt_text(pending: &PendingRequest) -> String {
+    let request = match pending.kind {
+        PermissionKind::Background => "wants to keep running in the background",
+        PermissionKind::Autostart => "wants to start automatically on login",
+    };
+
+    let mut text = format!("{} {request}.\n", pending.name);
+    if pending.name != pending.app_id {
+        text += &format!("({})\n", pending.app_id);
+    }
+    text += "\nPress Enter to allow, N to deny, or Escape to decide later.";
+    text
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    font: &FontDescription,
+    text: &str,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("permission_prompt::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = create_layout(&cr, font);
+    layout.set_alignment(Alignment::Center);
+    layout.set_text(text);
+
+    let (mut width, mut height) = layout.pixel_size();
+    width += padding * 2;
+    height += padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.paint()?;
+
+    cr.move_to(padding.into(), padding.into());
+    let layout = create_layout(&cr, font);
+    layout.set_alignment(Alignment::Center);
+    layout.set_text(text);
+
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    cr.move_to(0., 0.);
+    cr.line_to(width.into(), 0.);
+    cr.line_to(width.into(), height.into());
+    cr.line_to(0., height.into());
+    cr.line_to(0., 0.);
+    cr.set_source_rgb(0.5, 0.8, 1.0);
+    // Keep the border width even to avoid blurry edges.
+    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
+    cr.stroke()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_for_the_same_permission_share_an_answer() {
+        let mut prompt = PermissionPrompt::new(Default::default());
+        let (tx1, rx1) = async_channel::unbounded();
+        let (tx2, rx2) = async_channel::unbounded();
+        let (tx3, rx3) = async_channel::unbounded();
+
+        let app = || String::from("org.example.App");
+        prompt.ask(app(), None, PermissionKind::Background, None, tx1);
+        prompt.ask(app(), None, PermissionKind::Autostart, None, tx2);
+        prompt.ask(app(), None, PermissionKind::Background, None, tx3);
+
+        assert_eq!(
+            prompt.answer(false),
+            Some((app(), PermissionKind::Background))
+        );
+        assert_eq!(rx1.try_recv(), Ok(false));
+        assert_eq!(rx3.try_recv(), Ok(false));
+        assert!(rx2.is_empty());
+        assert!(prompt.is_open());
+
+        assert_eq!(
+            prompt.answer(true),
+            Some((app(), PermissionKind::Autostart))
+        );
+        assert_eq!(rx2.try_recv(), Ok(true));
+        assert!(!prompt.is_open());
+    }
+
+    #[test]
+    fn cancelled_requests_go_away() {
+        let mut prompt = PermissionPrompt::new(Default::default());
+        let (tx1, rx1) = async_channel::unbounded();
+        let (tx2, rx2) = async_channel::unbounded();
+        let (tx3, rx3) = async_channel::unbounded();
+
+        let app = || String::from("org.example.App");
+        let handle = |n: u32| Some(format!("/org/freedesktop/portal/desktop/request/1/{n}"));
+        prompt.ask(app(), None, PermissionKind::Background, handle(1), tx1);
+        prompt.ask(app(), None, PermissionKind::Background, handle(2), tx2);
+        prompt.ask(app(), None, PermissionKind::Autostart, None, tx3);
+
+        // The request stays while someone still waits for it.
+        assert!(prompt.cancel(handle(1).as_deref().unwrap()));
+        assert!(rx1.is_closed());
+        assert!(!prompt.cancel(handle(1).as_deref().unwrap()));
+
+        assert!(prompt.cancel(handle(2).as_deref().unwrap()));
+        assert!(rx2.is_closed());
+
+        assert_eq!(
+            prompt.answer(true),
+            Some((app(), PermissionKind::Autostart))
+        );
+        assert_eq!(rx3.try_recv(), Ok(true));
+        assert!(!prompt.is_open());
+    }
+}
@@ -0,0 +1,167 @@
+//! Keyboard resize mode.
+//!
+//! While the mode is active, arrow keys resize the focused window within its parent container,
+//! and a readout with the window size and its share of the container is drawn on top of it.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use niri_config::{Color, Config};
+use ordered_float::NotNan;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::output::Output;
+use smithay::utils::{Logical, Size};
+
+use crate::layout::{Layout, LayoutElement as _};
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::text::{render_text_box, ui_font};
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::window::Mapped;
+
+const PADDING: i32 = 8;
+const BACKGROUND: Color = Color::new_unpremul(0., 0., 0., 0.75);
+
+/// Share of the parent container changed by one arrow key press, in percent.
+pub const STEP: f64 = 5.;
+
+/// Share of the parent container changed by one arrow key press with Shift held, in percent.
+pub const FINE_STEP: f64 = 1.;
+
+pub struct ResizeMode {
+    is_active: bool,
+    /// Whether the last resize changed the width rather than the height.
+    horizontal: bool,
+    /// Text of the rendered readout.
+    text: RefCell<String>,
+    /// Rendered readout, by output scale.
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+    config: Rc<RefCell<Config>>,
+}
+
+impl ResizeMode {
+    pub fn new(config: Rc<RefCell<Config>>) -> Self {
+        Self {
+            is_active: false,
+            horizontal: true,
+            text: RefCell::new(String::new()),
+            buffers: RefCell::new(HashMap::new()),
+            config,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    pub fn start(&mut self) {
+        self.is_active = true;
+        self.horizontal = true;
+    }
+
+    /// Returns `true` if the mode was active.
+    pub fn stop(&mut self) -> bool {
+        if !self.is_active {
+            return false;
+        }
+
+        self.is_active = false;
+        self.text.borrow_mut().clear();
+        self.buffers.borrow_mut().clear();
+        true
+    }
+
+    /// Remembers which dimension was resized last, to show the matching share.
+    pub fn set_horizontal(&mut self, horizontal: bool) {
+        self.horizontal = horizontal;
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        layout: &Layout<Mapped>,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if !self.is_active {
+            return None;
+        }
+
+        let (focus, focus_output) = layout.focus_with_output()?;
+        if focus_output != output {
+            return None;
+        }
+
+        let mon = layout.monitor_for_output(output)?;
+        let (_, rect) = mon
+            .visible_windows_with_render_rects()
+            .into_iter()
+            .find(|(mapped, _)| mapped.id() == focus.id())?;
+
+        let (width_share, height_share) = layout.focused_window_shares();
+        let share = if self.horizontal {
+            width_share.or(height_share)
+        } else {
+            height_share.or(width_share)
+        };
+        let text = readout_text(focus.size(), share);
+
+        let scale = output.current_scale().fractional_scale();
+
+        let mut buffers = self.buffers.borrow_mut();
+        let mut cached_text = self.text.borrow_mut();
+        if *cached_text != text {
+            buffers.clear();
+            *cached_text = text;
+        }
+
+        let buffer = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| {
+                let font = ui_font(self.config.borrow().ui_font.as_deref(), scale);
+                let renderer = renderer.as_gles_renderer();
+                render_text_box(renderer, &cached_text, &font, BACKGROUND, PADDING, scale)
+                    .inspect_err(|err| warn!("error rendering resize readout: {err:?}"))
+                    .ok()
+            })
+            .clone()?;
+
+        // Center the readout on the window.
+        let size = buffer.logical_size();
+        let location = rect.loc + (rect.size.to_point() - size.to_point()).downscale(2.);
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn readout_text(size: Size<i32, Logical>, share: Option<f64>) -> String {
+    let mut text = format!("{}x{}", size.w, size.h);
+    if let Some(share) = share {
+        text += &format!(" / {:.0}%", share * 100.);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readout_shows_size_and_share() {
+        assert_eq!(
+            readout_text(Size::from((1200, 800)), Some(0.42)),
+            "1200x800 / 42%"
+        );
+        assert_eq!(readout_text(Size::from((640, 480)), None), "640x480");
+    }
+}
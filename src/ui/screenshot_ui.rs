@@ -26,6 +26,7 @@ use crate::layout::floating::DIRECTIONAL_MOVE_PX;
 use crate::niri_render_elements;
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::text::{create_layout, ui_font};
 use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
 use crate::render_helpers::{render_to_texture, RenderTarget};
 use crate::utils::to_physical_precise_round;
@@ -34,7 +35,6 @@ const SELECTION_BORDER: i32 = 2;
 
 const PADDING: i32 = 8;
 const RADIUS: i32 = 16;
-const FONT: &str = "sans 14px";
 const BORDER: i32 = 4;
 const TEXT_HIDE_P: &str =
     "Press <span face='mono' bgcolor='#2C2C2C'> Space </span> to save the screenshot.\n\
@@ -202,8 +202,9 @@ impl ScreenshotUi {
                 ];
                 let locations = [Default::default(); 8];
 
+                let font = ui_font(config.borrow().ui_font.as_deref(), scale);
                 let mut render_panel_ = |text| {
-                    render_panel(renderer, scale, text)
+                    render_panel(renderer, scale, &font, text)
                         .map_err(|err| warn!("error rendering help panel: {err:?}"))
                         .ok()
                 };
@@ -1109,6 +1110,7 @@ fn is_within_capture_button(
 fn render_panel(
     renderer: &mut GlesRenderer,
     scale: f64,
+    font: &FontDescription,
     text: &str,
 ) -> anyhow::Result<TextureBuffer<GlesTexture>> {
     let _span = tracy_client::span!("screenshot_ui::render_panel");
@@ -1123,14 +1125,9 @@ fn render_panel(
     // Add 2 px of spacing to separate the backgrounds of the "Space" and "P" keys.
     let spacing = to_physical_precise_round::<i32>(scale, 2) * 1024;
 
-    let mut font = FontDescription::from_string(FONT);
-    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
-
     let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
     let cr = cairo::Context::new(&surface)?;
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, font);
     layout.set_alignment(Alignment::Left);
     layout.set_markup(text);
     layout.set_spacing(spacing);
@@ -1169,9 +1166,7 @@ fn render_panel(
 
     cr.move_to(padding + r * 2. + padding - half_border_width, padding);
 
-    let layout = pangocairo::functions::create_layout(&cr);
-    layout.context().set_round_glyph_positions(false);
-    layout.set_font_description(Some(&font));
+    let layout = create_layout(&cr, font);
     layout.set_alignment(Alignment::Left);
     layout.set_markup(text);
     layout.set_spacing(spacing);
@@ -0,0 +1,272 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use niri_config::Config;
+use smithay::backend::renderer::element::utils::{
+    Relocate, RelocateRenderElement, RescaleRenderElement,
+};
+use smithay::backend::renderer::element::Kind;
+use smithay::output::Output;
+use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
+
+use crate::layout::tile::clip_layout_element;
+use crate::layout::{Layout, LayoutElement as _, LayoutElementRenderElement};
+use crate::niri_render_elements;
+use crate::render_helpers::border::BorderRenderElement;
+use crate::render_helpers::clipped_surface::ClippedSurfaceRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::render_helpers::RenderTarget;
+use crate::utils::{output_size, round_logical_in_physical};
+use crate::window::mapped::MappedId;
+use crate::window::Mapped;
+
+/// Largest preview side length.
+const MAX_SIZE: f64 = 240.;
+
+/// Border width around the preview.
+const BORDER: f64 = 2.;
+
+/// Gap between the pointer and the preview.
+const POINTER_GAP: f64 = 16.;
+
+niri_render_elements! {
+    TabPreviewThumbnailRenderElement<R> => {
+        LayoutElement = LayoutElementRenderElement<R>,
+        ClippedSurface = ClippedSurfaceRenderElement<R>,
+        Border = BorderRenderElement,
+    }
+}
+
+niri_render_elements! {
+    TabPreviewRenderElement<R> => {
+        SolidColor = SolidColorRenderElement,
+        Thumbnail = RelocateRenderElement<RescaleRenderElement<
+            TabPreviewThumbnailRenderElement<R>
+        >>,
+    }
+}
+
+/// Live preview of a window whose tab is under the pointer.
+///
+/// The preview shows up after the tab has been hovered for `hover-preview-delay-ms`. It renders
+/// the window contents without activating the window.
+pub struct TabPreview {
+    hover: Option<Hover>,
+    border: RefCell<SolidColorBuffer>,
+    config: Rc<RefCell<Config>>,
+}
+
+#[derive(Debug)]
+struct Hover {
+    id: MappedId,
+    output: Output,
+    /// Pointer position within the output when the hover started.
+    pos: Point<f64, Logical>,
+    /// Whether the delay has passed.
+    shown: bool,
+}
+
+impl TabPreview {
+    pub fn new(config: Rc<RefCell<Config>>) -> Self {
+        Self {
+            hover: None,
+            border: RefCell::new(SolidColorBuffer::default()),
+            config,
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.hover.as_ref().is_some_and(|hover| hover.shown)
+    }
+
+    /// Sets the window whose tab is hovered.
+    ///
+    /// Returns `true` if the hovered tab changed, in which case the preview is hidden until
+    /// [`Self::show()`].
+    pub fn set_hovered(
+        &mut self,
+        hovered: Option<(MappedId, Output, Point<f64, Logical>)>,
+    ) -> bool {
+        let unchanged = match (&self.hover, &hovered) {
+            (Some(hover), Some((id, output, _))) => hover.id == *id && hover.output == *output,
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return false;
+        }
+
+        self.hover = hovered.map(|(id, output, pos)| Hover {
+            id,
+            output,
+            pos,
+            shown: false,
+        });
+        true
+    }
+
+    /// Shows the preview for the hovered tab, if any.
+    ///
+    /// Returns `true` if the preview wasn't shown before.
+    pub fn show(&mut self) -> bool {
+        match &mut self.hover {
+            Some(hover) if !hover.shown => {
+                hover.shown = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        layout: &Layout<Mapped>,
+        renderer: &mut R,
+        output: &Output,
+        target: RenderTarget,
+        push: &mut dyn FnMut(TabPreviewRenderElement<R>),
+    ) {
+        let Some(hover) = &self.hover else {
+            return;
+        };
+        if !hover.shown || hover.output != *output {
+            return;
+        }
+
+        let Some((_, mapped)) = layout.windows().find(|(_, m)| m.id() == hover.id) else {
+            return;
+        };
+
+        let _span = tracy_client::span!("TabPreview::render");
+
+        let scale = output.current_scale().fractional_scale();
+        let s = Scale::from(scale);
+        let round = move |logical: f64| round_logical_in_physical(scale, logical);
+        let border = round(BORDER);
+
+        let geo = Rectangle::from_size(mapped.size().to_f64());
+        if geo.size.w <= 0. || geo.size.h <= 0. {
+            return;
+        }
+
+        let preview_geo = preview_geo(geo.size, hover.pos, output_size(output), border, scale);
+
+        let thumb_scale = Scale {
+            x: preview_geo.size.w / geo.size.w,
+            y: preview_geo.size.h / geo.size.h,
+        };
+        let radius = if mapped.sizing_mode().is_normal() {
+            mapped.rules().geometry_corner_radius
+        } else {
+            None
+        }
+        .unwrap_or_default();
+
+        let has_border_shader = BorderRenderElement::has_shader(renderer);
+        let clip_shader = ClippedSurfaceRenderElement::shader(renderer).cloned();
+
+        mapped.render_normal(renderer, Point::new(0., 0.), s, 1., target, &mut |elem| {
+            let elem =
+                clip_layout_element(elem, s, geo, radius, true, &clip_shader, has_border_shader);
+            let elem = RescaleRenderElement::from_element(elem, Point::new(0, 0), thumb_scale);
+            let elem = RelocateRenderElement::from_element(
+                elem,
+                preview_geo.loc.to_physical_precise_round(scale),
+                Relocate::Relative,
+            );
+            push(TabPreviewRenderElement::Thumbnail(elem));
+        });
+
+        // The border goes behind the preview, so make it bigger on all sides.
+        let color = self.config.borrow().layout.tab_bar.active_border;
+        let mut buffer = self.border.borrow_mut();
+        let border_size = preview_geo.size + Size::from((border, border)).upscale(2.);
+        buffer.update(border_size, color);
+        push(TabPreviewRenderElement::SolidColor(
+            SolidColorRenderElement::from_buffer(
+                &buffer,
+                preview_geo.loc - Point::new(border, border),
+                1.,
+                Kind::Unspecified,
+            ),
+        ));
+    }
+}
+
+/// Computes where the preview goes on the output.
+///
+/// The preview goes below the pointer, or above it when there's no room below, and stays within
+/// the output horizontally.
+fn preview_geo(
+    window_size: Size<f64, Logical>,
+    pointer: Point<f64, Logical>,
+    output_size: Size<f64, Logical>,
+    border: f64,
+    scale: f64,
+) -> Rectangle<f64, Logical> {
+    let thumb_scale = f64::min(1., MAX_SIZE / f64::max(window_size.w, window_size.h));
+    let size = window_size
+        .upscale(thumb_scale)
+        .to_physical_precise_round(scale)
+        .to_logical(scale);
+
+    let max_x = f64::max(border, output_size.w - size.w - border);
+    let x = (pointer.x - size.w / 2.).clamp(border, max_x);
+
+    let mut y = pointer.y + POINTER_GAP;
+    if y + size.h + border > output_size.h {
+        y = f64::max(border, pointer.y - POINTER_GAP - size.h);
+    }
+
+    let loc = Point::new(x, y)
+        .to_physical_precise_round(scale)
+        .to_logical(scale);
+    Rectangle::new(loc, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preview_geo_stays_on_output() {
+        let output = Size::from((1920., 1080.));
+
+        // Scaled down to fit, centered below the pointer.
+        let geo = preview_geo(
+            Size::from((960., 480.)),
+            Point::new(500., 100.),
+            output,
+            2.,
+            1.,
+        );
+        assert_eq!(
+            geo,
+            Rectangle::new(Point::new(380., 116.), Size::from((240., 120.)))
+        );
+
+        // Small windows aren't scaled up.
+        let geo = preview_geo(
+            Size::from((100., 50.)),
+            Point::new(500., 100.),
+            output,
+            2.,
+            1.,
+        );
+        assert_eq!(geo.size, Size::from((100., 50.)));
+
+        // Moved left from the right edge, and above the pointer near the bottom.
+        let geo = preview_geo(
+            Size::from((960., 480.)),
+            Point::new(1910., 1000.),
+            output,
+            2.,
+            1.,
+        );
+        assert_eq!(
+            geo,
+            Rectangle::new(Point::new(1678., 864.), Size::from((240., 120.)))
+        );
+    }
+}
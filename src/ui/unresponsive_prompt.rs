@@ -0,0 +1,209 @@
+//! Prompt over windows whose client stopped answering pings.
+//!
+//! The prompt covers the whole window: it dims the contents and shows a box in the middle with a
+//! Wait button and a Force close button.
+
+use std::cell::RefCell;
+
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::Alignment;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Logical, Physical, Point, Rectangle, Size, Transform};
+
+use crate::render_helpers::text::{create_layout, ui_font};
+use crate::render_helpers::texture::TextureBuffer;
+use crate::utils::to_physical_precise_round;
+
+const TEXT: &str = "Application not responding";
+const PADDING: i32 = 16;
+const BUTTON_PADDING: i32 = 8;
+const BUTTON_SPACING: i32 = 12;
+const DIM_COLOR: [f64; 4] = [0., 0., 0., 0.5];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptButton {
+    Wait,
+    ForceClose,
+}
+
+impl PromptButton {
+    const ALL: [Self; 2] = [Self::Wait, Self::ForceClose];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Wait => "Wait",
+            Self::ForceClose => "Force close",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UnresponsivePrompt {
+    rendered: RefCell<Option<Rendered>>,
+}
+
+#[derive(Debug)]
+struct Rendered {
+    size: Size<i32, Logical>,
+    scale: f64,
+    font: Option<String>,
+    buffer: Option<TextureBuffer<GlesTexture>>,
+    /// Button areas within the prompt.
+    buttons: Vec<(PromptButton, Rectangle<f64, Logical>)>,
+}
+
+impl UnresponsivePrompt {
+    /// Returns the prompt texture covering a window of the given size.
+    pub fn render(
+        &self,
+        renderer: &mut GlesRenderer,
+        size: Size<i32, Logical>,
+        scale: f64,
+        font: Option<&str>,
+    ) -> Option<TextureBuffer<GlesTexture>> {
+        let mut rendered = self.rendered.borrow_mut();
+        let unchanged = rendered
+            .as_ref()
+            .is_some_and(|r| r.size == size && r.scale == scale && r.font.as_deref() == font);
+        if !unchanged {
+            let (buffer, buttons) = match render(renderer, size, scale, font) {
+                Ok((buffer, buttons)) => (Some(buffer), buttons),
+                Err(err) => {
+                    warn!("error rendering unresponsive window prompt: {err:?}");
+                    (None, Vec::new())
+                }
+            };
+            *rendered = Some(Rendered {
+                size,
+                scale,
+                font: font.map(String::from),
+                buffer,
+                buttons,
+            });
+        }
+
+        rendered.as_ref()?.buffer.clone()
+    }
+
+    /// Returns the button under the point, relative to the window geometry.
+    pub fn button_at(&self, point: Point<f64, Logical>) -> Option<PromptButton> {
+        let rendered = self.rendered.borrow();
+        let (button, _) = rendered
+            .as_ref()?
+            .buttons
+            .iter()
+            .find(|(_, rect)| rect.contains(point))?;
+        Some(*button)
+    }
+
+    /// Frees the texture.
+    pub fn clear(&self) {
+        self.rendered.borrow_mut().take();
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    size: Size<i32, Logical>,
+    scale: f64,
+    font: Option<&str>,
+) -> anyhow::Result<(
+    TextureBuffer<GlesTexture>,
+    Vec<(PromptButton, Rectangle<f64, Logical>)>,
+)> {
+    let _span = tracy_client::span!("unresponsive_prompt::render");
+
+    let width: i32 = to_physical_precise_round(scale, size.w);
+    let height: i32 = to_physical_precise_round(scale, size.h);
+    anyhow::ensure!(width > 0 && height > 0, "window has zero size");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let button_padding: i32 = to_physical_precise_round(scale, BUTTON_PADDING);
+    let button_spacing: i32 = to_physical_precise_round(scale, BUTTON_SPACING);
+    let font = ui_font(font, scale);
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    let [r, g, b, a] = DIM_COLOR;
+    cr.set_source_rgba(r, g, b, a);
+    cr.paint()?;
+
+    let text = create_layout(&cr, &font);
+    text.set_alignment(Alignment::Center);
+    text.set_text(TEXT);
+    let (text_w, text_h) = text.pixel_size();
+
+    let labels = PromptButton::ALL.map(|button| {
+        let layout = create_layout(&cr, &font);
+        layout.set_text(button.label());
+        layout
+    });
+    let label_sizes = labels.each_ref().map(|layout| layout.pixel_size());
+    let button_h = label_sizes.iter().map(|(_, h)| *h).max().unwrap_or(0) + button_padding * 2;
+    let buttons_w = label_sizes
+        .iter()
+        .map(|(w, _)| w + button_padding * 2)
+        .sum::<i32>()
+        + button_spacing;
+
+    let box_w = text_w.max(buttons_w) + padding * 2;
+    let box_h = text_h + padding + button_h + padding * 2;
+    let box_x = (width - box_w) / 2;
+    let box_y = (height - box_h) / 2;
+
+    cr.rectangle(box_x.into(), box_y.into(), box_w.into(), box_h.into());
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.fill()?;
+
+    cr.move_to(
+        f64::from(box_x + (box_w - text_w) / 2),
+        f64::from(box_y + padding),
+    );
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &text);
+
+    let mut buttons = Vec::new();
+    let mut x = box_x + (box_w - buttons_w) / 2;
+    let y = box_y + padding + text_h + padding;
+    for (button, layout) in PromptButton::ALL.into_iter().zip(&labels) {
+        let (label_w, label_h) = layout.pixel_size();
+        let w = label_w + button_padding * 2;
+
+        cr.rectangle(x.into(), y.into(), w.into(), button_h.into());
+        match button {
+            PromptButton::Wait => cr.set_source_rgb(0.3, 0.3, 0.3),
+            PromptButton::ForceClose => cr.set_source_rgb(0.7, 0.2, 0.2),
+        }
+        cr.fill()?;
+
+        cr.move_to(
+            f64::from(x + button_padding),
+            f64::from(y + (button_h - label_h) / 2),
+        );
+        cr.set_source_rgb(1., 1., 1.);
+        pangocairo::functions::show_layout(&cr, layout);
+
+        let rect = Rectangle::<i32, Physical>::new(Point::from((x, y)), Size::from((w, button_h)));
+        buttons.push((button, rect.to_f64().to_logical(scale)));
+
+        x += w + button_spacing;
+    }
+    drop(labels);
+    drop(text);
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok((buffer, buttons))
+}
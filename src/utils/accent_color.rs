@@ -0,0 +1,98 @@
+//! Runtime changes of the accent color.
+//!
+//! Setting the accent color through IPC doesn't snap the focus ring, borders and tabs to the new
+//! color; instead they fade over a short animation.
+
+use niri_config::Color;
+
+use crate::animation::{Animation, Clock, Curve};
+
+const DURATION_MS: u64 = 250;
+
+#[derive(Debug)]
+pub struct AccentTransition {
+    from: Color,
+    to: Color,
+    anim: Animation,
+    /// Color last returned from [`Self::advance()`].
+    applied: Color,
+}
+
+impl AccentTransition {
+    pub fn new(clock: Clock, from: Color, to: Color) -> Self {
+        let anim = Animation::ease(clock, 0., 1., 0., DURATION_MS, Curve::EaseOutCubic);
+        Self {
+            from,
+            to,
+            anim,
+            applied: from,
+        }
+    }
+
+    /// Returns the color at the current point of the transition.
+    pub fn current(&self) -> Color {
+        lerp(self.from, self.to, self.anim.clamped_value() as f32)
+    }
+
+    /// Returns the current color if it changed since the last call.
+    pub fn advance(&mut self) -> Option<Color> {
+        let current = self.current();
+        if current == self.applied {
+            return None;
+        }
+
+        self.applied = current;
+        Some(current)
+    }
+
+    pub fn to(&self) -> Color {
+        self.to
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.anim.is_done()
+    }
+}
+
+fn lerp(from: Color, to: Color, t: f32) -> Color {
+    let from = from.to_array_unpremul();
+    let to = to.to_array_unpremul();
+    Color::from_array_unpremul(std::array::from_fn(|i| from[i] + (to[i] - from[i]) * t))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn advance_reports_only_changes() {
+        let from = Color::new_unpremul(0., 0., 1., 1.);
+        let to = Color::new_unpremul(1., 0., 0., 1.);
+        let mut clock = Clock::with_time(Duration::ZERO);
+        let mut transition = AccentTransition::new(clock.clone(), from, to);
+
+        // The clock didn't move, so neither did the color.
+        assert_eq!(transition.advance(), None);
+
+        clock.set_unadjusted(Duration::from_millis(100));
+        let color = transition.advance().unwrap();
+        assert_ne!(color, from);
+        assert_eq!(transition.advance(), None);
+
+        clock.set_unadjusted(Duration::from_millis(DURATION_MS));
+        assert_eq!(transition.advance(), Some(to));
+        assert!(transition.is_done());
+        assert_eq!(transition.advance(), None);
+    }
+
+    #[test]
+    fn lerp_goes_between_the_colors() {
+        let from = Color::new_unpremul(0., 0., 1., 1.);
+        let to = Color::new_unpremul(1., 0., 0., 0.5);
+        assert_eq!(lerp(from, to, 0.), from);
+        assert_eq!(lerp(from, to, 1.), to);
+        assert_eq!(lerp(from, to, 0.5), Color::new_unpremul(0.5, 0., 0.5, 0.75));
+    }
+}
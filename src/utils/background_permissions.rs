@@ -0,0 +1,281 @@
+//! Decisions for the background portal.
+//!
+//! Sandboxed apps ask through xdg-desktop-portal to keep running without windows and to start on
+//! login. niri asks the user once per app and permission, and saves the answers to
+//! `$XDG_CONFIG_HOME/niri/background-permissions.json` so that later requests are answered right
+//! away.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context as _};
+use directories::{BaseDirs, ProjectDirs};
+use niri_ipc::BackgroundPermission;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionKind {
+    /// Keep running without any open windows.
+    Background,
+    /// Start automatically on login.
+    Autostart,
+}
+
+#[derive(Debug, Default)]
+pub struct BackgroundPermissions {
+    apps: BTreeMap<String, Decisions>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Decisions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    background: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    autostart: Option<bool>,
+}
+
+impl Decisions {
+    fn get_mut(&mut self, kind: PermissionKind) -> &mut Option<bool> {
+        match kind {
+            PermissionKind::Background => &mut self.background,
+            PermissionKind::Autostart => &mut self.autostart,
+        }
+    }
+}
+
+impl BackgroundPermissions {
+    /// Loads the saved decisions, starting empty if there are none.
+    pub fn load() -> Self {
+        let Some(path) = permissions_path() else {
+            return Self::default();
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    warn!("error reading {path:?}: {err:?}");
+                }
+                return Self::default();
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(apps) => Self { apps },
+            Err(err) => {
+                warn!("error parsing {path:?}: {err:?}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Returns the saved decision, if the user was already asked.
+    pub fn get(&self, app_id: &str, kind: PermissionKind) -> Option<bool> {
+        let mut decisions = *self.apps.get(app_id)?;
+        *decisions.get_mut(kind)
+    }
+
+    /// Saves a decision.
+    pub fn set(&mut self, app_id: &str, kind: PermissionKind, allowed: bool) {
+        let decisions = self.apps.entry(app_id.to_owned()).or_default();
+        *decisions.get_mut(kind) = Some(allowed);
+
+        if let Err(err) = self.save() {
+            warn!("error saving background permissions: {err:?}");
+        }
+    }
+
+    pub fn to_ipc(&self) -> Vec<BackgroundPermission> {
+        self.apps
+            .iter()
+            .map(|(app_id, decisions)| BackgroundPermission {
+                app_id: app_id.clone(),
+                background: decisions.background,
+                autostart: decisions.autostart,
+            })
+            .collect()
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let path = permissions_path().context("error retrieving the config directory")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("error creating the config directory")?;
+        }
+
+        let contents = serde_json::to_string_pretty(&self.apps)?;
+        fs::write(&path, contents).with_context(|| format!("error writing {path:?}"))?;
+        Ok(())
+    }
+}
+
+fn permissions_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "niri")?;
+    let mut path = dirs.config_dir().to_owned();
+    path.push("background-permissions.json");
+    Some(path)
+}
+
+/// Adds an autostart entry for the app, or removes it with `commandline` set to `None`.
+pub fn set_autostart(
+    app_id: &str,
+    commandline: Option<&[String]>,
+    dbus_activatable: bool,
+) -> anyhow::Result<()> {
+    // The app ID ends up in a file name, so only allow what D-Bus names allow.
+    ensure!(is_valid_app_id(app_id), "invalid app ID: {app_id:?}");
+
+    let dirs = BaseDirs::new().context("error retrieving the home directory")?;
+    let mut path = dirs.config_dir().to_owned();
+    path.push("autostart");
+    path.push(format!("{app_id}.desktop"));
+
+    let Some(commandline) = commandline else {
+        return match fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(err).with_context(|| format!("error removing {path:?}"))
+            }
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("error creating the autostart directory")?;
+    }
+
+    let contents = desktop_entry(app_id, commandline, dbus_activatable)?;
+    fs::write(&path, contents).with_context(|| format!("error writing {path:?}"))?;
+    Ok(())
+}
+
+fn is_valid_app_id(app_id: &str) -> bool {
+    !app_id.is_empty()
+        && !app_id.starts_with('.')
+        && app_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn desktop_entry(
+    app_id: &str,
+    commandline: &[String],
+    dbus_activatable: bool,
+) -> anyhow::Result<String> {
+    // Desktop entry values can't contain control characters, not even escaped.
+    ensure!(
+        !commandline.iter().any(|arg| arg.contains(char::is_control)),
+        "command line contains control characters"
+    );
+
+    let exec = commandline
+        .iter()
+        .map(|arg| quote_exec_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    // Exec is a string key, so it goes through the general string escaping on top of quoting.
+    let exec = exec.replace('\\', "\\\\");
+
+    let mut entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={app_id}\n\
+         Exec={exec}\n\
+         X-Flatpak={app_id}\n"
+    );
+    if dbus_activatable {
+        entry.push_str("DBusActivatable=true\n");
+    }
+    Ok(entry)
+}
+
+/// Characters that need quoting in the `Exec` key of a desktop entry.
+const RESERVED_CHARS: &[char] = &[
+    '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`',
+];
+
+/// Quotes an argument for the `Exec` key of a desktop entry.
+fn quote_exec_arg(arg: &str) -> String {
+    // A literal percent sign would start a field code otherwise.
+    let arg = &arg.replace('%', "%%");
+
+    let needs_quoting = arg.is_empty()
+        || arg
+            .chars()
+            .any(|c| c.is_whitespace() || RESERVED_CHARS.contains(&c));
+    if !needs_quoting {
+        return arg.to_owned();
+    }
+
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn desktop_entry_quotes_arguments() {
+        let commandline = [
+            String::from("flatpak"),
+            String::from("run"),
+            String::from("--command=app"),
+            String::from("org.example.App"),
+            String::from("--title=My \"App\""),
+            String::from("C:\\dir"),
+            String::from("100%"),
+        ];
+        assert_eq!(
+            desktop_entry("org.example.App", &commandline, true).unwrap(),
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=org.example.App\n\
+             Exec=flatpak run --command=app org.example.App \"--title=My \\\\\"App\\\\\"\" \
+             \"C:\\\\\\\\dir\" 100%%\n\
+             X-Flatpak=org.example.App\n\
+             DBusActivatable=true\n"
+        );
+    }
+
+    #[test]
+    fn desktop_entry_rejects_control_characters() {
+        let commandline = [String::from("app"), String::from("--title=a\nExec=evil")];
+        assert!(desktop_entry("org.example.App", &commandline, false).is_err());
+
+        let commandline = [String::from("app\r")];
+        assert!(desktop_entry("org.example.App", &commandline, false).is_err());
+    }
+
+    #[test]
+    fn app_id_validation() {
+        assert!(is_valid_app_id("org.example.App"));
+        assert!(is_valid_app_id("org.example.my-app_2"));
+        assert!(!is_valid_app_id(""));
+        assert!(!is_valid_app_id("."));
+        assert!(!is_valid_app_id(".."));
+        assert!(!is_valid_app_id("../../.bashrc"));
+        assert!(!is_valid_app_id("org/example"));
+        assert!(!is_valid_app_id("org.example\nApp"));
+    }
+
+    #[test]
+    fn decisions_are_kept_per_permission() {
+        let mut permissions = BackgroundPermissions::default();
+        let decisions = permissions.apps.entry(String::from("app")).or_default();
+        *decisions.get_mut(PermissionKind::Autostart) = Some(false);
+
+        assert_eq!(
+            permissions.get("app", PermissionKind::Autostart),
+            Some(false)
+        );
+        assert_eq!(permissions.get("app", PermissionKind::Background), None);
+        assert_eq!(permissions.get("other", PermissionKind::Autostart), None);
+    }
+}
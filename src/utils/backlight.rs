@@ -0,0 +1,225 @@
+//! Backlight control for the `brightness-*` actions.
+//!
+//! The brightness is set through logind, which lets the session owner change it without extra
+//! permissions, or by writing to sysfs directly when logind is unavailable.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+
+const SYSFS_BACKLIGHT: &str = "/sys/class/backlight";
+
+/// Step of `brightness-up` and `brightness-down` without an argument, in percent.
+pub const DEFAULT_STEP: u8 = 5;
+
+/// Brightness that stepping down stops at, in percent, to keep the screen readable.
+const MIN_PERCENT: u8 = 1;
+
+/// How long a brightness that niri set takes precedence over what sysfs reports.
+///
+/// Setting through logind happens on a thread, so reading back right away can give the old value.
+const PENDING_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrightnessChange {
+    Up(u8),
+    Down(u8),
+    Set(u8),
+}
+
+#[derive(Debug)]
+pub struct Backlight {
+    /// Name of the device in sysfs, e.g. `intel_backlight`.
+    name: String,
+    path: PathBuf,
+    max: u32,
+    /// Brightness that niri set last, and when.
+    pending: Option<(u32, Duration)>,
+}
+
+impl Backlight {
+    /// Finds the backlight device of the built-in display.
+    ///
+    /// Like systemd, prefers firmware interfaces over platform ones, and those over raw ones.
+    pub fn find() -> Option<Self> {
+        let mut best: Option<(u8, PathBuf)> = None;
+        for entry in fs::read_dir(SYSFS_BACKLIGHT).ok()?.flatten() {
+            let path = entry.path();
+            let kind = fs::read_to_string(path.join("type")).unwrap_or_default();
+            let rank = match kind.trim() {
+                "firmware" => 0,
+                "platform" => 1,
+                _ => 2,
+            };
+            if best.as_ref().is_none_or(|(best_rank, _)| rank < *best_rank) {
+                best = Some((rank, path));
+            }
+        }
+
+        let (_, path) = best?;
+        match Self::open(&path) {
+            Ok(backlight) => {
+                debug!("using backlight {}", backlight.name);
+                Some(backlight)
+            }
+            Err(err) => {
+                warn!("error opening backlight {path:?}: {err:?}");
+                None
+            }
+        }
+    }
+
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("invalid device name")?
+            .to_owned();
+        let max = read_value(&path.join("max_brightness"))?;
+        anyhow::ensure!(max > 0, "max_brightness is 0");
+
+        Ok(Self {
+            name,
+            path: path.to_owned(),
+            max,
+            pending: None,
+        })
+    }
+
+    /// Changes the brightness and returns the new brightness in percent.
+    pub fn change(&mut self, change: BrightnessChange, now: Duration) -> anyhow::Result<u8> {
+        let current = match self.pending {
+            Some((value, at)) if now.saturating_sub(at) < PENDING_TIMEOUT => value,
+            _ => read_value(&self.path.join("brightness"))?,
+        };
+
+        let value = changed_value(current, self.max, change);
+        if value != current {
+            self.set(value);
+        }
+        self.pending = Some((value, now));
+
+        Ok(to_percent(value, self.max))
+    }
+
+    fn set(&self, value: u32) {
+        let name = self.name.clone();
+        let path = self.path.join("brightness");
+
+        let res = std::thread::Builder::new()
+            .name("Backlight Setter".to_owned())
+            .spawn(move || {
+                let _span = tracy_client::span!("set backlight");
+
+                #[cfg(feature = "dbus")]
+                match set_with_logind(&name, value) {
+                    Ok(()) => return,
+                    Err(err) => debug!("error setting brightness through logind: {err:?}"),
+                }
+
+                if let Err(err) = fs::write(&path, value.to_string()) {
+                    warn!("error setting brightness of {name}: {err:?}");
+                }
+            });
+
+        if let Err(err) = res {
+            warn!("error spawning a thread to set brightness: {err:?}");
+        }
+    }
+}
+
+#[cfg(feature = "dbus")]
+fn set_with_logind(name: &str, value: u32) -> anyhow::Result<()> {
+    let conn = zbus::blocking::Connection::system().context("error connecting to system bus")?;
+
+    // The auto path refers to the session of the caller.
+    conn.call_method(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1/session/auto",
+        Some("org.freedesktop.login1.Session"),
+        "SetBrightness",
+        &("backlight", name, value),
+    )
+    .context("error calling SetBrightness")?;
+
+    Ok(())
+}
+
+fn read_value(path: &Path) -> anyhow::Result<u32> {
+    let value = fs::read_to_string(path).with_context(|| format!("error reading {path:?}"))?;
+    value
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid value in {path:?}"))
+}
+
+fn to_percent(value: u32, max: u32) -> u8 {
+    (f64::from(value) / f64::from(max) * 100.).round() as u8
+}
+
+fn from_percent(percent: u8, max: u32) -> u32 {
+    (f64::from(percent.min(100)) / 100. * f64::from(max)).round() as u32
+}
+
+/// Returns the raw brightness after the change.
+fn changed_value(current: u32, max: u32, change: BrightnessChange) -> u32 {
+    let min = from_percent(MIN_PERCENT, max).max(1);
+    let current = current.min(max);
+
+    match change {
+        // Snap to the steps, so that up and down go through the same values.
+        BrightnessChange::Up(step) => {
+            let step = u32::from(step.max(1));
+            let percent = u32::from(to_percent(current, max));
+            let percent = (percent / step + 1) * step;
+            from_percent(percent.min(100) as u8, max).max(current.saturating_add(1).min(max))
+        }
+        BrightnessChange::Down(step) => {
+            let step = u32::from(step.max(1));
+            let percent = u32::from(to_percent(current, max));
+            let percent = percent.div_ceil(step).saturating_sub(1) * step;
+            from_percent(percent as u8, max)
+                .min(current.saturating_sub(1))
+                .max(min)
+        }
+        BrightnessChange::Set(percent) => from_percent(percent, max).max(min),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_snap_to_multiples() {
+        assert_eq!(changed_value(100, 1000, BrightnessChange::Up(5)), 150);
+        assert_eq!(changed_value(120, 1000, BrightnessChange::Up(5)), 150);
+        assert_eq!(changed_value(120, 1000, BrightnessChange::Down(5)), 100);
+        assert_eq!(changed_value(150, 1000, BrightnessChange::Down(5)), 100);
+        assert_eq!(changed_value(1000, 1000, BrightnessChange::Up(5)), 1000);
+    }
+
+    #[test]
+    fn down_stops_above_black() {
+        assert_eq!(changed_value(50, 1000, BrightnessChange::Down(5)), 10);
+        assert_eq!(changed_value(10, 1000, BrightnessChange::Down(5)), 10);
+        assert_eq!(changed_value(1, 7, BrightnessChange::Down(5)), 1);
+        assert_eq!(changed_value(500, 1000, BrightnessChange::Set(0)), 10);
+    }
+
+    #[test]
+    fn small_ranges_still_change() {
+        // With few levels, a step may round to the same value, so move by at least one.
+        assert_eq!(changed_value(3, 7, BrightnessChange::Up(5)), 4);
+        assert_eq!(changed_value(3, 7, BrightnessChange::Down(5)), 2);
+    }
+
+    #[test]
+    fn set_takes_percent() {
+        assert_eq!(changed_value(0, 255, BrightnessChange::Set(50)), 128);
+        assert_eq!(changed_value(0, 255, BrightnessChange::Set(200)), 255);
+        assert_eq!(to_percent(128, 255), 50);
+    }
+}
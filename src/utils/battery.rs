@@ -0,0 +1,105 @@
+//! Battery state for the automatic power saving mode.
+//!
+//! This follows the same rules as systemd's `on_ac_power`: the system runs on battery if it has a
+//! battery and no other power supply is online. Batteries of peripherals like wireless mice don't
+//! count.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const SYSFS_POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// How often to check whether the system runs on battery.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+struct PowerSupply {
+    /// Type of the supply, e.g. `Battery` or `Mains`.
+    kind: String,
+    /// Whether the supply powers a peripheral rather than the system.
+    is_device_scope: bool,
+    online: bool,
+}
+
+/// Returns whether the system runs on battery.
+pub fn is_on_battery() -> bool {
+    let Ok(entries) = fs::read_dir(SYSFS_POWER_SUPPLY) else {
+        return false;
+    };
+
+    let supplies: Vec<_> = entries
+        .flatten()
+        .map(|entry| {
+            let path = entry.path();
+            PowerSupply {
+                kind: read_attr(&path, "type"),
+                is_device_scope: read_attr(&path, "scope") == "Device",
+                online: read_attr(&path, "online") == "1",
+            }
+        })
+        .collect();
+    on_battery(&supplies)
+}
+
+fn read_attr(path: &Path, name: &str) -> String {
+    let value = fs::read_to_string(path.join(name)).unwrap_or_default();
+    value.trim().to_owned()
+}
+
+fn on_battery(supplies: &[PowerSupply]) -> bool {
+    let mut has_battery = false;
+    for supply in supplies {
+        if supply.is_device_scope {
+            continue;
+        }
+
+        if supply.kind == "Battery" {
+            has_battery = true;
+        } else if supply.online {
+            return false;
+        }
+    }
+    has_battery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply(kind: &str, is_device_scope: bool, online: bool) -> PowerSupply {
+        PowerSupply {
+            kind: String::from(kind),
+            is_device_scope,
+            online,
+        }
+    }
+
+    #[test]
+    fn laptop_follows_the_charger() {
+        let unplugged = [
+            supply("Mains", false, false),
+            supply("Battery", false, false),
+        ];
+        assert!(on_battery(&unplugged));
+
+        let plugged = [
+            supply("Mains", false, true),
+            supply("Battery", false, false),
+        ];
+        assert!(!on_battery(&plugged));
+
+        let usb_c = [
+            supply("Mains", false, false),
+            supply("USB", false, true),
+            supply("Battery", false, false),
+        ];
+        assert!(!on_battery(&usb_c));
+    }
+
+    #[test]
+    fn peripheral_batteries_dont_count() {
+        assert!(!on_battery(&[]));
+        assert!(!on_battery(&[supply("Battery", true, false)]));
+    }
+}
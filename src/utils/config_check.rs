@@ -0,0 +1,315 @@
+//! Semantic checks for `niri validate`.
+//!
+//! Parsing already catches syntax errors and invalid values like window rule regexes that don't
+//! compile. These checks look for config that parses fine but can't do what it says: binds that
+//! never trigger, modes that nothing enters, and names of outputs and workspaces that don't exist.
+
+use std::collections::{HashSet, VecDeque};
+
+use niri_config::{Action, Bind, Config, ModKey, OutputName, WorkspaceReference};
+use niri_ipc::BindConflictKind;
+use serde::Serialize;
+
+use crate::input::bind_conflicts;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+    /// The output isn't connected.
+    UnknownOutput,
+    /// The bind can never trigger.
+    UnreachableBind,
+    /// A `mode` action switches to a mode that isn't defined.
+    UndefinedMode,
+    /// The workspace isn't declared in the config.
+    UndefinedWorkspace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+/// Checks the config, returning issues in the order they appear in each section.
+///
+/// Output names are only checked when `outputs` is set, since the connected outputs are only
+/// known while niri is running.
+pub fn check(config: &Config, outputs: Option<&[niri_ipc::Output]>) -> Vec<Issue> {
+    let mut rv = Vec::new();
+    if let Some(outputs) = outputs {
+        check_outputs(&mut rv, config, outputs);
+    }
+    check_binds(&mut rv, config);
+    check_modes(&mut rv, config);
+    check_workspaces(&mut rv, config);
+    rv
+}
+
+fn issue(rv: &mut Vec<Issue>, kind: IssueKind, message: String) {
+    rv.push(Issue { kind, message });
+}
+
+/// Returns the default mode binds followed by the binds of each mode, sorted by name.
+fn all_binds(config: &Config) -> Vec<(&str, &[Bind])> {
+    let mut modes: Vec<_> = config
+        .modes
+        .iter()
+        .map(|(name, binds)| (name.as_str(), &binds.0[..]))
+        .collect();
+    modes.sort_by_key(|(name, _)| *name);
+    modes.insert(0, ("default", &config.binds.0[..]));
+    modes
+}
+
+fn check_outputs(rv: &mut Vec<Issue>, config: &Config, outputs: &[niri_ipc::Output]) {
+    let connected: Vec<_> = outputs.iter().map(OutputName::from_ipc_output).collect();
+    let mut check = |name: &str, place: String| {
+        if !connected.iter().any(|output| output.matches(name)) {
+            let message = format!("{place} refers to output \"{name}\" which isn't connected");
+            issue(rv, IssueKind::UnknownOutput, message);
+        }
+    };
+
+    // Output profiles are meant for other sets of monitors, so they aren't checked.
+    for output in &config.outputs.0 {
+        check(&output.name, String::from("output section"));
+    }
+    for ws in &config.workspaces {
+        if let Some(name) = &ws.open_on_output {
+            check(name, format!("workspace \"{}\"", ws.name.0));
+        }
+    }
+    for (idx, rule) in config.window_rules.iter().enumerate() {
+        if let Some(name) = &rule.open_on_output {
+            check(name, format!("window rule {}", idx + 1));
+        }
+    }
+    for (mode, binds) in all_binds(config) {
+        for bind in binds {
+            let name = match &bind.action {
+                Action::FocusMonitor(name)
+                | Action::MoveWindowToMonitor(name)
+                | Action::MoveColumnToMonitor(name)
+                | Action::MoveWorkspaceToMonitor(name) => name,
+                _ => continue,
+            };
+            check(name, format!("bind {} (mode {mode})", bind.key));
+        }
+    }
+}
+
+fn check_binds(rv: &mut Vec<Issue>, config: &Config) {
+    let mod_key = config.input.mod_key.unwrap_or(ModKey::Super);
+    for conflict in bind_conflicts::find(config, mod_key) {
+        // Binds that the resize mode takes over still work outside of it.
+        let BindConflictKind::Duplicate { shadowed_by } = conflict.kind else {
+            continue;
+        };
+
+        let message = format!(
+            "bind {} (mode {}) never triggers, {shadowed_by} is bound to the same keys",
+            conflict.key, conflict.mode
+        );
+        issue(rv, IssueKind::UnreachableBind, message);
+    }
+}
+
+fn check_modes(rv: &mut Vec<Issue>, config: &Config) {
+    let is_defined = |mode: &str| mode == "default" || config.modes.contains_key(mode);
+
+    for (mode, binds) in all_binds(config) {
+        for bind in binds {
+            if let Action::Mode(target) = &bind.action {
+                if !is_defined(target) {
+                    let message = format!(
+                        "bind {} (mode {mode}) switches to mode \"{target}\" which isn't defined",
+                        bind.key
+                    );
+                    issue(rv, IssueKind::UndefinedMode, message);
+                }
+            }
+        }
+    }
+
+    // Follow the mode switches from the default mode to find the modes that nothing enters.
+    let mut entered = HashSet::from(["default"]);
+    let mut queue = VecDeque::from(["default"]);
+    while let Some(mode) = queue.pop_front() {
+        let binds = match mode {
+            "default" => &config.binds,
+            mode => &config.modes[mode],
+        };
+        for bind in &binds.0 {
+            if let Action::Mode(target) = &bind.action {
+                if is_defined(target) && entered.insert(target.as_str()) {
+                    queue.push_back(target.as_str());
+                }
+            }
+        }
+    }
+
+    for (mode, binds) in all_binds(config) {
+        if !entered.contains(mode) && !binds.is_empty() {
+            let message = format!("mode {mode} is never entered, so none of its binds trigger");
+            issue(rv, IssueKind::UnreachableBind, message);
+        }
+    }
+}
+
+fn check_workspaces(rv: &mut Vec<Issue>, config: &Config) {
+    // Binds can name workspaces at runtime, so those names count as defined too.
+    let mut defined: Vec<&str> = config.workspaces.iter().map(|ws| &ws.name.0[..]).collect();
+    for profile in &config.output_profiles {
+        defined.extend(profile.workspaces.iter().map(|ws| &ws.name[..]));
+    }
+    for (_, binds) in all_binds(config) {
+        for bind in binds {
+            if let Action::SetWorkspaceName(name) = &bind.action {
+                defined.push(name);
+            }
+        }
+    }
+
+    let mut check = |name: &str, place: String| {
+        if !defined.iter().any(|ws| ws.eq_ignore_ascii_case(name)) {
+            let message = format!("{place} refers to workspace \"{name}\" which isn't defined");
+            issue(rv, IssueKind::UndefinedWorkspace, message);
+        }
+    };
+
+    for (idx, rule) in config.window_rules.iter().enumerate() {
+        if let Some(name) = &rule.open_on_workspace {
+            check(name, format!("window rule {}", idx + 1));
+        }
+    }
+    for (mode, binds) in all_binds(config) {
+        for bind in binds {
+            let name = match &bind.action {
+                Action::FocusWorkspace(WorkspaceReference::Name(name))
+                | Action::MoveWindowToWorkspace(WorkspaceReference::Name(name), _)
+                | Action::MoveColumnToWorkspace(WorkspaceReference::Name(name), _)
+                | Action::SpawnOnWorkspace(name, _) => name,
+                _ => continue,
+            };
+            check(name, format!("bind {} (mode {mode})", bind.key));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(text: &str) -> Vec<(IssueKind, String)> {
+        let config = Config::parse_mem(text).unwrap();
+        check(&config, None)
+            .into_iter()
+            .map(|issue| (issue.kind, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        let issues = issues(
+            r#"
+            workspace "web"
+
+            window-rule {
+                match app-id="firefox"
+                open-on-workspace "Web"
+            }
+
+            binds {
+                Mod+W { focus-workspace "web"; }
+                Mod+1 { focus-workspace 1; }
+                Mod+R { mode "resize"; }
+            }
+
+            mode "resize" {
+                Escape { mode "default"; }
+            }
+            "#,
+        );
+        assert_eq!(issues, []);
+    }
+
+    #[test]
+    fn modes_nothing_enters_are_unreachable() {
+        let issues = issues(
+            r#"
+            binds {
+                Mod+L { mode "launch"; }
+            }
+
+            mode "launch" {
+                F { spawn "firefox"; }
+                S { mode "system"; }
+                Escape { mode "default"; }
+            }
+
+            mode "system" {
+                Escape { mode "default"; }
+            }
+
+            mode "orphan" {
+                Escape { mode "default"; }
+                G { mode "gone"; }
+            }
+            "#,
+        );
+        assert_eq!(
+            issues,
+            [
+                (
+                    IssueKind::UndefinedMode,
+                    String::from(
+                        "bind g (mode orphan) switches to mode \"gone\" which isn't defined"
+                    ),
+                ),
+                (
+                    IssueKind::UnreachableBind,
+                    String::from("mode orphan is never entered, so none of its binds trigger"),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn workspace_names_must_be_defined() {
+        let issues = issues(
+            r#"
+            workspace "chat"
+
+            window-rule {
+                match app-id="thunderbird"
+                open-on-workspace "mail"
+            }
+
+            binds {
+                Mod+C { focus-workspace "chat"; }
+                Mod+M { move-column-to-workspace "music"; }
+                Mod+N { set-workspace-name "notes"; }
+                Mod+Shift+N { focus-workspace "notes"; }
+            }
+            "#,
+        );
+        assert_eq!(
+            issues,
+            [
+                (
+                    IssueKind::UndefinedWorkspace,
+                    String::from(
+                        "window rule 1 refers to workspace \"mail\" which isn't defined"
+                    ),
+                ),
+                (
+                    IssueKind::UndefinedWorkspace,
+                    String::from(
+                        "bind Mod+m (mode default) refers to workspace \"music\" which isn't defined"
+                    ),
+                ),
+            ]
+        );
+    }
+}
@@ -13,7 +13,7 @@ use anyhow::{ensure, Context};
 use bitflags::bitflags;
 use directories::UserDirs;
 use git_version::git_version;
-use niri_config::{Config, OutputName};
+use niri_config::{Config, OutputName, SafeArea};
 use smithay::backend::renderer::utils::with_renderer_surface_state;
 use smithay::input::pointer::CursorIcon;
 use smithay::output::{self, Output};
@@ -34,13 +34,26 @@ use wayland_backend::server::Credentials;
 use crate::handlers::KdeDecorationsModeState;
 use crate::niri::ClientState;
 
+pub mod accent_color;
+pub mod background_permissions;
+pub mod backlight;
+pub mod battery;
+pub mod config_check;
 pub mod id;
+pub mod night_light;
+pub mod pings;
+pub mod recent_logs;
 pub mod scale;
+pub mod session_snapshot;
 pub mod signals;
 pub mod spawning;
+pub mod title_format;
 pub mod transaction;
 pub mod vblank_throttle;
+pub mod video_cadence;
+pub mod watchdog;
 pub mod watcher;
+pub mod workspace_export;
 pub mod xwayland;
 
 pub static IS_SYSTEMD_SERVICE: AtomicBool = AtomicBool::new(false);
@@ -200,6 +213,31 @@ pub fn output_size(output: &Output) -> Size<f64, Logical> {
     output_transform.transform_size(logical_size)
 }
 
+/// Shrinks the area by the safe-area insets.
+pub fn inset_by_safe_area(
+    area: Rectangle<f64, Logical>,
+    safe_area: SafeArea,
+) -> Rectangle<f64, Logical> {
+    let mut area = area;
+    area.loc.x += safe_area.left.0;
+    area.loc.y += safe_area.top.0;
+    area.size.w = f64::max(0., area.size.w - safe_area.left.0 - safe_area.right.0);
+    area.size.h = f64::max(0., area.size.h - safe_area.top.0 - safe_area.bottom.0);
+    area
+}
+
+/// Returns the part of the output not hidden by a notch or rounded corners.
+pub fn output_safe_area(config: &Config, output: &Output) -> Rectangle<f64, Logical> {
+    let name = output.user_data().get::<OutputName>().unwrap();
+    let safe_area = config
+        .outputs
+        .find(name)
+        .and_then(|c| c.layout_config())
+        .and_then(|layout| layout.safe_area)
+        .unwrap_or(config.layout.safe_area);
+    inset_by_safe_area(Rectangle::from_size(output_size(output)), safe_area)
+}
+
 pub fn logical_output(output: &Output) -> niri_ipc::LogicalOutput {
     let loc = output.current_location();
     let size = output_size(output);
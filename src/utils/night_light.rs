@@ -0,0 +1,230 @@
+//! Night light: warmer colors at night.
+//!
+//! The color temperature follows the `night-light` schedule, ramping up and down over the
+//! transition time, or is toggled by hand with the `toggle-night-light` action. Outputs with
+//! gamma support get a gamma ramp; on the rest niri draws a tint over the image instead.
+
+use std::ptr::null_mut;
+use std::time::Duration;
+
+use niri_config::NightLight;
+
+/// Color temperature that leaves colors unchanged, in Kelvin.
+pub const NEUTRAL_TEMPERATURE: u16 = 6500;
+
+/// Lowest supported color temperature, in Kelvin.
+const MIN_TEMPERATURE: u16 = 1000;
+
+/// How often to follow the schedule.
+pub const UPDATE_INTERVAL: Duration = Duration::from_secs(30);
+
+const MINUTES_PER_DAY: f64 = 24. * 60.;
+
+/// Returns how far into the night the schedule is, from 0 (day) to 1 (night).
+///
+/// `now` is the local time in minutes since midnight.
+pub fn scheduled_strength(config: &NightLight, now: f64) -> f64 {
+    let (Some(from), Some(to)) = (config.from, config.to) else {
+        return 0.;
+    };
+
+    let from = f64::from(from.minutes());
+    let to = f64::from(to.minutes());
+
+    // The night may go past midnight.
+    let len = (to - from).rem_euclid(MINUTES_PER_DAY);
+    let since = (now - from).rem_euclid(MINUTES_PER_DAY);
+    if len == 0. || since >= len {
+        return 0.;
+    }
+
+    // Ramp up after the night starts and down before it ends.
+    let transition = f64::from(config.transition_minutes).min(len / 2.);
+    if transition == 0. {
+        return 1.;
+    }
+    (since / transition).min((len - since) / transition).min(1.)
+}
+
+/// Returns the state forced by the toggle that still overrides the schedule.
+///
+/// Forcing lasts only until the schedule reaches the same state, so after its next transition
+/// the schedule takes over again.
+pub fn still_forced(forced: Option<bool>, scheduled_strength: f64) -> Option<bool> {
+    forced.filter(|&on| on != (scheduled_strength > 0.))
+}
+
+/// Returns the color temperature at the given strength of the night light.
+pub fn temperature(config: &NightLight, strength: f64) -> u16 {
+    let night = config
+        .temperature
+        .clamp(MIN_TEMPERATURE, NEUTRAL_TEMPERATURE);
+    let delta = f64::from(NEUTRAL_TEMPERATURE - night) * strength.clamp(0., 1.);
+    NEUTRAL_TEMPERATURE - delta.round() as u16
+}
+
+/// Returns the local time in minutes since midnight.
+pub fn local_minutes_of_day() -> Option<f64> {
+    unsafe {
+        let time = libc::time(null_mut());
+        if time == -1 {
+            return None;
+        }
+
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            return None;
+        }
+
+        let minutes = tm.tm_hour * 60 + tm.tm_min;
+        Some(f64::from(minutes) + f64::from(tm.tm_sec) / 60.)
+    }
+}
+
+/// Returns the RGB multipliers for white at the color temperature.
+///
+/// Uses Tanner Helland's approximation of the black body color, normalized so that the neutral
+/// temperature maps to pure white.
+pub fn whitepoint(kelvin: u16) -> [f64; 3] {
+    let color = black_body(kelvin);
+    let neutral = black_body(NEUTRAL_TEMPERATURE);
+    std::array::from_fn(|i| (color[i] / neutral[i]).clamp(0., 1.))
+}
+
+fn black_body(kelvin: u16) -> [f64; 3] {
+    let t = f64::from(kelvin) / 100.;
+
+    let r = if t <= 66. {
+        255.
+    } else {
+        329.698727446 * (t - 60.).powf(-0.1332047592)
+    };
+    let g = if t <= 66. {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.).powf(-0.0755148492)
+    };
+    let b = if t >= 66. {
+        255.
+    } else if t <= 19. {
+        0.
+    } else {
+        138.5177312231 * (t - 10.).ln() - 305.0447927307
+    };
+
+    [r, g, b].map(|c| c.clamp(0., 255.) / 255.)
+}
+
+/// Builds a gamma ramp for the color temperature in the `wlr-gamma-control` layout: all red
+/// values, then all green, then all blue.
+pub fn gamma_ramp(size: u32, kelvin: u16) -> Vec<u16> {
+    let size = size as usize;
+    let whitepoint = whitepoint(kelvin);
+    let last = size.saturating_sub(1).max(1) as f64;
+
+    let mut ramp = Vec::with_capacity(size * 3);
+    for channel in whitepoint {
+        ramp.extend(
+            (0..size).map(|i| (i as f64 / last * channel * f64::from(u16::MAX)).round() as u16),
+        );
+    }
+    ramp
+}
+
+/// Returns the color and the alpha of the tint drawn where gamma can't be set.
+///
+/// The tint is blended over the image rather than multiplied with it, so it is only an
+/// approximation: whites come out the same as with the gamma ramp, but blacks get lifted a bit.
+pub fn tint(kelvin: u16) -> Option<([f32; 3], f32)> {
+    let [_, g, b] = whitepoint(kelvin);
+
+    // Red stays at full below the neutral temperature, and blue drops the most. Drawing
+    // (1, c, 0) with alpha 1 - b over white gives (1, 1 - b + (1 - b) c, b).
+    let alpha = 1. - b;
+    if alpha <= 0. {
+        return None;
+    }
+    let green = ((g - b) / alpha).clamp(0., 1.);
+    Some(([1., green as f32, 0.], alpha as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::TimeOfDay;
+
+    use super::*;
+
+    fn config(from: &str, to: &str) -> NightLight {
+        NightLight {
+            temperature: 4000,
+            from: Some(from.parse().unwrap()),
+            to: Some(to.parse().unwrap()),
+            transition_minutes: 30,
+        }
+    }
+
+    fn at(time: &str) -> f64 {
+        let time: TimeOfDay = time.parse().unwrap();
+        f64::from(time.minutes())
+    }
+
+    #[test]
+    fn neutral_temperature_is_white() {
+        assert_eq!(whitepoint(NEUTRAL_TEMPERATURE), [1., 1., 1.]);
+        assert_eq!(tint(NEUTRAL_TEMPERATURE), None);
+
+        let ramp = gamma_ramp(256, NEUTRAL_TEMPERATURE);
+        assert_eq!(ramp.len(), 768);
+        assert_eq!(ramp[255], u16::MAX);
+        assert_eq!(ramp[767], u16::MAX);
+    }
+
+    #[test]
+    fn warm_temperature_drops_blue() {
+        let [r, g, b] = whitepoint(4000);
+        assert_eq!(r, 1.);
+        assert!(b < g && g < 1.);
+
+        let ([_, green, _], alpha) = tint(4000).unwrap();
+        let white = [1., 1. - alpha + alpha * green, 1. - alpha];
+        assert!((f64::from(white[1]) - g).abs() < 1e-5);
+        assert!((f64::from(white[2]) - b).abs() < 1e-5);
+    }
+
+    #[test]
+    fn schedule_ramps_across_midnight() {
+        let config = config("22:00", "06:00");
+        assert_eq!(scheduled_strength(&config, at("12:00")), 0.);
+        assert_eq!(scheduled_strength(&config, at("22:00")), 0.);
+        assert_eq!(scheduled_strength(&config, at("22:15")), 0.5);
+        assert_eq!(scheduled_strength(&config, at("02:00")), 1.);
+        assert_eq!(scheduled_strength(&config, at("05:45")), 0.5);
+        assert_eq!(scheduled_strength(&config, at("06:00")), 0.);
+
+        assert_eq!(temperature(&config, 0.), NEUTRAL_TEMPERATURE);
+        assert_eq!(temperature(&config, 0.5), 5250);
+        assert_eq!(temperature(&config, 1.), 4000);
+    }
+
+    #[test]
+    fn forcing_ends_when_schedule_agrees() {
+        let config = config("22:00", "06:00");
+        let strength = |time| scheduled_strength(&config, at(time));
+
+        // Forced on during the day lasts until the night starts.
+        assert_eq!(still_forced(Some(true), strength("12:00")), Some(true));
+        assert_eq!(still_forced(Some(true), strength("22:15")), None);
+
+        // Forced off at night lasts until the day starts.
+        assert_eq!(still_forced(Some(false), strength("02:00")), Some(false));
+        assert_eq!(still_forced(Some(false), strength("06:00")), None);
+
+        assert_eq!(still_forced(None, strength("02:00")), None);
+    }
+
+    #[test]
+    fn no_schedule_means_day() {
+        let config = NightLight::default();
+        assert_eq!(scheduled_strength(&config, at("02:00")), 0.);
+    }
+}
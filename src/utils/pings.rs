@@ -0,0 +1,173 @@
+//! Bookkeeping of pings sent to clients with windows.
+//!
+//! Every client gets at most one ping in flight. A client that doesn't answer within the timeout
+//! counts as unresponsive until it answers, or until the user chooses to keep waiting, which
+//! restarts the timeout.
+
+use std::time::Duration;
+
+/// Pings waiting for a pong, by client ID `K`, along with the client `C` to match pongs against.
+#[derive(Debug)]
+pub struct PendingPings<K, C> {
+    pings: Vec<PendingPing<K, C>>,
+}
+
+#[derive(Debug)]
+struct PendingPing<K, C> {
+    client_id: K,
+    client: C,
+    /// When the ping was sent, or when the user chose to wait for the client.
+    since: Duration,
+    /// Whether the client took longer than the timeout to answer.
+    unresponsive: bool,
+}
+
+impl<K: PartialEq, C> PendingPings<K, C> {
+    pub fn new() -> Self {
+        Self { pings: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.pings.clear();
+    }
+
+    /// Forgets the pings of clients that are gone.
+    pub fn retain_clients(&mut self, mut f: impl FnMut(&C) -> bool) {
+        self.pings.retain(|ping| f(&ping.client));
+    }
+
+    /// Marks the clients that didn't answer within the timeout as unresponsive.
+    pub fn check_timeouts(&mut self, now: Duration, timeout: Duration) {
+        for ping in &mut self.pings {
+            if now.saturating_sub(ping.since) >= timeout {
+                ping.unresponsive = true;
+            }
+        }
+    }
+
+    /// Returns whether the client still owes a pong.
+    pub fn is_pending(&self, client_id: &K) -> bool {
+        self.pings.iter().any(|ping| ping.client_id == *client_id)
+    }
+
+    pub fn push(&mut self, client_id: K, client: C, now: Duration) {
+        self.pings.push(PendingPing {
+            client_id,
+            client,
+            since: now,
+            unresponsive: false,
+        });
+    }
+
+    /// Gives the client another timeout to answer.
+    pub fn wait(&mut self, client_id: &K, now: Duration) {
+        for ping in &mut self.pings {
+            if ping.client_id == *client_id {
+                ping.since = now;
+                ping.unresponsive = false;
+            }
+        }
+    }
+
+    /// Forgets the ping of a client that answered it, returning whether it was unresponsive.
+    pub fn answer(&mut self, mut is_client: impl FnMut(&C) -> bool) -> bool {
+        let mut was_unresponsive = false;
+        self.pings.retain(|ping| {
+            let answered = is_client(&ping.client);
+            was_unresponsive |= answered && ping.unresponsive;
+            !answered
+        });
+        was_unresponsive
+    }
+
+    /// Returns the IDs of the unresponsive clients.
+    pub fn unresponsive(&self) -> impl Iterator<Item = &K> + '_ {
+        self.pings
+            .iter()
+            .filter(|ping| ping.unresponsive)
+            .map(|ping| &ping.client_id)
+    }
+}
+
+impl<K: PartialEq, C> Default for PendingPings<K, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn unresponsive(pings: &PendingPings<u32, &str>) -> Vec<u32> {
+        pings.unresponsive().copied().collect()
+    }
+
+    #[test]
+    fn clients_become_unresponsive_after_timeout() {
+        let mut pings = PendingPings::new();
+        pings.push(1, "a", secs(0));
+        pings.push(2, "b", secs(3));
+        assert!(pings.is_pending(&1));
+        assert!(!pings.is_pending(&3));
+
+        pings.check_timeouts(secs(4), TIMEOUT);
+        assert!(unresponsive(&pings).is_empty());
+
+        pings.check_timeouts(secs(5), TIMEOUT);
+        assert_eq!(unresponsive(&pings), [1]);
+
+        pings.check_timeouts(secs(8), TIMEOUT);
+        assert_eq!(unresponsive(&pings), [1, 2]);
+    }
+
+    #[test]
+    fn answering_forgets_the_ping() {
+        let mut pings = PendingPings::new();
+        pings.push(1, "a", secs(0));
+        pings.push(2, "b", secs(0));
+        pings.check_timeouts(secs(1), TIMEOUT);
+
+        // Answered in time.
+        assert!(!pings.answer(|client| *client == "b"));
+        assert!(!pings.is_pending(&2));
+
+        pings.check_timeouts(secs(6), TIMEOUT);
+        assert!(pings.answer(|client| *client == "a"));
+        assert!(unresponsive(&pings).is_empty());
+        assert!(!pings.answer(|client| *client == "a"));
+    }
+
+    #[test]
+    fn waiting_restarts_the_timeout() {
+        let mut pings = PendingPings::new();
+        pings.push(1, "a", secs(0));
+        pings.check_timeouts(secs(5), TIMEOUT);
+        assert_eq!(unresponsive(&pings), [1]);
+
+        pings.wait(&1, secs(6));
+        assert!(unresponsive(&pings).is_empty());
+        assert!(pings.is_pending(&1));
+
+        pings.check_timeouts(secs(10), TIMEOUT);
+        assert!(unresponsive(&pings).is_empty());
+        pings.check_timeouts(secs(11), TIMEOUT);
+        assert_eq!(unresponsive(&pings), [1]);
+    }
+
+    #[test]
+    fn gone_clients_are_forgotten() {
+        let mut pings = PendingPings::new();
+        pings.push(1, "a", secs(0));
+        pings.push(2, "b", secs(0));
+        pings.retain_clients(|client| *client != "a");
+        assert!(!pings.is_pending(&1));
+        assert!(pings.is_pending(&2));
+    }
+}
@@ -0,0 +1,151 @@
+//! In-memory ring of recent log messages.
+//!
+//! niri usually runs as a session with its output going to the journal or nowhere at all, so
+//! after something glitches, the log may be hard to find. Keeping the last messages around lets
+//! `niri msg logs` show them.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use niri_ipc::{LogLevel, LogMessage};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// How many messages to keep.
+const CAPACITY: usize = 500;
+
+static MESSAGES: Mutex<VecDeque<LogMessage>> = Mutex::new(VecDeque::new());
+
+/// Tracing layer that keeps recent messages at the info level or above.
+pub struct RecentLogsLayer;
+
+impl<S: Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            Level::ERROR => LogLevel::Error,
+            Level::WARN => LogLevel::Warn,
+            Level::INFO => LogLevel::Info,
+            _ => return,
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let time_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis() as u64);
+
+        push(LogMessage {
+            time_ms,
+            level,
+            target: metadata.target().to_owned(),
+            message: visitor.finish(),
+        });
+    }
+}
+
+/// Returns kept messages at `level` or more severe, oldest first.
+///
+/// With a `limit`, returns only that many of the most recent matching messages.
+pub fn recent(level: Option<LogLevel>, limit: Option<usize>) -> Vec<LogMessage> {
+    let mut rv: Vec<_> = lock()
+        .iter()
+        .filter(|message| level.is_none_or(|level| message.level <= level))
+        .cloned()
+        .collect();
+
+    if let Some(limit) = limit {
+        let skip = rv.len().saturating_sub(limit);
+        rv.drain(..skip);
+    }
+
+    rv
+}
+
+fn push(message: LogMessage) {
+    let mut messages = lock();
+    if messages.len() == CAPACITY {
+        messages.pop_front();
+    }
+    messages.push_back(message);
+}
+
+fn lock() -> MutexGuard<'static, VecDeque<LogMessage>> {
+    // Logging from a panicking thread mustn't make every later message panic too.
+    MESSAGES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        let mut message = self.message;
+        if message.is_empty() {
+            return self.fields.trim_start().to_owned();
+        }
+
+        message.push_str(&self.fields);
+        message
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(level: LogLevel, text: &str) -> LogMessage {
+        LogMessage {
+            time_ms: 0,
+            level,
+            target: String::from("niri::test"),
+            message: String::from(text),
+        }
+    }
+
+    #[test]
+    fn recent_filters_by_level_and_keeps_the_newest() {
+        lock().clear();
+        push(message(LogLevel::Warn, "first"));
+        push(message(LogLevel::Info, "second"));
+        push(message(LogLevel::Error, "third"));
+
+        let texts = |messages: Vec<LogMessage>| {
+            messages
+                .into_iter()
+                .map(|message| message.message)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(recent(None, None)), ["first", "second", "third"]);
+        assert_eq!(
+            texts(recent(Some(LogLevel::Warn), None)),
+            ["first", "third"]
+        );
+        assert_eq!(texts(recent(None, Some(2))), ["second", "third"]);
+
+        for i in 0..CAPACITY {
+            push(message(LogLevel::Info, &i.to_string()));
+        }
+        let kept = recent(None, None);
+        assert_eq!(kept.len(), CAPACITY);
+        assert_eq!(kept[0].message, "0");
+    }
+}
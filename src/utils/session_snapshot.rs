@@ -0,0 +1,609 @@
+//! Crash-resilient session snapshots.
+//!
+//! While enabled, niri periodically saves the named workspaces, the app ID and title of every
+//! window on them and their tiling trees to a file in the state directory. The file is removed on
+//! a clean exit, so finding it at startup means that the previous session crashed.
+//!
+//! In that case, windows opening with a matching app ID go back to their saved workspace, and
+//! once every saved window has reopened, or after a timeout, the tiling trees are rebuilt. Windows
+//! whose title also matches take precedence, which helps telling apart several windows of the same
+//! app.
+
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use std::{fs, thread};
+
+use anyhow::Context as _;
+use calloop::channel::Sender;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::RegistrationToken;
+use directories::ProjectDirs;
+use niri_ipc::LayoutTreeLayout;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::container::{layout_from_ipc, layout_to_ipc, TreeTemplate};
+use crate::layout::workspace::{Workspace, WorkspaceId};
+use crate::niri::{Niri, State};
+use crate::utils::with_toplevel_role;
+use crate::window::mapped::MappedId;
+use crate::window::Mapped;
+
+/// How long to wait for the windows of a crashed session to reopen.
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Shortest interval between snapshots.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest time a changed snapshot waits for the session to settle before it's written anyway.
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// Held while writing or removing the file; `true` once niri exited cleanly.
+///
+/// Keeps a write that is still running at exit from putting the file back after its removal.
+static FILE_LOCK: Mutex<bool> = Mutex::new(false);
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+struct Snapshot {
+    workspaces: Vec<SnapshotWorkspace>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotWorkspace {
+    name: String,
+    /// Tiling tree of the workspace.
+    root: Option<SnapshotNode>,
+    /// Floating windows of the workspace.
+    floating: Vec<SnapshotWindow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotNode {
+    /// Layout of a container, `None` for a window.
+    layout: Option<LayoutTreeLayout>,
+    /// Share of the parent container.
+    percent: f64,
+    window: Option<SnapshotWindow>,
+    children: Vec<SnapshotNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SnapshotWindow {
+    app_id: Option<String>,
+    title: Option<String>,
+}
+
+/// Bookkeeping for writing snapshots on a separate thread.
+#[derive(Debug, Default)]
+pub struct SnapshotWrites {
+    /// Contents of the file as of the last finished write.
+    written: Option<String>,
+    /// Changed contents from the last check, and when they first differed from the file.
+    pending: Option<(String, Duration)>,
+    /// Whether a write is running.
+    in_flight: bool,
+}
+
+pub struct SessionRestore {
+    workspaces: Vec<RestoreWorkspace>,
+    slots: Vec<Slot>,
+    timer: RegistrationToken,
+}
+
+struct RestoreWorkspace {
+    name: String,
+    /// Saved tree with slot indices in the leaves.
+    template: Option<TreeTemplate<usize>>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Slot {
+    /// Index of the workspace in [`SessionRestore::workspaces`].
+    workspace: usize,
+    app_id: String,
+    title: Option<String>,
+    window: Option<MappedId>,
+}
+
+/// Starts taking snapshots and restores the previous session if it crashed.
+pub fn setup(state: &mut State) {
+    if state.niri.config.borrow().session_snapshot.on {
+        match load() {
+            Ok(Some(snapshot)) => {
+                info!("the previous session did not exit cleanly, restoring window placement");
+                start_restore(state, snapshot);
+            }
+            Ok(None) => (),
+            Err(err) => warn!("error loading the session snapshot: {err:?}"),
+        }
+    }
+
+    let (to_niri, from_writer) = calloop::channel::channel();
+    state
+        .niri
+        .event_loop
+        .insert_source(from_writer, |event, _, state| {
+            if let calloop::channel::Event::Msg((contents, res)) = event {
+                state.niri.session_snapshot_writes.finish(contents, res);
+            }
+        })
+        .unwrap();
+
+    state
+        .niri
+        .event_loop
+        .insert_source(Timer::immediate(), move |_, _, state| {
+            let config = state.niri.config.borrow();
+            let on = config.session_snapshot.on;
+            let interval = Duration::from_millis(u64::from(config.session_snapshot.interval_ms));
+            drop(config);
+
+            if on {
+                write_if_changed(&mut state.niri, &to_niri);
+            }
+
+            TimeoutAction::ToDuration(interval.max(MIN_INTERVAL))
+        })
+        .unwrap();
+}
+
+/// Removes the snapshot so that the next start doesn't take it for a crash.
+pub fn clean_exit() {
+    let mut exited = FILE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    *exited = true;
+
+    let Some(path) = snapshot_path() else {
+        return;
+    };
+
+    if let Err(err) = fs::remove_file(&path) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            warn!("error removing the session snapshot: {err:?}");
+        }
+    }
+}
+
+fn snapshot_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "niri")?;
+    let mut path = dirs.state_dir()?.to_owned();
+    path.push("session.json");
+    Some(path)
+}
+
+fn load() -> anyhow::Result<Option<Snapshot>> {
+    let Some(path) = snapshot_path() else {
+        return Ok(None);
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context("error reading the snapshot"),
+    };
+
+    let snapshot = serde_json::from_str(&contents).context("error parsing the snapshot")?;
+    Ok(Some(snapshot))
+}
+
+fn write_if_changed(niri: &mut Niri, to_niri: &Sender<(String, anyhow::Result<()>)>) {
+    // Keep the crashed session around until its windows are back in place.
+    if niri.session_restore.is_some() {
+        return;
+    }
+
+    let contents = match serde_json::to_string_pretty(&snapshot(niri)) {
+        Ok(contents) => contents,
+        Err(err) => {
+            warn!("error serializing the session snapshot: {err:?}");
+            return;
+        }
+    };
+
+    let now = niri.clock.now_unadjusted();
+    let Some(contents) = niri.session_snapshot_writes.poll(contents, now) else {
+        return;
+    };
+
+    let Some(path) = snapshot_path() else {
+        niri.session_snapshot_writes.in_flight = false;
+        return;
+    };
+
+    let to_niri = to_niri.clone();
+    let res = thread::Builder::new()
+        .name("Session Snapshot Writer".to_owned())
+        .spawn(move || {
+            let _span = tracy_client::span!("session snapshot writer");
+
+            let exited = FILE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+            let res = if *exited {
+                Ok(())
+            } else {
+                write_atomically(&path, &contents)
+            };
+            drop(exited);
+
+            let _ = to_niri.send((contents, res));
+        });
+
+    if let Err(err) = res {
+        warn!("error spawning a thread to write the session snapshot: {err:?}");
+        niri.session_snapshot_writes.in_flight = false;
+    }
+}
+
+impl SnapshotWrites {
+    /// Returns the contents to write now, if any.
+    ///
+    /// Changed contents are written once they stayed the same between two checks, or once they
+    /// have waited for [`MAX_DELAY`], and only if no other write is running.
+    fn poll(&mut self, contents: String, now: Duration) -> Option<String> {
+        if self.written.as_ref() == Some(&contents) {
+            self.pending = None;
+            return None;
+        }
+
+        let settled = self
+            .pending
+            .as_ref()
+            .is_some_and(|(pending, _)| *pending == contents);
+        let since = self.pending.as_ref().map_or(now, |(_, since)| *since);
+        if self.in_flight || !(settled || now.saturating_sub(since) >= MAX_DELAY) {
+            self.pending = Some((contents, since));
+            return None;
+        }
+
+        self.pending = None;
+        self.in_flight = true;
+        Some(contents)
+    }
+
+    /// Records the result of a write started by [`Self::poll()`].
+    fn finish(&mut self, contents: String, res: anyhow::Result<()>) {
+        self.in_flight = false;
+        match res {
+            Ok(()) => self.written = Some(contents),
+            Err(err) => warn!("error writing the session snapshot: {err:?}"),
+        }
+    }
+}
+
+/// Writes the file through a temporary one so that a crash mid-write can't leave it truncated.
+fn write_atomically(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("error creating the state directory")?;
+    }
+
+    let tmp = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp).context("error creating the temporary file")?;
+    file.write_all(contents.as_bytes())
+        .context("error writing the temporary file")?;
+    file.sync_all()
+        .context("error syncing the temporary file")?;
+    fs::rename(&tmp, path).context("error renaming the temporary file")?;
+
+    Ok(())
+}
+
+fn snapshot(niri: &Niri) -> Snapshot {
+    let workspaces = niri
+        .layout
+        .workspaces()
+        .filter_map(|(_, _, ws)| {
+            let name = ws.name()?.clone();
+            let root = ws
+                .tree_template()
+                .map(|template| snapshot_node(ws, 1., template));
+            let floating = ws
+                .windows()
+                .filter(|mapped| ws.is_floating(&mapped.id()))
+                .map(snapshot_window)
+                .collect();
+
+            Some(SnapshotWorkspace {
+                name,
+                root,
+                floating,
+            })
+        })
+        .collect();
+
+    Snapshot { workspaces }
+}
+
+fn snapshot_node(
+    ws: &Workspace<Mapped>,
+    percent: f64,
+    template: TreeTemplate<MappedId>,
+) -> SnapshotNode {
+    match template {
+        TreeTemplate::Container { layout, children } => SnapshotNode {
+            layout: Some(layout_to_ipc(layout)),
+            percent,
+            window: None,
+            children: children
+                .into_iter()
+                .map(|(percent, child)| snapshot_node(ws, percent, child))
+                .collect(),
+        },
+        TreeTemplate::Leaf(id) => SnapshotNode {
+            layout: None,
+            percent,
+            window: ws
+                .windows()
+                .find(|mapped| mapped.id() == id)
+                .map(snapshot_window),
+            children: Vec::new(),
+        },
+    }
+}
+
+fn snapshot_window(mapped: &Mapped) -> SnapshotWindow {
+    with_toplevel_role(mapped.toplevel(), |role| SnapshotWindow {
+        app_id: role.app_id.clone(),
+        title: role.title.clone(),
+    })
+}
+
+fn start_restore(state: &mut State, snapshot: Snapshot) {
+    let mut workspaces = Vec::new();
+    let mut slots = Vec::new();
+
+    for ws in snapshot.workspaces {
+        let idx = workspaces.len();
+        let template = ws.root.and_then(|root| restore_node(root, idx, &mut slots));
+        for window in ws.floating {
+            push_slot(&mut slots, idx, window);
+        }
+
+        workspaces.push(RestoreWorkspace {
+            name: ws.name,
+            template,
+        });
+    }
+
+    if slots.is_empty() {
+        return;
+    }
+
+    let timer = state
+        .niri
+        .event_loop
+        .insert_source(Timer::from_duration(RESTORE_TIMEOUT), |_, _, state| {
+            if let Some(restore) = &state.niri.session_restore {
+                let missing = restore.slots.iter().filter(|s| s.window.is_none()).count();
+                warn!("session restore timed out with {missing} windows missing");
+            }
+            finish(state);
+            TimeoutAction::Drop
+        })
+        .unwrap();
+
+    state.niri.session_restore = Some(SessionRestore {
+        workspaces,
+        slots,
+        timer,
+    });
+}
+
+/// Converts a saved node into a template, collecting the slots.
+///
+/// Leaves without an app ID are left out since no window can fill them.
+fn restore_node(
+    node: SnapshotNode,
+    workspace: usize,
+    slots: &mut Vec<Slot>,
+) -> Option<TreeTemplate<usize>> {
+    if let Some(layout) = node.layout {
+        let children = node
+            .children
+            .into_iter()
+            .filter_map(|child| {
+                let percent = child.percent;
+                Some((percent, restore_node(child, workspace, slots)?))
+            })
+            .collect::<Vec<_>>();
+        if children.is_empty() {
+            return None;
+        }
+
+        return Some(TreeTemplate::Container {
+            layout: layout_from_ipc(layout),
+            children,
+        });
+    }
+
+    let idx = push_slot(slots, workspace, node.window?)?;
+    Some(TreeTemplate::Leaf(idx))
+}
+
+fn push_slot(slots: &mut Vec<Slot>, workspace: usize, window: SnapshotWindow) -> Option<usize> {
+    slots.push(Slot {
+        workspace,
+        app_id: window.app_id?,
+        title: window.title,
+        window: None,
+    });
+    Some(slots.len() - 1)
+}
+
+/// Claims a newly opening window for a slot of the ongoing restore.
+///
+/// Returns the workspace the window should open on.
+pub fn claim_window(niri: &mut Niri, mapped: &Mapped) -> Option<WorkspaceId> {
+    let restore = niri.session_restore.as_mut()?;
+    let (app_id, title) = with_toplevel_role(mapped.toplevel(), |role| {
+        (role.app_id.clone(), role.title.clone())
+    });
+
+    let idx = find_slot(&restore.slots, app_id.as_deref()?, title.as_deref())?;
+    let slot = &mut restore.slots[idx];
+    slot.window = Some(mapped.id());
+
+    let name = &restore.workspaces[slot.workspace].name;
+    niri.layout
+        .find_workspace_by_name(name)
+        .map(|(_, ws)| ws.id())
+}
+
+/// Finds a free slot for a window, preferring one where the title matches too.
+fn find_slot(slots: &[Slot], app_id: &str, title: Option<&str>) -> Option<usize> {
+    let mut free = slots
+        .iter()
+        .enumerate()
+        .filter(|(_, slot)| slot.window.is_none() && slot.app_id == app_id);
+
+    let first = free.clone().next()?.0;
+    let same_title = free.find(|(_, slot)| title.is_some() && slot.title.as_deref() == title);
+    Some(same_title.map_or(first, |(idx, _)| idx))
+}
+
+/// Arranges the windows once every slot of the ongoing restore has one.
+pub fn window_opened(state: &mut State) {
+    let Some(restore) = &state.niri.session_restore else {
+        return;
+    };
+
+    if restore.slots.iter().all(|slot| slot.window.is_some()) {
+        finish(state);
+    }
+}
+
+fn finish(state: &mut State) {
+    let Some(restore) = state.niri.session_restore.take() else {
+        return;
+    };
+    state.niri.event_loop.remove(restore.timer);
+
+    for ws in restore.workspaces {
+        let Some(template) = ws.template else {
+            continue;
+        };
+        let Some(template) = fill_template(template, &restore.slots) else {
+            continue;
+        };
+        let Some((_, workspace)) = state.niri.layout.find_workspace_by_name(&ws.name) else {
+            continue;
+        };
+
+        let workspace_id = workspace.id();
+        state
+            .niri
+            .layout
+            .rebuild_workspace_tree(workspace_id, template);
+    }
+
+    state.niri.queue_redraw_all();
+}
+
+/// Replaces slot indices with the windows that filled them, leaving out empty slots.
+fn fill_template(template: TreeTemplate<usize>, slots: &[Slot]) -> Option<TreeTemplate<MappedId>> {
+    match template {
+        TreeTemplate::Container { layout, children } => {
+            let children = children
+                .into_iter()
+                .filter_map(|(percent, child)| Some((percent, fill_template(child, slots)?)))
+                .collect::<Vec<_>>();
+            (!children.is_empty()).then_some(TreeTemplate::Container { layout, children })
+        }
+        TreeTemplate::Leaf(idx) => slots[idx].window.map(TreeTemplate::Leaf),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(app_id: &str, title: &str) -> SnapshotWindow {
+        SnapshotWindow {
+            app_id: Some(String::from(app_id)),
+            title: Some(String::from(title)),
+        }
+    }
+
+    #[test]
+    fn snapshot_survives_serialization() {
+        let snapshot = Snapshot {
+            workspaces: vec![SnapshotWorkspace {
+                name: String::from("web"),
+                root: Some(SnapshotNode {
+                    layout: Some(LayoutTreeLayout::SplitH),
+                    percent: 1.,
+                    window: None,
+                    children: vec![SnapshotNode {
+                        layout: None,
+                        percent: 1.,
+                        window: Some(window("firefox", "Mozilla Firefox")),
+                        children: Vec::new(),
+                    }],
+                }),
+                floating: vec![window("pavucontrol", "Volume Control")],
+            }],
+        };
+
+        let contents = serde_json::to_string_pretty(&snapshot).unwrap();
+        let parsed: Snapshot = serde_json::from_str(&contents).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+
+    #[test]
+    fn writes_wait_for_changes_to_settle() {
+        let mut writes = SnapshotWrites::default();
+        let at = Duration::from_secs;
+        let s = String::from;
+
+        assert_eq!(writes.poll(s("a"), at(0)), None);
+        assert_eq!(writes.poll(s("b"), at(1)), None);
+        assert_eq!(writes.poll(s("b"), at(2)), Some(s("b")));
+
+        // No other write starts until this one finishes.
+        assert_eq!(writes.poll(s("c"), at(3)), None);
+        assert_eq!(writes.poll(s("c"), at(4)), None);
+        writes.finish(s("b"), Ok(()));
+        assert_eq!(writes.poll(s("c"), at(5)), Some(s("c")));
+        writes.finish(s("c"), Ok(()));
+
+        // Unchanged contents aren't written again.
+        assert_eq!(writes.poll(s("c"), at(6)), None);
+        assert_eq!(writes.poll(s("c"), at(7)), None);
+    }
+
+    #[test]
+    fn constant_changes_are_written_after_delay() {
+        let mut writes = SnapshotWrites::default();
+        let at = Duration::from_secs;
+        let s = String::from;
+
+        for secs in 0..10 {
+            assert_eq!(writes.poll(secs.to_string(), at(secs)), None);
+        }
+        assert_eq!(writes.poll(s("10"), at(10)), Some(s("10")));
+
+        // A failed write is retried.
+        writes.finish(s("10"), Err(anyhow::anyhow!("disk full")));
+        assert_eq!(writes.poll(s("10"), at(11)), None);
+        assert_eq!(writes.poll(s("10"), at(12)), Some(s("10")));
+    }
+
+    #[test]
+    fn claiming_prefers_matching_titles() {
+        let slot = |app_id: &str, title: &str| Slot {
+            workspace: 0,
+            app_id: String::from(app_id),
+            title: Some(String::from(title)),
+            window: None,
+        };
+        let slots = [
+            slot("foot", "htop"),
+            slot("foot", "vim"),
+            slot("firefox", "Mozilla Firefox"),
+        ];
+
+        assert_eq!(find_slot(&slots, "foot", Some("vim")), Some(1));
+        // Titles often change, so an app ID match is enough.
+        assert_eq!(find_slot(&slots, "foot", Some("zsh")), Some(0));
+        assert_eq!(find_slot(&slots, "foot", None), Some(0));
+        assert_eq!(find_slot(&slots, "mpv", Some("vim")), None);
+    }
+}
@@ -1,14 +1,17 @@
 use std::ffi::OsStr;
+use std::os::fd::{AsFd as _, OwnedFd};
 use std::os::unix::process::CommandExt;
 use std::path::Path;
 use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{io, thread};
 
 use atomic::Atomic;
 use libc::{getrlimit, rlim_t, rlimit, setrlimit, RLIMIT_NOFILE};
 use niri_config::Environment;
+use niri_ipc::SpawnedProcess;
 use smithay::wayland::xdg_activation::XdgActivationToken;
 
 use crate::utils::expand_home;
@@ -18,6 +21,9 @@ pub static REMOVE_ENV_RUST_LIB_BACKTRACE: AtomicBool = AtomicBool::new(false);
 pub static CHILD_ENV: RwLock<Environment> = RwLock::new(Environment(Vec::new()));
 pub static CHILD_DISPLAY: RwLock<Option<String>> = RwLock::new(None);
 
+/// Processes spawned by niri, see [`spawned_processes()`].
+static SPAWNED: Mutex<Vec<SpawnedProcess>> = Mutex::new(Vec::new());
+
 static ORIGINAL_NOFILE_RLIMIT_CUR: Atomic<rlim_t> = Atomic::new(0);
 static ORIGINAL_NOFILE_RLIMIT_MAX: Atomic<rlim_t> = Atomic::new(0);
 
@@ -62,11 +68,81 @@ pub fn restore_nofile_rlimit() {
     unsafe { setrlimit(RLIMIT_NOFILE, &rlim) };
 }
 
+/// Callback for a process that niri keeps track of, see [`spawn_watched()`].
+type OnSpawn = Box<dyn FnOnce(Option<WatchedChild>) + Send>;
+
+/// Process spawned with [`spawn_watched()`].
+#[derive(Debug)]
+pub struct WatchedChild {
+    pub pid: u32,
+    /// Pidfd of the process, which becomes readable when it exits.
+    pub pidfd: OwnedFd,
+}
+
+impl WatchedChild {
+    /// Reaps the exited process, returning whether it exited successfully.
+    pub fn reap(&self) -> io::Result<bool> {
+        pidfd::wait(self.pidfd.as_fd())
+    }
+
+    /// Sends a signal to the process.
+    ///
+    /// Unlike signalling the PID, this can't reach an unrelated process that reused it.
+    pub fn signal(&self, signal: i32) -> io::Result<()> {
+        pidfd::send_signal(self.pidfd.as_fd(), signal)
+    }
+}
+
+/// Extra settings for a spawned command.
+#[derive(Debug, Default)]
+pub struct SpawnOptions {
+    /// Activation token to hand over to the spawned app.
+    pub token: Option<XdgActivationToken>,
+    /// Variables to set on top of the configured environment.
+    pub env: Vec<(String, String)>,
+    /// Whether to put the process into a transient systemd scope even when niri isn't running as
+    /// a systemd service.
+    pub systemd_scope: bool,
+}
+
 /// Spawns the command to run independently of the compositor.
 pub fn spawn<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, token: Option<XdgActivationToken>) {
+    let options = SpawnOptions {
+        token,
+        ..Default::default()
+    };
+    spawn_with(command, options);
+}
+
+/// Spawns the command to run independently of the compositor, with extra settings.
+pub fn spawn_with<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, options: SpawnOptions) {
+    spawn_in_thread(command, options, None);
+}
+
+/// Spawns the command as a direct child of the compositor to watch.
+///
+/// `on_spawn` runs on the spawner thread with the process, or with `None` if it failed to spawn.
+/// It isn't called if the process can't be watched, for lack of pidfd support. Unlike
+/// [`spawn()`], this doesn't double-fork, so the process stays in the compositor's systemd scope,
+/// and it's up to the caller to [reap](WatchedChild::reap) it once it exits.
+pub fn spawn_watched<T: AsRef<OsStr> + Send + 'static>(
+    command: Vec<T>,
+    on_spawn: impl FnOnce(Option<WatchedChild>) + Send + 'static,
+) {
+    spawn_in_thread(command, SpawnOptions::default(), Some(Box::new(on_spawn)));
+}
+
+fn spawn_in_thread<T: AsRef<OsStr> + Send + 'static>(
+    command: Vec<T>,
+    options: SpawnOptions,
+    on_spawn: Option<OnSpawn>,
+) {
     let _span = tracy_client::span!();
 
     if command.is_empty() {
+        if let Some(on_spawn) = on_spawn {
+            on_spawn(None);
+        }
         return;
     }
 
@@ -75,7 +151,7 @@ pub fn spawn<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, token: Option<Xd
         .name("Command Spawner".to_owned())
         .spawn(move || {
             let (command, args) = command.split_first().unwrap();
-            spawn_sync(command, args, token);
+            spawn_sync(command, args, options, on_spawn);
         });
 
     if let Err(err) = res {
@@ -90,16 +166,81 @@ pub fn spawn<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, token: Option<Xd
 /// - https://github.com/swaywm/sway/blob/b3dcde8d69c3f1304b076968a7a64f54d0c958be/sway/commands/exec_always.c#L64
 /// - https://github.com/hyprwm/Hyprland/blob/1ac1ff457ab8ef1ae6a8f2ab17ee7965adfa729f/src/managers/KeybindManager.cpp#L987
 pub fn spawn_sh(command: String, token: Option<XdgActivationToken>) {
-    spawn(vec![String::from("sh"), String::from("-c"), command], token);
+    let options = SpawnOptions {
+        token,
+        ..Default::default()
+    };
+    spawn_sh_with(command, options);
+}
+
+/// Spawns the command through the shell, with extra settings.
+pub fn spawn_sh_with(command: String, options: SpawnOptions) {
+    let command = vec![String::from("sh"), String::from("-c"), command];
+    spawn_with(command, options);
+}
+
+/// Returns the processes spawned by niri that are still running, oldest first.
+///
+/// A process counts as running while anything is left in its process group, so this includes
+/// processes that it started in turn, unless they moved to a process group of their own.
+pub fn spawned_processes() -> Vec<SpawnedProcess> {
+    let mut spawned = SPAWNED.lock().unwrap();
+    spawned.retain(|process| is_process_group_alive(process.pgid));
+    spawned.clone()
+}
+
+/// Returns the spawned process that the process with this PID belongs to.
+///
+/// Matches by process group, so this also finds processes that a spawned command started in turn.
+pub fn spawned_process_of(pid: i32) -> Option<SpawnedProcess> {
+    // Returns -1 if the process doesn't exist anymore.
+    let pgid = unsafe { libc::getpgid(pid) };
+    let pgid = u32::try_from(pgid).ok()?;
+    spawned_processes()
+        .into_iter()
+        .find(|process| process.pgid == pgid)
+}
+
+fn register_spawned(pgid: u32, command: Vec<String>) {
+    let started_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis() as u64);
+
+    let mut spawned = SPAWNED.lock().unwrap();
+    spawned.retain(|process| is_process_group_alive(process.pgid));
+    spawned.push(SpawnedProcess {
+        pgid,
+        command,
+        started_ms,
+    });
+}
+
+fn is_process_group_alive(pgid: u32) -> bool {
+    let Ok(pgid) = i32::try_from(pgid) else {
+        return false;
+    };
+
+    // Signal 0 only checks whether the processes exist.
+    if unsafe { libc::kill(-pgid, 0) } == 0 {
+        return true;
+    }
+    io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
 }
 
 fn spawn_sync(
     command: impl AsRef<OsStr>,
     args: impl IntoIterator<Item = impl AsRef<OsStr>>,
-    token: Option<XdgActivationToken>,
+    options: SpawnOptions,
+    on_spawn: Option<OnSpawn>,
 ) {
     let _span = tracy_client::span!();
 
+    let args: Vec<_> = args.into_iter().collect();
+    let command_line: Vec<String> = std::iter::once(command.as_ref())
+        .chain(args.iter().map(|arg| arg.as_ref()))
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+
     let mut command = command.as_ref();
 
     // Expand `~` at the start.
@@ -146,17 +287,53 @@ fn spawn_sync(
     }
     drop(env);
 
-    if let Some(token) = token.as_ref() {
+    for (name, value) in &options.env {
+        process.env(name, value);
+    }
+
+    if let Some(token) = options.token.as_ref() {
         process.env("XDG_ACTIVATION_TOKEN", token.as_str());
         process.env("DESKTOP_STARTUP_ID", token.as_str());
     }
 
+    // Give the process a fresh process group. This keeps signals sent to niri's process group,
+    // like Ctrl+C in the terminal that started niri, from reaching it, and lets niri find
+    // everything that it started. With the double fork, the group outlives its leader.
+    process.process_group(0);
+
     unsafe { process.pre_exec(crate::utils::signals::unblock_all) };
 
-    let Some(mut child) = do_spawn(command, process) else {
+    let child = if on_spawn.is_some() {
+        do_spawn_direct(command, process)
+    } else {
+        do_spawn(command, process, options.systemd_scope)
+    };
+    let Some(mut child) = child else {
+        if let Some(on_spawn) = on_spawn {
+            on_spawn(None);
+        }
         return;
     };
 
+    // The child is the process group leader, so its PID is the process group ID.
+    register_spawned(child.id(), command_line);
+
+    // The watcher reaps the child through its pidfd.
+    if let Some(on_spawn) = on_spawn {
+        match pidfd::open(child.id()) {
+            Ok(pidfd) => {
+                on_spawn(Some(WatchedChild {
+                    pid: child.id(),
+                    pidfd,
+                }));
+                return;
+            }
+            Err(err) => {
+                warn!("error opening a pidfd for {command:?}, not watching it: {err:?}");
+            }
+        }
+    }
+
     match child.wait() {
         Ok(status) => {
             if !status.success() {
@@ -169,8 +346,96 @@ fn spawn_sync(
     }
 }
 
+/// Spawns the process as a direct child that can be waited on.
+fn do_spawn_direct(command: &OsStr, mut process: Command) -> Option<Child> {
+    unsafe {
+        process.pre_exec(|| {
+            restore_nofile_rlimit();
+            Ok(())
+        });
+    }
+
+    match process.spawn() {
+        Ok(child) => Some(child),
+        Err(err) => {
+            warn!("error spawning {command:?}: {err:?}");
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod pidfd {
+    use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+    use std::{io, mem, ptr};
+
+    pub fn open(pid: u32) -> io::Result<OwnedFd> {
+        // Pidfds are always close-on-exec.
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+    }
+
+    pub fn send_signal(pidfd: BorrowedFd, signal: i32) -> io::Result<()> {
+        let res = unsafe {
+            libc::syscall(
+                libc::SYS_pidfd_send_signal,
+                pidfd.as_raw_fd(),
+                signal,
+                ptr::null::<libc::siginfo_t>(),
+                0,
+            )
+        };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn wait(pidfd: BorrowedFd) -> io::Result<bool> {
+        let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+        let res = unsafe {
+            libc::waitid(
+                libc::P_PIDFD,
+                pidfd.as_raw_fd() as libc::id_t,
+                &mut info,
+                libc::WEXITED,
+            )
+        };
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(info.si_code == libc::CLD_EXITED && unsafe { info.si_status() } == 0)
+    }
+}
+
+// FIXME: use kqueue process descriptors on FreeBSD.
+#[cfg(not(target_os = "linux"))]
+mod pidfd {
+    use std::io;
+    use std::os::fd::{BorrowedFd, OwnedFd};
+
+    pub fn open(_pid: u32) -> io::Result<OwnedFd> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn send_signal(_pidfd: BorrowedFd, _signal: i32) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn wait(_pidfd: BorrowedFd) -> io::Result<bool> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
 #[cfg(not(feature = "systemd"))]
-fn do_spawn(command: &OsStr, mut process: Command) -> Option<Child> {
+fn do_spawn(command: &OsStr, mut process: Command, systemd_scope: bool) -> Option<Child> {
+    if systemd_scope {
+        warn!("niri was built without systemd support, spawning {command:?} without a scope");
+    }
+
     unsafe {
         // Double-fork to avoid having to waitpid the child.
         process.pre_exec(move || {
@@ -210,7 +475,7 @@ mod systemd {
 
     use super::*;
 
-    pub fn do_spawn(command: &OsStr, mut process: Command) -> Option<Child> {
+    pub fn do_spawn(command: &OsStr, mut process: Command, systemd_scope: bool) -> Option<Child> {
         #[cfg(target_env = "gnu")]
         use libc::close_range;
 
@@ -332,8 +597,13 @@ mod systemd {
                     trace!("spawned PID: {pid}");
 
                     // Start a systemd scope for the grandchild.
-                    if let Err(err) = start_systemd_scope(command, child.id(), pid as u32) {
-                        trace!("error starting systemd scope for spawned command: {err:?}");
+                    let res = start_systemd_scope(command, child.id(), pid as u32, systemd_scope);
+                    if let Err(err) = res {
+                        if systemd_scope {
+                            warn!("error starting systemd scope for spawned command: {err:?}");
+                        } else {
+                            trace!("error starting systemd scope for spawned command: {err:?}");
+                        }
                     }
                 }
                 Err(err) => {
@@ -384,10 +654,13 @@ mod systemd {
     ///
     /// This separates the pid from the compositor scope, which for example prevents the OOM killer
     /// from bringing down the compositor together with a misbehaving client.
+    ///
+    /// Unless `force` is set, this only happens when niri runs as a systemd service.
     fn start_systemd_scope(
         name: &OsStr,
         intermediate_pid: u32,
         child_pid: u32,
+        force: bool,
     ) -> anyhow::Result<()> {
         use std::fmt::Write as _;
         use std::os::unix::ffi::OsStrExt;
@@ -398,8 +671,8 @@ mod systemd {
 
         use crate::utils::IS_SYSTEMD_SERVICE;
 
-        // We only start transient scopes if we're a systemd service ourselves.
-        if !IS_SYSTEMD_SERVICE.load(Ordering::Relaxed) {
+        // By default, we only start transient scopes if we're a systemd service ourselves.
+        if !force && !IS_SYSTEMD_SERVICE.load(Ordering::Relaxed) {
             return Ok(());
         }
 
@@ -0,0 +1,168 @@
+//! Window titles rewritten by the `title-format` window rule.
+//!
+//! The template can reference the title, the app ID, the workspace and the marks of the window.
+//! Formatting happens only when one of those changes, so tab bars and IPC can read the result
+//! every frame for free.
+
+/// Everything that a title template can reference.
+#[derive(Debug, Clone, Copy)]
+pub struct TitleFields<'a> {
+    pub title: Option<&'a str>,
+    pub app_id: Option<&'a str>,
+    /// Name of the workspace, if it has one.
+    pub workspace_name: Option<&'a str>,
+    /// Index of the workspace on its monitor, used for unnamed workspaces.
+    pub workspace_idx: usize,
+    pub marks: &'a [String],
+}
+
+/// Formatted title, remembering what it was formatted from.
+#[derive(Debug)]
+pub struct FormattedTitle {
+    template: String,
+    title: Option<String>,
+    app_id: Option<String>,
+    workspace_name: Option<String>,
+    workspace_idx: usize,
+    marks: Vec<String>,
+    value: String,
+}
+
+impl FormattedTitle {
+    pub fn new(template: &str, fields: &TitleFields) -> Self {
+        Self {
+            template: template.to_owned(),
+            title: fields.title.map(str::to_owned),
+            app_id: fields.app_id.map(str::to_owned),
+            workspace_name: fields.workspace_name.map(str::to_owned),
+            workspace_idx: fields.workspace_idx,
+            marks: fields.marks.to_vec(),
+            value: format_title(template, fields),
+        }
+    }
+
+    /// Formats the title again if the template or any of the fields changed.
+    pub fn update(&mut self, template: &str, fields: &TitleFields) {
+        let unchanged = self.template == template
+            && self.title.as_deref() == fields.title
+            && self.app_id.as_deref() == fields.app_id
+            && self.workspace_name.as_deref() == fields.workspace_name
+            && self.workspace_idx == fields.workspace_idx
+            && self.marks == fields.marks;
+        if !unchanged {
+            *self = Self::new(template, fields);
+        }
+    }
+
+    /// Returns whether the title was formatted for this workspace and these marks.
+    pub fn has_context(
+        &self,
+        workspace_name: Option<&str>,
+        workspace_idx: usize,
+        marks: &[String],
+    ) -> bool {
+        self.workspace_name.as_deref() == workspace_name
+            && self.workspace_idx == workspace_idx
+            && self.marks == marks
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Substitutes the placeholders in the template.
+///
+/// Supported placeholders are `%title`, `%app`, `%workspace` and `%marks`, and `%%` for a literal
+/// percent sign. Anything else is copied as is.
+pub fn format_title(template: &str, fields: &TitleFields) -> String {
+    let mut rv = String::with_capacity(template.len());
+
+    let mut rest = template;
+    while let Some(idx) = rest.find('%') {
+        rv.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+
+        if let Some(tail) = rest.strip_prefix("title") {
+            rv.push_str(fields.title.unwrap_or(""));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("app") {
+            rv.push_str(fields.app_id.unwrap_or(""));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("workspace") {
+            // Unnamed workspaces go by their index on the monitor, like in `focus-workspace`.
+            match fields.workspace_name {
+                Some(name) => rv.push_str(name),
+                None => rv.push_str(&(fields.workspace_idx + 1).to_string()),
+            }
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("marks") {
+            rv.push_str(&fields.marks.join(","));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('%') {
+            rv.push('%');
+            rest = tail;
+        } else {
+            rv.push('%');
+        }
+    }
+    rv.push_str(rest);
+
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields<'a>(marks: &'a [String]) -> TitleFields<'a> {
+        TitleFields {
+            title: Some("README.md"),
+            app_id: Some("org.gnome.TextEditor"),
+            workspace_name: None,
+            workspace_idx: 1,
+            marks,
+        }
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let marks = [String::from("a"), String::from("b")];
+        assert_eq!(
+            format_title("[%workspace] %app - %title (%marks)", &fields(&marks)),
+            "[2] org.gnome.TextEditor - README.md (a,b)"
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_placeholders() {
+        assert_eq!(format_title("100%% %foo %", &fields(&[])), "100% %foo %");
+    }
+
+    #[test]
+    fn formats_again_on_changes() {
+        let mut title = FormattedTitle::new("%title", &fields(&[]));
+        assert_eq!(title.as_str(), "README.md");
+
+        let renamed = TitleFields {
+            title: Some("notes.txt"),
+            ..fields(&[])
+        };
+        title.update("%title", &renamed);
+        assert_eq!(title.as_str(), "notes.txt");
+
+        title.update("%title [%workspace]", &renamed);
+        assert_eq!(title.as_str(), "notes.txt [2]");
+    }
+
+    #[test]
+    fn context_covers_workspace_and_marks() {
+        let marks = [String::from("a")];
+        let title = FormattedTitle::new("%title", &fields(&marks));
+
+        assert!(title.has_context(None, 1, &marks));
+        assert!(!title.has_context(Some("chat"), 1, &marks));
+        assert!(!title.has_context(None, 0, &marks));
+        assert!(!title.has_context(None, 1, &[]));
+    }
+}
@@ -0,0 +1,130 @@
+//! Video playback detection from commit timing.
+//!
+//! Clients can say that a surface shows video through the content-type protocol, but many don't.
+//! For those, we look at how often they commit: video players commit new frames at a steady rate
+//! between roughly 24 and 60 FPS, and stop committing as soon as playback is paused.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How many recent commit intervals to look at.
+const HISTORY: usize = 16;
+
+/// Shortest interval between video frames, a bit above 60 FPS.
+const MIN_INTERVAL: Duration = Duration::from_millis(12);
+
+/// Longest interval between video frames.
+///
+/// 24 FPS video on a 60 Hz output alternates between 2 and 3 refresh cycles, so some frames take
+/// 50 ms.
+const MAX_INTERVAL: Duration = Duration::from_millis(55);
+
+/// Largest difference between the shortest and the longest recent interval.
+const MAX_JITTER: Duration = Duration::from_millis(20);
+
+/// Playback counts as stopped once no frame arrived for this long.
+pub const STOPPED_AFTER: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Default)]
+pub struct VideoCadence {
+    last_commit: Option<Duration>,
+    intervals: VecDeque<Duration>,
+}
+
+impl VideoCadence {
+    pub fn record_commit(&mut self, now: Duration) {
+        if let Some(last) = self.last_commit {
+            let interval = now.saturating_sub(last);
+            // A long pause starts a new sequence.
+            if interval > STOPPED_AFTER {
+                self.intervals.clear();
+            } else {
+                if self.intervals.len() == HISTORY {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back(interval);
+            }
+        }
+
+        self.last_commit = Some(now);
+    }
+
+    /// Returns whether the surface committed recently.
+    pub fn is_committing(&self, now: Duration) -> bool {
+        self.last_commit
+            .is_some_and(|last| now.saturating_sub(last) <= STOPPED_AFTER)
+    }
+
+    /// Returns when the surface last committed.
+    pub fn last_commit(&self) -> Option<Duration> {
+        self.last_commit
+    }
+
+    /// Returns the average number of commits per second over the recent history.
+    pub fn commit_rate(&self) -> Option<f64> {
+        let total: Duration = self.intervals.iter().sum();
+        if total.is_zero() {
+            return None;
+        }
+
+        Some(self.intervals.len() as f64 / total.as_secs_f64())
+    }
+
+    /// Returns whether recent commits look like steady video playback.
+    pub fn looks_like_video(&self, now: Duration) -> bool {
+        if !self.is_committing(now) || self.intervals.len() < HISTORY {
+            return false;
+        }
+
+        let min = self.intervals.iter().min().unwrap();
+        let max = self.intervals.iter().max().unwrap();
+        MIN_INTERVAL <= *min && *max <= MAX_INTERVAL && *max - *min <= MAX_JITTER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_every(cadence: &mut VideoCadence, start: Duration, intervals: &[u64]) -> Duration {
+        let mut now = start;
+        cadence.record_commit(now);
+        for _ in 0..HISTORY {
+            for ms in intervals {
+                now += Duration::from_millis(*ms);
+                cadence.record_commit(now);
+            }
+        }
+        now
+    }
+
+    #[test]
+    fn steady_frames_look_like_video() {
+        let mut cadence = VideoCadence::default();
+        let now = commit_every(&mut cadence, Duration::ZERO, &[33, 50]);
+        assert!(cadence.looks_like_video(now));
+
+        // Pausing stops it.
+        assert!(!cadence.looks_like_video(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn commit_rate_averages_recent_intervals() {
+        let mut cadence = VideoCadence::default();
+        assert_eq!(cadence.commit_rate(), None);
+
+        commit_every(&mut cadence, Duration::ZERO, &[10, 30]);
+        assert_eq!(cadence.commit_rate(), Some(50.));
+    }
+
+    #[test]
+    fn irregular_or_fast_frames_do_not_look_like_video() {
+        let mut cadence = VideoCadence::default();
+        let now = commit_every(&mut cadence, Duration::ZERO, &[7]);
+        assert!(!cadence.looks_like_video(now));
+
+        let mut cadence = VideoCadence::default();
+        let now = commit_every(&mut cadence, Duration::ZERO, &[16, 100]);
+        assert!(!cadence.looks_like_video(now));
+    }
+}
@@ -0,0 +1,368 @@
+//! Respawning of critical clients.
+//!
+//! Critical clients are `spawn-at-startup` commands marked with `critical=true`, usually the bar,
+//! the wallpaper and the notification daemon. When one of them fails, exiting with an error or
+//! getting killed, niri spawns it again after a delay that grows with every restart in a row. A
+//! client that exits successfully is done and stays gone.
+//!
+//! Clients are tracked through their pidfds, which the event loop polls for the exit.
+//!
+//! A client can also lose its Wayland connection without exiting, for example after a protocol
+//! error. If a critical client loses its connection together with its layer surfaces and is still
+//! running after a grace period, niri terminates it, which then respawns it as usual. Layer
+//! surfaces that a client destroys on its own (like a bar hiding itself) are left alone.
+
+use std::time::{Duration, Instant};
+
+use calloop::channel::Sender;
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{Interest, Mode, PostAction, RegistrationToken};
+use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
+use smithay::reexports::wayland_server::Resource as _;
+
+use crate::niri::State;
+use crate::utils::get_credentials_for_surface;
+use crate::utils::spawning::{spawn_watched, WatchedChild};
+
+/// Delay before the first respawn.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest delay between respawns.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+/// A client running for this long is considered stable, which resets the delay.
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+/// How long a client that lost its connection may keep running before it's terminated.
+const DISCONNECT_GRACE: Duration = Duration::from_secs(5);
+
+pub struct Watchdog {
+    clients: Vec<CriticalClient>,
+    to_main: Sender<ToMain>,
+}
+
+struct CriticalClient {
+    command: Vec<String>,
+    /// The running process.
+    child: Option<WatchedChild>,
+    /// Whether niri terminated the process, which respawns it whatever the exit status.
+    terminated: bool,
+    started_at: Instant,
+    /// Respawns in a row without the client becoming stable.
+    restarts: u32,
+    /// Layer surfaces of the running process.
+    layer_surfaces: Vec<WlSurface>,
+    /// Pending respawn or termination.
+    timer: Option<RegistrationToken>,
+}
+
+enum ToMain {
+    Spawned {
+        idx: usize,
+        child: Option<WatchedChild>,
+    },
+}
+
+impl CriticalClient {
+    fn pid(&self) -> Option<u32> {
+        self.child.as_ref().map(|child| child.pid)
+    }
+
+    fn respawn_delay(&self) -> Duration {
+        BASE_DELAY
+            .saturating_mul(1 << self.restarts.min(6))
+            .min(MAX_DELAY)
+    }
+}
+
+/// Spawns the critical clients and starts watching them.
+pub fn setup(state: &mut State, commands: Vec<Vec<String>>) {
+    if commands.is_empty() || state.niri.watchdog.is_some() {
+        return;
+    }
+
+    let (to_main, rx) = calloop::channel::channel();
+    state
+        .niri
+        .event_loop
+        .insert_source(rx, move |event, _, state| match event {
+            calloop::channel::Event::Msg(msg) => match msg {
+                ToMain::Spawned { idx, child } => on_spawned(state, idx, child),
+            },
+            calloop::channel::Event::Closed => (),
+        })
+        .unwrap();
+
+    let clients = commands
+        .into_iter()
+        .map(|command| CriticalClient {
+            command,
+            child: None,
+            terminated: false,
+            started_at: Instant::now(),
+            restarts: 0,
+            layer_surfaces: Vec::new(),
+            timer: None,
+        })
+        .collect::<Vec<_>>();
+    let count = clients.len();
+
+    state.niri.watchdog = Some(Watchdog { clients, to_main });
+
+    for idx in 0..count {
+        spawn(state, idx);
+    }
+}
+
+fn spawn(state: &mut State, idx: usize) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let client = &mut watchdog.clients[idx];
+    client.timer = None;
+    client.terminated = false;
+    client.started_at = Instant::now();
+
+    let to_main = watchdog.to_main.clone();
+    spawn_watched(client.command.clone(), move |child| {
+        let _ = to_main.send(ToMain::Spawned { idx, child });
+    });
+}
+
+fn on_spawned(state: &mut State, idx: usize, child: Option<WatchedChild>) {
+    let Some(child) = child else {
+        // Failing to spawn counts as a failure to run.
+        on_exited(state, idx, false);
+        return;
+    };
+
+    let pidfd = match child.pidfd.try_clone() {
+        Ok(pidfd) => pidfd,
+        Err(err) => {
+            warn!("error duplicating the pidfd of a critical client: {err:?}");
+            return;
+        }
+    };
+    let source = Generic::new(pidfd, Interest::READ, Mode::OneShot);
+    state
+        .niri
+        .event_loop
+        .insert_source(source, move |_, _, state| {
+            on_child_readable(state, idx);
+            Ok(PostAction::Remove)
+        })
+        .unwrap();
+
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let client = &mut watchdog.clients[idx];
+    client.child = Some(child);
+    client.layer_surfaces.clear();
+}
+
+/// Reaps a critical client whose pidfd reported the exit.
+fn on_child_readable(state: &mut State, idx: usize) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let Some(child) = watchdog.clients[idx].child.take() else {
+        return;
+    };
+
+    let success = match child.reap() {
+        Ok(success) => success,
+        Err(err) => {
+            warn!("error reaping a critical client: {err:?}");
+            false
+        }
+    };
+    on_exited(state, idx, success);
+}
+
+fn on_exited(state: &mut State, idx: usize, success: bool) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let client = &mut watchdog.clients[idx];
+    client.child = None;
+    client.layer_surfaces.clear();
+
+    if let Some(token) = client.timer.take() {
+        state.niri.event_loop.remove(token);
+    }
+
+    if success && !client.terminated {
+        debug!(
+            "critical client {:?} exited successfully, not respawning it",
+            client.command
+        );
+        return;
+    }
+
+    if client.started_at.elapsed() >= STABLE_AFTER {
+        client.restarts = 0;
+    }
+
+    let delay = client.respawn_delay();
+    warn!(
+        "critical client {:?} failed, respawning in {delay:?}",
+        client.command
+    );
+
+    let token = state
+        .niri
+        .event_loop
+        .insert_source(Timer::from_duration(delay), move |_, _, state| {
+            respawn(state, idx);
+            TimeoutAction::Drop
+        })
+        .unwrap();
+
+    let client = &mut state.niri.watchdog.as_mut().unwrap().clients[idx];
+    client.timer = Some(token);
+}
+
+fn respawn(state: &mut State, idx: usize) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let client = &mut watchdog.clients[idx];
+    client.restarts += 1;
+    let command = client.command.clone();
+    let restarts = client.restarts;
+
+    spawn(state, idx);
+    state.ipc_critical_client_restarted(command, restarts);
+}
+
+impl Watchdog {
+    fn client_for_surface(&self, surface: &WlSurface) -> Option<usize> {
+        let pid = get_credentials_for_surface(surface)?.pid;
+        let pid = u32::try_from(pid).ok()?;
+        self.clients
+            .iter()
+            .position(|client| client.pid() == Some(pid))
+    }
+}
+
+/// Starts tracking a new layer surface, if it belongs to a critical client.
+pub fn layer_surface_created(state: &mut State, surface: &WlSurface) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    if let Some(idx) = watchdog.client_for_surface(surface) {
+        let client = &mut watchdog.clients[idx];
+        client.layer_surfaces.push(surface.clone());
+
+        // The client got a new connection in time.
+        if client.child.is_some() {
+            if let Some(token) = client.timer.take() {
+                state.niri.event_loop.remove(token);
+            }
+        }
+    }
+}
+
+/// Handles a destroyed layer surface, terminating its client if it lost the connection.
+pub fn layer_surface_destroyed(state: &mut State, surface: &WlSurface) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let Some(idx) = watchdog
+        .clients
+        .iter()
+        .position(|client| client.layer_surfaces.contains(surface))
+    else {
+        return;
+    };
+
+    let client = &mut watchdog.clients[idx];
+    client.layer_surfaces.retain(|s| s != surface);
+
+    // Only the client's own connection going away is unexpected.
+    let disconnected = surface.client().is_none();
+    if !disconnected || !client.layer_surfaces.is_empty() || client.timer.is_some() {
+        return;
+    }
+    let Some(pid) = client.pid() else {
+        return;
+    };
+
+    let token = state
+        .niri
+        .event_loop
+        .insert_source(
+            Timer::from_duration(DISCONNECT_GRACE),
+            move |_, _, state| {
+                terminate_if_running(state, idx, pid);
+                TimeoutAction::Drop
+            },
+        )
+        .unwrap();
+
+    let client = &mut state.niri.watchdog.as_mut().unwrap().clients[idx];
+    client.timer = Some(token);
+}
+
+fn terminate_if_running(state: &mut State, idx: usize, pid: u32) {
+    let Some(watchdog) = &mut state.niri.watchdog else {
+        return;
+    };
+
+    let client = &mut watchdog.clients[idx];
+    client.timer = None;
+    let Some(child) = client.child.as_ref().filter(|child| child.pid == pid) else {
+        return;
+    };
+
+    warn!(
+        "critical client {:?} lost its connection, terminating it",
+        client.command
+    );
+    // The exit then respawns it.
+    match child.signal(libc::SIGTERM) {
+        Ok(()) => client.terminated = true,
+        Err(err) => warn!("error terminating a critical client: {err:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn respawn_delay_backs_off() {
+        let mut client = CriticalClient {
+            command: vec![String::from("waybar")],
+            child: None,
+            terminated: false,
+            started_at: Instant::now(),
+            restarts: 0,
+            layer_surfaces: Vec::new(),
+            timer: None,
+        };
+
+        let mut delays = Vec::new();
+        for restarts in [0, 1, 2, 5, 6, 100] {
+            client.restarts = restarts;
+            delays.push(client.respawn_delay().as_secs());
+        }
+        assert_eq!(delays, [1, 2, 4, 32, 60, 60]);
+    }
+
+    #[test]
+    fn reaping_tells_success_from_failure() {
+        for (command, success) in [("true", true), ("false", false)] {
+            let (tx, rx) = std::sync::mpsc::channel();
+            spawn_watched(vec![command], move |child| tx.send(child).unwrap());
+            let child = rx.recv().unwrap().unwrap();
+            assert_eq!(child.reap().unwrap(), success, "{command}");
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Export and import of workspace tiling trees.
+//!
+//! An export saves the tiling tree of a workspace together with the app ID and the launch command
+//! of every window. The launch command is the command that niri spawned the window's client with,
+//! and only the first window of every spawned process gets it, so that an import starts each
+//! process once.
+//!
+//! An import runs the saved commands and sends windows opening with a matching app ID to the
+//! target workspace. Once every slot has a window, or after a timeout, the windows are arranged
+//! into the saved tree.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::RegistrationToken;
+use niri_ipc::{SpawnedProcess, WorkspaceExport, WorkspaceExportNode};
+
+use crate::layout::container::{layout_from_ipc, layout_to_ipc, TreeTemplate};
+use crate::layout::workspace::{Workspace, WorkspaceId};
+use crate::niri::{Niri, State};
+use crate::utils::get_credentials_for_surface;
+use crate::utils::spawning::{spawn, spawned_process_of};
+use crate::utils::with_toplevel_role;
+use crate::window::mapped::MappedId;
+use crate::window::Mapped;
+
+/// How long to wait for the windows of an import to open.
+const IMPORT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct WorkspaceImport {
+    workspace_id: WorkspaceId,
+    /// Saved tree with slot indices in the leaves.
+    template: TreeTemplate<usize>,
+    slots: Vec<Slot>,
+    timer: RegistrationToken,
+}
+
+#[derive(Debug, PartialEq)]
+struct Slot {
+    app_id: String,
+    window: Option<MappedId>,
+}
+
+/// Exports the tiling tree of the focused workspace.
+pub fn export(niri: &Niri) -> WorkspaceExport {
+    let Some(ws) = niri.layout.active_workspace() else {
+        return WorkspaceExport {
+            name: None,
+            root: None,
+        };
+    };
+
+    WorkspaceExport {
+        name: ws.name().cloned(),
+        root: ws
+            .tree_template()
+            .map(|template| export_node(ws, 1., template, &mut HashSet::new())),
+    }
+}
+
+fn export_node(
+    ws: &Workspace<Mapped>,
+    percent: f64,
+    template: TreeTemplate<MappedId>,
+    exported_processes: &mut HashSet<u32>,
+) -> WorkspaceExportNode {
+    match template {
+        TreeTemplate::Container { layout, children } => WorkspaceExportNode {
+            layout: Some(layout_to_ipc(layout)),
+            percent,
+            app_id: None,
+            command: None,
+            children: children
+                .into_iter()
+                .map(|(percent, child)| export_node(ws, percent, child, exported_processes))
+                .collect(),
+        },
+        TreeTemplate::Leaf(id) => {
+            let mapped = ws.windows().find(|mapped| mapped.id() == id);
+            let app_id = mapped.and_then(|mapped| {
+                with_toplevel_role(mapped.toplevel(), |role| role.app_id.clone())
+            });
+            // Windows of a process that another window already launches get no command.
+            let command = mapped
+                .and_then(launch_process)
+                .filter(|process| exported_processes.insert(process.pgid))
+                .map(|process| process.command);
+
+            WorkspaceExportNode {
+                layout: None,
+                percent,
+                app_id,
+                command,
+                children: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Returns the process that niri spawned the window's client from.
+///
+/// Clients that niri didn't spawn, like ones started from a terminal, have no launch command.
+fn launch_process(mapped: &Mapped) -> Option<SpawnedProcess> {
+    let pid = get_credentials_for_surface(mapped.toplevel().wl_surface())?.pid;
+    spawned_process_of(pid).filter(|process| !process.command.is_empty())
+}
+
+/// Starts importing a tiling tree.
+///
+/// The tree goes to the workspace with the exported name if there's one, or to the focused
+/// workspace.
+pub fn import(state: &mut State, export: WorkspaceExport) -> Result<(), String> {
+    if state.niri.workspace_import.is_some() {
+        return Err(String::from("another import is in progress"));
+    }
+
+    let mut slots = Vec::new();
+    let mut commands = Vec::new();
+    let template = export
+        .root
+        .and_then(|root| import_node(root, &mut slots, &mut commands))
+        .ok_or_else(|| String::from("the export has no windows with an app ID"))?;
+
+    let layout = &state.niri.layout;
+    let ws = export
+        .name
+        .as_deref()
+        .and_then(|name| layout.find_workspace_by_name(name))
+        .map(|(_, ws)| ws)
+        .or_else(|| layout.active_workspace())
+        .ok_or_else(|| String::from("no workspace to import into"))?;
+    let workspace_id = ws.id();
+
+    let timer = state
+        .niri
+        .event_loop
+        .insert_source(Timer::from_duration(IMPORT_TIMEOUT), |_, _, state| {
+            if let Some(import) = &state.niri.workspace_import {
+                let missing = import.slots.iter().filter(|s| s.window.is_none()).count();
+                warn!("workspace import timed out with {missing} windows missing");
+            }
+            finish(state);
+            TimeoutAction::Drop
+        })
+        .unwrap();
+
+    state.niri.workspace_import = Some(WorkspaceImport {
+        workspace_id,
+        template,
+        slots,
+        timer,
+    });
+
+    // The export gives a command only to the first window of every process, so this starts every
+    // process once.
+    for command in commands {
+        spawn(command, None);
+    }
+
+    Ok(())
+}
+
+/// Converts an exported node into a template, collecting the slots and the commands to run.
+///
+/// Leaves without an app ID are left out since no window can fill them.
+fn import_node(
+    node: WorkspaceExportNode,
+    slots: &mut Vec<Slot>,
+    commands: &mut Vec<Vec<String>>,
+) -> Option<TreeTemplate<usize>> {
+    if let Some(layout) = node.layout {
+        let children = node
+            .children
+            .into_iter()
+            .filter_map(|child| {
+                let percent = child.percent;
+                Some((percent, import_node(child, slots, commands)?))
+            })
+            .collect::<Vec<_>>();
+        if children.is_empty() {
+            return None;
+        }
+
+        return Some(TreeTemplate::Container {
+            layout: layout_from_ipc(layout),
+            children,
+        });
+    }
+
+    let app_id = node.app_id?;
+    if let Some(command) = node.command.filter(|command| !command.is_empty()) {
+        commands.push(command);
+    }
+
+    slots.push(Slot {
+        app_id,
+        window: None,
+    });
+    Some(TreeTemplate::Leaf(slots.len() - 1))
+}
+
+/// Claims a newly opening window for a slot of the ongoing import.
+///
+/// Returns the workspace the window should open on.
+pub fn claim_window(niri: &mut Niri, mapped: &Mapped) -> Option<WorkspaceId> {
+    let import = niri.workspace_import.as_mut()?;
+    let app_id = with_toplevel_role(mapped.toplevel(), |role| role.app_id.clone())?;
+
+    let slot = import
+        .slots
+        .iter_mut()
+        .find(|slot| slot.window.is_none() && slot.app_id == app_id)?;
+    slot.window = Some(mapped.id());
+
+    let workspace_id = import.workspace_id;
+    niri.layout
+        .find_workspace_by_id(workspace_id)
+        .map(|_| workspace_id)
+}
+
+/// Arranges the windows once every slot of the ongoing import has one.
+pub fn window_opened(state: &mut State) {
+    let Some(import) = &state.niri.workspace_import else {
+        return;
+    };
+
+    if import.slots.iter().all(|slot| slot.window.is_some()) {
+        finish(state);
+    }
+}
+
+fn finish(state: &mut State) {
+    let Some(import) = state.niri.workspace_import.take() else {
+        return;
+    };
+    state.niri.event_loop.remove(import.timer);
+
+    let Some(template) = fill_template(import.template, &import.slots) else {
+        return;
+    };
+    state
+        .niri
+        .layout
+        .rebuild_workspace_tree(import.workspace_id, template);
+    state.niri.queue_redraw_all();
+}
+
+/// Replaces slot indices with the windows that filled them, leaving out empty slots.
+fn fill_template(template: TreeTemplate<usize>, slots: &[Slot]) -> Option<TreeTemplate<MappedId>> {
+    match template {
+        TreeTemplate::Container { layout, children } => {
+            let children = children
+                .into_iter()
+                .filter_map(|(percent, child)| Some((percent, fill_template(child, slots)?)))
+                .collect::<Vec<_>>();
+            (!children.is_empty()).then_some(TreeTemplate::Container { layout, children })
+        }
+        TreeTemplate::Leaf(idx) => slots[idx].window.map(TreeTemplate::Leaf),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_ipc::LayoutTreeLayout;
+
+    use super::*;
+    use crate::layout::container::Layout;
+
+    fn leaf(app_id: Option<&str>, command: Option<&str>) -> WorkspaceExportNode {
+        WorkspaceExportNode {
+            layout: None,
+            percent: 0.5,
+            app_id: app_id.map(String::from),
+            command: command.map(|c| vec![String::from(c)]),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn import_skips_leaves_without_app_id() {
+        let root = WorkspaceExportNode {
+            layout: Some(LayoutTreeLayout::SplitV),
+            percent: 1.,
+            app_id: None,
+            command: None,
+            children: vec![
+                leaf(Some("foot"), Some("foot")),
+                leaf(None, Some("mystery")),
+                leaf(Some("firefox"), None),
+            ],
+        };
+
+        let mut slots = Vec::new();
+        let mut commands = Vec::new();
+        let template = import_node(root, &mut slots, &mut commands).unwrap();
+
+        assert_eq!(
+            template,
+            TreeTemplate::Container {
+                layout: Layout::SplitV,
+                children: vec![(0.5, TreeTemplate::Leaf(0)), (0.5, TreeTemplate::Leaf(1))],
+            }
+        );
+        assert_eq!(slots.len(), 2);
+        assert_eq!(slots[1].app_id, "firefox");
+        // Slots without a command wait for a window opened by hand.
+        assert_eq!(commands, [vec![String::from("foot")]]);
+    }
+}
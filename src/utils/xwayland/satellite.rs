@@ -9,6 +9,7 @@ use calloop::channel::Sender;
 use calloop::generic::Generic;
 use calloop::{Interest, Mode, PostAction, RegistrationToken};
 use smithay::reexports::rustix::io::{fcntl_setfd, FdFlags};
+use wayland_backend::server::Credentials;
 
 use crate::niri::State;
 use crate::utils::expand_home;
@@ -19,16 +20,27 @@ pub struct Satellite {
     abstract_token: Option<RegistrationToken>,
     unix_token: Option<RegistrationToken>,
     to_main: Sender<ToMain>,
+    /// Pid of the running xwayland-satellite process.
+    pid: Option<i32>,
 }
 
 enum ToMain {
     SetupWatch,
+    Spawned(i32),
 }
 
 impl Satellite {
     pub fn display_name(&self) -> &str {
         &self.x11.display_name
     }
+
+    /// Returns whether the client with these credentials is xwayland-satellite.
+    ///
+    /// All X11 windows show up as windows of this client.
+    pub fn is_client(&self, credentials: Option<&Credentials>) -> bool {
+        self.pid
+            .is_some_and(|pid| credentials.is_some_and(|creds| creds.pid == pid))
+    }
 }
 
 pub fn setup(state: &mut State) {
@@ -60,7 +72,17 @@ pub fn setup(state: &mut State) {
     event_loop
         .insert_source(rx, move |event, _, state| match event {
             calloop::channel::Event::Msg(msg) => match msg {
-                ToMain::SetupWatch => setup_watch(state),
+                ToMain::SetupWatch => {
+                    if let Some(satellite) = &mut state.niri.satellite {
+                        satellite.pid = None;
+                    }
+                    setup_watch(state);
+                }
+                ToMain::Spawned(pid) => {
+                    if let Some(satellite) = &mut state.niri.satellite {
+                        satellite.pid = Some(pid);
+                    }
+                }
             },
             calloop::channel::Event::Closed => (),
         })
@@ -71,6 +93,7 @@ pub fn setup(state: &mut State) {
         abstract_token: None,
         unix_token: None,
         to_main,
+        pid: None,
     });
 
     setup_watch(state);
@@ -250,7 +273,7 @@ fn spawn(path: String, xwl: &Satellite) {
     let res = thread::Builder::new()
         .name("Xwl-s Spawner".to_owned())
         .spawn(move || {
-            spawn_and_wait(&path, process, abstract_fd, unix_fd);
+            spawn_and_wait(&path, process, abstract_fd, unix_fd, &to_main);
 
             // Once xwayland-satellite crashes or fails to spawn, re-establish our X11 socket watch
             // to try again next time.
@@ -268,6 +291,7 @@ fn spawn_and_wait(
     mut process: Command,
     abstract_fd: Option<OwnedFd>,
     unix_fd: OwnedFd,
+    to_main: &Sender<ToMain>,
 ) {
     let abstract_raw = abstract_fd.as_ref().map(|fd| fd.as_raw_fd());
     let unix_raw = unix_fd.as_raw_fd();
@@ -311,6 +335,8 @@ fn spawn_and_wait(
     drop(abstract_fd);
     drop(unix_fd);
 
+    let _ = to_main.send(ToMain::Spawned(child.id() as i32));
+
     let status = match child.wait() {
         Ok(status) => status,
         Err(err) => {
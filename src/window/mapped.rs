@@ -1,19 +1,22 @@
 use std::cell::{Cell, Ref, RefCell};
 use std::time::Duration;
 
-use niri_config::{Color, CornerRadius, GradientInterpolation, WindowRule};
+use niri_config::{BlockOutFrom, Color, CornerRadius, GradientInterpolation, WindowRule};
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::Kind;
-use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::backend::renderer::utils::{CommitCounter, RendererSurfaceStateUserData};
 use smithay::desktop::space::SpaceElement as _;
 use smithay::desktop::{PopupManager, Window};
 use smithay::output::{self, Output};
+use smithay::reexports::wayland_protocols::wp::content_type::v1::server::wp_content_type_v1;
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::Resource as _;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
 use smithay::wayland::compositor::{remove_pre_commit_hook, with_states, HookId, SurfaceData};
+use smithay::wayland::content_type::ContentTypeSurfaceCachedState;
 use smithay::wayland::seat::WaylandFocus;
 use smithay::wayland::shell::xdg::{
     SurfaceCachedState, ToplevelCachedState, ToplevelConfigure, ToplevelSurface,
@@ -36,9 +39,13 @@ use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderEleme
 use crate::render_helpers::surface::{
     push_elements_from_surface_tree, render_snapshot_from_surface_tree,
 };
+use crate::render_helpers::texture::TextureBuffer;
 use crate::render_helpers::{BakedBuffer, RenderTarget};
+use crate::ui::unresponsive_prompt::{PromptButton, UnresponsivePrompt};
 use crate::utils::id::IdCounter;
+use crate::utils::title_format::{FormattedTitle, TitleFields};
 use crate::utils::transaction::Transaction;
+use crate::utils::video_cadence::VideoCadence;
 use crate::utils::{
     get_credentials_for_surface, send_scale_transform, update_tiled_state,
     with_toplevel_last_uncommitted_configure, with_toplevel_role, with_toplevel_role_and_current,
@@ -55,12 +62,27 @@ pub struct Mapped {
     /// Credentials of the process that created the Wayland connection.
     credentials: Option<Credentials>,
 
+    /// Whether this is an X11 window shown through xwayland-satellite.
+    is_x11: bool,
+
+    /// Whether to draw this window at the scale of its output, if it's an X11 window.
+    scale_x11_to_output: bool,
+
+    /// Scale of the output this window was last sent.
+    output_scale: Cell<f64>,
+
     /// Pre-commit hook that we have on all mapped toplevel surfaces.
     pre_commit_hook: HookId,
 
     /// Up-to-date rules.
     rules: ResolvedWindowRules,
 
+    /// Title from the `title-format` window rule, if it's set.
+    formatted_title: Option<FormattedTitle>,
+
+    /// Whether the title, the app ID or the rules changed since the title was last formatted.
+    title_changed: bool,
+
     /// Whether the window rules need to be recomputed.
     ///
     /// This is not used in all cases; for example, app ID and title changes recompute the rules
@@ -86,6 +108,17 @@ pub struct Mapped {
     /// Whether this has an urgent indicator.
     is_urgent: bool,
 
+    /// Whether this window drew something new while unfocused.
+    has_activity: bool,
+
+    /// Buffer commit and acked configure serial as of the last commit, for telling activity apart
+    /// from redraws.
+    activity_commit: Option<CommitCounter>,
+    activity_serial: Option<Serial>,
+
+    /// Commit timing, for detecting video playback.
+    video_cadence: VideoCadence,
+
     /// Whether this window has the keyboard focus.
     is_focused: bool,
 
@@ -101,9 +134,33 @@ pub struct Mapped {
     /// Whether this window should ignore opacity set through window rules.
     ignore_opacity_window_rule: bool,
 
+    /// Block-out from the window rules, if the screencast block-out was toggled.
+    ///
+    /// While this is set, `rules.block_out_from` holds the flipped value.
+    block_out_from_before_toggle: Option<Option<BlockOutFrom>>,
+
+    /// Whether this window was asked to span multiple outputs when fullscreen.
+    ///
+    /// Set through the toggle action, on top of the fullscreen-span window rule.
+    fullscreen_span_requested: bool,
+
+    /// Whether this window was floating before a `match-on-change` rule changed it.
+    ///
+    /// Restored when the rule stops matching.
+    floating_before_rule_change: Option<bool>,
+
+    /// Floating state that `match-on-change` rules asked for, not yet applied to the layout.
+    pending_rule_floating: Option<bool>,
+
     /// Buffer to draw instead of the window when it should be blocked out.
     block_out_buffer: RefCell<SolidColorBuffer>,
 
+    /// Whether the client didn't answer a ping in time.
+    is_unresponsive: bool,
+
+    /// Prompt drawn over the window while the client is unresponsive.
+    unresponsive_prompt: UnresponsivePrompt,
+
     /// Whether the next configure should be animated, if the configured state changed.
     animate_next_configure: bool,
 
@@ -130,6 +187,11 @@ pub struct Mapped {
     /// Used for double-resize-click tracking.
     last_interactive_resize_start: Cell<Option<(Duration, ResizeEdge)>>,
 
+    /// Where the pointer last was inside the tile, as a fraction of the tile size.
+    ///
+    /// Used for warping the pointer back when the window is focused.
+    last_pointer_pos: Cell<Option<Point<f64, Logical>>>,
+
     /// Whether this window is in windowed (fake) fullscreen.
     ///
     /// In this mode, the underlying window is told that it's fullscreen, while keeping it as
@@ -268,19 +330,34 @@ impl Mapped {
             window,
             id: MappedId::next(),
             credentials,
+            is_x11: false,
+            scale_x11_to_output: false,
+            output_scale: Cell::new(1.),
             pre_commit_hook: hook,
             rules,
+            formatted_title: None,
+            title_changed: true,
             need_to_recompute_rules: false,
             needs_configure: false,
             needs_frame_callback: false,
             offscreen_data: RefCell::new(None),
             is_urgent: false,
+            has_activity: false,
+            activity_commit: None,
+            activity_serial: None,
+            video_cadence: VideoCadence::default(),
             is_focused: false,
             is_active_in_column: true,
             is_floating: false,
             is_window_cast_target: false,
             ignore_opacity_window_rule: false,
+            block_out_from_before_toggle: None,
+            fullscreen_span_requested: false,
+            floating_before_rule_change: None,
+            pending_rule_floating: None,
             block_out_buffer: RefCell::new(SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.])),
+            is_unresponsive: false,
+            unresponsive_prompt: UnresponsivePrompt::default(),
             animate_next_configure: false,
             animate_serials: Vec::new(),
             animation_snapshot: None,
@@ -289,6 +366,7 @@ impl Mapped {
             pending_transactions: Vec::new(),
             interactive_resize: None,
             last_interactive_resize_start: Cell::new(None),
+            last_pointer_pos: Cell::new(None),
             is_windowed_fullscreen: false,
             is_pending_windowed_fullscreen: false,
             is_pending_fullscreen: pending_fullscreen,
@@ -312,7 +390,12 @@ impl Mapped {
     pub fn recompute_window_rules(&mut self, rules: &[WindowRule], is_at_startup: bool) -> bool {
         self.need_to_recompute_rules = false;
 
-        let new_rules = ResolvedWindowRules::compute(rules, WindowRef::Mapped(self), is_at_startup);
+        let mut new_rules =
+            ResolvedWindowRules::compute(rules, WindowRef::Mapped(self), is_at_startup);
+        if let Some(before) = &mut self.block_out_from_before_toggle {
+            *before = new_rules.block_out_from;
+            new_rules.block_out_from = toggled_block_out_from(new_rules.block_out_from);
+        }
         if new_rules == self.rules {
             return false;
         }
@@ -323,7 +406,12 @@ impl Mapped {
             self.ignore_opacity_window_rule = false;
         }
 
+        let old_on_change_floating = self.rules.on_change_open_floating;
         self.rules = new_rules;
+        self.title_changed = true;
+        if let Some(floating) = self.floating_change_from_rules(old_on_change_floating) {
+            self.pending_rule_floating = Some(floating);
+        }
         true
     }
 
@@ -351,6 +439,27 @@ impl Mapped {
         self.credentials.as_ref()
     }
 
+    pub fn is_x11(&self) -> bool {
+        self.is_x11
+    }
+
+    pub fn set_is_x11(&mut self, is_x11: bool) {
+        self.is_x11 = is_x11;
+    }
+
+    pub fn set_scale_x11_to_output(&mut self, scale: bool) {
+        if self.scale_x11_to_output == scale {
+            return;
+        }
+
+        let old_scale = self.force_scale();
+        self.scale_x11_to_output = scale;
+        if self.is_x11 {
+            self.rescale_pending_sizes(old_scale);
+            self.needs_configure = true;
+        }
+    }
+
     pub fn offscreen_data(&self) -> Ref<'_, Option<OffscreenData>> {
         self.offscreen_data.borrow()
     }
@@ -375,6 +484,205 @@ impl Mapped {
         self.ignore_opacity_window_rule = !self.ignore_opacity_window_rule;
     }
 
+    pub fn toggle_screencast_block_out(&mut self) {
+        if let Some(before) = self.block_out_from_before_toggle.take() {
+            self.rules.block_out_from = before;
+        } else {
+            self.block_out_from_before_toggle = Some(self.rules.block_out_from);
+            self.rules.block_out_from = toggled_block_out_from(self.rules.block_out_from);
+        }
+    }
+
+    pub fn is_blocked_out_from_screencast(&self) -> bool {
+        self.rules.block_out_from.is_some()
+    }
+
+    pub fn set_fullscreen_span_requested(&mut self, value: bool) {
+        self.fullscreen_span_requested = value;
+    }
+
+    /// Returns the floating state that `match-on-change` rules asked for since the last call.
+    ///
+    /// The caller should apply it to the layout after recomputing the window rules.
+    pub fn take_floating_change_from_rules(&mut self) -> Option<bool> {
+        self.pending_rule_floating.take()
+    }
+
+    /// Returns the floating state that `match-on-change` rules now ask for, if it changed.
+    fn floating_change_from_rules(&mut self, old: Option<bool>) -> Option<bool> {
+        let new = self.rules.on_change_open_floating;
+        if old == new {
+            return None;
+        }
+
+        match new {
+            Some(floating) => {
+                if old.is_none() {
+                    self.floating_before_rule_change = Some(self.is_floating);
+                }
+                Some(floating)
+            }
+            None => self.floating_before_rule_change.take(),
+        }
+    }
+
+    /// Returns the outputs this window should span when fullscreen.
+    ///
+    /// An empty list means all outputs adjacent to the window's own output. `None` means the
+    /// window should stay on its own output.
+    pub fn fullscreen_span_outputs(&self) -> Option<&[String]> {
+        if let Some(span) = &self.rules.fullscreen_span {
+            return Some(&span.outputs);
+        }
+
+        self.fullscreen_span_requested.then_some(&[])
+    }
+
+    /// Returns the scale the window is drawn at on top of the output scale.
+    ///
+    /// The layout works with the scaled size, while the window itself sees the unscaled size.
+    pub fn force_scale(&self) -> f64 {
+        if let Some(scale) = self.rules.force_scale.filter(|scale| *scale > 0.) {
+            return scale;
+        }
+
+        // X11 clients don't know about the output scale, so scale them up instead.
+        if self.is_x11 && self.scale_x11_to_output {
+            return self.output_scale.get();
+        }
+
+        1.
+    }
+
+    /// Converts a size from the window's coordinates to the layout coordinates.
+    fn scale_from_client(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        size.to_f64().upscale(self.force_scale()).to_i32_round()
+    }
+
+    /// Converts a size from the layout coordinates to the window's coordinates.
+    fn scale_to_client(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        size.to_f64().downscale(self.force_scale()).to_i32_round()
+    }
+
+    /// Converts the pending configure sizes after the force scale changed from `old_scale`.
+    ///
+    /// The layout size stays the same, but the window needs a configure with its new size.
+    fn rescale_pending_sizes(&self, old_scale: f64) {
+        let new_scale = self.force_scale();
+        if new_scale == old_scale {
+            return;
+        }
+
+        let rescale = |size: Size<i32, Logical>| {
+            size.to_f64()
+                .upscale(old_scale)
+                .downscale(new_scale)
+                .to_i32_round()
+        };
+        self.toplevel().with_pending_state(|state| {
+            state.size = state.size.map(rescale);
+            state.bounds = state.bounds.map(rescale);
+        });
+    }
+
+    /// Returns the window geometry location scaled to the layout coordinates.
+    fn scaled_geometry_loc(&self) -> Point<f64, Logical> {
+        self.window
+            .geometry()
+            .loc
+            .to_f64()
+            .upscale(self.force_scale())
+    }
+
+    /// Returns the size the window is expected to have, in the window's coordinates.
+    fn expected_client_size(&self) -> Option<Size<i32, Logical>> {
+        // We can only use current size if it's not maximized or fullscreen.
+        let current_size = (self.sizing_mode().is_normal()).then(|| self.window.geometry().size);
+
+        // Check if we should be using the current window size.
+        //
+        // This branch can be useful (give different result than the logic below) in this example
+        // case:
+        //
+        // 1. We request_size_once a size change.
+        // 2. We send a second configure requesting a state change.
+        // 3. The window acks and commits-to the first configure but not the second, with a
+        //    different size.
+        //
+        // In this case self.request_size_once will already flip to UseWindowSize and this branch
+        // will return the window's own new size, but the logic below would see an uncommitted size
+        // change and return our size.
+        if let Some(RequestSizeOnce::UseWindowSize) = self.request_size_once {
+            return current_size;
+        }
+
+        let pending = with_states(self.toplevel().wl_surface(), |states| {
+            let role = states
+                .data_map
+                .get::<XdgToplevelSurfaceData>()
+                .unwrap()
+                .lock()
+                .unwrap();
+
+            // If we have a server-pending size change that we haven't sent yet, use that size.
+            let server_pending = role.server_pending.as_ref()?;
+
+            let current_server = role.current_server_state();
+            if server_pending.size != current_server.size {
+                return Some((
+                    server_pending.size.unwrap_or_default(),
+                    server_pending
+                        .states
+                        .contains(xdg_toplevel::State::Fullscreen),
+                    server_pending
+                        .states
+                        .contains(xdg_toplevel::State::Maximized),
+                ));
+            }
+
+            None
+        })
+        .or_else(|| {
+            with_toplevel_last_uncommitted_configure(self.toplevel(), |configure| {
+                // If we have a sent-but-not-committed-to size, use that.
+                let ToplevelConfigure { state, .. } = configure?;
+
+                Some((
+                    state.size.unwrap_or_default(),
+                    state.states.contains(xdg_toplevel::State::Fullscreen),
+                    state.states.contains(xdg_toplevel::State::Maximized),
+                ))
+            })
+        });
+
+        if let Some((mut size, fullscreen, maximized)) = pending {
+            // If the pending change is maximized or fullscreen, we can't use that size.
+            //
+            // Pending windowed fullscreen is good (means not real fullscreen), unless it's also
+            // pending maximized (means maximized windowed fullscreen, so maximized size, bad).
+            if maximized
+                || (fullscreen
+                    && (!self.is_pending_windowed_fullscreen || self.is_pending_maximized))
+            {
+                return None;
+            }
+
+            // If some component of the pending size is zero, substitute it with the current window
+            // size. But only if the current size is not fullscreen.
+            if size.w == 0 {
+                size.w = current_size?.w;
+            }
+            if size.h == 0 {
+                size.h = current_size?.h;
+            }
+
+            Some(size)
+        } else {
+            // No pending size, return the current size if it's non-fullscreen.
+            current_size
+        }
+    }
+
     pub fn set_is_focused(&mut self, is_focused: bool) {
         if self.is_focused == is_focused {
             return;
@@ -382,6 +690,7 @@ impl Mapped {
 
         self.is_focused = is_focused;
         self.is_urgent = false;
+        self.has_activity = false;
         self.need_to_recompute_rules = true;
     }
 
@@ -416,6 +725,16 @@ impl Mapped {
         let surface = self.toplevel().wl_surface();
         render_snapshot_from_surface_tree(renderer, surface, buf_pos, &mut contents);
 
+        let force_scale = self.force_scale();
+        if force_scale != 1. {
+            for baked in &mut contents {
+                baked.location = baked.location.upscale(force_scale);
+                baked.dst = baked
+                    .dst
+                    .map(|dst| dst.to_f64().upscale(force_scale).to_i32_round());
+            }
+        }
+
         RenderSnapshot {
             contents,
             blocked_out_contents,
@@ -481,6 +800,10 @@ impl Mapped {
         &self.last_interactive_resize_start
     }
 
+    pub fn last_pointer_pos(&self) -> &Cell<Option<Point<f64, Logical>>> {
+        &self.last_pointer_pos
+    }
+
     pub fn render_for_screen_cast<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -540,6 +863,16 @@ impl Mapped {
         );
     }
 
+    /// Returns the title from the `title-format` window rule, if it's set.
+    pub fn formatted_title(&self) -> Option<&str> {
+        self.formatted_title.as_ref().map(FormattedTitle::as_str)
+    }
+
+    /// Marks the title for formatting again after the client changed its title or app ID.
+    pub fn on_title_changed(&mut self) {
+        self.title_changed = true;
+    }
+
     pub fn get_focus_timestamp(&self) -> Option<Duration> {
         self.focus_timestamp
     }
@@ -603,6 +936,107 @@ impl Mapped {
     pub fn is_urgent(&self) -> bool {
         self.is_urgent
     }
+
+    /// Sets whether the client is unresponsive, returning whether it changed.
+    pub fn set_unresponsive(&mut self, unresponsive: bool) -> bool {
+        if self.is_unresponsive == unresponsive {
+            return false;
+        }
+
+        self.is_unresponsive = unresponsive;
+        if !unresponsive {
+            self.unresponsive_prompt.clear();
+        }
+        true
+    }
+
+    pub fn is_unresponsive(&self) -> bool {
+        self.is_unresponsive
+    }
+
+    /// Returns the unresponsive prompt button under the point, relative to the window geometry.
+    pub fn unresponsive_button_at(&self, point: Point<f64, Logical>) -> Option<PromptButton> {
+        if !self.is_unresponsive {
+            return None;
+        }
+
+        self.unresponsive_prompt.button_at(point)
+    }
+
+    /// Marks that this window drew something new while unfocused, called on every commit.
+    ///
+    /// Only new buffers with damage count. Commits that ack a new configure are the window
+    /// redrawing for a state that niri asked for, like losing focus, and commits without damage
+    /// are clients redrawing the same contents on a frame callback, so neither counts.
+    pub fn mark_activity(&mut self, acked_serial: Option<Serial>) {
+        let last_commit = self.activity_commit;
+        let (commit, damaged) = with_states(self.toplevel().wl_surface(), |states| {
+            let Some(data) = states.data_map.get::<RendererSurfaceStateUserData>() else {
+                return (None, false);
+            };
+            let data = data.lock().unwrap();
+            let commit = data.current_commit();
+            let damaged = last_commit
+                .is_some_and(|last| last != commit && !data.damage_since(Some(last)).is_empty());
+            (Some(commit), damaged)
+        });
+
+        let acked_new_configure = acked_serial != self.activity_serial;
+        self.activity_commit = commit;
+        self.activity_serial = acked_serial;
+
+        if !damaged
+            || acked_new_configure
+            || self.is_focused
+            || self.rules.track_activity == Some(false)
+        {
+            return;
+        }
+
+        self.has_activity = true;
+    }
+
+    pub fn has_activity(&self) -> bool {
+        // Also check the rule so that turning it off hides the indicator right away.
+        self.has_activity && self.rules.track_activity != Some(false)
+    }
+
+    /// Records a commit to this window or one of its subsurfaces.
+    pub fn record_commit(&mut self, now: Duration) {
+        self.video_cadence.record_commit(now);
+    }
+
+    /// Returns the recent commit rate and when the window last committed.
+    ///
+    /// The rate is `None` once the window stops committing.
+    pub fn commit_stats(&self, now: Duration) -> (Option<f64>, Option<Duration>) {
+        let rate = if self.video_cadence.is_committing(now) {
+            self.video_cadence.commit_rate()
+        } else {
+            None
+        };
+        (rate, self.video_cadence.last_commit())
+    }
+
+    /// Returns whether this window is currently playing video.
+    ///
+    /// Windows that set a content type are trusted as long as they keep committing. For the
+    /// rest, this looks at how steadily they commit.
+    pub fn is_playing_video(&self, now: Duration) -> bool {
+        let content_type = with_states(self.toplevel().wl_surface(), |states| {
+            *states
+                .cached_state
+                .get::<ContentTypeSurfaceCachedState>()
+                .current()
+                .content_type()
+        });
+
+        match content_type {
+            wp_content_type_v1::Type::Video => self.video_cadence.is_committing(now),
+            wp_content_type_v1::Type::None => self.video_cadence.looks_like_video(now),
+            _ => false,
+        }
+    }
 }
 
 impl Drop for Mapped {
@@ -619,19 +1053,63 @@ impl LayoutElement for Mapped {
     }
 
     fn title(&self) -> Option<String> {
+        if let Some(formatted) = &self.formatted_title {
+            return Some(formatted.as_str().to_owned());
+        }
+
         with_toplevel_role(self.toplevel(), |role| role.title.clone())
     }
 
+    fn refresh_title(
+        &mut self,
+        workspace_name: Option<&str>,
+        workspace_idx: usize,
+        marks: &[String],
+    ) {
+        let Some(template) = &self.rules.title_format else {
+            self.formatted_title = None;
+            return;
+        };
+
+        // Reading the title and the app ID needs the surface lock, so only do it when they
+        // changed, or when the window moved or got different marks.
+        let moved = !self
+            .formatted_title
+            .as_ref()
+            .is_some_and(|formatted| formatted.has_context(workspace_name, workspace_idx, marks));
+        if !self.title_changed && !moved {
+            return;
+        }
+        self.title_changed = false;
+
+        let toplevel = self.window.toplevel().expect("no X11 support");
+        let formatted_title = &mut self.formatted_title;
+        with_toplevel_role(toplevel, |role| {
+            let fields = TitleFields {
+                title: role.title.as_deref(),
+                app_id: role.app_id.as_deref(),
+                workspace_name,
+                workspace_idx,
+                marks,
+            };
+            match formatted_title {
+                Some(formatted) => formatted.update(template, &fields),
+                None => *formatted_title = Some(FormattedTitle::new(template, &fields)),
+            }
+        });
+    }
+
     fn size(&self) -> Size<i32, Logical> {
-        self.window.geometry().size
+        self.scale_from_client(self.window.geometry().size)
     }
 
     fn buf_loc(&self) -> Point<i32, Logical> {
-        Point::from((0, 0)) - self.window.geometry().loc
+        Point::from((0, 0)) - self.scaled_geometry_loc().to_i32_round()
     }
 
     fn is_in_input_region(&self, point: Point<f64, Logical>) -> bool {
-        let surface_local = point + self.window.geometry().loc.to_f64();
+        let surface_local =
+            point.downscale(self.force_scale()) + self.window.geometry().loc.to_f64();
         self.window.is_in_input_region(&surface_local)
     }
 
@@ -646,19 +1124,21 @@ impl LayoutElement for Mapped {
     ) {
         if target.should_block_out(self.rules.block_out_from) {
             let mut buffer = self.block_out_buffer.borrow_mut();
-            buffer.resize(self.window.geometry().size.to_f64());
+            buffer.resize(self.size().to_f64());
             let elem =
                 SolidColorRenderElement::from_buffer(&buffer, location, alpha, Kind::Unspecified);
             push(elem.into());
         } else {
-            let buf_pos = location - self.window.geometry().loc.to_f64();
+            let buf_pos = location - self.scaled_geometry_loc();
             let surface = self.toplevel().wl_surface();
             let mut push = |elem: WaylandSurfaceRenderElement<R>| push(elem.into());
+            // Rendering the surfaces at a bigger scale makes them bigger on the output.
+            let force_scale = self.force_scale();
             push_elements_from_surface_tree(
                 renderer,
                 surface,
                 buf_pos.to_physical_precise_round(scale),
-                scale,
+                Scale::from((scale.x * force_scale, scale.y * force_scale)),
                 alpha,
                 Kind::ScanoutCandidate,
                 &mut push,
@@ -679,17 +1159,19 @@ impl LayoutElement for Mapped {
             return;
         }
 
-        let buf_pos = location - self.window.geometry().loc.to_f64();
+        let force_scale = self.force_scale();
+        let buf_pos = location - self.scaled_geometry_loc();
         let surface = self.toplevel().wl_surface();
         let mut push = |elem: WaylandSurfaceRenderElement<R>| push(elem.into());
         for (popup, popup_offset) in PopupManager::popups_for_surface(surface) {
             let offset = self.window.geometry().loc + popup_offset - popup.geometry().loc;
+            let offset = offset.to_f64().upscale(force_scale);
 
             push_elements_from_surface_tree(
                 renderer,
                 popup.wl_surface(),
-                (buf_pos + offset.to_f64()).to_physical_precise_round(scale),
-                scale,
+                (buf_pos + offset).to_physical_precise_round(scale),
+                Scale::from((scale.x * force_scale, scale.y * force_scale)),
                 alpha,
                 Kind::ScanoutCandidate,
                 &mut push,
@@ -722,6 +1204,7 @@ impl LayoutElement for Mapped {
             self.needs_configure = true;
         }
 
+        let size = self.scale_to_client(size);
         let changed = self.toplevel().with_pending_state(|state| {
             let changed = state.size != Some(size);
             state.size = Some(size);
@@ -761,6 +1244,8 @@ impl LayoutElement for Mapped {
         // longer participate in any transactions with other windows.
         self.transaction_for_next_configure = None;
 
+        let size = self.scale_to_client(size);
+
         self.is_pending_fullscreen = false;
         self.is_pending_maximized = false;
         if self.is_maximized != self.is_pending_maximized {
@@ -835,7 +1320,7 @@ impl LayoutElement for Mapped {
             guard.current().min_size
         });
 
-        self.rules.apply_min_size(min_size)
+        self.rules.apply_min_size(self.scale_from_client(min_size))
     }
 
     fn max_size(&self) -> Size<i32, Logical> {
@@ -844,7 +1329,7 @@ impl LayoutElement for Mapped {
             guard.current().max_size
         });
 
-        self.rules.apply_max_size(max_size)
+        self.rules.apply_max_size(self.scale_from_client(max_size))
     }
 
     fn is_wl_surface(&self, wl_surface: &WlSurface) -> bool {
@@ -852,6 +1337,11 @@ impl LayoutElement for Mapped {
     }
 
     fn set_preferred_scale_transform(&self, scale: output::Scale, transform: Transform) {
+        // Scaled X11 windows change size along with the output scale.
+        let old_scale = self.force_scale();
+        self.output_scale.set(scale.fractional_scale());
+        self.rescale_pending_sizes(old_scale);
+
         self.window.with_surfaces(|surface, data| {
             send_scale_transform(surface, data, scale, transform);
         });
@@ -910,6 +1400,24 @@ impl LayoutElement for Mapped {
         self.is_urgent
     }
 
+    fn has_activity(&self) -> bool {
+        Mapped::has_activity(self)
+    }
+
+    fn render_unresponsive_prompt(
+        &self,
+        renderer: &mut GlesRenderer,
+        scale: f64,
+        font: Option<&str>,
+    ) -> Option<TextureBuffer<GlesTexture>> {
+        if !self.is_unresponsive {
+            return None;
+        }
+
+        self.unresponsive_prompt
+            .render(renderer, self.size(), scale, font)
+    }
+
     fn set_activated(&mut self, active: bool) {
         let changed = self.toplevel().with_pending_state(|state| {
             if active {
@@ -934,6 +1442,7 @@ impl LayoutElement for Mapped {
     }
 
     fn set_bounds(&self, bounds: Size<i32, Logical>) {
+        let bounds = self.scale_to_client(bounds);
         self.toplevel().with_pending_state(|state| {
             state.bounds = Some(bounds);
         });
@@ -1157,95 +1666,14 @@ impl LayoutElement for Mapped {
     }
 
     fn requested_size(&self) -> Option<Size<i32, Logical>> {
-        self.toplevel().with_pending_state(|state| state.size)
+        self.toplevel()
+            .with_pending_state(|state| state.size)
+            .map(|size| self.scale_from_client(size))
     }
 
     fn expected_size(&self) -> Option<Size<i32, Logical>> {
-        // We can only use current size if it's not maximized or fullscreen.
-        let current_size = (self.sizing_mode().is_normal()).then(|| self.window.geometry().size);
-
-        // Check if we should be using the current window size.
-        //
-        // This branch can be useful (give different result than the logic below) in this example
-        // case:
-        //
-        // 1. We request_size_once a size change.
-        // 2. We send a second configure requesting a state change.
-        // 3. The window acks and commits-to the first configure but not the second, with a
-        //    different size.
-        //
-        // In this case self.request_size_once will already flip to UseWindowSize and this branch
-        // will return the window's own new size, but the logic below would see an uncommitted size
-        // change and return our size.
-        if let Some(RequestSizeOnce::UseWindowSize) = self.request_size_once {
-            return current_size;
-        }
-
-        let pending = with_states(self.toplevel().wl_surface(), |states| {
-            let role = states
-                .data_map
-                .get::<XdgToplevelSurfaceData>()
-                .unwrap()
-                .lock()
-                .unwrap();
-
-            // If we have a server-pending size change that we haven't sent yet, use that size.
-            let server_pending = role.server_pending.as_ref()?;
-
-            let current_server = role.current_server_state();
-            if server_pending.size != current_server.size {
-                return Some((
-                    server_pending.size.unwrap_or_default(),
-                    server_pending
-                        .states
-                        .contains(xdg_toplevel::State::Fullscreen),
-                    server_pending
-                        .states
-                        .contains(xdg_toplevel::State::Maximized),
-                ));
-            }
-
-            None
-        })
-        .or_else(|| {
-            with_toplevel_last_uncommitted_configure(self.toplevel(), |configure| {
-                // If we have a sent-but-not-committed-to size, use that.
-                let ToplevelConfigure { state, .. } = configure?;
-
-                Some((
-                    state.size.unwrap_or_default(),
-                    state.states.contains(xdg_toplevel::State::Fullscreen),
-                    state.states.contains(xdg_toplevel::State::Maximized),
-                ))
-            })
-        });
-
-        if let Some((mut size, fullscreen, maximized)) = pending {
-            // If the pending change is maximized or fullscreen, we can't use that size.
-            //
-            // Pending windowed fullscreen is good (means not real fullscreen), unless it's also
-            // pending maximized (means maximized windowed fullscreen, so maximized size, bad).
-            if maximized
-                || (fullscreen
-                    && (!self.is_pending_windowed_fullscreen || self.is_pending_maximized))
-            {
-                return None;
-            }
-
-            // If some component of the pending size is zero, substitute it with the current window
-            // size. But only if the current size is not fullscreen.
-            if size.w == 0 {
-                size.w = current_size?.w;
-            }
-            if size.h == 0 {
-                size.h = current_size?.h;
-            }
-
-            Some(size)
-        } else {
-            // No pending size, return the current size if it's non-fullscreen.
-            current_size
-        }
+        self.expected_client_size()
+            .map(|size| self.scale_from_client(size))
     }
 
     fn is_pending_windowed_fullscreen(&self) -> bool {
@@ -1363,3 +1791,10 @@ impl LayoutElement for Mapped {
         });
     }
 }
+
+fn toggled_block_out_from(block_out_from: Option<BlockOutFrom>) -> Option<BlockOutFrom> {
+    match block_out_from {
+        Some(_) => None,
+        None => Some(BlockOutFrom::Screencast),
+    }
+}
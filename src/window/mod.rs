@@ -1,19 +1,22 @@
 use std::cmp::{max, min};
 
+use niri_config::animations::WindowAnimStyle;
 use niri_config::utils::MergeWith as _;
 use niri_config::window_rule::{Match, WindowRule};
 use niri_config::{
-    BlockOutFrom, BorderRule, CornerRadius, FloatingPosition, PresetSize, ShadowRule,
-    TabIndicatorRule,
+    BlockOutFrom, BorderRule, CornerRadius, FloatingPosition, FloatingSize, FocusOnActivation,
+    FullscreenSpan, PresetSize, ShadowRule, TabIndicatorPosition, TabIndicatorRule,
 };
 use niri_ipc::ColumnDisplay;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
+use smithay::reexports::wayland_server::Resource as _;
 use smithay::utils::{Logical, Size};
 use smithay::wayland::compositor::with_states;
 use smithay::wayland::shell::xdg::{
     SurfaceCachedState, ToplevelSurface, XdgToplevelSurfaceRoleAttributes,
 };
 
+use crate::niri::ClientState;
 use crate::utils::with_toplevel_role;
 
 pub mod mapped;
@@ -49,6 +52,12 @@ pub struct ResolvedWindowRules {
     /// Default column display for this window.
     pub default_column_display: Option<ColumnDisplay>,
 
+    /// Tab bar position for the container this window opens into.
+    pub tab_bar_position: Option<TabIndicatorPosition>,
+
+    /// Default floating size for this window.
+    pub default_floating_size: Option<FloatingSize>,
+
     /// Default floating position for this window.
     pub default_floating_position: Option<FloatingPosition>,
 
@@ -70,9 +79,17 @@ pub struct ResolvedWindowRules {
     /// Whether the window should open floating.
     pub open_floating: Option<bool>,
 
+    /// Whether the window should be floating according to `match-on-change` rules.
+    ///
+    /// Unlike `open_floating`, this is re-applied to live windows when it changes.
+    pub on_change_open_floating: Option<bool>,
+
     /// Whether the window should open focused.
     pub open_focused: Option<bool>,
 
+    /// Whether the window should open always on top.
+    pub open_always_on_top: Option<bool>,
+
     /// Extra bound on the minimum window width.
     pub min_width: Option<u16>,
     /// Extra bound on the minimum window height.
@@ -119,6 +136,36 @@ pub struct ResolvedWindowRules {
 
     /// Override whether to set the Tiled xdg-toplevel state on the window.
     pub tiled_state: Option<bool>,
+
+    /// Outputs that this window should span when fullscreen.
+    pub fullscreen_span: Option<FullscreenSpan>,
+
+    /// Override for the window open animation style.
+    pub open_animation: Option<WindowAnimStyle>,
+
+    /// Override for the window close animation style.
+    pub close_animation: Option<WindowAnimStyle>,
+
+    /// Whether to keep this window's workspace around after the window leaves it.
+    pub keep_workspace: Option<bool>,
+
+    /// Whether to throttle frame callbacks when this window is occluded.
+    pub throttle_when_occluded: Option<bool>,
+
+    /// Scale to draw this window at, on top of the output scale.
+    pub force_scale: Option<f64>,
+
+    /// Whether focus-follows-mouse applies to this window, or it needs a click to focus.
+    pub focus_follows_mouse: Option<bool>,
+
+    /// Whether to focus this window when it requests activation.
+    pub focus_on_activation: Option<FocusOnActivation>,
+
+    /// Whether to show an activity indicator when this window draws while unfocused.
+    pub track_activity: Option<bool>,
+
+    /// Template for the title shown in tab bars and reported over IPC.
+    pub title_format: Option<String>,
 }
 
 impl<'a> WindowRef<'a> {
@@ -179,6 +226,13 @@ impl ResolvedWindowRules {
 
         let mut resolved = ResolvedWindowRules::default();
 
+        // Read this before locking the role, since it needs the surface states too.
+        let has_fixed_size = with_states(window.toplevel().wl_surface(), |state| {
+            let mut guard = state.cached_state.get::<SurfaceCachedState>();
+            let current = guard.current();
+            current.min_size.h > 0 && current.min_size == current.max_size
+        });
+
         with_toplevel_role(window.toplevel(), |role| {
             // Ensure server_pending like in Smithay's with_pending_state().
             if role.server_pending.is_none() {
@@ -196,7 +250,7 @@ impl ResolvedWindowRules {
                         }
                     }
 
-                    window_matches(window, role, m)
+                    window_matches(window, role, has_fixed_size, m)
                 };
 
                 if !(rule.matches.is_empty() || rule.matches.iter().any(matches)) {
@@ -219,6 +273,14 @@ impl ResolvedWindowRules {
                     resolved.default_column_display = Some(x);
                 }
 
+                if let Some(x) = rule.tab_bar_position {
+                    resolved.tab_bar_position = Some(x);
+                }
+
+                if let Some(x) = rule.default_floating_size {
+                    resolved.default_floating_size = Some(x);
+                }
+
                 if let Some(x) = rule.default_floating_position {
                     resolved.default_floating_position = Some(x);
                 }
@@ -245,11 +307,18 @@ impl ResolvedWindowRules {
 
                 if let Some(x) = rule.open_floating {
                     resolved.open_floating = Some(x);
+
+                    if rule.match_on_change {
+                        resolved.on_change_open_floating = Some(x);
+                    }
                 }
 
                 if let Some(x) = rule.open_focused {
                     resolved.open_focused = Some(x);
                 }
+                if let Some(x) = rule.open_always_on_top {
+                    resolved.open_always_on_top = Some(x);
+                }
 
                 if let Some(x) = rule.min_width {
                     resolved.min_width = Some(x);
@@ -296,6 +365,36 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.tiled_state {
                     resolved.tiled_state = Some(x);
                 }
+                if let Some(x) = &rule.fullscreen_span {
+                    resolved.fullscreen_span = Some(x.clone());
+                }
+                if let Some(x) = rule.open_animation {
+                    resolved.open_animation = Some(x);
+                }
+                if let Some(x) = rule.close_animation {
+                    resolved.close_animation = Some(x);
+                }
+                if let Some(x) = rule.keep_workspace {
+                    resolved.keep_workspace = Some(x);
+                }
+                if let Some(x) = rule.throttle_when_occluded {
+                    resolved.throttle_when_occluded = Some(x);
+                }
+                if let Some(x) = rule.force_scale {
+                    resolved.force_scale = Some(x.0);
+                }
+                if let Some(x) = rule.focus_follows_mouse {
+                    resolved.focus_follows_mouse = Some(x);
+                }
+                if let Some(x) = rule.focus_on_activation {
+                    resolved.focus_on_activation = Some(x);
+                }
+                if let Some(x) = rule.track_activity {
+                    resolved.track_activity = Some(x);
+                }
+                if let Some(x) = &rule.title_format {
+                    resolved.title_format = Some(x.clone());
+                }
             }
 
             resolved.open_on_output = open_on_output.map(|x| x.to_owned());
@@ -339,6 +438,12 @@ impl ResolvedWindowRules {
         size
     }
 
+    /// Returns the default floating window size set by the rules, if any.
+    pub fn default_floating_window_size(&self) -> Option<Size<i32, Logical>> {
+        let size = self.default_floating_size?;
+        Some(Size::from((i32::from(size.width), i32::from(size.height))))
+    }
+
     pub fn apply_min_max_size(
         &self,
         min_size: Size<i32, Logical>,
@@ -371,7 +476,23 @@ impl ResolvedWindowRules {
     }
 }
 
-fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m: &Match) -> bool {
+/// Returns whether the toplevel is a browser picture-in-picture window.
+///
+/// Firefox titles these "Picture-in-Picture", and Chromium "Picture in picture".
+pub fn is_picture_in_picture(toplevel: &ToplevelSurface) -> bool {
+    with_toplevel_role(toplevel, |role| {
+        role.title.as_deref().is_some_and(|title| {
+            title.replace('-', " ").eq_ignore_ascii_case("picture in picture")
+        })
+    })
+}
+
+fn window_matches(
+    window: WindowRef,
+    role: &XdgToplevelSurfaceRoleAttributes,
+    has_fixed_size: bool,
+    m: &Match,
+) -> bool {
     // Must be ensured by the caller.
     let server_pending = role.server_pending.as_ref().unwrap();
 
@@ -433,5 +554,33 @@ fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m:
         }
     }
 
+    if let Some(has_parent) = m.has_parent {
+        if role.parent.is_some() != has_parent {
+            return false;
+        }
+    }
+
+    if let Some(is_dialog) = m.is_dialog {
+        // Same heuristic as for opening dialogs as floating: transient windows and fixed-size
+        // windows.
+        if (role.parent.is_some() || has_fixed_size) != is_dialog {
+            return false;
+        }
+    }
+
+    if let Some(sandbox_engine_re) = &m.sandbox_engine {
+        let client = window.toplevel().wl_surface().client();
+        let data = client.as_ref().and_then(|c| c.get_data::<ClientState>());
+        let engine = data
+            .and_then(|data| data.security_context.as_ref())
+            .and_then(|context| context.sandbox_engine.as_deref());
+        let Some(engine) = engine else {
+            return false;
+        };
+        if !sandbox_engine_re.0.is_match(engine) {
+            return false;
+        }
+    }
+
     true
 }
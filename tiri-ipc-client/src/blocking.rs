@@ -0,0 +1,229 @@
+//! Blocking client on top of std sockets.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use niri_ipc::{Event, Request, Response};
+
+use crate::{
+    decode_event, decode_reply, encode_request, expect_handled, socket_path, Error, Reconnect,
+    Target, TypedRequest,
+};
+
+/// Blocking connection to the niri IPC socket.
+pub struct Client {
+    stream: BufReader<UnixStream>,
+}
+
+impl Client {
+    /// Connects to the socket found with [`socket_path()`].
+    pub fn connect() -> io::Result<Self> {
+        Self::connect_to(socket_path()?)
+    }
+
+    /// Connects to the socket at the given path.
+    pub fn connect_to(path: impl AsRef<Path>) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    /// Sends a raw request and returns the response.
+    pub fn request(&mut self, request: Request) -> Result<Response, Error> {
+        self.stream
+            .get_mut()
+            .write_all(encode_request(&request).as_bytes())?;
+
+        let mut buf = String::new();
+        self.stream.read_line(&mut buf)?;
+        decode_reply(&buf)
+    }
+
+    /// Sends a typed request and returns its value.
+    pub fn get<R: TypedRequest>(&mut self, request: R) -> Result<R::Output, Error> {
+        let response = self.request(request.into_request())?;
+        R::from_response(response).map_err(|other| Error::UnexpectedResponse(Box::new(other)))
+    }
+
+    /// Turns this connection into an event stream.
+    ///
+    /// The event stream won't reconnect, since it doesn't know where this connection came from.
+    /// Use [`EventStream::connect()`] for a reconnecting stream.
+    pub fn into_event_stream(mut self) -> Result<EventStream, Error> {
+        expect_handled(self.request(Request::EventStream)?)?;
+        let _ = self.stream.get_mut().shutdown(Shutdown::Write);
+
+        Ok(EventStream {
+            target: None,
+            reconnect: Reconnect::NEVER,
+            stream: Some(self.stream),
+            buf: String::new(),
+        })
+    }
+}
+
+/// Blocking event stream that reconnects when the connection drops.
+pub struct EventStream {
+    /// Where to reconnect; `None` when the stream was made from an existing connection.
+    target: Option<Target>,
+    reconnect: Reconnect,
+    stream: Option<BufReader<UnixStream>>,
+    buf: String,
+}
+
+impl EventStream {
+    /// Starts an event stream on the socket found with [`socket_path()`].
+    ///
+    /// On reconnection, the socket is looked up again, so this follows a restarted compositor.
+    pub fn connect() -> Result<Self, Error> {
+        Self::with_target(Target::Discovered)
+    }
+
+    /// Starts an event stream on the socket at the given path.
+    pub fn connect_to(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        Self::with_target(Target::Path(path.into()))
+    }
+
+    fn with_target(target: Target) -> Result<Self, Error> {
+        let mut stream = Self {
+            target: Some(target),
+            reconnect: Reconnect::default(),
+            stream: None,
+            buf: String::new(),
+        };
+        stream.stream = Some(stream.open()?);
+        Ok(stream)
+    }
+
+    /// Sets how the stream reconnects after the connection drops.
+    pub fn set_reconnect(&mut self, reconnect: Reconnect) {
+        self.reconnect = reconnect;
+    }
+
+    fn open(&self) -> Result<BufReader<UnixStream>, Error> {
+        let target = self.target.as_ref().expect("open() needs a target");
+        let client = Client::connect_to(target.path()?)?;
+        let mut stream = client.into_event_stream()?;
+        Ok(stream.stream.take().unwrap())
+    }
+
+    fn reopen(&mut self) -> Result<(), Error> {
+        let mut last_err = io::Error::from(io::ErrorKind::UnexpectedEof);
+        let mut attempt = 0;
+        while self.target.is_some() && self.reconnect.allows(attempt) {
+            attempt += 1;
+            thread::sleep(self.reconnect.delay);
+
+            match self.open() {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    return Ok(());
+                }
+                Err(Error::Io(err)) => last_err = err,
+                // Niri refused the event stream, maybe because it's still starting up.
+                Err(_) => (),
+            }
+        }
+
+        Err(Error::ReconnectFailed(last_err))
+    }
+
+    /// Blocks until the next event arrives.
+    ///
+    /// When the connection drops, this reconnects according to [`Self::set_reconnect()`]. The
+    /// first events after a reconnection describe the full current state again.
+    pub fn next_event(&mut self) -> Result<Event, Error> {
+        loop {
+            if let Some(stream) = &mut self.stream {
+                self.buf.clear();
+                let result = stream
+                    .read_line(&mut self.buf)
+                    .and_then(|_| decode_event(&self.buf));
+                match result {
+                    Ok(event) => return Ok(event),
+                    Err(err) if err.kind() == io::ErrorKind::InvalidData => return Err(err.into()),
+                    // Connection dropped.
+                    Err(_) => self.stream = None,
+                }
+            }
+
+            self.reopen()?;
+        }
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = Result<Event, Error>;
+
+    /// Returns the next event, or `None` once the stream can't reconnect anymore.
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Err(Error::ReconnectFailed(_)) => None,
+            result => Some(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixListener;
+    use std::time::Duration;
+
+    use super::*;
+
+    fn serve_once(listener: &UnixListener, event: &Event) {
+        let (stream, _) = listener.accept().unwrap();
+        let mut stream = BufReader::new(stream);
+
+        let mut line = String::new();
+        stream.read_line(&mut line).unwrap();
+        assert_eq!(line.trim(), r#""EventStream""#);
+
+        let mut reply = String::from("{\"Ok\":\"Handled\"}\n");
+        reply += &serde_json::to_string(event).unwrap();
+        reply.push('\n');
+        stream.get_mut().write_all(reply.as_bytes()).unwrap();
+        // Dropping the stream closes the connection.
+    }
+
+    #[test]
+    fn event_stream_reconnects() {
+        let dir = std::env::temp_dir().join(format!("tiri-ipc-client-test-{}", std::process::id()));
+        let _ = std::fs::create_dir(&dir);
+        let path = dir.join("niri.test.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            serve_once(&listener, &Event::OverviewOpenedOrClosed { is_open: true });
+            serve_once(&listener, &Event::OverviewOpenedOrClosed { is_open: false });
+        });
+
+        let mut stream = EventStream::connect_to(&path).unwrap();
+        stream.set_reconnect(Reconnect {
+            max_attempts: Some(1),
+            delay: Duration::ZERO,
+        });
+
+        let first = stream.next().unwrap().unwrap();
+        assert!(matches!(
+            first,
+            Event::OverviewOpenedOrClosed { is_open: true }
+        ));
+        let second = stream.next().unwrap().unwrap();
+        assert!(matches!(
+            second,
+            Event::OverviewOpenedOrClosed { is_open: false }
+        ));
+
+        server.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The listener is gone, so reconnection fails and the stream ends.
+        assert!(stream.next().is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! Typed client for the niri IPC socket.
+//!
+//! [`niri_ipc`] defines the protocol types. This crate wraps the parts that every bar or tool
+//! ends up writing on top of them:
+//!
+//! - finding the socket ([`socket_path`]),
+//! - sending a request and getting the matching [`Response`] variant back as a typed value
+//!   ([`TypedRequest`] and the types in [`requests`]),
+//! - reading the event stream, reconnecting when the compositor restarts.
+//!
+//! The [`blocking`] module works with plain std sockets. The `nonblocking` module, behind the
+//! `async` feature, provides the same API on top of [async-io](https://lib.rs/crates/async-io),
+//! so it works with any executor.
+//!
+//! ```no_run
+//! use tiri_ipc_client::blocking::Client;
+//! use tiri_ipc_client::requests;
+//!
+//! fn main() -> Result<(), tiri_ipc_client::Error> {
+//!     let mut client = Client::connect()?;
+//!     for window in client.get(requests::Windows)? {
+//!         println!("{:?}: {:?}", window.id, window.title);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ## Event stream reconnection
+//!
+//! When the connection drops, the event stream reconnects to the socket and requests a new event
+//! stream. Niri always starts an event stream with the full current state (for example,
+//! [`Event::WorkspacesChanged`] with all workspaces), so applying events to a
+//! [`niri_ipc::state::EventStreamState`] keeps it correct across reconnections.
+//!
+//! ## Features
+//!
+//! - `async`: enables the `nonblocking` module.
+#![warn(missing_docs)]
+
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fmt, fs, io};
+
+pub use niri_ipc;
+use niri_ipc::socket::SOCKET_PATH_ENV;
+use niri_ipc::{Event, Reply, Request, Response};
+
+pub mod blocking;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod requests;
+
+pub use requests::TypedRequest;
+
+/// Error from a client operation.
+#[derive(Debug)]
+pub enum Error {
+    /// Error communicating with niri, including malformed JSON.
+    Io(io::Error),
+    /// Niri replied with an error message.
+    Niri(String),
+    /// Niri replied with a response that doesn't match the request.
+    ///
+    /// This usually means that the client and the compositor versions don't match.
+    UnexpectedResponse(Box<Response>),
+    /// The event stream couldn't reconnect after running out of attempts.
+    ReconnectFailed(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "error communicating with niri: {err}"),
+            Error::Niri(msg) => write!(f, "niri returned an error: {msg}"),
+            Error::UnexpectedResponse(response) => write!(f, "unexpected response: {response:?}"),
+            Error::ReconnectFailed(err) => write!(f, "error reconnecting to niri: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) | Error::ReconnectFailed(err) => Some(err),
+            Error::Niri(_) | Error::UnexpectedResponse(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+/// How the event stream reconnects after the connection drops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reconnect {
+    /// Number of connection attempts before giving up.
+    ///
+    /// `None` means retry forever. `Some(0)` disables reconnection.
+    pub max_attempts: Option<u32>,
+    /// Delay before every attempt.
+    pub delay: Duration,
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(20),
+            delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Reconnect {
+    /// Never reconnect; the event stream ends when the connection drops.
+    pub const NEVER: Self = Self {
+        max_attempts: Some(0),
+        delay: Duration::ZERO,
+    };
+
+    fn allows(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt < max)
+    }
+}
+
+/// Where an event stream reconnects to.
+enum Target {
+    /// Path found with [`socket_path()`], looked up again on every reconnection.
+    Discovered,
+    Path(PathBuf),
+}
+
+impl Target {
+    fn path(&self) -> io::Result<PathBuf> {
+        match self {
+            Target::Discovered => socket_path(),
+            Target::Path(path) => Ok(path.clone()),
+        }
+    }
+}
+
+/// Finds the niri IPC socket.
+///
+/// Uses [`SOCKET_PATH_ENV`] (`$NIRI_SOCKET`) when it's set. Otherwise, looks for niri sockets in
+/// `$XDG_RUNTIME_DIR` and picks the most recently created one. The fallback helps tools started
+/// outside of the niri session, such as from an SSH shell or a systemd user service.
+pub fn socket_path() -> io::Result<PathBuf> {
+    if let Some(path) = env::var_os(SOCKET_PATH_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let dir = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir);
+
+    let mut newest = None;
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        if !is_niri_socket_name(&entry.file_name()) {
+            continue;
+        }
+
+        let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+            continue;
+        };
+        if newest.as_ref().is_none_or(|(time, _)| *time < modified) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+
+    newest.map(|(_, path)| path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "{SOCKET_PATH_ENV} is not set and no niri socket was found in {}",
+                dir.display()
+            ),
+        )
+    })
+}
+
+/// Niri names its sockets `niri.<wayland display>.<pid>.sock`.
+///
+/// The read-only and i3 sockets next to it have an extra part before `.sock` and don't match.
+fn is_niri_socket_name(name: &OsStr) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    let Some(middle) = name
+        .strip_prefix("niri.")
+        .and_then(|name| name.strip_suffix(".sock"))
+    else {
+        return false;
+    };
+    let Some((display, pid)) = middle.rsplit_once('.') else {
+        return false;
+    };
+
+    !display.is_empty() && !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn encode_request(request: &Request) -> String {
+    let mut buf = serde_json::to_string(request).unwrap();
+    buf.push('\n');
+    buf
+}
+
+fn decode_reply(line: &str) -> Result<Response, Error> {
+    if line.is_empty() {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    let reply: Reply = serde_json::from_str(line).map_err(io::Error::from)?;
+    reply.map_err(Error::Niri)
+}
+
+fn decode_event(line: &str) -> io::Result<Event> {
+    if line.is_empty() {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(serde_json::from_str(line)?)
+}
+
+fn expect_handled(response: Response) -> Result<(), Error> {
+    match response {
+        Response::Handled => Ok(()),
+        other => Err(Error::UnexpectedResponse(Box::new(other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn niri_socket_names() {
+        assert!(is_niri_socket_name(OsStr::new("niri.wayland-1.1234.sock")));
+        assert!(!is_niri_socket_name(OsStr::new("wayland-1")));
+        assert!(!is_niri_socket_name(OsStr::new(
+            "niri.wayland-1.1234.sock.lock"
+        )));
+        assert!(!is_niri_socket_name(OsStr::new(
+            "niri.wayland-1.1234.read-only.sock"
+        )));
+        assert!(!is_niri_socket_name(OsStr::new(
+            "niri.wayland-1.1234.i3.sock"
+        )));
+        assert!(!is_niri_socket_name(OsStr::new("niri.wayland-1.sock")));
+        assert!(!is_niri_socket_name(OsStr::new("niri..1234.sock")));
+    }
+
+    #[test]
+    fn reply_decoding() {
+        assert!(matches!(
+            decode_reply(r#"{"Ok":"Handled"}"#),
+            Ok(Response::Handled)
+        ));
+        assert!(
+            matches!(decode_reply(r#"{"Err":"nope"}"#), Err(Error::Niri(msg)) if msg == "nope")
+        );
+        assert!(matches!(decode_reply(""), Err(Error::Io(_))));
+        assert!(matches!(decode_reply("garbage"), Err(Error::Io(_))));
+    }
+}
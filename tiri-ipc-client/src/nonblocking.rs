@@ -0,0 +1,207 @@
+//! Async client on top of [async-io](https://lib.rs/crates/async-io).
+//!
+//! The API mirrors the [`blocking`](crate::blocking) module.
+
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+use async_io::{Async, Timer};
+use futures_util::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+use niri_ipc::{Event, Request, Response};
+
+use crate::{
+    decode_event, decode_reply, encode_request, expect_handled, socket_path, Error, Reconnect,
+    Target, TypedRequest,
+};
+
+/// Async connection to the niri IPC socket.
+pub struct Client {
+    stream: BufReader<Async<UnixStream>>,
+}
+
+impl Client {
+    /// Connects to the socket found with [`socket_path()`].
+    pub async fn connect() -> io::Result<Self> {
+        Self::connect_to(socket_path()?).await
+    }
+
+    /// Connects to the socket at the given path.
+    pub async fn connect_to(path: impl AsRef<Path>) -> io::Result<Self> {
+        let stream = Async::<UnixStream>::connect(path).await?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    /// Sends a raw request and returns the response.
+    pub async fn request(&mut self, request: Request) -> Result<Response, Error> {
+        self.stream
+            .get_mut()
+            .write_all(encode_request(&request).as_bytes())
+            .await?;
+
+        let mut buf = String::new();
+        self.stream.read_line(&mut buf).await?;
+        decode_reply(&buf)
+    }
+
+    /// Sends a typed request and returns its value.
+    pub async fn get<R: TypedRequest>(&mut self, request: R) -> Result<R::Output, Error> {
+        let response = self.request(request.into_request()).await?;
+        R::from_response(response).map_err(|other| Error::UnexpectedResponse(Box::new(other)))
+    }
+
+    /// Turns this connection into an event stream.
+    ///
+    /// The event stream won't reconnect, since it doesn't know where this connection came from.
+    /// Use [`EventStream::connect()`] for a reconnecting stream.
+    pub async fn into_event_stream(mut self) -> Result<EventStream, Error> {
+        expect_handled(self.request(Request::EventStream).await?)?;
+        let _ = self.stream.get_ref().get_ref().shutdown(Shutdown::Write);
+
+        Ok(EventStream {
+            target: None,
+            reconnect: Reconnect::NEVER,
+            stream: Some(self.stream),
+            buf: String::new(),
+        })
+    }
+}
+
+/// Async event stream that reconnects when the connection drops.
+pub struct EventStream {
+    /// Where to reconnect; `None` when the stream was made from an existing connection.
+    target: Option<Target>,
+    reconnect: Reconnect,
+    stream: Option<BufReader<Async<UnixStream>>>,
+    buf: String,
+}
+
+impl EventStream {
+    /// Starts an event stream on the socket found with [`socket_path()`].
+    ///
+    /// On reconnection, the socket is looked up again, so this follows a restarted compositor.
+    pub async fn connect() -> Result<Self, Error> {
+        Self::with_target(Target::Discovered).await
+    }
+
+    /// Starts an event stream on the socket at the given path.
+    pub async fn connect_to(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        Self::with_target(Target::Path(path.into())).await
+    }
+
+    async fn with_target(target: Target) -> Result<Self, Error> {
+        let mut stream = Self {
+            target: Some(target),
+            reconnect: Reconnect::default(),
+            stream: None,
+            buf: String::new(),
+        };
+        stream.stream = Some(stream.open().await?);
+        Ok(stream)
+    }
+
+    /// Sets how the stream reconnects after the connection drops.
+    pub fn set_reconnect(&mut self, reconnect: Reconnect) {
+        self.reconnect = reconnect;
+    }
+
+    async fn open(&self) -> Result<BufReader<Async<UnixStream>>, Error> {
+        let target = self.target.as_ref().expect("open() needs a target");
+        let client = Client::connect_to(target.path()?).await?;
+        let mut stream = client.into_event_stream().await?;
+        Ok(stream.stream.take().unwrap())
+    }
+
+    async fn reopen(&mut self) -> Result<(), Error> {
+        let mut last_err = io::Error::from(io::ErrorKind::UnexpectedEof);
+        let mut attempt = 0;
+        while self.target.is_some() && self.reconnect.allows(attempt) {
+            attempt += 1;
+            Timer::after(self.reconnect.delay).await;
+
+            match self.open().await {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    return Ok(());
+                }
+                Err(Error::Io(err)) => last_err = err,
+                // Niri refused the event stream, maybe because it's still starting up.
+                Err(_) => (),
+            }
+        }
+
+        Err(Error::ReconnectFailed(last_err))
+    }
+
+    /// Waits for the next event.
+    ///
+    /// When the connection drops, this reconnects according to [`Self::set_reconnect()`]. The
+    /// first events after a reconnection describe the full current state again.
+    pub async fn next_event(&mut self) -> Result<Event, Error> {
+        loop {
+            if let Some(stream) = &mut self.stream {
+                self.buf.clear();
+                let result = match stream.read_line(&mut self.buf).await {
+                    Ok(_) => decode_event(&self.buf),
+                    Err(err) => Err(err),
+                };
+                match result {
+                    Ok(event) => return Ok(event),
+                    Err(err) if err.kind() == io::ErrorKind::InvalidData => return Err(err.into()),
+                    // Connection dropped.
+                    Err(_) => self.stream = None,
+                }
+            }
+
+            self.reopen().await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead as _, BufReader as StdBufReader, Write as _};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    use futures_lite::future::block_on;
+    use niri_ipc::Window;
+
+    use super::*;
+    use crate::requests;
+
+    #[test]
+    fn typed_request() {
+        let dir =
+            std::env::temp_dir().join(format!("tiri-ipc-client-async-{}", std::process::id()));
+        let _ = std::fs::create_dir(&dir);
+        let path = dir.join("niri.test.sock");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = StdBufReader::new(stream);
+            let mut line = String::new();
+            stream.read_line(&mut line).unwrap();
+            assert_eq!(line.trim(), r#""Windows""#);
+
+            let reply: niri_ipc::Reply = Ok(Response::Windows(Vec::<Window>::new()));
+            let mut reply = serde_json::to_string(&reply).unwrap();
+            reply.push('\n');
+            stream.get_mut().write_all(reply.as_bytes()).unwrap();
+        });
+
+        let windows = block_on(async {
+            let mut client = Client::connect_to(&path).await.unwrap();
+            client.get(requests::Windows).await.unwrap()
+        });
+        assert!(windows.is_empty());
+
+        server.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
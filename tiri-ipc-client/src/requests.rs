@@ -0,0 +1,214 @@
+//! Typed wrappers for niri IPC requests.
+//!
+//! Each type here sends one [`Request`] and extracts the matching [`Response`] variant. For
+//! requests not covered here, use the raw `request()` method on the clients.
+
+use std::collections::HashMap;
+
+use niri_ipc::{
+    Action, Cast, ConfigValue, LayerSurface, MacroStep, Output, Overview, Request, Response,
+    Window, Workspace, WorkspaceThumbnail,
+};
+
+/// Request with a typed response.
+pub trait TypedRequest {
+    /// Value extracted from the response.
+    type Output;
+
+    /// Converts this into the raw request.
+    fn into_request(self) -> Request;
+
+    /// Extracts the value from the raw response.
+    ///
+    /// Returns the response back if it's not the expected variant.
+    fn from_response(response: Response) -> Result<Self::Output, Response>;
+}
+
+macro_rules! typed_request {
+    ($(#[$meta:meta])* $name:ident => $request:ident, $variant:ident($output:ty)) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name;
+
+        impl TypedRequest for $name {
+            type Output = $output;
+
+            fn into_request(self) -> Request {
+                Request::$request
+            }
+
+            fn from_response(response: Response) -> Result<Self::Output, Response> {
+                match response {
+                    Response::$variant(value) => Ok(value),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+typed_request!(
+    /// Version string of the running compositor.
+    Version => Version, Version(String)
+);
+typed_request!(
+    /// Connected outputs, by name.
+    Outputs => Outputs, Outputs(HashMap<String, Output>)
+);
+typed_request!(
+    /// All workspaces.
+    Workspaces => Workspaces, Workspaces(Vec<Workspace>)
+);
+typed_request!(
+    /// All open windows.
+    Windows => Windows, Windows(Vec<Window>)
+);
+typed_request!(
+    /// All layer-shell surfaces.
+    Layers => Layers, Layers(Vec<LayerSurface>)
+);
+typed_request!(
+    /// Configured keyboard layouts.
+    KeyboardLayouts => KeyboardLayouts, KeyboardLayouts(niri_ipc::KeyboardLayouts)
+);
+typed_request!(
+    /// Focused output, if any.
+    FocusedOutput => FocusedOutput, FocusedOutput(Option<Output>)
+);
+typed_request!(
+    /// Focused window, if any.
+    FocusedWindow => FocusedWindow, FocusedWindow(Option<Window>)
+);
+typed_request!(
+    /// Overview state.
+    OverviewState => OverviewState, OverviewState(Overview)
+);
+typed_request!(
+    /// Tiling layout tree of the focused workspace.
+    LayoutTree => LayoutTree, LayoutTree(niri_ipc::LayoutTree)
+);
+typed_request!(
+    /// Active screencasts.
+    Casts => Casts, Casts(Vec<Cast>)
+);
+typed_request!(
+    /// Tiling tree of the focused workspace with the commands of its windows.
+    ExportWorkspace => ExportWorkspace, WorkspaceExport(niri_ipc::WorkspaceExport)
+);
+
+/// Performs an action.
+#[derive(Debug, Clone)]
+pub struct DoAction(pub Action);
+
+impl TypedRequest for DoAction {
+    type Output = ();
+
+    fn into_request(self) -> Request {
+        Request::Action(self.0)
+    }
+
+    fn from_response(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::Handled => Ok(()),
+            other => Err(other),
+        }
+    }
+}
+
+/// Plays back a macro, returning once it has played.
+#[derive(Debug, Clone)]
+pub struct PlayMacro(pub Vec<MacroStep>);
+
+impl TypedRequest for PlayMacro {
+    type Output = ();
+
+    fn into_request(self) -> Request {
+        Request::PlayMacro { steps: self.0 }
+    }
+
+    fn from_response(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::Handled => Ok(()),
+            other => Err(other),
+        }
+    }
+}
+
+/// Rebuilds an exported tiling tree and relaunches its windows.
+#[derive(Debug, Clone)]
+pub struct ImportWorkspace(pub niri_ipc::WorkspaceExport);
+
+impl TypedRequest for ImportWorkspace {
+    type Output = ();
+
+    fn into_request(self) -> Request {
+        Request::ImportWorkspace { export: self.0 }
+    }
+
+    fn from_response(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::Handled => Ok(()),
+            other => Err(other),
+        }
+    }
+}
+
+/// Current value of a runtime-tunable config option.
+#[derive(Debug, Clone)]
+pub struct GetConfigValue(pub String);
+
+impl TypedRequest for GetConfigValue {
+    type Output = ConfigValue;
+
+    fn into_request(self) -> Request {
+        Request::GetConfigValue { path: self.0 }
+    }
+
+    fn from_response(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::ConfigValue(value) => Ok(value),
+            other => Err(other),
+        }
+    }
+}
+
+/// Rendered thumbnail of a workspace.
+#[derive(Debug, Clone, Copy)]
+pub struct GetWorkspaceThumbnail {
+    /// Id of the workspace.
+    pub id: u64,
+    /// Maximum width and height of the thumbnail in physical pixels.
+    pub max_size: u16,
+}
+
+impl TypedRequest for GetWorkspaceThumbnail {
+    type Output = WorkspaceThumbnail;
+
+    fn into_request(self) -> Request {
+        Request::WorkspaceThumbnail {
+            id: self.id,
+            max_size: self.max_size,
+        }
+    }
+
+    fn from_response(response: Response) -> Result<Self::Output, Response> {
+        match response {
+            Response::WorkspaceThumbnail(thumbnail) => Ok(thumbnail),
+            other => Err(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_response_is_returned() {
+        let response = Windows::from_response(Response::Handled).unwrap_err();
+        assert!(matches!(response, Response::Handled));
+
+        let version = Version::from_response(Response::Version(String::from("1.0"))).unwrap();
+        assert_eq!(version, "1.0");
+    }
+}